@@ -1,7 +1,17 @@
-use crate::jcli_app::utils::key_parser::parse_pub_key;
+use crate::jcli_app::utils::{io, key_parser::parse_pub_key};
+use bip39::{dictionary, Entropy, Mnemonics};
 use chain_addr::{AddressReadable, Discrimination, Kind};
-use chain_crypto::{bech32::Bech32 as _, AsymmetricPublicKey, Ed25519, PublicKey};
-use structopt::StructOpt;
+use chain_crypto::{
+    bech32::Bech32 as _, AsymmetricPublicKey, Ed25519, Ed25519Extended, PublicKey, SecretKey,
+};
+use cryptoxide::{hmac::Hmac, pbkdf2::pbkdf2, sha2::Sha512};
+use ed25519_bip32::{DerivationScheme, XPrv, XPRV_SIZE};
+use serde::Serialize;
+use std::{
+    io::{Read, Write},
+    path::PathBuf,
+};
+use structopt::{clap::arg_enum, StructOpt};
 use thiserror::Error;
 
 #[derive(StructOpt)]
@@ -16,6 +26,10 @@ pub enum Address {
 
     /// Create an account address from a single public key.
     Account(AccountArgs),
+
+    /// Derive a batch of account keys and addresses from a mnemonic seed.
+    /// The same seed always gives the same keys and addresses.
+    AccountBatch(AccountBatchArgs),
 }
 
 #[derive(StructOpt)]
@@ -63,10 +77,91 @@ pub struct AccountArgs {
     discrimination_data: DiscriminationData,
 }
 
+#[derive(StructOpt)]
+pub struct AccountBatchArgs {
+    /// The file containing the mnemonic phrase to derive the keys from.
+    /// The phrase is read from stdin if not provided.
+    #[structopt(long = "seed-file")]
+    seed_file: Option<PathBuf>,
+
+    /// The number of accounts to derive.
+    #[structopt(long = "count")]
+    count: u32,
+
+    /// The discrimination of the addresses.
+    #[structopt(
+        long = "discrimination",
+        possible_values = &DiscriminationArg::variants(),
+        case_insensitive = true,
+        default_value = "production"
+    )]
+    discrimination: DiscriminationArg,
+
+    /// Set the prefix to use to describe the addresses.
+    #[structopt(long = "prefix", default_value = "ca")]
+    prefix: String,
+
+    /// The format of the manifest.
+    #[structopt(
+        long = "format",
+        possible_values = &ManifestFormat::variants(),
+        case_insensitive = true,
+        default_value = "csv"
+    )]
+    format: ManifestFormat,
+
+    /// Write the manifest to the given file or to stdout if not provided.
+    #[structopt(long = "output")]
+    output: Option<PathBuf>,
+}
+
+arg_enum! {
+    #[derive(Debug, Clone, Copy)]
+    pub enum DiscriminationArg {
+        Test,
+        Production,
+    }
+}
+
+arg_enum! {
+    #[derive(Debug, Clone, Copy)]
+    pub enum ManifestFormat {
+        Csv,
+        Json,
+    }
+}
+
+/// An entry of the manifest of `account-batch`.
+#[derive(Serialize)]
+struct BatchAccount {
+    index: u32,
+    secret_key: String,
+    public_key: String,
+    address: String,
+}
+
+/// The derivation path of the parent of the account keys, the index of the
+/// account being the last component: `m/1852'/1815'/0'/2/<index>`.
+const ACCOUNT_BATCH_PATH: [u32; 4] = [0x8000_0000 + 1852, 0x8000_0000 + 1815, 0x8000_0000, 2];
+
+/// Number of PBKDF2 iterations turning the entropy of the mnemonic into
+/// the root key.
+const ROOT_KEY_PBKDF2_ITERATIONS: u32 = 4096;
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("multisig addresses are not supported")]
     MultisigAddressNotSupported,
+    #[error("cannot read the mnemonic phrase")]
+    SeedFileReadFailed(#[source] std::io::Error),
+    #[error("invalid mnemonic phrase")]
+    InvalidMnemonics(#[from] bip39::Error),
+    #[error("invalid derived key")]
+    InvalidDerivedKey(#[from] chain_crypto::SecretKeyError),
+    #[error("cannot write the manifest")]
+    ManifestWriteFailed(#[from] std::io::Error),
+    #[error("cannot serialize the manifest")]
+    ManifestSerializationFailed(#[from] serde_json::Error),
 }
 
 impl Address {
@@ -94,11 +189,85 @@ impl Address {
                 account_args.key,
                 account_args.discrimination_data.testing,
             ),
+            Address::AccountBatch(batch_args) => batch_args.exec()?,
+        }
+        Ok(())
+    }
+}
+
+impl AccountBatchArgs {
+    fn exec(self) -> Result<(), Error> {
+        let discrimination = match self.discrimination {
+            DiscriminationArg::Test => Discrimination::Test,
+            DiscriminationArg::Production => Discrimination::Production,
+        };
+        let root = root_key(&read_mnemonics(&self.seed_file)?)?;
+        let parent = ACCOUNT_BATCH_PATH
+            .iter()
+            .fold(root, |key, index| key.derive(DerivationScheme::V2, *index));
+
+        let accounts = (0..self.count)
+            .map(|index| {
+                let child = parent.derive(DerivationScheme::V2, index);
+                let secret =
+                    SecretKey::<Ed25519Extended>::from_binary(&child.extended_secret_key())?;
+                let public = secret.to_public();
+                let address = chain_addr::Address(discrimination, Kind::Account(public.clone()));
+                Ok(BatchAccount {
+                    index,
+                    secret_key: secret.to_bech32_str(),
+                    public_key: public.to_bech32_str(),
+                    address: AddressReadable::from_address(&self.prefix, &address).to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut output = io::open_file_write(&self.output)?;
+        match self.format {
+            ManifestFormat::Csv => {
+                writeln!(output, "index,secret_key,public_key,address")?;
+                for account in accounts {
+                    writeln!(
+                        output,
+                        "{},{},{},{}",
+                        account.index, account.secret_key, account.public_key, account.address
+                    )?;
+                }
+            }
+            ManifestFormat::Json => {
+                serde_json::to_writer_pretty(&mut output, &accounts)?;
+                writeln!(output)?;
+            }
         }
         Ok(())
     }
 }
 
+/// read the mnemonic phrase from the given file or stdin. It is never
+/// taken from the command line, where it would be visible to the other
+/// processes and kept in the shell history.
+fn read_mnemonics(seed_file: &Option<PathBuf>) -> Result<Mnemonics, Error> {
+    let mut phrase = String::new();
+    io::open_file_read(seed_file)
+        .and_then(|mut reader| reader.read_to_string(&mut phrase))
+        .map_err(Error::SeedFileReadFailed)?;
+    let phrase = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+    Ok(Mnemonics::from_string(&dictionary::ENGLISH, &phrase)?)
+}
+
+fn root_key(mnemonics: &Mnemonics) -> Result<XPrv, Error> {
+    let entropy = Entropy::from_mnemonics(mnemonics)?;
+    let mut seed = [0; XPRV_SIZE];
+    let mut mac = Hmac::new(Sha512::new(), &[]);
+    pbkdf2(
+        &mut mac,
+        entropy.as_ref(),
+        ROOT_KEY_PBKDF2_ITERATIONS,
+        &mut seed,
+    );
+    Ok(XPrv::normalize_bytes_force3rd(seed))
+}
+
 fn address_info(address: &AddressReadable) -> Result<(), Error> {
     let chain_addr::Address(discrimination, kind) = address.to_address();
     match discrimination {
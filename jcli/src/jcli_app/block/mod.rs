@@ -5,11 +5,13 @@ use chain_impl_mockchain::{
     ledger::{self, Ledger},
 };
 use jormungandr_lib::interfaces::{
-    block0_configuration_documented_example, Block0Configuration, Block0ConfigurationError,
+    block0_configuration_documented_example, initial_certificates_from_csv, initial_funds_from_csv,
+    Block0Configuration, Block0ConfigurationError, InitialCsvError,
 };
 use std::{
-    io::{BufRead, Write},
-    path::PathBuf,
+    fs::File,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
 };
 use structopt::StructOpt;
 use thiserror::Error;
@@ -41,13 +43,25 @@ pub enum Error {
     GenesisSerializationFailed(#[source] serde_yaml::Error),
     #[error("failed to build genesis from block 0")]
     BuildingGenesisFromBlock0Failed(#[from] Block0ConfigurationError),
+    #[error("invalid CSV file path '{path}'")]
+    CsvInvalid {
+        #[source]
+        source: std::io::Error,
+        path: PathBuf,
+    },
+    #[error("CSV file '{path}' is invalid")]
+    CsvCorrupted {
+        #[source]
+        source: InitialCsvError,
+        path: PathBuf,
+    },
 }
 
 impl Genesis {
     pub fn exec(self) -> Result<(), Error> {
         match self {
             Genesis::Init => init_genesis_yaml(),
-            Genesis::Encode(create_arguments) => encode_block_0(create_arguments),
+            Genesis::Encode(encode_arguments) => encode_block_0(encode_arguments),
             Genesis::Decode(info_arguments) => decode_block_0(info_arguments),
             Genesis::Hash(hash_arguments) => print_hash(hash_arguments),
         }
@@ -59,10 +73,22 @@ fn init_genesis_yaml() -> Result<(), Error> {
     Ok(())
 }
 
-fn encode_block_0(common: Common) -> Result<(), Error> {
+fn encode_block_0(args: EncodeArgs) -> Result<(), Error> {
+    let common = args.common;
     let reader = common.input.open()?;
-    let genesis: Block0Configuration =
+    let mut genesis: Block0Configuration =
         serde_yaml::from_reader(reader).map_err(Error::GenesisFileCorrupted)?;
+    let discrimination = genesis.blockchain_configuration.discrimination;
+    for path in args.initial_funds_csv {
+        let funds = read_csv(&path, |reader| {
+            initial_funds_from_csv(reader, discrimination)
+        })?;
+        genesis.initial.extend(funds);
+    }
+    for path in args.initial_certs_csv {
+        let certs = read_csv(&path, initial_certificates_from_csv)?;
+        genesis.initial.extend(certs);
+    }
     let block = genesis.to_block();
     Ledger::new(block.id(), block.fragments())?;
     block
@@ -70,6 +96,20 @@ fn encode_block_0(common: Common) -> Result<(), Error> {
         .map_err(Error::BlockSerializationFailed)
 }
 
+fn read_csv<T, F>(path: &Path, read: F) -> Result<T, Error>
+where
+    F: FnOnce(BufReader<File>) -> Result<T, InitialCsvError>,
+{
+    let file = File::open(path).map_err(|source| Error::CsvInvalid {
+        source,
+        path: path.to_path_buf(),
+    })?;
+    read(BufReader::new(file)).map_err(|source| Error::CsvCorrupted {
+        source,
+        path: path.to_path_buf(),
+    })
+}
+
 fn decode_block_0(common: Common) -> Result<(), Error> {
     let block = common.input.load_block()?;
     let yaml = Block0Configuration::from_block(&block)?;
@@ -93,7 +133,7 @@ pub enum Genesis {
     /// create the block 0 file (the genesis block of the blockchain)
     /// from a given yaml file
     ///
-    Encode(Common),
+    Encode(EncodeArgs),
 
     /// Decode the block 0 and print the corresponding YAML file
     Decode(Common),
@@ -139,6 +179,23 @@ pub struct Common {
     output_file: Option<std::path::PathBuf>,
 }
 
+#[derive(StructOpt)]
+pub struct EncodeArgs {
+    #[structopt(flatten)]
+    common: Common,
+
+    /// CSV file of initial funds to add to the ones of the genesis file,
+    /// with the header `address,value`. Can be given multiple times.
+    #[structopt(long = "initial-funds-csv", parse(from_os_str))]
+    initial_funds_csv: Vec<PathBuf>,
+
+    /// CSV file of initial signed certificates, e.g. stake delegations,
+    /// to add to the ones of the genesis file, with the header
+    /// `certificate`. Can be given multiple times.
+    #[structopt(long = "initial-certs-csv", parse(from_os_str))]
+    initial_certs_csv: Vec<PathBuf>,
+}
+
 impl Common {
     fn open_output(&self) -> Result<impl Write, Error> {
         io::open_file_write(&self.output_file).map_err(|source| Error::OutputInvalid {
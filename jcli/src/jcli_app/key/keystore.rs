@@ -0,0 +1,207 @@
+use crate::jcli_app::key::{gen_priv_key, read_bech32, sign, to_public, Error, GenPrivKeyType};
+use crate::jcli_app::utils::output_file::OutputFile;
+use chain_crypto::{Curve25519_2HashDH, Ed25519, Ed25519Bip32, Ed25519Extended, SumEd25519_12};
+use jormungandr_lib::crypto::keystore::{KdfParams, Keystore as KeystoreFile};
+use std::{io::Write, path::PathBuf};
+use structopt::StructOpt;
+use zeroize::Zeroizing;
+
+/// environment variable holding the passphrase of the keystore when no
+/// passphrase file is given
+const PASSPHRASE_ENV_VAR: &str = "JCLI_KEYSTORE_PASSPHRASE";
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub enum Keystore {
+    /// create a new empty keystore directory
+    Init(Init),
+    /// store a secret key in the keystore, generating it if a type is given,
+    /// and print its public key
+    Add(Add),
+    /// write a secret key of the keystore in plain text
+    Export(Export),
+    /// sign data with a secret key of the keystore
+    Sign(Sign),
+    /// list the names of the keys in the keystore
+    List(List),
+}
+
+#[derive(StructOpt, Debug)]
+pub struct KeystoreArgs {
+    /// path to the keystore directory
+    #[structopt(long = "keystore")]
+    path: PathBuf,
+
+    /// file holding the passphrase of the keystore, if no value is passed
+    /// the passphrase is read from the JCLI_KEYSTORE_PASSPHRASE environment
+    /// variable
+    #[structopt(long = "passphrase-file")]
+    passphrase_file: Option<PathBuf>,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct Init {
+    #[structopt(flatten)]
+    keystore: KeystoreArgs,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct Add {
+    #[structopt(flatten)]
+    keystore: KeystoreArgs,
+
+    /// name of the key in the keystore
+    #[structopt(long = "name")]
+    name: String,
+
+    /// generate a new private key of the given type
+    ///
+    /// supported values are: ed25519, ed25519bip32, ed25519extended, curve25519_2hashdh or sumed25519_12
+    #[structopt(long = "type", conflicts_with = "input_key")]
+    key_type: Option<GenPrivKeyType>,
+
+    /// the private key to store, if neither this nor a type is given the key
+    /// is read from the standard input
+    #[structopt(long = "input")]
+    input_key: Option<PathBuf>,
+
+    #[structopt(flatten)]
+    output_file: OutputFile,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct Export {
+    #[structopt(flatten)]
+    keystore: KeystoreArgs,
+
+    /// name of the key in the keystore
+    #[structopt(long = "name")]
+    name: String,
+
+    #[structopt(flatten)]
+    output_file: OutputFile,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct Sign {
+    #[structopt(flatten)]
+    keystore: KeystoreArgs,
+
+    /// name of the key in the keystore
+    ///
+    /// supported key formats are: ed25519, ed25519bip32, ed25519extended and sumed25519_12
+    #[structopt(long = "name")]
+    name: String,
+
+    /// path to file to write signature into, if no value is passed, standard output will be used
+    #[structopt(long = "output", short = "o")]
+    output: Option<PathBuf>,
+
+    /// path to file with data to sign, if no value is passed, standard input will be used
+    data: Option<PathBuf>,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct List {
+    #[structopt(flatten)]
+    keystore: KeystoreArgs,
+}
+
+impl Keystore {
+    pub fn exec(self) -> Result<(), Error> {
+        match self {
+            Keystore::Init(args) => args.exec(),
+            Keystore::Add(args) => args.exec(),
+            Keystore::Export(args) => args.exec(),
+            Keystore::Sign(args) => args.exec(),
+            Keystore::List(args) => args.exec(),
+        }
+    }
+}
+
+impl KeystoreArgs {
+    fn passphrase(&self) -> Result<Zeroizing<String>, Error> {
+        match &self.passphrase_file {
+            Some(path) => {
+                let mut passphrase = Zeroizing::new(std::fs::read_to_string(path)?);
+                let len = passphrase.trim_end_matches(&['\r', '\n'][..]).len();
+                passphrase.truncate(len);
+                Ok(passphrase)
+            }
+            None => std::env::var(PASSPHRASE_ENV_VAR)
+                .map(Zeroizing::new)
+                .map_err(|_| Error::NoKeystorePassphrase),
+        }
+    }
+
+    fn open(&self) -> Result<KeystoreFile, Error> {
+        KeystoreFile::open(&self.path, self.passphrase()?.as_bytes()).map_err(Into::into)
+    }
+}
+
+impl Init {
+    fn exec(self) -> Result<(), Error> {
+        if self.keystore.path.exists() {
+            return Err(Error::KeystoreExists(self.keystore.path));
+        }
+        let passphrase = self.keystore.passphrase()?;
+        KeystoreFile::create(
+            &self.keystore.path,
+            passphrase.as_bytes(),
+            KdfParams::default(),
+        )?;
+        Ok(())
+    }
+}
+
+impl Add {
+    fn exec(self) -> Result<(), Error> {
+        let mut keystore = self.keystore.open()?;
+        let secret_key = Zeroizing::new(match self.key_type {
+            Some(GenPrivKeyType::Ed25519) => gen_priv_key::<Ed25519>(None)?,
+            Some(GenPrivKeyType::Ed25519Bip32) => gen_priv_key::<Ed25519Bip32>(None)?,
+            Some(GenPrivKeyType::Ed25519Extended) => gen_priv_key::<Ed25519Extended>(None)?,
+            Some(GenPrivKeyType::SumEd25519_12) => gen_priv_key::<SumEd25519_12>(None)?,
+            Some(GenPrivKeyType::Curve25519_2HashDH) => gen_priv_key::<Curve25519_2HashDH>(None)?,
+            None => {
+                let (hrp, data) = read_bech32(&self.input_key)?;
+                bech32::encode(&hrp, data)?
+            }
+        });
+
+        let (hrp, data) = bech32::decode(&secret_key)?;
+        let public_key = to_public(hrp, &data)?;
+
+        keystore.insert(&self.name, &secret_key)?;
+
+        let mut output = self.output_file.open()?;
+        writeln!(output, "{}", public_key)?;
+        Ok(())
+    }
+}
+
+impl Export {
+    fn exec(self) -> Result<(), Error> {
+        let secret_key = self.keystore.open()?.get(&self.name)?;
+        let mut output = self.output_file.open()?;
+        writeln!(output, "{}", *secret_key)?;
+        Ok(())
+    }
+}
+
+impl Sign {
+    fn exec(self) -> Result<(), Error> {
+        let secret_key = self.keystore.open()?.get(&self.name)?;
+        let (hrp, data) = bech32::decode(&secret_key)?;
+        sign(hrp, &data, &self.data, &self.output)
+    }
+}
+
+impl List {
+    fn exec(self) -> Result<(), Error> {
+        for name in self.keystore.open()?.names() {
+            println!("{}", name);
+        }
+        Ok(())
+    }
+}
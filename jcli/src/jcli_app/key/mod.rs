@@ -1,3 +1,5 @@
+mod keystore;
+
 use crate::jcli_app::utils::io;
 use crate::jcli_app::utils::output_file::{self, OutputFile};
 use bech32::{self, u5, FromBase32, ToBase32};
@@ -8,6 +10,7 @@ use chain_crypto::{
 };
 use ed25519_bip32::{DerivationError, DerivationScheme};
 use hex::FromHexError;
+use jormungandr_lib::crypto::keystore::KeystoreError;
 use rand::{rngs::OsRng, SeedableRng};
 use rand_chacha::ChaChaRng;
 use std::{
@@ -56,6 +59,14 @@ pub enum Error {
         public_hrp: String,
         private_hrp: String,
     },
+    #[error("keystore error")]
+    Keystore(#[from] KeystoreError),
+    #[error("keystore '{0}' already exists")]
+    KeystoreExists(PathBuf),
+    #[error(
+        "no passphrase for the keystore, use --passphrase-file or set JCLI_KEYSTORE_PASSPHRASE"
+    )]
+    NoKeystorePassphrase,
 }
 
 #[derive(StructOpt, Debug)]
@@ -75,6 +86,8 @@ pub enum Key {
     Verify(Verify),
     /// derive a child key from a ed25519bip32 parent key
     Derive(Derive),
+    /// manage the secret keys stored in an encrypted keystore
+    Keystore(keystore::Keystore),
 }
 
 #[derive(StructOpt, Debug)]
@@ -205,6 +218,7 @@ impl Key {
             Key::Sign(args) => args.exec(),
             Key::Verify(args) => args.exec(),
             Key::Derive(args) => args.exec(),
+            Key::Keystore(args) => args.exec(),
         }
     }
 }
@@ -227,14 +241,7 @@ impl Generate {
 impl ToPublic {
     fn exec(self) -> Result<(), Error> {
         let (hrp, data) = read_bech32(&self.input_key)?;
-        let pub_key_bech32 = match hrp.as_ref() {
-            Ed25519::SECRET_BECH32_HRP => gen_pub_key::<Ed25519>(&data),
-            Ed25519Bip32::SECRET_BECH32_HRP => gen_pub_key::<Ed25519Bip32>(&data),
-            Ed25519Extended::SECRET_BECH32_HRP => gen_pub_key::<Ed25519Extended>(&data),
-            SumEd25519_12::SECRET_BECH32_HRP => gen_pub_key::<SumEd25519_12>(&data),
-            Curve25519_2HashDH::SECRET_BECH32_HRP => gen_pub_key::<Curve25519_2HashDH>(&data),
-            _ => Err(Error::UnknownBech32PrivKeyHrp { hrp }),
-        }?;
+        let pub_key_bech32 = to_public(hrp, &data)?;
         let mut output = self.output_file.open()?;
         writeln!(output, "{}", pub_key_bech32)?;
         Ok(())
@@ -284,27 +291,7 @@ impl FromBytes {
 impl Sign {
     fn exec(self) -> Result<(), Error> {
         let (hrp, data) = read_bech32(&self.secret_key)?;
-        let secret_bytes = Vec::<u8>::from_base32(&data)?;
-        match hrp.as_ref() {
-            Ed25519::SECRET_BECH32_HRP => self.sign::<Ed25519>(&secret_bytes),
-            Ed25519Bip32::SECRET_BECH32_HRP => self.sign::<Ed25519Bip32>(&secret_bytes),
-            Ed25519Extended::SECRET_BECH32_HRP => self.sign::<Ed25519Extended>(&secret_bytes),
-            SumEd25519_12::SECRET_BECH32_HRP => self.sign::<SumEd25519_12>(&secret_bytes),
-            _ => Err(Error::UnknownBech32PrivKeyHrp { hrp }),
-        }
-    }
-
-    fn sign<A>(self, secret_bytes: &[u8]) -> Result<(), Error>
-    where
-        A: SigningAlgorithm,
-        <A as AsymmetricKey>::PubAlg: VerificationAlgorithm,
-    {
-        let secret = SecretKey::<A>::from_binary(secret_bytes)?;
-        let mut data = Vec::new();
-        io::open_file_read(&self.data)?.read_to_end(&mut data)?;
-        let signature = secret.sign(&data);
-        io::open_file_write(&self.output)?.write_all(signature.to_bech32_str().as_ref())?;
-        Ok(())
+        sign(hrp, &data, &self.data, &self.output)
     }
 }
 
@@ -380,6 +367,52 @@ impl Derive {
     }
 }
 
+fn to_public(hrp: String, secret_key: &[u5]) -> Result<String, Error> {
+    match hrp.as_ref() {
+        Ed25519::SECRET_BECH32_HRP => gen_pub_key::<Ed25519>(secret_key),
+        Ed25519Bip32::SECRET_BECH32_HRP => gen_pub_key::<Ed25519Bip32>(secret_key),
+        Ed25519Extended::SECRET_BECH32_HRP => gen_pub_key::<Ed25519Extended>(secret_key),
+        SumEd25519_12::SECRET_BECH32_HRP => gen_pub_key::<SumEd25519_12>(secret_key),
+        Curve25519_2HashDH::SECRET_BECH32_HRP => gen_pub_key::<Curve25519_2HashDH>(secret_key),
+        _ => Err(Error::UnknownBech32PrivKeyHrp { hrp }),
+    }
+}
+
+fn sign(
+    hrp: String,
+    secret_key: &[u5],
+    data: &Option<PathBuf>,
+    output: &Option<PathBuf>,
+) -> Result<(), Error> {
+    let secret_bytes = Vec::<u8>::from_base32(secret_key)?;
+    match hrp.as_ref() {
+        Ed25519::SECRET_BECH32_HRP => sign_with::<Ed25519>(&secret_bytes, data, output),
+        Ed25519Bip32::SECRET_BECH32_HRP => sign_with::<Ed25519Bip32>(&secret_bytes, data, output),
+        Ed25519Extended::SECRET_BECH32_HRP => {
+            sign_with::<Ed25519Extended>(&secret_bytes, data, output)
+        }
+        SumEd25519_12::SECRET_BECH32_HRP => sign_with::<SumEd25519_12>(&secret_bytes, data, output),
+        _ => Err(Error::UnknownBech32PrivKeyHrp { hrp }),
+    }
+}
+
+fn sign_with<A>(
+    secret_bytes: &[u8],
+    data: &Option<PathBuf>,
+    output: &Option<PathBuf>,
+) -> Result<(), Error>
+where
+    A: SigningAlgorithm,
+    <A as AsymmetricKey>::PubAlg: VerificationAlgorithm,
+{
+    let secret = SecretKey::<A>::from_binary(secret_bytes)?;
+    let mut bytes = Vec::new();
+    io::open_file_read(data)?.read_to_end(&mut bytes)?;
+    let signature = secret.sign(&bytes);
+    io::open_file_write(output)?.write_all(signature.to_bech32_str().as_ref())?;
+    Ok(())
+}
+
 fn read_hex<P: AsRef<Path>>(path: &Option<P>) -> Result<Vec<u8>, Error> {
     hex::decode(io::read_line(path)?).map_err(Into::into)
 }
@@ -3,16 +3,22 @@ use reqwest::{
     blocking::{Client, RequestBuilder},
     Url,
 };
-use std::path::PathBuf;
+use std::{path::PathBuf, str::FromStr};
 use structopt::StructOpt;
 use thiserror::Error;
 
 #[derive(StructOpt)]
 pub struct RestArgs {
-    /// node API address. Must always have `http://` or `https://` prefix.
-    /// E.g. `-h http://127.0.0.1`, `--host https://node.com:8443/cardano/api`
+    /// node API address. Must always have `http://`, `https://` or `unix:`
+    /// prefix, the latter for a node serving its API on a Unix socket.
+    /// E.g. `-h http://127.0.0.1`, `--host https://node.com:8443/cardano/api`,
+    /// `--host unix:/run/jormungandr/rest.sock`
     #[structopt(short, long, env = "JORMUNGANDR_RESTAPI_URL")]
-    host: Url,
+    host: Host,
+    /// token sent in the `Authorization: Bearer` header, for the endpoints
+    /// of a node configured with an admin token
+    #[structopt(long, name = "TOKEN", env = "JORMUNGANDR_API_TOKEN")]
+    api_token: Option<String>,
     /// print additional debug information to stderr.
     /// The output format is intentionally undocumented and unstable
     #[structopt(long)]
@@ -23,19 +29,38 @@ pub struct RestArgs {
     tls_cert_path: Option<PathBuf>,
 }
 
+/// address of the node API
+#[derive(Debug, Clone)]
+pub enum Host {
+    Url(Url),
+    Unix(PathBuf),
+}
+
+const UNIX_PREFIX: &str = "unix:";
+
+/// base of the URL of the requests sent on a Unix socket, only the path and
+/// query of the URL are actually sent
+const UNIX_BASE_URL: &str = "http://localhost/";
+
 pub struct RestClient {
     client: Client,
     debug: bool,
     base_url: Url,
+    unix_socket: Option<PathBuf>,
+    api_token: Option<String>,
 }
 
 pub struct RestRequestBuilder {
     client: Client,
     request_builder: RequestBuilder,
+    unix_socket: Option<PathBuf>,
     debug: bool,
 }
 
-pub struct RestResponse(reqwest::blocking::Response);
+pub enum RestResponse {
+    Http(reqwest::blocking::Response),
+    Unix(Bytes),
+}
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -65,6 +90,25 @@ pub enum Error {
     Redirecton(#[source] reqwest::Error),
     #[error("communication with node failed in unexpected way")]
     UnexpectedError(#[source] reqwest::Error),
+    #[error("communication with node over the Unix socket failed")]
+    UnixSocket(#[source] std::io::Error),
+    #[error("node responded with status {0}")]
+    UnixStatus(reqwest::StatusCode),
+    #[error("could not deserialize the response as JSON")]
+    UnixJson(#[source] serde_json::Error),
+    #[error("the response is not valid UTF-8")]
+    UnixText(#[source] std::string::FromUtf8Error),
+}
+
+impl FromStr for Host {
+    type Err = <Url as FromStr>::Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix(UNIX_PREFIX) {
+            Some(path) => Ok(Host::Unix(PathBuf::from(path))),
+            None => s.parse().map(Host::Url),
+        }
+    }
 }
 
 impl RestArgs {
@@ -75,11 +119,17 @@ impl RestArgs {
         let Self {
             tls_cert_path,
             host,
+            api_token,
             debug,
         } = self;
 
-        if host.cannot_be_a_base() {
-            return Err(Error::HostAddrNotBase { addr: host });
+        let (base_url, unix_socket) = match host {
+            Host::Url(url) => (url, None),
+            Host::Unix(path) => (Url::parse(UNIX_BASE_URL).unwrap(), Some(path)),
+        };
+
+        if base_url.cannot_be_a_base() {
+            return Err(Error::HostAddrNotBase { addr: base_url });
         }
 
         let client_builder = ClientBuilder::new();
@@ -102,7 +152,9 @@ impl RestArgs {
         let rest_client = RestClient {
             client,
             debug,
-            base_url: host,
+            base_url,
+            unix_socket,
+            api_token,
         };
 
         Ok(rest_client)
@@ -129,13 +181,19 @@ impl RestClient {
         let Self {
             client,
             base_url,
+            unix_socket,
+            api_token,
             debug,
         } = self;
         let url = make_url(base_url, address_segments);
-        let request_builder = f(&client, url);
+        let mut request_builder = f(&client, url);
+        if let Some(token) = api_token {
+            request_builder = request_builder.bearer_auth(token);
+        }
         RestRequestBuilder {
             client,
             request_builder,
+            unix_socket,
             debug,
         }
     }
@@ -184,6 +242,7 @@ impl RestRequestBuilder {
         let Self {
             client,
             request_builder,
+            unix_socket,
             debug,
         } = self;
 
@@ -193,6 +252,10 @@ impl RestRequestBuilder {
             eprintln!("Request: {:?}", request);
         }
 
+        if let Some(path) = unix_socket {
+            return execute_unix(&path, &request, debug);
+        }
+
         let response = client
             .execute(request)
             .and_then(|response| response.error_for_status())
@@ -218,23 +281,72 @@ impl RestRequestBuilder {
             eprintln!("Response: {:?}", response);
         }
 
-        Ok(RestResponse(response))
+        Ok(RestResponse::Http(response))
     }
 }
 
+fn execute_unix(
+    path: &std::path::Path,
+    request: &reqwest::blocking::Request,
+    debug: bool,
+) -> Result<RestResponse, Error> {
+    use std::io::ErrorKind;
+
+    let response = super::unix::send(path, request).map_err(|e| match e.kind() {
+        ErrorKind::TimedOut | ErrorKind::WouldBlock => Error::Timeout,
+        _ => Error::UnixSocket(e),
+    })?;
+
+    if debug {
+        eprintln!("Response: status {}", response.status);
+    }
+
+    if response.status.is_client_error() || response.status.is_server_error() {
+        return Err(Error::UnixStatus(response.status));
+    }
+
+    Ok(RestResponse::Unix(response.body))
+}
+
 impl RestResponse {
     pub fn json<T>(self) -> Result<T, Error>
     where
         T: serde::de::DeserializeOwned,
     {
-        self.0.json().map_err(Error::Json)
+        match self {
+            RestResponse::Http(response) => response.json().map_err(Error::Json),
+            RestResponse::Unix(body) => serde_json::from_slice(&body).map_err(Error::UnixJson),
+        }
     }
 
     pub fn bytes(self) -> Result<Bytes, Error> {
-        self.0.bytes().map_err(Error::Bytes)
+        match self {
+            RestResponse::Http(response) => response.bytes().map_err(Error::Bytes),
+            RestResponse::Unix(body) => Ok(body),
+        }
     }
 
     pub fn text(self) -> Result<String, Error> {
-        self.0.text().map_err(Error::Text)
+        match self {
+            RestResponse::Http(response) => response.text().map_err(Error::Text),
+            RestResponse::Unix(body) => String::from_utf8(body.to_vec()).map_err(Error::UnixText),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unix_prefix_selects_a_socket_path() {
+        match "unix:/run/jormungandr/rest.sock".parse().unwrap() {
+            Host::Unix(path) => assert_eq!(path, PathBuf::from("/run/jormungandr/rest.sock")),
+            host => panic!("unexpected host {:?}", host),
+        }
+        match "http://127.0.0.1:8443/api".parse().unwrap() {
+            Host::Url(url) => assert_eq!(url.as_str(), "http://127.0.0.1:8443/api"),
+            host => panic!("unexpected host {:?}", host),
+        }
     }
 }
@@ -1,4 +1,5 @@
 mod config;
+mod unix;
 mod v0;
 
 use crate::jcli_app::utils::{io::ReadYamlError, output_format};
@@ -29,6 +30,12 @@ pub enum Error {
     InputHexMalformed(#[from] FromHexError),
     #[error("error when trying to perform an HTTP request")]
     RequestError(#[from] config::Error),
+    #[error("the receipt returned by the node is malformed")]
+    ReceiptMalformed(#[source] serde_json::Error),
+    #[error("the receipt is not signed by the node it names")]
+    ReceiptSignatureInvalid,
+    #[error("the receipt is for the fragment {0}, not the posted one")]
+    ReceiptFragmentMismatch(String),
 }
 
 impl From<ReadYamlError> for Error {
@@ -0,0 +1,193 @@
+//! requests to a node serving its REST API on a Unix domain socket
+//!
+//! The requests are sent as HTTP/1.0, so the node closes the connection
+//! once the response is written and the body is read up to the end of the
+//! stream, without having to handle the chunked encoding.
+
+use bytes::Bytes;
+use reqwest::{blocking::Request, StatusCode};
+use std::{
+    io::{self, Write},
+    path::Path,
+};
+
+#[cfg(unix)]
+const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+pub struct Response {
+    pub status: StatusCode,
+    pub body: Bytes,
+}
+
+#[cfg(unix)]
+pub fn send(path: &Path, request: &Request) -> io::Result<Response> {
+    use std::{io::Read, os::unix::net::UnixStream};
+
+    let mut stream = UnixStream::connect(path)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    stream.write_all(&encode_request(request)?)?;
+
+    let mut buf = Vec::new();
+    stream.read_to_end(&mut buf)?;
+    decode_response(buf)
+}
+
+#[cfg(not(unix))]
+pub fn send(_path: &Path, _request: &Request) -> io::Result<Response> {
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        "Unix sockets are not supported on this platform",
+    ))
+}
+
+fn encode_request(request: &Request) -> io::Result<Vec<u8>> {
+    let body = match request.body() {
+        None => &[][..],
+        Some(body) => body.as_bytes().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "streamed bodies are not supported",
+            )
+        })?,
+    };
+    let url = request.url();
+    let target = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_owned(),
+    };
+
+    let mut buf = Vec::with_capacity(body.len() + 256);
+    write!(buf, "{} {} HTTP/1.0\r\n", request.method(), target)?;
+    write!(buf, "Host: localhost\r\n")?;
+    for (name, value) in request.headers() {
+        write!(buf, "{}: ", name)?;
+        buf.extend_from_slice(value.as_bytes());
+        buf.extend_from_slice(b"\r\n");
+    }
+    write!(buf, "Content-Length: {}\r\n\r\n", body.len())?;
+    buf.extend_from_slice(body);
+    Ok(buf)
+}
+
+fn decode_response(mut buf: Vec<u8>) -> io::Result<Response> {
+    let malformed = || io::Error::new(io::ErrorKind::InvalidData, "malformed HTTP response");
+
+    let head_len = buf
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or_else(malformed)?;
+    let status = std::str::from_utf8(&buf[..head_len])
+        .ok()
+        .and_then(|head| head.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .and_then(|code| StatusCode::from_u16(code).ok())
+        .ok_or_else(malformed)?;
+    let body = buf.split_off(head_len + 4);
+
+    Ok(Response {
+        status,
+        body: body.into(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::blocking::Client;
+
+    fn request_text(request: &Request) -> String {
+        String::from_utf8(encode_request(request).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn requests_are_encoded_as_http_1_0() {
+        let request = Client::new()
+            .post("http://localhost/api/v0/message?force=true")
+            .header("Authorization", "Bearer token")
+            .body(b"fragment".to_vec())
+            .build()
+            .unwrap();
+        let text = request_text(&request);
+
+        assert!(text.starts_with("POST /api/v0/message?force=true HTTP/1.0\r\nHost: localhost\r\n"));
+        assert!(text.contains("\r\nauthorization: Bearer token\r\n"));
+        assert!(text.ends_with("\r\nContent-Length: 8\r\n\r\nfragment"));
+    }
+
+    #[test]
+    fn requests_without_body_have_a_zero_length() {
+        let request = Client::new()
+            .get("http://localhost/api/v0/node/stats")
+            .build()
+            .unwrap();
+        let text = request_text(&request);
+
+        assert!(text.starts_with("GET /api/v0/node/stats HTTP/1.0\r\n"));
+        assert!(text.ends_with("Content-Length: 0\r\n\r\n"));
+    }
+
+    #[test]
+    fn responses_are_split_into_status_and_body() {
+        let response = decode_response(
+            b"HTTP/1.0 404 Not Found\r\nContent-Type: text/plain\r\n\r\nno such block\r\n\r\n"
+                .to_vec(),
+        )
+        .unwrap();
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert_eq!(&response.body[..], b"no such block\r\n\r\n");
+
+        let response = decode_response(b"HTTP/1.1 200 OK\r\n\r\n".to_vec()).unwrap();
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(response.body.is_empty());
+    }
+
+    #[test]
+    fn malformed_responses_are_rejected() {
+        for response in &[
+            &b"HTTP/1.0 200 OK\r\nContent-Length: 2\r\n"[..],
+            &b"HTTP/1.0 OK\r\n\r\n"[..],
+            &b"\r\n\r\n"[..],
+        ] {
+            let error = decode_response(response.to_vec()).err().unwrap();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn requests_are_sent_on_the_socket() {
+        use std::{io::Read, os::unix::net::UnixListener};
+
+        let dir = assert_fs::TempDir::new().unwrap();
+        let path = dir.path().join("rest.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut head = Vec::new();
+            let mut byte = [0];
+            while !head.ends_with(b"\r\n\r\n") {
+                stream.read_exact(&mut byte).unwrap();
+                head.push(byte[0]);
+            }
+            stream
+                .write_all(b"HTTP/1.0 200 OK\r\nContent-Type: text/plain\r\n\r\ntip")
+                .unwrap();
+            String::from_utf8(head).unwrap()
+        });
+
+        let request = Client::new()
+            .get("http://localhost/api/v0/tip")
+            .build()
+            .unwrap();
+        let response = send(&path, &request).unwrap();
+
+        assert!(server
+            .join()
+            .unwrap()
+            .starts_with("GET /api/v0/tip HTTP/1.0\r\n"));
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(&response.body[..], b"tip");
+    }
+}
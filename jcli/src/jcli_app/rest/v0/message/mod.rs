@@ -2,8 +2,9 @@ use crate::jcli_app::{
     rest::{Error, RestArgs},
     utils::{io, OutputFormat},
 };
-use chain_core::property::Deserialize;
+use chain_core::property::{Deserialize, Fragment as _};
 use chain_impl_mockchain::fragment::Fragment;
+use jormungandr_lib::{crypto::hash::Hash, interfaces::FragmentReceipt};
 use std::path::PathBuf;
 use structopt::StructOpt;
 
@@ -18,6 +19,11 @@ pub enum Message {
         /// If not provided, message will be read from stdin.
         #[structopt(short, long)]
         file: Option<PathBuf>,
+        /// Request a receipt signed by the node once the message is accepted
+        /// in its mempool, and print it instead of the id. Only available for
+        /// vote casts.
+        #[structopt(long)]
+        receipt: bool,
     },
 
     /// get the node's logs on the message pool. This will provide information
@@ -34,7 +40,11 @@ pub enum Message {
 impl Message {
     pub fn exec(self) -> Result<(), Error> {
         match self {
-            Message::Post { args, file } => post_message(args, file),
+            Message::Post {
+                args,
+                file,
+                receipt,
+            } => post_message(args, file, receipt),
             Message::Logs {
                 args,
                 output_format,
@@ -54,11 +64,14 @@ fn get_logs(args: RestArgs, output_format: OutputFormat) -> Result<(), Error> {
     Ok(())
 }
 
-fn post_message(args: RestArgs, file: Option<PathBuf>) -> Result<(), Error> {
+fn post_message(args: RestArgs, file: Option<PathBuf>, receipt: bool) -> Result<(), Error> {
     let msg_hex = io::read_line(&file)?;
     let msg_bin = hex::decode(&msg_hex)?;
-    let _fragment =
+    let fragment =
         Fragment::deserialize(msg_bin.as_slice()).map_err(Error::InputFragmentMalformed)?;
+    if receipt {
+        return post_message_with_receipt(args, &fragment, msg_bin);
+    }
     let fragment_id = args
         .client()?
         .post(&["v0", "message"])
@@ -68,3 +81,31 @@ fn post_message(args: RestArgs, file: Option<PathBuf>) -> Result<(), Error> {
     println!("{}", fragment_id);
     Ok(())
 }
+
+/// post the message and check the receipt returned by the node is signed
+/// by the node it names and is for the posted fragment
+fn post_message_with_receipt(
+    args: RestArgs,
+    fragment: &Fragment,
+    msg_bin: Vec<u8>,
+) -> Result<(), Error> {
+    let response = args
+        .client()?
+        .post(&["v0", "message"])
+        .query(&[("receipt", "true")])
+        .body(msg_bin)
+        .execute()?
+        .text()?;
+    let receipt: FragmentReceipt =
+        serde_json::from_str(&response).map_err(Error::ReceiptMalformed)?;
+    if !receipt.verify() {
+        return Err(Error::ReceiptSignatureInvalid);
+    }
+    if *receipt.fragment_id() != Hash::from(fragment.id()) {
+        return Err(Error::ReceiptFragmentMismatch(
+            receipt.fragment_id().to_string(),
+        ));
+    }
+    println!("{}", response);
+    Ok(())
+}
@@ -1,6 +1,12 @@
+mod quarantined;
+mod self_view;
 mod stats;
+mod view;
 
+use self::quarantined::Quarantined;
+use self::self_view::SelfView;
 use self::stats::Stats;
+use self::view::View;
 use crate::jcli_app::rest::Error;
 use structopt::StructOpt;
 
@@ -9,12 +15,22 @@ use structopt::StructOpt;
 pub enum Network {
     /// Network information
     Stats(Stats),
+    /// Nodes quarantined for misbehaving or being unreachable
+    Quarantined(Quarantined),
+    /// Nodes selected for gossiping and peer discovery
+    View(View),
+    /// What the node advertises about itself
+    #[structopt(name = "self")]
+    SelfView(SelfView),
 }
 
 impl Network {
     pub fn exec(self) -> Result<(), Error> {
         match self {
             Network::Stats(stats) => stats.exec(),
+            Network::Quarantined(quarantined) => quarantined.exec(),
+            Network::View(view) => view.exec(),
+            Network::SelfView(self_view) => self_view.exec(),
         }
     }
 }
@@ -0,0 +1,32 @@
+use crate::jcli_app::rest::{Error, RestArgs};
+use crate::jcli_app::utils::OutputFormat;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub enum Quarantined {
+    /// Get the nodes that have been quarantined
+    Get {
+        #[structopt(flatten)]
+        args: RestArgs,
+        #[structopt(flatten)]
+        output_format: OutputFormat,
+    },
+}
+
+impl Quarantined {
+    pub fn exec(self) -> Result<(), Error> {
+        let Quarantined::Get {
+            args,
+            output_format,
+        } = self;
+        let response = args
+            .client()?
+            .get(&["v0", "network", "p2p", "quarantined"])
+            .execute()?
+            .json()?;
+        let formatted = output_format.format_json(response)?;
+        println!("{}", formatted);
+        Ok(())
+    }
+}
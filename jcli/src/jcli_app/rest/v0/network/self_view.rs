@@ -0,0 +1,32 @@
+use crate::jcli_app::rest::{Error, RestArgs};
+use crate::jcli_app::utils::OutputFormat;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub enum SelfView {
+    /// Get what the node tells the network about itself
+    Get {
+        #[structopt(flatten)]
+        args: RestArgs,
+        #[structopt(flatten)]
+        output_format: OutputFormat,
+    },
+}
+
+impl SelfView {
+    pub fn exec(self) -> Result<(), Error> {
+        let SelfView::Get {
+            args,
+            output_format,
+        } = self;
+        let response = args
+            .client()?
+            .get(&["v0", "network", "p2p", "self"])
+            .execute()?
+            .json()?;
+        let formatted = output_format.format_json(response)?;
+        println!("{}", formatted);
+        Ok(())
+    }
+}
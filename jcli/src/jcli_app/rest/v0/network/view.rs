@@ -0,0 +1,34 @@
+use crate::jcli_app::rest::{Error, RestArgs};
+use crate::jcli_app::utils::OutputFormat;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub enum View {
+    /// Get the nodes selected for gossiping and peer discovery
+    Get {
+        #[structopt(flatten)]
+        args: RestArgs,
+        #[structopt(flatten)]
+        output_format: OutputFormat,
+        /// Only the nodes selected for the topic, `blocks` or `fragments`
+        #[structopt(long, possible_values = &["blocks", "fragments"])]
+        topic: Option<String>,
+    },
+}
+
+impl View {
+    pub fn exec(self) -> Result<(), Error> {
+        let View::Get {
+            args,
+            output_format,
+            topic,
+        } = self;
+        let mut path = vec!["v0", "network", "p2p", "view"];
+        path.extend(topic.as_deref());
+        let response = args.client()?.get(&path).execute()?.json()?;
+        let formatted = output_format.format_json(response)?;
+        println!("{}", formatted);
+        Ok(())
+    }
+}
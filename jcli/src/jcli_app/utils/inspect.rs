@@ -0,0 +1,159 @@
+use crate::jcli_app::utils::{output_format, OutputFormat};
+use bech32::FromBase32;
+use chain_addr::{AddressReadable, Discrimination, Kind};
+use chain_crypto::{
+    bech32::Bech32 as _, AsymmetricKey, AsymmetricPublicKey, Curve25519_2HashDH, Ed25519,
+    Ed25519Bip32, Ed25519Extended, SecretKey, SumEd25519_12,
+};
+use jormungandr_lib::interfaces::{CERTIFICATE_HRP, SIGNED_CERTIFICATE_HRP};
+use serde_json::{json, Value as JsonValue};
+use structopt::StructOpt;
+use thiserror::Error;
+
+/// size of the Blake2b256 hashes identifying blocks, fragments and transactions
+const HASH_SIZE: usize = 32;
+
+#[derive(StructOpt)]
+pub struct Inspect {
+    /// the string to inspect: a bech32 address, key or certificate,
+    /// or a hex-encoded block hash or fragment id
+    #[structopt(name = "INPUT")]
+    input: String,
+
+    #[structopt(flatten)]
+    output_format: OutputFormat,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid bech32 data")]
+    InvalidBech32(#[from] bech32::Error),
+    #[error("invalid secret key")]
+    InvalidSecretKey(#[from] chain_crypto::SecretKeyError),
+    #[error("input is neither bech32 nor hex-encoded data")]
+    UnknownFormat,
+    #[error("formatting output failed")]
+    OutputFormatFailed(#[from] output_format::Error),
+}
+
+impl Inspect {
+    pub fn exec(self) -> Result<(), Error> {
+        let info = inspect(self.input.trim())?;
+        println!("{}", self.output_format.format_json(info)?);
+        Ok(())
+    }
+}
+
+fn inspect(input: &str) -> Result<JsonValue, Error> {
+    if let Ok((hrp, data)) = bech32::decode(input) {
+        if let Ok(address) = input.parse::<AddressReadable>() {
+            return Ok(address_info(&hrp, &address));
+        }
+        let bytes = Vec::<u8>::from_base32(&data)?;
+        return bech32_info(&hrp, &bytes);
+    }
+
+    match hex::decode(input) {
+        Ok(bytes) if bytes.len() == HASH_SIZE => Ok(json!({
+            "type": "hash",
+            "description": "block hash, fragment id or transaction id",
+            "hex": input.to_lowercase(),
+        })),
+        Ok(bytes) => Ok(json!({
+            "type": "bytes",
+            "size": bytes.len(),
+        })),
+        Err(_) => Err(Error::UnknownFormat),
+    }
+}
+
+fn address_info(prefix: &str, address: &AddressReadable) -> JsonValue {
+    let chain_addr::Address(discrimination, kind) = address.to_address();
+    let discrimination = match discrimination {
+        Discrimination::Production => "production",
+        Discrimination::Test => "testing",
+    };
+    let mut info = json!({
+        "type": "address",
+        "prefix": prefix,
+        "discrimination": discrimination,
+    });
+    let parts = match kind {
+        Kind::Single(single) => json!({
+            "kind": "single",
+            "spending_key": single.to_bech32_str(),
+        }),
+        Kind::Group(spending, delegation) => json!({
+            "kind": "group",
+            "spending_key": spending.to_bech32_str(),
+            "delegation_key": delegation.to_bech32_str(),
+        }),
+        Kind::Account(account) => json!({
+            "kind": "account",
+            "account_key": account.to_bech32_str(),
+        }),
+        Kind::Multisig(id) => json!({
+            "kind": "multisig",
+            "multisig_identifier": hex::encode(&id),
+        }),
+        Kind::Script(id) => json!({
+            "kind": "script",
+            "script_identifier": hex::encode(&id),
+        }),
+    };
+    if let (Some(info), JsonValue::Object(parts)) = (info.as_object_mut(), parts) {
+        info.extend(parts);
+    }
+    info
+}
+
+fn bech32_info(hrp: &str, bytes: &[u8]) -> Result<JsonValue, Error> {
+    match hrp {
+        Ed25519::PUBLIC_BECH32_HRP => Ok(public_key_info("ed25519", bytes)),
+        Ed25519Bip32::PUBLIC_BECH32_HRP => Ok(public_key_info("ed25519-bip32", bytes)),
+        SumEd25519_12::PUBLIC_BECH32_HRP => Ok(public_key_info("sum-ed25519-12", bytes)),
+        Curve25519_2HashDH::PUBLIC_BECH32_HRP => Ok(public_key_info("curve25519-2hashdh", bytes)),
+        Ed25519::SECRET_BECH32_HRP => secret_key_info::<Ed25519>("ed25519", bytes),
+        Ed25519Bip32::SECRET_BECH32_HRP => secret_key_info::<Ed25519Bip32>("ed25519-bip32", bytes),
+        Ed25519Extended::SECRET_BECH32_HRP => {
+            secret_key_info::<Ed25519Extended>("ed25519-extended", bytes)
+        }
+        SumEd25519_12::SECRET_BECH32_HRP => {
+            secret_key_info::<SumEd25519_12>("sum-ed25519-12", bytes)
+        }
+        Curve25519_2HashDH::SECRET_BECH32_HRP => {
+            secret_key_info::<Curve25519_2HashDH>("curve25519-2hashdh", bytes)
+        }
+        CERTIFICATE_HRP => Ok(json!({
+            "type": "certificate",
+            "size": bytes.len(),
+        })),
+        SIGNED_CERTIFICATE_HRP => Ok(json!({
+            "type": "signed certificate",
+            "size": bytes.len(),
+        })),
+        _ => Ok(json!({
+            "type": "bech32",
+            "prefix": hrp,
+            "hex": hex::encode(bytes),
+        })),
+    }
+}
+
+fn public_key_info(algorithm: &str, bytes: &[u8]) -> JsonValue {
+    json!({
+        "type": "public key",
+        "algorithm": algorithm,
+        "hex": hex::encode(bytes),
+    })
+}
+
+/// the secret itself is never printed, only the matching public key
+fn secret_key_info<A: AsymmetricKey>(algorithm: &str, bytes: &[u8]) -> Result<JsonValue, Error> {
+    let secret = SecretKey::<A>::from_binary(bytes)?;
+    Ok(json!({
+        "type": "secret key",
+        "algorithm": algorithm,
+        "public_key": secret.to_public().to_bech32_str(),
+    }))
+}
@@ -1,4 +1,5 @@
 mod account_id;
+mod inspect;
 
 pub mod io;
 pub mod key_parser;
@@ -18,6 +19,9 @@ use thiserror::Error;
 pub enum Utils {
     /// convert a bech32 with hrp n into a bech32 with prefix m
     Bech32Convert(Bech32ConvertArgs),
+    /// detect the kind of a bech32 or hex-encoded string and display
+    /// what can be decoded from it
+    Inspect(inspect::Inspect),
 }
 
 #[derive(StructOpt)]
@@ -35,6 +39,8 @@ pub struct Bech32ConvertArgs {
 pub enum Error {
     #[error("failed to convert bech32")]
     Bech32ConversionFailure(#[from] bech32::Error),
+    #[error("failed to inspect the input")]
+    Inspect(#[from] inspect::Error),
 }
 
 impl Utils {
@@ -43,6 +49,7 @@ impl Utils {
             Utils::Bech32Convert(convert_args) => {
                 convert_prefix(convert_args.from_bech32, convert_args.new_hrp).map_err(|e| e)
             }
+            Utils::Inspect(inspect) => inspect.exec().map_err(Into::into),
         }
     }
 }
@@ -6,7 +6,7 @@ use thiserror::Error;
 
 #[derive(StructOpt)]
 pub struct OutputFormat {
-    /// Format of output data. Possible values: json, yaml.
+    /// Format of output data. Possible values: json, yaml, table.
     /// Any other value is treated as a custom format using values from output data structure.
     /// Syntax is Go text template: https://golang.org/pkg/text/template/.
     #[structopt(long = "output-format", default_value = "yaml", parse(from_str))]
@@ -16,6 +16,7 @@ pub struct OutputFormat {
 enum FormatVariant {
     Yaml,
     Json,
+    Table,
     Custom(String),
 }
 
@@ -24,6 +25,7 @@ impl<'a> From<&'a str> for FormatVariant {
         match format.trim().to_ascii_lowercase().as_str() {
             "yaml" => FormatVariant::Yaml,
             "json" => FormatVariant::Json,
+            "table" => FormatVariant::Table,
             _ => FormatVariant::Custom(format.to_string()),
         }
     }
@@ -55,6 +57,7 @@ impl OutputFormat {
         Ok(match self.format {
             FormatVariant::Yaml => serde_yaml::to_string(&data)?,
             FormatVariant::Json => serde_json::to_string_pretty(&data)?,
+            FormatVariant::Table => format_table(&data),
             FormatVariant::Custom(ref format) => {
                 let gtmpl_value = json_value_to_gtmpl(data);
                 gtmpl::template(format.as_str(), gtmpl_value).map_err(GtmplError)?
@@ -63,6 +66,67 @@ impl OutputFormat {
     }
 }
 
+/// An array of objects is printed with a row per object and a column per
+/// field, an object with a row per field. The nested values are printed as
+/// compact JSON.
+fn format_table(data: &JsonValue) -> String {
+    let rows: Vec<Vec<String>> = match data {
+        JsonValue::Array(items) if items.iter().all(JsonValue::is_object) => {
+            let mut columns: Vec<&str> = Vec::new();
+            for item in items.iter().filter_map(JsonValue::as_object) {
+                for key in item.keys() {
+                    if !columns.contains(&key.as_str()) {
+                        columns.push(key);
+                    }
+                }
+            }
+            let header = columns.iter().map(|column| column.to_string()).collect();
+            let body = items.iter().filter_map(JsonValue::as_object).map(|item| {
+                columns
+                    .iter()
+                    .map(|column| item.get(*column).map_or_else(String::new, table_cell))
+                    .collect()
+            });
+            std::iter::once(header).chain(body).collect()
+        }
+        JsonValue::Array(items) => items.iter().map(|item| vec![table_cell(item)]).collect(),
+        JsonValue::Object(object) => object
+            .iter()
+            .map(|(key, value)| vec![key.clone(), table_cell(value)])
+            .collect(),
+        value => vec![vec![table_cell(value)]],
+    };
+    let mut widths: Vec<usize> = Vec::new();
+    for row in &rows {
+        for (i, cell) in row.iter().enumerate() {
+            let width = cell.chars().count();
+            match widths.get_mut(i) {
+                Some(max) => *max = (*max).max(width),
+                None => widths.push(width),
+            }
+        }
+    }
+    rows.iter()
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect();
+            cells.join("  ").trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn table_cell(value: &JsonValue) -> String {
+    match value {
+        JsonValue::Null => String::new(),
+        JsonValue::String(string) => string.clone(),
+        value => value.to_string(),
+    }
+}
+
 fn json_value_to_gtmpl(value: JsonValue) -> GtmplValue {
     match value {
         JsonValue::Null => GtmplValue::Nil,
@@ -93,3 +157,27 @@ fn json_object_to_gtmpl(object: JsonMap<String, JsonValue>) -> GtmplValue {
         .collect();
     GtmplValue::Object(values)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn table_has_a_column_per_field() {
+        let data = json!([
+            { "address": "/ip4/1.2.3.4/tcp/3000", "id": "a" },
+            { "id": "bcd", "layers": ["rings"] },
+        ]);
+        let expected = [
+            "address                id   layers",
+            "/ip4/1.2.3.4/tcp/3000  a",
+            "                       bcd  [\"rings\"]",
+        ];
+        assert_eq!(format_table(&data), expected.join("\n"));
+        assert_eq!(
+            format_table(&json!({ "nodeId": "ab", "address": null })),
+            "address\nnodeId   ab"
+        );
+    }
+}
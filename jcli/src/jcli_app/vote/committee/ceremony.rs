@@ -0,0 +1,632 @@
+//! generation of the committee member keys across the committee members
+//!
+//! The ceremony replaces the manual sequence of the `crs`,
+//! `communication-key` and `member-key` invocations. The files of each round
+//! are exchanged out-of-band between a coordinator and the members, the
+//! secret keys never leaving the members:
+//!
+//! 1. the coordinator creates the ceremony file with `init`;
+//! 2. each member generates its communication key with `communication-key`
+//!    and sends back the resulting share;
+//! 3. the coordinator gathers the shares with `collect` and sends the
+//!    resulting ceremony file to the members;
+//! 4. each member generates its member key with `member-key` and sends back
+//!    the resulting share;
+//! 5. the coordinator gathers the shares with `finalize`, giving the member
+//!    public keys of the vote plan and the encrypting vote key.
+
+use super::member_key::{parse_crs, parse_member_communication_key};
+use crate::jcli_app::{
+    utils::io,
+    vote::{bech32_constants, encrypting_vote_key::parse_member_key, Error, OutputFile, Seed},
+};
+use bech32::{FromBase32, ToBase32};
+use chain_vote::{MemberCommunicationKey, MemberState};
+use rand::rngs::OsRng;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+};
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub enum Ceremony {
+    /// create the ceremony file of a committee, with a new common reference
+    /// string
+    Init(Init),
+    /// generate the communication key of a committee member and the share
+    /// to send to the coordinator
+    CommunicationKey(CommunicationKey),
+    /// gather the communication keys of all the committee members in the
+    /// ceremony file
+    Collect(Collect),
+    /// generate the member key of a committee member and the share to send
+    /// to the coordinator
+    MemberKey(MemberKey),
+    /// gather the member public keys of all the committee members and build
+    /// the encrypting vote key
+    Finalize(Finalize),
+}
+
+#[derive(StructOpt)]
+pub struct Init {
+    /// threshold number of the committee members sufficient for
+    /// decrypting the tally
+    #[structopt(long, short, name = "THRESHOLD")]
+    threshold: usize,
+
+    /// number of the committee members
+    #[structopt(long, short, name = "MEMBERS")]
+    members: usize,
+
+    /// optional seed to generate the common reference string, for the same
+    /// entropy the same string will be generated (32 bytes in hexadecimal).
+    /// Do not use if you are not sure.
+    #[structopt(long = "seed", short = "s", name = "SEED", parse(try_from_str))]
+    seed: Option<Seed>,
+
+    #[structopt(flatten)]
+    output_file: OutputFile,
+}
+
+#[derive(StructOpt)]
+pub struct CommunicationKey {
+    /// the ceremony file created by the coordinator
+    #[structopt(long)]
+    ceremony: PathBuf,
+
+    /// index of the committee member the key is generated for
+    #[structopt(long, short, name = "INDEX")]
+    index: usize,
+
+    /// file to write the communication private key into, it must not
+    /// exist yet
+    #[structopt(long)]
+    secret_key: PathBuf,
+
+    /// optional seed to generate the key, for the same entropy the same key
+    /// will be generated (32 bytes in hexadecimal). Do not use if you are
+    /// not sure.
+    #[structopt(long = "seed", short = "s", name = "SEED", parse(try_from_str))]
+    seed: Option<Seed>,
+
+    #[structopt(flatten)]
+    output_file: OutputFile,
+}
+
+#[derive(StructOpt)]
+pub struct Collect {
+    /// the ceremony file created with `init`
+    #[structopt(long)]
+    ceremony: PathBuf,
+
+    /// the communication key shares of the committee members
+    #[structopt(long = "share", name = "SHARE", required = true)]
+    shares: Vec<PathBuf>,
+
+    #[structopt(flatten)]
+    output_file: OutputFile,
+}
+
+#[derive(StructOpt)]
+pub struct MemberKey {
+    /// the ceremony file with the communication keys, given by `collect`
+    #[structopt(long)]
+    ceremony: PathBuf,
+
+    /// index of the committee member the key is generated for
+    #[structopt(long, short, name = "INDEX")]
+    index: usize,
+
+    /// the communication private key of the committee member
+    #[structopt(long)]
+    communication_key: PathBuf,
+
+    /// file to write the member private key into, it must not exist yet
+    #[structopt(long)]
+    secret_key: PathBuf,
+
+    /// optional seed to generate the key, for the same entropy the same key
+    /// will be generated (32 bytes in hexadecimal). Do not use if you are
+    /// not sure.
+    #[structopt(long = "seed", short = "s", name = "SEED", parse(try_from_str))]
+    seed: Option<Seed>,
+
+    #[structopt(flatten)]
+    output_file: OutputFile,
+}
+
+#[derive(StructOpt)]
+pub struct Finalize {
+    /// the ceremony file with the communication keys, given by `collect`
+    #[structopt(long)]
+    ceremony: PathBuf,
+
+    /// the member key shares of the committee members
+    #[structopt(long = "share", name = "SHARE", required = true)]
+    shares: Vec<PathBuf>,
+
+    #[structopt(flatten)]
+    output_file: OutputFile,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CeremonyFile {
+    threshold: usize,
+    members: usize,
+    crs: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    communication_keys: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CommunicationKeyShare {
+    index: usize,
+    communication_key: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MemberKeyShare {
+    index: usize,
+    member_public_key: String,
+}
+
+#[derive(Serialize)]
+struct Outcome {
+    member_public_keys: Vec<String>,
+    encrypting_vote_key: String,
+}
+
+impl Ceremony {
+    pub fn exec(self) -> Result<(), Error> {
+        match self {
+            Ceremony::Init(args) => args.exec(),
+            Ceremony::CommunicationKey(args) => args.exec(),
+            Ceremony::Collect(args) => args.exec(),
+            Ceremony::MemberKey(args) => args.exec(),
+            Ceremony::Finalize(args) => args.exec(),
+        }
+    }
+}
+
+impl Init {
+    fn exec(self) -> Result<(), Error> {
+        check_threshold(self.threshold, self.members)?;
+
+        let crs = chain_vote::CRS::random(&mut rng(self.seed)?);
+        let ceremony = CeremonyFile {
+            threshold: self.threshold,
+            members: self.members,
+            crs: hex::encode(crs.to_bytes().as_ref()),
+            communication_keys: Vec::new(),
+        };
+        write_json(&self.output_file, &ceremony)
+    }
+}
+
+impl CommunicationKey {
+    fn exec(self) -> Result<(), Error> {
+        let ceremony = read_ceremony(&self.ceremony)?;
+        check_index(self.index, ceremony.members)?;
+
+        let key = MemberCommunicationKey::new(&mut rng(self.seed)?);
+        let secret_key = bech32::encode(
+            bech32_constants::COMMUNICATION_SK_HRP,
+            key.to_bytes().to_base32(),
+        )?;
+        let communication_key = to_public(
+            &secret_key,
+            bech32_constants::COMMUNICATION_SK_HRP,
+            bech32_constants::COMMUNICATION_PK_HRP,
+        )?;
+
+        write_secret_key(&self.secret_key, &secret_key)?;
+        let share = CommunicationKeyShare {
+            index: self.index,
+            communication_key,
+        };
+        write_json(&self.output_file, &share)
+    }
+}
+
+impl Collect {
+    fn exec(self) -> Result<(), Error> {
+        let mut ceremony = read_ceremony(&self.ceremony)?;
+
+        let mut shares = Vec::with_capacity(self.shares.len());
+        for path in &self.shares {
+            let share: CommunicationKeyShare = read_json(path)?;
+            parse_member_communication_key(&share.communication_key)?;
+            shares.push((share.index, share.communication_key));
+        }
+        ceremony.communication_keys = gather(ceremony.members, shares)?;
+
+        write_json(&self.output_file, &ceremony)
+    }
+}
+
+impl MemberKey {
+    fn exec(self) -> Result<(), Error> {
+        let ceremony = read_ceremony(&self.ceremony)?;
+        check_collected(&ceremony)?;
+        check_index(self.index, ceremony.members)?;
+
+        // catch a mix-up of the indices or of the key files before the
+        // member key is bound to the wrong position in the committee
+        let communication_key = to_public(
+            &io::read_line(&Some(&self.communication_key))?,
+            bech32_constants::COMMUNICATION_SK_HRP,
+            bech32_constants::COMMUNICATION_PK_HRP,
+        )?;
+        if communication_key != ceremony.communication_keys[self.index] {
+            return Err(Error::CeremonyKeyMismatch(self.index));
+        }
+
+        let crs = parse_crs(&ceremony.crs)?;
+        let communication_keys = ceremony
+            .communication_keys
+            .iter()
+            .map(|key| parse_member_communication_key(key))
+            .collect::<Result<Vec<_>, _>>()?;
+        let state = MemberState::new(
+            &mut rng(self.seed)?,
+            ceremony.threshold,
+            &crs,
+            &communication_keys,
+            self.index,
+        );
+
+        let secret_key = bech32::encode(
+            bech32_constants::MEMBER_SK_HRP,
+            state.secret_key().to_bytes().to_base32(),
+        )?;
+        let member_public_key = to_public(
+            &secret_key,
+            bech32_constants::MEMBER_SK_HRP,
+            bech32_constants::MEMBER_PK_HRP,
+        )?;
+
+        write_secret_key(&self.secret_key, &secret_key)?;
+        let share = MemberKeyShare {
+            index: self.index,
+            member_public_key,
+        };
+        write_json(&self.output_file, &share)
+    }
+}
+
+impl Finalize {
+    fn exec(self) -> Result<(), Error> {
+        let ceremony = read_ceremony(&self.ceremony)?;
+        check_collected(&ceremony)?;
+
+        let mut shares = Vec::with_capacity(self.shares.len());
+        for path in &self.shares {
+            let share: MemberKeyShare = read_json(path)?;
+            shares.push((share.index, share.member_public_key));
+        }
+        let member_public_keys = gather(ceremony.members, shares)?;
+
+        let keys = member_public_keys
+            .iter()
+            .map(|key| parse_member_key(key))
+            .collect::<Result<Vec<_>, _>>()?;
+        let encrypting_vote_key = bech32::encode(
+            bech32_constants::ENCRYPTING_VOTE_PK_HRP,
+            chain_vote::EncryptingVoteKey::from_participants(&keys)
+                .to_bytes()
+                .to_base32(),
+        )?;
+
+        let outcome = Outcome {
+            member_public_keys,
+            encrypting_vote_key,
+        };
+        write_json(&self.output_file, &outcome)
+    }
+}
+
+fn rng(seed: Option<Seed>) -> Result<ChaCha20Rng, Error> {
+    match seed {
+        Some(seed) => Ok(ChaCha20Rng::from_seed(seed.0)),
+        None => ChaCha20Rng::from_rng(OsRng).map_err(Error::from),
+    }
+}
+
+fn check_threshold(threshold: usize, members: usize) -> Result<(), Error> {
+    if threshold == 0 || threshold > members {
+        return Err(Error::InvalidThreshold {
+            threshold,
+            committee_members: members,
+        });
+    }
+    Ok(())
+}
+
+fn check_index(index: usize, members: usize) -> Result<(), Error> {
+    if index >= members {
+        return Err(Error::CeremonyIndexOutOfRange { index, members });
+    }
+    Ok(())
+}
+
+fn check_collected(ceremony: &CeremonyFile) -> Result<(), Error> {
+    if ceremony.communication_keys.len() != ceremony.members {
+        return Err(Error::CeremonyNotCollected);
+    }
+    Ok(())
+}
+
+/// the keys of the shares ordered by the index of the committee members,
+/// exactly one share being expected for each member
+fn gather(members: usize, shares: Vec<(usize, String)>) -> Result<Vec<String>, Error> {
+    let mut keys = vec![None; members];
+    for (index, key) in shares {
+        let slot = keys
+            .get_mut(index)
+            .ok_or(Error::CeremonyIndexOutOfRange { index, members })?;
+        if slot.replace(key).is_some() {
+            return Err(Error::CeremonyDuplicateShare(index));
+        }
+    }
+    keys.into_iter()
+        .enumerate()
+        .map(|(index, key)| key.ok_or(Error::CeremonyMissingShare(index)))
+        .collect()
+}
+
+/// the bech32 public key of the given bech32 secret key
+fn to_public(secret_key: &str, sk_hrp: &str, pk_hrp: &str) -> Result<String, Error> {
+    let (hrp, data) = bech32::decode(secret_key)?;
+    if hrp != sk_hrp {
+        return Err(Error::InvalidSecretKey);
+    }
+    let key = chain_vote::gargamel::SecretKey::from_bytes(
+        &Vec::<u8>::from_base32(&data).map_err(|_| Error::InvalidSecretKey)?,
+    )
+    .ok_or(Error::InvalidSecretKey)?;
+    let public_key = chain_vote::gargamel::Keypair::from_secretkey(key).public_key;
+    bech32::encode(pk_hrp, public_key.to_bytes().to_base32()).map_err(Error::from)
+}
+
+fn read_ceremony(path: &Path) -> Result<CeremonyFile, Error> {
+    let ceremony: CeremonyFile = read_json(path)?;
+    check_threshold(ceremony.threshold, ceremony.members)?;
+    parse_crs(&ceremony.crs)?;
+    if !ceremony.communication_keys.is_empty()
+        && ceremony.communication_keys.len() != ceremony.members
+    {
+        return Err(Error::CeremonyNotCollected);
+    }
+    Ok(ceremony)
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, Error> {
+    serde_json::from_reader(io::open_file_read(&Some(path))?).map_err(Error::from)
+}
+
+fn write_json<T: Serialize>(output_file: &OutputFile, value: &T) -> Result<(), Error> {
+    let mut output = output_file.open()?;
+    serde_json::to_writer_pretty(&mut output, value)?;
+    writeln!(output)?;
+    Ok(())
+}
+
+/// write the secret key in a new file, only readable by its owner
+fn write_secret_key(path: &Path, secret_key: &str) -> Result<(), Error> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::AlreadyExists {
+            Error::SecretKeyFileExists(path.to_path_buf())
+        } else {
+            Error::Io(e)
+        }
+    })?;
+    writeln!(file, "{}", secret_key)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::TempDir;
+
+    fn run(dir: &TempDir, args: &[&str]) -> Result<(), Error> {
+        let args = std::iter::once("ceremony".to_string()).chain(args.iter().map(|arg| {
+            if arg.ends_with(".json") || arg.ends_with(".sk") {
+                dir.path().join(arg).to_string_lossy().into_owned()
+            } else {
+                arg.to_string()
+            }
+        }));
+        Ceremony::from_iter(args).exec()
+    }
+
+    /// run the ceremony up to the collection of the communication keys
+    fn collected(dir: &TempDir, members: usize) {
+        let members_arg = members.to_string();
+        run(
+            dir,
+            &["init", "-t", "2", "-m", &members_arg, "ceremony.json"],
+        )
+        .unwrap();
+        for index in 0..members {
+            let index_arg = index.to_string();
+            let secret_key = format!("communication-{}.sk", index);
+            let share = format!("communication-share-{}.json", index);
+            run(
+                dir,
+                &[
+                    "communication-key",
+                    "--ceremony",
+                    "ceremony.json",
+                    "--index",
+                    &index_arg,
+                    "--secret-key",
+                    &secret_key,
+                    &share,
+                ],
+            )
+            .unwrap();
+        }
+        let mut args = vec!["collect", "--ceremony", "ceremony.json"];
+        let shares: Vec<_> = (0..members)
+            .map(|index| format!("communication-share-{}.json", index))
+            .collect();
+        for share in &shares {
+            args.push("--share");
+            args.push(share);
+        }
+        args.push("ceremony-collected.json");
+        run(dir, &args).unwrap();
+    }
+
+    fn member_key(dir: &TempDir, index: usize, communication_key: usize) -> Result<(), Error> {
+        let index_arg = index.to_string();
+        let communication_key = format!("communication-{}.sk", communication_key);
+        let secret_key = format!("member-{}.sk", index);
+        let share = format!("member-share-{}.json", index);
+        run(
+            dir,
+            &[
+                "member-key",
+                "--ceremony",
+                "ceremony-collected.json",
+                "--index",
+                &index_arg,
+                "--communication-key",
+                &communication_key,
+                "--secret-key",
+                &secret_key,
+                &share,
+            ],
+        )
+    }
+
+    #[test]
+    fn ceremony_gives_the_keys_of_the_committee() {
+        let dir = TempDir::new().unwrap();
+        collected(&dir, 3);
+        for index in 0..3 {
+            member_key(&dir, index, index).unwrap();
+        }
+        run(
+            &dir,
+            &[
+                "finalize",
+                "--ceremony",
+                "ceremony-collected.json",
+                "--share",
+                "member-share-2.json",
+                "--share",
+                "member-share-0.json",
+                "--share",
+                "member-share-1.json",
+                "committee.json",
+            ],
+        )
+        .unwrap();
+
+        let outcome: serde_json::Value = read_json(&dir.path().join("committee.json")).unwrap();
+        let member_public_keys = outcome["member_public_keys"].as_array().unwrap();
+        assert_eq!(member_public_keys.len(), 3);
+        for (index, key) in member_public_keys.iter().enumerate() {
+            let share: MemberKeyShare =
+                read_json(&dir.path().join(format!("member-share-{}.json", index))).unwrap();
+            assert_eq!(key.as_str().unwrap(), share.member_public_key);
+            parse_member_key(&share.member_public_key).unwrap();
+        }
+        let (hrp, _) = bech32::decode(outcome["encrypting_vote_key"].as_str().unwrap()).unwrap();
+        assert_eq!(hrp, bech32_constants::ENCRYPTING_VOTE_PK_HRP);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.path().join("member-0.sk"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn member_key_checks_the_communication_key_and_the_secret_key_file() {
+        let dir = TempDir::new().unwrap();
+        collected(&dir, 2);
+
+        assert!(matches!(
+            member_key(&dir, 0, 1),
+            Err(Error::CeremonyKeyMismatch(0))
+        ));
+        assert!(matches!(
+            member_key(&dir, 2, 0),
+            Err(Error::CeremonyIndexOutOfRange {
+                index: 2,
+                members: 2
+            })
+        ));
+
+        member_key(&dir, 0, 0).unwrap();
+        let secret_key = std::fs::read_to_string(dir.path().join("member-0.sk")).unwrap();
+        assert!(matches!(
+            member_key(&dir, 0, 0),
+            Err(Error::SecretKeyFileExists(_))
+        ));
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("member-0.sk")).unwrap(),
+            secret_key
+        );
+    }
+
+    #[test]
+    fn gather_expects_one_share_per_member() {
+        let share = |index: usize| (index, format!("key{}", index));
+
+        assert_eq!(
+            gather(2, vec![share(1), share(0)]).unwrap(),
+            vec!["key0".to_string(), "key1".to_string()]
+        );
+        assert!(matches!(
+            gather(2, vec![share(0), share(0)]),
+            Err(Error::CeremonyDuplicateShare(0))
+        ));
+        assert!(matches!(
+            gather(2, vec![share(0)]),
+            Err(Error::CeremonyMissingShare(1))
+        ));
+        assert!(matches!(
+            gather(2, vec![share(0), share(2)]),
+            Err(Error::CeremonyIndexOutOfRange {
+                index: 2,
+                members: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn init_checks_the_threshold() {
+        let dir = TempDir::new().unwrap();
+        assert!(matches!(
+            run(&dir, &["init", "-t", "3", "-m", "2", "ceremony.json"]),
+            Err(Error::InvalidThreshold {
+                threshold: 3,
+                committee_members: 2
+            })
+        ));
+    }
+}
@@ -143,7 +143,9 @@ impl MemberKey {
     }
 }
 
-fn parse_member_communication_key(key: &str) -> Result<MemberCommunicationPublicKey, Error> {
+pub(super) fn parse_member_communication_key(
+    key: &str,
+) -> Result<MemberCommunicationPublicKey, Error> {
     let (hrp, raw_key) = bech32::decode(key).map_err(Error::Bech32)?;
 
     if hrp != crate::jcli_app::vote::bech32_constants::COMMUNICATION_PK_HRP {
@@ -155,7 +157,7 @@ fn parse_member_communication_key(key: &str) -> Result<MemberCommunicationPublic
     Ok(MemberCommunicationPublicKey::from_public_key(pk))
 }
 
-fn parse_crs(crs: &str) -> Result<chain_vote::CRS, Error> {
+pub(super) fn parse_crs(crs: &str) -> Result<chain_vote::CRS, Error> {
     let bytes = hex::decode(crs)?;
 
     chain_vote::CRS::from_bytes(&bytes).ok_or(Error::InvalidCrs)
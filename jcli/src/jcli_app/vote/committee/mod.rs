@@ -1,3 +1,4 @@
+mod ceremony;
 mod communication_key;
 mod member_key;
 
@@ -11,6 +12,9 @@ pub enum Committee {
     CommunicationKey(communication_key::CommunicationKey),
     /// commands for managing committee member stake keys
     MemberKey(member_key::MemberKey),
+    /// commands for generating the keys of all the committee members, with
+    /// files exchanged between a coordinator and the members
+    Ceremony(ceremony::Ceremony),
 }
 
 impl Committee {
@@ -18,6 +22,7 @@ impl Committee {
         match self {
             Committee::CommunicationKey(args) => args.exec(),
             Committee::MemberKey(args) => args.exec(),
+            Committee::Ceremony(args) => args.exec(),
         }
     }
 }
@@ -38,7 +38,7 @@ impl EncryptingVoteKey {
     }
 }
 
-fn parse_member_key(key: &str) -> Result<chain_vote::committee::MemberPublicKey, Error> {
+pub(super) fn parse_member_key(key: &str) -> Result<chain_vote::committee::MemberPublicKey, Error> {
     bech32::decode(key)
         .map_err(Error::from)
         .and_then(|(hrp, raw_key)| {
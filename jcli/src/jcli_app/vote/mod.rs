@@ -38,12 +38,41 @@ pub enum Error {
     },
     #[error("invalid committee member index")]
     InvalidCommitteMemberIndex,
+    #[error("committee member index {index} is out of range, the committee has {members} members")]
+    CeremonyIndexOutOfRange { index: usize, members: usize },
+    #[error("several shares are given for the committee member {0}")]
+    CeremonyDuplicateShare(usize),
+    #[error("the share of the committee member {0} is missing")]
+    CeremonyMissingShare(usize),
+    #[error(
+        "the communication keys of the committee members are not collected in the ceremony file"
+    )]
+    CeremonyNotCollected,
+    #[error("the communication key is not the one collected for the committee member {0}")]
+    CeremonyKeyMismatch(usize),
+    #[error("the secret key file '{}' already exists", .0.display())]
+    SecretKeyFileExists(std::path::PathBuf),
     #[error("failed to read encrypted tally bytes")]
     EncryptedTallyRead,
     #[error("failed to read decryption key bytes")]
     DecryptionKeyRead,
     #[error("failed to read share bytes")]
     DecryptionShareRead,
+    #[error("no decryption share is given")]
+    NoDecryptionShares,
+    #[error("the votes of the option {0} could not be decrypted")]
+    TallyResultUndecrypted(usize),
+    #[error("{keys} member keys are given for {shares} decryption shares")]
+    TallyShareKeysMismatch { keys: usize, shares: usize },
+    #[error("the member key {0} is not a key of the committee of the vote plan")]
+    TallyUnknownMember(usize),
+    #[error("the member key {0} is given more than once")]
+    TallyDuplicateMember(usize),
+    #[error("the decrypted tally {decrypted:?} does not match the claimed result {claimed:?}")]
+    TallyResultMismatch {
+        claimed: Vec<u64>,
+        decrypted: Vec<u64>,
+    },
     #[error(transparent)]
     FormatError(#[from] crate::jcli_app::utils::output_format::Error),
     #[error(transparent)]
@@ -1,5 +1,6 @@
 mod decrypt_shares;
 mod decryption_tally;
+mod verify;
 
 use super::Error;
 use structopt::StructOpt;
@@ -17,6 +18,11 @@ pub enum Tally {
     /// The decrypted tally data will be printed in hexadecimal encoding
     /// on standard output.
     Decrypt(decrypt_shares::TallyDecryptWithAllShares),
+    /// Verify a private tally result by decrypting the tally again.
+    ///
+    /// Fails if the result does not match the one decrypted with the
+    /// given shares.
+    Verify(verify::TallyVerify),
 }
 
 impl Tally {
@@ -24,6 +30,7 @@ impl Tally {
         match self {
             Tally::DecryptionShare(cmd) => cmd.exec(),
             Tally::Decrypt(cmd) => cmd.exec(),
+            Tally::Verify(cmd) => cmd.exec(),
         }
     }
 }
@@ -0,0 +1,224 @@
+use super::Error;
+use crate::jcli_app::utils::{io, OutputFormat};
+use crate::jcli_app::vote::encrypting_vote_key::parse_member_key;
+use chain_vote::{EncryptedTally, MemberPublicKey};
+use jormungandr_lib::interfaces::VotePlanStatus;
+use serde::{Deserialize, Serialize};
+use std::io::BufRead;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Verify a private tally result against the published encrypted tally.
+///
+/// The tally is decrypted again with the decryption shares of the committee
+/// members and compared with the claimed result, so anyone holding the
+/// shares can audit the result independently of the committee.
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct TallyVerify {
+    /// The path to base64-encoded encrypted tally state, as published in the
+    /// status of the vote plan. If this parameter is not specified, the
+    /// encrypted tally state will be read from the standard input.
+    #[structopt(long = "tally")]
+    encrypted_tally: Option<PathBuf>,
+    /// Maximum supported number of votes
+    #[structopt(long = "maxvotes")]
+    max_votes: u64,
+    /// Computing table cache size, usually total_votes/number_of_options
+    #[structopt(long = "table_size")]
+    table_size: usize,
+    /// The path to the decryption shares of the committee members, one
+    /// base64-encoded share per line.
+    #[structopt(long = "shares")]
+    shares: PathBuf,
+    /// The member public keys of the committee members who gave the
+    /// decryption shares, in the order of the shares.
+    #[structopt(
+        long = "keys",
+        parse(try_from_str = parse_member_key),
+        required = true
+    )]
+    member_keys: Vec<MemberPublicKey>,
+    /// The path to the status of the vote plan in JSON, as given by
+    /// `rest v0 vote active plans get`, the member keys are checked
+    /// against its committee.
+    #[structopt(long = "vote-plan")]
+    vote_plan: PathBuf,
+    /// The path to the claimed result, either the output of `tally decrypt`
+    /// or the decrypted result published in the status of the vote plan,
+    /// in JSON.
+    #[structopt(long = "result")]
+    result: PathBuf,
+    #[structopt(flatten)]
+    output_format: OutputFormat,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ClaimedResult {
+    Decrypted { result: Vec<Option<u64>> },
+    Published { results: Vec<u64> },
+}
+
+#[derive(Serialize)]
+struct Output {
+    verified: bool,
+    shares: usize,
+    result: Vec<u64>,
+}
+
+impl ClaimedResult {
+    fn into_votes(self) -> Result<Vec<u64>, Error> {
+        match self {
+            ClaimedResult::Decrypted { result } => votes(result),
+            ClaimedResult::Published { results } => Ok(results),
+        }
+    }
+}
+
+/// the votes of each option, a result with an option that could not be
+/// decrypted cannot be verified
+fn votes(result: Vec<Option<u64>>) -> Result<Vec<u64>, Error> {
+    result
+        .into_iter()
+        .enumerate()
+        .map(|(option, votes)| votes.ok_or(Error::TallyResultUndecrypted(option)))
+        .collect()
+}
+
+/// check that there is a distinct key of the committee for each share
+fn check_member_keys(
+    committee: &[MemberPublicKey],
+    keys: &[MemberPublicKey],
+    shares: usize,
+) -> Result<(), Error> {
+    if keys.len() != shares {
+        return Err(Error::TallyShareKeysMismatch {
+            keys: keys.len(),
+            shares,
+        });
+    }
+    let committee: Vec<_> = committee.iter().map(MemberPublicKey::to_bytes).collect();
+    let mut seen = Vec::with_capacity(keys.len());
+    for (index, key) in keys.iter().enumerate() {
+        let key = key.to_bytes();
+        if !committee.contains(&key) {
+            return Err(Error::TallyUnknownMember(index));
+        }
+        if seen.contains(&key) {
+            return Err(Error::TallyDuplicateMember(index));
+        }
+        seen.push(key);
+    }
+    Ok(())
+}
+
+impl TallyVerify {
+    pub fn exec(&self) -> Result<(), Error> {
+        let encrypted_tally_base64 = io::read_line(&self.encrypted_tally)?;
+        let encrypted_tally_bytes = base64::decode(encrypted_tally_base64)?;
+        let encrypted_tally =
+            EncryptedTally::from_bytes(&encrypted_tally_bytes).ok_or(Error::EncryptedTallyRead)?;
+
+        let mut shares = Vec::new();
+        for line in io::open_file_read(&Some(&self.shares))?.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            shares.push(
+                chain_vote::TallyDecryptShare::from_bytes(&base64::decode(line.trim())?)
+                    .ok_or(Error::DecryptionShareRead)?,
+            );
+        }
+        if shares.is_empty() {
+            return Err(Error::NoDecryptionShares);
+        }
+
+        let vote_plan: VotePlanStatus =
+            serde_json::from_reader(io::open_file_read(&Some(&self.vote_plan))?)?;
+        check_member_keys(
+            &vote_plan.committee_member_keys,
+            &self.member_keys,
+            shares.len(),
+        )?;
+
+        let claimed: ClaimedResult =
+            serde_json::from_reader(io::open_file_read(&Some(&self.result))?)?;
+        let claimed = claimed.into_votes()?;
+
+        let state = encrypted_tally.state();
+        let result =
+            votes(chain_vote::result(self.max_votes, self.table_size, &state, &shares).votes)?;
+        if result != claimed {
+            return Err(Error::TallyResultMismatch {
+                claimed,
+                decrypted: result,
+            });
+        }
+
+        let output = self
+            .output_format
+            .format_json(serde_json::to_value(Output {
+                verified: true,
+                shares: shares.len(),
+                result,
+            })?)?;
+        println!("{}", output);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_vote::gargamel::Keypair;
+    use rand_chacha::rand_core::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    fn member_keys(n: usize) -> Vec<MemberPublicKey> {
+        let mut rng = ChaCha20Rng::from_seed([0; 32]);
+        (0..n)
+            .map(|_| MemberPublicKey::from(Keypair::generate(&mut rng).public_key))
+            .collect()
+    }
+
+    #[test]
+    fn undecrypted_options_are_refused() {
+        assert_eq!(votes(vec![Some(3), Some(0)]).unwrap(), vec![3, 0]);
+        assert!(matches!(
+            votes(vec![Some(3), None]),
+            Err(Error::TallyResultUndecrypted(1))
+        ));
+
+        let claimed: ClaimedResult = serde_json::from_str(r#"{"result":[null,2]}"#).unwrap();
+        assert!(matches!(
+            claimed.into_votes(),
+            Err(Error::TallyResultUndecrypted(0))
+        ));
+        let published: ClaimedResult =
+            serde_json::from_str(r#"{"results":[1,2],"options":{"start":0,"end":2}}"#).unwrap();
+        assert_eq!(published.into_votes().unwrap(), vec![1, 2]);
+    }
+
+    #[test]
+    fn member_keys_are_checked_against_the_committee() {
+        let keys = member_keys(4);
+        let committee = &keys[..3];
+
+        check_member_keys(committee, &[keys[2].clone(), keys[0].clone()], 2).unwrap();
+        assert!(matches!(
+            check_member_keys(committee, &[keys[0].clone()], 2),
+            Err(Error::TallyShareKeysMismatch { keys: 1, shares: 2 })
+        ));
+        assert!(matches!(
+            check_member_keys(committee, &[keys[0].clone(), keys[3].clone()], 2),
+            Err(Error::TallyUnknownMember(1))
+        ));
+        assert!(matches!(
+            check_member_keys(committee, &[keys[1].clone(), keys[1].clone()], 2),
+            Err(Error::TallyDuplicateMember(1))
+        ));
+    }
+}
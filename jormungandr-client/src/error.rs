@@ -0,0 +1,178 @@
+use crate::retry::Retryable;
+use reqwest::StatusCode;
+use std::fmt;
+use thiserror::Error;
+
+const INTERNAL_SERVER_ERROR_PREFIX: &str = "Internal server error: ";
+const CAUSE_PREFIX: &str = "-> ";
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("could not send request")]
+    Request(#[from] reqwest::Error),
+    #[error(transparent)]
+    Node(#[from] NodeError),
+    #[error("could not deserialize response")]
+    CannotDeserialize(#[from] serde_json::Error),
+    #[error("hash parse error")]
+    HashParse(#[from] chain_crypto::hash::Error),
+    #[error("cannot serialize fragment")]
+    FragmentSerialization(#[source] std::io::Error),
+    #[error("request still failing after {attempts} attempts")]
+    RetriesExhausted {
+        attempts: u32,
+        #[source]
+        last: Box<ClientError>,
+    },
+}
+
+impl ClientError {
+    /// whether sending the same request again could succeed: the node is
+    /// unreachable or failing for now
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ClientError::Request(error) => error.is_connect() || error.is_timeout(),
+            ClientError::Node(error) => error.is_transient(),
+            _ => false,
+        }
+    }
+}
+
+impl Retryable for ClientError {
+    fn is_transient(&self) -> bool {
+        ClientError::is_transient(self)
+    }
+
+    fn retries_exhausted(attempts: u32, last: Self) -> Self {
+        ClientError::RetriesExhausted {
+            attempts,
+            last: Box::new(last),
+        }
+    }
+}
+
+/// What went wrong on the node side, from the status code of its reply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeErrorKind {
+    /// the request is invalid, e.g. a malformed hash or public key
+    BadRequest,
+    Unauthorized,
+    /// the node is read-only
+    Forbidden,
+    /// the resource does not exist or its service is disabled
+    NotFound,
+    TooManyRequests,
+    /// the node failed to process a valid request
+    InternalServerError,
+    /// the node, or a proxy in front of it, is not available for now
+    Unavailable,
+    Other,
+}
+
+impl From<StatusCode> for NodeErrorKind {
+    fn from(status: StatusCode) -> Self {
+        match status {
+            StatusCode::BAD_REQUEST => NodeErrorKind::BadRequest,
+            StatusCode::UNAUTHORIZED => NodeErrorKind::Unauthorized,
+            StatusCode::FORBIDDEN => NodeErrorKind::Forbidden,
+            StatusCode::NOT_FOUND => NodeErrorKind::NotFound,
+            StatusCode::TOO_MANY_REQUESTS => NodeErrorKind::TooManyRequests,
+            StatusCode::INTERNAL_SERVER_ERROR => NodeErrorKind::InternalServerError,
+            StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT => NodeErrorKind::Unavailable,
+            _ => NodeErrorKind::Other,
+        }
+    }
+}
+
+/// An error reply of the node. The rest api answers with the message of
+/// the error as a plain text body, followed for internal server errors by
+/// one line per source of the error.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub struct NodeError {
+    pub status: u16,
+    pub kind: NodeErrorKind,
+    pub message: String,
+    /// the sources of the error, outermost first
+    pub causes: Vec<String>,
+}
+
+impl NodeError {
+    pub fn parse(status: StatusCode, body: &str) -> Self {
+        let mut lines = body.lines();
+        let first = lines.next().unwrap_or_default();
+        let message = first
+            .strip_prefix(INTERNAL_SERVER_ERROR_PREFIX)
+            .unwrap_or(first)
+            .to_string();
+        let causes = lines
+            .filter_map(|line| line.strip_prefix(CAUSE_PREFIX))
+            .map(str::to_string)
+            .collect();
+        Self {
+            status: status.as_u16(),
+            kind: status.into(),
+            message,
+            causes,
+        }
+    }
+
+    /// whether the same request may succeed later
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self.kind,
+            NodeErrorKind::TooManyRequests
+                | NodeErrorKind::InternalServerError
+                | NodeErrorKind::Unavailable
+        )
+    }
+}
+
+impl fmt::Display for NodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "node replied with {}: {}", self.status, self.message)?;
+        for cause in &self.causes {
+            write!(f, " -> {}", cause)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn internal_server_errors_are_split_into_message_and_causes() {
+        let error = NodeError::parse(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error: storage error\n-> block not found\n-> io error\n",
+        );
+        assert_eq!(error.kind, NodeErrorKind::InternalServerError);
+        assert_eq!(error.message, "storage error");
+        assert_eq!(error.causes, vec!["block not found", "io error"]);
+        assert!(error.is_transient());
+    }
+
+    #[test]
+    fn client_errors_keep_the_whole_body() {
+        let error = NodeError::parse(
+            StatusCode::FORBIDDEN,
+            "the node is read-only and does not accept fragments",
+        );
+        assert_eq!(error.kind, NodeErrorKind::Forbidden);
+        assert_eq!(
+            error.message,
+            "the node is read-only and does not accept fragments"
+        );
+        assert!(error.causes.is_empty());
+        assert!(!error.is_transient());
+    }
+
+    #[test]
+    fn throttled_requests_are_transient() {
+        let error: ClientError = NodeError::parse(StatusCode::TOO_MANY_REQUESTS, "").into();
+        assert!(error.is_transient());
+    }
+}
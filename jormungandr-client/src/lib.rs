@@ -0,0 +1,23 @@
+//! Client library for the REST API of the jormungandr node.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), jormungandr_client::ClientError> {
+//! use jormungandr_client::{RestClient, RetryPolicy};
+//! use std::time::Duration;
+//!
+//! let client = RestClient::new("http://127.0.0.1:8443/api").with_retry_policy(
+//!     RetryPolicy::exponential(5, Duration::from_millis(100), Duration::from_secs(10)),
+//! );
+//! let tip = client.tip().await?;
+//! println!("node tip: {}", tip);
+//! # Ok(())
+//! # }
+//! ```
+
+mod error;
+mod rest;
+mod retry;
+
+pub use error::{ClientError, NodeError, NodeErrorKind};
+pub use rest::RestClient;
+pub use retry::{RetryPolicy, Retryable};
@@ -0,0 +1,366 @@
+use crate::{ClientError, NodeError, NodeErrorKind, RetryPolicy};
+use chain_core::property::{Fragment as _, Serialize as _};
+use chain_impl_mockchain::{
+    account,
+    fragment::{Fragment, FragmentId},
+};
+use jormungandr_lib::{
+    crypto::hash::Hash,
+    interfaces::{
+        AccountState, EnclaveLeaderId, EpochRewardsInfo, FragmentDetails, FragmentLog,
+        FragmentStatus, LeadershipLog, NodeStatsDto, PeerRecord, PeerStats, SettingsDto,
+        StakeDistributionDto, StakeSnapshot, VotePlanStatus,
+    },
+};
+use reqwest::{Certificate, Client, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+
+/// Asynchronous client of the REST API of a node. The requests failing with
+/// a transient error are sent again according to the retry policy.
+#[derive(Debug, Clone)]
+pub struct RestClient {
+    uri: String,
+    client: Client,
+    retry_policy: RetryPolicy,
+}
+
+impl RestClient {
+    /// client of the node API at the given address, e.g.
+    /// `http://127.0.0.1:8443/api`
+    pub fn new<S: Into<String>>(uri: S) -> Self {
+        Self::with_client(uri, Client::new())
+    }
+
+    /// same as `new`, sending the requests with the given http client, e.g.
+    /// to share its connection pool
+    pub fn with_client<S: Into<String>>(uri: S, client: Client) -> Self {
+        Self {
+            uri: uri.into().trim_end_matches('/').to_string(),
+            client,
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// trust the given certificate when connecting over https
+    pub fn with_certificate(mut self, certificate: Certificate) -> Result<Self, ClientError> {
+        self.client = Client::builder()
+            .use_rustls_tls()
+            .add_root_certificate(certificate)
+            .build()?;
+        Ok(self)
+    }
+
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    fn path(&self, version: &str, path: &str) -> String {
+        format!("{}/{}/{}", self.uri, version, path)
+    }
+
+    /// send the request, again while it fails with a transient error, and
+    /// return the first successful response
+    async fn send<F>(&self, request: F) -> Result<Response, ClientError>
+    where
+        F: Fn(&Client) -> RequestBuilder,
+    {
+        let request = &request;
+        let client = &self.client;
+        self.retry_policy
+            .run_async(move || send_once(request(client)))
+            .await
+    }
+
+    async fn get_text(&self, path: &str) -> Result<String, ClientError> {
+        let url = self.path("v0", path);
+        let response = self.send(|client| client.get(&url)).await?;
+        response.text().await.map_err(Into::into)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, ClientError> {
+        serde_json::from_str(&self.get_text(path).await?).map_err(Into::into)
+    }
+
+    /// same as `get`, for the lists the node answers with an empty body
+    /// when they are empty
+    async fn get_list<T: DeserializeOwned>(&self, path: &str) -> Result<Vec<T>, ClientError> {
+        let text = self.get_text(path).await?;
+        if text.is_empty() {
+            return Ok(Vec::new());
+        }
+        serde_json::from_str(&text).map_err(Into::into)
+    }
+
+    pub async fn tip(&self) -> Result<Hash, ClientError> {
+        self.get_text("tip")
+            .await?
+            .parse()
+            .map_err(ClientError::HashParse)
+    }
+
+    pub async fn settings(&self) -> Result<SettingsDto, ClientError> {
+        self.get("settings").await
+    }
+
+    pub async fn stats(&self) -> Result<NodeStatsDto, ClientError> {
+        self.get("node/stats").await
+    }
+
+    pub async fn account_state(
+        &self,
+        identifier: &account::Identifier,
+    ) -> Result<AccountState, ClientError> {
+        let key = hex::encode(identifier.as_ref().as_ref());
+        self.get(&format!("account/{}", key)).await
+    }
+
+    pub async fn stake_distribution(&self) -> Result<StakeDistributionDto, ClientError> {
+        self.get("stake").await
+    }
+
+    pub async fn stake_distribution_at(
+        &self,
+        epoch: u32,
+    ) -> Result<StakeDistributionDto, ClientError> {
+        self.get(&format!("stake/{}", epoch)).await
+    }
+
+    /// stake of every delegator used for the leader election of the epoch
+    pub async fn stake_snapshot(&self, epoch: u32) -> Result<StakeSnapshot, ClientError> {
+        self.get(&format!("stake/{}/snapshot", epoch)).await
+    }
+
+    pub async fn stake_pools(&self) -> Result<Vec<String>, ClientError> {
+        self.get("stake_pools").await
+    }
+
+    pub async fn epoch_reward_history(&self, epoch: u32) -> Result<EpochRewardsInfo, ClientError> {
+        self.get(&format!("rewards/epoch/{}", epoch)).await
+    }
+
+    pub async fn reward_history(&self, length: u32) -> Result<Vec<EpochRewardsInfo>, ClientError> {
+        self.get(&format!("rewards/history/{}", length)).await
+    }
+
+    pub async fn network_stats(&self) -> Result<Vec<PeerStats>, ClientError> {
+        self.get("network/stats").await
+    }
+
+    pub async fn p2p_quarantined(&self) -> Result<Vec<PeerRecord>, ClientError> {
+        self.get("network/p2p/quarantined").await
+    }
+
+    pub async fn p2p_non_public(&self) -> Result<Vec<PeerRecord>, ClientError> {
+        self.get("network/p2p/non_public").await
+    }
+
+    pub async fn p2p_available(&self) -> Result<Vec<PeerRecord>, ClientError> {
+        self.get("network/p2p/available").await
+    }
+
+    pub async fn p2p_view(&self) -> Result<Vec<String>, ClientError> {
+        self.get("network/p2p/view").await
+    }
+
+    pub async fn leaders(&self) -> Result<Vec<EnclaveLeaderId>, ClientError> {
+        self.get_list("leaders").await
+    }
+
+    pub async fn leaders_log(&self) -> Result<Vec<LeadershipLog>, ClientError> {
+        self.get("leaders/logs").await
+    }
+
+    pub async fn vote_plan_statuses(&self) -> Result<Vec<VotePlanStatus>, ClientError> {
+        self.get("vote/active/plans").await
+    }
+
+    pub async fn fragment_logs(&self) -> Result<Vec<FragmentLog>, ClientError> {
+        self.get_list("fragment/logs").await
+    }
+
+    /// the details of the fragment, `None` if the node does not know it
+    pub async fn fragment_details(
+        &self,
+        fragment_id: &FragmentId,
+    ) -> Result<Option<FragmentDetails>, ClientError> {
+        match self.get(&format!("fragment/{}", fragment_id)).await {
+            Ok(details) => Ok(Some(details)),
+            Err(ClientError::Node(NodeError {
+                kind: NodeErrorKind::NotFound,
+                ..
+            })) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// statuses of the fragments, keyed by the fragment id
+    pub async fn fragment_statuses(
+        &self,
+        ids: &[FragmentId],
+    ) -> Result<HashMap<String, FragmentStatus>, ClientError> {
+        let ids: Vec<String> = ids.iter().map(ToString::to_string).collect();
+        let url = self.path("v1", "fragments/statuses");
+        let query = [("fragment_ids", ids.join(","))];
+        let response = self.send(|client| client.get(&url).query(&query)).await?;
+        response.json().await.map_err(Into::into)
+    }
+
+    /// submit the fragment to the mempool of the node, returning its id
+    pub async fn send_fragment(&self, fragment: &Fragment) -> Result<FragmentId, ClientError> {
+        let body = fragment
+            .serialize_as_vec()
+            .map_err(ClientError::FragmentSerialization)?;
+        self.send_raw_fragment(body).await?;
+        Ok(fragment.id())
+    }
+
+    /// submit the serialized fragment to the mempool of the node
+    pub async fn send_raw_fragment(&self, body: Vec<u8>) -> Result<(), ClientError> {
+        let url = self.path("v0", "message");
+        self.send(|client| {
+            client
+                .post(&url)
+                .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+                .body(body.clone())
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// submit the fragments to the mempool of the node, returning their ids
+    pub async fn send_fragment_batch(
+        &self,
+        fragments: &[Fragment],
+    ) -> Result<Vec<String>, ClientError> {
+        let fragments = fragments
+            .iter()
+            .map(|fragment| fragment.serialize_as_vec().map(hex::encode))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(ClientError::FragmentSerialization)?;
+        let url = self.path("v1", "fragments");
+        let response = self
+            .send(|client| client.post(&url).json(&fragments))
+            .await?;
+        response.json().await.map_err(Into::into)
+    }
+}
+
+/// the response to the request if it is successful, or the error the node
+/// replied with
+async fn send_once(request: RequestBuilder) -> Result<Response, ClientError> {
+    let response = request.send().await?;
+    let status = response.status();
+    if status.is_success() {
+        Ok(response)
+    } else {
+        let body = response.text().await?;
+        Err(NodeError::parse(status, &body).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        net::SocketAddr,
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+    use warp::{http::StatusCode, Filter};
+
+    const TIP: &str = "0000000000000000000000000000000000000000000000000000000000000001";
+
+    /// serve the filter on a local port, returning the uri of the api
+    fn serve<F>(filter: F) -> String
+    where
+        F: Filter<Error = warp::Rejection> + Clone + Send + Sync + 'static,
+        F::Extract: warp::Reply,
+    {
+        let (addr, server) =
+            warp::serve(filter).bind_ephemeral(SocketAddr::from(([127, 0, 0, 1], 0)));
+        tokio::spawn(server);
+        format!("http://{}/api/", addr)
+    }
+
+    #[test]
+    fn trailing_slash_is_trimmed_from_the_uri() {
+        let client = RestClient::new("http://127.0.0.1:8443/api/");
+        assert_eq!(client.uri(), "http://127.0.0.1:8443/api");
+        assert_eq!(client.path("v0", "tip"), "http://127.0.0.1:8443/api/v0/tip");
+    }
+
+    #[tokio::test]
+    async fn tip_is_parsed() {
+        let uri = serve(warp::path!("api" / "v0" / "tip").map(|| TIP));
+        let tip = RestClient::new(uri).tip().await.unwrap();
+        assert_eq!(tip.to_string(), TIP);
+    }
+
+    #[tokio::test]
+    async fn error_replies_are_node_errors() {
+        let uri = serve(warp::path!("api" / "v0" / "tip").map(|| {
+            warp::reply::with_status(
+                "Internal server error: storage error\n-> io error",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+        }));
+        match RestClient::new(uri).tip().await {
+            Err(ClientError::Node(error)) => {
+                assert_eq!(error.kind, NodeErrorKind::InternalServerError);
+                assert_eq!(error.message, "storage error");
+                assert_eq!(error.causes, vec!["io error"]);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn transient_errors_are_retried() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        let uri = serve(warp::path!("api" / "v0" / "tip").map(move || {
+            if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                warp::reply::with_status("", StatusCode::SERVICE_UNAVAILABLE)
+            } else {
+                warp::reply::with_status(TIP, StatusCode::OK)
+            }
+        }));
+        let client =
+            RestClient::new(uri).with_retry_policy(RetryPolicy::fixed(2, Duration::from_millis(1)));
+        assert_eq!(client.tip().await.unwrap().to_string(), TIP);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn empty_lists_have_an_empty_body() {
+        let uri = serve(warp::path!("api" / "v0" / "fragment" / "logs").map(|| ""));
+        assert!(RestClient::new(uri)
+            .fragment_logs()
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn unknown_fragments_have_no_details() {
+        let uri = serve(
+            warp::path!("api" / "v0" / "fragment" / String)
+                .map(|_| warp::reply::with_status("", StatusCode::NOT_FOUND)),
+        );
+        let id = FragmentId::hash_bytes(b"fragment");
+        assert!(RestClient::new(uri)
+            .fragment_details(&id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,215 @@
+use std::{future::Future, time::Duration};
+
+/// The errors of the calls to the node, as seen by the retry policy.
+pub trait Retryable: Sized {
+    /// whether the same call may succeed later
+    fn is_transient(&self) -> bool;
+
+    /// the error to give up with after the given number of calls, the last
+    /// one failing with `last`
+    fn retries_exhausted(attempts: u32, last: Self) -> Self;
+}
+
+/// How often and how long to wait before calling the node again when a
+/// call fails. Only the failures which may go away are retried: the
+/// connection errors, the timeouts and the internal or unavailable replies
+/// of the node, the others are returned right away.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// calls made at most, the first one included
+    pub attempts: u32,
+    /// wait before the second call
+    pub initial_backoff: Duration,
+    /// the wait is multiplied by this factor after each failed call
+    pub multiplier: u32,
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub fn no_retry() -> Self {
+        Self::fixed(1, Duration::from_secs(0))
+    }
+
+    pub fn fixed(attempts: u32, backoff: Duration) -> Self {
+        Self {
+            attempts,
+            initial_backoff: backoff,
+            multiplier: 1,
+            max_backoff: backoff,
+        }
+    }
+
+    pub fn exponential(attempts: u32, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            attempts,
+            initial_backoff,
+            multiplier: 2,
+            max_backoff,
+        }
+    }
+
+    /// the wait before the call following the failed `attempt`, counted
+    /// from 1
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = self
+            .multiplier
+            .checked_pow(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial_backoff
+            .checked_mul(factor)
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+
+    /// call again, blocking the thread between the calls
+    pub fn run<T, E, F>(&self, mut action: F) -> Result<T, E>
+    where
+        E: Retryable,
+        F: FnMut() -> Result<T, E>,
+    {
+        let mut attempt = 1;
+        loop {
+            match action() {
+                Ok(value) => return Ok(value),
+                Err(error) => std::thread::sleep(self.on_failure(attempt, error)?),
+            }
+            attempt += 1;
+        }
+    }
+
+    /// same as `run`, for the asynchronous calls
+    pub async fn run_async<T, E, F, Fut>(&self, mut action: F) -> Result<T, E>
+    where
+        E: Retryable,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match action().await {
+                Ok(value) => return Ok(value),
+                Err(error) => tokio::time::delay_for(self.on_failure(attempt, error)?).await,
+            }
+            attempt += 1;
+        }
+    }
+
+    /// the wait before calling again after the failed `attempt`, or the
+    /// error to give up with. An error which would not go away is returned
+    /// as is, even after retries.
+    fn on_failure<E: Retryable>(&self, attempt: u32, error: E) -> Result<Duration, E> {
+        if !error.is_transient() {
+            return Err(error);
+        }
+        if attempt >= self.attempts {
+            return Err(if attempt > 1 {
+                E::retries_exhausted(attempt, error)
+            } else {
+                error
+            });
+        }
+        Ok(self.backoff(attempt))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::no_retry()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ClientError, NodeError};
+    use reqwest::StatusCode;
+
+    fn unavailable() -> ClientError {
+        NodeError::parse(StatusCode::SERVICE_UNAVAILABLE, "unavailable").into()
+    }
+
+    fn bad_request() -> ClientError {
+        NodeError::parse(StatusCode::BAD_REQUEST, "bad request").into()
+    }
+
+    #[test]
+    fn transient_errors_are_retried_until_exhausted() {
+        let policy = RetryPolicy::fixed(3, Duration::from_millis(1));
+        let mut calls = 0;
+        let result: Result<(), _> = policy.run(|| {
+            calls += 1;
+            Err(unavailable())
+        });
+        assert_eq!(calls, 3);
+        assert!(matches!(
+            result,
+            Err(ClientError::RetriesExhausted { attempts: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn single_attempt_returns_the_error_as_is() {
+        let mut calls = 0;
+        let result: Result<(), _> = RetryPolicy::no_retry().run(|| {
+            calls += 1;
+            Err(unavailable())
+        });
+        assert_eq!(calls, 1);
+        assert!(matches!(result, Err(ClientError::Node(_))));
+    }
+
+    #[test]
+    fn other_errors_are_returned_as_is_after_retries() {
+        let policy = RetryPolicy::fixed(3, Duration::from_millis(1));
+        let mut calls = 0;
+        let result: Result<(), _> = policy.run(|| {
+            calls += 1;
+            if calls == 1 {
+                Err(unavailable())
+            } else {
+                Err(bad_request())
+            }
+        });
+        assert_eq!(calls, 2);
+        assert!(matches!(result, Err(ClientError::Node(_))));
+    }
+
+    #[tokio::test]
+    async fn asynchronous_calls_are_retried() {
+        let policy = RetryPolicy::fixed(3, Duration::from_millis(1));
+        let mut calls = 0;
+        let result = policy
+            .run_async(|| {
+                calls += 1;
+                let result = if calls < 3 {
+                    Err(unavailable())
+                } else {
+                    Ok(calls)
+                };
+                async move { result }
+            })
+            .await;
+        assert_eq!(result.unwrap(), 3);
+    }
+
+    #[test]
+    fn exponential_backoff_is_capped() {
+        let policy =
+            RetryPolicy::exponential(5, Duration::from_millis(100), Duration::from_millis(500));
+        let backoffs: Vec<_> = (1..5).map(|attempt| policy.backoff(attempt)).collect();
+        assert_eq!(
+            backoffs,
+            vec![
+                Duration::from_millis(100),
+                Duration::from_millis(200),
+                Duration::from_millis(400),
+                Duration::from_millis(500),
+            ]
+        );
+    }
+
+    #[test]
+    fn fixed_backoff_does_not_grow() {
+        let policy = RetryPolicy::fixed(3, Duration::from_secs(1));
+        assert_eq!(policy.backoff(1), policy.backoff(2));
+    }
+}
@@ -0,0 +1,76 @@
+#ifndef JORMUNGANDR_LIB_FFI_H
+#define JORMUNGANDR_LIB_FFI_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum {
+  JORM_RESULT_OK = 0,
+  JORM_RESULT_NULL_POINTER = 1,
+  JORM_RESULT_INVALID_STRING = 2,
+  JORM_RESULT_INVALID_ADDRESS = 3,
+  JORM_RESULT_INVALID_KEY = 4,
+  JORM_RESULT_INVALID_HASH = 5,
+  JORM_RESULT_INVALID_VOTE = 6,
+  JORM_RESULT_SERIALIZATION = 7,
+} JormResult;
+
+enum {
+  JORM_ADDRESS_KIND_SINGLE = 0,
+  JORM_ADDRESS_KIND_GROUP = 1,
+  JORM_ADDRESS_KIND_ACCOUNT = 2,
+  JORM_ADDRESS_KIND_MULTISIG = 3,
+  JORM_ADDRESS_KIND_SCRIPT = 4,
+};
+
+typedef struct {
+  uint8_t testing;
+  uint8_t kind;
+} JormAddressInfo;
+
+/* the fee parameters of the blockchain, as in the block0 configuration */
+typedef struct {
+  uint64_t constant;
+  uint64_t coefficient;
+  uint64_t certificate;
+  uint64_t certificate_vote_plan;
+  uint64_t certificate_vote_cast;
+} Fees;
+
+/* description of the last error of the thread, to release with
+ * jorm_string_delete, or NULL */
+char *jorm_last_error(void);
+void jorm_string_delete(char *string);
+void jorm_bytes_delete(uint8_t *bytes, size_t len);
+
+JormResult jorm_address_parse(const char *address, JormAddressInfo *out);
+
+uint64_t jorm_transaction_fee(Fees fees, uint8_t inputs, uint8_t outputs);
+uint64_t jorm_vote_cast_fee(Fees fees);
+
+/* the fragment bytes are to release with jorm_bytes_delete */
+JormResult jorm_vote_cast_public(const char *block0_hash,
+                                 const char *account_secret_key,
+                                 uint32_t spending_counter,
+                                 const char *vote_plan_id,
+                                 uint8_t proposal_index, uint8_t choice,
+                                 Fees fees, uint8_t **out, size_t *out_len);
+
+JormResult jorm_vote_cast_private(const char *block0_hash,
+                                  const char *account_secret_key,
+                                  uint32_t spending_counter,
+                                  const char *vote_plan_id,
+                                  uint8_t proposal_index,
+                                  const char *encrypting_key, uint8_t options,
+                                  uint8_t choice, Fees fees, uint8_t **out,
+                                  size_t *out_len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,294 @@
+//! C bindings. The functions report failures with a `JormResult` code, the
+//! description of the last error of the thread being available with
+//! `jorm_last_error`.
+
+use crate::{AddressInfo, Error, Fees, VoteCastParams};
+use chain_impl_mockchain::vote::Payload;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JormResult {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidString = 2,
+    InvalidAddress = 3,
+    InvalidKey = 4,
+    InvalidHash = 5,
+    InvalidVote = 6,
+    Serialization = 7,
+}
+
+impl From<&Error> for JormResult {
+    fn from(error: &Error) -> Self {
+        match error {
+            Error::InvalidAddress(_) => JormResult::InvalidAddress,
+            Error::InvalidSecretKey(_)
+            | Error::InvalidBech32(_)
+            | Error::InvalidBech32Prefix { .. }
+            | Error::InvalidEncryptingKey => JormResult::InvalidKey,
+            Error::InvalidBlock0Hash | Error::InvalidVotePlanId => JormResult::InvalidHash,
+            Error::InvalidChoice { .. } => JormResult::InvalidVote,
+            Error::Serialization(_) => JormResult::Serialization,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct JormAddressInfo {
+    /// 1 for the testing discrimination, 0 for the production one
+    pub testing: u8,
+    /// one of the `AddressKind` values
+    pub kind: u8,
+}
+
+impl From<AddressInfo> for JormAddressInfo {
+    fn from(info: AddressInfo) -> Self {
+        Self {
+            testing: info.testing as u8,
+            kind: info.kind as u8,
+        }
+    }
+}
+
+fn set_last_error(message: String) {
+    LAST_ERROR.with(|last| *last.borrow_mut() = CString::new(message).ok());
+}
+
+fn report(error: Error) -> JormResult {
+    let result = JormResult::from(&error);
+    set_last_error(error.to_string());
+    result
+}
+
+unsafe fn read_str<'a>(ptr: *const c_char) -> Result<&'a str, JormResult> {
+    if ptr.is_null() {
+        set_last_error("null pointer given instead of a string".to_string());
+        return Err(JormResult::NullPointer);
+    }
+    CStr::from_ptr(ptr).to_str().map_err(|_| {
+        set_last_error("string is not valid UTF-8".to_string());
+        JormResult::InvalidString
+    })
+}
+
+unsafe fn write_bytes(bytes: Vec<u8>, out: *mut *mut u8, out_len: *mut usize) -> JormResult {
+    if out.is_null() || out_len.is_null() {
+        set_last_error("null pointer given for the output".to_string());
+        return JormResult::NullPointer;
+    }
+    let bytes = bytes.into_boxed_slice();
+    *out_len = bytes.len();
+    *out = Box::into_raw(bytes) as *mut u8;
+    JormResult::Ok
+}
+
+macro_rules! try_ffi {
+    ($expr:expr) => {
+        match $expr {
+            Ok(value) => value,
+            Err(result) => return result,
+        }
+    };
+}
+
+/// Description of the last error of the calling thread, or null if there
+/// was none. The string must be released with `jorm_string_delete`.
+#[no_mangle]
+pub extern "C" fn jorm_last_error() -> *mut c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow_mut()
+            .take()
+            .map_or(ptr::null_mut(), CString::into_raw)
+    })
+}
+
+/// # Safety
+///
+/// `string` must be null or returned by this library and not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn jorm_string_delete(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// # Safety
+///
+/// `bytes` and `len` must be null or returned by this library and the
+/// bytes not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn jorm_bytes_delete(bytes: *mut u8, len: usize) {
+    if !bytes.is_null() {
+        drop(Box::from_raw(std::slice::from_raw_parts_mut(bytes, len)));
+    }
+}
+
+/// # Safety
+///
+/// `address` must be a nul terminated string and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn jorm_address_parse(
+    address: *const c_char,
+    out: *mut JormAddressInfo,
+) -> JormResult {
+    let address = try_ffi!(read_str(address));
+    if out.is_null() {
+        set_last_error("null pointer given for the output".to_string());
+        return JormResult::NullPointer;
+    }
+    match crate::parse_address(address) {
+        Ok(info) => {
+            *out = info.into();
+            JormResult::Ok
+        }
+        Err(error) => report(error),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn jorm_transaction_fee(fees: Fees, inputs: u8, outputs: u8) -> u64 {
+    crate::transaction_fee(fees, inputs, outputs)
+}
+
+#[no_mangle]
+pub extern "C" fn jorm_vote_cast_fee(fees: Fees) -> u64 {
+    crate::vote_cast_fee(fees)
+}
+
+#[allow(clippy::too_many_arguments)]
+unsafe fn vote_cast(
+    block0_hash: *const c_char,
+    account_secret_key: *const c_char,
+    spending_counter: u32,
+    vote_plan_id: *const c_char,
+    proposal_index: u8,
+    fees: Fees,
+    payload: Result<Payload, Error>,
+    out: *mut *mut u8,
+    out_len: *mut usize,
+) -> JormResult {
+    let params = VoteCastParams {
+        block0_hash: try_ffi!(read_str(block0_hash)),
+        account_secret_key: try_ffi!(read_str(account_secret_key)),
+        spending_counter,
+        vote_plan_id: try_ffi!(read_str(vote_plan_id)),
+        proposal_index,
+        fees,
+    };
+    match payload.and_then(|payload| crate::vote_cast_fragment(&params, payload)) {
+        Ok(bytes) => write_bytes(bytes, out, out_len),
+        Err(error) => report(error),
+    }
+}
+
+/// Build a signed fragment casting a public vote. The fragment bytes must be
+/// released with `jorm_bytes_delete`.
+///
+/// # Safety
+///
+/// The strings must be nul terminated, `out` and `out_len` valid pointers.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn jorm_vote_cast_public(
+    block0_hash: *const c_char,
+    account_secret_key: *const c_char,
+    spending_counter: u32,
+    vote_plan_id: *const c_char,
+    proposal_index: u8,
+    choice: u8,
+    fees: Fees,
+    out: *mut *mut u8,
+    out_len: *mut usize,
+) -> JormResult {
+    vote_cast(
+        block0_hash,
+        account_secret_key,
+        spending_counter,
+        vote_plan_id,
+        proposal_index,
+        fees,
+        Ok(crate::public_vote(choice)),
+        out,
+        out_len,
+    )
+}
+
+/// Build a signed fragment casting a vote encrypted with the bech32 encoded
+/// key of the committee. The fragment bytes must be released with
+/// `jorm_bytes_delete`.
+///
+/// # Safety
+///
+/// The strings must be nul terminated, `out` and `out_len` valid pointers.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn jorm_vote_cast_private(
+    block0_hash: *const c_char,
+    account_secret_key: *const c_char,
+    spending_counter: u32,
+    vote_plan_id: *const c_char,
+    proposal_index: u8,
+    encrypting_key: *const c_char,
+    options: u8,
+    choice: u8,
+    fees: Fees,
+    out: *mut *mut u8,
+    out_len: *mut usize,
+) -> JormResult {
+    let encrypting_key = try_ffi!(read_str(encrypting_key));
+    vote_cast(
+        block0_hash,
+        account_secret_key,
+        spending_counter,
+        vote_plan_id,
+        proposal_index,
+        fees,
+        crate::private_vote(encrypting_key, options, choice),
+        out,
+        out_len,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_error() -> Option<String> {
+        let error = jorm_last_error();
+        if error.is_null() {
+            return None;
+        }
+        let message = unsafe { CStr::from_ptr(error) }
+            .to_str()
+            .unwrap()
+            .to_owned();
+        unsafe { jorm_string_delete(error) };
+        Some(message)
+    }
+
+    #[test]
+    fn failures_are_reported_with_the_last_error() {
+        let mut info = JormAddressInfo {
+            testing: 0,
+            kind: 0,
+        };
+        let result = unsafe { jorm_address_parse(ptr::null(), &mut info) };
+        assert_eq!(result, JormResult::NullPointer);
+        assert!(last_error().is_some());
+
+        let address = CString::new("ca1notanaddress").unwrap();
+        let result = unsafe { jorm_address_parse(address.as_ptr(), &mut info) };
+        assert_eq!(result, JormResult::InvalidAddress);
+        assert_eq!(last_error().as_deref(), Some("invalid address"));
+        assert_eq!(last_error(), None);
+    }
+}
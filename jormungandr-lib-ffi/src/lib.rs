@@ -0,0 +1,388 @@
+//! Bindings for the wallets building the fragments submitted to jormungandr.
+//!
+//! The functions of this module are the Rust API shared by the C bindings
+//! (see `include/jormungandr_lib_ffi.h`) and, with the `wasm` feature, by
+//! the WASM bindings. They produce the same binary formats as `jcli`.
+
+mod c;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+pub use c::*;
+
+use chain_addr::{AddressReadable, Discrimination, Kind};
+use chain_core::property::Serialize as _;
+use chain_crypto::{bech32::Bech32 as _, Ed25519, Ed25519Extended, SecretKey};
+use chain_impl_mockchain::{
+    account::SpendingCounter,
+    certificate::{Certificate, CertificatePayload, VoteCast, VotePlanId},
+    fee::{FeeAlgorithm, LinearFee, PerVoteCertificateFee},
+    fragment::Fragment,
+    header::HeaderId,
+    key::EitherEd25519SecretKey,
+    transaction::{Input, TxBuilder, UnspecifiedAccountIdentifier, Witness},
+    vote::{Choice, Payload},
+};
+use jormungandr_lib::interfaces::{TransactionInput, TransactionInputType, Value};
+use rand_chacha::rand_core::SeedableRng;
+use std::num::NonZeroU64;
+use thiserror::Error;
+
+/// human readable part of the bech32 encoded vote encrypting keys
+pub const ENCRYPTING_VOTE_PK_HRP: &str = "p256k1_votepk";
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid address")]
+    InvalidAddress(#[source] chain_addr::Error),
+    #[error("invalid secret key")]
+    InvalidSecretKey(#[source] chain_crypto::bech32::Error),
+    #[error("invalid block0 hash")]
+    InvalidBlock0Hash,
+    #[error("invalid vote plan id")]
+    InvalidVotePlanId,
+    #[error("invalid bech32 encoding")]
+    InvalidBech32(#[from] bech32::Error),
+    #[error("expected a key with prefix '{expected}', found '{actual}'")]
+    InvalidBech32Prefix { expected: String, actual: String },
+    #[error("invalid vote encrypting key")]
+    InvalidEncryptingKey,
+    #[error("choice {choice} is out of the {options} options of the proposal")]
+    InvalidChoice { choice: u8, options: u8 },
+    #[error("cannot serialize fragment")]
+    Serialization(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AddressKind {
+    Single = 0,
+    Group = 1,
+    Account = 2,
+    Multisig = 3,
+    Script = 4,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressInfo {
+    pub testing: bool,
+    pub kind: AddressKind,
+}
+
+/// parse an address in its bech32 representation, whatever its prefix
+pub fn parse_address(address: &str) -> Result<AddressInfo, Error> {
+    let address = AddressReadable::from_string_anyprefix(address)
+        .map_err(Error::InvalidAddress)?
+        .to_address();
+    let kind = match address.kind() {
+        Kind::Single(_) => AddressKind::Single,
+        Kind::Group(_, _) => AddressKind::Group,
+        Kind::Account(_) => AddressKind::Account,
+        Kind::Multisig(_) => AddressKind::Multisig,
+        Kind::Script(_) => AddressKind::Script,
+    };
+    Ok(AddressInfo {
+        testing: address.discrimination() == Discrimination::Test,
+        kind,
+    })
+}
+
+/// the fee parameters of the blockchain, as in the block0 configuration
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[repr(C)]
+pub struct Fees {
+    pub constant: u64,
+    pub coefficient: u64,
+    pub certificate: u64,
+    pub certificate_vote_plan: u64,
+    pub certificate_vote_cast: u64,
+}
+
+impl From<Fees> for LinearFee {
+    fn from(fees: Fees) -> Self {
+        let mut linear_fee = LinearFee::new(fees.constant, fees.coefficient, fees.certificate);
+        linear_fee.per_vote_certificate_fees(PerVoteCertificateFee::new(
+            NonZeroU64::new(fees.certificate_vote_plan),
+            NonZeroU64::new(fees.certificate_vote_cast),
+        ));
+        linear_fee
+    }
+}
+
+/// fee of a transaction without certificate
+pub fn transaction_fee(fees: Fees, inputs: u8, outputs: u8) -> u64 {
+    LinearFee::from(fees).calculate(None, inputs, outputs).0
+}
+
+fn certificate_fee(fees: Fees, certificate: &Certificate, inputs: u8, outputs: u8) -> u64 {
+    let payload = CertificatePayload::from(certificate);
+    LinearFee::from(fees)
+        .calculate(Some(payload.as_slice()), inputs, outputs)
+        .0
+}
+
+/// fee of a vote cast fragment spending from a single account
+pub fn vote_cast_fee(fees: Fees) -> u64 {
+    let dummy = VoteCast::new(
+        VotePlanId::from([0; 32]),
+        0,
+        Payload::public(Choice::new(0)),
+    );
+    certificate_fee(fees, &Certificate::VoteCast(dummy), 1, 0)
+}
+
+/// vote payload in clear, visible to everyone on the blockchain
+pub fn public_vote(choice: u8) -> Payload {
+    Payload::public(Choice::new(choice))
+}
+
+/// vote payload encrypted with the bech32 encoded key of the committee
+pub fn private_vote(encrypting_key: &str, options: u8, choice: u8) -> Result<Payload, Error> {
+    use bech32::FromBase32 as _;
+
+    if choice >= options {
+        return Err(Error::InvalidChoice { choice, options });
+    }
+    let (hrp, data) = bech32::decode(encrypting_key)?;
+    if hrp != ENCRYPTING_VOTE_PK_HRP {
+        return Err(Error::InvalidBech32Prefix {
+            expected: ENCRYPTING_VOTE_PK_HRP.to_string(),
+            actual: hrp,
+        });
+    }
+    let key = chain_vote::EncryptingVoteKey::from_bytes(&Vec::<u8>::from_base32(&data)?)
+        .ok_or(Error::InvalidEncryptingKey)?;
+    let vote = chain_vote::Vote::new(options as usize, choice as usize);
+    let mut rng = rand_chacha::ChaChaRng::from_entropy();
+    let (encrypted_vote, proof) = chain_impl_mockchain::vote::encrypt_vote(&mut rng, &key, vote);
+    Ok(Payload::Private {
+        encrypted_vote,
+        proof,
+    })
+}
+
+fn parse_secret_key(bech32_str: &str) -> Result<EitherEd25519SecretKey, Error> {
+    match SecretKey::<Ed25519Extended>::try_from_bech32_str(bech32_str) {
+        Ok(sk) => Ok(EitherEd25519SecretKey::Extended(sk)),
+        Err(_) => SecretKey::<Ed25519>::try_from_bech32_str(bech32_str)
+            .map(EitherEd25519SecretKey::Normal)
+            .map_err(Error::InvalidSecretKey),
+    }
+}
+
+/// Everything needed to sign a vote cast fragment spending the fee from an
+/// account.
+#[derive(Debug, Clone)]
+pub struct VoteCastParams<'a> {
+    /// hex encoded hash of the block0 of the blockchain
+    pub block0_hash: &'a str,
+    /// bech32 encoded secret key of the account
+    pub account_secret_key: &'a str,
+    /// current spending counter of the account
+    pub spending_counter: u32,
+    /// hex encoded identifier of the vote plan
+    pub vote_plan_id: &'a str,
+    pub proposal_index: u8,
+    pub fees: Fees,
+}
+
+/// build the signed vote cast fragment, serialized as sent to the node
+pub fn vote_cast_fragment(params: &VoteCastParams, payload: Payload) -> Result<Vec<u8>, Error> {
+    let block0_hash: HeaderId = params
+        .block0_hash
+        .parse()
+        .map_err(|_| Error::InvalidBlock0Hash)?;
+    let vote_plan_id: VotePlanId = params
+        .vote_plan_id
+        .parse()
+        .map_err(|_| Error::InvalidVotePlanId)?;
+    let secret_key = parse_secret_key(params.account_secret_key)?;
+    let vote_cast = VoteCast::new(vote_plan_id, params.proposal_index, payload);
+
+    let fee = certificate_fee(params.fees, &Certificate::VoteCast(vote_cast.clone()), 1, 0);
+    let account = UnspecifiedAccountIdentifier::from_single_account(secret_key.to_public().into());
+    let input: Input = TransactionInput {
+        input: TransactionInputType::Account(account.into()),
+        value: Value::from(fee),
+    }
+    .into();
+
+    let builder = TxBuilder::new()
+        .set_payload(&vote_cast)
+        .set_ios(&[input], &[]);
+    let sign_data_hash = builder.get_auth_data_for_witness().hash();
+    let witness = Witness::new_account(
+        &block0_hash,
+        &sign_data_hash,
+        SpendingCounter::from(params.spending_counter),
+        |d| secret_key.sign(d),
+    );
+    let transaction = builder.set_witnesses(&[witness]).set_payload_auth(&());
+
+    Fragment::VoteCast(transaction)
+        .serialize_as_vec()
+        .map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_addr::Address;
+    use chain_core::property::Deserialize as _;
+    use chain_crypto::PublicKey;
+
+    const BLOCK0_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000001";
+    const VOTE_PLAN_ID: &str = "0000000000000000000000000000000000000000000000000000000000000002";
+
+    fn account_secret_key() -> SecretKey<Ed25519> {
+        SecretKey::generate(rand_chacha::ChaChaRng::from_seed([0; 32]))
+    }
+
+    fn account_address(discrimination: Discrimination, pk: PublicKey<Ed25519>) -> String {
+        let address = Address(discrimination, Kind::Account(pk));
+        AddressReadable::from_address("ca", &address).to_string()
+    }
+
+    #[test]
+    fn account_addresses_are_parsed() {
+        let pk = account_secret_key().to_public();
+        assert_eq!(
+            parse_address(&account_address(Discrimination::Test, pk.clone())).unwrap(),
+            AddressInfo {
+                testing: true,
+                kind: AddressKind::Account
+            }
+        );
+        assert_eq!(
+            parse_address(&account_address(Discrimination::Production, pk)).unwrap(),
+            AddressInfo {
+                testing: false,
+                kind: AddressKind::Account
+            }
+        );
+        assert!(matches!(
+            parse_address("ca1notanaddress"),
+            Err(Error::InvalidAddress(_))
+        ));
+    }
+
+    #[test]
+    fn transaction_fee_is_linear() {
+        let fees = Fees {
+            constant: 2,
+            coefficient: 3,
+            certificate: 100,
+            ..Fees::default()
+        };
+        assert_eq!(transaction_fee(fees, 0, 0), 2);
+        assert_eq!(transaction_fee(fees, 1, 2), 2 + 3 * 3);
+    }
+
+    #[test]
+    fn vote_cast_fee_uses_vote_certificate_fee() {
+        let mut fees = Fees {
+            constant: 2,
+            coefficient: 1,
+            certificate: 4,
+            ..Fees::default()
+        };
+        assert_eq!(vote_cast_fee(fees), 7);
+        fees.certificate_vote_cast = 10;
+        assert_eq!(vote_cast_fee(fees), 13);
+    }
+
+    #[test]
+    fn private_vote_rejects_choice_out_of_options() {
+        assert!(matches!(
+            private_vote("p256k1_votepk1", 2, 2),
+            Err(Error::InvalidChoice {
+                choice: 2,
+                options: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn private_vote_rejects_other_bech32_prefixes() {
+        let key = account_secret_key().to_bech32_str();
+        assert!(matches!(
+            private_vote(&key, 2, 1),
+            Err(Error::InvalidBech32Prefix { .. })
+        ));
+    }
+
+    #[test]
+    fn vote_cast_fragment_pays_the_fee_from_the_account() {
+        let secret_key = account_secret_key().to_bech32_str();
+        let fees = Fees {
+            constant: 2,
+            coefficient: 1,
+            certificate_vote_cast: 10,
+            ..Fees::default()
+        };
+        let params = VoteCastParams {
+            block0_hash: BLOCK0_HASH,
+            account_secret_key: &secret_key,
+            spending_counter: 3,
+            vote_plan_id: VOTE_PLAN_ID,
+            proposal_index: 4,
+            fees,
+        };
+        let bytes = vote_cast_fragment(&params, public_vote(1)).unwrap();
+
+        let transaction = match Fragment::deserialize(bytes.as_slice()).unwrap() {
+            Fragment::VoteCast(transaction) => transaction,
+            fragment => panic!("unexpected fragment {:?}", fragment),
+        };
+        let transaction = transaction.as_slice();
+        let vote_cast = transaction.payload().into_payload();
+        assert_eq!(vote_cast.vote_plan(), &VOTE_PLAN_ID.parse().unwrap());
+        assert_eq!(vote_cast.proposal_index(), 4);
+        assert_eq!(transaction.nb_inputs(), 1);
+        assert_eq!(transaction.nb_outputs(), 0);
+        assert_eq!(transaction.total_input().unwrap().0, vote_cast_fee(fees));
+    }
+
+    #[test]
+    fn vote_cast_fragment_rejects_malformed_identifiers() {
+        let secret_key = account_secret_key().to_bech32_str();
+        let params = VoteCastParams {
+            block0_hash: BLOCK0_HASH,
+            account_secret_key: &secret_key,
+            spending_counter: 0,
+            vote_plan_id: VOTE_PLAN_ID,
+            proposal_index: 0,
+            fees: Fees::default(),
+        };
+        assert!(matches!(
+            vote_cast_fragment(
+                &VoteCastParams {
+                    block0_hash: "00",
+                    ..params.clone()
+                },
+                public_vote(0)
+            ),
+            Err(Error::InvalidBlock0Hash)
+        ));
+        assert!(matches!(
+            vote_cast_fragment(
+                &VoteCastParams {
+                    vote_plan_id: "not a vote plan",
+                    ..params.clone()
+                },
+                public_vote(0)
+            ),
+            Err(Error::InvalidVotePlanId)
+        ));
+        assert!(matches!(
+            vote_cast_fragment(
+                &VoteCastParams {
+                    account_secret_key: "ed25519_sk1invalid",
+                    ..params
+                },
+                public_vote(0)
+            ),
+            Err(Error::InvalidSecretKey(_))
+        ));
+    }
+}
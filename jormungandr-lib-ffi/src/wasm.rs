@@ -0,0 +1,103 @@
+//! WASM bindings, the fragments being returned as bytes and the failures as
+//! exceptions carrying the error description.
+
+use crate::{Fees, VoteCastParams};
+use wasm_bindgen::prelude::*;
+
+fn to_js(error: crate::Error) -> JsValue {
+    JsValue::from_str(&error.to_string())
+}
+
+#[wasm_bindgen]
+pub struct AddressInfo {
+    pub testing: bool,
+    pub kind: u8,
+}
+
+#[wasm_bindgen(js_name = parseAddress)]
+pub fn parse_address(address: &str) -> Result<AddressInfo, JsValue> {
+    let info = crate::parse_address(address).map_err(to_js)?;
+    Ok(AddressInfo {
+        testing: info.testing,
+        kind: info.kind as u8,
+    })
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Default)]
+pub struct LinearFees(Fees);
+
+#[wasm_bindgen]
+impl LinearFees {
+    #[wasm_bindgen(constructor)]
+    pub fn new(constant: u64, coefficient: u64, certificate: u64) -> Self {
+        Self(Fees {
+            constant,
+            coefficient,
+            certificate,
+            ..Fees::default()
+        })
+    }
+
+    #[wasm_bindgen(js_name = setVoteFees)]
+    pub fn set_vote_fees(&mut self, vote_plan: u64, vote_cast: u64) {
+        self.0.certificate_vote_plan = vote_plan;
+        self.0.certificate_vote_cast = vote_cast;
+    }
+
+    #[wasm_bindgen(js_name = transactionFee)]
+    pub fn transaction_fee(&self, inputs: u8, outputs: u8) -> u64 {
+        crate::transaction_fee(self.0, inputs, outputs)
+    }
+
+    #[wasm_bindgen(js_name = voteCastFee)]
+    pub fn vote_cast_fee(&self) -> u64 {
+        crate::vote_cast_fee(self.0)
+    }
+}
+
+#[wasm_bindgen(js_name = voteCastPublic)]
+pub fn vote_cast_public(
+    block0_hash: &str,
+    account_secret_key: &str,
+    spending_counter: u32,
+    vote_plan_id: &str,
+    proposal_index: u8,
+    choice: u8,
+    fees: &LinearFees,
+) -> Result<Vec<u8>, JsValue> {
+    let params = VoteCastParams {
+        block0_hash,
+        account_secret_key,
+        spending_counter,
+        vote_plan_id,
+        proposal_index,
+        fees: fees.0,
+    };
+    crate::vote_cast_fragment(&params, crate::public_vote(choice)).map_err(to_js)
+}
+
+#[wasm_bindgen(js_name = voteCastPrivate)]
+#[allow(clippy::too_many_arguments)]
+pub fn vote_cast_private(
+    block0_hash: &str,
+    account_secret_key: &str,
+    spending_counter: u32,
+    vote_plan_id: &str,
+    proposal_index: u8,
+    encrypting_key: &str,
+    options: u8,
+    choice: u8,
+    fees: &LinearFees,
+) -> Result<Vec<u8>, JsValue> {
+    let params = VoteCastParams {
+        block0_hash,
+        account_secret_key,
+        spending_counter,
+        vote_plan_id,
+        proposal_index,
+        fees: fees.0,
+    };
+    let payload = crate::private_vote(encrypting_key, options, choice).map_err(to_js)?;
+    crate::vote_cast_fragment(&params, payload).map_err(to_js)
+}
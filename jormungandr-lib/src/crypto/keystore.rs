@@ -0,0 +1,510 @@
+//! Encrypted store of the secret keys of a node or a stake pool owner.
+//!
+//! The keystore is a directory holding one key file per secret key, named
+//! after the name chosen by the user for the key, so the node secret file
+//! can refer to the keys instead of holding them in plain text. The key
+//! files follow the Web3 Secret Storage Definition (version 3): the secret
+//! key, in its bech32 form, is encrypted with AES-128-CTR under a key
+//! derived from the passphrase with scrypt, and authenticated with a
+//! Keccak-256 MAC.
+
+use crate::crypto::key::SigningKey;
+use aes_ctr::{
+    cipher::stream::{NewStreamCipher, SyncStreamCipher},
+    Aes128Ctr,
+};
+use chain_crypto::AsymmetricKey;
+use cryptoxide::scrypt::{scrypt, ScryptParams};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+use subtle::ConstantTimeEq;
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+const VERSION: u32 = 3;
+const CIPHER: &str = "aes-128-ctr";
+const KDF: &str = "scrypt";
+const DKLEN: usize = 32;
+const SALT_SIZE: usize = 32;
+const IV_SIZE: usize = 16;
+const KEY_FILE_EXTENSION: &str = "json";
+
+#[derive(Debug, Error)]
+pub enum KeystoreError {
+    #[error("cannot read or write the keystore")]
+    Io(#[from] std::io::Error),
+    #[error("malformed key file")]
+    Format(#[from] serde_json::Error),
+    #[error("the keystore already exists at {0}")]
+    AlreadyExists(PathBuf),
+    #[error("unsupported key file version {0}")]
+    UnsupportedVersion(u32),
+    #[error("unsupported cipher or key derivation '{0}'")]
+    UnsupportedAlgorithm(String),
+    #[error("invalid passphrase")]
+    InvalidPassphrase,
+    #[error("the keystore has no entry '{0}'")]
+    EntryNotFound(String),
+    #[error("the keystore already has an entry '{0}'")]
+    EntryExists(String),
+    #[error("invalid entry name '{0}'")]
+    InvalidName(String),
+    #[error("entry '{0}' is corrupted")]
+    CorruptedEntry(String),
+    #[error("entry '{name}' is not a valid secret key of the expected type")]
+    InvalidKey {
+        name: String,
+        #[source]
+        source: chain_crypto::bech32::Error,
+    },
+}
+
+/// parameters of the scrypt derivation of the encryption key of new
+/// entries, the entries read keep the parameters they were written with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl KdfParams {
+    /// whether scrypt accepts the parameters: N greater than 1 and below
+    /// 2^(16 r), and r p below 2^30
+    fn is_valid(&self) -> bool {
+        self.log_n > 0
+            && self.r > 0
+            && self.p > 0
+            && u64::from(self.log_n) < u64::from(self.r) * 16
+            && u64::from(self.r) * u64::from(self.p) < 1 << 30
+    }
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams {
+            log_n: 15,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+/// An opened keystore. The passphrase is kept in memory, to open the
+/// entries and seal the new ones, and is wiped when the keystore is dropped.
+pub struct Keystore {
+    dir: PathBuf,
+    passphrase: Zeroizing<Vec<u8>>,
+    params: KdfParams,
+    entries: BTreeMap<String, KeyFile>,
+}
+
+impl Keystore {
+    /// create a new empty keystore directory, the entries being protected
+    /// by the passphrase
+    pub fn create<P: AsRef<Path>>(
+        dir: P,
+        passphrase: &[u8],
+        params: KdfParams,
+    ) -> Result<Self, KeystoreError> {
+        let dir = dir.as_ref();
+        if dir.exists() {
+            return Err(KeystoreError::AlreadyExists(dir.to_owned()));
+        }
+        fs::create_dir_all(dir)?;
+        Ok(Keystore {
+            dir: dir.to_owned(),
+            passphrase: Zeroizing::new(passphrase.to_vec()),
+            params,
+            entries: BTreeMap::new(),
+        })
+    }
+
+    /// open the keystore directory, failing if the passphrase does not open
+    /// its entries
+    pub fn open<P: AsRef<Path>>(dir: P, passphrase: &[u8]) -> Result<Self, KeystoreError> {
+        let dir = dir.as_ref();
+        let mut entries = BTreeMap::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(KEY_FILE_EXTENSION) {
+                continue;
+            }
+            let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(name) => name.to_owned(),
+                None => continue,
+            };
+            let key_file: KeyFile = serde_json::from_reader(BufReader::new(File::open(&path)?))?;
+            key_file.check_algorithms()?;
+            entries.insert(name, key_file);
+        }
+        let keystore = Keystore {
+            dir: dir.to_owned(),
+            passphrase: Zeroizing::new(passphrase.to_vec()),
+            params: KdfParams::default(),
+            entries,
+        };
+        // check the passphrase on the first entry, rather than when a key
+        // is needed
+        if let Some(name) = keystore.entries.keys().next() {
+            keystore.get(name)?;
+        }
+        Ok(keystore)
+    }
+
+    /// the scrypt parameters of the entries added from now on
+    pub fn with_kdf_params(self, params: KdfParams) -> Self {
+        Keystore { params, ..self }
+    }
+
+    /// names of the entries, in alphabetical order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    /// store the bech32 encoded secret key under the given name, writing
+    /// its key file to the keystore directory
+    pub fn insert(&mut self, name: &str, secret_bech32: &str) -> Result<(), KeystoreError> {
+        if name.is_empty() || name.starts_with('.') || name.contains(&['/', '\\'][..]) {
+            return Err(KeystoreError::InvalidName(name.to_owned()));
+        }
+        if self.contains(name) {
+            return Err(KeystoreError::EntryExists(name.to_owned()));
+        }
+        let key_file = KeyFile::seal(&self.passphrase, self.params, secret_bech32.as_bytes());
+        self.write_key_file(name, &key_file)?;
+        self.entries.insert(name.to_owned(), key_file);
+        Ok(())
+    }
+
+    /// the bech32 encoded secret key stored under the given name
+    pub fn get(&self, name: &str) -> Result<Zeroizing<String>, KeystoreError> {
+        let key_file = self
+            .entries
+            .get(name)
+            .ok_or_else(|| KeystoreError::EntryNotFound(name.to_owned()))?;
+        let plaintext = key_file
+            .crypto
+            .open(&self.passphrase)
+            .map_err(|error| error.for_entry(name))?;
+        String::from_utf8(plaintext)
+            .map(Zeroizing::new)
+            .map_err(|error| {
+                drop(Zeroizing::new(error.into_bytes()));
+                KeystoreError::CorruptedEntry(name.to_owned())
+            })
+    }
+
+    pub fn signing_key<A: AsymmetricKey>(
+        &self,
+        name: &str,
+    ) -> Result<SigningKey<A>, KeystoreError> {
+        SigningKey::from_bech32_str(&self.get(name)?).map_err(|source| KeystoreError::InvalidKey {
+            name: name.to_owned(),
+            source,
+        })
+    }
+
+    fn write_key_file(&self, name: &str, key_file: &KeyFile) -> Result<(), KeystoreError> {
+        let path = self.dir.join(format!("{}.{}", name, KEY_FILE_EXTENSION));
+        let path_tmp = path.with_extension("tmp");
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        {
+            let mut writer = BufWriter::new(options.open(&path_tmp)?);
+            serde_json::to_writer_pretty(&mut writer, key_file)?;
+            writer.flush()?;
+        }
+        fs::rename(path_tmp, path)?;
+        Ok(())
+    }
+}
+
+/// A key file of the Web3 Secret Storage Definition, version 3.
+#[derive(Serialize, Deserialize)]
+struct KeyFile {
+    version: u32,
+    id: String,
+    crypto: Crypto,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Crypto {
+    cipher: String,
+    cipherparams: CipherParams,
+    ciphertext: String,
+    kdf: String,
+    kdfparams: ScryptKdfParams,
+    mac: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CipherParams {
+    iv: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ScryptKdfParams {
+    dklen: usize,
+    n: u64,
+    r: u32,
+    p: u32,
+    salt: String,
+}
+
+/// why an entry cannot be opened, before knowing its name
+enum OpenError {
+    InvalidPassphrase,
+    Corrupted,
+}
+
+impl OpenError {
+    fn for_entry(self, name: &str) -> KeystoreError {
+        match self {
+            OpenError::InvalidPassphrase => KeystoreError::InvalidPassphrase,
+            OpenError::Corrupted => KeystoreError::CorruptedEntry(name.to_owned()),
+        }
+    }
+}
+
+impl KeyFile {
+    fn seal(passphrase: &[u8], params: KdfParams, plaintext: &[u8]) -> Self {
+        let mut salt = [0; SALT_SIZE];
+        OsRng.fill_bytes(&mut salt);
+        let mut iv = [0; IV_SIZE];
+        OsRng.fill_bytes(&mut iv);
+
+        let derived_key = derive_key(passphrase, &salt, params);
+        let mut ciphertext = plaintext.to_vec();
+        Aes128Ctr::new_var(&derived_key[..16], &iv)
+            .expect("valid key and iv sizes")
+            .apply_keystream(&mut ciphertext);
+        let mac = mac(&derived_key, &ciphertext);
+
+        KeyFile {
+            version: VERSION,
+            id: random_uuid(),
+            crypto: Crypto {
+                cipher: CIPHER.to_owned(),
+                cipherparams: CipherParams {
+                    iv: hex::encode(iv),
+                },
+                ciphertext: hex::encode(ciphertext),
+                kdf: KDF.to_owned(),
+                kdfparams: ScryptKdfParams {
+                    dklen: DKLEN,
+                    n: 1 << params.log_n,
+                    r: params.r,
+                    p: params.p,
+                    salt: hex::encode(salt),
+                },
+                mac: hex::encode(mac),
+            },
+        }
+    }
+
+    fn check_algorithms(&self) -> Result<(), KeystoreError> {
+        if self.version != VERSION {
+            return Err(KeystoreError::UnsupportedVersion(self.version));
+        }
+        if self.crypto.cipher != CIPHER {
+            return Err(KeystoreError::UnsupportedAlgorithm(
+                self.crypto.cipher.clone(),
+            ));
+        }
+        if self.crypto.kdf != KDF {
+            return Err(KeystoreError::UnsupportedAlgorithm(self.crypto.kdf.clone()));
+        }
+        Ok(())
+    }
+}
+
+impl Crypto {
+    fn open(&self, passphrase: &[u8]) -> Result<Vec<u8>, OpenError> {
+        let kdfparams = &self.kdfparams;
+        if kdfparams.dklen != DKLEN || !kdfparams.n.is_power_of_two() {
+            return Err(OpenError::Corrupted);
+        }
+        let params = KdfParams {
+            log_n: kdfparams.n.trailing_zeros() as u8,
+            r: kdfparams.r,
+            p: kdfparams.p,
+        };
+        if !params.is_valid() {
+            return Err(OpenError::Corrupted);
+        }
+        let salt = hex::decode(&kdfparams.salt).map_err(|_| OpenError::Corrupted)?;
+        let iv = hex::decode(&self.cipherparams.iv).map_err(|_| OpenError::Corrupted)?;
+        let expected_mac = hex::decode(&self.mac).map_err(|_| OpenError::Corrupted)?;
+        let mut plaintext = hex::decode(&self.ciphertext).map_err(|_| OpenError::Corrupted)?;
+
+        let derived_key = derive_key(passphrase, &salt, params);
+        // compared in constant time, not to tell how much of the MAC matches
+        if !bool::from(mac(&derived_key, &plaintext).ct_eq(&expected_mac)) {
+            return Err(OpenError::InvalidPassphrase);
+        }
+        Aes128Ctr::new_var(&derived_key[..16], &iv)
+            .map_err(|_| OpenError::Corrupted)?
+            .apply_keystream(&mut plaintext);
+        Ok(plaintext)
+    }
+}
+
+fn derive_key(passphrase: &[u8], salt: &[u8], params: KdfParams) -> Zeroizing<[u8; DKLEN]> {
+    let mut key = Zeroizing::new([0; DKLEN]);
+    let params = ScryptParams::new(params.log_n, params.r, params.p);
+    scrypt(passphrase, salt, &params, &mut key[..]);
+    key
+}
+
+/// the MAC of the key file, authenticating both the ciphertext and the
+/// passphrase
+fn mac(derived_key: &[u8; DKLEN], ciphertext: &[u8]) -> Vec<u8> {
+    let mut hasher = Keccak256::new();
+    hasher.update(&derived_key[16..]);
+    hasher.update(ciphertext);
+    hasher.finalize().to_vec()
+}
+
+/// a random (version 4) UUID, identifying the key file
+fn random_uuid() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex::encode(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_crypto::{Curve25519_2HashDH, Ed25519};
+
+    const TEST_PARAMS: KdfParams = KdfParams {
+        log_n: 4,
+        r: 8,
+        p: 1,
+    };
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("keystore-{:016x}", OsRng.next_u64()))
+    }
+
+    #[test]
+    fn entries_survive_a_round_trip() {
+        let dir = temp_dir();
+        let key = SigningKey::<Curve25519_2HashDH>::generate(rand::thread_rng());
+
+        let mut keystore = Keystore::create(&dir, b"passphrase", TEST_PARAMS).unwrap();
+        keystore.insert("vrf", &key.to_bech32_str()).unwrap();
+
+        let keystore = Keystore::open(&dir, b"passphrase").unwrap();
+        assert_eq!(keystore.names().collect::<Vec<_>>(), vec!["vrf"]);
+        let loaded: SigningKey<Curve25519_2HashDH> = keystore.signing_key("vrf").unwrap();
+        assert_eq!(loaded.to_bech32_str(), key.to_bech32_str());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let dir = temp_dir();
+        let key = SigningKey::<Ed25519>::generate(rand::thread_rng());
+        Keystore::create(&dir, b"passphrase", TEST_PARAMS)
+            .unwrap()
+            .insert("owner", &key.to_bech32_str())
+            .unwrap();
+
+        assert!(matches!(
+            Keystore::open(&dir, b"other"),
+            Err(KeystoreError::InvalidPassphrase)
+        ));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn key_files_follow_the_web3_secret_storage_format() {
+        let dir = temp_dir();
+        let key = SigningKey::<Ed25519>::generate(rand::thread_rng());
+        Keystore::create(&dir, b"passphrase", TEST_PARAMS)
+            .unwrap()
+            .insert("owner", &key.to_bech32_str())
+            .unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_reader(File::open(dir.join("owner.json")).unwrap()).unwrap();
+        assert_eq!(json["version"], 3);
+        assert_eq!(json["crypto"]["cipher"], "aes-128-ctr");
+        assert_eq!(json["crypto"]["kdf"], "scrypt");
+        assert_eq!(json["crypto"]["kdfparams"]["n"], 16);
+        assert_eq!(json["crypto"]["kdfparams"]["dklen"], 32);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_scrypt_parameters_are_rejected() {
+        let mut key_file = KeyFile::seal(b"passphrase", TEST_PARAMS, b"secret");
+        assert_eq!(key_file.crypto.open(b"passphrase").ok().unwrap(), b"secret");
+
+        key_file.crypto.kdfparams.r = 0;
+        assert!(matches!(
+            key_file.crypto.open(b"passphrase"),
+            Err(OpenError::Corrupted)
+        ));
+        key_file.crypto.kdfparams.r = 1;
+        key_file.crypto.kdfparams.n = 1 << 16;
+        assert!(matches!(
+            key_file.crypto.open(b"passphrase"),
+            Err(OpenError::Corrupted)
+        ));
+    }
+
+    #[test]
+    fn tampered_entries_are_rejected() {
+        let key = SigningKey::<Ed25519>::generate(rand::thread_rng());
+        let dir = temp_dir();
+        let mut keystore = Keystore::create(&dir, b"passphrase", TEST_PARAMS).unwrap();
+        keystore.insert("owner", &key.to_bech32_str()).unwrap();
+
+        let entry = keystore.entries.get_mut("owner").unwrap();
+        let mut ciphertext = hex::decode(&entry.crypto.ciphertext).unwrap();
+        ciphertext[0] ^= 1;
+        entry.crypto.ciphertext = hex::encode(ciphertext);
+        assert!(matches!(
+            keystore.get("owner"),
+            Err(KeystoreError::InvalidPassphrase)
+        ));
+        assert!(matches!(
+            keystore.insert("owner", &key.to_bech32_str()),
+            Err(KeystoreError::EntryExists(_))
+        ));
+        assert!(matches!(
+            keystore.insert("../owner", &key.to_bech32_str()),
+            Err(KeystoreError::InvalidName(_))
+        ));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -18,6 +18,11 @@
 //! `serde_json`) it will give a bech32 encoding. But utilising
 //! `Display` will provide an hexadecimal encoding version of the key.
 //!
+//! # Keystore
+//!
+//! Secret keys encrypted with a passphrase, stored in a file and
+//! referred to by name.
+//!
 //! # Account keys
 //!
 //! The proper type for the account management and interfaces.
@@ -28,4 +33,5 @@
 pub mod account;
 pub mod hash;
 pub mod key;
+pub mod keystore;
 pub(crate) mod serde;
@@ -0,0 +1,23 @@
+use crate::{crypto::hash::Hash, interfaces::BlockDate};
+use serde::{Deserialize, Serialize};
+
+/// the usage of an address on the blockchain, as needed by the wallets to
+/// discover the addresses to restore.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AddressUsage {
+    /// the address, as given in the request
+    pub address: String,
+    /// `true` if the address appears in at least one transaction
+    pub used: bool,
+    /// the first block with a transaction involving the address
+    pub first_activity: Option<AddressActivityBlock>,
+    /// the last block with a transaction involving the address
+    pub last_activity: Option<AddressActivityBlock>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AddressActivityBlock {
+    pub block: Hash,
+    pub date: BlockDate,
+    pub chain_length: u32,
+}
@@ -0,0 +1,248 @@
+//! import of the initial fragments of the block0 from CSV files
+//!
+//! Large testnets may have thousands of initial funds or delegation
+//! certificates, which are unmanageable as YAML lists. These can be kept in
+//! CSV files instead, one entry per line after a header line:
+//!
+//! * initial funds: `address,value`;
+//! * initial certificates: `certificate`, the bech32 of a signed certificate.
+//!
+//! Empty lines and lines starting with `#` are ignored. Exact duplicates of
+//! a previous entry are dropped, conflicting entries (the same address with
+//! different values) are rejected.
+
+use crate::interfaces::{Address, Initial, InitialUTxO, SignedCertificate, Value};
+use chain_addr::Discrimination;
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    io::BufRead,
+};
+use thiserror::Error;
+
+pub const INITIAL_FUNDS_CSV_HEADER: &str = "address,value";
+pub const INITIAL_CERTIFICATES_CSV_HEADER: &str = "certificate";
+
+/// maximum number of outputs of an initial funds fragment
+pub const MAX_INITIAL_UTXOS_PER_FRAGMENT: usize = 254;
+
+#[derive(Debug, Error)]
+pub enum InitialCsvError {
+    #[error("cannot read the CSV file")]
+    Io(#[from] std::io::Error),
+    #[error("the CSV file is empty, expected the header '{expected}'")]
+    MissingHeader { expected: &'static str },
+    #[error("invalid CSV header '{found}', expected '{expected}'")]
+    InvalidHeader {
+        expected: &'static str,
+        found: String,
+    },
+    #[error("line {line}: expected {expected} fields, found {found}")]
+    InvalidFieldCount {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    #[error("line {line}: invalid address")]
+    InvalidAddress {
+        line: usize,
+        #[source]
+        source: chain_addr::Error,
+    },
+    #[error("line {line}: the address discrimination is {found}, expected {expected}")]
+    InvalidDiscrimination {
+        line: usize,
+        expected: Discrimination,
+        found: Discrimination,
+    },
+    #[error("line {line}: invalid value")]
+    InvalidValue {
+        line: usize,
+        #[source]
+        source: std::num::ParseIntError,
+    },
+    #[error("line {line}: the address was already given a different value on line {first_line}")]
+    ConflictingFunds { line: usize, first_line: usize },
+    #[error("line {line}: invalid signed certificate")]
+    InvalidCertificate {
+        line: usize,
+        #[source]
+        source: crate::interfaces::CertificateFromBech32Error,
+    },
+}
+
+/// read the initial funds from a CSV file, packed in as few fragments as
+/// possible.
+///
+/// All the addresses must have the given discrimination, which is the one
+/// of the blockchain the funds are for.
+pub fn initial_funds_from_csv<R: BufRead>(
+    reader: R,
+    discrimination: Discrimination,
+) -> Result<Vec<Initial>, InitialCsvError> {
+    let mut funds = Vec::new();
+    let mut seen: HashMap<Vec<u8>, (usize, Value)> = HashMap::new();
+
+    for record in records(reader, INITIAL_FUNDS_CSV_HEADER)? {
+        let (line, fields) = record?;
+        let address: Address = fields[0]
+            .parse()
+            .map_err(|source| InitialCsvError::InvalidAddress { line, source })?;
+        let value: Value = fields[1]
+            .parse()
+            .map_err(|source| InitialCsvError::InvalidValue { line, source })?;
+
+        let found = (address.1).0;
+        if found != discrimination {
+            return Err(InitialCsvError::InvalidDiscrimination {
+                line,
+                expected: discrimination,
+                found,
+            });
+        }
+
+        match seen.entry(address.1.to_bytes()) {
+            Entry::Occupied(entry) => {
+                let (first_line, first_value) = *entry.get();
+                if first_value != value {
+                    return Err(InitialCsvError::ConflictingFunds { line, first_line });
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert((line, value));
+                funds.push(InitialUTxO { address, value });
+            }
+        }
+    }
+
+    Ok(funds
+        .chunks(MAX_INITIAL_UTXOS_PER_FRAGMENT)
+        .map(|chunk| Initial::Fund(chunk.to_vec()))
+        .collect())
+}
+
+/// read the initial certificates from a CSV file, one fragment per
+/// certificate.
+pub fn initial_certificates_from_csv<R: BufRead>(
+    reader: R,
+) -> Result<Vec<Initial>, InitialCsvError> {
+    let mut certificates = Vec::new();
+    let mut seen = HashSet::new();
+
+    for record in records(reader, INITIAL_CERTIFICATES_CSV_HEADER)? {
+        let (line, fields) = record?;
+        let certificate = SignedCertificate::from_bech32(&fields[0])
+            .map_err(|source| InitialCsvError::InvalidCertificate { line, source })?;
+        if seen.insert(fields[0].clone()) {
+            certificates.push(Initial::Cert(certificate));
+        }
+    }
+
+    Ok(certificates)
+}
+
+/// the fields of the records of the CSV file, with their line numbers
+fn records<R: BufRead>(
+    reader: R,
+    header: &'static str,
+) -> Result<impl Iterator<Item = Result<(usize, Vec<String>), InitialCsvError>>, InitialCsvError> {
+    let expected_fields = header.split(',').count();
+    let mut lines = reader
+        .lines()
+        .enumerate()
+        .map(|(index, line)| line.map(|line| (index + 1, line)))
+        .filter(|line| match line {
+            Ok((_, line)) => {
+                let line = line.trim();
+                !line.is_empty() && !line.starts_with('#')
+            }
+            Err(_) => true,
+        });
+
+    match lines.next() {
+        None => return Err(InitialCsvError::MissingHeader { expected: header }),
+        Some(line) => {
+            let (_, found) = line?;
+            let found: String = found.split_whitespace().collect();
+            if found != header {
+                return Err(InitialCsvError::InvalidHeader {
+                    expected: header,
+                    found,
+                });
+            }
+        }
+    }
+
+    Ok(lines.map(move |line| {
+        let (line, content) = line?;
+        let fields: Vec<String> = content
+            .split(',')
+            .map(|field| field.trim().to_string())
+            .collect();
+        if fields.len() != expected_fields {
+            return Err(InitialCsvError::InvalidFieldCount {
+                line,
+                expected: expected_fields,
+                found: fields.len(),
+            });
+        }
+        Ok((line, fields))
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chain_addr::Kind;
+    use chain_crypto::{Ed25519, SecretKey};
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng as _;
+
+    fn address(seed: u8, discrimination: Discrimination) -> Address {
+        let sk: SecretKey<Ed25519> = SecretKey::generate(&mut ChaChaRng::from_seed([seed; 32]));
+        chain_addr::Address(discrimination, Kind::Account(sk.to_public())).into()
+    }
+
+    #[test]
+    fn funds_are_deduplicated_and_chunked() {
+        let mut csv = String::from("address, value\n# a comment\n\n");
+        for seed in 0..=255u8 {
+            csv.push_str(&format!("{},{}\n", address(seed, Discrimination::Test), 10));
+        }
+        csv.push_str(&format!("{},10\n", address(0, Discrimination::Test)));
+
+        let initial = initial_funds_from_csv(csv.as_bytes(), Discrimination::Test).unwrap();
+        let sizes: Vec<usize> = initial
+            .iter()
+            .map(|initial| match initial {
+                Initial::Fund(funds) => funds.len(),
+                _ => panic!("expected initial funds"),
+            })
+            .collect();
+        assert_eq!(sizes, vec![MAX_INITIAL_UTXOS_PER_FRAGMENT, 2]);
+    }
+
+    #[test]
+    fn conflicting_funds_are_rejected() {
+        let address = address(0, Discrimination::Test);
+        let csv = format!("address,value\n{},10\n{},20\n", address, address);
+        match initial_funds_from_csv(csv.as_bytes(), Discrimination::Test) {
+            Err(InitialCsvError::ConflictingFunds {
+                line: 3,
+                first_line: 2,
+            }) => (),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn funds_with_wrong_discrimination_are_rejected() {
+        let csv = format!(
+            "address,value\n{},10\n",
+            address(0, Discrimination::Production)
+        );
+        assert!(matches!(
+            initial_funds_from_csv(csv.as_bytes(), Discrimination::Test),
+            Err(InitialCsvError::InvalidDiscrimination { line: 2, .. })
+        ));
+    }
+}
@@ -4,6 +4,7 @@ mod default_values;
 mod epoch_stability_depth;
 mod fees_go_to;
 mod initial_config;
+mod initial_csv;
 mod initial_fragment;
 mod kes_update_speed;
 mod leader_id;
@@ -17,6 +18,10 @@ pub use self::default_values::*;
 pub use self::epoch_stability_depth::EpochStabilityDepth;
 pub use self::fees_go_to::FeesGoTo;
 pub use self::initial_config::BlockchainConfiguration;
+pub use self::initial_csv::{
+    initial_certificates_from_csv, initial_funds_from_csv, InitialCsvError,
+    INITIAL_CERTIFICATES_CSV_HEADER, INITIAL_FUNDS_CSV_HEADER, MAX_INITIAL_UTXOS_PER_FRAGMENT,
+};
 pub use self::initial_fragment::{
     try_initials_vec_from_messages, Initial, InitialUTxO, LegacyUTxO,
 };
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 pub struct PoolMaxEntries(usize);
@@ -15,6 +16,61 @@ pub struct Mempool {
     /// maximum number of entries in the fragment logs
     #[serde(default)]
     pub log_max_entries: LogMaxEntries,
+    /// maximum number of entries in the mempool from a single origin
+    /// (the REST interface or a given network peer)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin_max_entries: Option<usize>,
+    /// number of epochs the ids of the fragments added in a block are kept
+    /// for, to refuse their re-submissions as already confirmed. The ids
+    /// are not kept if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirmed_fragments_epochs: Option<u32>,
+    /// limits per fragment type applied when selecting the content of a block
+    #[serde(default)]
+    pub selection_quotas: SelectionQuotas,
+}
+
+/// the types of fragments the selection quotas can be set for
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FragmentType {
+    Transaction,
+    OwnerStakeDelegation,
+    StakeDelegation,
+    PoolRegistration,
+    PoolRetirement,
+    PoolUpdate,
+    VotePlan,
+    VoteCast,
+    VoteTally,
+    EncryptedVoteTally,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SelectionQuotas {
+    /// maximum number of fragments of the given type in a block
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub max_count: BTreeMap<FragmentType, u32>,
+    /// size (in bytes) of the block content reserved for the fragments of the
+    /// given type, fragments of other types cannot use this space
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub reserved_size: BTreeMap<FragmentType, u32>,
+    /// size (in bytes) the content of the blocks produced by the node may
+    /// not exceed, below the limit of the protocol. The fragments larger
+    /// than this are not accepted in the mempool nor relayed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_content_size: Option<u32>,
+}
+
+impl SelectionQuotas {
+    /// the size the content of a block produced by the node may not exceed:
+    /// the limit of the protocol, lowered by the cap of the node if any. A
+    /// cap above the limit of the protocol has no effect.
+    pub fn max_content_size(&self, protocol_max_size: u32) -> u32 {
+        self.max_content_size
+            .map_or(protocol_max_size, |cap| cap.min(protocol_max_size))
+    }
 }
 
 impl Default for PoolMaxEntries {
@@ -34,6 +90,9 @@ impl Default for Mempool {
         Mempool {
             pool_max_entries: PoolMaxEntries::default(),
             log_max_entries: LogMaxEntries::default(),
+            origin_max_entries: None,
+            confirmed_fragments_epochs: None,
+            selection_quotas: SelectionQuotas::default(),
         }
     }
 }
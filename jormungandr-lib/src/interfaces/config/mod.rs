@@ -4,9 +4,12 @@ mod node;
 mod secret;
 
 pub use log::{Log, LogEntry, LogOutput};
-pub use mempool::{LogMaxEntries, Mempool, PoolMaxEntries};
+pub use mempool::{FragmentType, LogMaxEntries, Mempool, PoolMaxEntries, SelectionQuotas};
 pub use node::{
-    Cors, Explorer, LayersConfig, NodeConfig, P2p, Policy, PreferredListConfig, Rest, Tls,
-    TopicsOfInterest, TrustedPeer,
+    Backup, ClientAuth, ClockCheck, Cors, EpochHooks, Explorer, HandshakeNonces, LayersConfig,
+    Leadership, Listen, MethodLimit, MethodLimits, NodeConfig, OverflowPolicies, OverflowPolicy,
+    P2p, PeerSelectionStrategy, Policy, PortMapping, PortMappingProtocol, PreferredListConfig,
+    PreferredViewMax, Rest, RewardsHistory, StreamLimits, Tls, TopicsOfInterest, Tracing,
+    TrustedPeer, UnixSocket, VirtualHost, VotePlanStatsConfig, NODE_CONFIG_VERSION,
 };
 pub use secret::{Bft, GenesisPraos, NodeSecret};
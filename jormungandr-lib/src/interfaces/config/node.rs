@@ -1,12 +1,23 @@
 #![allow(deprecated)]
 use crate::{
+    crypto::{hash::Hash, key::Identifier},
     interfaces::{Log, Mempool},
     time::Duration,
 };
+use chain_crypto::Ed25519;
 use serde::{de::Visitor, Deserialize, Deserializer, Serialize};
-use std::{fmt, net::SocketAddr, path::PathBuf, str::FromStr};
+use std::{
+    fmt,
+    net::{Ipv4Addr, SocketAddr},
+    path::PathBuf,
+    str::FromStr,
+};
 const DEFAULT_PREFERRED_VIEW_MAX: usize = 20;
 
+/// the release of the node whose configuration is described by `NodeConfig`,
+/// the node and this crate being released together
+pub const NODE_CONFIG_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
 pub struct Rest {
@@ -17,6 +28,50 @@ pub struct Rest {
     /// Enables CORS if provided
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cors: Option<Cors>,
+    /// Bearer token required by the administrative endpoints,
+    /// these endpoints are disabled if not provided
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub admin_token: Option<String>,
+    /// Binds the listening socket with `SO_REUSEPORT`, so the node can be
+    /// restarted while the previous instance is still shutting down
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub reuse_port: bool,
+    /// Serves the API on a Unix domain socket too, or only there if
+    /// the TCP socket is disabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unix_socket: Option<UnixSocket>,
+    /// Routes the requests of this node when several nodes are served
+    /// behind the same load balancer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub virtual_host: Option<VirtualHost>,
+    /// Maximum number of addresses looked up by a single request to the
+    /// address index, 1000 if not provided
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_addresses_per_request: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct UnixSocket {
+    /// Path of the socket, any file left at this path by a previous run
+    /// is replaced
+    pub path: PathBuf,
+    /// Does not serve the API on the `listen` address
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub disable_tcp: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct VirtualHost {
+    /// Only serves the requests whose `Host` header, or `:authority` with
+    /// HTTP/2, names this host, the port excluded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    /// Serves the API under this path, e.g. `node-1` to serve
+    /// `/node-1/api/v0/node/stats`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_prefix: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -98,12 +153,18 @@ pub struct P2p {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub listen_address: Option<poldercast::Address>,
 
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub listen: Vec<Listen>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_connections: Option<u32>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_inbound_connections: Option<u32>,
 
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_connections_threshold: Option<u32>,
+
     pub allow_private_addresses: bool,
 
     pub topics_of_interest: Option<TopicsOfInterest>,
@@ -112,6 +173,159 @@ pub struct P2p {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub layers: Option<LayersConfig>,
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub reuse_port: bool,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_server_requests: Option<usize>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_client_requests: Option<usize>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method_limits: Option<MethodLimits>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_timeout: Option<Duration>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_cache_size: Option<usize>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_unreachable_nodes_to_connect_per_event: Option<usize>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gossip_interval: Option<Duration>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gossip_fanout: Option<usize>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topology_force_reset_interval: Option<Duration>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bootstrap_attempts: Option<usize>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream_limits: Option<StreamLimits>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub handshake_nonces: Option<HandshakeNonces>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overflow_policies: Option<OverflowPolicies>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_fetch_strategy: Option<PeerSelectionStrategy>,
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub mempool_warmup: bool,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port_mapping: Option<PortMapping>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_auth: Option<ClientAuth>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Listen {
+    pub address: poldercast::Address,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub advertise: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MethodLimits {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pull_blocks: Option<MethodLimit>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub get_blocks: Option<MethodLimit>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub push_headers: Option<MethodLimit>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MethodLimit {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OverflowPolicies {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fragments: Option<OverflowPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gossip: Option<OverflowPolicy>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    Disconnect,
+    DropNewest,
+    DropOldest,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PeerSelectionStrategy {
+    MostRecent,
+    Random,
+    RoundRobin,
+    Latency,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PortMapping {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<PortMappingProtocol>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lease_duration: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gateway: Option<Ipv4Addr>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PortMappingProtocol {
+    Upnp,
+    NatPmp,
+    Any,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClientAuth {
+    pub trusted_node_ids: Vec<Identifier<Ed25519>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StreamLimits {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_headers: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_blocks: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_block_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HandshakeNonces {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expiry: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_pending: Option<usize>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -120,10 +334,14 @@ pub struct TopicsOfInterest {
     pub blocks: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Policy {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub quarantine_duration: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_quarantine: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_num_quarantine_records: Option<usize>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub quarantine_whitelist: Option<Vec<poldercast::Address>>,
 }
@@ -149,6 +367,12 @@ impl Default for PreferredViewMax {
     }
 }
 
+impl From<usize> for PreferredViewMax {
+    fn from(view_max: usize) -> Self {
+        Self(view_max)
+    }
+}
+
 impl From<PreferredViewMax> for usize {
     fn from(pvm: PreferredViewMax) -> Self {
         pvm.0
@@ -176,17 +400,97 @@ pub struct TrustedPeer {
     pub id: Option<poldercast::Id>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Leadership {
+    pub logs_capacity: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signed_slots_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Tracing {
+    pub otlp_endpoint: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sampling_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Backup {
+    pub directory: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub every_epochs: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RewardsHistory {
+    pub retention: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VotePlanStatsConfig {
+    pub samples: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EpochHooks {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClockCheck {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ntp_server: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_skew: Option<Duration>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct NodeConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub storage: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub genesis_block_hash: Option<Hash>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub namespace_by_genesis: bool,
     pub rest: Rest,
     pub p2p: P2p,
     pub log: Option<Log>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tracing: Option<Tracing>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leadership: Option<Leadership>,
     pub explorer: Explorer,
     pub mempool: Option<Mempool>,
     pub bootstrap_from_trusted_peers: Option<bool>,
     pub skip_bootstrap: Option<bool>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub http_fetch_block0_service: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_blockchain_updates_warning_interval: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup: Option<Backup>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rewards_history: Option<RewardsHistory>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vote_plan_stats: Option<VotePlanStatsConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epoch_hooks: Option<EpochHooks>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clock_check: Option<ClockCheck>,
 }
 
 impl P2p {
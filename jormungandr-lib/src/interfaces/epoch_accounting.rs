@@ -0,0 +1,69 @@
+use crate::interfaces::{EpochRewardsInfo, Value};
+use chain_impl_mockchain::block::Epoch;
+use serde::{Deserialize, Serialize};
+
+/// Flow of the value at the transition to an epoch: what entered the
+/// reward pot and where it went.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EpochAccounting {
+    epoch: Epoch,
+    /// fees collected during the previous epoch
+    fees: Value,
+    /// value drawn from the reward reserve
+    drawn: Value,
+    /// cut of the rewards sent to the treasury
+    treasury: Value,
+    /// rewards distributed to the stake pools and their delegators
+    distributed: Value,
+    /// value of the reward pot not credited anywhere, e.g. rounding leftovers
+    burned: Value,
+}
+
+impl EpochAccounting {
+    pub fn epoch(&self) -> Epoch {
+        self.epoch
+    }
+
+    pub fn fees(&self) -> Value {
+        self.fees
+    }
+
+    pub fn drawn(&self) -> Value {
+        self.drawn
+    }
+
+    pub fn treasury(&self) -> Value {
+        self.treasury
+    }
+
+    pub fn distributed(&self) -> Value {
+        self.distributed
+    }
+
+    pub fn burned(&self) -> Value {
+        self.burned
+    }
+}
+
+impl From<&EpochRewardsInfo> for EpochAccounting {
+    fn from(info: &EpochRewardsInfo) -> Self {
+        let lovelaces = |value: Value| -> u64 { value.into() };
+        let distributed = info
+            .stake_pools()
+            .values()
+            .map(|(taxed, distributed)| lovelaces(*taxed).saturating_add(lovelaces(*distributed)))
+            .fold(0, u64::saturating_add);
+        let burned = lovelaces(info.drawn().saturating_add(info.fees()))
+            .saturating_sub(lovelaces(info.treasury()))
+            .saturating_sub(distributed);
+
+        Self {
+            epoch: info.epoch(),
+            fees: info.fees(),
+            drawn: info.drawn(),
+            treasury: info.treasury(),
+            distributed: distributed.into(),
+            burned: burned.into(),
+        }
+    }
+}
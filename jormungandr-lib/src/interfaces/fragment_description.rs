@@ -0,0 +1,187 @@
+use crate::{
+    crypto::hash::Hash,
+    interfaces::{Certificate, TransactionInput, TransactionOutput, TransactionWitness, Value},
+};
+use chain_core::property::Fragment as _;
+use chain_impl_mockchain::{certificate, fragment::Fragment, transaction::Transaction};
+use serde::{Deserialize, Serialize};
+
+/// human readable description of a fragment, e.g. to inspect the
+/// fragments rejected by a node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FragmentDescription {
+    pub id: Hash,
+    /// the type of the fragment, e.g. `Transaction` or `PoolRegistration`
+    #[serde(rename = "type")]
+    pub fragment_type: String,
+    pub inputs: Vec<TransactionInput>,
+    pub outputs: Vec<TransactionOutput>,
+    pub witnesses: Vec<TransactionWitness>,
+    pub certificate: Option<Certificate>,
+    /// the difference between the inputs and the outputs of a transaction.
+    /// Not set if the outputs exceed the inputs.
+    pub fee: Option<Value>,
+}
+
+impl FragmentDescription {
+    fn new(fragment: &Fragment, fragment_type: &str) -> Self {
+        FragmentDescription {
+            id: fragment.id().into(),
+            fragment_type: fragment_type.to_owned(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            witnesses: Vec::new(),
+            certificate: None,
+            fee: None,
+        }
+    }
+
+    fn with_transaction<P>(
+        mut self,
+        tx: &Transaction<P>,
+        certificate: Option<certificate::Certificate>,
+    ) -> Self {
+        let slice = tx.as_slice();
+        self.inputs = slice.inputs().iter().map(Into::into).collect();
+        self.outputs = slice.outputs().iter().map(Into::into).collect();
+        self.witnesses = slice.witnesses().iter().map(Into::into).collect();
+        self.certificate = certificate.map(Into::into);
+        self.fee = match (tx.total_input(), tx.total_output()) {
+            (Ok(input), Ok(output)) => (input - output).ok().map(Into::into),
+            _ => None,
+        };
+        self
+    }
+}
+
+impl From<&Fragment> for FragmentDescription {
+    fn from(fragment: &Fragment) -> Self {
+        match fragment {
+            Fragment::Initial(_) => Self::new(fragment, "Initial"),
+            Fragment::OldUtxoDeclaration(_) => Self::new(fragment, "OldUtxoDeclaration"),
+            Fragment::UpdateProposal(_) => Self::new(fragment, "UpdateProposal"),
+            Fragment::UpdateVote(_) => Self::new(fragment, "UpdateVote"),
+            Fragment::Transaction(tx) => {
+                Self::new(fragment, "Transaction").with_transaction(tx, None)
+            }
+            Fragment::OwnerStakeDelegation(tx) => Self::new(fragment, "OwnerStakeDelegation")
+                .with_transaction(
+                    tx,
+                    Some(certificate::Certificate::OwnerStakeDelegation(
+                        tx.as_slice().payload().into_payload(),
+                    )),
+                ),
+            Fragment::StakeDelegation(tx) => Self::new(fragment, "StakeDelegation")
+                .with_transaction(
+                    tx,
+                    Some(certificate::Certificate::StakeDelegation(
+                        tx.as_slice().payload().into_payload(),
+                    )),
+                ),
+            Fragment::PoolRegistration(tx) => Self::new(fragment, "PoolRegistration")
+                .with_transaction(
+                    tx,
+                    Some(certificate::Certificate::PoolRegistration(
+                        tx.as_slice().payload().into_payload(),
+                    )),
+                ),
+            Fragment::PoolRetirement(tx) => Self::new(fragment, "PoolRetirement").with_transaction(
+                tx,
+                Some(certificate::Certificate::PoolRetirement(
+                    tx.as_slice().payload().into_payload(),
+                )),
+            ),
+            Fragment::PoolUpdate(tx) => Self::new(fragment, "PoolUpdate").with_transaction(
+                tx,
+                Some(certificate::Certificate::PoolUpdate(
+                    tx.as_slice().payload().into_payload(),
+                )),
+            ),
+            Fragment::VotePlan(tx) => Self::new(fragment, "VotePlan").with_transaction(
+                tx,
+                Some(certificate::Certificate::VotePlan(
+                    tx.as_slice().payload().into_payload(),
+                )),
+            ),
+            Fragment::VoteCast(tx) => Self::new(fragment, "VoteCast").with_transaction(
+                tx,
+                Some(certificate::Certificate::VoteCast(
+                    tx.as_slice().payload().into_payload(),
+                )),
+            ),
+            Fragment::VoteTally(tx) => Self::new(fragment, "VoteTally").with_transaction(
+                tx,
+                Some(certificate::Certificate::VoteTally(
+                    tx.as_slice().payload().into_payload(),
+                )),
+            ),
+            Fragment::EncryptedVoteTally(tx) => Self::new(fragment, "EncryptedVoteTally")
+                .with_transaction(
+                    tx,
+                    Some(certificate::Certificate::EncryptedVoteTally(
+                        tx.as_slice().payload().into_payload(),
+                    )),
+                ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_addr::{Address, Discrimination, Kind};
+    use chain_crypto::{Ed25519, SecretKey};
+    use chain_impl_mockchain::{
+        account::SpendingCounter,
+        header::HeaderId,
+        transaction::{Input, Output, TxBuilder, Witness},
+        value,
+    };
+
+    fn transaction(input: u64, output: u64) -> Fragment {
+        let key = SecretKey::<Ed25519>::generate(rand::thread_rng());
+        let input = Input::from_account_public_key(key.to_public(), value::Value(input));
+        let output = Output::from_address(
+            Address(Discrimination::Test, Kind::Account(key.to_public())),
+            value::Value(output),
+        );
+        let builder = TxBuilder::new()
+            .set_nopayload()
+            .set_ios(&[input], &[output]);
+        let sign_data_hash = builder.get_auth_data_for_witness().hash();
+        let witness = Witness::new_account(
+            &HeaderId::zero_hash(),
+            &sign_data_hash,
+            SpendingCounter::from(0),
+            |data| key.sign(data),
+        );
+        Fragment::Transaction(builder.set_witnesses(&[witness]).set_payload_auth(&()))
+    }
+
+    #[test]
+    fn transactions_are_described_with_their_fee() {
+        let fragment = transaction(15, 10);
+        let description = FragmentDescription::from(&fragment);
+
+        assert_eq!(description.id, fragment.id().into());
+        assert_eq!(description.fragment_type, "Transaction");
+        assert_eq!(description.inputs.len(), 1);
+        assert_eq!(description.outputs.len(), 1);
+        assert_eq!(description.witnesses.len(), 1);
+        assert!(description.certificate.is_none());
+        assert_eq!(description.fee, Some(5.into()));
+    }
+
+    #[test]
+    fn no_fee_is_given_when_the_outputs_exceed_the_inputs() {
+        let description = FragmentDescription::from(&transaction(10, 15));
+        assert_eq!(description.fee, None);
+    }
+
+    #[test]
+    fn descriptions_are_serialized_with_a_type_field() {
+        let json = serde_json::to_value(FragmentDescription::from(&transaction(15, 10))).unwrap();
+        assert_eq!(json["type"], "Transaction");
+        assert_eq!(json["fee"], 5);
+    }
+}
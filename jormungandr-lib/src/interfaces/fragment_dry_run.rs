@@ -0,0 +1,41 @@
+//! outcome of applying a fragment to a copy of the ledger of the tip of a
+//! node, without submitting it to the mempool
+
+use crate::{
+    crypto::hash::Hash,
+    interfaces::{AccountIdentifier, BlockDate, Value},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FragmentDryRun {
+    pub fragment_id: Hash,
+    /// the tip whose ledger the fragment was applied to
+    pub tip: Hash,
+    /// the date the fragment was applied at
+    pub date: BlockDate,
+    pub outcome: DryRunOutcome,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DryRunOutcome {
+    /// the fragment applies, with the balances of the accounts it spends
+    /// from or pays to
+    Applied { balances: Vec<AccountBalanceChange> },
+    /// the ledger refuses the fragment
+    Rejected {
+        reason: String,
+        /// the error variant with its parameters
+        ledger_error: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountBalanceChange {
+    pub account: AccountIdentifier,
+    /// the balance before the fragment, `None` if the account does not exist
+    pub before: Option<Value>,
+    /// the balance after the fragment, `None` if the account does not exist
+    pub after: Option<Value>,
+}
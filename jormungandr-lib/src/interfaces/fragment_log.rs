@@ -1,9 +1,11 @@
 use crate::{crypto::hash::Hash, interfaces::BlockDate, time::SystemTime};
 use chain_impl_mockchain::key;
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
 
 /// identify the source of a fragment
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(from = "FragmentOriginDef")]
 pub enum FragmentOrigin {
     /// trace back the origin of a fragment to a given
     /// network node. This will allow tracking back the
@@ -11,22 +13,90 @@ pub enum FragmentOrigin {
     /// the senders from sending us more fragment (in case
     /// they are invalids or so)
     ///
-    /// TODO: add the network identifier/IP Address
-    Network,
+    /// The address is not known if the fragment was first
+    /// seen in a block received from the network.
+    Network { addr: Option<SocketAddr> },
     /// This marks the fragment is coming from the REST interface
     /// (a client wallet or another service).
     Rest,
 }
 
+/// the origins as serialized by the nodes which did not record the address
+/// of the peers are still accepted, `Network` being a plain string then
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FragmentOriginDef {
+    Current(CurrentFragmentOrigin),
+    Legacy(LegacyFragmentOrigin),
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "FragmentOrigin")]
+enum CurrentFragmentOrigin {
+    Network { addr: Option<SocketAddr> },
+    Rest,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "FragmentOrigin")]
+enum LegacyFragmentOrigin {
+    Network,
+}
+
+impl From<FragmentOriginDef> for FragmentOrigin {
+    fn from(origin: FragmentOriginDef) -> Self {
+        match origin {
+            FragmentOriginDef::Current(CurrentFragmentOrigin::Network { addr }) => {
+                FragmentOrigin::Network { addr }
+            }
+            FragmentOriginDef::Current(CurrentFragmentOrigin::Rest) => FragmentOrigin::Rest,
+            FragmentOriginDef::Legacy(LegacyFragmentOrigin::Network) => {
+                FragmentOrigin::Network { addr: None }
+            }
+        }
+    }
+}
+
 /// status of the fragment within the blockchain or the pool
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum FragmentStatus {
     /// the fragment is yet to be processed
     Pending,
     /// the fragment has been rejected and won't be added in a block
-    Rejected { reason: String },
+    Rejected {
+        reason: String,
+        /// the error of the ledger the fragment failed to apply with, with
+        /// its parameters, if it was rejected by the ledger
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ledger_error: Option<String>,
+    },
     /// The fragment has been added in a block
     InABlock { date: BlockDate, block: Hash },
+    /// the fragment was submitted again after it had been added in a block,
+    /// and was refused: it is in the given block
+    AlreadyConfirmed { date: BlockDate, block: Hash },
+}
+
+/// a fragment with its status and, once it is in a block, its position
+/// in the blockchain
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FragmentDetails {
+    pub id: Hash,
+    pub status: FragmentStatus,
+    /// the hex-encoded fragment, if the node still has it: the rejected
+    /// fragments are not kept
+    pub fragment: Option<String>,
+    /// the block containing the fragment, if it is in a block
+    pub block: Option<FragmentBlockContext>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FragmentBlockContext {
+    pub block: Hash,
+    pub date: BlockDate,
+    pub chain_length: u32,
+    /// the index of the fragment among the contents of the block
+    pub index: u32,
 }
 
 /// the log associated to a given fragment
@@ -54,6 +124,11 @@ impl FragmentStatus {
     pub fn is_in_a_block(&self) -> bool {
         matches!(self, FragmentStatus::InABlock { .. })
     }
+
+    #[inline]
+    pub fn is_already_confirmed(&self) -> bool {
+        matches!(self, FragmentStatus::AlreadyConfirmed { .. })
+    }
 }
 
 impl FragmentLog {
@@ -84,6 +159,11 @@ impl FragmentLog {
         self.status().is_in_a_block()
     }
 
+    #[inline]
+    pub fn is_already_confirmed(&self) -> bool {
+        self.status().is_already_confirmed()
+    }
+
     /// set the new status
     #[inline]
     pub fn modify(&mut self, new_status: FragmentStatus) {
@@ -116,3 +196,31 @@ impl FragmentLog {
         &self.status
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize_fragment_origin() {
+        let addr = "127.0.0.1:3000".parse().unwrap();
+        for (yaml, origin) in vec![
+            ("Rest", FragmentOrigin::Rest),
+            ("Network", FragmentOrigin::Network { addr: None }),
+            (
+                "Network:\n  addr: ~",
+                FragmentOrigin::Network { addr: None },
+            ),
+            (
+                "Network:\n  addr: 127.0.0.1:3000",
+                FragmentOrigin::Network { addr: Some(addr) },
+            ),
+        ] {
+            let deserialized: FragmentOrigin = serde_yaml::from_str(yaml).unwrap();
+            assert_eq!(deserialized, origin);
+            let serialized = serde_yaml::to_string(&origin).unwrap();
+            let deserialized: FragmentOrigin = serde_yaml::from_str(&serialized).unwrap();
+            assert_eq!(deserialized, origin);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! receipt of a fragment accepted in the mempool of a node
+//!
+//! The receipt is signed with the key identifying the node in the p2p
+//! network, so the submitter of a fragment can later show which node
+//! accepted it, when, and on top of which tip.
+
+use crate::{
+    crypto::{
+        hash::Hash,
+        key::{Identifier, Signature, SigningKey},
+    },
+    time::SystemTime,
+};
+use chain_crypto::{Ed25519, Verification};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// prefix of the signed data, so the signature cannot be mistaken for the
+/// signature of something else made with the node key
+const SIGNATURE_DOMAIN: &[u8] = b"jormungandr fragment receipt";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FragmentReceipt {
+    fragment_id: Hash,
+    node_id: Identifier<Ed25519>,
+    accepted_at: SystemTime,
+    tip: Hash,
+    signature: Signature<Vec<u8>, Ed25519>,
+}
+
+impl FragmentReceipt {
+    /// a receipt of the fragment accepted now, on top of the given tip.
+    /// The time is truncated to the second, so it survives the text
+    /// serialization of the receipt unchanged.
+    pub fn new(fragment_id: Hash, tip: Hash, node_key: &SigningKey<Ed25519>) -> Self {
+        let secs = std::time::SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since_epoch| since_epoch.as_secs())
+            .unwrap_or(0);
+        let accepted_at = SystemTime::from(UNIX_EPOCH + Duration::from_secs(secs));
+        let node_id = node_key.identifier();
+        let signature = node_key.sign(&signed_data(&fragment_id, &node_id, &accepted_at, &tip));
+        FragmentReceipt {
+            fragment_id,
+            node_id,
+            accepted_at,
+            tip,
+            signature,
+        }
+    }
+
+    pub fn fragment_id(&self) -> &Hash {
+        &self.fragment_id
+    }
+
+    /// the identifier of the node which accepted the fragment
+    pub fn node_id(&self) -> &Identifier<Ed25519> {
+        &self.node_id
+    }
+
+    pub fn accepted_at(&self) -> &SystemTime {
+        &self.accepted_at
+    }
+
+    /// the tip of the node when it accepted the fragment
+    pub fn tip(&self) -> &Hash {
+        &self.tip
+    }
+
+    /// check the receipt is signed by the node it names
+    pub fn verify(&self) -> bool {
+        let data = signed_data(
+            &self.fragment_id,
+            &self.node_id,
+            &self.accepted_at,
+            &self.tip,
+        );
+        self.signature.verify(&self.node_id, &data) == Verification::Success
+    }
+}
+
+fn signed_data(
+    fragment_id: &Hash,
+    node_id: &Identifier<Ed25519>,
+    accepted_at: &SystemTime,
+    tip: &Hash,
+) -> Vec<u8> {
+    let secs = accepted_at
+        .as_ref()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or(0);
+
+    let mut data = SIGNATURE_DOMAIN.to_vec();
+    data.extend_from_slice(&<[u8; 32]>::from(*fragment_id));
+    data.extend_from_slice(node_id.as_ref().as_ref());
+    data.extend_from_slice(&secs.to_be_bytes());
+    data.extend_from_slice(&<[u8; 32]>::from(*tip));
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_receipt(key: &SigningKey<Ed25519>) -> FragmentReceipt {
+        FragmentReceipt::new(Hash::from([1; 32]), Hash::from([2; 32]), key)
+    }
+
+    #[test]
+    fn receipt_survives_serialization() {
+        let key = SigningKey::generate(rand::thread_rng());
+        let receipt = new_receipt(&key);
+        assert!(receipt.verify());
+
+        let json = serde_json::to_string(&receipt).unwrap();
+        let decoded: FragmentReceipt = serde_json::from_str(&json).unwrap();
+        assert!(decoded.verify());
+        assert_eq!(decoded.fragment_id(), receipt.fragment_id());
+        assert_eq!(decoded.accepted_at(), receipt.accepted_at());
+    }
+
+    #[test]
+    fn tampered_receipt_is_rejected() {
+        let key = SigningKey::generate(rand::thread_rng());
+        let mut receipt = new_receipt(&key);
+        receipt.tip = Hash::from([3; 32]);
+        assert!(!receipt.verify());
+
+        let other_key = SigningKey::generate(rand::thread_rng());
+        let mut receipt = new_receipt(&key);
+        receipt.node_id = other_key.identifier();
+        assert!(!receipt.verify());
+    }
+}
@@ -1,12 +1,17 @@
 mod account_identifier;
 mod account_state;
 mod address;
+mod address_usage;
 mod block0_configuration;
 mod blockdate;
 mod certificate;
 mod committee;
 mod config;
+mod epoch_accounting;
+mod fragment_description;
+mod fragment_dry_run;
 mod fragment_log;
+mod fragment_receipt;
 mod leadership_log;
 mod linear_fee;
 mod old_address;
@@ -30,6 +35,7 @@ mod vote;
 pub use self::account_identifier::AccountIdentifier;
 pub use self::account_state::AccountState;
 pub use self::address::Address;
+pub use self::address_usage::{AddressActivityBlock, AddressUsage};
 pub use self::block0_configuration::*;
 pub use self::blockdate::BlockDate;
 pub use self::certificate::{
@@ -38,23 +44,37 @@ pub use self::certificate::{
 };
 pub use self::committee::CommitteeIdDef;
 pub use self::config::*;
-pub use self::fragment_log::{FragmentLog, FragmentOrigin, FragmentStatus};
+pub use self::epoch_accounting::EpochAccounting;
+pub use self::fragment_description::FragmentDescription;
+pub use self::fragment_dry_run::{AccountBalanceChange, DryRunOutcome, FragmentDryRun};
+pub use self::fragment_log::{
+    FragmentBlockContext, FragmentDetails, FragmentLog, FragmentOrigin, FragmentStatus,
+};
+pub use self::fragment_receipt::FragmentReceipt;
 pub use self::leadership_log::{
     EnclaveLeaderId, LeadershipLog, LeadershipLogId, LeadershipLogStatus,
 };
 pub use self::linear_fee::LinearFeeDef;
 pub use self::old_address::OldAddress;
 pub use self::peer_stats::{
-    Info, Logs, PeerRecord, PeerStats, Profile, Record, Strike, Subscription, When,
+    GossipSettings, Info, Logs, NodeSelfView, PeerRecord, PeerStats, Profile, Record, Strike,
+    Subscription, When,
 };
 pub use self::ratio::{ParseRatioError, Ratio};
 pub use self::reward_parameters::RewardParams;
 pub use self::rewards_info::EpochRewardsInfo;
-pub use self::settings::{ParametersDef, RatioDef, SettingsDto, TaxTypeDef, TaxTypeSerde};
+pub use self::settings::{
+    CertificateFees, ParametersDef, RatioDef, SettingsDto, TaxTypeDef, TaxTypeSerde,
+};
 pub use self::stake::{Stake, StakeDef};
-pub use self::stake_distribution::{StakeDistribution, StakeDistributionDto};
+pub use self::stake_distribution::{
+    PoolStakeSnapshot, StakeDistribution, StakeDistributionDto, StakeSnapshot,
+};
 pub use self::stake_pool_stats::{Rewards, StakePoolStats};
-pub use self::stats::{NodeState, NodeStats, NodeStatsDto};
+pub use self::stats::{
+    BlockPropagationStats, FirstAnnouncerCount, LatencyPercentiles, MempoolOriginUsage,
+    MempoolSummary, NodeState, NodeStats, NodeStatsDto, PortMappingStatus, SyncState,
+};
 pub use self::tax_type::TaxType;
 pub use self::transaction_input::{TransactionInput, TransactionInputType};
 pub use self::transaction_output::TransactionOutput;
@@ -62,6 +82,6 @@ pub use self::transaction_witness::TransactionWitness;
 pub use self::utxo_info::{UTxOInfo, UTxOOutputInfo};
 pub use self::value::{Value, ValueDef};
 pub use self::vote::{
-    Payload, Tally, TallyResult, VotePlanDef, VotePlanStatus, VoteProposalStatus,
-    MEMBER_PUBLIC_KEY_BECH32_HRP,
+    Payload, Tally, TallyResult, VoteCountSample, VotePlanDef, VotePlanStats, VotePlanStatus,
+    VoteProposalStatus, MEMBER_PUBLIC_KEY_BECH32_HRP,
 };
@@ -1,6 +1,6 @@
-use crate::time::{SecondsSinceUnixEpoch, SystemTime};
+use crate::time::{Duration, SecondsSinceUnixEpoch, SystemTime};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::net::SocketAddr;
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -11,6 +11,41 @@ pub struct PeerStats {
     pub last_block_received: Option<SystemTime>,
     pub last_fragment_received: Option<SystemTime>,
     pub last_gossip_received: Option<SystemTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// the number of items received from the peer which could not be
+    /// decoded, by error code
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub decode_errors: BTreeMap<String, u64>,
+}
+
+/// the gossip settings in effect in the node, after the defaults are
+/// applied to the configuration
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GossipSettings {
+    pub interval: Duration,
+    /// the most peers gossiped to at every interval, all the peers of the
+    /// topology view if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fanout: Option<usize>,
+    pub max_unreachable_nodes_to_connect_per_event: usize,
+    pub preferred_list_view_max: usize,
+}
+
+/// what the node tells the network about itself
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct NodeSelfView {
+    /// the hex-encoded id the node authenticates with
+    pub node_id: String,
+    /// the address advertised to the peers in the gossip, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// the interest level of the node in the topics it subscribes to
+    pub topics_of_interest: BTreeMap<String, String>,
+    /// the layers of the topology selecting the peers of the node
+    pub layers: Vec<String>,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
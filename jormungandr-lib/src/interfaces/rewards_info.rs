@@ -7,7 +7,7 @@ use chain_impl_mockchain::ledger::EpochRewardsInfo as EpochRewardsInfoStd;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct EpochRewardsInfo {
     epoch: Epoch,
     drawn: Value,
@@ -22,6 +22,18 @@ impl EpochRewardsInfo {
         self.epoch
     }
 
+    pub fn drawn(&self) -> Value {
+        self.drawn
+    }
+
+    pub fn fees(&self) -> Value {
+        self.fees
+    }
+
+    pub fn treasury(&self) -> Value {
+        self.treasury
+    }
+
     pub fn stake_pools(&self) -> &BTreeMap<Hash, (Value, Value)> {
         &self.stake_pools
     }
@@ -1,7 +1,8 @@
 use crate::{
-    interfaces::{LinearFeeDef, ValueDef},
+    interfaces::{ActiveSlotCoefficient, LinearFeeDef, ValueDef},
     time::SystemTime,
 };
+use chain_crypto::Blake2b256;
 use chain_impl_mockchain::block::Epoch;
 use chain_impl_mockchain::fee::LinearFee;
 use chain_impl_mockchain::rewards::{CompoundingType, Limit, Parameters, Ratio, TaxType};
@@ -26,6 +27,58 @@ pub struct SettingsDto {
     pub treasury_tax: TaxType,
     #[serde(with = "ParametersDef")]
     pub reward_params: Parameters,
+    /// `None` when the consensus is BFT
+    #[serde(default)]
+    pub active_slot_coefficient: Option<ActiveSlotCoefficient>,
+    /// the fee of each certificate type, as resolved from `fees`
+    #[serde(default)]
+    pub certificate_fees: CertificateFees,
+    /// changes whenever one of the parameters above changes, see
+    /// [`SettingsDto::compute_version`]
+    #[serde(default)]
+    pub settings_version: String,
+}
+
+impl SettingsDto {
+    /// the hash of the parameters of the blockchain, everything but the
+    /// current slot start time and the version itself
+    pub fn compute_version(&self) -> String {
+        let mut parameters = self.clone();
+        parameters.curr_slot_start_time = None;
+        parameters.settings_version = String::new();
+        let bytes = serde_json::to_vec(&parameters).expect("settings are always serializable");
+        Blake2b256::new(&bytes).to_string()
+    }
+}
+
+/// the fee of each certificate type, on top of the fee of the transaction
+/// carrying it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct CertificateFees {
+    pub stake_delegation: u64,
+    pub owner_stake_delegation: u64,
+    pub pool_registration: u64,
+    pub vote_plan: u64,
+    pub vote_cast: u64,
+    /// the fee of the other certificates
+    pub default: u64,
+}
+
+impl From<&LinearFee> for CertificateFees {
+    fn from(fees: &LinearFee) -> Self {
+        let or_default = |fee: Option<NonZeroU64>| fee.map_or(fees.certificate, NonZeroU64::get);
+        CertificateFees {
+            stake_delegation: or_default(fees.per_certificate_fees.certificate_stake_delegation),
+            owner_stake_delegation: or_default(
+                fees.per_certificate_fees.certificate_owner_stake_delegation,
+            ),
+            pool_registration: or_default(fees.per_certificate_fees.certificate_pool_registration),
+            vote_plan: or_default(fees.per_vote_certificate_fees.certificate_vote_plan),
+            vote_cast: or_default(fees.per_vote_certificate_fees.certificate_vote_cast),
+            default: fees.certificate,
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -93,5 +146,8 @@ impl PartialEq<SettingsDto> for SettingsDto {
             && self.slots_per_epoch == other.slots_per_epoch
             && self.treasury_tax == other.treasury_tax
             && self.reward_params == other.reward_params
+            && self.active_slot_coefficient == other.active_slot_coefficient
+            && self.certificate_fees == other.certificate_fees
+            && self.settings_version == other.settings_version
     }
 }
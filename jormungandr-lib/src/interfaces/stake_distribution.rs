@@ -1,4 +1,4 @@
-use crate::crypto::hash::Hash;
+use crate::crypto::{account::Identifier, hash::Hash};
 use crate::interfaces::stake::Stake;
 use serde::{Deserialize, Serialize};
 
@@ -16,3 +16,22 @@ pub struct StakeDistribution {
     pub unassigned: Stake,
     pub pools: Vec<(Hash, Stake)>,
 }
+
+/// stake of every delegator of the pools used for the leader election of
+/// an epoch
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StakeSnapshot {
+    pub epoch: u32,
+    pub dangling: Stake,
+    pub unassigned: Stake,
+    pub pools: Vec<PoolStakeSnapshot>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PoolStakeSnapshot {
+    pub id: Hash,
+    pub total: Stake,
+    pub delegators: Vec<(Identifier, Stake)>,
+}
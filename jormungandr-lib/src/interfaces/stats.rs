@@ -1,5 +1,9 @@
-use crate::time::SystemTime;
+use crate::{
+    interfaces::{FragmentOrigin, FragmentType},
+    time::SystemTime,
+};
 use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, net::SocketAddr};
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -15,6 +19,10 @@ pub struct NodeStatsDto {
 pub struct NodeStats {
     pub block_recv_cnt: u64,
     pub last_block_content_size: u32,
+    /// the size the content of the blocks produced by the node may not
+    /// exceed: the limit of the protocol, lowered by the cap of the node
+    #[serde(default)]
+    pub max_block_content_size: Option<u32>,
     pub last_block_date: Option<String>,
     pub last_block_fees: u64,
     pub last_block_hash: Option<String>,
@@ -30,6 +38,189 @@ pub struct NodeStats {
     pub peer_unreachable_cnt: usize,
     pub tx_recv_cnt: u64,
     pub uptime: Option<u64>,
+    #[serde(default)]
+    pub mempool_usage: Vec<MempoolOriginUsage>,
+    /// number of gossip entries received from the peers
+    #[serde(default)]
+    pub gossip_recv_cnt: u64,
+    /// number of received gossip entries dropped because they were
+    /// already received recently
+    #[serde(default)]
+    pub gossip_dup_cnt: u64,
+    /// number of requests from the peers currently being served
+    #[serde(default)]
+    pub server_requests_in_flight: usize,
+    /// number of requests to the peers made by the node to catch up
+    /// with the network currently in progress
+    #[serde(default)]
+    pub client_requests_in_flight: usize,
+    /// number of blocks served to the peers from the cache of the
+    /// recently served blocks
+    #[serde(default)]
+    pub block_cache_hit_cnt: u64,
+    /// number of blocks served to the peers which had to be read from
+    /// the storage
+    #[serde(default)]
+    pub block_cache_miss_cnt: u64,
+    /// how far the tip of the node is from the current slot
+    #[serde(default)]
+    pub sync_state: Option<SyncState>,
+    /// how quickly the blocks of the network reach the node
+    #[serde(default)]
+    pub block_propagation: Option<BlockPropagationStats>,
+    /// content of the mempool and outcome of the fragments received since
+    /// the node started
+    #[serde(default)]
+    pub mempool: Option<MempoolSummary>,
+    /// outcome of the mapping of the public port on the router, if enabled
+    #[serde(default)]
+    pub port_mapping: Option<PortMappingStatus>,
+    /// skew of the clock of the node in milliseconds, positive if it is
+    /// ahead, estimated from the delays between the start of the slot of
+    /// the blocks announced by the peers and their announcement: the
+    /// propagation of the blocks is included
+    #[serde(default)]
+    pub peer_clock_skew: Option<i64>,
+    /// skew of the clock of the node from the NTP server queried when the
+    /// node started, in milliseconds, positive if it is ahead
+    #[serde(default)]
+    pub ntp_clock_skew: Option<i64>,
+    /// delays between the start of the slot of the recent leader events
+    /// of the node and the wake-up of the leadership task for them
+    #[serde(default)]
+    pub leader_wake_delay: Option<LatencyPercentiles>,
+}
+
+/// outcome of the mapping of the public port of the node on the router of
+/// the local network
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct PortMappingStatus {
+    /// the protocol the port was last mapped with, `upnp` or `nat_pmp`
+    pub protocol: Option<String>,
+    /// the external address the router forwards to the node
+    pub external_address: Option<SocketAddr>,
+    /// the last time the mapping was made or renewed
+    pub mapped_at: Option<SystemTime>,
+    /// whether the external address reported by the router is the
+    /// public address of the node
+    pub public_address_matches: Option<bool>,
+    /// whether the node could connect to itself through the external
+    /// address
+    #[serde(default)]
+    pub reachable: Option<bool>,
+    /// the error of the last attempt, if it failed
+    pub last_error: Option<String>,
+}
+
+/// delays between the start of the slot of the blocks received from the
+/// network and their arrival at the node, over the most recent blocks
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct BlockPropagationStats {
+    /// delay until the block was received
+    pub arrival: LatencyPercentiles,
+    /// delay until the block became the tip of the node
+    pub adoption: LatencyPercentiles,
+    /// number of blocks each peer was the first to announce, most first
+    pub first_announcers: Vec<FirstAnnouncerCount>,
+}
+
+/// percentiles of a delay in milliseconds, `None` without any sample
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct LatencyPercentiles {
+    pub samples: usize,
+    pub p50: Option<u64>,
+    pub p90: Option<u64>,
+    pub p99: Option<u64>,
+}
+
+impl LatencyPercentiles {
+    /// compute the percentiles of the given delays, in milliseconds
+    pub fn from_samples(samples: &[u64]) -> Self {
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        let percentile = |p: usize| -> Option<u64> {
+            if sorted.is_empty() {
+                return None;
+            }
+            let rank = (p * sorted.len() + 99) / 100;
+            Some(sorted[rank.max(1) - 1])
+        };
+        LatencyPercentiles {
+            samples: sorted.len(),
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct FirstAnnouncerCount {
+    pub peer: String,
+    pub blocks: u64,
+}
+
+/// classification of the tip of the node against the slot of the wall
+/// clock
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+pub enum SyncState {
+    /// the node has not finished starting up
+    Bootstrapping,
+    /// the tip is behind the current slot, but blocks are still being
+    /// received
+    CatchingUp {
+        /// the number of slots between the tip and the current slot
+        behind_by: u64,
+    },
+    /// the tip is close enough to the current slot
+    Synced,
+    /// the tip is behind the current slot and no block was received for
+    /// a while
+    Stalled {
+        /// the time the last block was received, or the time of the tip if
+        /// no block was received since the node started
+        since: SystemTime,
+    },
+}
+
+/// occupancy of the mempool by the fragments of a given origin
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct MempoolOriginUsage {
+    pub origin: FragmentOrigin,
+    /// number of fragments from this origin currently in the mempool
+    pub entries: usize,
+    /// number of fragments refused because the origin was over its quota
+    pub over_quota_cnt: u64,
+    /// number of fragments from this origin evicted to make room for others
+    pub evicted_cnt: u64,
+}
+
+/// content of the mempool and outcome of the fragments received since the
+/// node started
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct MempoolSummary {
+    /// number of fragments currently in the mempool
+    pub entries: usize,
+    /// number of fragments of each type currently in the mempool
+    pub entries_by_type: BTreeMap<FragmentType, usize>,
+    /// number of fragments accepted in the mempool
+    pub accepted_cnt: u64,
+    /// number of fragments refused when received, or rejected while in the
+    /// mempool
+    pub rejected_cnt: u64,
+    /// number of the recently included fragments the average time to block
+    /// is computed on
+    pub time_to_block_samples: usize,
+    /// average delay between the reception of a fragment and its inclusion
+    /// in a block of the main chain, in milliseconds
+    pub avg_time_to_block: Option<u64>,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -41,3 +232,41 @@ pub enum NodeState {
     StartingWorkers,
     Running,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latency_percentiles() {
+        assert_eq!(
+            LatencyPercentiles::from_samples(&[]),
+            LatencyPercentiles::default()
+        );
+
+        let samples: Vec<u64> = (1..=100).rev().collect();
+        let percentiles = LatencyPercentiles::from_samples(&samples);
+        assert_eq!(percentiles.samples, 100);
+        assert_eq!(percentiles.p50, Some(50));
+        assert_eq!(percentiles.p90, Some(90));
+        assert_eq!(percentiles.p99, Some(99));
+
+        let percentiles = LatencyPercentiles::from_samples(&[7]);
+        assert_eq!(percentiles.p50, Some(7));
+        assert_eq!(percentiles.p99, Some(7));
+    }
+
+    #[test]
+    fn sync_state_serialization() {
+        assert_eq!(
+            serde_json::to_value(SyncState::Synced).unwrap(),
+            serde_json::json!("synced")
+        );
+        assert_eq!(
+            serde_json::to_value(SyncState::CatchingUp { behind_by: 3 }).unwrap(),
+            serde_json::json!({ "catching_up": { "behind_by": 3 } })
+        );
+        let state: SyncState = serde_json::from_value(serde_json::json!("bootstrapping")).unwrap();
+        assert_eq!(state, SyncState::Bootstrapping);
+    }
+}
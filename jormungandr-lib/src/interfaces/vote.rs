@@ -368,7 +368,7 @@ where
     Ok(proposals)
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct VotePlanStatus {
     pub id: Hash,
     #[serde(with = "PayloadTypeDef")]
@@ -384,7 +384,7 @@ pub struct VotePlanStatus {
     pub proposals: Vec<VoteProposalStatus>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum Tally {
     Public { result: TallyResult },
     Private { state: PrivateTallyState },
@@ -405,6 +405,12 @@ impl TallyResult {
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct EncryptedTally(#[serde(with = "serde_base64_bytes")] Vec<u8>);
 
+impl AsRef<[u8]> for EncryptedTally {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
 mod serde_base64_bytes {
     use serde::de::{Error, Visitor};
     use serde::{Deserializer, Serializer};
@@ -495,7 +501,26 @@ pub enum Payload {
     },
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// number of votes cast on each proposal of a vote plan over time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VotePlanStats {
+    pub id: Hash,
+    /// the samples in increasing chain length, a sample being recorded at
+    /// each block of the main chain changing the counts
+    pub samples: Vec<VoteCountSample>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoteCountSample {
+    pub chain_length: u32,
+    pub block: Hash,
+    #[serde(with = "BlockDateDef")]
+    pub date: BlockDate,
+    /// the number of votes cast on each proposal, by proposal index
+    pub votes_cast: Vec<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct VoteProposalStatus {
     pub index: u8,
     pub proposal_id: Hash,
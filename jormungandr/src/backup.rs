@@ -0,0 +1,196 @@
+//! periodic backups of the chain stored by the node
+//!
+//! The block storage has no snapshot facility of its own, so a backup is
+//! a chain archive (see [`blockchain::archive`]) of the main chain at the
+//! time of the backup. The blocks are immutable once stored, so the archive
+//! is consistent even though the node keeps running while it is written.
+//!
+//! [`blockchain::archive`]: ../blockchain/archive/index.html
+
+use crate::{
+    blockchain::{archive, Storage, Tip},
+    settings::start::{BackupSchedule, BackupSettings},
+    utils::{fs::write_atomically, task::TokioServiceInfo},
+};
+use futures::executor::block_on;
+use jormungandr_lib::time::SystemTime;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::UNIX_EPOCH,
+};
+use thiserror::Error;
+use tokio::time::interval;
+
+const FILE_PREFIX: &str = "chain-";
+const FILE_EXTENSION: &str = "arch";
+
+#[derive(Debug, Error)]
+enum Error {
+    #[error("I/O error on the backup directory")]
+    Io(#[from] io::Error),
+    #[error("cannot write the chain archive")]
+    Archive(#[from] archive::Error),
+}
+
+/// status of the backups, reported in the diagnostic data
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BackupStatus {
+    /// the file of the last successful backup
+    pub last_backup: Option<PathBuf>,
+    /// the time of the last successful backup
+    pub last_backup_time: Option<SystemTime>,
+    /// the number of blocks in the last successful backup
+    pub last_backup_blocks: Option<u32>,
+    /// the error of the last backup, if it failed
+    pub last_error: Option<String>,
+    /// the number of backups kept in the backup directory
+    pub backups: usize,
+}
+
+/// shared handle to the status of the backups
+#[derive(Clone, Default)]
+pub struct Status(Arc<Mutex<BackupStatus>>);
+
+impl Status {
+    pub fn get(&self) -> BackupStatus {
+        self.0.lock().unwrap().clone()
+    }
+
+    fn update<F: FnOnce(&mut BackupStatus)>(&self, f: F) {
+        f(&mut self.0.lock().unwrap())
+    }
+}
+
+pub async fn run_backups(
+    service_info: TokioServiceInfo,
+    settings: BackupSettings,
+    storage: Storage,
+    blockchain_tip: Tip,
+    status: Status,
+) {
+    let logger = service_info.logger().clone();
+
+    let mut ticks = match settings.schedule {
+        BackupSchedule::Interval(period) => Some(interval(period)),
+        // only a new tip can start a new epoch, so the tip updates are
+        // awaited instead
+        BackupSchedule::Epochs(_) => None,
+    };
+    if let Some(ticks) = ticks.as_mut() {
+        // the first tick completes immediately, start counting from now
+        ticks.tick().await;
+    }
+
+    let mut tip_updates = blockchain_tip.updates();
+    let mut last_epoch = blockchain_tip.get_ref().await.block_date().epoch;
+
+    loop {
+        if let Some(ticks) = ticks.as_mut() {
+            ticks.tick().await;
+        }
+
+        if let BackupSchedule::Epochs(epochs) = settings.schedule {
+            let epoch = match tip_updates.changed().await {
+                Some(tip) => tip.block_date().epoch,
+                None => break,
+            };
+            if epoch < last_epoch.saturating_add(epochs) {
+                continue;
+            }
+            last_epoch = epoch;
+        }
+
+        let result = {
+            let settings = settings.clone();
+            let storage = storage.clone();
+            tokio::task::spawn_blocking(move || backup(&settings, &storage))
+                .await
+                .map_err(|e| Error::Io(io::Error::new(io::ErrorKind::Other, e)))
+                .and_then(|result| result)
+        };
+        match result {
+            Ok((path, blocks, backups)) => {
+                info!(logger, "chain backed up"; "file" => %path.display(), "blocks" => blocks);
+                status.update(|status| {
+                    status.last_backup = Some(path);
+                    status.last_backup_time = Some(SystemTime::now());
+                    status.last_backup_blocks = Some(blocks);
+                    status.last_error = None;
+                    status.backups = backups;
+                });
+            }
+            Err(e) => {
+                error!(logger, "chain backup failed"; "reason" => %e);
+                status.update(|status| status.last_error = Some(e.to_string()));
+            }
+        }
+    }
+}
+
+/// write a new backup and remove the oldest ones over the retention limit,
+/// returning the path of the backup, the number of blocks and the number
+/// of backups kept. The files are written from the calling thread, which
+/// must allow blocking.
+fn backup(settings: &BackupSettings, storage: &Storage) -> Result<(PathBuf, u32, usize), Error> {
+    fs::create_dir_all(&settings.directory)?;
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let path = settings
+        .directory
+        .join(format!("{}{}.{}", FILE_PREFIX, timestamp, FILE_EXTENSION));
+
+    // the archive is written aside and moved in place, so an interrupted
+    // backup does not look like a complete one
+    let mut blocks = 0;
+    let mut export_error = None;
+    let written = write_atomically(&path, |file| {
+        match block_on(archive::export(storage, file)) {
+            Ok(exported) => {
+                blocks = exported;
+                Ok(())
+            }
+            Err(e) => {
+                export_error = Some(e);
+                Err(io::ErrorKind::Other.into())
+            }
+        }
+    });
+    if let Some(e) = export_error {
+        return Err(e.into());
+    }
+    written?;
+
+    let mut backups = list_backups(&settings.directory)?;
+    while backups.len() > settings.keep {
+        let (_, oldest) = backups.remove(0);
+        fs::remove_file(oldest)?;
+    }
+
+    Ok((path, blocks, backups.len()))
+}
+
+/// the backups in the directory with their timestamps, oldest first
+fn list_backups(directory: &Path) -> Result<Vec<(u64, PathBuf)>, Error> {
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some(FILE_EXTENSION) {
+            continue;
+        }
+        let timestamp = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.strip_prefix(FILE_PREFIX))
+            .and_then(|timestamp| timestamp.parse().ok());
+        if let Some(timestamp) = timestamp {
+            backups.push((timestamp, path));
+        }
+    }
+    backups.sort();
+    Ok(backups)
+}
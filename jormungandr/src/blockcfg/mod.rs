@@ -43,12 +43,15 @@ pub enum Block0Malformed {
     NoSlotDuration,
     #[error("missing `slots_per_epoch' value in the block0")]
     NoSlotsPerEpoch,
+    #[error("missing `consensus_version' value in the block0")]
+    NoConsensusVersion,
 }
 
 pub trait Block0DataSource {
     fn slot_duration(&self) -> Result<Duration, Block0Error>;
     fn slots_per_epoch(&self) -> Result<u32, Block0Error>;
     fn start_time(&self) -> Result<SystemTime, Block0Error>;
+    fn consensus_version(&self) -> Result<ConsensusVersion, Block0Error>;
 }
 
 impl Block0DataSource for Block {
@@ -78,6 +81,15 @@ impl Block0DataSource for Block {
         }
         Err(Block0Malformed::NoStartTime.into())
     }
+
+    fn consensus_version(&self) -> Result<ConsensusVersion, Block0Error> {
+        for config in initial(self)?.iter() {
+            if let ConfigParam::ConsensusVersion(version) = config {
+                return Ok(*version);
+            }
+        }
+        Err(Block0Malformed::NoConsensusVersion.into())
+    }
 }
 
 fn initial(block: &Block) -> Result<&ConfigParams, Block0Malformed> {
@@ -0,0 +1,301 @@
+//! archives of the main chain, to seed new nodes out-of-band
+//!
+//! The archive starts with a magic string, the format version and the hash
+//! of the block0. It is followed by the blocks of the main chain from the
+//! block0 to the tip, each one stored as a big-endian `u32` length, the
+//! serialized block and the Blake2b256 checksum of the serialized block.
+//! A zero length marks the end of the archive.
+
+use super::{Storage, StorageError, MAIN_BRANCH_TAG};
+use crate::blockcfg::{Block, HeaderHash};
+use chain_core::property::{Block as _, Deserialize, Serialize};
+use chain_crypto::Blake2b256;
+use futures::prelude::*;
+use slog::Logger;
+use thiserror::Error;
+
+use std::io::{self, Read, Write};
+
+const MAGIC: &[u8; 8] = b"JORMARCH";
+const VERSION: u8 = 1;
+const CHECKSUM_SIZE: usize = 32;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("storage error")]
+    Storage(#[from] StorageError),
+    #[error("I/O error on the archive")]
+    Io(#[from] io::Error),
+    #[error("the storage has no main chain to export")]
+    NoMainChain,
+    #[error("not a chain archive")]
+    InvalidMagic,
+    #[error("the archive has no block")]
+    Empty,
+    #[error("unsupported archive version {0}")]
+    UnsupportedVersion(u8),
+    #[error("the archive holds the chain of the genesis block {archive}, not {expected}")]
+    Block0Mismatch {
+        expected: HeaderHash,
+        archive: HeaderHash,
+    },
+    #[error("checksum mismatch on block #{0} of the archive")]
+    ChecksumMismatch(u32),
+    #[error("block #{0} of the archive is not a child of the previous block")]
+    Discontinuity(u32),
+    #[error("invalid block #{index} in the archive")]
+    InvalidBlock {
+        index: u32,
+        #[source]
+        source: io::Error,
+    },
+}
+
+/// statistics of an import
+pub struct ImportSummary {
+    /// the last block of the archive
+    pub tip: HeaderHash,
+    /// number of blocks written to the storage
+    pub imported: u32,
+    /// number of blocks found already in the storage, e.g. from an
+    /// interrupted import of the same archive
+    pub skipped: u32,
+}
+
+/// write the main chain of the storage in an archive, returning the number
+/// of exported blocks.
+pub async fn export<W: Write>(storage: &Storage, writer: W) -> Result<u32, Error> {
+    let mut writer = io::BufWriter::new(writer);
+    let tip = storage
+        .get_tag(MAIN_BRANCH_TAG)?
+        .ok_or(Error::NoMainChain)?;
+    let block0 = storage
+        .get_blocks_by_chain_length(0)?
+        .pop()
+        .ok_or(Error::NoMainChain)?;
+    let block0_id = block0.id();
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[VERSION])?;
+    writer.write_all(block0_id.as_ref())?;
+
+    write_block(&mut writer, &block0)?;
+    let mut exported = 1;
+    let mut last_id = block0_id;
+
+    if tip != block0_id {
+        let mut blocks = Box::pin(storage.stream_from_to(block0_id, tip)?);
+        while let Some(block) = blocks.next().await {
+            let block = block.map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            let id = block.id();
+            if id == last_id {
+                continue;
+            }
+            write_block(&mut writer, &block)?;
+            exported += 1;
+            last_id = id;
+        }
+    }
+
+    writer.write_all(&0u32.to_be_bytes())?;
+    writer.flush()?;
+    Ok(exported)
+}
+
+/// store the blocks of an archive and make its last block the tip of the
+/// main chain. The archive must hold the chain of the given genesis block.
+///
+/// The blocks already present in the storage are skipped, so an interrupted
+/// import can be resumed by importing the same archive again.
+pub fn import<R: Read>(
+    storage: &Storage,
+    reader: R,
+    expected_block0: HeaderHash,
+    logger: &Logger,
+) -> Result<ImportSummary, Error> {
+    let mut reader = io::BufReader::new(reader);
+
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(Error::InvalidMagic);
+    }
+    let mut version = [0; 1];
+    reader.read_exact(&mut version)?;
+    if version[0] != VERSION {
+        return Err(Error::UnsupportedVersion(version[0]));
+    }
+    let mut block0_id = [0; 32];
+    reader.read_exact(&mut block0_id)?;
+    let block0_id = HeaderHash::deserialize(&block0_id[..]).map_err(StorageError::Deserialize)?;
+    if block0_id != expected_block0 {
+        return Err(Error::Block0Mismatch {
+            expected: expected_block0,
+            archive: block0_id,
+        });
+    }
+
+    let mut summary = ImportSummary {
+        tip: block0_id,
+        imported: 0,
+        skipped: 0,
+    };
+    let mut index = 0;
+    while let Some(block) = read_block(&mut reader, index)? {
+        let continuous = if index == 0 {
+            block.id() == block0_id
+        } else {
+            block.parent_id() == summary.tip
+        };
+        if !continuous {
+            return Err(Error::Discontinuity(index));
+        }
+
+        summary.tip = block.id();
+        if storage.block_exists(summary.tip)? {
+            summary.skipped += 1;
+        } else {
+            storage.put_block(&block)?;
+            summary.imported += 1;
+        }
+
+        index += 1;
+        if index % 10_000 == 0 {
+            info!(logger, "imported {} blocks", index);
+        }
+    }
+
+    if index == 0 {
+        return Err(Error::Empty);
+    }
+
+    // do not move the main chain back if the node is already further
+    // on the same chain
+    let keep_head = match storage.get_tag(MAIN_BRANCH_TAG)? {
+        Some(head) => storage.is_ancestor(summary.tip, head)?.is_some(),
+        None => false,
+    };
+    if !keep_head {
+        storage.put_tag(MAIN_BRANCH_TAG, summary.tip)?;
+    }
+
+    Ok(summary)
+}
+
+fn write_block<W: Write>(writer: &mut W, block: &Block) -> Result<(), Error> {
+    let bytes = block.serialize_as_vec().map_err(StorageError::Serialize)?;
+    writer.write_all(&(bytes.len() as u32).to_be_bytes())?;
+    writer.write_all(&bytes)?;
+    writer.write_all(Blake2b256::new(&bytes).as_ref())?;
+    Ok(())
+}
+
+fn read_block<R: Read>(reader: &mut R, index: u32) -> Result<Option<Block>, Error> {
+    let mut len = [0; 4];
+    reader.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len == 0 {
+        return Ok(None);
+    }
+
+    let mut bytes = vec![0; len];
+    reader.read_exact(&mut bytes)?;
+    let mut checksum = [0; CHECKSUM_SIZE];
+    reader.read_exact(&mut checksum)?;
+    if Blake2b256::new(&bytes).as_ref() != &checksum[..] {
+        return Err(Error::ChecksumMismatch(index));
+    }
+
+    Block::deserialize(bytes.as_slice())
+        .map(Some)
+        .map_err(|source| Error::InvalidBlock { index, source })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blockcfg::{BlockDate, BlockVersion, Contents, ContentsBuilder, HeaderBuilderNew},
+        utils::test_fixtures::block0,
+    };
+
+    /// an empty block on top of `parent`, the slot telling apart the
+    /// children of the same parent
+    fn child(parent: &Block, slot_id: u32) -> Block {
+        let contents: Contents = ContentsBuilder::new().into();
+        let header = HeaderBuilderNew::new(BlockVersion::Genesis, &contents)
+            .set_parent(&parent.id(), parent.header.chain_length().increase())
+            .set_date(BlockDate { epoch: 0, slot_id })
+            .into_unsigned_header()
+            .unwrap()
+            .generalize();
+        Block { header, contents }
+    }
+
+    fn storage() -> Storage {
+        Storage::memory(Logger::root(slog::Discard, o!())).unwrap()
+    }
+
+    /// a storage with a main chain of `length` blocks after the block0 and
+    /// an abandoned branch, returning the main chain and the abandoned block
+    fn populated(storage: &Storage, length: u32) -> (Vec<Block>, Block) {
+        let mut chain = vec![block0()];
+        for slot_id in 1..=length {
+            chain.push(child(chain.last().unwrap(), slot_id));
+        }
+        let abandoned = child(&chain[1], length + 1);
+        for block in chain.iter().chain(std::iter::once(&abandoned)) {
+            storage.put_block(block).unwrap();
+        }
+        storage
+            .put_tag(MAIN_BRANCH_TAG, chain.last().unwrap().id())
+            .unwrap();
+        (chain, abandoned)
+    }
+
+    #[tokio::test]
+    async fn exported_main_chain_is_imported() {
+        let source = storage();
+        let (chain, abandoned) = populated(&source, 5);
+        let block0_id = chain[0].id();
+        let tip = chain.last().unwrap().id();
+
+        let mut archive = Vec::new();
+        assert_eq!(export(&source, &mut archive).await.unwrap(), 6);
+
+        let logger = Logger::root(slog::Discard, o!());
+        let target = storage();
+        let summary = import(&target, archive.as_slice(), block0_id, &logger).unwrap();
+        assert_eq!(
+            (summary.tip, summary.imported, summary.skipped),
+            (tip, 6, 0)
+        );
+        assert_eq!(target.get_tag(MAIN_BRANCH_TAG).unwrap(), Some(tip));
+        for block in &chain {
+            assert!(target.block_exists(block.id()).unwrap());
+        }
+        assert!(!target.block_exists(abandoned.id()).unwrap());
+
+        // importing again, as when resuming, skips the stored blocks
+        let summary = import(&target, archive.as_slice(), block0_id, &logger).unwrap();
+        assert_eq!((summary.imported, summary.skipped), (0, 6));
+    }
+
+    #[tokio::test]
+    async fn archives_of_another_genesis_block_are_rejected() {
+        let source = storage();
+        let (chain, _) = populated(&source, 2);
+        let mut archive = Vec::new();
+        export(&source, &mut archive).await.unwrap();
+
+        let logger = Logger::root(slog::Discard, o!());
+        let target = storage();
+        let expected = block0().id();
+        assert!(matches!(
+            import(&target, archive.as_slice(), expected, &logger),
+            Err(Error::Block0Mismatch { archive, .. }) if archive == chain[0].id()
+        ));
+        assert!(!target.block_exists(chain[0].id()).unwrap());
+        assert_eq!(target.get_tag(MAIN_BRANCH_TAG).unwrap(), None);
+    }
+}
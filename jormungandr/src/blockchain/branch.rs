@@ -1,7 +1,8 @@
+use crate::blockcfg::HeaderHash;
 use crate::blockchain::Ref;
 use futures::stream::{FuturesUnordered, StreamExt};
 use std::{iter::FromIterator, sync::Arc};
-use tokio::sync::RwLock;
+use tokio::sync::{watch, RwLock};
 
 #[derive(Clone)]
 pub struct Branches {
@@ -15,6 +16,17 @@ struct BranchesData {
 #[derive(Clone)]
 pub struct Branch {
     inner: Arc<RwLock<BranchData>>,
+    /// announces the new references of the branch to the tasks waiting
+    /// for it to move, instead of them polling it
+    updates: Arc<watch::Sender<Arc<Ref>>>,
+    /// kept so that the announcements never fail for lack of receivers
+    updates_receiver: watch::Receiver<Arc<Ref>>,
+}
+
+/// Follows the reference of a branch as it moves.
+pub struct BranchUpdates {
+    receiver: watch::Receiver<Arc<Ref>>,
+    last_seen: HeaderHash,
 }
 
 /// the data that is contained in a branch
@@ -96,8 +108,11 @@ impl BranchesData {
 
 impl Branch {
     pub fn new(reference: Arc<Ref>) -> Self {
+        let (updates, updates_receiver) = watch::channel(Arc::clone(&reference));
         Branch {
             inner: Arc::new(RwLock::new(BranchData::new(reference))),
+            updates: Arc::new(updates),
+            updates_receiver,
         }
     }
 
@@ -108,12 +123,30 @@ impl Branch {
 
     pub async fn update_ref(&mut self, new_ref: Arc<Ref>) -> Arc<Ref> {
         let mut guard = self.inner.write().await;
-        guard.update(new_ref)
+        let old_ref = guard.update(Arc::clone(&new_ref));
+        self.announce(new_ref);
+        old_ref
+    }
+
+    /// follow the moves of the branch from its current reference on
+    pub fn updates(&self) -> BranchUpdates {
+        let receiver = self.updates_receiver.clone();
+        let last_seen = receiver.borrow().hash();
+        BranchUpdates {
+            receiver,
+            last_seen,
+        }
+    }
+
+    fn announce(&self, reference: Arc<Ref>) {
+        // cannot fail, the branch keeps a receiver
+        let _ = self.updates.broadcast(reference);
     }
 
     async fn continue_with(&mut self, candidate: Arc<Ref>) -> Option<Self> {
         let mut guard = self.inner.write().await;
-        if guard.continue_with(candidate) {
+        if guard.continue_with(Arc::clone(&candidate)) {
+            self.announce(candidate);
             Some(self.clone())
         } else {
             None
@@ -121,6 +154,20 @@ impl Branch {
     }
 }
 
+impl BranchUpdates {
+    /// wait for the branch to point to another block than the last one
+    /// seen. Returns `None` if the branch is gone.
+    pub async fn changed(&mut self) -> Option<Arc<Ref>> {
+        loop {
+            let reference = self.receiver.recv().await?;
+            if reference.hash() != self.last_seen {
+                self.last_seen = reference.hash();
+                return Some(reference);
+            }
+        }
+    }
+}
+
 impl BranchData {
     /// create the branch data with the current `last_updated` to
     /// the current time this function was called
@@ -151,3 +198,119 @@ impl BranchData {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockcfg::{
+        Block, BlockVersion, Contents, ContentsBuilder, HeaderBuilderNew, Ledger,
+    };
+    use crate::blockchain::{Blockchain, Multiverse, Storage, Tip};
+    use crate::utils::{json_store::JsonStore, test_fixtures::block0};
+    use slog::Logger;
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    async fn block0_branch() -> Branch {
+        let logger = Logger::root(slog::Discard, o!());
+        let block0 = block0();
+        let blockchain = Blockchain::new(
+            block0.header.hash(),
+            Storage::memory(logger).unwrap(),
+            16,
+            false,
+            JsonStore::in_memory(None),
+            JsonStore::in_memory(None),
+            JsonStore::in_memory(None),
+            None,
+            None,
+            false,
+        );
+        blockchain.load_from_block0(block0).await.unwrap()
+    }
+
+    /// a reference to a block on top of `parent`, with the same ledger
+    async fn child(ledgers: &Multiverse<Ledger>, parent: &Ref) -> Arc<Ref> {
+        let contents: Contents = ContentsBuilder::new().into();
+        let header = HeaderBuilderNew::new(BlockVersion::Genesis, &contents)
+            .set_parent(&parent.hash(), parent.chain_length().increase())
+            .set_date(parent.block_date())
+            .into_unsigned_header()
+            .unwrap()
+            .generalize();
+        let ledger = ledgers
+            .insert(
+                header.chain_length(),
+                header.hash(),
+                parent.ledger().as_ref().clone(),
+            )
+            .await;
+        Arc::new(Ref::new(
+            ledger,
+            Arc::clone(parent.time_frame()),
+            Arc::clone(parent.epoch_leadership_schedule()),
+            Arc::clone(parent.epoch_ledger_parameters()),
+            None,
+            header,
+            None,
+        ))
+    }
+
+    async fn no_change(updates: &mut BranchUpdates) -> bool {
+        timeout(Duration::from_millis(100), updates.changed())
+            .await
+            .is_err()
+    }
+
+    #[tokio::test]
+    async fn updates_follow_the_moves_of_the_branch() {
+        let ledgers = Multiverse::new();
+        let mut branch = block0_branch().await;
+        let block0 = branch.get_ref().await;
+        let mut updates = branch.updates();
+        assert!(no_change(&mut updates).await);
+
+        let block1 = child(&ledgers, &block0).await;
+        branch.update_ref(Arc::clone(&block1)).await;
+        let changed = updates.changed().await.unwrap();
+        assert_eq!(changed.hash(), block1.hash());
+
+        // pointing to the same block again is not a move
+        branch.update_ref(Arc::clone(&block1)).await;
+        assert!(no_change(&mut updates).await);
+
+        // a block on top of another one does not move the branch
+        let fork = child(&ledgers, &block0).await;
+        assert!(branch.continue_with(Arc::clone(&fork)).await.is_none());
+        assert!(no_change(&mut updates).await);
+
+        let block2 = child(&ledgers, &block1).await;
+        assert!(branch.continue_with(Arc::clone(&block2)).await.is_some());
+        let changed = updates.changed().await.unwrap();
+        assert_eq!(changed.hash(), block2.hash());
+    }
+
+    #[tokio::test]
+    async fn tip_updates_follow_a_swap() {
+        let ledgers = Multiverse::new();
+        let main = block0_branch().await;
+        let block0 = main.get_ref().await;
+        let mut tip = Tip::new(main.clone());
+        let mut updates = tip.updates();
+
+        let fork = child(&ledgers, &block0).await;
+        let other = Branch::new(Arc::clone(&fork));
+        tip.swap(other.clone()).await;
+        let changed = updates.changed().await.unwrap();
+        assert_eq!(changed.hash(), fork.hash());
+        assert_eq!(other.get_ref().await.hash(), block0.hash());
+    }
+
+    #[tokio::test]
+    async fn updates_end_with_the_branch() {
+        let branch = block0_branch().await;
+        let mut updates = branch.updates();
+        drop(branch);
+        assert!(updates.changed().await.is_none());
+    }
+}
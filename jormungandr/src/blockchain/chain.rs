@@ -55,7 +55,11 @@ use crate::{
         Block, Block0Error, BlockDate, ChainLength, Epoch, EpochRewardsInfo, Header, HeaderHash,
         Leadership, Ledger, LedgerParameters, RewardsInfoParameters,
     },
-    blockchain::{Branch, Checkpoints, Multiverse, Ref, Storage, StorageError},
+    blockchain::{
+        ActiveVotePlans, Branch, Checkpoints, EpochAccountingLog, EpochHooks, Finality,
+        HeaderChain, Multiverse, Ref, RewardsHistory, Storage, StorageError, VotePlanArchive,
+        VoteStats,
+    },
 };
 use chain_impl_mockchain::{leadership::Verification, ledger};
 use chain_time::TimeFrame;
@@ -171,6 +175,12 @@ pub fn pre_verify_link(
 /// * `RefCache`: a cache of blocks headers and associated states;
 /// * `Multiverse`: of ledger. It is a cache of different ledger states.
 ///
+/// In relay mode, the contents of the blocks are not applied: every block
+/// shares the ledger state of the block0, so the node only keeps the chain
+/// of headers and the blocks in the storage. The headers are still signed
+/// by the BFT leaders of the block0, as relay mode is refused for the
+/// Genesis Praos consensus.
+///
 #[derive(Clone)]
 pub struct Blockchain {
     branches: Branches,
@@ -184,6 +194,24 @@ pub struct Blockchain {
     block0: HeaderHash,
 
     rewards_report_all: bool,
+
+    rewards_history: RewardsHistory,
+
+    epoch_accounting: EpochAccountingLog,
+
+    vote_plan_archive: VotePlanArchive,
+
+    active_vote_plans: ActiveVotePlans,
+
+    header_chain: HeaderChain,
+
+    vote_stats: Option<VoteStats>,
+
+    epoch_hooks: Option<EpochHooks>,
+
+    finality: Finality,
+
+    relay: bool,
 }
 
 pub enum PreCheckedHeader {
@@ -266,11 +294,18 @@ pub enum CheckHeaderProof {
 }
 
 impl Blockchain {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         block0: HeaderHash,
         storage: Storage,
         cache_capacity: usize,
         rewards_report_all: bool,
+        rewards_history: RewardsHistory,
+        epoch_accounting: EpochAccountingLog,
+        vote_plan_archive: VotePlanArchive,
+        vote_stats: Option<VoteStats>,
+        epoch_hooks: Option<EpochHooks>,
+        relay: bool,
     ) -> Self {
         Blockchain {
             branches: Branches::new(),
@@ -279,6 +314,15 @@ impl Blockchain {
             storage,
             block0,
             rewards_report_all,
+            rewards_history,
+            epoch_accounting,
+            vote_plan_archive,
+            active_vote_plans: ActiveVotePlans::default(),
+            header_chain: HeaderChain::new(),
+            vote_stats,
+            epoch_hooks,
+            finality: Finality::default(),
+            relay,
         }
     }
 
@@ -286,6 +330,45 @@ impl Blockchain {
         &self.block0
     }
 
+    pub fn rewards_history(&self) -> &RewardsHistory {
+        &self.rewards_history
+    }
+
+    pub fn epoch_accounting(&self) -> &EpochAccountingLog {
+        &self.epoch_accounting
+    }
+
+    /// the final status of the vote plans closed on the main chain
+    pub fn vote_plan_archive(&self) -> &VotePlanArchive {
+        &self.vote_plan_archive
+    }
+
+    /// the vote plans active at the tip
+    pub fn active_vote_plans(&self) -> &ActiveVotePlans {
+        &self.active_vote_plans
+    }
+
+    /// the index of the latest headers of the main chain
+    pub fn header_chain(&self) -> &HeaderChain {
+        &self.header_chain
+    }
+
+    /// the time series of the votes cast on the active vote plans, if
+    /// enabled
+    pub fn vote_stats(&self) -> Option<&VoteStats> {
+        self.vote_stats.as_ref()
+    }
+
+    /// the commands to notify of the epoch transitions, if configured
+    pub fn epoch_hooks(&self) -> Option<&EpochHooks> {
+        self.epoch_hooks.as_ref()
+    }
+
+    /// the actions waiting for blocks of the main chain to be final
+    pub fn finality(&self) -> &Finality {
+        &self.finality
+    }
+
     pub fn storage(&self) -> &Storage {
         &self.storage
     }
@@ -424,12 +507,34 @@ impl Blockchain {
     /// * the header,
     /// * the ledger state associated to the parent block
     /// * the leadership schedule associated to the header
+    ///
+    /// In relay mode the ledger state stays the one of the block0, which is
+    /// enough to verify the signature of the BFT leaders, the only consensus
+    /// relay mode is allowed with.
     pub async fn post_check_header(
         &self,
         header: Header,
         parent: Arc<Ref>,
         check_header_proof: CheckHeaderProof,
     ) -> Result<PostCheckedHeader> {
+        if self.relay {
+            if check_header_proof == CheckHeaderProof::Enabled {
+                let leadership = Leadership::new(header.block_date().epoch, &parent.ledger());
+                if let Verification::Failure(error) = leadership.verify(&header) {
+                    return Err(ErrorKind::BlockHeaderVerificationFailed(error.to_string()).into());
+                }
+            }
+            return Ok(PostCheckedHeader {
+                header,
+                epoch_leadership_schedule: parent.epoch_leadership_schedule().clone(),
+                epoch_ledger_parameters: parent.epoch_ledger_parameters().clone(),
+                epoch_rewards_info: None,
+                parent_ledger_state: parent.ledger(),
+                time_frame: parent.time_frame().clone(),
+                previous_epoch_state: None,
+            });
+        }
+
         let current_date = header.block_date();
         let rewards_report_all = self.rewards_report_all;
 
@@ -474,6 +579,10 @@ impl Blockchain {
 
         debug_assert!(block.header.hash() == block_id);
 
+        if self.relay {
+            return Ok(ledger.as_ref().clone());
+        }
+
         let metadata = header.to_content_eval_context();
 
         ledger
@@ -754,8 +863,9 @@ impl Blockchain {
         Ok(branch)
     }
 
-    pub async fn get_checkpoints(&self, branch: &Branch) -> Checkpoints {
-        Checkpoints::new_from(branch.get_ref().await)
+    pub async fn get_checkpoints(&self, branch: &Branch) -> Result<Checkpoints> {
+        Checkpoints::new_from(branch.get_ref().await, self.block0, &self.storage)
+            .map_err(|e| Error::with_chain(e, "cannot compute the checkpoints of the branch"))
     }
 }
 
@@ -898,3 +1008,73 @@ pub fn new_epoch_leadership_from(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockcfg::{BlockVersion, Contents, ContentsBuilder, HeaderBuilderNew};
+    use crate::utils::{json_store::JsonStore, test_fixtures};
+    use chain_crypto::{Ed25519, SecretKey};
+    use chain_impl_mockchain::leadership::bft::LeaderId;
+
+    fn block0(leader: &SecretKey<Ed25519>) -> Block {
+        test_fixtures::block0_with_funds(&leader.to_public(), &[])
+    }
+
+    fn relay(block0: &Block) -> Blockchain {
+        Blockchain::new(
+            block0.header.hash(),
+            Storage::memory(Logger::root(slog::Discard, o!())).unwrap(),
+            16,
+            false,
+            JsonStore::in_memory(None),
+            JsonStore::in_memory(None),
+            JsonStore::in_memory(None),
+            None,
+            None,
+            true,
+        )
+    }
+
+    /// the header of an empty block on top of `parent`, signed with the key
+    fn signed_child(parent: &Ref, key: &SecretKey<Ed25519>) -> Header {
+        let contents: Contents = ContentsBuilder::new().into();
+        let builder = HeaderBuilderNew::new(BlockVersion::Ed25519Signed, &contents)
+            .set_parent(&parent.hash(), parent.chain_length().increase())
+            .set_date(BlockDate {
+                epoch: 0,
+                slot_id: 1,
+            })
+            .into_bft_builder()
+            .unwrap()
+            .set_consensus_data(&LeaderId::from(key.to_public()));
+        let signature = key.sign_slice(builder.get_authenticated_data());
+        builder.set_signature(signature.into()).generalize()
+    }
+
+    #[tokio::test]
+    async fn relay_verifies_the_signature_of_the_bft_leaders() {
+        let leader = SecretKey::<Ed25519>::generate(rand_core::OsRng);
+        let block0 = block0(&leader);
+        let blockchain = relay(&block0);
+        let block0_ref = blockchain
+            .load_from_block0(block0)
+            .await
+            .unwrap()
+            .get_ref()
+            .await;
+
+        let header = signed_child(&block0_ref, &leader);
+        assert!(blockchain
+            .post_check_header(header, Arc::clone(&block0_ref), CheckHeaderProof::Enabled)
+            .await
+            .is_ok());
+
+        let intruder = SecretKey::<Ed25519>::generate(rand_core::OsRng);
+        let header = signed_child(&block0_ref, &intruder);
+        assert!(blockchain
+            .post_check_header(header, block0_ref, CheckHeaderProof::Enabled)
+            .await
+            .is_err());
+    }
+}
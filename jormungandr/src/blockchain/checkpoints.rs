@@ -1,4 +1,7 @@
-use crate::{blockcfg::HeaderHash, blockchain::Ref};
+use crate::{
+    blockcfg::HeaderHash,
+    blockchain::{Ref, Storage, StorageError},
+};
 use std::sync::Arc;
 
 /// list of pre-computed checkpoints from a given [`Ref`].
@@ -10,38 +13,27 @@ pub struct Checkpoints(Vec<HeaderHash>);
 impl Checkpoints {
     /// create a new list of checkpoints from the given starting point (tip).
     ///
-    /// The function make use of the `Ref` object pointing to previous epoch in order
-    /// to populate _interestingly_ spaced checkpoints.
-    ///
-    /// For now the algorithm provide the current block, the parent, the last block of the
-    /// previous epoch, the last block of the epoch before that... until the block0.
-    pub fn new_from(from: Arc<Ref>) -> Self {
-        let mut checkpoints = vec![from.hash(), from.block_parent_hash()];
-
-        let mut ignore_prev = 0;
-        let mut current_ref = from;
-        while let Some(prev_epoch) = current_ref.last_ref_previous_epoch() {
-            current_ref = Arc::clone(prev_epoch);
-
-            for _ in 0..ignore_prev {
-                if let Some(prev_epoch) = current_ref.last_ref_previous_epoch() {
-                    current_ref = Arc::clone(&prev_epoch);
-                } else {
-                    break;
-                }
-            }
-
-            let hash = current_ref.hash();
-
-            // prevent the `from`'s parent to appear twice in the event the parent is also
-            // the last block of the previous epoch.
-            if checkpoints[checkpoints.len() - 1] != hash {
-                ignore_prev += 1;
-                checkpoints.push(hash);
-            }
+    /// The checkpoints form a skip list of the branch ending at the tip:
+    /// the tip itself, then the ancestors at distance 1, 2, 4, 8... from the
+    /// tip, and the block0. A peer can then find the closest common ancestor
+    /// with O(log n) checkpoints, however long the node was offline.
+    pub fn new_from(
+        from: Arc<Ref>,
+        block0: HeaderHash,
+        storage: &Storage,
+    ) -> Result<Self, StorageError> {
+        let tip = from.hash();
+        let distances = skip_list_distances(from.chain_length().into());
+        let mut checkpoints = vec![tip];
+        checkpoints.extend(storage.ancestor_ids(tip, &distances)?);
+
+        // prevent the block0 to appear twice in the event it is `from`
+        // itself.
+        if checkpoints[checkpoints.len() - 1] != block0 {
+            checkpoints.push(block0);
         }
 
-        Checkpoints(checkpoints)
+        Ok(Checkpoints(checkpoints))
     }
 
     pub fn iter(&self) -> impl Iterator<Item = &HeaderHash> {
@@ -73,3 +65,130 @@ impl From<Checkpoints> for Vec<HeaderHash> {
         checkpoints.0
     }
 }
+
+/// the distances 1, 2, 4, 8... from the tip, stopping before the block0
+fn skip_list_distances(tip_length: u32) -> Vec<u32> {
+    std::iter::successors(Some(1u32), |distance| distance.checked_mul(2))
+        .take_while(|distance| *distance < tip_length)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockcfg::{
+        Block, BlockVersion, Contents, ContentsBuilder, HeaderBuilderNew, Ledger,
+    };
+    use crate::blockchain::{Blockchain, Multiverse};
+    use crate::utils::{json_store::JsonStore, test_fixtures::block0};
+    use slog::Logger;
+
+    fn blockchain(block0: &Block) -> Blockchain {
+        let logger = Logger::root(slog::Discard, o!());
+        Blockchain::new(
+            block0.header.hash(),
+            Storage::memory(logger).unwrap(),
+            16,
+            false,
+            JsonStore::in_memory(None),
+            JsonStore::in_memory(None),
+            JsonStore::in_memory(None),
+            None,
+            None,
+            false,
+        )
+    }
+
+    /// stores a block on top of `parent`, with the same ledger, and returns
+    /// its reference
+    async fn child(
+        blockchain: &Blockchain,
+        ledgers: &Multiverse<Ledger>,
+        parent: &Ref,
+    ) -> Arc<Ref> {
+        let contents: Contents = ContentsBuilder::new().into();
+        let header = HeaderBuilderNew::new(BlockVersion::Genesis, &contents)
+            .set_parent(&parent.hash(), parent.chain_length().increase())
+            .set_date(parent.block_date())
+            .into_unsigned_header()
+            .unwrap()
+            .generalize();
+        blockchain
+            .storage()
+            .put_block(&Block {
+                header: header.clone(),
+                contents,
+            })
+            .unwrap();
+        let ledger = ledgers
+            .insert(
+                header.chain_length(),
+                header.hash(),
+                parent.ledger().as_ref().clone(),
+            )
+            .await;
+        Arc::new(Ref::new(
+            ledger,
+            Arc::clone(parent.time_frame()),
+            Arc::clone(parent.epoch_leadership_schedule()),
+            Arc::clone(parent.epoch_ledger_parameters()),
+            None,
+            header,
+            None,
+        ))
+    }
+
+    /// the block0 followed by `length` blocks, in chain order
+    async fn chain(blockchain: &Blockchain, block0: Block, length: usize) -> Vec<Arc<Ref>> {
+        let ledgers = Multiverse::new();
+        let branch = blockchain.load_from_block0(block0).await.unwrap();
+        let mut refs = vec![branch.get_ref().await];
+        for _ in 0..length {
+            let parent = Arc::clone(refs.last().unwrap());
+            refs.push(child(blockchain, &ledgers, &parent).await);
+        }
+        refs
+    }
+
+    #[test]
+    fn distances_double_until_the_block0() {
+        assert!(skip_list_distances(0).is_empty());
+        assert!(skip_list_distances(1).is_empty());
+        assert_eq!(skip_list_distances(2), vec![1]);
+        assert_eq!(skip_list_distances(9), vec![1, 2, 4, 8]);
+        assert_eq!(skip_list_distances(16), vec![1, 2, 4, 8]);
+        assert_eq!(skip_list_distances(u32::MAX).len(), 32);
+    }
+
+    #[tokio::test]
+    async fn checkpoints_are_exponentially_spaced_ancestors() {
+        let block0 = block0();
+        let block0_hash = block0.header.hash();
+        let blockchain = blockchain(&block0);
+        let refs = chain(&blockchain, block0, 20).await;
+
+        let tip = Arc::clone(&refs[20]);
+        let checkpoints = Checkpoints::new_from(tip, block0_hash, blockchain.storage()).unwrap();
+        let expected: Vec<_> = [20, 19, 18, 16, 12, 4, 0]
+            .iter()
+            .map(|length| refs[*length].hash())
+            .collect();
+        assert_eq!(Vec::from(checkpoints), expected);
+    }
+
+    #[tokio::test]
+    async fn block0_is_not_repeated() {
+        let block0 = block0();
+        let block0_hash = block0.header.hash();
+        let blockchain = blockchain(&block0);
+        let refs = chain(&blockchain, block0, 1).await;
+
+        let checkpoints =
+            Checkpoints::new_from(Arc::clone(&refs[0]), block0_hash, blockchain.storage()).unwrap();
+        assert_eq!(Vec::from(checkpoints), vec![block0_hash]);
+
+        let checkpoints =
+            Checkpoints::new_from(Arc::clone(&refs[1]), block0_hash, blockchain.storage()).unwrap();
+        assert_eq!(Vec::from(checkpoints), vec![refs[1].hash(), block0_hash]);
+    }
+}
@@ -0,0 +1,29 @@
+//! accounting of the value flows at the epoch transitions
+//!
+//! The records are tiny, one per epoch, so all of them are kept, persisted
+//! in the storage directory when there is one.
+
+use crate::{
+    blockcfg::Epoch,
+    utils::json_store::{JsonStore, Record},
+};
+use jormungandr_lib::interfaces::EpochAccounting;
+use std::path::{Path, PathBuf};
+
+const FILE_NAME: &str = "epoch-accounting.json";
+
+/// the path of the accounting file in the storage directory
+pub fn path<P: AsRef<Path>>(storage_dir: P) -> PathBuf {
+    storage_dir.as_ref().join(FILE_NAME)
+}
+
+/// the accounting by epoch
+pub type EpochAccountingLog = JsonStore<EpochAccounting>;
+
+impl Record for EpochAccounting {
+    type Key = Epoch;
+
+    fn key(&self) -> Epoch {
+        self.epoch()
+    }
+}
@@ -0,0 +1,312 @@
+//! notification of the epoch transitions to external programs
+//!
+//! The rewards of an epoch are distributed at the start of the following
+//! one. Rather than guessing when they are known, the payout scripts of the
+//! pool operators can be run with the summary of each epoch transition
+//! once it is final in the main chain. The hooks run in the background and
+//! their failures are only logged. Each epoch is notified at most once: the
+//! latest epoch notified is persisted in the storage directory when there is
+//! one, so the hooks do not run again for it after a restart.
+
+use crate::{
+    settings::start::EpochHookSettings,
+    utils::json_store::{JsonStore, Record},
+};
+use jormungandr_lib::interfaces::{EpochAccounting, EpochRewardsInfo};
+use reqwest::{header::CONTENT_TYPE, Url};
+use slog::Logger;
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus, Stdio},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+use tokio::sync::Mutex;
+
+const FILE_NAME: &str = "epoch-hooks.json";
+
+/// how long the webhook has to answer
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// how long the command has to finish before it is killed
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// how often the command is checked for completion
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Error)]
+enum Error {
+    #[error("cannot run the command '{command}'")]
+    Command {
+        command: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("the command '{command}' failed with {status}")]
+    CommandFailed { command: String, status: ExitStatus },
+    #[error("the command '{command}' did not finish in {timeout:?} and was killed")]
+    CommandTimedOut { command: String, timeout: Duration },
+    #[error("cannot post to the webhook")]
+    Webhook(#[from] reqwest::Error),
+}
+
+/// the path of the file of the latest epoch notified in the storage
+/// directory
+pub fn path<P: AsRef<Path>>(storage_dir: P) -> PathBuf {
+    storage_dir.as_ref().join(FILE_NAME)
+}
+
+/// an epoch the hooks were run for
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NotifiedEpoch {
+    pub epoch: u32,
+}
+
+impl Record for NotifiedEpoch {
+    type Key = u32;
+
+    fn key(&self) -> u32 {
+        self.epoch
+    }
+}
+
+/// the latest epoch the hooks were run for, the only one retained
+pub type NotifiedEpochs = JsonStore<NotifiedEpoch>;
+
+/// the summary of an epoch transition given to the hooks
+#[derive(Serialize)]
+pub struct EpochSummary {
+    pub epoch: u32,
+    pub accounting: EpochAccounting,
+    pub rewards: EpochRewardsInfo,
+}
+
+#[derive(Clone)]
+pub struct EpochHooks {
+    command: Option<String>,
+    webhook: Option<Url>,
+    client: reqwest::Client,
+    notified: NotifiedEpochs,
+    /// held while an epoch is claimed
+    claiming: Arc<Mutex<()>>,
+    logger: Logger,
+}
+
+impl EpochHooks {
+    pub fn new(settings: EpochHookSettings, notified: NotifiedEpochs, logger: Logger) -> Self {
+        EpochHooks {
+            command: settings.command,
+            webhook: settings.webhook,
+            client: reqwest::Client::new(),
+            notified,
+            claiming: Arc::new(Mutex::new(())),
+            logger,
+        }
+    }
+
+    /// run the hooks with the summary of the epoch transition, without
+    /// waiting for them to finish. The epochs already notified are skipped.
+    pub async fn notify(&self, summary: &EpochSummary) {
+        if !self.claim(summary.epoch).await {
+            debug!(self.logger, "epoch already notified"; "epoch" => summary.epoch);
+            return;
+        }
+
+        let json = match serde_json::to_vec(summary) {
+            Ok(json) => json,
+            Err(err) => {
+                error!(self.logger, "cannot serialize the epoch summary"; "reason" => %err);
+                return;
+            }
+        };
+        let epoch = summary.epoch;
+
+        if let Some(command) = self.command.clone() {
+            let json = json.clone();
+            let logger = self.logger.clone();
+            tokio::spawn(async move {
+                let result = tokio::task::spawn_blocking(move || {
+                    run_command(&command, &json, COMMAND_TIMEOUT)
+                })
+                .await
+                .expect("the epoch hook command does not panic");
+                match result {
+                    Ok(()) => info!(logger, "epoch hook command succeeded"; "epoch" => epoch),
+                    Err(err) => {
+                        warn!(logger, "epoch hook command failed"; "epoch" => epoch, "reason" => %err)
+                    }
+                }
+            });
+        }
+
+        if let Some(url) = self.webhook.clone() {
+            let request = self
+                .client
+                .post(url)
+                .header(CONTENT_TYPE, "application/json")
+                .timeout(WEBHOOK_TIMEOUT)
+                .body(json);
+            let logger = self.logger.clone();
+            tokio::spawn(async move {
+                let result = async {
+                    request.send().await?.error_for_status()?;
+                    Ok::<_, Error>(())
+                }
+                .await;
+                match result {
+                    Ok(()) => info!(logger, "epoch webhook notified"; "epoch" => epoch),
+                    Err(err) => {
+                        warn!(logger, "epoch webhook failed"; "epoch" => epoch, "reason" => %err)
+                    }
+                }
+            });
+        }
+    }
+
+    /// whether the epoch is to be notified, which it is only once and after
+    /// the epochs before it. The epoch is saved as notified before the hooks
+    /// run, so a crash does not notify it twice.
+    async fn claim(&self, epoch: u32) -> bool {
+        let _claiming = self.claiming.lock().await;
+        let last_epoch = self.notified.latest(1).await.first().map(|last| last.epoch);
+        if last_epoch.map_or(false, |last| last >= epoch) {
+            return false;
+        }
+        if let Err(err) = self.notified.record(NotifiedEpoch { epoch }).await {
+            warn!(self.logger, "cannot save the epoch notified"; "epoch" => epoch, "reason" => %err);
+        }
+        true
+    }
+}
+
+fn run_command(command: &str, input: &[u8], timeout: Duration) -> Result<(), Error> {
+    let command_error = |source| Error::Command {
+        command: command.to_owned(),
+        source,
+    };
+
+    let mut child = if cfg!(windows) {
+        Command::new("cmd")
+            .args(&["/C", command])
+            .stdin(Stdio::piped())
+            .spawn()
+    } else {
+        Command::new("sh")
+            .args(&["-c", command])
+            .stdin(Stdio::piped())
+            .spawn()
+    }
+    .map_err(command_error)?;
+
+    // written from another thread, so a command not reading its input does
+    // not block the node past the timeout
+    let writer = child.stdin.take().map(|mut stdin| {
+        let input = input.to_vec();
+        thread::spawn(move || stdin.write_all(&input))
+    });
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(command_error)? {
+            break status;
+        }
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(Error::CommandTimedOut {
+                command: command.to_owned(),
+                timeout,
+            });
+        }
+        thread::sleep(COMMAND_POLL_INTERVAL);
+    };
+
+    // the command may not read its input, which is not an error
+    if let Some(writer) = writer {
+        match writer.join().expect("the input writer does not panic") {
+            Err(err) if err.kind() != io::ErrorKind::BrokenPipe => {
+                return Err(command_error(err));
+            }
+            _ => (),
+        }
+    }
+
+    if !status.success() {
+        return Err(Error::CommandFailed {
+            command: command.to_owned(),
+            status,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn epoch_hooks(notified: NotifiedEpochs) -> EpochHooks {
+        EpochHooks::new(
+            EpochHookSettings {
+                command: None,
+                webhook: None,
+            },
+            notified,
+            Logger::root(slog::Discard, o!()),
+        )
+    }
+
+    #[tokio::test]
+    async fn epochs_are_claimed_once_and_in_order() {
+        let hooks = epoch_hooks(NotifiedEpochs::in_memory(Some(1)));
+        assert!(hooks.claim(3).await);
+        assert!(!hooks.claim(3).await);
+        assert!(!hooks.claim(2).await);
+        assert!(hooks.clone().claim(4).await);
+        assert!(!hooks.claim(4).await);
+    }
+
+    #[tokio::test]
+    async fn epochs_claimed_before_a_restart_are_not_claimed_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = path(dir.path());
+
+        let hooks = epoch_hooks(NotifiedEpochs::open(&path, Some(1)).unwrap());
+        assert!(hooks.claim(3).await);
+        drop(hooks);
+
+        let hooks = epoch_hooks(NotifiedEpochs::open(&path, Some(1)).unwrap());
+        assert!(!hooks.claim(3).await);
+        assert!(hooks.claim(4).await);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn command_reads_the_summary() {
+        let timeout = Duration::from_secs(10);
+        run_command("grep -q epoch", b"{\"epoch\":1}", timeout).unwrap();
+        match run_command("grep -q epoch", b"{}", timeout) {
+            Err(Error::CommandFailed { .. }) => (),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn command_ignoring_its_input_succeeds() {
+        run_command("true", &[0; 1 << 20], Duration::from_secs(10)).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hung_command_is_killed() {
+        let started = Instant::now();
+        match run_command("sleep 30", b"", Duration::from_millis(200)) {
+            Err(Error::CommandTimedOut { .. }) => (),
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+}
@@ -0,0 +1,76 @@
+//! actions deferred until the block they derive from is final
+//!
+//! The node switches to a fork of the chain as long as the fork does not
+//! roll back more than the epoch stability depth. What the node publishes
+//! about a block outside of its own state, like the notifications of the
+//! epoch hooks or the archive of the closed vote plans, waits until the
+//! block is that deep in the main chain, and is dropped if the block was
+//! abandoned meanwhile.
+
+use super::{EpochSummary, Ref, Storage};
+use crate::blockcfg::HeaderHash;
+use jormungandr_lib::interfaces::VotePlanStatus;
+use slog::Logger;
+use std::sync::{Arc, Mutex};
+
+pub enum FinalAction {
+    /// notify the epoch hooks of the epoch transition
+    EpochTransition(EpochSummary),
+    /// archive the final status of the vote plans closed by the block
+    ClosedVotePlans(Vec<VotePlanStatus>),
+}
+
+struct Pending {
+    block: HeaderHash,
+    chain_length: u32,
+    action: FinalAction,
+}
+
+#[derive(Clone, Default)]
+pub struct Finality(Arc<Mutex<Vec<Pending>>>);
+
+impl Finality {
+    /// run the action once the given block of the main chain is final
+    pub fn defer(&self, block: &Ref, action: FinalAction) {
+        self.0.lock().unwrap().push(Pending {
+            block: block.hash(),
+            chain_length: block.chain_length().into(),
+            action,
+        });
+    }
+
+    /// take the actions of the blocks which are final with the new tip,
+    /// in the order they were deferred. The actions of the blocks no longer
+    /// in the main chain are dropped.
+    pub fn settle(&self, logger: &Logger, storage: &Storage, new_tip: &Ref) -> Vec<FinalAction> {
+        let depth = new_tip.epoch_ledger_parameters().epoch_stability_depth;
+        let tip_length: u32 = new_tip.chain_length().into();
+        let deep_enough =
+            |pending: &Pending| pending.chain_length.saturating_add(depth) <= tip_length;
+
+        let settled: Vec<Pending> = {
+            let mut pending = self.0.lock().unwrap();
+            if !pending.iter().any(deep_enough) {
+                return Vec::new();
+            }
+            let (settled, remaining) = pending.drain(..).partition(deep_enough);
+            *pending = remaining;
+            settled
+        };
+
+        settled
+            .into_iter()
+            .filter_map(|pending| match storage.is_ancestor(pending.block, new_tip.hash()) {
+                Ok(Some(_)) => Some(pending.action),
+                Ok(None) => {
+                    debug!(logger, "dropping the deferred action of an abandoned block"; "block" => %pending.block);
+                    None
+                }
+                Err(err) => {
+                    warn!(logger, "cannot check whether the block is final"; "block" => %pending.block, "reason" => %err);
+                    None
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,131 @@
+//! in-memory index of the most recent headers of the main chain
+//!
+//! The peers catching up with the network ask for ranges of headers ending
+//! at the tip. Answering them from the storage means looking up the
+//! checkpoints and reading the whole blocks only to send their headers.
+//! The index keeps the headers of the latest blocks of the main chain,
+//! updated on every change of the tip, so these requests can be answered
+//! from memory.
+
+use crate::blockcfg::{Header, HeaderHash};
+use crate::blockchain::{Storage, StorageError};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+use tokio::sync::RwLock;
+
+/// The number of headers kept in the index
+const CAPACITY: usize = 10_000;
+
+#[derive(Clone)]
+pub struct HeaderChain(Arc<RwLock<internal::HeaderChain>>);
+
+impl Default for HeaderChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HeaderChain {
+    pub fn new() -> Self {
+        HeaderChain(Arc::new(RwLock::new(internal::HeaderChain {
+            headers: VecDeque::new(),
+            positions: HashMap::new(),
+        })))
+    }
+
+    /// follow the new tip of the main chain. When the tip switched to
+    /// another branch, the headers of the branch since the fork are read
+    /// from the storage.
+    pub async fn update(&self, storage: &Storage, tip: Header) -> Result<(), StorageError> {
+        // the headers are read from the storage without holding the lock,
+        // the index starting with the first tip it sees rather than
+        // reading the past headers
+        let mut branch = vec![tip];
+        loop {
+            let parent = branch.last().unwrap().block_parent_hash();
+            {
+                let chain = self.0.read().await;
+                if chain.headers.is_empty() || chain.position(&parent).is_some() {
+                    break;
+                }
+            }
+            if branch.len() >= CAPACITY {
+                break;
+            }
+            match storage.get(parent)? {
+                Some(block) => branch.push(block.header()),
+                None => break,
+            }
+        }
+        self.0.write().await.attach(branch);
+        Ok(())
+    }
+
+    pub async fn get(&self, id: HeaderHash) -> Option<Header> {
+        let chain = self.0.read().await;
+        chain
+            .position(&id)
+            .map(|position| chain.headers[position].clone())
+    }
+
+    /// the headers following the closest of the checkpoints up to `to`,
+    /// or `None` if `to` or all the checkpoints are out of the index
+    pub async fn range(&self, checkpoints: &[HeaderHash], to: HeaderHash) -> Option<Vec<Header>> {
+        let chain = self.0.read().await;
+        let to = chain.position(&to)?;
+        let from = checkpoints
+            .iter()
+            .filter_map(|checkpoint| chain.position(checkpoint))
+            .filter(|&position| position <= to)
+            .max()?;
+        Some(chain.headers.range(from + 1..=to).cloned().collect())
+    }
+}
+
+mod internal {
+    use super::*;
+
+    pub struct HeaderChain {
+        /// headers of the main chain, by increasing chain length
+        pub headers: VecDeque<Header>,
+        /// the chain length of the indexed headers
+        pub positions: HashMap<HeaderHash, u32>,
+    }
+
+    impl HeaderChain {
+        pub fn position(&self, id: &HeaderHash) -> Option<usize> {
+            let first = self.headers.front()?;
+            let chain_length = *self.positions.get(id)?;
+            Some((chain_length - u32::from(first.chain_length())) as usize)
+        }
+
+        /// attach the branch, given from the tip backwards, after its
+        /// parent, or replace the whole index if the parent is not in it
+        pub fn attach(&mut self, branch: Vec<Header>) {
+            let parent = branch.last().unwrap().block_parent_hash();
+            let len = self.position(&parent).map_or(0, |position| position + 1);
+            self.truncate(len);
+            for header in branch.into_iter().rev() {
+                self.push(header);
+            }
+        }
+
+        fn push(&mut self, header: Header) {
+            if self.headers.len() >= CAPACITY {
+                let oldest = self.headers.pop_front().unwrap();
+                self.positions.remove(&oldest.hash());
+            }
+            self.positions
+                .insert(header.hash(), header.chain_length().into());
+            self.headers.push_back(header);
+        }
+
+        fn truncate(&mut self, len: usize) {
+            for header in self.headers.drain(len..) {
+                self.positions.remove(&header.hash());
+            }
+        }
+    }
+}
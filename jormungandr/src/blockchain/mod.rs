@@ -1,14 +1,22 @@
+pub mod archive;
 mod branch;
 mod candidate;
 mod chain;
 mod chain_selection;
 mod checkpoints;
+pub mod epoch_accounting;
+pub mod epoch_hooks;
+mod finality;
+mod header_chain;
 mod multiverse;
 mod process;
 mod reference;
 mod reference_cache;
+pub mod rewards_history;
 mod storage;
 mod tip;
+pub mod vote_archive;
+mod vote_stats;
 
 // Constants
 
@@ -25,16 +33,23 @@ mod chunk_sizes {
 // Re-exports
 
 pub use self::{
-    branch::Branch,
+    branch::{Branch, BranchUpdates},
     chain::{
         new_epoch_leadership_from, Blockchain, CheckHeaderProof, EpochLeadership, Error, ErrorKind,
         PreCheckedHeader, MAIN_BRANCH_TAG,
     },
     chain_selection::{compare_against, ComparisonResult},
     checkpoints::Checkpoints,
+    epoch_accounting::EpochAccountingLog,
+    epoch_hooks::{EpochHooks, EpochSummary, NotifiedEpochs},
+    finality::{FinalAction, Finality},
+    header_chain::HeaderChain,
     multiverse::Multiverse,
     process::{process_new_ref, Process},
     reference::Ref,
+    rewards_history::RewardsHistory,
     storage::{Error as StorageError, Storage},
     tip::Tip,
+    vote_archive::{ActiveVotePlans, VotePlanArchive},
+    vote_stats::VoteStats,
 };
@@ -2,10 +2,11 @@ use super::{
     candidate,
     chain::{self, AppliedBlock, CheckHeaderProof},
     chain_selection::{self, ComparisonResult},
-    Blockchain, Error, ErrorKind, PreCheckedHeader, Ref, Tip, MAIN_BRANCH_TAG,
+    Blockchain, EpochSummary, Error, ErrorKind, FinalAction, PreCheckedHeader, Ref, Storage,
+    StorageError, Tip, MAIN_BRANCH_TAG,
 };
 use crate::{
-    blockcfg::{Block, FragmentId, Header, HeaderHash},
+    blockcfg::{Block, ChainLength, Fragment, FragmentId, Header, HeaderHash},
     blockchain::Checkpoints,
     intercom::{self, BlockMsg, ExplorerMsg, NetworkMsg, PropagateMsg, TransactionMsg},
     log,
@@ -20,12 +21,15 @@ use crate::{
     },
 };
 use chain_core::property::{Block as _, Fragment as _, HasHeader as _, Header as _};
-use jormungandr_lib::interfaces::FragmentStatus;
+use jormungandr_lib::interfaces::{
+    EpochAccounting, EpochRewardsInfo, FragmentStatus, VotePlanStatus,
+};
 
 use futures::prelude::*;
 use slog::Logger;
+use tracing_futures::Instrument;
 
-use std::{sync::Arc, time::Duration};
+use std::{collections::HashSet, sync::Arc, time::Duration};
 
 type PullHeadersScheduler = FireForgetScheduler<HeaderHash, Address, Checkpoints>;
 type GetNextBlockScheduler = FireForgetScheduler<HeaderHash, Address, ()>;
@@ -37,6 +41,10 @@ const DEFAULT_TIMEOUT_PROCESS_ANNOUNCEMENT: u64 = 5;
 const DEFAULT_TIMEOUT_PROCESS_BLOCKS: u64 = 60;
 const DEFAULT_TIMEOUT_PROCESS_HEADERS: u64 = 60;
 
+/// blocks further than this from the tip are caught up on rather than
+/// propagated, so they are left out of the block propagation stats
+const PROPAGATION_MAX_TIP_DISTANCE: u32 = 2;
+
 const PULL_HEADERS_SCHEDULER_CONFIG: FireForgetSchedulerConfig = FireForgetSchedulerConfig {
     max_running: 16,
     max_running_same_task: 2,
@@ -104,6 +112,12 @@ impl Process {
 
                 info!(logger, "receiving block from leadership service");
 
+                let span = tracing::info_span!(
+                    "leadership_block",
+                    hash = %block.header.hash(),
+                    date = %block.header.block_date(),
+                );
+
                 info.timeout_spawn_fallible(
                     "process leadership block",
                     Duration::from_secs(DEFAULT_TIMEOUT_PROCESS_LEADERSHIP),
@@ -116,7 +130,8 @@ impl Process {
                         explorer_msg_box,
                         block,
                         stats_counter,
-                    ),
+                    )
+                    .instrument(span),
                 )
             }
             BlockMsg::AnnouncedBlock(header, node_id) => {
@@ -128,6 +143,12 @@ impl Process {
 
                 info!(logger, "received block announcement from network");
 
+                let span = tracing::info_span!(
+                    "block_announcement",
+                    hash = %header.hash(),
+                    peer = %node_id,
+                );
+
                 info.timeout_spawn_fallible(
                     "process block announcement",
                     Duration::from_secs(DEFAULT_TIMEOUT_PROCESS_ANNOUNCEMENT),
@@ -138,14 +159,19 @@ impl Process {
                         node_id,
                         pull_headers_scheduler.clone(),
                         get_next_block_scheduler.clone(),
+                        stats_counter,
                         logger.clone(),
-                    ),
+                    )
+                    .instrument(span),
                 )
             }
             BlockMsg::NetworkBlocks(handle) => {
-                info!(info.logger(), "receiving block stream from network");
+                let logger = info
+                    .logger()
+                    .new(o!(log::KEY_TRACE_ID => handle.trace_id()));
+
+                info!(logger, "receiving block stream from network");
 
-                let logger = info.logger().clone();
                 let get_next_block_scheduler = get_next_block_scheduler.clone();
 
                 info.timeout_spawn_fallible(
@@ -161,13 +187,18 @@ impl Process {
                         handle,
                         stats_counter,
                         logger,
-                    ),
+                    )
+                    .instrument(tracing::info_span!("network_blocks")),
                 );
             }
             BlockMsg::ChainHeaders(handle) => {
-                info!(info.logger(), "receiving header stream from network");
+                let logger = info.logger().new(o!(
+                    log::KEY_SUB_TASK => "chain_pull",
+                    log::KEY_TRACE_ID => handle.trace_id(),
+                ));
+
+                info!(logger, "receiving header stream from network");
 
-                let logger = info.logger().new(o!(log::KEY_SUB_TASK => "chain_pull"));
                 let pull_headers_scheduler = pull_headers_scheduler.clone();
 
                 info.timeout_spawn(
@@ -188,12 +219,20 @@ impl Process {
     fn start_branch_reprocessing(&self, info: &TokioServiceInfo) {
         let tip = self.blockchain_tip.clone();
         let blockchain = self.blockchain.clone();
+        let tx_msg_box = self.fragment_msgbox.clone();
         let logger = info.logger().clone();
 
         info.run_periodic_fallible(
             "branch reprocessing",
             BRANCH_REPROCESSING_INTERVAL,
-            move || reprocess_tip(logger.clone(), blockchain.clone(), tip.clone()),
+            move || {
+                reprocess_tip(
+                    logger.clone(),
+                    blockchain.clone(),
+                    tip.clone(),
+                    tx_msg_box.clone(),
+                )
+            },
         )
     }
 
@@ -276,10 +315,76 @@ fn try_request_fragment_removal(
     tx_msg_box.try_send(TransactionMsg::RemoveTransactions(fragment_ids, status))
 }
 
+/// collect the fragments of the blocks rolled back when switching the tip
+/// from `old_tip` to `new_tip`, leaving out the fragments also included in
+/// the blocks of the new branch.
+fn rolled_back_fragments(
+    storage: &Storage,
+    old_tip: HeaderHash,
+    new_tip: HeaderHash,
+) -> Result<Vec<Fragment>, StorageError> {
+    let mut rolled_back = Vec::new();
+    let mut current = old_tip;
+    while storage
+        .find_closest_ancestor(vec![current], new_tip)?
+        .is_none()
+    {
+        let block = storage.get(current)?.ok_or(StorageError::BlockNotFound)?;
+        rolled_back.extend(block.fragments().cloned());
+        current = block.header.block_parent_hash();
+    }
+    let common_ancestor = current;
+
+    let mut in_new_branch = HashSet::new();
+    let mut current = new_tip;
+    while current != common_ancestor {
+        let block = storage.get(current)?.ok_or(StorageError::BlockNotFound)?;
+        in_new_branch.extend(block.fragments().map(|fragment| fragment.id()));
+        current = block.header.block_parent_hash();
+    }
+
+    rolled_back.retain(|fragment| !in_new_branch.contains(&fragment.id()));
+    Ok(rolled_back)
+}
+
+/// send the fragments from the blocks abandoned by a branch switch back to
+/// the fragment pool, so they can be included in the blocks of the new branch
+fn try_request_fragment_restore(
+    logger: &Logger,
+    storage: &Storage,
+    tx_msg_box: &mut MessageBox<TransactionMsg>,
+    old_tip: &Ref,
+    new_tip: &Ref,
+) {
+    match rolled_back_fragments(storage, old_tip.hash(), new_tip.hash()) {
+        Ok(fragments) if fragments.is_empty() => (),
+        Ok(fragments) => {
+            debug!(
+                logger,
+                "returning {} fragments from the abandoned branch to the pool",
+                fragments.len()
+            );
+            tx_msg_box
+                .try_send(TransactionMsg::RestoreTransactions(fragments))
+                .unwrap_or_else(
+                    |err| error!(logger, "cannot return fragments to the pool"; "reason" => %err),
+                );
+        }
+        Err(err) => {
+            error!(logger, "cannot collect the fragments of the abandoned branch"; "reason" => %err)
+        }
+    }
+}
+
 /// this function will re-process the tip against the different branches
 /// this is because a branch may have become more interesting with time
 /// moving forward and branches may have been dismissed
-async fn reprocess_tip(logger: Logger, mut blockchain: Blockchain, tip: Tip) -> Result<(), Error> {
+async fn reprocess_tip(
+    logger: Logger,
+    mut blockchain: Blockchain,
+    tip: Tip,
+    mut tx_msg_box: MessageBox<TransactionMsg>,
+) -> Result<(), Error> {
     let branches: Vec<Arc<Ref>> = blockchain.branches().branches().await;
 
     let tip_as_ref = tip.get_ref().await;
@@ -290,7 +395,14 @@ async fn reprocess_tip(logger: Logger, mut blockchain: Blockchain, tip: Tip) ->
         .collect::<Vec<_>>();
 
     for other in others {
-        process_new_ref(&logger, &mut blockchain, tip.clone(), Arc::clone(other)).await?
+        process_new_ref_and_restore(
+            &logger,
+            &mut blockchain,
+            tip.clone(),
+            Arc::clone(other),
+            &mut tx_msg_box,
+        )
+        .await?
     }
 
     Ok(())
@@ -308,9 +420,37 @@ async fn reprocess_tip(logger: Logger, mut blockchain: Blockchain, tip: Tip) ->
 pub async fn process_new_ref(
     logger: &Logger,
     blockchain: &mut Blockchain,
-    mut tip: Tip,
+    tip: Tip,
     candidate: Arc<Ref>,
 ) -> Result<(), Error> {
+    update_tip(logger, blockchain, tip, candidate)
+        .await
+        .map(|_| ())
+}
+
+/// like `process_new_ref`, also returning the fragments of the blocks
+/// abandoned when switching to another branch to the fragment pool.
+async fn process_new_ref_and_restore(
+    logger: &Logger,
+    blockchain: &mut Blockchain,
+    tip: Tip,
+    candidate: Arc<Ref>,
+    tx_msg_box: &mut MessageBox<TransactionMsg>,
+) -> Result<(), Error> {
+    let new_tip = Arc::clone(&candidate);
+    if let Some(old_tip) = update_tip(logger, blockchain, tip, candidate).await? {
+        try_request_fragment_restore(logger, blockchain.storage(), tx_msg_box, &old_tip, &new_tip);
+    }
+    Ok(())
+}
+
+/// returns the previous tip if the tip was switched to another branch
+async fn update_tip(
+    logger: &Logger,
+    blockchain: &mut Blockchain,
+    mut tip: Tip,
+    candidate: Arc<Ref>,
+) -> Result<Option<Arc<Ref>>, Error> {
     let candidate_hash = candidate.hash();
     let tip_ref = tip.get_ref().await;
 
@@ -322,6 +462,7 @@ pub async fn process_new_ref(
                 candidate.header().description(),
                 tip_ref.header().description(),
             );
+            Ok(None)
         }
         ComparisonResult::PreferCandidate => {
             if tip_ref.hash() == candidate.block_parent_hash() {
@@ -337,7 +478,16 @@ pub async fn process_new_ref(
                     .put_tag(MAIN_BRANCH_TAG, candidate_hash)
                     .map_err(|e| Error::with_chain(e, "Cannot update the main storage's tip"))?;
 
+                record_epoch_transitions(logger, blockchain, &tip_ref, &candidate).await;
+                update_header_chain(logger, blockchain, &candidate).await;
+                defer_closed_vote_plans(blockchain, &tip_ref, &candidate);
+                if let Some(vote_stats) = blockchain.vote_stats() {
+                    let statuses = blockchain.active_vote_plans().at(&candidate);
+                    vote_stats.push(&candidate, &statuses).await;
+                }
+                run_final_actions(logger, blockchain, &candidate).await;
                 tip.update_ref(candidate).await;
+                Ok(None)
             } else {
                 info!(
                     logger,
@@ -351,13 +501,159 @@ pub async fn process_new_ref(
                     .put_tag(MAIN_BRANCH_TAG, candidate_hash)
                     .map_err(|e| Error::with_chain(e, "Cannot update the main storage's tip"))?;
 
+                record_epoch_transitions(logger, blockchain, &tip_ref, &candidate).await;
+                update_header_chain(logger, blockchain, &candidate).await;
+                defer_closed_vote_plans(blockchain, &tip_ref, &candidate);
+                if let Some(vote_stats) = blockchain.vote_stats() {
+                    let statuses = blockchain.active_vote_plans().at(&candidate);
+                    if let Err(err) = vote_stats
+                        .switch_branch(blockchain.storage(), &candidate, &statuses)
+                        .await
+                    {
+                        warn!(logger, "cannot update the vote plan statistics"; "reason" => %err);
+                    }
+                }
+                run_final_actions(logger, blockchain, &candidate).await;
                 let branch = blockchain.branches_mut().apply_or_create(candidate).await;
                 tip.swap(branch).await;
+                Ok(Some(tip_ref))
             }
         }
     }
+}
 
-    Ok(())
+/// archive the final status of the vote plans whose committee period ended
+/// between the previous tip and the new one, once the new tip is final
+fn defer_closed_vote_plans(blockchain: &Blockchain, previous_tip: &Ref, new_tip: &Ref) {
+    let previously_active = blockchain.active_vote_plans().at(previous_tip);
+    let still_active = Arc::new(new_tip.active_vote_plans());
+    blockchain
+        .active_vote_plans()
+        .set(new_tip, Arc::clone(&still_active));
+
+    let date = new_tip.block_date();
+    let closed: Vec<VotePlanStatus> = previously_active
+        .iter()
+        .filter(|status| status.committee_end <= date)
+        .filter(|status| !still_active.iter().any(|active| active.id == status.id))
+        .cloned()
+        .map(VotePlanStatus::from)
+        .collect();
+    if !closed.is_empty() {
+        blockchain
+            .finality()
+            .defer(new_tip, FinalAction::ClosedVotePlans(closed));
+    }
+}
+
+/// record the epoch transitions of the chain of the new tip which are not on
+/// the chain of the previous tip. The tip may move by many epochs at once,
+/// when bootstrapping or switching to another branch.
+async fn record_epoch_transitions(
+    logger: &Logger,
+    blockchain: &Blockchain,
+    previous_tip: &Arc<Ref>,
+    new_tip: &Arc<Ref>,
+) {
+    for epoch_ref in started_epochs(previous_tip, new_tip) {
+        record_epoch_transition(logger, blockchain, &epoch_ref).await;
+    }
+}
+
+/// a block of each epoch of the chain of the new tip which did not start
+/// the same way on the chain of the previous tip, oldest first. Nothing is
+/// returned while the tip stays in the same epoch of the same chain.
+fn started_epochs(previous_tip: &Arc<Ref>, new_tip: &Arc<Ref>) -> Vec<Arc<Ref>> {
+    fn epoch_start(block: &Ref) -> Option<HeaderHash> {
+        block.last_ref_previous_epoch().map(|last| last.hash())
+    }
+
+    let mut started = Vec::new();
+    let mut previous = Some(Arc::clone(previous_tip));
+    let mut current = Some(Arc::clone(new_tip));
+    while let Some(block) = current {
+        let epoch = block.block_date().epoch;
+        while let Some(previous_block) = previous
+            .as_ref()
+            .filter(|previous_block| previous_block.block_date().epoch > epoch)
+        {
+            previous = previous_block.last_ref_previous_epoch().cloned();
+        }
+        let same_start = previous.as_ref().map_or(false, |previous_block| {
+            previous_block.block_date().epoch == epoch
+                && epoch_start(previous_block) == epoch_start(&block)
+        });
+        if same_start {
+            break;
+        }
+        current = block.last_ref_previous_epoch().cloned();
+        started.push(block);
+    }
+    started.reverse();
+    started
+}
+
+/// keep the rewards distributed at the start of the epoch of the block and
+/// the accounting of the value flows of the epoch transition
+async fn record_epoch_transition(logger: &Logger, blockchain: &Blockchain, epoch_ref: &Ref) {
+    if let Some(rewards_info) = epoch_ref.epoch_rewards_info() {
+        let epoch = epoch_ref.block_date().epoch;
+        let info = EpochRewardsInfo::from(epoch, rewards_info.as_ref());
+        let accounting = EpochAccounting::from(&info);
+        if blockchain.epoch_hooks().is_some() {
+            let summary = EpochSummary {
+                epoch,
+                accounting: accounting.clone(),
+                rewards: info.clone(),
+            };
+            blockchain
+                .finality()
+                .defer(epoch_ref, FinalAction::EpochTransition(summary));
+        }
+        if let Err(err) = blockchain.epoch_accounting().record(accounting).await {
+            warn!(logger, "cannot record the epoch accounting"; "epoch" => epoch, "reason" => %err);
+        }
+        if let Err(err) = blockchain.rewards_history().record(info).await {
+            warn!(logger, "cannot record the rewards history"; "epoch" => epoch, "reason" => %err);
+        }
+    }
+}
+
+/// run the actions deferred until their blocks are final, which the new
+/// tip made final
+async fn run_final_actions(logger: &Logger, blockchain: &Blockchain, new_tip: &Ref) {
+    for action in blockchain
+        .finality()
+        .settle(logger, blockchain.storage(), new_tip)
+    {
+        match action {
+            FinalAction::EpochTransition(summary) => {
+                if let Some(hooks) = blockchain.epoch_hooks() {
+                    hooks.notify(&summary).await;
+                }
+            }
+            FinalAction::ClosedVotePlans(closed) => {
+                for status in closed {
+                    let id = status.id;
+                    if let Err(err) = blockchain.vote_plan_archive().record(status).await {
+                        warn!(logger, "cannot archive the vote plan"; "id" => %id, "reason" => %err);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// follow the new tip in the index of the headers served to the peers
+async fn update_header_chain(logger: &Logger, blockchain: &Blockchain, new_tip: &Ref) {
+    let header = new_tip.header().clone();
+    if let Err(err) = blockchain
+        .header_chain()
+        .update(blockchain.storage(), header)
+        .await
+    {
+        warn!(logger, "cannot update the index of the headers"; "reason" => %err);
+    }
 }
 
 async fn process_and_propagate_new_ref(
@@ -365,12 +661,13 @@ async fn process_and_propagate_new_ref(
     blockchain: &mut Blockchain,
     tip: Tip,
     new_block_ref: Arc<Ref>,
+    tx_msg_box: &mut MessageBox<TransactionMsg>,
     mut network_msg_box: MessageBox<NetworkMsg>,
 ) -> Result<(), Error> {
     let header = new_block_ref.header().clone();
     debug!(logger, "processing the new block and propagating");
 
-    process_new_ref(logger, blockchain, tip, new_block_ref).await?;
+    process_new_ref_and_restore(logger, blockchain, tip, new_block_ref, tx_msg_box).await?;
 
     debug!(logger, "propagating block to the network");
     network_msg_box
@@ -405,6 +702,7 @@ async fn process_leadership_block(
         &mut blockchain,
         blockchain_tip,
         Arc::clone(&new_block_ref),
+        &mut tx_msg_box,
         network_msg_box,
     )
     .await?;
@@ -457,6 +755,7 @@ async fn process_leadership_block_inner(
     Ok(new_ref)
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn process_block_announcement(
     blockchain: Blockchain,
     blockchain_tip: Tip,
@@ -464,6 +763,7 @@ async fn process_block_announcement(
     node_id: Address,
     mut pull_headers_scheduler: PullHeadersScheduler,
     mut get_next_block_scheduler: GetNextBlockScheduler,
+    stats_counter: StatsCounter,
     logger: Logger,
 ) -> Result<(), Error> {
     let pre_checked = blockchain
@@ -478,7 +778,7 @@ async fn process_block_announcement(
         PreCheckedHeader::MissingParent { header, .. } => {
             debug!(logger, "block is missing a locally stored parent");
             let to = header.hash();
-            let from = blockchain.get_checkpoints(blockchain_tip.branch()).await;
+            let from = blockchain.get_checkpoints(blockchain_tip.branch()).await?;
             pull_headers_scheduler
                 .schedule(to, node_id, from)
                 .unwrap_or_else(move |err| {
@@ -489,14 +789,32 @@ async fn process_block_announcement(
                 });
             Ok(())
         }
-        PreCheckedHeader::HeaderWithCache {
-            header,
-            parent_ref: _,
-        } => {
+        PreCheckedHeader::HeaderWithCache { header, parent_ref } => {
             debug!(
                 logger,
                 "Announced block has a locally stored parent, fetch it"
             );
+            let tip_length = blockchain_tip.get_ref().await.chain_length();
+            if is_near_tip(header.chain_length(), tip_length) {
+                let post_checked = blockchain
+                    .post_check_header(
+                        header.clone(),
+                        Arc::clone(&parent_ref),
+                        CheckHeaderProof::Enabled,
+                    )
+                    .await
+                    .map_err(|err| Error::with_chain(err, "cannot process block announcement"))?;
+                let first = stats_counter
+                    .record_block_announcement(post_checked.header().hash(), node_id.to_string());
+                // a new block is announced right after the start of its slot,
+                // the delay of the first announcement tells how far the clock
+                // of the node is off
+                if first {
+                    if let Some(slot_time) = parent_ref.slot_time_at(&header.block_date()) {
+                        stats_counter.record_peer_clock_delay(slot_time);
+                    }
+                }
+            }
             get_next_block_scheduler
                 .schedule(header.id(), node_id, ())
                 .unwrap_or_else(move |err| {
@@ -523,6 +841,7 @@ async fn process_network_blocks(
     logger: Logger,
 ) -> Result<(), Error> {
     let (mut stream, reply) = handle.into_stream_and_reply();
+    let tip_length = blockchain_tip.get_ref().await.chain_length();
     let mut candidate = None;
     let mut latest_block: Option<Arc<Block>> = None;
 
@@ -543,6 +862,9 @@ async fn process_network_blocks(
                 match res {
                     Ok(Some(r)) => {
                         stats_counter.add_block_recv_cnt(1);
+                        if is_near_tip(r.chain_length(), tip_length) {
+                            stats_counter.record_block_arrival(r.time());
+                        }
                         stream = stream_tail;
                         candidate = Some(r);
                     }
@@ -573,12 +895,19 @@ async fn process_network_blocks(
             process_and_propagate_new_ref(
                 &logger,
                 &mut blockchain,
-                blockchain_tip,
+                blockchain_tip.clone(),
                 Arc::clone(&new_block_ref),
+                &mut tx_msg_box,
                 network_msg_box,
             )
             .await?;
 
+            if blockchain_tip.get_ref().await.hash() == new_block_ref.hash()
+                && is_near_tip(new_block_ref.chain_length(), tip_length)
+            {
+                stats_counter.record_block_adoption(new_block_ref.time());
+            }
+
             // Add block if found
             if let Some(b) = latest_block {
                 stats_counter.set_tip_block(b);
@@ -589,6 +918,13 @@ async fn process_network_blocks(
     }
 }
 
+/// whether a block is close enough to the tip to be counted in the block
+/// propagation stats
+fn is_near_tip(chain_length: ChainLength, tip_length: ChainLength) -> bool {
+    let (length, tip_length) = (u32::from(chain_length), u32::from(tip_length));
+    length.max(tip_length) - length.min(tip_length) <= PROPAGATION_MAX_TIP_DISTANCE
+}
+
 async fn process_network_block(
     blockchain: &Blockchain,
     block: Block,
@@ -766,3 +1102,19 @@ fn chain_header_error_into_reply(err: candidate::Error) -> intercom::Error {
         HeaderChainVerificationFailed(e) => intercom::Error::invalid_argument(e),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_blocks_near_the_tip_are_counted() {
+        let tip = ChainLength::from(100);
+        assert!(is_near_tip(ChainLength::from(101), tip));
+        assert!(is_near_tip(ChainLength::from(100), tip));
+        assert!(is_near_tip(ChainLength::from(98), tip));
+        assert!(!is_near_tip(ChainLength::from(97), tip));
+        assert!(!is_near_tip(ChainLength::from(103), tip));
+        assert!(!is_near_tip(ChainLength::from(5), tip));
+    }
+}
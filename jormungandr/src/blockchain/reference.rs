@@ -154,6 +154,17 @@ impl Ref {
         }
     }
 
+    /// the start time of the slot at the given date, in the era of this
+    /// block, e.g. for a child block not validated yet
+    pub fn slot_time_at(&self, date: &BlockDate) -> Option<SystemTime> {
+        let era = self.epoch_leadership_schedule().era();
+        let slot = era.from_era_to_slot(EpochPosition {
+            epoch: Epoch(date.epoch),
+            slot: EpochSlotOffset(date.slot_id),
+        });
+        self.time_frame().slot_to_systemtime(slot)
+    }
+
     /// retrieve the time of the slot of the block. If the block is set
     /// in the future, this function will return an error.
     pub fn elapsed(&self) -> Result<Duration, std::time::SystemTimeError> {
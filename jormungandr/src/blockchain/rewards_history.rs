@@ -0,0 +1,32 @@
+//! history of the rewards distributed at the start of each epoch
+//!
+//! The rewards are only computed at the epoch transitions, and the
+//! references holding them are not kept in memory forever nor rebuilt from
+//! the storage after a restart. This history keeps the rewards of the most
+//! recent epochs, persisted in the storage directory when there is one.
+
+use crate::{
+    blockcfg::Epoch,
+    utils::json_store::{JsonStore, Record},
+};
+use jormungandr_lib::interfaces::EpochRewardsInfo;
+use std::path::{Path, PathBuf};
+
+const FILE_NAME: &str = "rewards-history.json";
+
+/// the path of the history file in the storage directory
+pub fn path<P: AsRef<Path>>(storage_dir: P) -> PathBuf {
+    storage_dir.as_ref().join(FILE_NAME)
+}
+
+/// the rewards by epoch. The rewards recorded for an epoch replace the ones
+/// recorded for a fork of the chain.
+pub type RewardsHistory = JsonStore<EpochRewardsInfo>;
+
+impl Record for EpochRewardsInfo {
+    type Key = Epoch;
+
+    fn key(&self) -> Epoch {
+        self.epoch()
+    }
+}
@@ -219,6 +219,25 @@ impl Storage {
         handle.start_sending().send_all(&mut stream).await
     }
 
+    /// The ids of the `length` blocks of the branch ending at `to`,
+    /// in chain order. Only the block index is read, not the blocks.
+    pub fn branch_ids(&self, to: HeaderHash, length: u32) -> Result<Vec<HeaderHash>, Error> {
+        if length == 0 {
+            return Ok(Vec::new());
+        }
+        let mut ids = vec![to];
+        while ids.len() < length as usize {
+            let info = self
+                .storage
+                .get_block_info(ids.last().unwrap().as_bytes())?;
+            let parent_id =
+                HeaderHash::deserialize(info.parent_id().as_ref()).map_err(Error::Deserialize)?;
+            ids.push(parent_id);
+        }
+        ids.reverse();
+        Ok(ids)
+    }
+
     pub fn find_closest_ancestor(
         &self,
         checkpoints: Vec<HeaderHash>,
@@ -262,6 +281,41 @@ impl Storage {
         }))
     }
 
+    /// distance from `ancestor` to `descendant`, or `None` if the first
+    /// block is not an ancestor of the second one
+    pub fn is_ancestor(
+        &self,
+        ancestor: HeaderHash,
+        descendant: HeaderHash,
+    ) -> Result<Option<u32>, Error> {
+        self.storage
+            .is_ancestor(ancestor.as_bytes(), descendant.as_bytes())
+            .map_err(Into::into)
+    }
+
+    /// The ids of the ancestors of `descendant` at the given distances, in
+    /// increasing order. The branch is walked once, reading only the block
+    /// index, not the blocks.
+    pub fn ancestor_ids(
+        &self,
+        descendant: HeaderHash,
+        distances: &[u32],
+    ) -> Result<Vec<HeaderHash>, Error> {
+        let mut ids = Vec::with_capacity(distances.len());
+        let mut current = descendant;
+        let mut walked = 0;
+        for &distance in distances {
+            while walked < distance {
+                let info = self.storage.get_block_info(current.as_bytes())?;
+                current = HeaderHash::deserialize(info.parent_id().as_ref())
+                    .map_err(Error::Deserialize)?;
+                walked += 1;
+            }
+            ids.push(current);
+        }
+        Ok(ids)
+    }
+
     pub fn gc(&self, threshold_depth: u32, main_branch_tip: &[u8]) -> Result<(), Error> {
         let main_info = self.storage.get_block_info(main_branch_tip)?;
         let threshold_length = match main_info.chain_length().checked_sub(threshold_depth) {
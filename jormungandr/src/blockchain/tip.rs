@@ -1,4 +1,4 @@
-use crate::blockchain::{Branch, Ref};
+use crate::blockchain::{Branch, BranchUpdates, Ref};
 use std::sync::Arc;
 
 #[derive(Clone)]
@@ -29,4 +29,10 @@ impl Tip {
     pub fn branch(&self) -> &Branch {
         &self.branch
     }
+
+    /// follow the moves of the tip, for the tasks which would otherwise
+    /// poll it
+    pub fn updates(&self) -> BranchUpdates {
+        self.branch.updates()
+    }
 }
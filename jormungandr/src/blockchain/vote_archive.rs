@@ -0,0 +1,57 @@
+//! final status of the vote plans closed on the main chain
+//!
+//! A vote plan is dropped from the ledger once its committee period is
+//! over, taking the tally with it. The last status of the plan, final
+//! tally included, is kept here once the block closing the plan is final,
+//! persisted in the storage directory when there is one.
+
+use super::Ref;
+use crate::{
+    blockcfg::HeaderHash,
+    utils::json_store::{JsonStore, Record},
+};
+use chain_impl_mockchain::vote;
+use jormungandr_lib::{crypto::hash::Hash, interfaces::VotePlanStatus};
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+const FILE_NAME: &str = "vote-plan-archive.json";
+
+/// the path of the archive file in the storage directory
+pub fn path<P: AsRef<Path>>(storage_dir: P) -> PathBuf {
+    storage_dir.as_ref().join(FILE_NAME)
+}
+
+/// the final status of the closed vote plans, by vote plan id
+pub type VotePlanArchive = JsonStore<VotePlanStatus>;
+
+impl Record for VotePlanStatus {
+    type Key = Hash;
+
+    fn key(&self) -> Hash {
+        self.id
+    }
+}
+
+/// the vote plans active at the tip, kept so the ones closed by the next
+/// block are found without computing them again
+#[derive(Clone, Default)]
+pub struct ActiveVotePlans(Arc<Mutex<Option<(HeaderHash, Arc<Vec<vote::VotePlanStatus>>)>>>);
+
+impl ActiveVotePlans {
+    /// the vote plans active at the block, computed if not the cached ones
+    pub fn at(&self, block: &Ref) -> Arc<Vec<vote::VotePlanStatus>> {
+        match &*self.0.lock().unwrap() {
+            Some((hash, plans)) if *hash == block.hash() => return Arc::clone(plans),
+            _ => (),
+        }
+        Arc::new(block.active_vote_plans())
+    }
+
+    /// keep the vote plans active at the block, the new tip
+    pub fn set(&self, block: &Ref, plans: Arc<Vec<vote::VotePlanStatus>>) {
+        *self.0.lock().unwrap() = Some((block.hash(), plans));
+    }
+}
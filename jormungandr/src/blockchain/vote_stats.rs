@@ -0,0 +1,142 @@
+//! time series of the number of votes cast on the proposals of the active
+//! vote plans
+//!
+//! The dashboards of a voting event chart the participation over time,
+//! which would otherwise mean replaying all the vote cast fragments. The
+//! counts are sampled when the tip of the main chain changes, a sample being
+//! only recorded when the counts differ from the previous one. The series of
+//! a vote plan is dropped once the plan is no longer active.
+
+use crate::{
+    blockcfg::HeaderHash,
+    blockchain::{Ref, Storage, StorageError},
+};
+use chain_impl_mockchain::{certificate::VotePlanId, vote::VotePlanStatus};
+use jormungandr_lib::interfaces::{VoteCountSample, VotePlanStats};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+};
+use tokio::sync::RwLock;
+
+#[derive(Clone)]
+pub struct VoteStats(Arc<RwLock<internal::VoteStats>>);
+
+impl VoteStats {
+    /// keep at most the given number of samples for each vote plan
+    pub fn new(capacity: usize) -> Self {
+        VoteStats(Arc::new(RwLock::new(internal::VoteStats {
+            capacity,
+            plans: HashMap::new(),
+        })))
+    }
+
+    /// sample the counts of the vote plans active at the new tip extending
+    /// the main chain
+    pub async fn push(&self, tip: &Ref, statuses: &[VotePlanStatus]) {
+        self.0.write().await.push(tip, statuses)
+    }
+
+    /// sample the counts at the new tip of another branch, dropping the
+    /// samples of the blocks abandoned with the previous branch
+    pub async fn switch_branch(
+        &self,
+        storage: &Storage,
+        tip: &Ref,
+        statuses: &[VotePlanStatus],
+    ) -> Result<(), StorageError> {
+        // the blocks of the samples are looked up in the storage without
+        // holding the lock
+        let blocks: Vec<Vec<HeaderHash>> = self
+            .0
+            .read()
+            .await
+            .plans
+            .values()
+            .map(|samples| {
+                samples
+                    .iter()
+                    .rev()
+                    .map(|sample| sample.block.into_hash())
+                    .collect()
+            })
+            .collect();
+        let mut abandoned = HashSet::new();
+        for blocks in blocks {
+            for block in blocks {
+                if storage.is_ancestor(block, tip.hash())?.is_some() {
+                    break;
+                }
+                abandoned.insert(block);
+            }
+        }
+
+        let mut stats = self.0.write().await;
+        for samples in stats.plans.values_mut() {
+            while let Some(last) = samples.back() {
+                if !abandoned.contains(&last.block.into_hash()) {
+                    break;
+                }
+                samples.pop_back();
+            }
+        }
+        stats.push(tip, statuses);
+        Ok(())
+    }
+
+    pub async fn get(&self, id: &VotePlanId) -> Option<VotePlanStats> {
+        self.0
+            .read()
+            .await
+            .plans
+            .get(id)
+            .map(|samples| VotePlanStats {
+                id: id.clone().into(),
+                samples: samples.iter().cloned().collect(),
+            })
+    }
+}
+
+mod internal {
+    use super::*;
+
+    pub struct VoteStats {
+        pub capacity: usize,
+        pub plans: HashMap<VotePlanId, VecDeque<VoteCountSample>>,
+    }
+
+    impl VoteStats {
+        pub fn push(&mut self, tip: &Ref, statuses: &[VotePlanStatus]) {
+            self.plans
+                .retain(|id, _| statuses.iter().any(|status| &status.id == id));
+
+            let chain_length = u32::from(tip.chain_length());
+            for status in statuses {
+                let votes_cast: Vec<usize> = status
+                    .proposals
+                    .iter()
+                    .map(|proposal| proposal.votes.size())
+                    .collect();
+                let samples = self
+                    .plans
+                    .entry(status.id.clone())
+                    .or_insert_with(VecDeque::new);
+                if samples
+                    .back()
+                    .map_or(false, |last| last.votes_cast == votes_cast)
+                {
+                    continue;
+                }
+                samples.push_back(VoteCountSample {
+                    chain_length,
+                    block: tip.hash().into(),
+                    date: tip.block_date(),
+                    votes_cast,
+                });
+                if samples.len() > self.capacity {
+                    samples.pop_front();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! cache of the blocks recently served to the peers
+//!
+//! The same blocks tend to be requested by many peers in a short time: the
+//! blocks near the tip, or the blocks missed by all the peers during an
+//! outage of the network. Keeping them in memory spares the storage from
+//! reading and deserializing them again for every request.
+
+use crate::blockcfg::{Block, HeaderHash};
+use crate::blockchain::{Storage, StorageError};
+use crate::stats_counter::StatsCounter;
+use lru::LruCache;
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone)]
+pub struct BlockCache {
+    // `None` when the cache is disabled with a capacity of 0
+    inner: Option<Arc<Mutex<LruCache<HeaderHash, Block>>>>,
+    capacity: usize,
+    stats_counter: StatsCounter,
+}
+
+impl BlockCache {
+    pub fn new(capacity: usize, stats_counter: StatsCounter) -> Self {
+        let inner = if capacity > 0 {
+            Some(Arc::new(Mutex::new(LruCache::new(capacity))))
+        } else {
+            None
+        };
+        BlockCache {
+            inner,
+            capacity,
+            stats_counter,
+        }
+    }
+
+    /// the maximum number of blocks kept in the cache
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// get the block from the cache, or read it from the storage and keep
+    /// it in the cache for the next requests
+    pub fn get(&self, storage: &Storage, id: HeaderHash) -> Result<Option<Block>, StorageError> {
+        let inner = match &self.inner {
+            Some(inner) => inner,
+            None => return storage.get(id),
+        };
+
+        if let Some(block) = inner.lock().unwrap().get(&id) {
+            self.stats_counter.add_block_cache_hit();
+            return Ok(Some(block.clone()));
+        }
+        self.stats_counter.add_block_cache_miss();
+
+        let block = storage.get(id)?;
+        if let Some(block) = &block {
+            inner.lock().unwrap().put(id, block.clone());
+        }
+        Ok(block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_fixtures::block0;
+    use slog::Logger;
+
+    fn storage_with_block0() -> (Storage, HeaderHash) {
+        let block0 = block0();
+        let storage = Storage::memory(Logger::root(slog::Discard, o!())).unwrap();
+        storage.put_block(&block0).unwrap();
+        (storage, block0.header.hash())
+    }
+
+    #[test]
+    fn blocks_are_served_from_the_cache_once_read() {
+        let (storage, block0_hash) = storage_with_block0();
+        let stats_counter = StatsCounter::default();
+        let cache = BlockCache::new(4, stats_counter.clone());
+
+        for _ in 0..3 {
+            let block = cache.get(&storage, block0_hash).unwrap().unwrap();
+            assert_eq!(block.header.hash(), block0_hash);
+        }
+        assert_eq!(stats_counter.block_cache_miss_cnt(), 1);
+        assert_eq!(stats_counter.block_cache_hit_cnt(), 2);
+
+        assert!(cache
+            .get(&storage, HeaderHash::zero_hash())
+            .unwrap()
+            .is_none());
+        assert!(cache
+            .get(&storage, HeaderHash::zero_hash())
+            .unwrap()
+            .is_none());
+        assert_eq!(stats_counter.block_cache_miss_cnt(), 3);
+        assert_eq!(stats_counter.block_cache_hit_cnt(), 2);
+    }
+
+    #[test]
+    fn disabled_cache_reads_the_storage() {
+        let (storage, block0_hash) = storage_with_block0();
+        let stats_counter = StatsCounter::default();
+        let cache = BlockCache::new(0, stats_counter.clone());
+
+        for _ in 0..2 {
+            assert!(cache.get(&storage, block0_hash).unwrap().is_some());
+        }
+        assert_eq!(cache.capacity(), 0);
+        assert_eq!(stats_counter.block_cache_miss_cnt(), 0);
+        assert_eq!(stats_counter.block_cache_hit_cnt(), 0);
+    }
+}
@@ -1,6 +1,11 @@
+mod block_cache;
+
+pub use self::block_cache::BlockCache;
+
 use crate::blockcfg::{Block, Header, HeaderHash};
-use crate::blockchain::{Storage, Tip};
-use crate::intercom::{ClientMsg, Error, ReplySendError, ReplyStreamHandle};
+use crate::blockchain::{HeaderChain, Storage, Tip};
+use crate::intercom::{ClientMsg, Error, ReplySendError, ReplyStreamHandle, ReplyStreamSink};
+use crate::log;
 use crate::utils::async_msg::MessageQueue;
 use crate::utils::task::TokioServiceInfo;
 use chain_core::property::HasHeader;
@@ -22,6 +27,8 @@ const PROCESS_TIMEOUT_PULL_BLOCKS_TO_TIP: u64 = 60 * 60;
 pub struct TaskData {
     pub storage: Storage,
     pub blockchain_tip: Tip,
+    pub block_cache: BlockCache,
+    pub header_chain: HeaderChain,
 }
 
 pub async fn start(
@@ -30,6 +37,12 @@ pub async fn start(
     mut input: MessageQueue<ClientMsg>,
 ) {
     while let Some(input) = input.next().await {
+        // the requesting peer may be gone already, or the request may have
+        // timed out while queued: do not spend storage I/O on it then
+        if input.is_canceled() {
+            debug!(info.logger(), "dropping canceled request"; "request" => ?input);
+            continue;
+        }
         handle_input(&info, &mut task_data, input);
     }
 }
@@ -37,12 +50,15 @@ pub async fn start(
 fn handle_input(info: &TokioServiceInfo, task_data: &mut TaskData, input: ClientMsg) {
     match input {
         ClientMsg::GetBlockTip(handle) => {
+            let logger = info.logger().new(o!(
+                "request" => "GetBlockTip",
+                log::KEY_TRACE_ID => handle.trace_id(),
+            ));
             let blockchain_tip = task_data.blockchain_tip.clone();
             let fut = async move {
                 let tip = get_block_tip(blockchain_tip).await;
                 handle.reply_ok(tip);
             };
-            let logger = info.logger().new(o!("request" => "GetBlockTip"));
             info.spawn_fallible(
                 "get block tip",
                 timeout(Duration::from_secs(PROCESS_TIMEOUT_GET_BLOCK_TIP), fut).map_err(
@@ -57,44 +73,79 @@ fn handle_input(info: &TokioServiceInfo, task_data: &mut TaskData, input: Client
             );
         }
         ClientMsg::GetHeaders(ids, handle) => {
+            debug!(
+                info.logger(),
+                "processing request";
+                "request" => "GetHeaders",
+                log::KEY_TRACE_ID => handle.trace_id(),
+            );
             let storage = task_data.storage.clone();
+            let header_chain = task_data.header_chain.clone();
             info.timeout_spawn_fallible(
                 "GetHeaders",
                 Duration::from_secs(PROCESS_TIMEOUT_GET_HEADERS),
-                handle_get_headers(storage, ids, handle),
+                handle_get_headers(storage, header_chain, ids, handle),
             );
         }
         ClientMsg::GetHeadersRange(checkpoints, to, handle) => {
+            debug!(
+                info.logger(),
+                "processing request";
+                "request" => "GetHeadersRange",
+                log::KEY_TRACE_ID => handle.trace_id(),
+            );
             let storage = task_data.storage.clone();
+            let header_chain = task_data.header_chain.clone();
             info.timeout_spawn_fallible(
                 "GetHeadersRange",
                 Duration::from_secs(PROCESS_TIMEOUT_GET_HEADERS_RANGE),
-                handle_get_headers_range(storage, checkpoints, to, handle),
+                handle_get_headers_range(storage, header_chain, checkpoints, to, handle),
             );
         }
         ClientMsg::GetBlocks(ids, handle) => {
+            debug!(
+                info.logger(),
+                "processing request";
+                "request" => "GetBlocks",
+                log::KEY_TRACE_ID => handle.trace_id(),
+            );
             let storage = task_data.storage.clone();
+            let block_cache = task_data.block_cache.clone();
             info.timeout_spawn_fallible(
                 "get blocks",
                 Duration::from_secs(PROCESS_TIMEOUT_GET_BLOCKS),
-                handle_get_blocks(storage, ids, handle),
+                handle_get_blocks(storage, block_cache, ids, handle),
             );
         }
         ClientMsg::PullBlocks(from, to, handle) => {
+            debug!(
+                info.logger(),
+                "processing request";
+                "request" => "PullBlocks",
+                log::KEY_TRACE_ID => handle.trace_id(),
+            );
             let storage = task_data.storage.clone();
+            let block_cache = task_data.block_cache.clone();
             info.timeout_spawn_fallible(
                 "PullBlocks",
                 Duration::from_secs(PROCESS_TIMEOUT_PULL_BLOCKS),
-                handle_pull_blocks(storage, from, to, handle),
+                handle_pull_blocks(storage, block_cache, from, to, handle),
             );
         }
         ClientMsg::PullBlocksToTip(from, handle) => {
+            debug!(
+                info.logger(),
+                "processing request";
+                "request" => "PullBlocksToTip",
+                log::KEY_TRACE_ID => handle.trace_id(),
+            );
             let storage = task_data.storage.clone();
+            let block_cache = task_data.block_cache.clone();
             let blockchain_tip = task_data.blockchain_tip.clone();
             info.timeout_spawn_fallible(
                 "PullBlocksToTip",
                 Duration::from_secs(PROCESS_TIMEOUT_PULL_BLOCKS_TO_TIP),
-                handle_pull_blocks_to_tip(storage, blockchain_tip, from, handle),
+                handle_pull_blocks_to_tip(storage, block_cache, blockchain_tip, from, handle),
             );
         }
     }
@@ -107,10 +158,16 @@ async fn get_block_tip(blockchain_tip: Tip) -> Header {
 
 async fn handle_get_headers_range(
     storage: Storage,
+    header_chain: HeaderChain,
     checkpoints: Vec<HeaderHash>,
     to: HeaderHash,
     handle: ReplyStreamHandle<Header>,
 ) -> Result<(), ReplySendError> {
+    if let Some(headers) = header_chain.range(&checkpoints, to).await {
+        let mut stream = stream::iter(headers.into_iter().map(Ok).map(Ok));
+        return handle.start_sending().send_all(&mut stream).await;
+    }
+
     let res = storage.find_closest_ancestor(checkpoints, to);
     match res {
         Ok(maybe_ancestor) => {
@@ -128,12 +185,24 @@ async fn handle_get_headers_range(
 
 async fn handle_get_blocks(
     storage: Storage,
+    block_cache: BlockCache,
     ids: Vec<HeaderHash>,
     handle: ReplyStreamHandle<Block>,
 ) -> Result<(), ReplySendError> {
-    let mut sink = handle.start_sending();
+    send_blocks(&storage, &block_cache, ids, handle.start_sending()).await
+}
+
+async fn send_blocks(
+    storage: &Storage,
+    block_cache: &BlockCache,
+    ids: Vec<HeaderHash>,
+    mut sink: ReplyStreamSink<Block>,
+) -> Result<(), ReplySendError> {
     for id in ids {
-        let res = match storage.get(id) {
+        if sink.is_canceled() {
+            break;
+        }
+        let res = match block_cache.get(storage, id) {
             Ok(Some(block)) => Ok(block),
             Ok(None) => Err(Error::not_found(format!(
                 "block {} is not known to this node",
@@ -150,13 +219,43 @@ async fn handle_get_blocks(
     sink.close().await
 }
 
+/// Stream the branch ending at `to` like `Storage::send_branch`, serving
+/// the blocks from the cache when the branch is short enough to be in it.
+async fn send_branch(
+    storage: Storage,
+    block_cache: BlockCache,
+    to: HeaderHash,
+    depth: Option<u32>,
+    handle: ReplyStreamHandle<Block>,
+) -> Result<(), ReplySendError> {
+    let length = depth.unwrap_or(1);
+    if length as usize > block_cache.capacity() {
+        return storage.send_branch(to, depth, handle).await;
+    }
+    match storage.branch_ids(to, length) {
+        Ok(ids) => send_blocks(&storage, &block_cache, ids, handle.start_sending()).await,
+        Err(e) => {
+            handle.reply_error(e.into());
+            Ok(())
+        }
+    }
+}
+
 async fn handle_get_headers(
     storage: Storage,
+    header_chain: HeaderChain,
     ids: Vec<HeaderHash>,
     handle: ReplyStreamHandle<Header>,
 ) -> Result<(), ReplySendError> {
     let mut sink = handle.start_sending();
     for id in ids {
+        if sink.is_canceled() {
+            break;
+        }
+        if let Some(header) = header_chain.get(id).await {
+            sink.send(Ok(header)).await?;
+            continue;
+        }
         let res = match storage.get(id) {
             Ok(Some(block)) => Ok(block.header()),
             Ok(None) => Err(Error::not_found(format!(
@@ -176,6 +275,7 @@ async fn handle_get_headers(
 
 async fn handle_pull_blocks(
     storage: Storage,
+    block_cache: BlockCache,
     from: Vec<HeaderHash>,
     to: HeaderHash,
     handle: ReplyStreamHandle<Block>,
@@ -191,7 +291,7 @@ async fn handle_pull_blocks(
                 .ok_or_else(|| IntercomError::not_found("`from` not found"))
         });
     match res {
-        Ok((to, depth)) => storage.send_branch(to, Some(depth), handle).await,
+        Ok((to, depth)) => send_branch(storage, block_cache, to, Some(depth), handle).await,
         Err(e) => {
             handle.reply_error(e);
             Ok(())
@@ -201,6 +301,7 @@ async fn handle_pull_blocks(
 
 async fn handle_pull_blocks_to_tip(
     storage: Storage,
+    block_cache: BlockCache,
     blockchain_tip: Tip,
     checkpoints: Vec<HeaderHash>,
     handle: ReplyStreamHandle<Block>,
@@ -214,7 +315,7 @@ async fn handle_pull_blocks_to_tip(
             (tip_hash, depth)
         });
     match res {
-        Ok((to, depth)) => storage.send_branch(to, depth, handle).await,
+        Ok((to, depth)) => send_branch(storage, block_cache, to, depth, handle).await,
         Err(e) => {
             handle.reply_error(e.into());
             Ok(())
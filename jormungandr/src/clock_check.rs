@@ -0,0 +1,204 @@
+//! detection of the skew of the clock of the node
+//!
+//! The slots are derived from the clock of the node, so a node with a
+//! skewed clock misses its leadership slots, or produces blocks its peers
+//! reject as coming from the future, without any other sign of trouble.
+//!
+//! The skew is estimated from the block announcements of the peers: a
+//! block is announced right after the start of its slot, so the delay
+//! between the two, as measured by the node, is the skew of its clock plus
+//! the propagation of the block. The clock can also be compared to a NTP
+//! server when the node starts.
+
+use crate::{
+    settings::start::ClockCheckSettings, stats_counter::StatsCounter, utils::task::TokioServiceInfo,
+};
+use std::{
+    convert::TryInto,
+    io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use thiserror::Error;
+use tokio::{task, time};
+
+/// how often the skew estimated from the block announcements is checked
+const PEER_SKEW_CHECK_PERIOD: Duration = Duration::from_secs(60);
+
+/// how long to wait for the answer of the NTP server
+const NTP_TIMEOUT: Duration = Duration::from_secs(5);
+
+const NTP_PACKET_SIZE: usize = 48;
+
+/// seconds between the epoch of the NTP timestamps, 1900, and the Unix
+/// epoch
+const NTP_UNIX_EPOCH_OFFSET: i64 = 2_208_988_800;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("cannot resolve the NTP server address")]
+    NtpAddress(#[source] io::Error),
+    #[error("failed to query the NTP server")]
+    NtpIo(#[source] io::Error),
+    #[error("malformed answer from the NTP server")]
+    NtpMalformed,
+    #[error("the NTP query task failed")]
+    Task(#[from] task::JoinError),
+}
+
+/// compare the clock to the NTP server if any, then keep an eye on the
+/// skew estimated from the block announcements
+pub async fn run(
+    service_info: TokioServiceInfo,
+    settings: ClockCheckSettings,
+    stats_counter: StatsCounter,
+) {
+    let logger = service_info.logger().clone();
+    let max_skew_ms = settings.max_skew.as_millis() as i64;
+
+    if let Some(server) = settings.ntp_server {
+        match ntp_skew(server.clone()).await {
+            Ok(skew) => {
+                stats_counter.set_ntp_clock_skew(skew);
+                if skew.abs() > max_skew_ms {
+                    warn!(
+                        logger,
+                        "the clock of the node is off, the node may miss its leadership slots";
+                        "ntp_server" => server,
+                        "skew_ms" => skew,
+                    );
+                } else {
+                    info!(
+                        logger,
+                        "the clock of the node agrees with the NTP server";
+                        "ntp_server" => server,
+                        "skew_ms" => skew,
+                    );
+                }
+            }
+            Err(e) => {
+                warn!(
+                    logger,
+                    "cannot check the clock against the NTP server";
+                    "ntp_server" => server,
+                    "reason" => %e,
+                );
+            }
+        }
+    }
+
+    let mut interval = time::interval(PEER_SKEW_CHECK_PERIOD);
+    loop {
+        interval.tick().await;
+        let skew = stats_counter.peer_clock_skew();
+        if let Some(skew) = skew.filter(|skew| skew.abs() > max_skew_ms) {
+            warn!(
+                logger,
+                "the clock of the node seems off compared to the block announcements of its peers, \
+                 the node may miss its leadership slots";
+                "skew_ms" => skew,
+            );
+        }
+    }
+}
+
+/// the skew of the clock from the NTP server, in milliseconds, positive if
+/// the clock of the node is ahead
+async fn ntp_skew(server: String) -> Result<i64, Error> {
+    task::spawn_blocking(move || {
+        let server = server
+            .to_socket_addrs()
+            .map_err(Error::NtpAddress)?
+            .next()
+            .ok_or_else(|| {
+                Error::NtpAddress(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "no address for the NTP server",
+                ))
+            })?;
+        query_ntp(server)
+    })
+    .await?
+}
+
+fn query_ntp(server: SocketAddr) -> Result<i64, Error> {
+    let local: SocketAddr = if server.is_ipv4() {
+        (Ipv4Addr::UNSPECIFIED, 0).into()
+    } else {
+        (Ipv6Addr::UNSPECIFIED, 0).into()
+    };
+    let socket = UdpSocket::bind(local).map_err(Error::NtpIo)?;
+    socket
+        .set_read_timeout(Some(NTP_TIMEOUT))
+        .map_err(Error::NtpIo)?;
+    socket.connect(server).map_err(Error::NtpIo)?;
+
+    let mut request = [0; NTP_PACKET_SIZE];
+    // no leap second warning, version 3, client mode
+    request[0] = 0x1b;
+    let sent_at = unix_millis(SystemTime::now());
+    socket.send(&request).map_err(Error::NtpIo)?;
+    let mut answer = [0; NTP_PACKET_SIZE];
+    let len = socket.recv(&mut answer).map_err(Error::NtpIo)?;
+    let received_at = unix_millis(SystemTime::now());
+
+    skew_from_answer(&answer[..len], sent_at, received_at)
+}
+
+/// the skew of the local clock given the answer of the NTP server and the
+/// local times the query was sent and answered at, the network delay
+/// being assumed the same both ways
+fn skew_from_answer(answer: &[u8], sent_at: i64, received_at: i64) -> Result<i64, Error> {
+    if answer.len() < NTP_PACKET_SIZE {
+        return Err(Error::NtpMalformed);
+    }
+    let server_received_at = ntp_millis(&answer[32..40]);
+    let server_sent_at = ntp_millis(&answer[40..48]);
+    let offset = ((server_received_at - sent_at) + (server_sent_at - received_at)) / 2;
+    Ok(-offset)
+}
+
+/// a NTP timestamp as milliseconds since the Unix epoch
+fn ntp_millis(timestamp: &[u8]) -> i64 {
+    let seconds = u32::from_be_bytes(timestamp[..4].try_into().unwrap()) as i64;
+    let fraction = u32::from_be_bytes(timestamp[4..8].try_into().unwrap()) as i64;
+    (seconds - NTP_UNIX_EPOCH_OFFSET) * 1000 + ((fraction * 1000) >> 32)
+}
+
+fn unix_millis(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ntp_timestamp(unix_millis: i64) -> [u8; 8] {
+        let seconds = (unix_millis / 1000 + NTP_UNIX_EPOCH_OFFSET) as u32;
+        let fraction = (((unix_millis % 1000) << 32) / 1000) as u32;
+        let mut timestamp = [0; 8];
+        timestamp[..4].copy_from_slice(&seconds.to_be_bytes());
+        timestamp[4..].copy_from_slice(&fraction.to_be_bytes());
+        timestamp
+    }
+
+    #[test]
+    fn skew_is_computed_from_the_ntp_answer() {
+        // the node is 1.5 seconds ahead, with 100ms to reach the server and
+        // 100ms to get the answer back
+        let sent_at = 1_600_000_000_000;
+        let mut answer = [0; NTP_PACKET_SIZE];
+        answer[32..40].copy_from_slice(&ntp_timestamp(sent_at - 1_500 + 100));
+        answer[40..48].copy_from_slice(&ntp_timestamp(sent_at - 1_500 + 150));
+        let received_at = sent_at + 250;
+        // the conversions of the fractions of second may round down
+        let skew = skew_from_answer(&answer, sent_at, received_at).unwrap();
+        assert!((1_500..=1_501).contains(&skew), "skew: {}", skew);
+
+        assert!(matches!(
+            skew_from_answer(&answer[..40], sent_at, received_at),
+            Err(Error::NtpMalformed)
+        ));
+    }
+}
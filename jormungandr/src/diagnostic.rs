@@ -1,3 +1,7 @@
+use crate::{
+    backup::BackupStatus,
+    network::{p2p::comm::BlockFetchStats, StreamBuffersUsage},
+};
 use std::fmt::{self, Display, Formatter};
 use thiserror::Error;
 
@@ -14,6 +18,17 @@ pub enum DiagnosticError {
 pub struct Diagnostic {
     pub open_files_limit: Option<u64>,
     pub cpu_usage_limit: Option<u64>,
+    /// occupancy of the network stream buffers, filled in when the
+    /// diagnostic data is requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_buffers: Option<StreamBuffersUsage>,
+    /// status of the periodic backups of the chain, if enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup: Option<BackupStatus>,
+    /// counts of the block fetches from the peers chosen by the
+    /// configured strategy, once the node is running
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_fetch: Option<BlockFetchStats>,
 }
 
 impl Diagnostic {
@@ -23,6 +38,9 @@ impl Diagnostic {
             Ok(Self {
                 open_files_limit: Some(getrlimit(RlimitResource::NoFile)?),
                 cpu_usage_limit: Some(getrlimit(RlimitResource::CPU)?),
+                stream_buffers: None,
+                backup: None,
+                block_fetch: None,
             })
         }
         #[cfg(any(not(unix), target_os = "android"))]
@@ -30,6 +48,9 @@ impl Diagnostic {
             Ok(Self {
                 open_files_limit: None,
                 cpu_usage_limit: None,
+                stream_buffers: None,
+                backup: None,
+                block_fetch: None,
             })
         }
     }
@@ -14,7 +14,6 @@ use super::indexing::{
 };
 use super::persistent_sequence::PersistentSequence;
 use crate::blockcfg::{self, FragmentId, HeaderHash};
-use cardano_legacy_address::Addr as OldAddress;
 use certificates::*;
 use chain_impl_mockchain::certificate;
 use chain_impl_mockchain::key::BftLeaderId;
@@ -407,10 +406,8 @@ struct Address {
 
 impl Address {
     fn from_bech32(bech32: &str) -> FieldResult<Address> {
-        let addr = chain_addr::AddressReadable::from_string_anyprefix(bech32)
-            .map(|adr| ExplorerAddress::New(adr.to_address()))
-            .or_else(|_| OldAddress::from_str(bech32).map(ExplorerAddress::Old))
-            .map_err(|_| ErrorKind::InvalidAddress(bech32.to_string()))?;
+        let addr = ExplorerAddress::from_bech32(bech32)
+            .ok_or_else(|| ErrorKind::InvalidAddress(bech32.to_string()))?;
 
         Ok(Address { id: addr })
     }
@@ -96,6 +96,16 @@ pub enum ExplorerAddress {
     Old(OldAddress),
 }
 
+impl ExplorerAddress {
+    /// parse a bech32 address with any prefix, or a legacy base58 address
+    pub fn from_bech32(s: &str) -> Option<ExplorerAddress> {
+        chain_addr::AddressReadable::from_string_anyprefix(s)
+            .map(|addr| ExplorerAddress::New(addr.to_address()))
+            .ok()
+            .or_else(|| s.parse().map(ExplorerAddress::Old).ok())
+    }
+}
+
 #[derive(Clone)]
 pub struct ExplorerVotePlan {
     pub id: VotePlanId,
@@ -6,12 +6,13 @@ mod persistent_sequence;
 use self::error::{Error, ErrorKind, Result};
 use self::graphql::Context;
 use self::indexing::{
-    Addresses, Blocks, ChainLengths, EpochData, Epochs, ExplorerAddress, ExplorerBlock,
-    ExplorerVotePlan, ExplorerVoteProposal, ExplorerVoteTally, StakePool, StakePoolBlocks,
-    StakePoolData, Transactions, VotePlans,
+    Addresses, Blocks, ChainLengths, EpochData, Epochs, ExplorerVotePlan, ExplorerVoteProposal,
+    ExplorerVoteTally, StakePool, StakePoolBlocks, StakePoolData, Transactions, VotePlans,
 };
 use self::persistent_sequence::PersistentSequence;
 
+pub use self::indexing::{ExplorerAddress, ExplorerBlock};
+
 use crate::blockcfg::{
     Block, ChainLength, ConfigParam, ConfigParams, ConsensusVersion, Epoch, Fragment, FragmentId,
     HeaderHash,
@@ -354,6 +355,25 @@ impl ExplorerDB {
         .await
     }
 
+    /// the first and the last blocks with a transaction involving the
+    /// given address, if any
+    pub async fn get_address_activity(
+        &self,
+        address: &ExplorerAddress,
+    ) -> Option<(Arc<ExplorerBlock>, Arc<ExplorerBlock>)> {
+        let address = address.clone();
+        self.with_latest_state(move |state| {
+            let transactions = state.addresses.lookup(&address)?;
+            let block_of = |index: u64| {
+                let transaction_id = transactions.get(index)?;
+                let block_id = state.transactions.lookup(transaction_id.as_ref())?;
+                state.blocks.lookup(block_id.as_ref()).cloned()
+            };
+            Some((block_of(0)?, block_of(transactions.len().checked_sub(1)?)?))
+        })
+        .await
+    }
+
     // Get the hashes of all blocks in the range [from, to)
     // the ChainLength is returned to for easy of use in the case where
     // `to` is greater than the max
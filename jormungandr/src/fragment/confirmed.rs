@@ -0,0 +1,266 @@
+use crate::{fragment::FragmentId, utils::fs::write_atomically};
+use jormungandr_lib::{
+    crypto::hash::Hash,
+    interfaces::{BlockDate, FragmentStatus},
+};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+/// the file the confirmed fragments are saved in, in the storage directory
+pub const CONFIRMED_FRAGMENTS_FILE: &str = "confirmed_fragments.json";
+
+/// the block a fragment was added in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Confirmation {
+    pub date: BlockDate,
+    pub block: Hash,
+}
+
+/// The ids of the fragments added in a block in the last epochs, so their re-submissions are refused even once they are
+/// gone from the fragment logs or the node restarted.
+#[derive(Clone)]
+pub struct ConfirmedFragments {
+    epochs: u32,
+    path: Option<PathBuf>,
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    confirmations: HashMap<Hash, Confirmation>,
+    /// the latest epoch a fragment was confirmed in
+    epoch: u32,
+    /// whether the confirmations changed since they were last saved
+    dirty: bool,
+}
+
+impl ConfirmedFragments {
+    /// keep the fragments confirmed in the last `epochs` epochs, saving
+    /// them in the given file if any
+    pub fn new(epochs: u32, path: Option<PathBuf>) -> Self {
+        ConfirmedFragments {
+            epochs,
+            path,
+            inner: Default::default(),
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap()
+    }
+
+    pub fn get(&self, fragment_id: FragmentId) -> Option<Confirmation> {
+        let fragment_id: Hash = fragment_id.into();
+        self.lock().confirmations.get(&fragment_id).copied()
+    }
+
+    /// record the fragments added in a block, the `status` being the one
+    /// of the fragments removed from the pool. Fragments removed for any
+    /// other reason are ignored.
+    pub fn confirm_all(&self, fragment_ids: &[FragmentId], status: &FragmentStatus) {
+        let confirmation = match status {
+            FragmentStatus::InABlock { date, block } => Confirmation {
+                date: *date,
+                block: *block,
+            },
+            _ => return,
+        };
+        let mut inner = self.lock();
+        for fragment_id in fragment_ids {
+            inner
+                .confirmations
+                .insert((*fragment_id).into(), confirmation);
+        }
+        inner.dirty = true;
+        inner.advance(confirmation.date.as_ref().epoch, self.epochs);
+    }
+
+    /// forget the fragments of blocks no longer in the main branch
+    pub fn forget_all(&self, fragment_ids: &[FragmentId]) {
+        let mut inner = self.lock();
+        for fragment_id in fragment_ids {
+            let fragment_id: Hash = (*fragment_id).into();
+            if inner.confirmations.remove(&fragment_id).is_some() {
+                inner.dirty = true;
+            }
+        }
+    }
+
+    /// load the fragments saved by a previous run of the node, dropping the
+    /// ones too old to be kept
+    pub fn load(&self) -> io::Result<usize> {
+        let path = match &self.path {
+            Some(path) if path.exists() => path,
+            _ => return Ok(0),
+        };
+        let saved: Vec<(Hash, Confirmation)> =
+            serde_json::from_reader(BufReader::new(File::open(path)?))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut inner = self.lock();
+        let epoch = saved
+            .iter()
+            .map(|(_, confirmation)| confirmation.date.as_ref().epoch)
+            .max()
+            .unwrap_or(0);
+        inner.confirmations.extend(saved);
+        inner.advance(epoch, self.epochs);
+        Ok(inner.confirmations.len())
+    }
+
+    /// save the fragments in their file, replacing its content, if they
+    /// changed since the last save
+    pub fn save(&self) -> io::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let saved: Vec<(Hash, Confirmation)> = {
+            let mut inner = self.lock();
+            if !inner.dirty {
+                return Ok(());
+            }
+            inner.dirty = false;
+            inner
+                .confirmations
+                .iter()
+                .map(|(fragment_id, confirmation)| (*fragment_id, *confirmation))
+                .collect()
+        };
+        let result = write_atomically(path, |file| {
+            let mut writer = BufWriter::new(file);
+            serde_json::to_writer(&mut writer, &saved)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            writer.flush()
+        });
+        if result.is_err() {
+            // try again on the next save
+            self.lock().dirty = true;
+        }
+        result
+    }
+
+    /// same as `load`, on a thread where blocking is allowed
+    pub async fn load_in_background(&self) -> io::Result<usize> {
+        let confirmed = self.clone();
+        tokio::task::spawn_blocking(move || confirmed.load())
+            .await
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+    }
+
+    /// same as `save`, on a thread where blocking is allowed
+    pub async fn save_in_background(&self) -> io::Result<()> {
+        let confirmed = self.clone();
+        tokio::task::spawn_blocking(move || confirmed.save())
+            .await
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+    }
+}
+
+impl Inner {
+    /// move to the given epoch if it is a later one, dropping the fragments
+    /// confirmed more than `epochs` epochs before it
+    fn advance(&mut self, epoch: u32, epochs: u32) {
+        if epoch <= self.epoch {
+            return;
+        }
+        self.epoch = epoch;
+        let oldest = self.epoch.saturating_sub(epochs.saturating_sub(1));
+        let count = self.confirmations.len();
+        self.confirmations
+            .retain(|_, confirmation| confirmation.date.as_ref().epoch >= oldest);
+        if self.confirmations.len() != count {
+            self.dirty = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_impl_mockchain::block;
+
+    fn fragment_id(n: u8) -> FragmentId {
+        FragmentId::hash_bytes(&[n])
+    }
+
+    fn in_a_block(epoch: u32) -> FragmentStatus {
+        FragmentStatus::InABlock {
+            date: block::BlockDate { epoch, slot_id: 0 }.into(),
+            block: fragment_id(u8::MAX).into(),
+        }
+    }
+
+    #[test]
+    fn advance_drops_the_old_confirmations() {
+        let confirmed = ConfirmedFragments::new(2, None);
+        confirmed.confirm_all(&[fragment_id(0)], &in_a_block(0));
+        confirmed.confirm_all(&[fragment_id(1)], &in_a_block(1));
+        assert!(confirmed.get(fragment_id(0)).is_some());
+
+        confirmed.confirm_all(&[fragment_id(2)], &in_a_block(2));
+        assert!(confirmed.get(fragment_id(0)).is_none());
+        assert!(confirmed.get(fragment_id(1)).is_some());
+        assert!(confirmed.get(fragment_id(2)).is_some());
+
+        // confirmations of an earlier epoch do not move the window back
+        confirmed.confirm_all(&[fragment_id(3)], &in_a_block(1));
+        assert!(confirmed.get(fragment_id(1)).is_some());
+        assert!(confirmed.get(fragment_id(3)).is_some());
+    }
+
+    #[test]
+    fn only_fragments_in_a_block_are_confirmed() {
+        let confirmed = ConfirmedFragments::new(2, None);
+        let status = FragmentStatus::Rejected {
+            reason: "test".to_owned(),
+            ledger_error: None,
+        };
+        confirmed.confirm_all(&[fragment_id(0)], &status);
+        assert!(confirmed.get(fragment_id(0)).is_none());
+    }
+
+    #[test]
+    fn saved_confirmations_are_loaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIRMED_FRAGMENTS_FILE);
+
+        let confirmed = ConfirmedFragments::new(2, Some(path.clone()));
+        confirmed.confirm_all(&[fragment_id(0)], &in_a_block(0));
+        confirmed.confirm_all(&[fragment_id(1), fragment_id(2)], &in_a_block(1));
+        confirmed.forget_all(&[fragment_id(2)]);
+        confirmed.save().unwrap();
+
+        let loaded = ConfirmedFragments::new(1, Some(path));
+        assert_eq!(loaded.load().unwrap(), 1);
+        assert!(loaded.get(fragment_id(0)).is_none());
+        assert_eq!(loaded.get(fragment_id(1)), confirmed.get(fragment_id(1)),);
+        assert!(loaded.get(fragment_id(2)).is_none());
+    }
+
+    #[test]
+    fn unchanged_confirmations_are_not_saved_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIRMED_FRAGMENTS_FILE);
+
+        let confirmed = ConfirmedFragments::new(2, Some(path.clone()));
+        confirmed.confirm_all(&[fragment_id(0)], &in_a_block(0));
+        confirmed.save().unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+        confirmed.save().unwrap();
+        assert!(!path.exists());
+
+        confirmed.forget_all(&[fragment_id(0)]);
+        confirmed.save().unwrap();
+        assert!(path.exists());
+    }
+}
@@ -4,16 +4,27 @@ use jormungandr_lib::{
     interfaces::{FragmentLog, FragmentOrigin, FragmentStatus},
 };
 use lru::LruCache;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::time::SystemTime;
+
+/// number of the most recently included fragments the average time to
+/// block is computed on
+const TIME_TO_BLOCK_SAMPLES: usize = 1_000;
 
 pub struct Logs {
     entries: LruCache<Hash, FragmentLog>,
+    accepted_cnt: u64,
+    rejected_cnt: u64,
+    time_to_block_ms: VecDeque<u64>,
 }
 
 impl Logs {
     pub fn new(max_entries: usize) -> Self {
         Logs {
             entries: LruCache::new(max_entries),
+            accepted_cnt: 0,
+            rejected_cnt: 0,
+            time_to_block_ms: VecDeque::new(),
         }
     }
 
@@ -36,6 +47,7 @@ impl Logs {
             false
         } else {
             self.entries.put(fragment_id, log);
+            self.accepted_cnt += 1;
             true
         }
     }
@@ -52,6 +64,19 @@ impl Logs {
         let fragment_id: Hash = fragment_id.into();
         match self.entries.get_mut(&fragment_id) {
             Some(entry) => {
+                if entry.is_pending() {
+                    if status.is_rejected() {
+                        self.rejected_cnt += 1;
+                    } else if status.is_in_a_block() {
+                        let delay = SystemTime::now()
+                            .duration_since(*entry.received_at().as_ref())
+                            .map_or(0, |delay| delay.as_millis() as u64);
+                        self.time_to_block_ms.push_back(delay);
+                        if self.time_to_block_ms.len() > TIME_TO_BLOCK_SAMPLES {
+                            self.time_to_block_ms.pop_front();
+                        }
+                    }
+                }
                 entry.modify(status);
             }
             None => {
@@ -62,7 +87,10 @@ impl Logs {
 
                 self.entries.put(
                     fragment_id,
-                    FragmentLog::new(fragment_id.clone().into_hash(), FragmentOrigin::Network),
+                    FragmentLog::new(
+                        fragment_id.clone().into_hash(),
+                        FragmentOrigin::Network { addr: None },
+                    ),
                 );
             }
         }
@@ -95,7 +123,80 @@ impl Logs {
         result
     }
 
+    /// log a fragment refused on reception with its final status, counting
+    /// it as rejected
+    pub fn insert_refused(&mut self, log: FragmentLog) {
+        let fragment_id = *log.fragment_id();
+        self.entries.put(fragment_id, log);
+        self.rejected_cnt += 1;
+    }
+
+    /// count fragments refused before being logged, e.g. because they are
+    /// not valid
+    pub fn count_refused(&mut self, count: usize) {
+        self.rejected_cnt += count as u64;
+    }
+
+    /// number of fragments accepted and rejected since the node started
+    pub fn outcome_counts(&self) -> (u64, u64) {
+        (self.accepted_cnt, self.rejected_cnt)
+    }
+
+    /// average time between the reception and the inclusion in a block of
+    /// the most recently included fragments, with the number of fragments
+    /// it is computed on
+    pub fn avg_time_to_block(&self) -> (usize, Option<u64>) {
+        let samples = self.time_to_block_ms.len();
+        let avg = if samples == 0 {
+            None
+        } else {
+            Some(self.time_to_block_ms.iter().sum::<u64>() / samples as u64)
+        };
+        (samples, avg)
+    }
+
     pub fn logs(&self) -> impl Iterator<Item = &FragmentLog> {
         self.entries.iter().map(|(_, v)| v)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_impl_mockchain::block::BlockDate;
+
+    fn fragment_id(id: u8) -> FragmentId {
+        Hash::from([id; 32]).into_hash()
+    }
+
+    fn log(id: u8) -> FragmentLog {
+        FragmentLog::new(fragment_id(id), FragmentOrigin::Rest)
+    }
+
+    #[test]
+    fn outcomes_of_the_fragments_are_counted() {
+        let mut logs = Logs::new(16);
+        assert_eq!(logs.insert_all(vec![log(0), log(1), log(2), log(0)]), 3);
+        assert_eq!(logs.outcome_counts(), (3, 0));
+        assert_eq!(logs.avg_time_to_block(), (0, None));
+
+        let rejected = FragmentStatus::Rejected {
+            reason: "invalid".to_string(),
+            ledger_error: None,
+        };
+        let in_a_block = FragmentStatus::InABlock {
+            date: BlockDate::first().into(),
+            block: Hash::from([0; 32]),
+        };
+        logs.modify(fragment_id(0), rejected.clone());
+        logs.modify(fragment_id(1), in_a_block);
+        // only the first outcome of a fragment is counted
+        logs.modify(fragment_id(1), rejected);
+        logs.count_refused(2);
+
+        assert_eq!(logs.outcome_counts(), (3, 3));
+        let (samples, avg) = logs.avg_time_to_block();
+        assert_eq!(samples, 1);
+        assert!(avg.unwrap() < 60_000);
+    }
+}
@@ -1,9 +1,11 @@
+mod confirmed;
 mod entry;
 mod logs;
 mod pool;
 mod process;
 pub mod selection;
 
+pub use self::confirmed::{ConfirmedFragments, CONFIRMED_FRAGMENTS_FILE};
 pub use self::entry::PoolEntry;
 pub use self::logs::Logs;
 pub use self::pool::Pool;
@@ -1,8 +1,11 @@
 use crate::{
     blockcfg::{BlockDate, Ledger, LedgerParameters},
     fragment::{
-        selection::{FragmentSelectionAlgorithm, FragmentSelectionAlgorithmParams, OldestFirst},
-        Fragment, FragmentId, Logs,
+        selection::{
+            fragment_size, fragment_type, FragmentSelectionAlgorithm,
+            FragmentSelectionAlgorithmParams, OldestFirst,
+        },
+        ConfirmedFragments, Fragment, FragmentId, Logs,
     },
     intercom::{NetworkMsg, PropagateMsg},
     utils::async_msg::MessageBox,
@@ -10,12 +13,20 @@ use crate::{
 use chain_core::property::Fragment as _;
 use chain_impl_mockchain::{fragment::Contents, transaction::Transaction};
 use futures::sink::SinkExt;
-use jormungandr_lib::interfaces::{FragmentLog, FragmentOrigin, FragmentStatus};
+use jormungandr_lib::interfaces::{
+    FragmentLog, FragmentOrigin, FragmentStatus, FragmentType, MempoolOriginUsage, MempoolSummary,
+    SelectionQuotas,
+};
 use slog::Logger;
 
+/// rejection reason of the fragments evicted by the node operator
+const CANCELLED_LOCALLY: &str = "cancelled locally";
+
 pub struct Pool {
     logs: Logs,
     pool: internal::Pool,
+    selection_quotas: SelectionQuotas,
+    confirmed: Option<ConfirmedFragments>,
     network_msg_box: MessageBox<NetworkMsg>,
     logger: Logger,
 }
@@ -23,13 +34,18 @@ pub struct Pool {
 impl Pool {
     pub fn new(
         max_entries: usize,
+        origin_max_entries: Option<usize>,
+        selection_quotas: SelectionQuotas,
+        confirmed: Option<ConfirmedFragments>,
         logs: Logs,
         network_msg_box: MessageBox<NetworkMsg>,
         logger: Logger,
     ) -> Self {
         Pool {
             logs,
-            pool: internal::Pool::new(max_entries),
+            pool: internal::Pool::new(max_entries, origin_max_entries),
+            selection_quotas,
+            confirmed,
             network_msg_box,
             logger,
         }
@@ -43,24 +59,52 @@ impl Pool {
     pub async fn insert_and_propagate_all(
         &mut self,
         origin: FragmentOrigin,
-        mut fragments: Vec<Fragment>,
+        fragments: Vec<Fragment>,
     ) -> Result<usize, ()> {
+        let new_fragments = self.insert_all(origin, fragments);
+        let count = new_fragments.len();
+        let mut network_msg_box = self.network_msg_box.clone();
+        for fragment in new_fragments.into_iter() {
+            let fragment_msg = NetworkMsg::Propagate(PropagateMsg::Fragment(fragment));
+            network_msg_box
+                .send(fragment_msg)
+                .await
+                .map_err(|e| error!(self.logger, "cannot propagate fragment to network: {}", e))?;
+        }
+        Ok(count)
+    }
+
+    /// register the fragments without propagating them to the network,
+    /// returns the fragments added to the pool
+    pub fn insert_all(
+        &mut self,
+        origin: FragmentOrigin,
+        mut fragments: Vec<Fragment>,
+    ) -> Vec<Fragment> {
         debug!(self.logger, "received {} fragments", fragments.len(); "origin" => ?origin);
+        let received = fragments.len();
         fragments.retain(is_fragment_valid);
+        self.logs.count_refused(received - fragments.len());
+        if let Some(max_size) = self.selection_quotas.max_content_size {
+            fragments = self.refuse_oversized(fragments, origin, max_size);
+        }
         if fragments.is_empty() {
             debug!(self.logger, "none of the received fragments are valid");
-            return Ok(0);
+            return Vec::new();
         }
-        let mut network_msg_box = self.network_msg_box.clone();
         let fragment_ids = fragments.iter().map(Fragment::id).collect::<Vec<_>>();
         let fragments_exist_in_logs = self.logs.exist_all(fragment_ids);
         let new_fragments = fragments
             .into_iter()
             .zip(fragments_exist_in_logs)
             .filter(|(_, exists_in_logs)| !exists_in_logs)
-            .map(|(fragment, _)| fragment);
-        let new_fragments = self.pool.insert_all(new_fragments);
+            .map(|(fragment, _)| fragment)
+            .collect::<Vec<_>>();
+        let new_fragments = self.refuse_confirmed(new_fragments, origin);
+        let unknown = new_fragments.len();
+        let new_fragments = self.pool.insert_all(new_fragments, origin);
         let count = new_fragments.len();
+        self.logs.count_refused(unknown - count);
         debug!(
             self.logger,
             "{} of the received fragments were added to the pool", count
@@ -69,22 +113,179 @@ impl Pool {
             .iter()
             .map(move |fragment| FragmentLog::new(fragment.id(), origin))
             .collect::<Vec<_>>();
-        for fragment in new_fragments.into_iter() {
-            let fragment_msg = NetworkMsg::Propagate(PropagateMsg::Fragment(fragment));
-            network_msg_box
-                .send(fragment_msg)
-                .await
-                .map_err(|e| error!(self.logger, "cannot propagate fragment to network: {}", e))?;
-        }
         self.logs.insert_all(fragment_logs);
-        Ok(count)
+        new_fragments
+    }
+
+    /// refuse the fragments larger than the cap of the node on the content
+    /// size of its blocks, it would never put them in a block
+    fn refuse_oversized(
+        &mut self,
+        fragments: Vec<Fragment>,
+        origin: FragmentOrigin,
+        max_size: u32,
+    ) -> Vec<Fragment> {
+        let mut accepted = Vec::with_capacity(fragments.len());
+        for fragment in fragments {
+            let size = fragment_size(&fragment);
+            if size <= max_size {
+                accepted.push(fragment);
+                continue;
+            }
+            let fragment_id = fragment.id();
+            if self.logs.exists(fragment_id) {
+                continue;
+            }
+            debug!(
+                self.logger,
+                "refusing a fragment larger than the max content size";
+                "fragment_id" => %fragment_id,
+                "size" => size,
+            );
+            let mut log = FragmentLog::new(fragment_id, origin);
+            log.modify(FragmentStatus::Rejected {
+                reason: format!(
+                    "fragment of {} bytes exceeds the block content cap of {} bytes",
+                    size, max_size
+                ),
+                ledger_error: None,
+            });
+            self.logs.insert_refused(log);
+        }
+        accepted
+    }
+
+    /// refuse the fragments already added in a block, which are no longer
+    /// in the logs, and log them as already confirmed
+    fn refuse_confirmed(
+        &mut self,
+        fragments: Vec<Fragment>,
+        origin: FragmentOrigin,
+    ) -> Vec<Fragment> {
+        let confirmed = match &self.confirmed {
+            Some(confirmed) => confirmed,
+            None => return fragments,
+        };
+        let mut new_fragments = Vec::with_capacity(fragments.len());
+        for fragment in fragments {
+            let fragment_id = fragment.id();
+            match confirmed.get(fragment_id) {
+                Some(confirmation) => {
+                    debug!(
+                        self.logger,
+                        "refusing a fragment already in a block";
+                        "fragment_id" => %fragment_id,
+                        "block" => %confirmation.block,
+                    );
+                    let mut log = FragmentLog::new(fragment_id, origin);
+                    log.modify(FragmentStatus::AlreadyConfirmed {
+                        date: confirmation.date,
+                        block: confirmation.block,
+                    });
+                    self.logs.insert_refused(log);
+                }
+                None => new_fragments.push(fragment),
+            }
+        }
+        new_fragments
     }
 
     pub fn remove_added_to_block(&mut self, fragment_ids: Vec<FragmentId>, status: FragmentStatus) {
+        if let Some(confirmed) = &self.confirmed {
+            confirmed.confirm_all(&fragment_ids, &status);
+        }
         self.pool.remove_all(fragment_ids.iter().cloned());
         self.logs.modify_all(fragment_ids, status);
     }
 
+    /// evict a pending fragment on request of the node operator. The log
+    /// entry is kept so the fragment is not accepted again from the network.
+    ///
+    /// Returns `false` if the fragment is not in the pool.
+    pub fn cancel(&mut self, fragment_id: FragmentId) -> bool {
+        if !self.pool.cancel(&fragment_id) {
+            return false;
+        }
+        debug!(self.logger, "fragment cancelled locally"; "fragment_id" => %fragment_id);
+        self.logs.modify(
+            fragment_id,
+            FragmentStatus::Rejected {
+                reason: CANCELLED_LOCALLY.to_owned(),
+                ledger_error: None,
+            },
+        );
+        true
+    }
+
+    /// put back the fragments of blocks which are no longer in the main
+    /// branch. The fragments were already propagated, so they are not sent
+    /// to the network again.
+    pub fn restore_rolled_back(&mut self, mut fragments: Vec<Fragment>) {
+        fragments.retain(is_fragment_valid);
+        let fragment_ids = fragments.iter().map(Fragment::id).collect::<Vec<_>>();
+        if let Some(confirmed) = &self.confirmed {
+            confirmed.forget_all(&fragment_ids);
+        }
+        let origins = self.logs.logs_by_ids(fragment_ids.clone());
+        let origins = fragment_ids
+            .iter()
+            .map(|fragment_id| {
+                origins
+                    .get(fragment_id)
+                    .map(|log| *log.received_from())
+                    .unwrap_or(FragmentOrigin::Network { addr: None })
+            })
+            .collect::<Vec<_>>();
+        for (fragment, origin) in fragments.into_iter().zip(origins) {
+            self.pool.put_back(fragment, origin);
+        }
+        debug!(
+            self.logger,
+            "{} fragments from rolled back blocks returned to the pool",
+            fragment_ids.len()
+        );
+        self.logs.modify_all(fragment_ids, FragmentStatus::Pending);
+    }
+
+    /// mempool occupancy per fragment origin
+    pub fn origin_usage(&self) -> Vec<MempoolOriginUsage> {
+        self.pool.origin_usage()
+    }
+
+    /// content of the mempool and outcome of the fragments received since
+    /// the node started
+    pub fn summary(&self) -> MempoolSummary {
+        let (accepted_cnt, rejected_cnt) = self.logs.outcome_counts();
+        let (time_to_block_samples, avg_time_to_block) = self.logs.avg_time_to_block();
+        MempoolSummary {
+            entries: self.pool.len(),
+            entries_by_type: self.pool.type_usage(),
+            accepted_cnt,
+            rejected_cnt,
+            time_to_block_samples,
+            avg_time_to_block,
+        }
+    }
+
+    /// the pending fragment with the given id, if it is in the pool
+    pub fn get(&self, fragment_id: &FragmentId) -> Option<&Fragment> {
+        self.pool.get(fragment_id)
+    }
+
+    /// the pending fragments with the given ids
+    pub fn pending(&self, fragment_ids: &[FragmentId]) -> Vec<Fragment> {
+        fragment_ids
+            .iter()
+            .filter_map(|fragment_id| self.pool.get(fragment_id))
+            .cloned()
+            .collect()
+    }
+
+    /// at most `max_fragments` of the pending fragments, oldest first
+    pub fn snapshot(&self, max_fragments: usize) -> Vec<Fragment> {
+        self.pool.fragments().take(max_fragments).cloned().collect()
+    }
+
     pub fn select(
         &mut self,
         ledger: Ledger,
@@ -92,10 +293,16 @@ impl Pool {
         ledger_params: LedgerParameters,
         selection_alg: FragmentSelectionAlgorithmParams,
     ) -> Contents {
-        let Pool { logs, pool, .. } = self;
+        let Pool {
+            logs,
+            pool,
+            selection_quotas,
+            ..
+        } = self;
         match selection_alg {
             FragmentSelectionAlgorithmParams::OldestFirst => {
-                let mut selection_alg = OldestFirst::new(self.logger.clone());
+                let mut selection_alg =
+                    OldestFirst::new(selection_quotas.clone(), self.logger.clone());
                 selection_alg.select(&ledger, &ledger_params, block_date, logs, pool);
                 selection_alg.finalize()
             }
@@ -116,8 +323,12 @@ fn is_fragment_valid(fragment: &Fragment) -> bool {
         Fragment::PoolRetirement(ref tx) => is_transaction_valid(tx),
         Fragment::PoolUpdate(ref tx) => is_transaction_valid(tx),
         // vote stuff
-        Fragment::UpdateProposal(_) => false, // TODO: enable when ready
-        Fragment::UpdateVote(_) => false,     // TODO: enable when ready
+        // the update proposals and votes of the ledger do not carry a
+        // signature of their proposer or voter yet: accepting them from the
+        // network would let anyone propose or vote in the name of a BFT
+        // leader, so they are refused for now
+        Fragment::UpdateProposal(_) => false,
+        Fragment::UpdateVote(_) => false,
         Fragment::VotePlan(ref tx) => is_transaction_valid(tx),
         Fragment::VoteCast(ref tx) => is_transaction_valid(tx),
         Fragment::VoteTally(ref tx) => is_transaction_valid(tx),
@@ -132,48 +343,222 @@ fn is_transaction_valid<E>(tx: &Transaction<E>) -> bool {
 pub(super) mod internal {
     use super::*;
     use lru::LruCache;
+    use std::collections::{BTreeMap, HashMap};
+
+    /// number of block selections a fragment can be put back in the pool
+    /// after failing to apply because of its spending counter, before it is
+    /// rejected
+    const MAX_DEFERRALS: u32 = 3;
+
+    /// number of origins the spam counters are kept for. The counters of
+    /// the origins not seen for the longest time are dropped first.
+    const MAX_TRACKED_ORIGINS: usize = 1024;
+
+    /// what an origin was refused or lost since the node started, kept
+    /// after all its fragments have left the pool
+    #[derive(Clone, Copy, Default)]
+    struct SpamCounters {
+        over_quota_cnt: u64,
+        evicted_cnt: u64,
+    }
 
     pub struct Pool {
-        entries: LruCache<FragmentId, Fragment>,
+        entries: LruCache<FragmentId, (Fragment, FragmentOrigin)>,
+        deferrals: LruCache<FragmentId, u32>,
+        max_entries: usize,
+        origin_max_entries: Option<usize>,
+        /// number of fragments in the pool for each origin, only tracked
+        /// while the origin has fragments in the pool: the peers come and go
+        origins: HashMap<FragmentOrigin, usize>,
+        spam: LruCache<FragmentOrigin, SpamCounters>,
+        types: BTreeMap<FragmentType, usize>,
     }
 
     impl Pool {
-        pub fn new(max_entries: usize) -> Self {
+        pub fn new(max_entries: usize, origin_max_entries: Option<usize>) -> Self {
             Pool {
                 entries: LruCache::new(max_entries),
+                deferrals: LruCache::new(max_entries),
+                max_entries,
+                origin_max_entries,
+                origins: HashMap::new(),
+                spam: LruCache::new(MAX_TRACKED_ORIGINS),
+                types: BTreeMap::new(),
             }
         }
 
         /// Returns clone of fragment if it was registered
-        pub fn insert(&mut self, fragment: Fragment) -> Option<Fragment> {
+        pub fn insert(&mut self, fragment: Fragment, origin: FragmentOrigin) -> Option<Fragment> {
             let fragment_id = fragment.id();
             if self.entries.contains(&fragment_id) {
-                None
-            } else {
-                self.entries.put(fragment_id, fragment.clone());
-                Some(fragment)
+                return None;
             }
+            if let Some(origin_max_entries) = self.origin_max_entries {
+                let entries = self.origins.get(&origin).copied().unwrap_or(0);
+                if entries >= origin_max_entries {
+                    self.spam_counters(origin).over_quota_cnt += 1;
+                    return None;
+                }
+            }
+            self.put(fragment_id, fragment.clone(), origin);
+            Some(fragment)
         }
 
         /// Returns clones of registered fragments
         pub fn insert_all(
             &mut self,
             fragments: impl IntoIterator<Item = Fragment>,
+            origin: FragmentOrigin,
         ) -> Vec<Fragment> {
             fragments
                 .into_iter()
-                .filter_map(|fragment| self.insert(fragment))
+                .filter_map(|fragment| self.insert(fragment, origin))
                 .collect()
         }
 
+        /// put back a fragment which was already accepted in the pool,
+        /// without checking the quota of its origin
+        pub fn put_back(&mut self, fragment: Fragment, origin: FragmentOrigin) {
+            let fragment_id = fragment.id();
+            if !self.entries.contains(&fragment_id) {
+                self.put(fragment_id, fragment, origin);
+            }
+        }
+
+        fn put(&mut self, fragment_id: FragmentId, fragment: Fragment, origin: FragmentOrigin) {
+            if self.entries.len() >= self.max_entries {
+                self.evict();
+            }
+            if let Some(fragment_type) = fragment_type(&fragment) {
+                *self.types.entry(fragment_type).or_insert(0) += 1;
+            }
+            self.entries.put(fragment_id, (fragment, origin));
+            *self.origins.entry(origin).or_insert(0) += 1;
+        }
+
+        fn spam_counters(&mut self, origin: FragmentOrigin) -> &mut SpamCounters {
+            if !self.spam.contains(&origin) {
+                self.spam.put(origin, SpamCounters::default());
+            }
+            self.spam.get_mut(&origin).unwrap()
+        }
+
+        /// make room for a new entry: the oldest fragment from the origin
+        /// occupying the most of the pool is removed. Fragments submitted
+        /// through the REST interface are only evicted if there are no
+        /// others.
+        fn evict(&mut self) {
+            let victim = self
+                .origins
+                .iter()
+                .filter(|(_, entries)| **entries > 0)
+                .max_by_key(|(origin, entries)| (**origin != FragmentOrigin::Rest, **entries))
+                .map(|(origin, _)| *origin);
+            let victim = match victim {
+                Some(victim) => victim,
+                None => return,
+            };
+            let fragment_id = self
+                .entries
+                .iter()
+                .rev()
+                .find(|(_, (_, origin))| *origin == victim)
+                .map(|(fragment_id, _)| *fragment_id);
+            if let Some(fragment_id) = fragment_id {
+                self.spam_counters(victim).evicted_cnt += 1;
+                self.remove(&fragment_id);
+            }
+        }
+
+        fn remove(&mut self, fragment_id: &FragmentId) -> Option<(Fragment, FragmentOrigin)> {
+            let entry = self.entries.pop(fragment_id)?;
+            if let Some(entries) = self.origins.get_mut(&entry.1) {
+                *entries -= 1;
+                if *entries == 0 {
+                    self.origins.remove(&entry.1);
+                }
+            }
+            if let Some(fragment_type) = fragment_type(&entry.0) {
+                if let Some(count) = self.types.get_mut(&fragment_type) {
+                    *count -= 1;
+                }
+            }
+            Some(entry)
+        }
+
+        pub fn get(&self, fragment_id: &FragmentId) -> Option<&Fragment> {
+            self.entries.peek(fragment_id).map(|(fragment, _)| fragment)
+        }
+
+        /// the fragments in the pool, oldest first
+        pub fn fragments(&self) -> impl Iterator<Item = &Fragment> {
+            self.entries.iter().rev().map(|(_, (fragment, _))| fragment)
+        }
+
+        pub fn cancel(&mut self, fragment_id: &FragmentId) -> bool {
+            self.deferrals.pop(fragment_id);
+            self.remove(fragment_id).is_some()
+        }
+
         pub fn remove_all(&mut self, fragment_ids: impl IntoIterator<Item = FragmentId>) {
             for fragment_id in fragment_ids {
-                self.entries.pop(&fragment_id);
+                self.remove(&fragment_id);
+                self.deferrals.pop(&fragment_id);
             }
         }
 
-        pub fn remove_oldest(&mut self) -> Option<Fragment> {
-            self.entries.pop_lru().map(|(_, value)| value)
+        /// put back a fragment which failed to apply because of its spending
+        /// counter, as it may become applicable later. Returns `false` if the fragment was already
+        /// put back too many times, in which case it is dropped.
+        pub fn defer(&mut self, fragment: Fragment, origin: FragmentOrigin) -> bool {
+            let fragment_id = fragment.id();
+            let count = self.deferrals.pop(&fragment_id).unwrap_or(0) + 1;
+            if count > MAX_DEFERRALS {
+                return false;
+            }
+            self.deferrals.put(fragment_id, count);
+            self.put_back(fragment, origin);
+            true
+        }
+
+        pub fn remove_oldest(&mut self) -> Option<(Fragment, FragmentOrigin)> {
+            let fragment_id = *self.entries.peek_lru()?.0;
+            self.remove(&fragment_id)
+        }
+
+        pub fn len(&self) -> usize {
+            self.entries.len()
+        }
+
+        /// number of fragments of each type in the pool
+        pub fn type_usage(&self) -> BTreeMap<FragmentType, usize> {
+            self.types
+                .iter()
+                .filter(|(_, count)| **count > 0)
+                .map(|(fragment_type, count)| (*fragment_type, *count))
+                .collect()
+        }
+
+        /// the usage of the origins with fragments in the pool or with
+        /// spam counters
+        pub fn origin_usage(&self) -> Vec<MempoolOriginUsage> {
+            let spam = self
+                .spam
+                .iter()
+                .map(|(origin, counters)| (*origin, *counters));
+            let mut usage: HashMap<FragmentOrigin, SpamCounters> = spam.collect();
+            for origin in self.origins.keys() {
+                usage.entry(*origin).or_default();
+            }
+            usage
+                .into_iter()
+                .map(|(origin, counters)| MempoolOriginUsage {
+                    origin,
+                    entries: self.origins.get(&origin).copied().unwrap_or(0),
+                    over_quota_cnt: counters.over_quota_cnt,
+                    evicted_cnt: counters.evicted_cnt,
+                })
+                .collect()
         }
     }
 }
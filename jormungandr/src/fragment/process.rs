@@ -1,32 +1,53 @@
 use crate::{
-    fragment::{Logs, Pool},
+    fragment::{ConfirmedFragments, Logs, Pool},
     intercom::{NetworkMsg, TransactionMsg},
+    log,
     stats_counter::StatsCounter,
     utils::{
         async_msg::{MessageBox, MessageQueue},
         task::TokioServiceInfo,
     },
 };
-use std::collections::HashMap;
+use futures::{sink::SinkExt, stream};
+use jormungandr_lib::interfaces::SelectionQuotas;
+use std::{collections::HashMap, time::Duration};
 use tokio::stream::StreamExt;
 
+/// how often the confirmed fragments are saved
+const CONFIRMED_FRAGMENTS_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// the most fragments sent to a peer asking for a snapshot of the mempool
+const MEMPOOL_SNAPSHOT_MAX_FRAGMENTS: usize = 1024;
+
 pub struct Process {
     pool_max_entries: usize,
+    origin_max_entries: Option<usize>,
+    selection_quotas: SelectionQuotas,
+    confirmed: Option<ConfirmedFragments>,
     logs: Logs,
     network_msg_box: MessageBox<NetworkMsg>,
+    read_only: bool,
 }
 
 impl Process {
     pub fn new(
         pool_max_entries: usize,
         logs_max_entries: usize,
+        origin_max_entries: Option<usize>,
+        selection_quotas: SelectionQuotas,
+        confirmed: Option<ConfirmedFragments>,
         network_msg_box: MessageBox<NetworkMsg>,
+        read_only: bool,
     ) -> Self {
         let logs = Logs::new(logs_max_entries);
         Process {
             pool_max_entries,
+            origin_max_entries,
+            selection_quotas,
+            confirmed,
             logs,
             network_msg_box,
+            read_only,
         }
     }
 
@@ -36,8 +57,35 @@ impl Process {
         stats_counter: StatsCounter,
         mut input: MessageQueue<TransactionMsg>,
     ) -> Result<(), ()> {
+        if let Some(confirmed) = &self.confirmed {
+            match confirmed.load_in_background().await {
+                Ok(count) => debug!(
+                    service_info.logger(),
+                    "loaded {} confirmed fragments", count
+                ),
+                Err(e) => warn!(
+                    service_info.logger(),
+                    "cannot load the confirmed fragments"; "reason" => %e
+                ),
+            }
+            if confirmed.path().is_some() {
+                let confirmed = confirmed.clone();
+                service_info.run_periodic_fallible(
+                    "save confirmed fragments",
+                    CONFIRMED_FRAGMENTS_SAVE_INTERVAL,
+                    move || {
+                        let confirmed = confirmed.clone();
+                        async move { confirmed.save_in_background().await }
+                    },
+                );
+            }
+        }
+
         let mut pool = Pool::new(
             self.pool_max_entries,
+            self.origin_max_entries,
+            self.selection_quotas,
+            self.confirmed,
             self.logs,
             self.network_msg_box,
             service_info.logger().clone(),
@@ -45,6 +93,14 @@ impl Process {
 
         while let Some(input_result) = input.next().await {
             match input_result {
+                TransactionMsg::SendTransaction(origin, txs) if self.read_only => {
+                    debug!(
+                        service_info.logger(),
+                        "dropping {} fragments, the node is read-only",
+                        txs.len();
+                        "origin" => ?origin,
+                    );
+                }
                 TransactionMsg::SendTransaction(origin, txs) => {
                     // Note that we cannot use apply_block here, since we don't have a valid context to which to apply
                     // those blocks. one valid tx in a given context, could be invalid in another. for example
@@ -63,6 +119,20 @@ impl Process {
                         .await
                         .map(move |count| stats_counter.add_tx_recv_cnt(count))?;
                 }
+                TransactionMsg::WarmUpTransactions(origin, txs) if self.read_only => {
+                    debug!(
+                        service_info.logger(),
+                        "dropping {} fragments of the mempool snapshot, the node is read-only",
+                        txs.len();
+                        "origin" => ?origin,
+                    );
+                }
+                TransactionMsg::WarmUpTransactions(origin, txs) => {
+                    // the peers of the node hold these fragments already,
+                    // they are not propagated
+                    let count = pool.insert_all(origin, txs).len();
+                    stats_counter.add_tx_recv_cnt(count);
+                }
                 TransactionMsg::RemoveTransactions(fragment_ids, status) => {
                     debug!(
                         service_info.logger(),
@@ -70,11 +140,41 @@ impl Process {
                     );
                     pool.remove_added_to_block(fragment_ids, status);
                 }
+                TransactionMsg::RestoreTransactions(_) if self.read_only => {}
+                TransactionMsg::RestoreTransactions(fragments) => {
+                    debug!(
+                        service_info.logger(),
+                        "restoring {} fragments from an abandoned branch",
+                        fragments.len()
+                    );
+                    pool.restore_rolled_back(fragments);
+                }
+                TransactionMsg::CancelTransaction(fragment_id, reply_handle) => {
+                    debug!(
+                        service_info.logger(),
+                        "processing request";
+                        "request" => "CancelTransaction",
+                        log::KEY_TRACE_ID => reply_handle.trace_id(),
+                    );
+                    reply_handle.reply_ok(pool.cancel(fragment_id));
+                }
                 TransactionMsg::GetLogs(reply_handle) => {
+                    debug!(
+                        service_info.logger(),
+                        "processing request";
+                        "request" => "GetLogs",
+                        log::KEY_TRACE_ID => reply_handle.trace_id(),
+                    );
                     let logs = pool.logs().logs().cloned().collect();
                     reply_handle.reply_ok(logs);
                 }
                 TransactionMsg::GetStatuses(fragment_ids, reply_handle) => {
+                    debug!(
+                        service_info.logger(),
+                        "processing request";
+                        "request" => "GetStatuses",
+                        log::KEY_TRACE_ID => reply_handle.trace_id(),
+                    );
                     let mut statuses = HashMap::new();
                     pool.logs().logs_by_ids(fragment_ids).into_iter().for_each(
                         |(fragment_id, log)| {
@@ -83,6 +183,49 @@ impl Process {
                     );
                     reply_handle.reply_ok(statuses);
                 }
+                TransactionMsg::GetFragment(fragment_id, reply_handle) => {
+                    debug!(
+                        service_info.logger(),
+                        "processing request";
+                        "request" => "GetFragment",
+                        log::KEY_TRACE_ID => reply_handle.trace_id(),
+                    );
+                    let status = pool
+                        .logs()
+                        .logs_by_ids(Some(fragment_id))
+                        .remove(&fragment_id)
+                        .map(|log| log.status().clone());
+                    let found = status.map(|status| (status, pool.get(&fragment_id).cloned()));
+                    reply_handle.reply_ok(found);
+                }
+                TransactionMsg::GetFragments(fragment_ids, reply_handle) => {
+                    debug!(
+                        service_info.logger(),
+                        "processing request";
+                        "request" => "GetFragments",
+                        log::KEY_TRACE_ID => reply_handle.trace_id(),
+                    );
+                    let fragments = pool.pending(&fragment_ids);
+                    service_info.spawn("get fragments", async move {
+                        let mut stream = stream::iter(fragments.into_iter().map(Ok).map(Ok));
+                        // the requester giving up on the reply is not an error
+                        let _ = reply_handle.start_sending().send_all(&mut stream).await;
+                    });
+                }
+                TransactionMsg::GetMempoolSnapshot(reply_handle) => {
+                    debug!(
+                        service_info.logger(),
+                        "processing request";
+                        "request" => "GetMempoolSnapshot",
+                        log::KEY_TRACE_ID => reply_handle.trace_id(),
+                    );
+                    let fragments = pool.snapshot(MEMPOOL_SNAPSHOT_MAX_FRAGMENTS);
+                    service_info.spawn("get mempool snapshot", async move {
+                        let mut stream = stream::iter(fragments.into_iter().map(Ok).map(Ok));
+                        // the requester giving up on the reply is not an error
+                        let _ = reply_handle.start_sending().send_all(&mut stream).await;
+                    });
+                }
                 TransactionMsg::SelectTransactions {
                     ledger,
                     block_date,
@@ -90,10 +233,21 @@ impl Process {
                     selection_alg,
                     reply_handle,
                 } => {
+                    debug!(
+                        service_info.logger(),
+                        "selecting fragments for a block";
+                        log::KEY_TRACE_ID => reply_handle.trace_id(),
+                    );
+                    let span = tracing::debug_span!("fragment_selection", date = %block_date);
+                    let _enter = span.enter();
                     let contents = pool.select(ledger, block_date, ledger_params, selection_alg);
+                    tracing::debug!(count = contents.iter().count(), "fragments selected");
                     reply_handle.reply_ok(contents);
                 }
             }
+
+            stats_counter.set_mempool_usage(pool.origin_usage());
+            stats_counter.set_mempool_summary(pool.summary());
         }
 
         Ok(())
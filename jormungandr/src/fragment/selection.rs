@@ -2,12 +2,17 @@ use super::logs::Logs;
 use super::pool::internal::Pool;
 use crate::{
     blockcfg::{BlockDate, Contents, ContentsBuilder, Ledger, LedgerParameters},
-    fragment::FragmentId,
+    fragment::{Fragment, FragmentId},
 };
 use chain_core::property::Fragment as _;
-use jormungandr_lib::interfaces::FragmentStatus;
+use chain_impl_mockchain::{
+    ledger::Error as LedgerError,
+    transaction::{InputEnum, Payload, Transaction, UnspecifiedAccountIdentifier},
+};
+use jormungandr_lib::interfaces::{FragmentOrigin, FragmentStatus, FragmentType, SelectionQuotas};
 
 use slog::Logger;
+use std::collections::HashMap;
 
 pub enum SelectionOutput {
     Commit { fragment_id: FragmentId },
@@ -37,17 +42,232 @@ pub enum FragmentSelectionAlgorithmParams {
 pub struct OldestFirst {
     builder: ContentsBuilder,
     current_total_size: u32,
+    quotas: SelectionQuotas,
+    type_counts: HashMap<FragmentType, u32>,
+    type_sizes: HashMap<FragmentType, u32>,
     logger: Logger,
 }
 
 impl OldestFirst {
-    pub fn new(logger: Logger) -> Self {
+    pub fn new(quotas: SelectionQuotas, logger: Logger) -> Self {
         OldestFirst {
             builder: ContentsBuilder::new(),
             current_total_size: 0,
+            quotas,
+            type_counts: HashMap::new(),
+            type_sizes: HashMap::new(),
             logger,
         }
     }
+
+    /// check the fragment does not exceed the maximum count of its type and
+    /// does not use the space reserved for fragments of other types
+    fn within_quotas(
+        &self,
+        fragment_type: Option<FragmentType>,
+        total_size: u32,
+        block_content_max_size: u32,
+    ) -> bool {
+        if let Some(fragment_type) = fragment_type {
+            if let Some(max_count) = self.quotas.max_count.get(&fragment_type) {
+                let count = self.type_counts.get(&fragment_type).copied().unwrap_or(0);
+                if count >= *max_count {
+                    return false;
+                }
+            }
+        }
+
+        let reserved_for_others: u32 = self
+            .quotas
+            .reserved_size
+            .iter()
+            .filter(|(reserved_type, _)| Some(**reserved_type) != fragment_type)
+            .map(|(reserved_type, reserved)| {
+                let used = self.type_sizes.get(reserved_type).copied().unwrap_or(0);
+                reserved.saturating_sub(used)
+            })
+            .sum();
+
+        total_size.saturating_add(reserved_for_others) <= block_content_max_size
+    }
+
+    fn count_selected(&mut self, fragment_type: Option<FragmentType>, fragment_size: u32) {
+        if let Some(fragment_type) = fragment_type {
+            *self.type_counts.entry(fragment_type).or_insert(0) += 1;
+            *self.type_sizes.entry(fragment_type).or_insert(0) += fragment_size;
+        }
+    }
+}
+
+/// the size of the fragment in the content of a block
+pub(super) fn fragment_size(fragment: &Fragment) -> u32 {
+    // TODO: replace everything to FragmentRaw in the node
+    fragment.to_raw().size_bytes_plus_size() as u32
+}
+
+pub(super) fn fragment_type(fragment: &Fragment) -> Option<FragmentType> {
+    match fragment {
+        Fragment::Initial(_) => None,
+        Fragment::OldUtxoDeclaration(_) => None,
+        Fragment::Transaction(_) => Some(FragmentType::Transaction),
+        Fragment::OwnerStakeDelegation(_) => Some(FragmentType::OwnerStakeDelegation),
+        Fragment::StakeDelegation(_) => Some(FragmentType::StakeDelegation),
+        Fragment::PoolRegistration(_) => Some(FragmentType::PoolRegistration),
+        Fragment::PoolRetirement(_) => Some(FragmentType::PoolRetirement),
+        Fragment::PoolUpdate(_) => Some(FragmentType::PoolUpdate),
+        Fragment::UpdateProposal(_) => None,
+        Fragment::UpdateVote(_) => None,
+        Fragment::VotePlan(_) => Some(FragmentType::VotePlan),
+        Fragment::VoteCast(_) => Some(FragmentType::VoteCast),
+        Fragment::VoteTally(_) => Some(FragmentType::VoteTally),
+        Fragment::EncryptedVoteTally(_) => Some(FragmentType::EncryptedVoteTally),
+    }
+}
+
+/// a fragment which failed to apply, with the reason of the failure
+type Deferred = (Fragment, FragmentOrigin, Failure);
+
+/// the error of the ledger a fragment failed to apply with
+struct Failure {
+    /// the description of the error and of its source
+    reason: String,
+    /// the error variant with its parameters
+    ledger_error: String,
+    /// the fragment may have been submitted ahead of another fragment of
+    /// the same account: the witness does not match the current spending
+    /// counter of the account
+    out_of_order: bool,
+}
+
+impl Failure {
+    fn into_status(self) -> FragmentStatus {
+        FragmentStatus::Rejected {
+            reason: self.reason,
+            ledger_error: Some(self.ledger_error),
+        }
+    }
+}
+
+/// outcome of an attempt to add a fragment to the block
+enum Attempt {
+    Applied,
+    Failed {
+        fragment: Fragment,
+        origin: FragmentOrigin,
+        failure: Failure,
+    },
+    Skipped,
+}
+
+impl OldestFirst {
+    fn try_apply(
+        &mut self,
+        ledger_simulation: &mut Ledger,
+        ledger_params: &LedgerParameters,
+        block_date: BlockDate,
+        fragment: Fragment,
+        origin: FragmentOrigin,
+        held_back: &mut Vec<(Fragment, FragmentOrigin)>,
+    ) -> Attempt {
+        let id = fragment.id();
+        let fragment_size = fragment_size(&fragment);
+        let total_size = self.current_total_size + fragment_size;
+        let max_content_size = self
+            .quotas
+            .max_content_size(ledger_params.block_content_max_size);
+
+        if total_size > max_content_size {
+            return Attempt::Skipped;
+        }
+
+        let logger = self.logger.new(o!("hash" => id.to_string()));
+        let fragment_type = fragment_type(&fragment);
+        if !self.within_quotas(fragment_type, total_size, max_content_size) {
+            debug!(
+                logger,
+                "fragment is over the selection quotas, keeping it for a later block"
+            );
+            held_back.push((fragment, origin));
+            return Attempt::Skipped;
+        }
+
+        debug!(logger, "applying fragment in simulation");
+        match ledger_simulation.apply_fragment(ledger_params, &fragment, block_date) {
+            Ok(ledger_new) => {
+                self.builder.push(fragment);
+                self.count_selected(fragment_type, fragment_size);
+                self.current_total_size = total_size;
+                *ledger_simulation = ledger_new;
+                debug!(logger, "successfully applied and committed the fragment");
+                Attempt::Applied
+            }
+            Err(error) => {
+                use std::error::Error as _;
+                let out_of_order = matches!(error, LedgerError::AccountInvalidSignature { .. });
+                let reason = if let Some(source) = error.source() {
+                    format!("{}: {}", error, source)
+                } else {
+                    error.to_string()
+                };
+                let ledger_error = format!("{:?}", error);
+                debug!(logger, "fragment failed to apply"; "reason" => %reason, "ledger_error" => %ledger_error);
+                Attempt::Failed {
+                    fragment,
+                    origin,
+                    failure: Failure {
+                        reason,
+                        ledger_error,
+                        out_of_order,
+                    },
+                }
+            }
+        }
+    }
+
+    /// retry the fragments spending from `account` which failed to apply,
+    /// in arrival order, for as long as some of them succeed.
+    fn retry_deferred(
+        &mut self,
+        account: &UnspecifiedAccountIdentifier,
+        deferred: &mut HashMap<UnspecifiedAccountIdentifier, Vec<Deferred>>,
+        ledger_simulation: &mut Ledger,
+        ledger_params: &LedgerParameters,
+        block_date: BlockDate,
+        held_back: &mut Vec<(Fragment, FragmentOrigin)>,
+    ) {
+        loop {
+            let pending = match deferred.remove(account) {
+                Some(pending) => pending,
+                None => return,
+            };
+            let pending_count = pending.len();
+            let mut remaining = Vec::new();
+            for (fragment, origin, _) in pending {
+                match self.try_apply(
+                    ledger_simulation,
+                    ledger_params,
+                    block_date,
+                    fragment,
+                    origin,
+                    held_back,
+                ) {
+                    Attempt::Applied | Attempt::Skipped => (),
+                    Attempt::Failed {
+                        fragment,
+                        origin,
+                        failure,
+                    } => remaining.push((fragment, origin, failure)),
+                }
+            }
+            let progress = remaining.len() < pending_count;
+            if !remaining.is_empty() {
+                deferred.insert(account.clone(), remaining);
+            }
+            if !progress {
+                return;
+            }
+        }
+    }
 }
 
 impl FragmentSelectionAlgorithm for OldestFirst {
@@ -64,40 +284,315 @@ impl FragmentSelectionAlgorithm for OldestFirst {
         pool: &mut Pool,
     ) {
         let mut ledger_simulation = ledger.clone();
+        // fragments held back by the quotas, to be returned to the pool
+        let mut held_back = Vec::new();
+        // fragments spending from an account which failed to apply because
+        // of their spending counter: it may only become usable once another
+        // fragment from the same account is applied, so they are not
+        // rejected right away. Any other failure is final.
+        let mut deferred = HashMap::new();
+        let max_content_size = self
+            .quotas
+            .max_content_size(ledger_params.block_content_max_size);
 
-        while let Some(fragment) = pool.remove_oldest() {
-            let id = fragment.id();
-            let fragment_raw = fragment.to_raw(); // TODO: replace everything to FragmentRaw in the node
-            let fragment_size = fragment_raw.size_bytes_plus_size() as u32;
-            let total_size = self.current_total_size + fragment_size;
-
-            if total_size <= ledger_params.block_content_max_size {
-                let logger = self.logger.new(o!("hash" => id.to_string()));
-                debug!(logger, "applying fragment in simulation");
-                match ledger_simulation.apply_fragment(ledger_params, &fragment, block_date) {
-                    Ok(ledger_new) => {
-                        self.builder.push(fragment);
-                        ledger_simulation = ledger_new;
-                        debug!(logger, "successfully applied and committed the fragment");
-                    }
-                    Err(error) => {
-                        use std::error::Error as _;
-                        let error = if let Some(source) = error.source() {
-                            format!("{}: {}", error, source)
-                        } else {
-                            error.to_string()
-                        };
-                        debug!(logger, "fragment is rejected"; "reason" => %error);
-                        logs.modify(id, FragmentStatus::Rejected { reason: error })
+        while let Some((fragment, origin)) = pool.remove_oldest() {
+            let account = source_account(&fragment);
+            match self.try_apply(
+                &mut ledger_simulation,
+                ledger_params,
+                block_date,
+                fragment,
+                origin,
+                &mut held_back,
+            ) {
+                Attempt::Applied => {
+                    if let Some(account) = account {
+                        self.retry_deferred(
+                            &account,
+                            &mut deferred,
+                            &mut ledger_simulation,
+                            ledger_params,
+                            block_date,
+                            &mut held_back,
+                        );
                     }
                 }
+                Attempt::Failed {
+                    fragment,
+                    origin,
+                    failure,
+                } => match account {
+                    Some(account) if failure.out_of_order => deferred
+                        .entry(account)
+                        .or_insert_with(Vec::new)
+                        .push((fragment, origin, failure)),
+                    _ => {
+                        debug!(self.logger, "fragment is rejected"; "hash" => %fragment.id(), "reason" => %failure.reason);
+                        logs.modify(fragment.id(), failure.into_status())
+                    }
+                },
+                Attempt::Skipped => (),
+            }
 
-                self.current_total_size = total_size;
+            if self.current_total_size == max_content_size {
+                break;
+            }
+        }
 
-                if total_size == ledger_params.block_content_max_size {
-                    break;
-                }
+        for (fragment, origin) in held_back {
+            pool.put_back(fragment, origin);
+        }
+
+        for (fragment, origin, failure) in deferred.into_iter().flat_map(|(_, pending)| pending) {
+            let id = fragment.id();
+            // a retry may have failed for another reason than the counter
+            if !failure.out_of_order || !pool.defer(fragment, origin) {
+                debug!(self.logger, "fragment is rejected"; "hash" => %id, "reason" => %failure.reason);
+                logs.modify(id, failure.into_status())
+            }
+        }
+    }
+}
+
+/// the account the fragment spends from, if any. Fragments from the same
+/// account need to be applied in the order of their spending counter.
+fn source_account(fragment: &Fragment) -> Option<UnspecifiedAccountIdentifier> {
+    fn first_account_input<P: Payload>(
+        tx: &Transaction<P>,
+    ) -> Option<UnspecifiedAccountIdentifier> {
+        tx.as_slice()
+            .inputs()
+            .iter()
+            .find_map(|input| match input.to_enum() {
+                InputEnum::AccountInput(account, _) => Some(account),
+                InputEnum::UtxoInput(_) => None,
+            })
+    }
+
+    match fragment {
+        Fragment::Initial(_) => None,
+        Fragment::OldUtxoDeclaration(_) => None,
+        Fragment::Transaction(ref tx) => first_account_input(tx),
+        Fragment::OwnerStakeDelegation(ref tx) => first_account_input(tx),
+        Fragment::StakeDelegation(ref tx) => first_account_input(tx),
+        Fragment::PoolRegistration(ref tx) => first_account_input(tx),
+        Fragment::PoolRetirement(ref tx) => first_account_input(tx),
+        Fragment::PoolUpdate(ref tx) => first_account_input(tx),
+        Fragment::UpdateProposal(_) => None,
+        Fragment::UpdateVote(_) => None,
+        Fragment::VotePlan(ref tx) => first_account_input(tx),
+        Fragment::VoteCast(ref tx) => first_account_input(tx),
+        Fragment::VoteTally(ref tx) => first_account_input(tx),
+        Fragment::EncryptedVoteTally(ref tx) => first_account_input(tx),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{blockcfg::HeaderId, utils::test_fixtures};
+    use chain_core::property::Fragment as _;
+    use chain_crypto::{Ed25519, SecretKey};
+    use jormungandr_lib::interfaces::FragmentLog;
+    use slog::Discard;
+    use std::collections::BTreeMap;
+
+    /// a ledger where the account of the key holds some funds, with no fees
+    fn ledger_with_account(key: &SecretKey<Ed25519>) -> (HeaderId, Ledger, LedgerParameters) {
+        let leader = SecretKey::<Ed25519>::generate(rand_core::OsRng).to_public();
+        let block0 = test_fixtures::block0_with_funds(&leader, &[(key.to_public(), 1_000)]);
+        let (ledger, ledger_params) = test_fixtures::ledger(&block0);
+        (block0.header.hash(), ledger, ledger_params)
+    }
+
+    /// a transfer from the account of the key to a new account, with the
+    /// given spending counter
+    fn transfer(block0_hash: &HeaderId, key: &SecretKey<Ed25519>, counter: u32) -> Fragment {
+        let receiver = SecretKey::<Ed25519>::generate(rand_core::OsRng).to_public();
+        test_fixtures::transfer(block0_hash, key, &receiver, counter)
+    }
+
+    fn select(
+        ledger: &Ledger,
+        ledger_params: &LedgerParameters,
+        logs: &mut Logs,
+        pool: &mut Pool,
+    ) -> Vec<FragmentId> {
+        let mut selection =
+            OldestFirst::new(SelectionQuotas::default(), Logger::root(Discard, o!()));
+        selection.select(ledger, ledger_params, ledger.date(), logs, pool);
+        selection
+            .finalize()
+            .iter()
+            .map(|fragment| fragment.id())
+            .collect()
+    }
+
+    fn submit(logs: &mut Logs, pool: &mut Pool, fragment: &Fragment) {
+        logs.insert(FragmentLog::new(fragment.id().into(), FragmentOrigin::Rest));
+        pool.insert(fragment.clone(), FragmentOrigin::Rest);
+    }
+
+    #[test]
+    fn fragments_of_an_account_are_applied_in_spending_counter_order() {
+        let key = SecretKey::<Ed25519>::generate(rand_core::OsRng);
+        let (block0_hash, ledger, ledger_params) = ledger_with_account(&key);
+        let first = transfer(&block0_hash, &key, 0);
+        let second = transfer(&block0_hash, &key, 1);
+
+        let mut logs = Logs::new(16);
+        let mut pool = Pool::new(16, None);
+        // the second transfer arrives first
+        submit(&mut logs, &mut pool, &second);
+        submit(&mut logs, &mut pool, &first);
+
+        assert_eq!(
+            select(&ledger, &ledger_params, &mut logs, &mut pool),
+            vec![first.id(), second.id()]
+        );
+        assert_eq!(pool.len(), 0);
+    }
+
+    #[test]
+    fn fragments_never_applicable_are_rejected_after_some_selections() {
+        let key = SecretKey::<Ed25519>::generate(rand_core::OsRng);
+        let (block0_hash, ledger, ledger_params) = ledger_with_account(&key);
+        // the fragment with the spending counter 0 never comes
+        let gap = transfer(&block0_hash, &key, 1);
+
+        let mut logs = Logs::new(16);
+        let mut pool = Pool::new(16, None);
+        submit(&mut logs, &mut pool, &gap);
+
+        for _ in 0..3 {
+            assert!(select(&ledger, &ledger_params, &mut logs, &mut pool).is_empty());
+            assert_eq!(pool.len(), 1);
+            assert!(logs.logs().all(|log| log.is_pending()));
+        }
+        assert!(select(&ledger, &ledger_params, &mut logs, &mut pool).is_empty());
+        assert_eq!(pool.len(), 0);
+        assert!(logs.logs().all(|log| log.is_rejected()));
+    }
+
+    #[test]
+    fn fragments_failing_for_another_reason_are_rejected_right_away() {
+        let key = SecretKey::<Ed25519>::generate(rand_core::OsRng);
+        let (block0_hash, ledger, ledger_params) = ledger_with_account(&key);
+        // this account has never been funded
+        let unknown = SecretKey::<Ed25519>::generate(rand_core::OsRng);
+        let fragment = transfer(&block0_hash, &unknown, 0);
+
+        let mut logs = Logs::new(16);
+        let mut pool = Pool::new(16, None);
+        submit(&mut logs, &mut pool, &fragment);
+
+        assert!(select(&ledger, &ledger_params, &mut logs, &mut pool).is_empty());
+        assert_eq!(pool.len(), 0);
+        assert!(logs.logs().all(|log| log.is_rejected()));
+    }
+
+    #[test]
+    fn rejected_fragments_record_the_ledger_error() {
+        let key = SecretKey::<Ed25519>::generate(rand_core::OsRng);
+        let (block0_hash, ledger, ledger_params) = ledger_with_account(&key);
+        let gap = transfer(&block0_hash, &key, 1);
+
+        let mut logs = Logs::new(16);
+        let mut pool = Pool::new(16, None);
+        submit(&mut logs, &mut pool, &gap);
+        for _ in 0..4 {
+            select(&ledger, &ledger_params, &mut logs, &mut pool);
+        }
+
+        let log = logs.logs().next().unwrap();
+        match log.status() {
+            FragmentStatus::Rejected {
+                reason,
+                ledger_error: Some(ledger_error),
+            } => {
+                assert!(!reason.is_empty());
+                assert!(ledger_error.starts_with("AccountInvalidSignature"));
             }
+            status => panic!("unexpected status {:?}", status),
+        }
+    }
+
+    #[test]
+    fn quotas_are_checked_against_the_capped_content_size() {
+        let mut reserved_size = BTreeMap::new();
+        reserved_size.insert(FragmentType::VoteCast, 100);
+        let quotas = SelectionQuotas {
+            reserved_size,
+            max_content_size: Some(500),
+            ..Default::default()
+        };
+        let max_content_size = quotas.max_content_size(4096);
+        assert_eq!(max_content_size, 500);
+        let selection = OldestFirst::new(quotas, Logger::root(Discard, o!()));
+
+        let transaction = Some(FragmentType::Transaction);
+        assert!(selection.within_quotas(transaction, 400, max_content_size));
+        assert!(!selection.within_quotas(transaction, 401, max_content_size));
+
+        let vote_cast = Some(FragmentType::VoteCast);
+        assert!(selection.within_quotas(vote_cast, 500, max_content_size));
+        assert!(!selection.within_quotas(vote_cast, 501, max_content_size));
+    }
+
+    #[test]
+    fn the_count_of_a_fragment_type_is_capped() {
+        let mut max_count = BTreeMap::new();
+        max_count.insert(FragmentType::VoteCast, 2);
+        let quotas = SelectionQuotas {
+            max_count,
+            ..Default::default()
+        };
+        let mut selection = OldestFirst::new(quotas, Logger::root(Discard, o!()));
+
+        let vote_cast = Some(FragmentType::VoteCast);
+        for _ in 0..2 {
+            assert!(selection.within_quotas(vote_cast, 100, 1000));
+            selection.count_selected(vote_cast, 10);
         }
+        assert!(!selection.within_quotas(vote_cast, 100, 1000));
+
+        // the other types and the fragments without a type are not capped
+        assert!(selection.within_quotas(Some(FragmentType::Transaction), 100, 1000));
+        assert!(selection.within_quotas(None, 100, 1000));
+    }
+
+    #[test]
+    fn reserved_space_is_released_as_it_is_used() {
+        let mut reserved_size = BTreeMap::new();
+        reserved_size.insert(FragmentType::VoteCast, 100);
+        let mut selection = OldestFirst::new(
+            SelectionQuotas {
+                reserved_size,
+                ..Default::default()
+            },
+            Logger::root(Discard, o!()),
+        );
+
+        let transaction = Some(FragmentType::Transaction);
+        assert!(!selection.within_quotas(transaction, 401, 500));
+        assert!(!selection.within_quotas(None, 401, 500));
+
+        selection.count_selected(Some(FragmentType::VoteCast), 60);
+        assert!(selection.within_quotas(transaction, 460, 500));
+        assert!(!selection.within_quotas(transaction, 461, 500));
+
+        // once the reserved space is used up, it does not count anymore
+        selection.count_selected(Some(FragmentType::VoteCast), 60);
+        assert!(selection.within_quotas(transaction, 500, 500));
+    }
+
+    #[test]
+    fn a_cap_above_the_protocol_limit_has_no_effect() {
+        let quotas = SelectionQuotas {
+            max_content_size: Some(1_000_000),
+            ..Default::default()
+        };
+        assert_eq!(quotas.max_content_size(4096), 4096);
+        assert_eq!(SelectionQuotas::default().max_content_size(4096), 4096);
     }
 }
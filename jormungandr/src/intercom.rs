@@ -3,8 +3,12 @@ use crate::blockcfg::{
 };
 use crate::blockchain::{Checkpoints, StorageError};
 use crate::fragment::selection::FragmentSelectionAlgorithmParams;
+use crate::log::KEY_TRACE_ID;
 use crate::network::p2p::{comm::PeerInfo, Address};
-use crate::utils::async_msg::{self, MessageBox, MessageQueue};
+use crate::utils::{
+    async_msg::{self, MessageBox, MessageQueue},
+    buffer_gauge::{BufferGauge, BufferTracker},
+};
 use chain_impl_mockchain::fragment::Contents as FragmentContents;
 use chain_network::error as net_error;
 use jormungandr_lib::interfaces::{FragmentLog, FragmentOrigin, FragmentStatus};
@@ -12,7 +16,7 @@ use jormungandr_lib::interfaces::{FragmentLog, FragmentOrigin, FragmentStatus};
 use futures::channel::{mpsc, oneshot};
 use futures::prelude::*;
 use futures::ready;
-use slog::Logger;
+use slog::{Logger, Record, Serializer};
 use std::{
     collections::HashMap,
     error,
@@ -20,7 +24,9 @@ use std::{
     marker::PhantomData,
     pin::Pin,
     task::{Context, Poll},
+    time::Duration,
 };
+use tokio::time::{self, Delay};
 
 /// The error values passed via intercom messages.
 #[derive(Debug)]
@@ -60,6 +66,16 @@ impl Error {
         }
     }
 
+    pub fn deadline_exceeded<T>(cause: T) -> Self
+    where
+        T: Into<Box<dyn error::Error + Send + Sync>>,
+    {
+        Error {
+            code: net_error::Code::DeadlineExceeded,
+            cause: cause.into(),
+        }
+    }
+
     pub fn failed_precondition<T>(cause: T) -> Self
     where
         T: Into<Box<dyn error::Error + Send + Sync>>,
@@ -147,14 +163,55 @@ impl error::Error for Error {
     }
 }
 
+/// Identifier of a single request, generated at the network or REST
+/// entry point and carried in the reply handles, so that processing of the
+/// request can be followed in the logs of all the tasks involved.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TraceId(u64);
+
+impl TraceId {
+    pub fn generate() -> Self {
+        TraceId(rand::random())
+    }
+}
+
+impl Display for TraceId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl slog::Value for TraceId {
+    fn serialize(
+        &self,
+        _: &Record,
+        key: slog::Key,
+        serializer: &mut dyn Serializer,
+    ) -> slog::Result {
+        serializer.emit_arguments(key, &format_args!("{}", self))
+    }
+}
+
 type ReplySender<T> = oneshot::Sender<Result<T, Error>>;
 
 #[derive(Debug)]
 pub struct ReplyHandle<T> {
     sender: ReplySender<T>,
+    trace_id: TraceId,
 }
 
 impl<T> ReplyHandle<T> {
+    /// Returns the trace ID of the request this handle replies to.
+    pub fn trace_id(&self) -> TraceId {
+        self.trace_id
+    }
+
+    /// Checks whether the requester has given up on the reply, either
+    /// because it timed out or because the future was dropped.
+    pub fn is_canceled(&self) -> bool {
+        self.sender.is_canceled()
+    }
+
     pub fn reply(self, result: Result<T, Error>) {
         // Ignoring a send error: it means the result is no longer needed
         let _ = self.sender.send(result);
@@ -171,16 +228,33 @@ impl<T> ReplyHandle<T> {
 
 pub struct ReplyFuture<T> {
     receiver: oneshot::Receiver<Result<T, Error>>,
+    deadline: Option<Delay>,
     logger: Logger,
 }
 
+impl<T> ReplyFuture<T> {
+    /// Fails the request with `DeadlineExceeded` if the reply is not
+    /// received in time. The processing task is then notified that the
+    /// reply is no longer needed.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.deadline = Some(time::delay_for(timeout));
+        self
+    }
+}
+
 impl<T> Unpin for ReplyFuture<T> {}
 
 impl<T> Future for ReplyFuture<T> {
     type Output = Result<T, Error>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<T, Error>> {
-        Pin::new(&mut self.receiver).poll(cx).map(|res| match res {
+        let res = Pin::new(&mut self.receiver).poll(cx);
+        if res.is_pending() && poll_deadline(&mut self.deadline, cx) {
+            warn!(self.logger, "request timed out");
+            self.receiver.close();
+            return Poll::Ready(Err(Error::deadline_exceeded("request timed out")));
+        }
+        res.map(|res| match res {
             Ok(Ok(item)) => {
                 debug!(self.logger, "request processed");
                 Ok(item)
@@ -198,9 +272,19 @@ impl<T> Future for ReplyFuture<T> {
 }
 
 pub fn unary_reply<T>(logger: Logger) -> (ReplyHandle<T>, ReplyFuture<T>) {
+    let trace_id = TraceId::generate();
+    unary_reply_traced(trace_id, logger.new(o!(KEY_TRACE_ID => trace_id)))
+}
+
+// The logger is expected to already have the trace ID in its values.
+fn unary_reply_traced<T>(trace_id: TraceId, logger: Logger) -> (ReplyHandle<T>, ReplyFuture<T>) {
     let (sender, receiver) = oneshot::channel();
-    let future = ReplyFuture { receiver, logger };
-    (ReplyHandle { sender }, future)
+    let future = ReplyFuture {
+        receiver,
+        deadline: None,
+        logger,
+    };
+    (ReplyHandle { sender, trace_id }, future)
 }
 
 #[derive(Debug)]
@@ -252,9 +336,22 @@ impl<T: 'static> error::Error for ReplyTrySendError<T> {
 pub struct ReplyStreamHandle<T> {
     lead_sender: oneshot::Sender<Result<mpsc::Receiver<Result<T, Error>>, Error>>,
     buffer_size: usize,
+    tracker: BufferTracker,
+    trace_id: TraceId,
 }
 
 impl<T> ReplyStreamHandle<T> {
+    /// Returns the trace ID of the request this handle replies to.
+    pub fn trace_id(&self) -> TraceId {
+        self.trace_id
+    }
+
+    /// Checks whether the requester has given up on the reply, either
+    /// because it timed out or because the future was dropped.
+    pub fn is_canceled(&self) -> bool {
+        self.lead_sender.is_canceled()
+    }
+
     fn reply(self, result: Result<mpsc::Receiver<Result<T, Error>>, Error>) {
         // Ignoring a send error: it means the result is no longer needed
         let _ = self.lead_sender.send(result);
@@ -262,8 +359,9 @@ impl<T> ReplyStreamHandle<T> {
 
     pub fn start_sending(self) -> ReplyStreamSink<T> {
         let (sender, receiver) = mpsc::channel(self.buffer_size);
+        let tracker = self.tracker.clone();
         self.reply(Ok(receiver));
-        ReplyStreamSink { sender }
+        ReplyStreamSink { sender, tracker }
     }
 
     pub fn reply_error(self, error: Error) {
@@ -274,6 +372,7 @@ impl<T> ReplyStreamHandle<T> {
 #[derive(Debug)]
 pub struct ReplyStreamSink<T> {
     sender: mpsc::Sender<Result<T, Error>>,
+    tracker: BufferTracker,
 }
 
 impl<T> Unpin for ReplyStreamSink<T> {}
@@ -282,17 +381,35 @@ impl<T> Clone for ReplyStreamSink<T> {
     fn clone(&self) -> Self {
         ReplyStreamSink {
             sender: self.sender.clone(),
+            tracker: self.tracker.clone(),
         }
     }
 }
 
 impl<T> ReplyStreamSink<T> {
+    /// Checks whether the requester has stopped receiving the stream,
+    /// in which case there is no point in producing more items.
+    pub fn is_canceled(&self) -> bool {
+        self.sender.is_closed()
+    }
+
     pub fn try_send_item(&mut self, item: Result<T, Error>) -> Result<(), ReplyTrySendError<T>> {
-        self.sender.try_send(item).map_err(ReplyTrySendError)
+        match self.sender.try_send(item) {
+            Ok(()) => {
+                self.tracker.items_queued(1);
+                Ok(())
+            }
+            Err(e) => {
+                self.tracker.set_stalled(e.is_full());
+                Err(ReplyTrySendError(e))
+            }
+        }
     }
 
     pub fn poll_ready(&mut self, cx: &mut Context) -> Poll<Result<(), ReplySendError>> {
-        self.sender.poll_ready(cx).map_err(|_| ReplySendError)
+        let res = self.sender.poll_ready(cx);
+        self.tracker.set_stalled(res.is_pending());
+        res.map_err(|_| ReplySendError)
     }
 }
 
@@ -300,15 +417,15 @@ impl<T> Sink<Result<T, Error>> for ReplyStreamSink<T> {
     type Error = ReplySendError;
 
     fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
-        Pin::new(&mut self.sender)
-            .poll_ready(cx)
-            .map_err(|_| ReplySendError)
+        ReplyStreamSink::poll_ready(&mut *self, cx)
     }
 
     fn start_send(mut self: Pin<&mut Self>, item: Result<T, Error>) -> Result<(), Self::Error> {
         Pin::new(&mut self.sender)
             .start_send(item)
-            .map_err(|_| ReplySendError)
+            .map_err(|_| ReplySendError)?;
+        self.tracker.items_queued(1);
+        Ok(())
     }
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
@@ -326,10 +443,22 @@ impl<T> Sink<Result<T, Error>> for ReplyStreamSink<T> {
 
 pub struct ReplyStreamFuture<T, E> {
     lead_receiver: oneshot::Receiver<Result<mpsc::Receiver<Result<T, Error>>, Error>>,
+    deadline: Option<Delay>,
+    tracker: BufferTracker,
     logger: Logger,
     _phantom_error: PhantomData<E>,
 }
 
+impl<T, E> ReplyStreamFuture<T, E> {
+    /// Fails the request with `DeadlineExceeded` if the stream is not
+    /// received entirely in time. The processing task is then notified
+    /// that the rest of the stream is no longer needed.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.deadline = Some(time::delay_for(timeout));
+        self
+    }
+}
+
 impl<T, E> Unpin for ReplyStreamFuture<T, E> {}
 
 impl<T, E> Future for ReplyStreamFuture<T, E>
@@ -339,14 +468,21 @@ where
     type Output = Result<ReplyStream<T, E>, E>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
-        let receiver = ready!(Pin::new(&mut self.lead_receiver).poll(cx)).map_err(
-            |e: oneshot::Canceled| {
-                warn!(self.logger, "response canceled by the processing task");
-                Error::from(e)
-            },
-        )??;
+        let res = Pin::new(&mut self.lead_receiver).poll(cx);
+        if res.is_pending() && poll_deadline(&mut self.deadline, cx) {
+            warn!(self.logger, "request timed out");
+            self.lead_receiver.close();
+            let err = Error::deadline_exceeded("request timed out");
+            return Poll::Ready(Err(err.into()));
+        }
+        let receiver = ready!(res).map_err(|e: oneshot::Canceled| {
+            warn!(self.logger, "response canceled by the processing task");
+            Error::from(e)
+        })??;
         let stream = ReplyStream {
             receiver,
+            deadline: self.deadline.take(),
+            tracker: self.tracker.clone(),
             logger: self.logger.clone(),
             _phantom_error: PhantomData,
         };
@@ -356,6 +492,8 @@ where
 
 pub struct ReplyStream<T, E> {
     receiver: mpsc::Receiver<Result<T, Error>>,
+    deadline: Option<Delay>,
+    tracker: BufferTracker,
     logger: Logger,
     _phantom_error: PhantomData<E>,
 }
@@ -376,20 +514,56 @@ where
     type Item = Result<T, E>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
-        Pin::new(&mut self.receiver)
-            .poll_next(cx)
-            .map(|maybe_res| match maybe_res {
-                Some(Ok(item)) => Some(Ok(item)),
-                None => None,
-                Some(Err(e)) => {
-                    info!(
-                        self.logger,
-                        "error while streaming response";
-                        "error" => ?e,
-                    );
-                    Some(Err(e.into()))
-                }
-            })
+        let res = Pin::new(&mut self.receiver).poll_next(cx);
+        if res.is_pending() && poll_deadline(&mut self.deadline, cx) {
+            warn!(self.logger, "response stream timed out");
+            self.close();
+            let err = Error::deadline_exceeded("response stream timed out");
+            return Poll::Ready(Some(Err(err.into())));
+        }
+        if let Poll::Ready(Some(_)) = res {
+            self.tracker.items_taken(1);
+        }
+        res.map(|maybe_res| match maybe_res {
+            Some(Ok(item)) => Some(Ok(item)),
+            None => None,
+            Some(Err(e)) => {
+                info!(
+                    self.logger,
+                    "error while streaming response";
+                    "error" => ?e,
+                );
+                Some(Err(e.into()))
+            }
+        })
+    }
+}
+
+impl<T, E> ReplyStream<T, E> {
+    // Stops the stream, notifying the processing task. The items left
+    // in the buffer are accounted for and discarded.
+    fn close(&mut self) {
+        self.receiver.close();
+        while let Ok(Some(_)) = self.receiver.try_next() {
+            self.tracker.items_taken(1);
+        }
+    }
+}
+
+impl<T, E> Drop for ReplyStream<T, E> {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+// Polls the deadline, if any, returning true once when it has elapsed.
+fn poll_deadline(deadline: &mut Option<Delay>, cx: &mut Context) -> bool {
+    match deadline {
+        Some(delay) if Pin::new(delay).poll(cx).is_ready() => {
+            *deadline = None;
+            true
+        }
+        _ => false,
     }
 }
 
@@ -412,18 +586,27 @@ impl<T> Stream for UploadStream<T> {
     }
 }
 
+/// Creates a handle for replying with a stream of items and the future
+/// receiving the stream. The buffer of the stream is sized and monitored
+/// by the given gauge.
 pub fn stream_reply<T, E>(
-    buffer_size: usize,
+    gauge: &'static BufferGauge,
     logger: Logger,
 ) -> (ReplyStreamHandle<T>, ReplyStreamFuture<T, E>) {
     let (lead_sender, lead_receiver) = oneshot::channel();
+    let trace_id = TraceId::generate();
+    let tracker = gauge.track();
     let handle = ReplyStreamHandle {
         lead_sender,
-        buffer_size,
+        buffer_size: gauge.capacity(),
+        tracker: tracker.clone(),
+        trace_id,
     };
     let future = ReplyStreamFuture {
         lead_receiver,
-        logger,
+        deadline: None,
+        tracker,
+        logger: logger.new(o!(KEY_TRACE_ID => trace_id)),
         _phantom_error: PhantomData,
     };
     (handle, future)
@@ -441,6 +624,11 @@ pub struct RequestSink<T> {
 }
 
 impl<T, R> RequestStreamHandle<T, R> {
+    /// Returns the trace ID of the request stream.
+    pub fn trace_id(&self) -> TraceId {
+        self.reply.trace_id()
+    }
+
     pub fn into_stream_and_reply(self) -> (MessageQueue<T>, ReplyHandle<R>) {
         (self.receiver, self.reply)
     }
@@ -500,12 +688,17 @@ impl<T> Sink<T> for RequestSink<T> {
     }
 }
 
+/// Creates the handle for processing a stream of request items, the sink
+/// to feed the items and the future receiving the reply. The buffer of
+/// the request stream is sized and monitored by the given gauge.
 pub fn stream_request<T, R>(
-    buffer: usize,
+    gauge: &'static BufferGauge,
     logger: Logger,
 ) -> (RequestStreamHandle<T, R>, RequestSink<T>, ReplyFuture<R>) {
-    let (sender, receiver) = async_msg::channel(buffer);
-    let (reply, reply_future) = unary_reply(logger.clone());
+    let trace_id = TraceId::generate();
+    let logger = logger.new(o!(KEY_TRACE_ID => trace_id));
+    let (sender, receiver) = async_msg::gauged_channel(gauge);
+    let (reply, reply_future) = unary_reply_traced(trace_id, logger.clone());
     let handle = RequestStreamHandle { receiver, reply };
     let sink = RequestSink { sender, logger };
     (handle, sink, reply_future)
@@ -516,12 +709,30 @@ pub fn stream_request<T, R>(
 #[derive(Debug)]
 pub enum TransactionMsg {
     SendTransaction(FragmentOrigin, Vec<Fragment>),
+    /// fragments of the mempool snapshot of a peer, added to the pool
+    /// without being propagated
+    WarmUpTransactions(FragmentOrigin, Vec<Fragment>),
     RemoveTransactions(Vec<FragmentId>, FragmentStatus),
+    /// fragments from blocks rolled back by a branch switch
+    RestoreTransactions(Vec<Fragment>),
+    /// evict a pending fragment on request of the node operator,
+    /// replies `false` if the fragment is not in the pool
+    CancelTransaction(FragmentId, ReplyHandle<bool>),
     GetLogs(ReplyHandle<Vec<FragmentLog>>),
     GetStatuses(
         Vec<FragmentId>,
         ReplyHandle<HashMap<FragmentId, FragmentStatus>>,
     ),
+    /// the status of a fragment, along with the fragment itself if it is
+    /// still in the pool. Replies `None` if the fragment is not in the logs.
+    GetFragment(
+        FragmentId,
+        ReplyHandle<Option<(FragmentStatus, Option<Fragment>)>>,
+    ),
+    /// the pending fragments with the given ids
+    GetFragments(Vec<FragmentId>, ReplyStreamHandle<Fragment>),
+    /// a capped snapshot of the pending fragments, oldest first
+    GetMempoolSnapshot(ReplyStreamHandle<Fragment>),
     SelectTransactions {
         ledger: Ledger,
         block_date: BlockDate,
@@ -542,6 +753,21 @@ pub enum ClientMsg {
     PullBlocksToTip(Vec<HeaderHash>, ReplyStreamHandle<Block>),
 }
 
+impl ClientMsg {
+    /// Checks whether the requester has given up on the reply while the
+    /// message was queued.
+    pub fn is_canceled(&self) -> bool {
+        match self {
+            ClientMsg::GetBlockTip(handle) => handle.is_canceled(),
+            ClientMsg::GetHeaders(_, handle) => handle.is_canceled(),
+            ClientMsg::GetHeadersRange(_, _, handle) => handle.is_canceled(),
+            ClientMsg::GetBlocks(_, handle) => handle.is_canceled(),
+            ClientMsg::PullBlocks(_, _, handle) => handle.is_canceled(),
+            ClientMsg::PullBlocksToTip(_, handle) => handle.is_canceled(),
+        }
+    }
+}
+
 impl Debug for ClientMsg {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -1,12 +1,28 @@
+use crate::utils::json_store::{JsonStore, Record};
 pub use jormungandr_lib::interfaces::LeadershipLogStatus;
 use jormungandr_lib::interfaces::{LeadershipLog, LeadershipLogId};
-use std::sync::Arc;
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use tokio::sync::RwLock;
 
+const HISTORY_FILE_NAME: &str = "leadership-logs.json";
+
+/// the path of the history of the finished logs in the storage directory
+pub fn history_path<P: AsRef<Path>>(storage_dir: P) -> PathBuf {
+    storage_dir.as_ref().join(HISTORY_FILE_NAME)
+}
+
 /// all leadership logs, allow for following up on the different entity
 /// of the blockchain
 #[derive(Clone)]
-pub struct Logs(Arc<RwLock<internal::Logs>>);
+pub struct Logs {
+    inner: Arc<RwLock<internal::Logs>>,
+    /// the finished logs, by epoch of the scheduled event
+    history: JsonStore<LeadershipLog>,
+}
 
 /// leadership log handle. will allow to update the status of the log
 /// without having to hold the [`Logs`]
@@ -46,7 +62,7 @@ impl LeadershipLogHandle {
     /// on non-release build, this function will panic if the log was already
     /// marked as finished.
     ///
-    pub async fn mark_finished(&self) {
+    pub async fn mark_finished(&self) -> io::Result<()> {
         self.logs.mark_finished(self.internal_id).await
     }
 }
@@ -55,13 +71,28 @@ impl Logs {
     /// create a Leadership Logs. Logs will be removed once the `Logs` passed
     /// beyond a certain number of entries.
     ///
+    /// The history of the finished logs is only kept in memory.
+    ///
     pub fn new(cap: usize) -> Self {
-        Logs(Arc::new(RwLock::new(internal::Logs::new(cap))))
+        Logs {
+            inner: Arc::new(RwLock::new(internal::Logs::new(cap))),
+            history: JsonStore::in_memory(None),
+        }
+    }
+
+    /// create a Leadership Logs keeping the history of the finished logs
+    /// in the file at the given path, loading it if it exists.
+    ///
+    pub fn open<P: AsRef<Path>>(cap: usize, history_path: P) -> io::Result<Self> {
+        Ok(Logs {
+            inner: Arc::new(RwLock::new(internal::Logs::new(cap))),
+            history: JsonStore::open(history_path, None)?,
+        })
     }
 
     pub async fn insert(&self, log: LeadershipLog) -> Result<LeadershipLogHandle, ()> {
         let logs = self.clone();
-        let id = logs.0.write().await.insert(log);
+        let id = logs.inner.write().await.insert(log);
         Ok(LeadershipLogHandle {
             internal_id: id,
             logs,
@@ -69,31 +100,60 @@ impl Logs {
     }
 
     async fn mark_wake(&self, leadership_log_id: LeadershipLogId) {
-        let inner = self.0.clone();
-        inner.write().await.mark_wake(&leadership_log_id);
+        self.inner.write().await.mark_wake(&leadership_log_id);
     }
 
     async fn set_status(&self, leadership_log_id: LeadershipLogId, status: LeadershipLogStatus) {
-        let inner = self.0.clone();
-        inner.write().await.set_status(&leadership_log_id, status);
+        self.inner
+            .write()
+            .await
+            .set_status(&leadership_log_id, status);
     }
 
-    async fn mark_finished(&self, leadership_log_id: LeadershipLogId) {
-        let inner = self.0.clone();
-        inner.write().await.mark_finished(&leadership_log_id);
+    async fn mark_finished(&self, leadership_log_id: LeadershipLogId) -> io::Result<()> {
+        let finished = self.inner.write().await.mark_finished(&leadership_log_id);
+        match finished {
+            Some(log) => self.history.record(log).await,
+            None => Ok(()),
+        }
     }
 
     pub async fn logs(&self) -> Vec<LeadershipLog> {
-        let inner = self.0.clone();
-        let guard = inner.read().await;
+        let guard = self.inner.read().await;
         guard.logs().cloned().collect()
     }
+
+    /// the finished logs of the events scheduled in the given epoch, `None`
+    /// if no event of this epoch has finished
+    pub async fn epoch(&self, epoch: u32) -> Option<Vec<LeadershipLog>> {
+        let logs = self
+            .history
+            .filter(|log| internal::epoch_of(log) == epoch)
+            .await;
+        if logs.is_empty() {
+            None
+        } else {
+            Some(logs)
+        }
+    }
+}
+
+impl Record for LeadershipLog {
+    type Key = (u32, LeadershipLogId);
+
+    fn key(&self) -> Self::Key {
+        (internal::epoch_of(self), self.leadership_log_id())
+    }
 }
 
 pub(super) mod internal {
-    use super::{LeadershipLog, LeadershipLogId, LeadershipLogStatus};
+    use super::*;
     use lru::LruCache;
 
+    pub fn epoch_of(log: &LeadershipLog) -> u32 {
+        log.scheduled_at_date().as_ref().epoch
+    }
+
     pub struct Logs {
         entries: LruCache<LeadershipLogId, LeadershipLog>,
     }
@@ -128,10 +188,14 @@ pub(super) mod internal {
             }
         }
 
-        pub fn mark_finished(&mut self, leadership_log_id: &LeadershipLogId) {
-            if let Some(ref mut log) = self.entries.get_mut(leadership_log_id) {
-                log.mark_finished();
-            }
+        /// mark the log as finished, returning it to be added to the history
+        pub fn mark_finished(
+            &mut self,
+            leadership_log_id: &LeadershipLogId,
+        ) -> Option<LeadershipLog> {
+            let log = self.entries.get_mut(leadership_log_id)?;
+            log.mark_finished();
+            Some(log.clone())
         }
 
         pub fn logs(&self) -> impl Iterator<Item = &LeadershipLog> {
@@ -59,8 +59,11 @@
 
 mod enclave;
 mod logs;
+pub mod preview;
 mod process;
+mod signed_slots;
 
 pub use self::enclave::{Enclave, EnclaveError, LeaderEvent};
-pub use self::logs::{LeadershipLogHandle, Logs};
+pub use self::logs::{history_path as logs_history_path, LeadershipLogHandle, Logs};
 pub use self::process::Module;
+pub use self::signed_slots::SignedSlots;
@@ -0,0 +1,83 @@
+//! offline preview of the leadership schedule of a given leader
+//!
+//! This is used by the `leadership preview` command to compute the slots
+//! a stake pool would be elected for in an epoch, from the blockchain state
+//! loaded from the node's storage.
+
+use crate::{
+    blockcfg::{BlockDate, Epoch, Leader, LeaderOutput, Leadership},
+    blockchain::{new_epoch_leadership_from, EpochLeadership, Ref},
+    secure::NodeSecret,
+};
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PreviewError {
+    #[error("the secret does not contain a genesis praos VRF key")]
+    NoGenesisLeader,
+    #[error("the leadership nonce of epoch {epoch} is not fixed yet, the stored tip is in epoch {tip_epoch}")]
+    NonceNotFixed { epoch: Epoch, tip_epoch: Epoch },
+    #[error("the state of epoch {epoch} is no longer available in the loaded blockchain")]
+    EpochNotAvailable { epoch: Epoch },
+}
+
+/// compute the dates at which the genesis leader from the given secret
+/// is elected during `epoch`.
+///
+/// `tip` is the tip of the blockchain loaded from the storage. The epoch
+/// can be any epoch up to the one following the epoch of the tip.
+pub fn preview(
+    tip: Arc<Ref>,
+    secret: &NodeSecret,
+    epoch: Epoch,
+    rewards_report_all: bool,
+) -> Result<Vec<BlockDate>, PreviewError> {
+    let genesis_leader = secret.genesis().ok_or(PreviewError::NoGenesisLeader)?;
+    let leader = Leader {
+        bft_leader: None,
+        genesis_leader: Some(genesis_leader),
+    };
+
+    let leadership = epoch_leadership(tip, epoch, rewards_report_all)?;
+
+    let dates = (0..leadership.era().slots_per_epoch())
+        .map(|slot| leadership.date_at_slot(slot))
+        .filter(|date| match leadership.is_leader_for_date(&leader, *date) {
+            Ok(LeaderOutput::None) | Err(_) => false,
+            Ok(_) => true,
+        })
+        .collect();
+
+    Ok(dates)
+}
+
+fn epoch_leadership(
+    tip: Arc<Ref>,
+    epoch: Epoch,
+    rewards_report_all: bool,
+) -> Result<Arc<Leadership>, PreviewError> {
+    let tip_epoch = tip.block_date().epoch;
+    if epoch > tip_epoch + 1 {
+        return Err(PreviewError::NonceNotFixed { epoch, tip_epoch });
+    }
+
+    // find the last block at or before the requested epoch
+    let mut current = tip;
+    while current.block_date().epoch > epoch {
+        current = current
+            .last_ref_previous_epoch()
+            .cloned()
+            .ok_or(PreviewError::EpochNotAvailable { epoch })?;
+    }
+
+    if current.block_date().epoch == epoch {
+        Ok(Arc::clone(current.epoch_leadership_schedule()))
+    } else {
+        // no block in the requested epoch yet: compute the leadership
+        // as it would be at the epoch transition
+        let EpochLeadership { leadership, .. } =
+            new_epoch_leadership_from(epoch, current, rewards_report_all);
+        Ok(leadership)
+    }
+}
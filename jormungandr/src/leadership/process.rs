@@ -8,8 +8,9 @@ use crate::{
     intercom::{unary_reply, BlockMsg, Error as IntercomError, TransactionMsg},
     leadership::{
         enclave::{Enclave, EnclaveError, LeaderEvent, Schedule},
-        LeadershipLogHandle, Logs,
+        LeadershipLogHandle, Logs, SignedSlots,
     },
+    stats_counter::StatsCounter,
     utils::{async_msg::MessageBox, task::TokioServiceInfo},
 };
 use chain_time::{
@@ -23,10 +24,19 @@ use jormungandr_lib::{
 };
 use slog::Logger;
 use std::cmp::Ordering;
-use std::{sync::Arc, time::Instant};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use thiserror::Error;
 use tokio::time::{delay_until, timeout_at, Instant as TokioInstant};
 
+/// the longest the scheduler sleeps before checking the system time
+/// again, so a change of the system clock while waiting, or a drift
+/// between the timers and the system clock, delays the wake-up at most by
+/// this much
+const MAX_TIMER_STEP: Duration = Duration::from_secs(1);
+
 #[derive(Error, Debug)]
 pub enum LeadershipError {
     #[error("The blockchain Timeline hasn't started yet")]
@@ -60,6 +70,15 @@ struct Entry {
     log: LeadershipLogHandle,
 }
 
+/// what the scheduler wakes up for
+enum WakeUp {
+    /// a leader event is due at the start of its slot, which may have
+    /// started already
+    LeaderEvent(SystemTime),
+    /// nothing is scheduled before the start of the next epoch
+    NextEpoch(SystemTime),
+}
+
 pub struct Module {
     schedule: Option<Schedule>,
     service_info: TokioServiceInfo,
@@ -68,17 +87,22 @@ pub struct Module {
     tip: Tip,
     pool: MessageBox<TransactionMsg>,
     enclave: Enclave,
+    signed_slots: SignedSlots,
     block_message: MessageBox<BlockMsg>,
+    stats_counter: StatsCounter,
 }
 
 impl Module {
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         service_info: TokioServiceInfo,
         logs: Logs,
         tip: Tip,
         pool: MessageBox<TransactionMsg>,
         enclave: Enclave,
+        signed_slots: SignedSlots,
         block_message: MessageBox<BlockMsg>,
+        stats_counter: StatsCounter,
     ) -> Result<Self, LeadershipError> {
         let tip_ref = tip.get_ref().await;
 
@@ -90,7 +114,9 @@ impl Module {
             tip,
             pool,
             enclave,
+            signed_slots,
             block_message,
+            stats_counter,
         })
     }
 
@@ -146,26 +172,6 @@ impl Module {
         Ok(self.slot_time(epoch, slot))
     }
 
-    fn next_epoch_instant(&self) -> Result<Instant, LeadershipError> {
-        let next_epoch_time = self.next_epoch_time()?;
-
-        match next_epoch_time
-            .as_ref()
-            .duration_since(SystemTime::now().into())
-        {
-            Err(err) => {
-                // only possible if `next_epoch_time` is earlier than now. I.e. if the next
-                // epoch is in the past.
-
-                unreachable!(
-                    "next epoch is in the past. This is not possible, but it seems it append. {}",
-                    err
-                )
-            }
-            Ok(duration) => Ok(Instant::now() + duration),
-        }
-    }
-
     fn slot_time(&self, epoch: Epoch, slot: EpochSlotOffset) -> SystemTime {
         let leadership = self.tip_ref.epoch_leadership_schedule();
         let time_frame = self.tip_ref.time_frame();
@@ -209,27 +215,24 @@ impl Module {
         }
     }
 
-    fn slot_instant(&self, epoch: Epoch, slot: EpochSlotOffset) -> Option<Instant> {
-        let slot_time = self.slot_time(epoch, slot);
-
-        match slot_time.as_ref().duration_since(SystemTime::now().into()) {
-            Err(_err) => {
-                // this may happen if the epoch/slot is long gone
-                None
+    async fn wait(mut self) -> Result<Self, LeadershipError> {
+        match self.wait_peek_deadline().await? {
+            WakeUp::LeaderEvent(deadline) => {
+                wait_until(deadline).await;
+                // how late the scheduler woke up for the event
+                let delay = std::time::SystemTime::now()
+                    .duration_since(deadline.into())
+                    .unwrap_or_default();
+                self.stats_counter.record_leader_wake_delay(delay);
             }
-            Ok(duration) => Some(Instant::now() + duration),
+            WakeUp::NextEpoch(deadline) => wait_until(deadline).await,
         }
-    }
-
-    async fn wait(mut self) -> Result<Self, LeadershipError> {
-        let deadline = self.wait_peek_deadline().await?;
-        delay_until(TokioInstant::from_std(deadline)).await;
         let tip = self.tip.clone();
         self.tip_ref = tip.get_ref().await;
         Ok(self)
     }
 
-    async fn wait_peek_deadline(&mut self) -> Result<Instant, LeadershipError> {
+    async fn wait_peek_deadline(&mut self) -> Result<WakeUp, LeadershipError> {
         match self
             .schedule
             .as_mut()
@@ -246,25 +249,23 @@ impl Module {
                     self.service_info.logger(),
                     "no item scheduled, waiting for next epoch"
                 );
-                self.next_epoch_instant()
+                self.next_epoch_time().map(WakeUp::NextEpoch)
             }
             Some(event) => {
                 let logger = self.service_info.logger().new(o!(
                     "event_date" => event.date.to_string(),
                     "leader_id" => event.id.to_string(),
                 ));
-                let epoch = Epoch(event.date.epoch);
-                let slot = EpochSlotOffset(event.date.slot_id);
-                if let Some(instant) = self.slot_instant(epoch, slot) {
+                let slot_time = self.event_slot_time(&event);
+                if SystemTime::now() < slot_time {
                     debug!(logger, "awaiting");
-                    Ok(instant)
                 } else {
-                    // if the entry didn't have a valid epoch instant it means
                     // we are looking at passed entry already or it is happening
-                    // now. so don't wait any further
+                    // now, so there is no waiting, but the delay is still
+                    // recorded
                     debug!(logger, "scheduled time for event was missed");
-                    Ok(Instant::now())
                 }
+                Ok(WakeUp::LeaderEvent(slot_time))
             }
         }
     }
@@ -294,7 +295,12 @@ impl Module {
         let end_log = entry.log.clone();
         entry.log.mark_wake().await;
         let module = module.action_run_entry(entry).await?;
-        end_log.mark_finished().await;
+        if let Err(err) = end_log.mark_finished().await {
+            warn!(
+                module.service_info.logger(),
+                "cannot record the leadership log in the history" ; "reason" => %err
+            );
+        }
         Ok(module)
     }
 
@@ -326,26 +332,17 @@ impl Module {
 
             Ok(self)
         } else {
-            let right_time = entry.instant(&self)?;
-
-            if let Some(right_time) = right_time {
+            if now < event_start {
                 warn!(
                     logger,
                     "system woke a bit early for the event, delaying until right time."
                 );
 
-                // await the right_time before starting the action
-                delay_until(TokioInstant::from_std(right_time)).await;
-                self.action_run_entry_in_bound(entry, logger, event_end)
-                    .await
-            } else {
-                // because we checked that the entry's slot was below the current
-                // time, if we cannot compute the _right_time_ it means the time
-                // is just starting now to be correct. So it's okay to start
-                // running it now still
-                self.action_run_entry_in_bound(entry, logger, event_end)
-                    .await
+                // await the start of the slot before starting the action
+                wait_until(event_start).await;
             }
+            self.action_run_entry_in_bound(entry, logger, event_end)
+                .await
         }
     }
 
@@ -442,6 +439,33 @@ impl Module {
             return Ok(());
         };
 
+        // never sign a second block for the same slot, even if the node
+        // was restarted since the first one was signed
+        match self.signed_slots.record(event.date).await {
+            Ok(true) => (),
+            Ok(false) => {
+                error!(
+                    logger,
+                    "A block was already signed for this slot, refusing to sign another one"
+                );
+                event_logs
+                    .set_status(LeadershipLogStatus::Rejected {
+                        reason: "A block was already signed for this slot".to_owned(),
+                    })
+                    .await;
+                return Ok(());
+            }
+            Err(err) => {
+                error!(logger, "Cannot record the signed slot, refusing to sign the block" ; "reason" => %err);
+                event_logs
+                    .set_status(LeadershipLogStatus::Rejected {
+                        reason: format!("Cannot record the signed slot: {}", err),
+                    })
+                    .await;
+                return Ok(());
+            }
+        }
+
         let contents =
             prepare_block(pool, event.date, ledger, ledger_parameters, logger.clone()).await?;
 
@@ -618,14 +642,6 @@ impl Module {
     }
 }
 
-impl Entry {
-    fn instant(&self, module: &Module) -> Result<Option<Instant>, LeadershipError> {
-        let epoch = Epoch(self.event.date.epoch);
-        let slot = EpochSlotOffset(self.event.date.slot_id);
-        Ok(module.slot_instant(epoch, slot))
-    }
-}
-
 async fn prepare_block(
     mut fragment_pool: MessageBox<TransactionMsg>,
     block_date: BlockDate,
@@ -656,6 +672,41 @@ async fn prepare_block(
     }
 }
 
+/// Wait until the system time reaches the deadline. The timers follow a
+/// monotonic clock which may drift from the system clock, and the system
+/// clock may be set while waiting, so the remaining time is computed again
+/// from the system time after every step.
+async fn wait_until(deadline: SystemTime) {
+    loop {
+        let remaining = match deadline.as_ref().duration_since(SystemTime::now().into()) {
+            Ok(remaining) if remaining > Duration::from_secs(0) => remaining,
+            _ => return,
+        };
+        let step = std::cmp::min(remaining, MAX_TIMER_STEP);
+        delay_until(TokioInstant::now() + step).await;
+    }
+}
+
 fn too_late(now: SystemTime, event_end: SystemTime) -> bool {
     event_end <= now
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn wait_until_returns_at_once_for_a_past_deadline() {
+        let deadline = SystemTime::from(std::time::SystemTime::now() - Duration::from_secs(10));
+        let started = Instant::now();
+        wait_until(deadline).await;
+        assert!(started.elapsed() < MAX_TIMER_STEP);
+    }
+
+    #[tokio::test]
+    async fn wait_until_does_not_return_before_the_deadline() {
+        let deadline = SystemTime::from(std::time::SystemTime::now() + Duration::from_millis(1500));
+        wait_until(deadline).await;
+        assert!(SystemTime::now() >= deadline);
+    }
+}
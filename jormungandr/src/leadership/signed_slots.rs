@@ -0,0 +1,242 @@
+//! record of the slots the node has already signed a block for
+//!
+//! Before the leadership task signs a block, the date of the block is
+//! appended to this record (and synced to disk when a file is configured).
+//! A date already present in the record is never signed again, even after
+//! the node has crashed or its storage has been restored from a backup.
+//! This protects the stake pool from producing two different blocks for
+//! the same slot.
+//!
+//! Only the dates of the current and the previous epoch are kept: once a
+//! date of a new epoch is recorded, older entries are dropped from memory
+//! and the file is compacted. A last line torn by a crash in the middle of
+//! a write is discarded when the record is opened.
+
+use crate::blockcfg::BlockDate;
+use std::{
+    collections::BTreeSet,
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+#[derive(Clone)]
+pub struct SignedSlots(Arc<Mutex<internal::SignedSlots>>);
+
+impl SignedSlots {
+    /// only keep the record in memory. The protection only applies
+    /// for as long as the node is running.
+    pub fn in_memory() -> Self {
+        SignedSlots(Arc::new(Mutex::new(internal::SignedSlots {
+            path: None,
+            file: None,
+            dates: BTreeSet::new(),
+        })))
+    }
+
+    /// open (or create) the record file at the given path and load
+    /// the dates already signed.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+
+        let mut dates = BTreeSet::new();
+        let mut torn = false;
+        if path.exists() {
+            let mut reader = BufReader::new(File::open(path)?);
+            let mut line = String::new();
+            while reader.read_line(&mut line)? != 0 {
+                let complete = line.ends_with('\n');
+                let entry = line.trim();
+                if !entry.is_empty() {
+                    match entry.parse::<BlockDate>() {
+                        Ok(date) => {
+                            dates.insert(date);
+                        }
+                        // the last write did not complete before the node
+                        // stopped: the block for this date was not signed
+                        Err(_) if !complete => torn = true,
+                        Err(e) => {
+                            return Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                format!("invalid signed slot entry '{}': {}", entry, e),
+                            ))
+                        }
+                    }
+                }
+                line.clear();
+            }
+        }
+
+        let mut inner = internal::SignedSlots {
+            path: Some(path.to_path_buf()),
+            file: None,
+            dates,
+        };
+        if torn {
+            inner.compact()?;
+        } else {
+            inner.file = Some(OpenOptions::new().create(true).append(true).open(path)?);
+        }
+
+        Ok(SignedSlots(Arc::new(Mutex::new(inner))))
+    }
+
+    /// record that a block is about to be signed for the given date.
+    ///
+    /// Returns `false` if a block was already signed for this date, in
+    /// which case the block must not be signed. The record is written on a
+    /// thread where blocking is allowed.
+    pub async fn record(&self, date: BlockDate) -> io::Result<bool> {
+        let inner = Arc::clone(&self.0);
+        tokio::task::spawn_blocking(move || inner.lock().unwrap().record(date))
+            .await
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+    }
+}
+
+mod internal {
+    use super::*;
+
+    pub struct SignedSlots {
+        pub path: Option<PathBuf>,
+        pub file: Option<File>,
+        pub dates: BTreeSet<BlockDate>,
+    }
+
+    impl SignedSlots {
+        pub fn record(&mut self, date: BlockDate) -> io::Result<bool> {
+            if self.dates.contains(&date) {
+                return Ok(false);
+            }
+
+            let new_epoch = self
+                .dates
+                .iter()
+                .next_back()
+                .map_or(false, |last| last.epoch < date.epoch);
+            if new_epoch {
+                self.prune(date.epoch.saturating_sub(1));
+                self.compact()?;
+            }
+
+            // persist before updating the in-memory record so a failure
+            // to write prevents signing
+            if let Some(file) = self.file.as_mut() {
+                writeln!(file, "{}", date)?;
+                file.sync_data()?;
+            }
+
+            self.dates.insert(date);
+            Ok(true)
+        }
+
+        /// drop the dates of the epochs before `oldest_epoch`
+        fn prune(&mut self, oldest_epoch: u32) {
+            let oldest = BlockDate {
+                epoch: oldest_epoch,
+                slot_id: 0,
+            };
+            self.dates = self.dates.split_off(&oldest);
+        }
+
+        /// rewrite the file with the dates still in memory. The new content
+        /// is written next to the file and renamed over it, so the record on
+        /// disk is never lost half way.
+        pub fn compact(&mut self) -> io::Result<()> {
+            let path = match self.path.as_ref() {
+                Some(path) => path,
+                None => return Ok(()),
+            };
+            self.file = None;
+
+            let tmp = path.with_extension("tmp");
+            {
+                let mut file = File::create(&tmp)?;
+                for date in self.dates.iter() {
+                    writeln!(file, "{}", date)?;
+                }
+                file.sync_all()?;
+            }
+            std::fs::rename(&tmp, path)?;
+
+            self.file = Some(OpenOptions::new().append(true).open(path)?);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn date(epoch: u32, slot_id: u32) -> BlockDate {
+        BlockDate { epoch, slot_id }
+    }
+
+    #[tokio::test]
+    async fn a_slot_is_only_signed_once() {
+        let signed_slots = SignedSlots::in_memory();
+
+        assert!(signed_slots.record(date(1, 2)).await.unwrap());
+        assert!(!signed_slots.record(date(1, 2)).await.unwrap());
+        assert!(signed_slots.record(date(1, 3)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn the_record_persists_across_a_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signed_slots");
+
+        let signed_slots = SignedSlots::open(&path).unwrap();
+        assert!(signed_slots.record(date(1, 2)).await.unwrap());
+        drop(signed_slots);
+
+        let signed_slots = SignedSlots::open(&path).unwrap();
+        assert!(!signed_slots.record(date(1, 2)).await.unwrap());
+        assert!(signed_slots.record(date(1, 3)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn a_torn_last_entry_is_discarded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signed_slots");
+        fs::write(&path, "1.2\n1.").unwrap();
+
+        let signed_slots = SignedSlots::open(&path).unwrap();
+        assert!(!signed_slots.record(date(1, 2)).await.unwrap());
+        assert!(signed_slots.record(date(1, 3)).await.unwrap());
+        drop(signed_slots);
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "1.2\n1.3\n");
+    }
+
+    #[test]
+    fn a_garbage_file_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signed_slots");
+        fs::write(&path, "1.2\nnot a date\n1.3\n").unwrap();
+
+        let err = SignedSlots::open(&path).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn dates_before_the_previous_epoch_are_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signed_slots");
+
+        let signed_slots = SignedSlots::open(&path).unwrap();
+        assert!(signed_slots.record(date(1, 2)).await.unwrap());
+        assert!(signed_slots.record(date(2, 5)).await.unwrap());
+        assert!(signed_slots.record(date(3, 1)).await.unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "2.5\n3.1\n");
+
+        let dates = signed_slots.0.lock().unwrap().dates.clone();
+        assert_eq!(
+            dates.into_iter().collect::<Vec<_>>(),
+            vec![date(2, 5), date(3, 1)]
+        );
+    }
+}
@@ -1,8 +1,12 @@
 mod asyncable_drain;
+mod slog_layer;
 pub mod stream;
 
 pub use self::asyncable_drain::AsyncableDrain;
+pub use self::slog_layer::SlogLayer;
 
 pub const KEY_TASK: &str = "task";
 pub const KEY_SUB_TASK: &str = "sub_task";
 pub const KEY_SCOPE: &str = "scope";
+pub const KEY_TRACE_ID: &str = "trace_id";
+pub const KEY_NODE: &str = "node";
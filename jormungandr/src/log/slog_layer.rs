@@ -0,0 +1,111 @@
+//! Compatibility layer forwarding `tracing` events to the slog logger
+//! built from the node's logging configuration.
+//!
+//! The fields of all the spans the event is emitted in are appended
+//! to the key-value pairs of the log record, so that instrumented code
+//! produces the same output as code logging with slog directly.
+
+use slog::Logger;
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Level, Subscriber,
+};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+use std::fmt::{self, Write};
+
+pub struct SlogLayer {
+    logger: Logger,
+}
+
+impl SlogLayer {
+    pub fn new(logger: Logger) -> Self {
+        SlogLayer { logger }
+    }
+}
+
+#[derive(Clone, Default)]
+struct KeyValues(Vec<(&'static str, String)>);
+
+impl slog::KV for KeyValues {
+    fn serialize(&self, _: &slog::Record, serializer: &mut dyn slog::Serializer) -> slog::Result {
+        for (key, value) in self.0.iter() {
+            serializer.emit_str(key, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    values: KeyValues,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            self.values.0.push((field.name(), value.to_owned()));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            self.values.0.push((field.name(), format!("{:?}", value)));
+        }
+    }
+}
+
+// Span fields, stored in the span extensions of the registry.
+struct SpanValues(KeyValues);
+
+impl<S> Layer<S> for SlogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).expect("new span is not in the registry");
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        span.extensions_mut().insert(SpanValues(visitor.values));
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let mut visitor = FieldVisitor::default();
+            values.record(&mut visitor);
+            if let Some(span_values) = span.extensions_mut().get_mut::<SpanValues>() {
+                (span_values.0).0.extend(visitor.values.0);
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        let mut values = KeyValues::default();
+        for span in ctx.scope() {
+            values.0.push(("span", span.name().to_owned()));
+            if let Some(span_values) = span.extensions().get::<SpanValues>() {
+                values.0.extend((span_values.0).0.iter().cloned());
+            }
+        }
+        values.0.extend(visitor.values.0);
+
+        let metadata = event.metadata();
+        let logger = self.logger.new(o!("target" => metadata.target(), values));
+        let message = visitor.message;
+        match *metadata.level() {
+            Level::ERROR => error!(logger, "{}", message),
+            Level::WARN => warn!(logger, "{}", message),
+            Level::INFO => info!(logger, "{}", message),
+            Level::DEBUG => debug!(logger, "{}", message),
+            Level::TRACE => trace!(logger, "{}", message),
+        }
+    }
+}
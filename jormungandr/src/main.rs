@@ -26,10 +26,13 @@ use crate::{
     settings::start::Settings,
     utils::{async_msg, task::Services},
 };
+use chain_network::data::NodeKeyPair;
 use futures::executor::block_on;
 use futures::prelude::*;
 use jormungandr_lib::interfaces::NodeState;
-use settings::{start::RawSettings, CommandLine};
+use settings::{
+    start::RawSettings, Command, CommandLine, KeyCommand, LeadershipCommand, StorageCommand,
+};
 use slog::Logger;
 use tokio::signal;
 use tokio_util::sync::CancellationToken;
@@ -37,15 +40,19 @@ use tokio_util::sync::CancellationToken;
 use std::sync::Arc;
 use std::time::Duration;
 
+pub mod backup;
 pub mod blockcfg;
 pub mod blockchain;
 pub mod client;
+pub mod clock_check;
 pub mod diagnostic;
 pub mod explorer;
 pub mod fragment;
 pub mod intercom;
 pub mod leadership;
 pub mod log;
+#[cfg(feature = "in-memory-transport")]
+pub mod multi;
 pub mod network;
 pub mod rest;
 pub mod secure;
@@ -59,7 +66,96 @@ pub mod utils;
 use stats_counter::StatsCounter;
 
 fn start() -> Result<(), start_up::Error> {
-    let initialized_node = initialize_node()?;
+    let command_line = CommandLine::load();
+    #[cfg(feature = "in-memory-transport")]
+    {
+        if let Some(Command::Multi { config }) = &command_line.command {
+            return multi::start(config);
+        }
+    }
+    let result = start_node(command_line, NodeScope::Process);
+    settings::logging::shutdown_tracing();
+    result
+}
+
+/// how the node is run: alone in the process, or as a node of a cluster
+/// sharing the process with the others
+pub enum NodeScope {
+    Process,
+    #[cfg(feature = "in-memory-transport")]
+    Cluster(multi::ClusterMember),
+}
+
+impl NodeScope {
+    /// the logs of the nodes of a cluster are tagged with their name
+    fn logger(&self, logger: Logger) -> Logger {
+        match self {
+            NodeScope::Process => logger,
+            #[cfg(feature = "in-memory-transport")]
+            NodeScope::Cluster(member) => logger.new(o!(log::KEY_NODE => member.name().to_owned())),
+        }
+    }
+
+    /// the tracing subscriber is global, it is installed once for all the
+    /// nodes of a cluster
+    fn init_tracing(
+        &self,
+        logger: &Logger,
+        raw_settings: &RawSettings,
+    ) -> Result<(), start_up::Error> {
+        match self {
+            NodeScope::Process => {
+                settings::logging::init_tracing(logger, raw_settings.tracing_settings())?;
+            }
+            #[cfg(feature = "in-memory-transport")]
+            NodeScope::Cluster(_) => {
+                if raw_settings.tracing_settings().is_some() {
+                    warn!(
+                        logger,
+                        "the tracing settings are ignored in a cluster, the spans of all the nodes go to the logs of the cluster"
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// the nodes of a cluster connect to each other in memory
+    fn transport(&self) -> network::NodeTransport {
+        match self {
+            NodeScope::Process => network::NodeTransport::Tcp,
+            #[cfg(feature = "in-memory-transport")]
+            NodeScope::Cluster(member) => network::NodeTransport::Memory(member.network()),
+        }
+    }
+
+    /// the token cancelled to stop the node: the signals are handled by the
+    /// process, once for all the nodes of a cluster
+    fn init_shutdown_watchers(&self, services: &mut Services) -> CancellationToken {
+        let token = CancellationToken::new();
+        match self {
+            NodeScope::Process => init_os_signal_watchers(services, token.clone()),
+            #[cfg(feature = "in-memory-transport")]
+            NodeScope::Cluster(member) => {
+                let shutdown = member.shutdown();
+                let token = token.clone();
+                services.spawn_future("cluster_shutdown_watcher", move |_| async move {
+                    shutdown.cancelled().await;
+                    token.cancel();
+                });
+            }
+        }
+        token
+    }
+}
+
+/// run a node until it stops
+pub fn start_node(command_line: CommandLine, scope: NodeScope) -> Result<(), start_up::Error> {
+    let initialized_node = match initialize_node(command_line, scope)? {
+        Some(initialized_node) => initialized_node,
+        // a command was run instead of the node
+        None => return Ok(()),
+    };
 
     let bootstrapped_node = bootstrap(initialized_node)?;
 
@@ -103,17 +199,41 @@ fn start_services(bootstrapped_node: BootstrappedNode) -> Result<(), start_up::E
     let (client_msgbox, client_queue) = async_msg::channel(CLIENT_TASK_QUEUE_LEN);
     let blockchain_tip = bootstrapped_node.blockchain_tip;
     let blockchain = bootstrapped_node.blockchain;
-    let leadership_logs =
-        leadership::Logs::new(bootstrapped_node.settings.leadership.logs_capacity);
+    let logs_capacity = bootstrapped_node.settings.leadership.logs_capacity;
+    let leadership_logs = match &bootstrapped_node.settings.storage {
+        Some(dir) => leadership::Logs::open(logs_capacity, leadership::logs_history_path(dir))
+            .map_err(|err| start_up::Error::IO {
+                source: err,
+                reason: start_up::ErrorKind::LeadershipLogs,
+            })?,
+        None => leadership::Logs::new(logs_capacity),
+    };
 
     let stats_counter = StatsCounter::default();
 
+    let confirmed_fragments = bootstrapped_node
+        .settings
+        .mempool
+        .confirmed_fragments_epochs
+        .map(|epochs| {
+            let path = bootstrapped_node
+                .settings
+                .storage
+                .as_ref()
+                .map(|dir| dir.join(fragment::CONFIRMED_FRAGMENTS_FILE));
+            fragment::ConfirmedFragments::new(epochs, path)
+        });
+
     {
         let stats_counter = stats_counter.clone();
         let process = fragment::Process::new(
             bootstrapped_node.settings.mempool.pool_max_entries.into(),
             bootstrapped_node.settings.mempool.log_max_entries.into(),
+            bootstrapped_node.settings.mempool.origin_max_entries,
+            bootstrapped_node.settings.mempool.selection_quotas.clone(),
+            confirmed_fragments.clone(),
             network_msgbox.clone(),
+            bootstrapped_node.settings.read_only,
         );
 
         services.spawn_try_future("fragment", move |info| {
@@ -171,6 +291,11 @@ fn start_services(bootstrapped_node: BootstrappedNode) -> Result<(), start_up::E
         let task_data = client::TaskData {
             storage: blockchain.storage().clone(),
             blockchain_tip: blockchain_tip.clone(),
+            block_cache: client::BlockCache::new(
+                bootstrapped_node.settings.network.block_cache_size,
+                stats_counter.clone(),
+            ),
+            header_chain: blockchain.header_chain().clone(),
         };
 
         services.spawn_future("client-query", move |info| {
@@ -178,16 +303,22 @@ fn start_services(bootstrapped_node: BootstrappedNode) -> Result<(), start_up::E
         });
     }
 
+    let node_key =
+        start_up::prepare_node_key(&bootstrapped_node.settings, &bootstrapped_node.logger)?;
+
     // FIXME: reduce state sharing across services
     let network_state = Arc::new(network::GlobalState::new(
         bootstrapped_node.block0_hash,
         bootstrapped_node.settings.network.clone(),
+        NodeKeyPair::from(node_key.as_ref().clone()),
         stats_counter.clone(),
         bootstrapped_node
             .logger
             .new(o!(crate::log::KEY_TASK => "network")),
     ));
 
+    let port_mapping_state = network_state.clone();
+
     {
         let fragment_msgbox = fragment_msgbox.clone();
         let block_msgbox = block_msgbox.clone();
@@ -223,12 +354,28 @@ fn start_services(bootstrapped_node: BootstrappedNode) -> Result<(), start_up::E
     let leader_secrets = leader_secrets?;
     let enclave = block_on(Enclave::from_vec(leader_secrets));
 
-    {
+    let signed_slots = match &bootstrapped_node.settings.leadership.signed_slots_file {
+        Some(path) => leadership::SignedSlots::open(path).map_err(|err| start_up::Error::IO {
+            source: err,
+            reason: start_up::ErrorKind::SignedSlots,
+        })?,
+        None => leadership::SignedSlots::in_memory(),
+    };
+
+    if bootstrapped_node.settings.read_only || bootstrapped_node.settings.relay {
+        if !bootstrapped_node.settings.secrets.is_empty() {
+            warn!(
+                bootstrapped_node.logger,
+                "the node is read-only or a relay, the leadership task is not started despite the secrets given"
+            );
+        }
+    } else {
         let leadership_logs = leadership_logs.clone();
         let block_msgbox = block_msgbox;
         let blockchain_tip = blockchain_tip.clone();
         let enclave = leadership::Enclave::new(enclave.clone());
         let fragment_msgbox = fragment_msgbox.clone();
+        let stats_counter = stats_counter.clone();
 
         services.spawn_try_future("leadership", move |info| {
             leadership::Module::new(
@@ -237,7 +384,9 @@ fn start_services(bootstrapped_node: BootstrappedNode) -> Result<(), start_up::E
                 blockchain_tip,
                 fragment_msgbox,
                 enclave,
+                signed_slots,
                 block_msgbox,
+                stats_counter,
             )
             .and_then(|module| module.run())
             .map_err(|e| {
@@ -246,6 +395,25 @@ fn start_services(bootstrapped_node: BootstrappedNode) -> Result<(), start_up::E
         });
     }
 
+    {
+        let settings = bootstrapped_node.settings.clock_check.clone();
+        let stats_counter = stats_counter.clone();
+        services.spawn_future("clock_check", move |info| {
+            clock_check::run(info, settings, stats_counter)
+        });
+    }
+
+    let backup_status = bootstrapped_node.settings.backup.clone().map(|settings| {
+        let status = backup::Status::default();
+        let storage = blockchain.storage().clone();
+        let blockchain_tip = blockchain_tip.clone();
+        let backup_status = status.clone();
+        services.spawn_future("backup", move |info| {
+            backup::run_backups(info, settings, storage, blockchain_tip, backup_status)
+        });
+        status
+    });
+
     if let Some(rest_context) = bootstrapped_node.rest_context {
         let full_context = rest::FullContext {
             stats_counter,
@@ -255,6 +423,20 @@ fn start_services(bootstrapped_node: BootstrappedNode) -> Result<(), start_up::E
             enclave,
             network_state,
             explorer: explorer.as_ref().map(|(_msg_box, context)| context.clone()),
+            backup_status,
+            read_only: bootstrapped_node.settings.read_only,
+            node_key,
+            max_block_content_size: bootstrapped_node
+                .settings
+                .mempool
+                .selection_quotas
+                .max_content_size,
+            max_addresses_per_request: bootstrapped_node
+                .settings
+                .rest
+                .as_ref()
+                .and_then(|rest| rest.max_addresses_per_request)
+                .unwrap_or(rest::DEFAULT_MAX_ADDRESSES_PER_REQUEST),
         };
         block_on(async {
             let mut rest_context = rest_context.write().await;
@@ -278,7 +460,29 @@ fn start_services(bootstrapped_node: BootstrappedNode) -> Result<(), start_up::E
         });
     }
 
-    match services.wait_any_finished() {
+    let finished = services.wait_any_finished();
+
+    // the confirmations since the last periodic save would be lost otherwise
+    if let Some(confirmed_fragments) = confirmed_fragments {
+        if let Err(err) = confirmed_fragments.save() {
+            warn!(
+                bootstrapped_node.logger,
+                "cannot save the confirmed fragments";
+                "reason" => %err
+            );
+        }
+    }
+
+    // the router would otherwise forward the port until the lease expires
+    if let Err(err) = port_mapping_state.remove_port_mapping() {
+        warn!(
+            bootstrapped_node.logger,
+            "cannot remove the port mapping from the router";
+            "reason" => %err
+        );
+    }
+
+    match finished {
         Err(err) => {
             crit!(
                 bootstrapped_node.logger,
@@ -386,11 +590,25 @@ async fn bootstrap_internal(
 
     let cache_capacity = 102_400;
 
+    let rewards_history = start_up::prepare_rewards_history(&settings)?;
+    let epoch_accounting = start_up::prepare_epoch_accounting(&settings)?;
+    let vote_plan_archive = start_up::prepare_vote_plan_archive(&settings)?;
+    let vote_stats = settings
+        .vote_plan_stats_samples
+        .map(blockchain::VoteStats::new);
+    let epoch_hooks = start_up::prepare_epoch_hooks(&settings, &logger)?;
+
     let (blockchain, blockchain_tip) = start_up::load_blockchain(
         block0,
         storage,
         cache_capacity,
         settings.rewards_report_all,
+        rewards_history,
+        epoch_accounting,
+        vote_plan_archive,
+        vote_stats,
+        epoch_hooks,
+        settings.relay,
         &logger,
     )
     .await?;
@@ -527,8 +745,91 @@ fn init_os_signal_watchers(services: &mut Services, token: CancellationToken) {
     });
 }
 
-fn initialize_node() -> Result<InitializedNode, start_up::Error> {
-    let command_line = CommandLine::load();
+fn run_command(
+    command: Command,
+    settings: Settings,
+    logger: Logger,
+) -> Result<(), start_up::Error> {
+    match command {
+        Command::Leadership(LeadershipCommand::Preview { secret, epoch }) => {
+            let secret = secure::NodeSecret::load_from_file(&secret)?;
+            let storage = start_up::prepare_storage(&settings, &logger)?;
+
+            let mut services = Services::new(logger);
+            let dates = services.block_on_task("leadership_preview", |info| async move {
+                let block0 = start_up::prepare_block_0(&settings, &storage, info.logger()).await?;
+                let (_, tip) = start_up::load_blockchain(
+                    block0,
+                    storage,
+                    1024,
+                    settings.rewards_report_all,
+                    blockchain::RewardsHistory::in_memory(Some(0)),
+                    blockchain::EpochAccountingLog::in_memory(None),
+                    blockchain::VotePlanArchive::in_memory(None),
+                    None,
+                    None,
+                    false,
+                    info.logger(),
+                )
+                .await?;
+                let tip_ref = tip.get_ref().await;
+
+                if epoch > tip_ref.block_date().epoch {
+                    warn!(
+                        info.logger(),
+                        "the stored tip is in an earlier epoch, the schedule is only final if the storage is up to date with the end of that epoch";
+                        "tip" => %tip_ref.block_date(),
+                    );
+                }
+
+                leadership::preview::preview(tip_ref, &secret, epoch, settings.rewards_report_all)
+                    .map_err(start_up::Error::from)
+            })?;
+
+            for date in dates {
+                println!("{}", date);
+            }
+        }
+        Command::Key(KeyCommand::RotateNodeId) => {
+            let key = start_up::rotate_node_key(&settings)?;
+            info!(
+                logger,
+                "generated a new node id, it will be used the next time the node starts"
+            );
+            println!("{}", key.identifier());
+        }
+        Command::Storage(StorageCommand::Export { to }) => {
+            let mut services = Services::new(logger);
+            let exported = services.block_on_task("storage_export", |info| async move {
+                start_up::export_chain(&settings, &to, info.logger()).await
+            })?;
+            println!("exported {} blocks", exported);
+        }
+        Command::Storage(StorageCommand::Import { from }) => {
+            let summary = start_up::import_chain(&settings, &from, &logger)?;
+            info!(
+                logger,
+                "the chain archive is imported, the blocks will be validated when the node starts";
+                "tip" => %summary.tip,
+            );
+            println!(
+                "imported {} blocks, skipped {} blocks already in the storage",
+                summary.imported, summary.skipped
+            );
+        }
+        #[cfg(feature = "in-memory-transport")]
+        Command::Multi { .. } => unreachable!("the nodes of a cluster are started by `start`"),
+    }
+    Ok(())
+}
+
+/// `None` if the command line asked for a command instead of the node, the
+/// command being already run
+fn initialize_node(
+    mut command_line: CommandLine,
+    scope: NodeScope,
+) -> Result<Option<InitializedNode>, start_up::Error> {
+    let command = command_line.command.take();
 
     if command_line.full_version {
         println!("{}", env!("FULL_VERSION"));
@@ -542,6 +843,8 @@ fn initialize_node() -> Result<InitializedNode, start_up::Error> {
 
     let log_settings = raw_settings.log_settings();
     let logger = log_settings.to_logger()?;
+    let logger = scope.logger(logger);
+    scope.init_tracing(&logger, &raw_settings)?;
 
     let init_logger = logger.new(o!(log::KEY_TASK => "init"));
     info!(init_logger, "Starting {}", env!("FULL_VERSION"),);
@@ -549,7 +852,13 @@ fn initialize_node() -> Result<InitializedNode, start_up::Error> {
     let diagnostic = Diagnostic::new()?;
     debug!(init_logger, "system settings are: {}", diagnostic);
 
-    let settings = raw_settings.try_into_settings(&init_logger)?;
+    let mut settings = raw_settings.try_into_settings(&init_logger)?;
+    settings.network.transport = scope.transport();
+
+    if let Some(command) = command {
+        run_command(command, settings, logger)?;
+        return Ok(None);
+    }
 
     if settings.network.trusted_peers.is_empty() && !settings.network.skip_bootstrap {
         return Err(network::bootstrap::Error::EmptyTrustedPeers.into());
@@ -557,8 +866,7 @@ fn initialize_node() -> Result<InitializedNode, start_up::Error> {
 
     let mut services = Services::new(logger.clone());
 
-    let cancellation_token = CancellationToken::new();
-    init_os_signal_watchers(&mut services, cancellation_token.clone());
+    let cancellation_token = scope.init_shutdown_watchers(&mut services);
 
     let rest_context = match settings.rest.clone() {
         Some(rest) => {
@@ -624,7 +932,7 @@ fn initialize_node() -> Result<InitializedNode, start_up::Error> {
         result
     })?;
 
-    Ok(InitializedNode {
+    Ok(Some(InitializedNode {
         settings,
         block0,
         storage,
@@ -632,7 +940,7 @@ fn initialize_node() -> Result<InitializedNode, start_up::Error> {
         rest_context,
         services,
         cancellation_token,
-    })
+    }))
 }
 
 fn main() {
@@ -0,0 +1,451 @@
+//! several nodes running in the same process, e.g. for a local test network
+//!
+//! Starting one process per node makes the local test networks slow to
+//! start and heavy to run. The nodes of a cluster each run on a thread of
+//! their own, with their own runtime and the settings they would be given on
+//! the command line, and their logs are tagged with their name. They connect
+//! to each other through an in-memory network shared by the cluster, at
+//! their p2p listen addresses; only the REST servers listen on sockets.
+//!
+//! What is global to the process is set up once for the cluster: the signals
+//! stop all the nodes, the tracing spans go to the logs of the cluster, and
+//! the sockets passed by a service manager are closed.
+
+use crate::{
+    log,
+    network::MemoryNetwork,
+    settings::{
+        logging::{self, LogFormat, LogOutput, LogSettings, LogSettingsEntry},
+        CommandLine,
+    },
+    start_node,
+    start_up::{Error, ErrorKind},
+    utils::listen_socket,
+    NodeScope,
+};
+use futures::{
+    future::{self, Either},
+    prelude::*,
+};
+use slog::{FilterLevel, Logger};
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+    thread::{self, JoinHandle},
+};
+use structopt::StructOpt;
+use tokio::runtime;
+use tokio_util::sync::CancellationToken;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Cluster {
+    nodes: Vec<ClusterNode>,
+}
+
+/// the settings of a node of the cluster. The paths are relative to the
+/// directory of the cluster file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ClusterNode {
+    /// the name tagging the logs of the node
+    name: String,
+    config: PathBuf,
+    #[serde(default)]
+    secrets: Vec<PathBuf>,
+    /// a path, or an HTTP(S) URL
+    genesis_block: Option<String>,
+    genesis_block_hash: Option<String>,
+    storage: Option<PathBuf>,
+    #[serde(default)]
+    enable_explorer: bool,
+    /// any other command line argument of the node
+    #[serde(default)]
+    args: Vec<String>,
+}
+
+/// what a node shares with the other nodes of its cluster
+pub struct ClusterMember {
+    name: String,
+    network: MemoryNetwork,
+    shutdown: CancellationToken,
+}
+
+impl ClusterMember {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// the network the nodes of the cluster connect to each other through
+    pub fn network(&self) -> MemoryNetwork {
+        self.network.clone()
+    }
+
+    /// the token cancelled to stop all the nodes of the cluster
+    pub fn shutdown(&self) -> CancellationToken {
+        self.shutdown.clone()
+    }
+}
+
+impl Cluster {
+    fn load(cluster_file: &Path) -> Result<Self, Error> {
+        let file = File::open(cluster_file).map_err(|source| Error::IO {
+            source,
+            reason: ErrorKind::Cluster,
+        })?;
+        serde_yaml::from_reader(file).map_err(|e| Error::ParseError {
+            source: io::Error::new(io::ErrorKind::InvalidData, e),
+            reason: ErrorKind::Cluster,
+        })
+    }
+
+    /// the names of the nodes and the command lines they would be started
+    /// with, checked before any of them is started
+    fn command_lines(&self, base: &Path) -> Result<Vec<(String, CommandLine)>, Error> {
+        if self.nodes.is_empty() {
+            return Err(Error::EmptyCluster);
+        }
+
+        let mut names = HashSet::new();
+        let mut command_lines = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            if !names.insert(node.name.as_str()) {
+                return Err(node.invalid("the name is given to another node"));
+            }
+            command_lines.push((node.name.clone(), node.command_line(base)?));
+        }
+        Ok(command_lines)
+    }
+}
+
+impl ClusterNode {
+    fn invalid(&self, reason: impl Into<String>) -> Error {
+        Error::InvalidClusterNode {
+            name: self.name.clone(),
+            reason: reason.into(),
+        }
+    }
+
+    /// the command line the node would be started with
+    fn command_line(&self, base: &Path) -> Result<CommandLine, Error> {
+        let mut args: Vec<OsString> = vec!["jormungandr".into()];
+        let mut push = |flag: &str, value: OsString| {
+            args.push(flag.into());
+            args.push(value);
+        };
+
+        push("--config", base.join(&self.config).into_os_string());
+        for secret in &self.secrets {
+            push("--secret", base.join(secret).into_os_string());
+        }
+        if let Some(genesis_block) = &self.genesis_block {
+            if genesis_block.contains("://") {
+                push("--genesis-block", genesis_block.into());
+            } else {
+                push("--genesis-block", base.join(genesis_block).into_os_string());
+            }
+        }
+        if let Some(hash) = &self.genesis_block_hash {
+            push("--genesis-block-hash", hash.into());
+        }
+        if let Some(storage) = &self.storage {
+            push("--storage", base.join(storage).into_os_string());
+        }
+        if self.enable_explorer {
+            args.push("--enable-explorer".into());
+        }
+        args.extend(self.args.iter().map(OsString::from));
+
+        let command_line =
+            CommandLine::from_iter_safe(args).map_err(|err| self.invalid(err.message))?;
+        if command_line.command.is_some() {
+            return Err(self.invalid("commands cannot be run in a cluster"));
+        }
+        if command_line.full_version || command_line.source_version {
+            return Err(self.invalid("the version cannot be displayed in a cluster"));
+        }
+        Ok(command_line)
+    }
+}
+
+/// run the nodes of the cluster file until they all stop, or until the
+/// process is asked to stop
+pub fn start(cluster_file: &Path) -> Result<(), Error> {
+    let cluster = Cluster::load(cluster_file)?;
+    let base = cluster_file.parent().unwrap_or_else(|| Path::new(""));
+    let command_lines = cluster.command_lines(base)?;
+
+    let logger = cluster_logger()?;
+    logging::init_tracing(&logger, None)?;
+    let closed = listen_socket::close_inherited();
+    if closed > 0 {
+        warn!(
+            logger,
+            "the sockets passed by the service manager are closed, the nodes of a cluster bind their own";
+            "sockets" => closed,
+        );
+    }
+
+    // the signals are watched on this thread, while the nodes run on theirs
+    let mut runtime = runtime::Builder::new()
+        .basic_scheduler()
+        .enable_all()
+        .build()
+        .map_err(|source| Error::IO {
+            source,
+            reason: ErrorKind::Cluster,
+        })?;
+
+    let network = MemoryNetwork::new();
+    let shutdown = CancellationToken::new();
+    let mut nodes = Vec::with_capacity(command_lines.len());
+    for (name, command_line) in command_lines {
+        let member = ClusterMember {
+            name: name.clone(),
+            network: network.clone(),
+            shutdown: shutdown.clone(),
+        };
+        let node_logger = logger.new(o!(log::KEY_NODE => name.clone()));
+        let handle = thread::Builder::new()
+            .name(name.clone())
+            .spawn(move || run_node(command_line, member, node_logger))
+            .map_err(|source| Error::IO {
+                source,
+                reason: ErrorKind::Cluster,
+            })?;
+        nodes.push((name, handle));
+    }
+    info!(logger, "started the nodes of the cluster"; "nodes" => nodes.len());
+
+    runtime.block_on(async move {
+        let join_logger = logger.clone();
+        let stopped = tokio::task::spawn_blocking(move || join_nodes(nodes, &join_logger));
+        let stop = stop_requested(&logger).boxed();
+        let stopped = match future::select(stopped, stop).await {
+            Either::Left((stopped, _)) => stopped,
+            Either::Right(((), stopped)) => {
+                info!(logger, "stopping the nodes of the cluster");
+                shutdown.cancel();
+                stopped.await
+            }
+        };
+        stopped.expect("the nodes of the cluster are joined without panicking")
+    })
+}
+
+/// the logger of the cluster itself, the nodes log with their own settings
+fn cluster_logger() -> Result<Logger, Error> {
+    let settings = LogSettings(vec![LogSettingsEntry {
+        level: FilterLevel::Info,
+        format: LogFormat::Plain,
+        output: LogOutput::Stderr,
+    }]);
+    Ok(settings.to_logger()?)
+}
+
+/// wait for the nodes to stop. A failing node does not stop the others, the
+/// first failure is reported once they have all stopped.
+fn join_nodes(nodes: Vec<(String, JoinHandle<Option<i32>>)>, logger: &Logger) -> Result<(), Error> {
+    let mut result = Ok(());
+    for (name, handle) in nodes {
+        let code = handle.join().unwrap_or_else(|_| {
+            crit!(logger, "the node panicked"; log::KEY_NODE => &name);
+            Some(1)
+        });
+        if let (Some(code), Ok(())) = (code, &result) {
+            result = Err(Error::ClusterNode { name, code });
+        }
+    }
+    result
+}
+
+/// run the node, returning the exit code of the failure if it did not stop
+/// normally
+fn run_node(command_line: CommandLine, member: ClusterMember, logger: Logger) -> Option<i32> {
+    match start_node(command_line, NodeScope::Cluster(member)) {
+        Ok(()) | Err(Error::Interrupted) => {
+            info!(logger, "the node stopped");
+            None
+        }
+        Err(error) => {
+            crit!(
+                logger,
+                "the node failed";
+                "reason" => describe(&error),
+                "code" => error.code(),
+            );
+            Some(error.code())
+        }
+    }
+}
+
+/// the error with the chain of its sources
+fn describe(error: &dyn std::error::Error) -> String {
+    let mut description = error.to_string();
+    let mut source = error.source();
+    while let Some(err) = source {
+        description.push_str(": ");
+        description.push_str(&err.to_string());
+        source = err.source();
+    }
+    description
+}
+
+#[cfg(unix)]
+async fn stop_requested(logger: &Logger) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut signals = Vec::new();
+    for (kind, name) in &[
+        (SignalKind::terminate(), "SIGTERM"),
+        (SignalKind::interrupt(), "SIGINT"),
+    ] {
+        match signal(*kind) {
+            Ok(signal) => signals.push(signal.into_future().map(|_| ()).boxed()),
+            Err(e) => warn!(logger, "failed to install handler for {}", name; "reason" => %e),
+        }
+    }
+    if signals.is_empty() {
+        future::pending::<()>().await;
+    }
+    future::select_all(signals).await;
+}
+
+#[cfg(not(unix))]
+async fn stop_requested(logger: &Logger) {
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        warn!(logger, "ctrl+c watcher failed"; "reason" => %e);
+        future::pending::<()>().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cluster(yaml: &str) -> Cluster {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn nodes_get_the_command_line_arguments() {
+        let cluster = cluster(
+            r#"
+nodes:
+  - name: leader
+    config: leader/node.config
+    secrets: [leader/node_secret.yaml]
+    genesis_block: block-0.bin
+    storage: leader/storage
+  - name: passive
+    config: passive/node.config
+    genesis_block_hash: adbdd5ede31637f6c9bad5c271eec0bc3d0cb9efb86a5b913bb55cba549d0770
+    enable_explorer: true
+    args: ["--log-level", "debug"]
+"#,
+        );
+        let base = Path::new("cluster");
+        let command_lines = cluster.command_lines(base).unwrap();
+        assert_eq!(command_lines.len(), 2);
+
+        let (name, leader) = &command_lines[0];
+        assert_eq!(name, "leader");
+        let start = &leader.start_arguments;
+        assert_eq!(start.node_config, Some(base.join("leader/node.config")));
+        assert_eq!(start.secret, vec![base.join("leader/node_secret.yaml")]);
+        assert_eq!(start.block_0_path, Some(base.join("block-0.bin")));
+        assert_eq!(start.storage, Some(base.join("leader/storage")));
+        assert!(!start.explorer_enabled);
+
+        let (name, passive) = &command_lines[1];
+        assert_eq!(name, "passive");
+        assert!(passive.start_arguments.block_0_hash.is_some());
+        assert!(passive.start_arguments.explorer_enabled);
+        assert_eq!(passive.log_level, Some(FilterLevel::Debug));
+    }
+
+    #[test]
+    fn genesis_block_urls_are_not_relative() {
+        let cluster = cluster(
+            r#"
+nodes:
+  - name: node
+    config: node.config
+    genesis_block: https://example.com/block-0.bin
+    genesis_block_hash: adbdd5ede31637f6c9bad5c271eec0bc3d0cb9efb86a5b913bb55cba549d0770
+"#,
+        );
+        let command_lines = cluster.command_lines(Path::new("cluster")).unwrap();
+        assert_eq!(
+            command_lines[0].1.start_arguments.block_0_path,
+            Some(PathBuf::from("https://example.com/block-0.bin"))
+        );
+    }
+
+    #[test]
+    fn clusters_without_nodes_are_rejected() {
+        let cluster = cluster("nodes: []");
+        assert!(matches!(
+            cluster.command_lines(Path::new("")),
+            Err(Error::EmptyCluster)
+        ));
+    }
+
+    #[test]
+    fn node_names_are_unique() {
+        let cluster = cluster(
+            r#"
+nodes:
+  - name: node
+    config: first.config
+  - name: node
+    config: second.config
+"#,
+        );
+        assert!(matches!(
+            cluster.command_lines(Path::new("")),
+            Err(Error::InvalidClusterNode { name, .. }) if name == "node"
+        ));
+    }
+
+    #[test]
+    fn nodes_cannot_run_commands() {
+        for args in &[
+            r#"["storage", "export", "--to", "archive"]"#,
+            r#"["--full-version"]"#,
+            r#"["--no-such-flag"]"#,
+        ] {
+            let cluster = cluster(&format!(
+                "nodes:\n  - name: node\n    config: node.config\n    args: {}\n",
+                args
+            ));
+            assert!(matches!(
+                cluster.command_lines(Path::new("")),
+                Err(Error::InvalidClusterNode { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn nodes_share_the_network_and_the_shutdown() {
+        let network = MemoryNetwork::new();
+        let shutdown = CancellationToken::new();
+        let member = |name: &str| ClusterMember {
+            name: name.to_owned(),
+            network: network.clone(),
+            shutdown: shutdown.clone(),
+        };
+        let (first, second) = (member("first"), member("second"));
+
+        let addr = "127.0.0.1:3000".parse().unwrap();
+        let _incoming = first.network().bind(addr).unwrap();
+        assert!(second.network().connect(addr).is_ok());
+
+        shutdown.cancel();
+        assert!(first.shutdown().is_cancelled());
+        assert!(second.shutdown().is_cancelled());
+    }
+}
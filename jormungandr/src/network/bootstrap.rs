@@ -1,5 +1,5 @@
-use super::grpc;
-use crate::blockcfg::{Block, HeaderDesc, HeaderHash};
+use super::{grpc, NodeTransport};
+use crate::blockcfg::{Block, Fragment, FragmentId, HeaderDesc, HeaderHash};
 use crate::blockchain::{self, Blockchain, Error as BlockchainError, PreCheckedHeader, Ref, Tip};
 use crate::settings::start::network::Peer;
 use chain_core::property::{Deserialize, HasHeader};
@@ -12,6 +12,7 @@ use tokio_util::sync::CancellationToken;
 use std::fmt::Debug;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -49,17 +50,50 @@ pub enum Error {
     Interrupted,
     #[error("Trusted peers cannot be empty. To avoid bootstrap use `skip_bootstrap: true`")]
     EmptyTrustedPeers,
+    #[error("the peer did not report its tip in time")]
+    ProbeTimeout,
+    #[error("mempool snapshot request failed")]
+    MempoolRequestFailed(#[source] NetworkError),
+    #[error("mempool snapshot stream failed")]
+    MempoolStreamFailed(#[source] NetworkError),
+    #[error("decoding of a fragment failed")]
+    FragmentDecodingFailed(#[source] NetworkError),
+    #[error("the peer did not send its mempool snapshot in time")]
+    MempoolTimeout,
 }
 
 const MAX_BOOTSTRAP_PEERS: u32 = 32;
 
-pub async fn peers_from_trusted_peer(peer: &Peer, logger: Logger) -> Result<Vec<Peer>, Error> {
+/// The time limit for a bootstrap peer to accept a connection and report
+/// its tip when the peers are probed.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The peers with a tip at most this number of blocks behind the longest
+/// tip reported are considered up to date.
+const PROBE_TIP_TOLERANCE: u32 = 2;
+
+/// The number of blocks after which the progress of the bootstrap is saved,
+/// by moving the tip of the main branch in the storage to the last block
+/// applied. An interrupted bootstrap resumes from there after a restart.
+const PROGRESS_SAVE_DISTANCE: u64 = 1000;
+
+/// The time limit for a trusted peer to send the content of its mempool
+/// when the mempool of the node is warmed up.
+const MEMPOOL_WARMUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub async fn peers_from_trusted_peer(
+    transport: &NodeTransport,
+    peer: &Peer,
+    logger: Logger,
+) -> Result<Vec<Peer>, Error> {
     info!(
         logger,
         "getting peers from bootstrap peer {}", peer.connection
     );
 
-    let mut client = grpc::connect(&peer).await.map_err(Error::Connect)?;
+    let mut client = grpc::connect(transport, &peer)
+        .await
+        .map_err(Error::Connect)?;
     let peers = client
         .peers(MAX_BOOTSTRAP_PEERS)
         .await
@@ -75,7 +109,139 @@ pub async fn peers_from_trusted_peer(peer: &Peer, logger: Logger) -> Result<Vec<
     Ok(peers)
 }
 
+struct ProbedPeer {
+    peer: Peer,
+    chain_length: u32,
+    latency: Duration,
+}
+
+async fn probe_peer(transport: &NodeTransport, peer: &Peer) -> Result<ProbedPeer, Error> {
+    use crate::network::convert::Decode;
+
+    let start = Instant::now();
+    let probe = async {
+        let mut client = grpc::connect(transport, peer)
+            .await
+            .map_err(Error::Connect)?;
+        client
+            .tip()
+            .await
+            .and_then(|header| header.decode())
+            .map_err(Error::TipFailed)
+    };
+    let tip = tokio::time::timeout(PROBE_TIMEOUT, probe)
+        .await
+        .map_err(|_| Error::ProbeTimeout)??;
+    Ok(ProbedPeer {
+        peer: peer.clone(),
+        chain_length: tip.chain_length().into(),
+        latency: start.elapsed(),
+    })
+}
+
+/// Probe the bootstrap peers concurrently and order them by preference:
+/// the peers with an up to date tip first, by increasing latency, then the
+/// others by decreasing chain length. The peers that failed to respond are
+/// left out.
+pub async fn probe_peers(
+    transport: &NodeTransport,
+    peers: Vec<&Peer>,
+    logger: &Logger,
+) -> Vec<Peer> {
+    let results = future::join_all(peers.iter().map(|&peer| probe_peer(transport, peer))).await;
+
+    let mut probed = Vec::with_capacity(results.len());
+    for (peer, result) in peers.iter().zip(results) {
+        match result {
+            Ok(probed_peer) => {
+                debug!(
+                    logger,
+                    "probed bootstrap peer";
+                    "peer_addr" => %peer.connection,
+                    "chain_length" => probed_peer.chain_length,
+                    "latency" => ?probed_peer.latency,
+                );
+                probed.push(probed_peer);
+            }
+            Err(e) => {
+                warn!(
+                    logger,
+                    "bootstrap peer is not responding, it will not be used";
+                    "peer_addr" => %peer.connection,
+                    "reason" => %e,
+                );
+            }
+        }
+    }
+
+    rank_probed_peers(&mut probed);
+
+    info!(
+        logger,
+        "{} of {} bootstrap peers responded",
+        probed.len(),
+        peers.len()
+    );
+    probed.into_iter().map(|p| p.peer).collect()
+}
+
+/// The fragment ids asked for to get a snapshot of the mempool of a
+/// peer: the single id made of zeroes, which no fragment has.
+pub fn mempool_snapshot_request() -> Vec<FragmentId> {
+    vec![FragmentId::zero_hash()]
+}
+
+/// Get a snapshot of the mempool of the peer: the fragments it holds
+/// pending, oldest first, up to the cap of the peer.
+pub async fn pull_mempool(
+    transport: &NodeTransport,
+    peer: &Peer,
+    logger: &Logger,
+) -> Result<Vec<Fragment>, Error> {
+    use crate::network::convert::{Decode, Encode};
+
+    let pull = async {
+        let mut client = grpc::connect(transport, peer)
+            .await
+            .map_err(Error::Connect)?;
+        client
+            .get_fragments(mempool_snapshot_request().encode())
+            .await
+            .map_err(Error::MempoolRequestFailed)?
+            .map_err(Error::MempoolStreamFailed)
+            .and_then(|fragment| {
+                future::ready(fragment.decode().map_err(Error::FragmentDecodingFailed))
+            })
+            .try_collect::<Vec<_>>()
+            .await
+    };
+    let fragments = tokio::time::timeout(MEMPOOL_WARMUP_TIMEOUT, pull)
+        .await
+        .map_err(|_| Error::MempoolTimeout)??;
+    debug!(
+        logger,
+        "received mempool snapshot";
+        "peer_addr" => %peer.connection,
+        "fragments" => fragments.len(),
+    );
+    Ok(fragments)
+}
+
+/// Order the peers with an up to date tip first, by increasing latency,
+/// then the others by decreasing chain length.
+fn rank_probed_peers(probed: &mut [ProbedPeer]) {
+    let longest = probed.iter().map(|p| p.chain_length).max().unwrap_or(0);
+    let up_to_date = |p: &ProbedPeer| p.chain_length + PROBE_TIP_TOLERANCE >= longest;
+    probed.sort_by(|a, b| match (up_to_date(a), up_to_date(b)) {
+        (true, true) => a.latency.cmp(&b.latency),
+        (true, false) => std::cmp::Ordering::Less,
+        (false, true) => std::cmp::Ordering::Greater,
+        (false, false) => b.chain_length.cmp(&a.chain_length),
+    });
+}
+
 pub async fn bootstrap_from_peer(
+    transport: &NodeTransport,
     peer: &Peer,
     blockchain: Blockchain,
     tip: Tip,
@@ -100,9 +266,19 @@ pub async fn bootstrap_from_peer(
 
     debug!(logger, "connecting to bootstrap peer {}", peer.connection);
 
-    let mut client = with_cancellation_token(grpc::connect(&peer).boxed(), &cancellation_token)
-        .await?
-        .map_err(Error::Connect)?;
+    let mut client =
+        with_cancellation_token(grpc::connect(transport, &peer).boxed(), &cancellation_token)
+            .await?
+            .map_err(Error::Connect)?;
+
+    let start = tip.get_ref().await;
+    if start.hash() != *blockchain.block0() {
+        info!(
+            logger,
+            "resuming the bootstrap from {}",
+            start.header().description()
+        );
+    }
 
     loop {
         let remote_tip = with_cancellation_token(client.tip().boxed(), &cancellation_token)
@@ -115,7 +291,10 @@ pub async fn bootstrap_from_peer(
             break Ok(());
         }
 
-        let checkpoints = blockchain.get_checkpoints(tip.branch()).await;
+        let checkpoints = blockchain
+            .get_checkpoints(tip.branch())
+            .await
+            .map_err(Error::GetCheckpointsFailed)?;
         let checkpoints = net_data::block::try_ids_from_iter(checkpoints).unwrap();
 
         let remote_tip = BlockId::try_from(remote_tip.as_ref()).unwrap();
@@ -264,6 +443,10 @@ where
         };
 
         match result {
+            Ok(parent_tip) if bootstrap_info.block_received % PROGRESS_SAVE_DISTANCE == 0 => {
+                save_progress(&mut blockchain, branch.clone(), parent_tip, &logger).await?;
+                maybe_parent_tip = None;
+            }
             Ok(parent_tip) => {
                 maybe_parent_tip = Some(parent_tip);
             }
@@ -295,6 +478,33 @@ where
     }
 }
 
+/// make the last applied block the tip of the main branch and flush the
+/// blocks to the permanent storage, so the bootstrap does not start over if
+/// the node is stopped
+async fn save_progress(
+    blockchain: &mut Blockchain,
+    branch: Tip,
+    parent_tip: Arc<Ref>,
+    logger: &Logger,
+) -> Result<(), Error> {
+    blockchain::process_new_ref(logger, blockchain, branch.clone(), parent_tip)
+        .await
+        .map_err(Error::ChainSelectionFailed)?;
+    // the chain selection may keep the previous tip, so the progress is
+    // the tip of the branch, not necessarily the last block applied
+    let saved = branch.get_ref().await;
+    blockchain
+        .gc(saved.clone())
+        .await
+        .map_err(Error::GcFailed)?;
+    debug!(
+        logger,
+        "bootstrap progress saved at {}",
+        saved.header().description()
+    );
+    Ok(())
+}
+
 async fn handle_block(
     blockchain: &Blockchain,
     block: Block,
@@ -337,3 +547,44 @@ async fn handle_block(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    fn probed(port: u16, chain_length: u32, latency_ms: u64) -> ProbedPeer {
+        ProbedPeer {
+            peer: Peer::new(SocketAddr::from((Ipv4Addr::LOCALHOST, port))),
+            chain_length,
+            latency: Duration::from_millis(latency_ms),
+        }
+    }
+
+    #[test]
+    fn up_to_date_peers_are_ranked_first_by_latency() {
+        let mut peers = vec![
+            probed(1, 90, 10),
+            probed(2, 100, 300),
+            probed(3, 98, 100),
+            probed(4, 95, 5),
+            probed(5, 99, 200),
+        ];
+        rank_probed_peers(&mut peers);
+        let ports: Vec<_> = peers.iter().map(|p| p.peer.address().port()).collect();
+        assert_eq!(ports, vec![3, 5, 2, 4, 1]);
+    }
+
+    #[tokio::test]
+    async fn peers_not_responding_are_left_out() {
+        let address = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let peer = Peer::new(address);
+        let logger = Logger::root(slog::Discard, o!());
+        assert!(probe_peers(&NodeTransport::Tcp, vec![&peer], &logger)
+            .await
+            .is_empty());
+    }
+}
@@ -1,7 +1,7 @@
 use super::{Client, ClientBuilder, InboundSubscriptions};
 use crate::blockcfg::HeaderHash;
 use crate::network::{
-    grpc,
+    grpc::{self, Transport},
     p2p::{comm::PeerComms, Address},
     security_params::NONCE_LEN,
     Channels, ConnectionState,
@@ -9,7 +9,7 @@ use crate::network::{
 use chain_core::mempack::{self, ReadBuf, Readable};
 use chain_network::data::{AuthenticatedNodeId, NodeId};
 use chain_network::error::{self as net_error, HandshakeError};
-use chain_network::grpc::legacy;
+use chain_network::grpc::{client::Builder, legacy};
 
 use futures::channel::oneshot;
 use futures::future::BoxFuture;
@@ -20,42 +20,54 @@ use rand::Rng;
 use std::convert::TryInto;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Instant;
 
-/// Initiates a client connection, returning a connection handle and
-/// the connection future that must be polled to complete the connection.
+/// Initiates a client connection over the transport, returning a connection
+/// handle and the connection future that must be polled to complete the
+/// connection.
 ///
 /// Note that this is the only function in this module that is tied to the
 /// gRPC protocol, all other code is generic in terms of network-core traits.
 /// This is intentional, to facilitate extension to different protocols
 /// in the future.
-pub fn connect(state: ConnectionState, channels: Channels) -> (ConnectHandle, ConnectFuture) {
+pub fn connect<T: Transport>(
+    transport: &T,
+    state: ConnectionState,
+    channels: Channels,
+) -> (ConnectHandle, ConnectFuture) {
     let (sender, receiver) = oneshot::channel();
+    let transport = transport.clone();
     let peer = state.peer();
     let keypair = state.global.keypair.clone();
     let legacy_node_id = state.global.config.legacy_node_id;
     let logger = state.logger().clone();
     let cf = async move {
-        let mut grpc_client = if let Some(node_id) = legacy_node_id {
+        let mut builder = Builder::new();
+        if let Some(node_id) = legacy_node_id {
             let node_id: legacy::NodeId = node_id.as_ref().try_into().unwrap();
             debug!(
                 logger,
                 "connecting with legacy node id {}",
                 hex::encode(node_id.as_bytes())
             );
-            grpc::connect_legacy(&peer, node_id).await
+            builder.legacy_node_id(node_id);
         } else {
             debug!(logger, "connecting");
-            grpc::connect(&peer).await
         }
-        .map_err(ConnectError::Transport)?;
+        let peer_version = grpc::PeerVersion::default();
+        let mut grpc_client = grpc::connect_with(&transport, &peer, builder, peer_version.clone())
+            .await
+            .map_err(ConnectError::Transport)?;
 
         let mut nonce = [0u8; NONCE_LEN];
         rand::thread_rng().fill(&mut nonce);
 
+        let handshake_start = Instant::now();
         let hr = grpc_client
             .handshake(&nonce[..])
             .await
             .map_err(ConnectError::Handshake)?;
+        let latency = handshake_start.elapsed();
         let mut buf = ReadBuf::from(hr.block0_id.as_bytes());
         let block0_hash = HeaderHash::read(&mut buf).map_err(ConnectError::DecodeBlock0)?;
         let expected = state.global.block0_hash;
@@ -66,6 +78,15 @@ pub fn connect(state: ConnectionState, channels: Channels) -> (ConnectHandle, Co
 
         debug!(logger, "authenticated server peer node"; "node_id" => ?peer_id);
 
+        if let Some(version) = peer_version.get() {
+            debug!(logger, "peer node version"; "version" => &version);
+            state
+                .global
+                .peers
+                .set_peer_version(Address::tcp(peer.connection), version)
+                .await;
+        }
+
         // Send client authentication
         let auth = keypair.sign(&hr.nonce);
         grpc_client
@@ -75,6 +96,7 @@ pub fn connect(state: ConnectionState, channels: Channels) -> (ConnectHandle, Co
 
         let mut comms = PeerComms::new();
         comms.set_node_id(peer_id);
+        comms.set_latency(latency);
         let (block_sub, fragment_sub, gossip_sub) = future::try_join3(
             grpc_client
                 .clone()
@@ -1,7 +1,8 @@
 mod connect;
+mod validation;
 
 use super::{
-    buffer_sizes,
+    buffer_gauges,
     convert::{Decode, Encode},
     grpc::{
         self,
@@ -11,7 +12,8 @@ use super::{
         comm::{OutboundSubscription, PeerComms},
         Address,
     },
-    subscription::{BlockAnnouncementProcessor, FragmentProcessor, GossipProcessor},
+    stream_limit::StreamLimit,
+    subscription::{self, BlockAnnouncementProcessor, FragmentProcessor, GossipProcessor},
     Channels, GlobalStateR,
 };
 use crate::{
@@ -20,15 +22,18 @@ use crate::{
 };
 use chain_network::data as net_data;
 use chain_network::data::block::{BlockEvent, BlockIds, ChainPullRequest};
+use chain_network::error::{Code, Error};
 
 use futures::prelude::*;
 use futures::ready;
+use poldercast::StrikeReason;
 use slog::Logger;
 
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
 pub use self::connect::{connect, ConnectError, ConnectFuture, ConnectHandle};
+use self::validation::{PulledHeaders, ResponseError, SolicitedBlocks};
 
 #[must_use = "Client must be polled"]
 pub struct Client {
@@ -104,6 +109,37 @@ impl Client {
     }
 }
 
+/// record the first invalid item of a response, for the peer to be
+/// penalized once the response stream is over
+fn invalid_response(e: ResponseError, invalid: &mut Option<ResponseError>) -> Error {
+    let err = Error::new(Code::InvalidArgument, e.clone());
+    invalid.get_or_insert(e);
+    err
+}
+
+/// penalize a peer which responded with data the node did not ask for, or
+/// with too much data: record a strike against it in the topology and
+/// disconnect it
+async fn penalize_peer(
+    global_state: &GlobalStateR,
+    peer: Address,
+    e: ResponseError,
+    logger: &Logger,
+) {
+    warn!(
+        logger,
+        "invalid response from the peer, disconnecting";
+        "reason" => %e,
+    );
+    future::join(
+        global_state
+            .topology
+            .report_node(peer.clone(), StrikeReason::InvalidData),
+        global_state.peers.remove_peer(peer),
+    )
+    .await;
+}
+
 struct InboundSubscriptions {
     pub peer_address: Address,
     pub block_events: BlockSubscription,
@@ -268,11 +304,14 @@ impl Client {
             block_ids[0]
         );
         let (reply_handle, future) =
-            intercom::stream_reply(buffer_sizes::outbound::BLOCKS, logger.clone());
+            intercom::stream_reply(&buffer_gauges::outbound::BLOCKS, logger.clone());
         debug_assert!(self.incoming_solicitation.is_none());
         self.incoming_solicitation = Some(ClientMsg::GetBlocks(block_ids, reply_handle));
         let mut client = self.inner.clone();
+        let global_state = self.global_state.clone();
         self.global_state.spawn(async move {
+            // uploading blocks serves the peer's solicitation
+            let _permit = global_state.server_requests.acquire().await;
             let stream = match future.await {
                 Ok(stream) => stream.upload().map(|item| item.encode()),
                 Err(e) => {
@@ -323,7 +362,7 @@ impl Client {
             "to" => ?to,
         );
         let (reply_handle, future) =
-            intercom::stream_reply(buffer_sizes::outbound::HEADERS, logger.clone());
+            intercom::stream_reply(&buffer_gauges::outbound::HEADERS, logger.clone());
         debug_assert!(self.incoming_solicitation.is_none());
         self.incoming_solicitation = Some(ClientMsg::GetHeadersRange(from, to, reply_handle));
         let mut client = self.inner.clone();
@@ -360,8 +399,15 @@ impl Client {
         let mut block_box = self.block_sink.message_box();
         let logger = self.logger.new(o!("request" => "PullHeaders"));
         let logger1 = logger.clone();
+        let mut pulled = match (req.from.clone().decode(), req.to.decode()) {
+            (Ok(from), Ok(to)) => PulledHeaders::new(&from, to, self.global_state.block0_hash),
+            (Err(e), _) | (_, Err(e)) => {
+                error!(logger, "failed to decode the chain pull request"; "reason" => %e);
+                return;
+            }
+        };
         let (handle, sink, _) =
-            intercom::stream_request(buffer_sizes::inbound::HEADERS, logger.clone());
+            intercom::stream_request(&buffer_gauges::inbound::HEADERS, logger.clone());
         // TODO: make sure that back pressure on the number of requests
         // in flight prevents unlimited spawning of these tasks.
         // https://github.com/input-output-hk/jormungandr/issues/1034
@@ -376,7 +422,10 @@ impl Client {
             }
         });
         let mut client = self.inner.clone();
+        let global_state = self.global_state.clone();
+        let peer = self.inbound.peer_address.clone();
         self.global_state.spawn(async move {
+            let _permit = global_state.client_requests.acquire().await;
             match client.pull_headers(req.from, req.to).await {
                 Err(e) => {
                     info!(
@@ -386,9 +435,27 @@ impl Client {
                     );
                 }
                 Ok(stream) => {
-                    let stream = stream.and_then(|item| async { item.decode() });
+                    let mut invalid = None;
+                    // The range was asked for, so a long one is no fault of
+                    // the peer: the headers past the limit are not read and
+                    // are left to the next chain pull. Headers past the end
+                    // of the range are caught by the range check.
+                    let max_headers = global_state.config.stream_limits.max_headers;
+                    let stream = stream.take(max_headers).map(|item| {
+                        let header = item?;
+                        let header = header.decode().map_err(|e| {
+                            subscription::record_decode_error(&global_state, peer.clone(), &e);
+                            e
+                        })?;
+                        pulled
+                            .check(header.hash(), header.block_parent_hash())
+                            .map_err(|e| invalid_response(e, &mut invalid))?;
+                        Ok(header)
+                    });
                     let res = stream.forward(sink.sink_err_into()).await;
-                    if let Err(e) = res {
+                    if let Some(e) = invalid {
+                        penalize_peer(&global_state, peer, e, &logger1).await;
+                    } else if let Err(e) = res {
                         info!(
                             logger1,
                             "response stream failed";
@@ -405,8 +472,15 @@ impl Client {
         let logger = self.logger.new(o!("request" => "GetBlocks"));
         let req_err_logger = logger.clone();
         let res_logger = logger.clone();
+        let mut solicited = match block_ids.clone().decode() {
+            Ok(ids) => SolicitedBlocks::new(&ids),
+            Err(e) => {
+                error!(logger, "failed to decode the block solicitation"; "reason" => %e);
+                return;
+            }
+        };
         let (handle, sink, _) =
-            intercom::stream_request(buffer_sizes::inbound::BLOCKS, logger.clone());
+            intercom::stream_request(&buffer_gauges::inbound::BLOCKS, logger.clone());
         // TODO: make sure that back pressure on the number of requests
         // in flight prevents unlimited spawning of these tasks.
         // https://github.com/input-output-hk/jormungandr/issues/1034
@@ -421,7 +495,10 @@ impl Client {
             }
         });
         let mut client = self.inner.clone();
+        let global_state = self.global_state.clone();
+        let peer = self.inbound.peer_address.clone();
         self.global_state.spawn(async move {
+            let _permit = global_state.client_requests.acquire().await;
             match client.get_blocks(block_ids).await {
                 Err(e) => {
                     info!(
@@ -431,9 +508,26 @@ impl Client {
                     );
                 }
                 Ok(stream) => {
-                    let stream = stream.and_then(|item| async { item.decode() });
+                    let mut invalid = None;
+                    let mut stream_limit = StreamLimit::blocks(&global_state.config.stream_limits);
+                    let stream = stream.map(|item| {
+                        let block = item?;
+                        stream_limit
+                            .check(block.as_ref().len())
+                            .map_err(|e| invalid_response(e.into(), &mut invalid))?;
+                        let block = block.decode().map_err(|e| {
+                            subscription::record_decode_error(&global_state, peer.clone(), &e);
+                            e
+                        })?;
+                        solicited
+                            .check(block.header().hash())
+                            .map_err(|e| invalid_response(e, &mut invalid))?;
+                        Ok(block)
+                    });
                     let res = stream.forward(sink.sink_err_into()).await;
-                    if let Err(e) = res {
+                    if let Some(e) = invalid {
+                        penalize_peer(&global_state, peer, e, &res_logger).await;
+                    } else if let Err(e) = res {
                         info!(
                             res_logger,
                             "response stream failed";
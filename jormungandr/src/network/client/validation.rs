@@ -0,0 +1,126 @@
+//! checks of the responses of the peers to the requests of the client
+//!
+//! The blocks and headers received from a peer are checked against the
+//! request, and counted against the stream limits, before they are passed
+//! to the block task, so that a peer sending data the node did not ask for
+//! is caught, and penalized, right away.
+
+use crate::{blockcfg::HeaderHash, network::stream_limit::LimitExceeded};
+use std::collections::HashSet;
+
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum ResponseError {
+    #[error("received block {0} which was not solicited, or more than once")]
+    UnsolicitedBlock(HeaderHash),
+    #[error("received header {id} whose parent {parent} is not one of the checkpoints")]
+    HeaderNotFromCheckpoints { id: HeaderHash, parent: HeaderHash },
+    #[error("received header {id} which does not follow the previous header {previous}")]
+    HeaderNotChained {
+        id: HeaderHash,
+        previous: HeaderHash,
+    },
+    #[error("received header {0} past the end of the requested range")]
+    HeaderPastRange(HeaderHash),
+    #[error("the response is too large")]
+    TooLarge(#[from] LimitExceeded),
+}
+
+/// the blocks solicited from a peer and not received yet
+pub struct SolicitedBlocks {
+    pending: HashSet<HeaderHash>,
+}
+
+impl SolicitedBlocks {
+    pub fn new(ids: &[HeaderHash]) -> Self {
+        SolicitedBlocks {
+            pending: ids.iter().copied().collect(),
+        }
+    }
+
+    /// check that the block with the given id was solicited and not
+    /// received before
+    pub fn check(&mut self, id: HeaderHash) -> Result<(), ResponseError> {
+        if self.pending.remove(&id) {
+            Ok(())
+        } else {
+            Err(ResponseError::UnsolicitedBlock(id))
+        }
+    }
+}
+
+/// the range of headers pulled from a peer: a chain starting after one
+/// of the checkpoints, or at the genesis block if the peer knows none of
+/// them, up to the requested header
+pub struct PulledHeaders {
+    checkpoints: HashSet<HeaderHash>,
+    block0: HeaderHash,
+    to: HeaderHash,
+    last: Option<HeaderHash>,
+}
+
+impl PulledHeaders {
+    pub fn new(checkpoints: &[HeaderHash], to: HeaderHash, block0: HeaderHash) -> Self {
+        PulledHeaders {
+            checkpoints: checkpoints.iter().copied().collect(),
+            block0,
+            to,
+            last: None,
+        }
+    }
+
+    /// check that the header with the given id and parent is the next one
+    /// of the range
+    pub fn check(&mut self, id: HeaderHash, parent: HeaderHash) -> Result<(), ResponseError> {
+        match self.last {
+            None => {
+                if !self.checkpoints.contains(&parent) && id != self.block0 {
+                    return Err(ResponseError::HeaderNotFromCheckpoints { id, parent });
+                }
+            }
+            Some(last) if last == self.to => return Err(ResponseError::HeaderPastRange(id)),
+            Some(last) => {
+                if parent != last {
+                    return Err(ResponseError::HeaderNotChained { id, previous: last });
+                }
+            }
+        }
+        self.last = Some(id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(n: u8) -> HeaderHash {
+        HeaderHash::hash_bytes(&[n])
+    }
+
+    #[test]
+    fn solicited_blocks_are_received_once() {
+        let mut solicited = SolicitedBlocks::new(&[hash(1), hash(2)]);
+        assert!(solicited.check(hash(2)).is_ok());
+        assert!(solicited.check(hash(2)).is_err());
+        assert!(solicited.check(hash(3)).is_err());
+        assert!(solicited.check(hash(1)).is_ok());
+    }
+
+    #[test]
+    fn pulled_headers_form_the_requested_chain() {
+        let mut pulled = PulledHeaders::new(&[hash(0), hash(1)], hash(3), hash(100));
+        assert!(pulled.check(hash(2), hash(1)).is_ok());
+        assert!(pulled.check(hash(3), hash(2)).is_ok());
+        assert!(pulled.check(hash(4), hash(3)).is_err());
+
+        let mut pulled = PulledHeaders::new(&[hash(0)], hash(3), hash(100));
+        assert!(pulled.check(hash(2), hash(1)).is_err());
+
+        let mut pulled = PulledHeaders::new(&[hash(0)], hash(3), hash(100));
+        assert!(pulled.check(hash(1), hash(0)).is_ok());
+        assert!(pulled.check(hash(3), hash(0)).is_err());
+
+        let mut pulled = PulledHeaders::new(&[hash(0)], hash(3), hash(100));
+        assert!(pulled.check(hash(100), hash(50)).is_ok());
+    }
+}
@@ -1,22 +1,106 @@
 use super::p2p::Gossip;
-use crate::blockcfg::{Block, Fragment, Header, HeaderId};
+use crate::blockcfg::{Block, Fragment, FragmentId, Header, HeaderId};
 use crate::intercom;
 use chain_core::mempack::{ReadBuf, Readable};
 use chain_core::property::{Deserialize, Serialize};
 use chain_network::data as net_data;
 use chain_network::error::{Code, Error};
 
+use futures::prelude::*;
 use futures::stream::{self, TryStreamExt};
+use tokio::sync::OwnedSemaphorePermit;
 
 use std::convert::TryFrom;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// the size of the block ids
+const HASH_SIZE: usize = 32;
+
+/// the largest header accepted from a peer. The headers signed with
+/// the KES keys of Genesis Praos are the largest, well below this.
+pub const MAX_HEADER_SIZE: usize = 4096;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// The reasons a data item received from a peer can not be decoded.
+///
+/// The error message sent back to the peer starts with the code of the
+/// error, so that the other implementations of the protocol can tell
+/// which of their items is rejected.
+#[derive(thiserror::Error, Debug)]
+pub enum DecodeError {
+    #[error("invalid_hash_length: block id of {0} bytes, expected {}", HASH_SIZE)]
+    InvalidHashLength(usize),
+    #[error(
+        "header_too_large: header of {0} bytes, the maximum is {}",
+        MAX_HEADER_SIZE
+    )]
+    HeaderTooLarge(usize),
+    #[error("bad_header_encoding: {0}")]
+    BadHeaderEncoding(#[source] BoxError),
+    #[error("bad_block_encoding: {0}")]
+    BadBlockEncoding(#[source] BoxError),
+    #[error("bad_fragment_encoding: {0}")]
+    BadFragmentEncoding(#[source] BoxError),
+    #[error("bad_gossip_encoding: {0}")]
+    BadGossipEncoding(#[source] BoxError),
+}
+
+impl DecodeError {
+    const CODES: [&'static str; 6] = [
+        "invalid_hash_length",
+        "header_too_large",
+        "bad_header_encoding",
+        "bad_block_encoding",
+        "bad_fragment_encoding",
+        "bad_gossip_encoding",
+    ];
+
+    /// the code of the decoding error reported in the message of a gRPC
+    /// status, if any
+    pub fn code_in_message(message: &str) -> Option<&'static str> {
+        Self::CODES.iter().copied().find(|code| {
+            message
+                .find(code)
+                .map_or(false, |at| message[at + code.len()..].starts_with(": "))
+        })
+    }
+
+    /// the code of the error, as reported to the peer and in the
+    /// statistics of the peer
+    pub fn code(&self) -> &'static str {
+        match self {
+            DecodeError::InvalidHashLength(_) => "invalid_hash_length",
+            DecodeError::HeaderTooLarge(_) => "header_too_large",
+            DecodeError::BadHeaderEncoding(_) => "bad_header_encoding",
+            DecodeError::BadBlockEncoding(_) => "bad_block_encoding",
+            DecodeError::BadFragmentEncoding(_) => "bad_fragment_encoding",
+            DecodeError::BadGossipEncoding(_) => "bad_gossip_encoding",
+        }
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(e: DecodeError) -> Self {
+        Error::new(Code::InvalidArgument, e)
+    }
+}
 
-fn read<T, U>(src: &T) -> Result<U, Error>
+/// the code of the decoding error carried by a protocol error, if any
+pub fn decode_error_code(e: &Error) -> Option<&'static str> {
+    std::error::Error::source(e)
+        .and_then(|source| source.downcast_ref::<DecodeError>())
+        .map(DecodeError::code)
+}
+
+fn read<T, U>(src: &T) -> Result<U, BoxError>
 where
     T: AsRef<[u8]>,
     U: Readable,
 {
     let mut buf = ReadBuf::from(src.as_ref());
-    U::read(&mut buf).map_err(|e| Error::new(Code::InvalidArgument, e))
+    U::read(&mut buf).map_err(Into::into)
 }
 
 /// Conversion from a chain-network byte container data type
@@ -35,13 +119,29 @@ pub trait Encode {
     fn encode(&self) -> Self::NetworkData;
 }
 
-pub type ResponseStream<T> =
-    stream::MapOk<intercom::ReplyStream<T, Error>, fn(T) -> <T as Encode>::NetworkData>;
+/// stream of encoded response items. The request slots of the request
+/// are held until the stream is dropped.
+pub struct ResponseStream<T: Encode> {
+    inner: stream::MapOk<intercom::ReplyStream<T, Error>, fn(T) -> <T as Encode>::NetworkData>,
+    _permits: Vec<OwnedSemaphorePermit>,
+}
+
+impl<T: Encode> Stream for ResponseStream<T> {
+    type Item = Result<T::NetworkData, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.inner).poll_next(cx)
+    }
+}
 
 pub fn response_stream<T: Encode>(
     reply_stream: intercom::ReplyStream<T, Error>,
+    permits: Vec<OwnedSemaphorePermit>,
 ) -> ResponseStream<T> {
-    reply_stream.map_ok(|item| item.encode())
+    ResponseStream {
+        inner: reply_stream.map_ok(|item| item.encode()),
+        _permits: permits,
+    }
 }
 
 impl<T, N> Decode for Box<[N]>
@@ -59,7 +159,20 @@ impl Decode for net_data::BlockId {
     type Object = HeaderId;
 
     fn decode(self) -> Result<Self::Object, Error> {
-        read(&self)
+        let len = self.as_ref().len();
+        if len != HASH_SIZE {
+            return Err(DecodeError::InvalidHashLength(len).into());
+        }
+        // reading a hash of the right length cannot fail
+        read(&self).map_err(|e| Error::new(Code::InvalidArgument, e))
+    }
+}
+
+impl Decode for net_data::FragmentId {
+    type Object = FragmentId;
+
+    fn decode(self) -> Result<Self::Object, Error> {
+        read(&self).map_err(|e| Error::new(Code::InvalidArgument, e))
     }
 }
 
@@ -67,7 +180,7 @@ impl Decode for net_data::Block {
     type Object = Block;
 
     fn decode(self) -> Result<Self::Object, Error> {
-        read(&self)
+        read(&self).map_err(|e| DecodeError::BadBlockEncoding(e).into())
     }
 }
 
@@ -75,7 +188,11 @@ impl Decode for net_data::Header {
     type Object = Header;
 
     fn decode(self) -> Result<Self::Object, Error> {
-        read(&self)
+        let len = self.as_ref().len();
+        if len > MAX_HEADER_SIZE {
+            return Err(DecodeError::HeaderTooLarge(len).into());
+        }
+        read(&self).map_err(|e| DecodeError::BadHeaderEncoding(e).into())
     }
 }
 
@@ -83,14 +200,16 @@ impl Decode for net_data::Fragment {
     type Object = Fragment;
 
     fn decode(self) -> Result<Self::Object, Error> {
-        Fragment::deserialize(self.as_bytes()).map_err(|e| Error::new(Code::InvalidArgument, e))
+        Fragment::deserialize(self.as_bytes())
+            .map_err(|e| DecodeError::BadFragmentEncoding(e.into()).into())
     }
 }
 
 impl Decode for net_data::gossip::Node {
     type Object = Gossip;
     fn decode(self) -> Result<Self::Object, Error> {
-        Gossip::deserialize(self.as_bytes()).map_err(|e| Error::new(Code::InvalidArgument, e))
+        Gossip::deserialize(self.as_bytes())
+            .map_err(|e| DecodeError::BadGossipEncoding(e.into()).into())
     }
 }
 
@@ -113,6 +232,14 @@ impl Encode for HeaderId {
     }
 }
 
+impl Encode for FragmentId {
+    type NetworkData = net_data::FragmentId;
+
+    fn encode(&self) -> Self::NetworkData {
+        net_data::FragmentId::try_from(self.as_bytes()).unwrap()
+    }
+}
+
 impl Encode for Block {
     type NetworkData = net_data::Block;
 
@@ -146,3 +273,36 @@ impl Encode for Gossip {
         net_data::gossip::Node::from_bytes(bytes)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decoding_errors_carry_their_code() {
+        let header = net_data::Header::from_bytes(vec![0; MAX_HEADER_SIZE + 1]);
+        let e = header.decode().unwrap_err();
+        assert_eq!(decode_error_code(&e), Some("header_too_large"));
+
+        let header = net_data::Header::from_bytes(vec![0; 3]);
+        let e = header.decode().unwrap_err();
+        assert_eq!(decode_error_code(&e), Some("bad_header_encoding"));
+
+        let e = Error::new(Code::InvalidArgument, "not a decoding error");
+        assert_eq!(decode_error_code(&e), None);
+    }
+
+    #[test]
+    fn codes_are_found_in_status_messages() {
+        let e = DecodeError::InvalidHashLength(3);
+        assert_eq!(
+            DecodeError::code_in_message(&format!("InvalidArgument: {}", e)),
+            Some(e.code())
+        );
+        assert_eq!(
+            DecodeError::code_in_message("InvalidArgument: header_too_large"),
+            None
+        );
+        assert_eq!(DecodeError::code_in_message("Unavailable: timeout"), None);
+    }
+}
@@ -1,3 +1,8 @@
+use super::transport::{NodeTransport, Transport};
+use super::{
+    node_version, DIAL_BACK_HEADER, DIAL_BACK_REACHABLE, DIAL_BACK_RESULT_HEADER,
+    DIAL_BACK_UNREACHABLE, NODE_VERSION_HEADER,
+};
 use crate::{
     blockcfg::{Block, HeaderHash},
     network::convert::Decode,
@@ -6,14 +11,18 @@ use crate::{
 };
 use chain_network::data as net_data;
 use chain_network::error as net_error;
-use chain_network::grpc::{client::Builder, legacy};
+use chain_network::grpc::client::Builder;
+use futures::future::BoxFuture;
 use futures::prelude::*;
+use http::{header::HeaderValue, Request, Response};
 use slog::Logger;
 use thiserror::Error;
-use tonic::transport;
+use tonic::{body::BoxBody, codegen::Service, transport};
 
 use std::convert::TryFrom;
 use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
 
 pub use chain_network::grpc::client::{
     BlockSubscription, FragmentSubscription, GossipSubscription,
@@ -31,28 +40,144 @@ pub enum FetchBlockError {
     NoBlocks,
 }
 
+#[derive(Error, Debug)]
+pub enum DialBackError {
+    #[error("connection to peer failed")]
+    Connect { source: ConnectError },
+    #[error("tip request failed")]
+    Tip { source: net_error::Error },
+}
+
 pub type ConnectError = transport::Error;
 
-pub type Client = chain_network::grpc::Client<tonic::transport::Channel>;
+pub type Client = chain_network::grpc::Client<VersionedChannel>;
+
+/// The version the peer tells in the responses to the requests made on a
+/// connection, once the peer has answered one.
+#[derive(Clone, Default)]
+pub struct PeerVersion(Arc<Mutex<Option<String>>>);
+
+impl PeerVersion {
+    pub fn get(&self) -> Option<String> {
+        self.0.lock().unwrap().clone()
+    }
+
+    fn set(&self, version: String) {
+        *self.0.lock().unwrap() = Some(version);
+    }
+}
+
+/// A request for the peer to connect back to this node, with the outcome
+/// the peer tells once it has answered.
+#[derive(Clone)]
+struct DialBack {
+    port: u16,
+    reachable: Arc<Mutex<Option<bool>>>,
+}
+
+/// The channel of a connection to a peer, which tells the version of this
+/// node in the headers of the requests and keeps the version the peer tells
+/// in the headers of its responses.
+#[derive(Clone)]
+pub struct VersionedChannel {
+    inner: transport::Channel,
+    peer_version: PeerVersion,
+    dial_back: Option<DialBack>,
+}
+
+impl Service<Request<BoxBody>> for VersionedChannel {
+    type Response = Response<transport::Body>;
+    type Error = transport::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
 
-pub async fn connect(peer: &Peer) -> Result<Client, ConnectError> {
-    connect_internal(peer, Builder::new()).await
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        tonic::client::GrpcService::poll_ready(&mut self.inner, cx)
+    }
+
+    fn call(&mut self, mut req: Request<BoxBody>) -> Self::Future {
+        req.headers_mut()
+            .insert(NODE_VERSION_HEADER, node_version());
+        if let Some(dial_back) = &self.dial_back {
+            req.headers_mut()
+                .insert(DIAL_BACK_HEADER, HeaderValue::from(dial_back.port));
+        }
+        let peer_version = self.peer_version.clone();
+        let dial_back = self.dial_back.clone();
+        tonic::client::GrpcService::call(&mut self.inner, req)
+            .map_ok(move |res| {
+                if let Some(version) = super::peer_version(res.headers()) {
+                    peer_version.set(version);
+                }
+                if let Some(dial_back) = dial_back {
+                    let reachable = match res.headers().get(DIAL_BACK_RESULT_HEADER) {
+                        Some(value) if value == DIAL_BACK_REACHABLE => Some(true),
+                        Some(value) if value == DIAL_BACK_UNREACHABLE => Some(false),
+                        _ => None,
+                    };
+                    if reachable.is_some() {
+                        *dial_back.reachable.lock().unwrap() = reachable;
+                    }
+                }
+                res
+            })
+            .boxed()
+    }
 }
 
-pub async fn connect_legacy(peer: &Peer, node_id: legacy::NodeId) -> Result<Client, ConnectError> {
-    let mut builder = Builder::new();
-    builder.legacy_node_id(node_id);
-    connect_internal(peer, builder).await
+pub async fn connect(transport: &NodeTransport, peer: &Peer) -> Result<Client, ConnectError> {
+    connect_with(transport, peer, Builder::new(), PeerVersion::default()).await
 }
 
-async fn connect_internal(peer: &Peer, builder: Builder) -> Result<Client, ConnectError> {
+/// connect to the peer over the given transport, keeping the version the
+/// peer tells in `peer_version`
+pub async fn connect_with<T: Transport>(
+    transport: &T,
+    peer: &Peer,
+    builder: Builder,
+    peer_version: PeerVersion,
+) -> Result<Client, ConnectError> {
     assert!(peer.protocol == Protocol::Grpc);
     let endpoint = destination_endpoint(peer.connection)
         .concurrency_limit(concurrency_limits::CLIENT_REQUESTS)
-        .tcp_keepalive(Some(keepalive_durations::TCP))
         .http2_keep_alive_interval(keepalive_durations::HTTP2)
         .timeout(peer.timeout);
-    builder.connect(endpoint).await
+    let inner = transport.connect(endpoint).await?;
+    Ok(builder.build(VersionedChannel {
+        inner,
+        peer_version,
+        dial_back: None,
+    }))
+}
+
+/// ask the peer to connect back to this node, at the given port of the
+/// address the peer sees the connection coming from. Returns whether the
+/// peer could connect, or `None` if the peer does not serve such requests.
+pub async fn request_dial_back(
+    transport: &NodeTransport,
+    peer: &Peer,
+    port: u16,
+) -> Result<Option<bool>, DialBackError> {
+    assert!(peer.protocol == Protocol::Grpc);
+    let endpoint = destination_endpoint(peer.connection).timeout(peer.timeout);
+    let inner = transport
+        .connect(endpoint)
+        .await
+        .map_err(|source| DialBackError::Connect { source })?;
+    let dial_back = DialBack {
+        port,
+        reachable: Arc::new(Mutex::new(None)),
+    };
+    let mut client = Builder::new().build(VersionedChannel {
+        inner,
+        peer_version: PeerVersion::default(),
+        dial_back: Some(dial_back.clone()),
+    });
+    client
+        .tip()
+        .await
+        .map_err(|source| DialBackError::Tip { source })?;
+    let reachable = *dial_back.reachable.lock().unwrap();
+    Ok(reachable)
 }
 
 fn destination_endpoint(addr: SocketAddr) -> transport::Endpoint {
@@ -63,12 +188,13 @@ fn destination_endpoint(addr: SocketAddr) -> transport::Endpoint {
 // Fetches a block from a network peer.
 // This function is used during node bootstrap to fetch the genesis block.
 pub async fn fetch_block(
+    transport: &NodeTransport,
     peer: &Peer,
     hash: HeaderHash,
     logger: &Logger,
 ) -> Result<Block, FetchBlockError> {
     info!(logger, "fetching block {}", hash);
-    let mut client = connect(peer)
+    let mut client = connect(transport, peer)
         .await
         .map_err(|err| FetchBlockError::Connect { source: err })?;
     let block_id = net_data::BlockId::try_from(hash.as_bytes()).unwrap();
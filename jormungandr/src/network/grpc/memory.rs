@@ -0,0 +1,426 @@
+//! an in-memory network, to run the client and server state machines
+//! without sockets: in the tests, and between the nodes of a cluster
+//!
+//! The connections are pairs of in-memory pipes. Each connecting client is
+//! given an address of its own, so the server can tell its peers apart.
+//! Faults can be injected in the connections, drawn from a seeded random
+//! generator so that a failing run can be replayed.
+
+use super::{client::ConnectError, transport::Transport};
+use futures::{
+    channel::mpsc,
+    future::{self, BoxFuture},
+    prelude::*,
+    ready,
+};
+use http::Uri;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaChaRng;
+use slog::Logger;
+use std::{
+    collections::{HashMap, VecDeque},
+    io,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    pin::Pin,
+    sync::{Arc, Mutex, Weak},
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    time::{self, Delay},
+};
+use tonic::transport::{server::Connected, Channel, Endpoint};
+use tower_service::Service;
+
+/// the first port given to the connecting clients
+const FIRST_CLIENT_PORT: u16 = 49152;
+
+/// the faults injected in the writes to the connections
+#[derive(Clone, Debug, Default)]
+pub struct Faults {
+    /// the probability for a write to reset the connection
+    pub reset_rate: f64,
+    /// the probability for a write to be held back, as by a slow peer
+    pub delay_rate: f64,
+    /// the longest a write is held back
+    pub max_delay: Duration,
+}
+
+enum Fault {
+    Reset,
+    Delay(Duration),
+}
+
+/// the addresses listened to in memory. The clones share the same
+/// listeners.
+#[derive(Clone)]
+pub struct MemoryNetwork(Arc<Mutex<internal::MemoryNetwork>>);
+
+impl Default for MemoryNetwork {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryNetwork {
+    /// a network without faults
+    pub fn new() -> Self {
+        Self::with_faults(0, Faults::default())
+    }
+
+    /// a network injecting the faults, drawn from a generator with the
+    /// given seed
+    pub fn with_faults(seed: u64, faults: Faults) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&faults.reset_rate) && (0.0..=1.0).contains(&faults.delay_rate),
+            "the fault rates are probabilities"
+        );
+        MemoryNetwork(Arc::new(Mutex::new(internal::MemoryNetwork {
+            listeners: HashMap::new(),
+            next_port: FIRST_CLIENT_PORT,
+            pipes: Vec::new(),
+            faults,
+            rng: ChaChaRng::seed_from_u64(seed),
+        })))
+    }
+
+    /// reset all the open connections, as when the network goes down
+    #[cfg(test)]
+    pub fn reset_all(&self) {
+        let mut network = self.0.lock().unwrap();
+        for pipe in network.pipes.drain(..).filter_map(|pipe| pipe.upgrade()) {
+            pipe.lock().unwrap().reset();
+        }
+    }
+
+    /// start accepting connections on the address, which must not be
+    /// listened to already
+    pub fn bind(&self, addr: SocketAddr) -> io::Result<MemoryIncoming> {
+        let mut network = self.0.lock().unwrap();
+        if let Some(listener) = network.listeners.get(&addr) {
+            if !listener.is_closed() {
+                return Err(io::ErrorKind::AddrInUse.into());
+            }
+        }
+        let (sender, receiver) = mpsc::unbounded();
+        network.listeners.insert(addr, sender);
+        Ok(MemoryIncoming(receiver))
+    }
+
+    /// connect to the address, which must be listened to
+    pub fn connect(&self, addr: SocketAddr) -> io::Result<MemoryStream> {
+        let mut network = self.0.lock().unwrap();
+        let client_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), network.next_port);
+        network.next_port = network.next_port.wrapping_add(1).max(FIRST_CLIENT_PORT);
+
+        let (client, server) = MemoryStream::pair(self, client_addr, addr);
+        network.pipes.retain(|pipe| pipe.strong_count() > 0);
+        network.pipes.push(Arc::downgrade(&client.reader));
+        network.pipes.push(Arc::downgrade(&client.writer));
+        let accepted = network
+            .listeners
+            .get(&addr)
+            .map_or(false, |listener| listener.unbounded_send(server).is_ok());
+        if !accepted {
+            network.listeners.remove(&addr);
+            return Err(io::ErrorKind::ConnectionRefused.into());
+        }
+        Ok(client)
+    }
+}
+
+impl Transport for MemoryNetwork {
+    type Stream = MemoryStream;
+    type Incoming = MemoryIncoming;
+
+    fn connect(&self, endpoint: Endpoint) -> BoxFuture<'static, Result<Channel, ConnectError>> {
+        let connector = self.clone();
+        async move { endpoint.connect_with_connector(connector).await }.boxed()
+    }
+
+    fn listen(
+        &self,
+        addr: SocketAddr,
+        _reuse_port: bool,
+        _logger: &Logger,
+    ) -> io::Result<Self::Incoming> {
+        self.bind(addr)
+    }
+}
+
+/// connect to the address of the endpoint URI
+impl Service<Uri> for MemoryNetwork {
+    type Response = MemoryStream;
+    type Error = io::Error;
+    type Future = future::Ready<io::Result<MemoryStream>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        // the inherent method, not the one of `Transport`
+        let addr = uri
+            .authority()
+            .and_then(|authority| authority.as_str().parse().ok())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a socket address"));
+        future::ready(addr.and_then(|addr| MemoryNetwork::connect(self, addr)))
+    }
+}
+
+/// the connections accepted on an address
+pub struct MemoryIncoming(mpsc::UnboundedReceiver<MemoryStream>);
+
+impl Stream for MemoryIncoming {
+    type Item = io::Result<MemoryStream>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.0)
+            .poll_next(cx)
+            .map(|stream| stream.map(Ok))
+    }
+}
+
+/// one end of an in-memory connection
+pub struct MemoryStream {
+    reader: Arc<Mutex<internal::Pipe>>,
+    writer: Arc<Mutex<internal::Pipe>>,
+    remote_addr: SocketAddr,
+    network: MemoryNetwork,
+    /// the write held back by an injected delay
+    delay: Option<Pin<Box<Delay>>>,
+}
+
+impl MemoryStream {
+    fn pair(
+        network: &MemoryNetwork,
+        client_addr: SocketAddr,
+        server_addr: SocketAddr,
+    ) -> (Self, Self) {
+        let to_server = Arc::new(Mutex::new(internal::Pipe::default()));
+        let to_client = Arc::new(Mutex::new(internal::Pipe::default()));
+        let client = MemoryStream {
+            reader: to_client.clone(),
+            writer: to_server.clone(),
+            remote_addr: server_addr,
+            network: network.clone(),
+            delay: None,
+        };
+        let server = MemoryStream {
+            reader: to_server,
+            writer: to_client,
+            remote_addr: client_addr,
+            network: network.clone(),
+            delay: None,
+        };
+        (client, server)
+    }
+}
+
+impl Connected for MemoryStream {
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        Some(self.remote_addr)
+    }
+}
+
+impl AsyncRead for MemoryStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut pipe = self.reader.lock().unwrap();
+        if pipe.reset {
+            return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()));
+        }
+        if pipe.buffer.is_empty() && !pipe.closed {
+            pipe.waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        let len = buf.len().min(pipe.buffer.len());
+        for (dst, src) in buf.iter_mut().zip(pipe.buffer.drain(..len)) {
+            *dst = src;
+        }
+        Poll::Ready(Ok(len))
+    }
+}
+
+impl AsyncWrite for MemoryStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        if this.delay.is_none() {
+            // the lock on the network is released before the pipes are locked
+            let fault = this.network.0.lock().unwrap().draw_fault();
+            match fault {
+                Some(Fault::Reset) => {
+                    this.writer.lock().unwrap().reset();
+                    this.reader.lock().unwrap().reset();
+                }
+                Some(Fault::Delay(duration)) => {
+                    this.delay = Some(Box::pin(time::delay_for(duration)));
+                }
+                None => (),
+            }
+        }
+        if let Some(delay) = &mut this.delay {
+            ready!(delay.as_mut().poll(cx));
+            this.delay = None;
+        }
+
+        let mut pipe = this.writer.lock().unwrap();
+        if pipe.reset {
+            return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()));
+        }
+        if pipe.closed {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+        pipe.buffer.extend(buf);
+        pipe.wake();
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.writer.lock().unwrap().close();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for MemoryStream {
+    fn drop(&mut self) {
+        self.writer.lock().unwrap().close();
+        self.reader.lock().unwrap().close();
+    }
+}
+
+mod internal {
+    use super::*;
+
+    pub struct MemoryNetwork {
+        pub listeners: HashMap<SocketAddr, mpsc::UnboundedSender<MemoryStream>>,
+        pub next_port: u16,
+        /// the pipes of the connections, to reset them
+        pub pipes: Vec<Weak<Mutex<Pipe>>>,
+        pub faults: Faults,
+        pub rng: ChaChaRng,
+    }
+
+    impl MemoryNetwork {
+        pub(super) fn draw_fault(&mut self) -> Option<Fault> {
+            if self.rng.gen_bool(self.faults.reset_rate) {
+                return Some(Fault::Reset);
+            }
+            let max_delay = self.faults.max_delay.as_millis() as u64;
+            if max_delay > 0 && self.rng.gen_bool(self.faults.delay_rate) {
+                let delay = self.rng.gen_range(1, max_delay + 1);
+                return Some(Fault::Delay(Duration::from_millis(delay)));
+            }
+            None
+        }
+    }
+
+    /// the bytes written to one end of a connection and not yet read from
+    /// the other end
+    #[derive(Default)]
+    pub struct Pipe {
+        pub buffer: VecDeque<u8>,
+        pub closed: bool,
+        pub reset: bool,
+        pub waker: Option<Waker>,
+    }
+
+    impl Pipe {
+        pub fn close(&mut self) {
+            self.closed = true;
+            self.wake();
+        }
+
+        pub fn reset(&mut self) {
+            self.reset = true;
+            self.buffer.clear();
+            self.wake();
+        }
+
+        pub fn wake(&mut self) {
+            if let Some(waker) = self.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn addr(port: u16) -> SocketAddr {
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port)
+    }
+
+    #[tokio::test]
+    async fn connections_carry_bytes_both_ways() {
+        let network = MemoryNetwork::new();
+        let mut incoming = network.bind(addr(3000)).unwrap();
+        let mut client = network.connect(addr(3000)).unwrap();
+        let mut server = incoming.next().await.unwrap().unwrap();
+        assert_eq!(server.remote_addr(), Some(addr(FIRST_CLIENT_PORT)));
+
+        client.write_all(b"ping").await.unwrap();
+        let mut buf = [0; 4];
+        server.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+
+        server.write_all(b"pong").await.unwrap();
+        drop(server);
+        let mut buf = Vec::new();
+        client.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, b"pong");
+    }
+
+    #[test]
+    fn addresses_are_listened_to_once() {
+        let network = MemoryNetwork::new();
+        assert_eq!(
+            network.connect(addr(3000)).err().map(|e| e.kind()),
+            Some(io::ErrorKind::ConnectionRefused)
+        );
+
+        let incoming = network.bind(addr(3000)).unwrap();
+        assert_eq!(
+            network.bind(addr(3000)).err().map(|e| e.kind()),
+            Some(io::ErrorKind::AddrInUse)
+        );
+
+        drop(incoming);
+        assert!(network.bind(addr(3000)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn reset_connections_fail() {
+        let network = MemoryNetwork::new();
+        let mut incoming = network.bind(addr(3000)).unwrap();
+        let mut client = network.connect(addr(3000)).unwrap();
+        let mut server = incoming.next().await.unwrap().unwrap();
+
+        client.write_all(b"lost").await.unwrap();
+        network.reset_all();
+        let mut buf = [0; 4];
+        assert_eq!(
+            server.read(&mut buf).await.map_err(|e| e.kind()),
+            Err(io::ErrorKind::ConnectionReset)
+        );
+        assert_eq!(
+            client.write_all(b"ping").await.map_err(|e| e.kind()),
+            Err(io::ErrorKind::ConnectionReset)
+        );
+    }
+}
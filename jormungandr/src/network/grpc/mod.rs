@@ -1,7 +1,47 @@
 pub(super) mod client;
+// the in-memory network connects the nodes of a cluster, and is always
+// built for the tests
+#[cfg(any(test, feature = "in-memory-transport"))]
+pub mod memory;
 mod server;
+mod transport;
 
 pub use self::client::{
-    connect, connect_legacy, fetch_block, Client, ConnectError, FetchBlockError,
+    connect, connect_with, fetch_block, request_dial_back, Client, ConnectError, DialBackError,
+    FetchBlockError, PeerVersion,
 };
-pub use self::server::run_listen_socket;
+pub use self::server::{current_peer, current_peer_version, serve};
+pub use self::transport::{NodeTransport, Transport};
+
+use http::header::{HeaderMap, HeaderValue};
+
+/// the gRPC header in which the nodes tell their version to each other,
+/// in the requests of the clients and in the responses of the servers
+const NODE_VERSION_HEADER: &str = "jormungandr-version";
+
+/// the longest version string kept from the headers of a peer
+const MAX_PEER_VERSION_LEN: usize = 64;
+
+/// the gRPC header in which a node asks the peer to connect back to it, at
+/// the given port of the address the peer sees the request coming from
+const DIAL_BACK_HEADER: &str = "jormungandr-dial-back";
+
+/// the gRPC header in which the peer answers whether it could connect back
+const DIAL_BACK_RESULT_HEADER: &str = "jormungandr-dial-back-result";
+
+const DIAL_BACK_REACHABLE: &str = "reachable";
+const DIAL_BACK_UNREACHABLE: &str = "unreachable";
+
+fn node_version() -> HeaderValue {
+    HeaderValue::from_static(env!("SIMPLE_VERSION"))
+}
+
+/// the version the peer tells in the headers, if it is valid text of a
+/// reasonable length
+fn peer_version(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(NODE_VERSION_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|version| !version.is_empty() && version.len() <= MAX_PEER_VERSION_LEN)
+        .map(ToOwned::to_owned)
+}
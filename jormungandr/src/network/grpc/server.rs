@@ -1,15 +1,53 @@
 use super::super::{
-    concurrency_limits, keepalive_durations, service::NodeService, Channels, GlobalStateR,
-    ListenError,
+    concurrency_limits, convert::DecodeError, port_mapping, service::NodeService, Channels,
+    GlobalStateR, ListenError,
+};
+use super::transport::Transport;
+use super::{
+    node_version, peer_version, DIAL_BACK_HEADER, DIAL_BACK_REACHABLE, DIAL_BACK_RESULT_HEADER,
+    DIAL_BACK_UNREACHABLE, NODE_VERSION_HEADER,
 };
 use crate::settings::start::network::Listen;
 use chain_network::grpc;
-
-use tonic::transport::Server;
+use futures::future::{BoxFuture, FutureExt, MapOk, TryFutureExt};
+use http::{header::HeaderValue, Request, Response};
+use tonic::{
+    body::BoxBody,
+    codegen::Service,
+    transport::{NamedService, Server},
+};
 
 use std::convert::TryInto;
+use std::net::SocketAddr;
+use std::task::{Context, Poll};
+
+/// the gRPC header carrying the details of the status of a response
+const STATUS_DETAILS_HEADER: &str = "grpc-status-details-bin";
+
+tokio::task_local! {
+    /// the address of the peer whose request is served by the task
+    static PEER_ADDR: SocketAddr;
+
+    /// the version the peer tells in the headers of the request served by
+    /// the task
+    static PEER_VERSION: Option<String>;
+}
 
-pub async fn run_listen_socket(
+/// the address of the peer whose request is being served, for the methods
+/// of the service which are not given the peer by the protocol
+pub fn current_peer() -> Option<SocketAddr> {
+    PEER_ADDR.try_with(|addr| *addr).ok()
+}
+
+/// the version the peer tells in the headers of the request being served,
+/// if it tells one
+pub fn current_peer_version() -> Option<String> {
+    PEER_VERSION.try_with(Clone::clone).ok().flatten()
+}
+
+/// accept the connections of the peers over the given transport
+pub async fn serve<T: Transport>(
+    transport: &T,
     listen: &Listen,
     state: GlobalStateR,
     channels: Channels,
@@ -17,6 +55,14 @@ pub async fn run_listen_socket(
     let sockaddr = listen.address();
 
     let logger = state.logger().new(o!("local_addr" => sockaddr.to_string()));
+
+    let incoming = transport
+        .listen(sockaddr, state.config.reuse_port, &logger)
+        .map_err(|cause| ListenError {
+            cause: cause.into(),
+            sockaddr,
+        })?;
+
     info!(logger, "listening and accepting gRPC connections");
 
     let mut builder = grpc::server::Builder::new();
@@ -24,13 +70,343 @@ pub async fn run_listen_socket(
         let node_id: grpc::legacy::NodeId = node_id.as_ref().try_into().unwrap();
         builder.legacy_node_id(node_id);
     }
-    let service = builder.build(NodeService::new(channels, state));
+    let service = PeerScope {
+        inner: VersionHeader {
+            inner: DialBack {
+                inner: DecodeErrorDetails {
+                    inner: builder.build(NodeService::new(channels, state)),
+                },
+            },
+        },
+    };
 
     Server::builder()
         .concurrency_limit_per_connection(concurrency_limits::SERVER_REQUESTS)
-        .tcp_keepalive(Some(keepalive_durations::TCP))
         .add_service(service)
-        .serve(sockaddr)
+        .serve_with_incoming(incoming)
         .await
-        .map_err(|cause| ListenError { cause, sockaddr })
+        .map_err(|cause| ListenError {
+            cause: cause.into(),
+            sockaddr,
+        })
+}
+
+/// Serves each request in the scope of the address of the peer and of the
+/// version it tells, for `current_peer` and `current_peer_version` to find
+/// them.
+#[derive(Clone)]
+struct PeerScope<S> {
+    inner: S,
+}
+
+impl<S: NamedService> NamedService for PeerScope<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+impl<S, B> Service<Request<B>> for PeerScope<S>
+where
+    S: Service<Request<B>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<S::Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        // the transport puts the remote address of the connection in the
+        // extensions of the request
+        let peer = req.extensions().get::<SocketAddr>().copied();
+        let version = peer_version(req.headers());
+        let future = PEER_VERSION.scope(version, self.inner.call(req));
+        match peer {
+            Some(peer) => PEER_ADDR.scope(peer, future).boxed(),
+            None => future.boxed(),
+        }
+    }
+}
+
+/// Tells the version of this node in the headers of the responses.
+#[derive(Clone)]
+struct VersionHeader<S> {
+    inner: S,
+}
+
+impl<S: NamedService> NamedService for VersionHeader<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+impl<S, B, R> Service<Request<B>> for VersionHeader<S>
+where
+    S: Service<Request<B>, Response = Response<R>>,
+{
+    type Response = Response<R>;
+    type Error = S::Error;
+    type Future = MapOk<S::Future, fn(Response<R>) -> Response<R>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        self.inner
+            .call(req)
+            .map_ok(add_version_header as fn(Response<R>) -> Response<R>)
+    }
+}
+
+fn add_version_header<T>(mut res: Response<T>) -> Response<T> {
+    res.headers_mut()
+        .insert(NODE_VERSION_HEADER, node_version());
+    res
+}
+
+/// Connects back to the peer when the request asks for it, at the port of
+/// the request and the address the connection comes from, and tells in the
+/// headers of the response whether it could. The peers find out with it
+/// whether they can be reached from outside of their network.
+#[derive(Clone)]
+struct DialBack<S> {
+    inner: S,
+}
+
+impl<S: NamedService> NamedService for DialBack<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+impl<S, B, R> Service<Request<B>> for DialBack<S>
+where
+    S: Service<Request<B>, Response = Response<R>>,
+    S::Future: Send + 'static,
+    R: Send + 'static,
+{
+    type Response = Response<R>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response<R>, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        let target = dial_back_target(&req);
+        let future = self.inner.call(req);
+        async move {
+            let mut res = future.await?;
+            if let Some(target) = target {
+                let reachable = port_mapping::dial_back(target).await;
+                add_dial_back_result(&mut res, reachable);
+            }
+            Ok(res)
+        }
+        .boxed()
+    }
+}
+
+/// the address to connect back to: only the address the request comes
+/// from can be dialed, the peer only chooses the port
+fn dial_back_target<B>(req: &Request<B>) -> Option<SocketAddr> {
+    let peer = req.extensions().get::<SocketAddr>()?;
+    let port = req
+        .headers()
+        .get(DIAL_BACK_HEADER)?
+        .to_str()
+        .ok()?
+        .parse::<u16>()
+        .ok()
+        .filter(|port| *port != 0)?;
+    Some(SocketAddr::new(peer.ip(), port))
+}
+
+fn add_dial_back_result<T>(res: &mut Response<T>, reachable: bool) {
+    let result = if reachable {
+        DIAL_BACK_REACHABLE
+    } else {
+        DIAL_BACK_UNREACHABLE
+    };
+    res.headers_mut()
+        .insert(DIAL_BACK_RESULT_HEADER, HeaderValue::from_static(result));
+}
+
+/// Puts the code of the decoding errors in the details of the status of
+/// the responses, for the other implementations of the protocol to tell
+/// which of their items is rejected without parsing the message.
+#[derive(Clone)]
+struct DecodeErrorDetails<S> {
+    inner: S,
+}
+
+impl<S: NamedService> NamedService for DecodeErrorDetails<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+impl<S, B> Service<Request<B>> for DecodeErrorDetails<S>
+where
+    S: Service<Request<B>, Response = Response<BoxBody>>,
+{
+    type Response = Response<BoxBody>;
+    type Error = S::Error;
+    type Future = MapOk<S::Future, fn(Response<BoxBody>) -> Response<BoxBody>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        self.inner
+            .call(req)
+            .map_ok(add_decode_error_details as fn(Response<BoxBody>) -> Response<BoxBody>)
+    }
+}
+
+/// the errors returned by the methods of the service are sent in the
+/// headers of the response, with no body
+fn add_decode_error_details<T>(mut res: Response<T>) -> Response<T> {
+    let code = res
+        .headers()
+        .get("grpc-message")
+        .and_then(|message| message.to_str().ok())
+        .and_then(|message| DecodeError::code_in_message(&message.replace("%20", " ")));
+    if let Some(code) = code {
+        let details = base64::encode_config(code, base64::STANDARD_NO_PAD);
+        res.headers_mut().insert(
+            STATUS_DETAILS_HEADER,
+            HeaderValue::from_str(&details).expect("base64 is a valid header value"),
+        );
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::MAX_PEER_VERSION_LEN;
+    use super::*;
+
+    fn status_response(message: &str) -> Response<()> {
+        let mut res = Response::new(());
+        res.headers_mut()
+            .insert("grpc-message", HeaderValue::from_str(message).unwrap());
+        res
+    }
+
+    /// answers each request with the peer it is served for
+    struct CurrentPeer;
+
+    impl Service<Request<()>> for CurrentPeer {
+        type Response = Option<SocketAddr>;
+        type Error = ();
+        type Future = BoxFuture<'static, Result<Option<SocketAddr>, ()>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), ()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _: Request<()>) -> Self::Future {
+            async { Ok(current_peer()) }.boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn requests_are_served_in_the_scope_of_the_peer() {
+        let addr: SocketAddr = "192.0.2.1:49152".parse().unwrap();
+        let mut service = PeerScope { inner: CurrentPeer };
+
+        let mut req = Request::new(());
+        req.extensions_mut().insert(addr);
+        assert_eq!(service.call(req).await, Ok(Some(addr)));
+
+        assert_eq!(service.call(Request::new(())).await, Ok(None));
+        assert_eq!(current_peer(), None);
+    }
+
+    /// answers each request with the version of the peer it is served for
+    struct CurrentPeerVersion;
+
+    impl Service<Request<()>> for CurrentPeerVersion {
+        type Response = Option<String>;
+        type Error = ();
+        type Future = BoxFuture<'static, Result<Option<String>, ()>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), ()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _: Request<()>) -> Self::Future {
+            async { Ok(current_peer_version()) }.boxed()
+        }
+    }
+
+    fn version_request(version: &str) -> Request<()> {
+        let mut req = Request::new(());
+        req.headers_mut()
+            .insert(NODE_VERSION_HEADER, HeaderValue::from_str(version).unwrap());
+        req
+    }
+
+    #[tokio::test]
+    async fn requests_are_served_in_the_scope_of_the_peer_version() {
+        let mut service = PeerScope {
+            inner: CurrentPeerVersion,
+        };
+
+        let version = service.call(version_request("0.10.0")).await;
+        assert_eq!(version, Ok(Some("0.10.0".to_owned())));
+
+        assert_eq!(service.call(Request::new(())).await, Ok(None));
+        let too_long = "0".repeat(MAX_PEER_VERSION_LEN + 1);
+        assert_eq!(service.call(version_request(&too_long)).await, Ok(None));
+        assert_eq!(current_peer_version(), None);
+    }
+
+    #[test]
+    fn responses_tell_the_node_version() {
+        let res = add_version_header(Response::new(()));
+        let version = res.headers().get(NODE_VERSION_HEADER).unwrap();
+        assert_eq!(version, env!("SIMPLE_VERSION"));
+    }
+
+    #[test]
+    fn only_the_address_of_the_peer_is_dialed_back() {
+        let addr: SocketAddr = "192.0.2.1:49152".parse().unwrap();
+        let dial_back_request = |port: &str| {
+            let mut req = Request::new(());
+            req.extensions_mut().insert(addr);
+            req.headers_mut()
+                .insert(DIAL_BACK_HEADER, HeaderValue::from_str(port).unwrap());
+            req
+        };
+
+        assert_eq!(
+            dial_back_target(&dial_back_request("3000")),
+            Some("192.0.2.1:3000".parse().unwrap())
+        );
+        assert_eq!(dial_back_target(&dial_back_request("0")), None);
+        assert_eq!(dial_back_target(&dial_back_request("192.0.2.2:3000")), None);
+
+        let mut req = Request::new(());
+        req.extensions_mut().insert(addr);
+        assert_eq!(dial_back_target(&req), None);
+    }
+
+    #[test]
+    fn decode_errors_carry_their_code_in_the_details() {
+        let res = add_decode_error_details(status_response(
+            "invalid_hash_length:%20block%20id%20of%203%20bytes,%20expected%2032",
+        ));
+        let details = res.headers().get(STATUS_DETAILS_HEADER).unwrap();
+        let details = base64::decode_config(details, base64::STANDARD_NO_PAD).unwrap();
+        assert_eq!(details, b"invalid_hash_length");
+    }
+
+    #[test]
+    fn other_errors_have_no_details() {
+        let res = add_decode_error_details(status_response("request%20timed%20out"));
+        assert!(res.headers().get(STATUS_DETAILS_HEADER).is_none());
+
+        let res = add_decode_error_details(Response::new(()));
+        assert!(res.headers().get(STATUS_DETAILS_HEADER).is_none());
+    }
 }
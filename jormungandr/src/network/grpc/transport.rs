@@ -0,0 +1,185 @@
+//! the connections carrying the gRPC protocol
+//!
+//! The node connects to its peers and accepts their connections over TCP.
+//! The client and server state machines only need byte streams, so they can
+//! also be run over another transport, e.g. in memory in the tests or
+//! between the nodes of a cluster.
+
+use super::client::ConnectError;
+#[cfg(feature = "in-memory-transport")]
+use super::memory::{MemoryNetwork, MemoryStream};
+use crate::{network::keepalive_durations, utils::listen_socket};
+use futures::{future::BoxFuture, prelude::*, stream::BoxStream};
+use slog::Logger;
+use std::{
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpListener, TcpStream},
+};
+use tonic::transport::{server::Connected, Channel, Endpoint};
+
+pub trait Transport: Clone + Send + Sync + 'static {
+    /// a connection accepted by the server
+    type Stream: AsyncRead + AsyncWrite + Connected + Unpin + Send + 'static;
+    /// the connections accepted on a listen address
+    type Incoming: Stream<Item = io::Result<Self::Stream>> + Send + 'static;
+
+    /// open a channel to the endpoint
+    fn connect(&self, endpoint: Endpoint) -> BoxFuture<'static, Result<Channel, ConnectError>>;
+
+    /// start accepting connections on the address
+    fn listen(
+        &self,
+        addr: SocketAddr,
+        reuse_port: bool,
+        logger: &Logger,
+    ) -> io::Result<Self::Incoming>;
+}
+
+/// the transport of the node: TCP sockets
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Tcp;
+
+impl Transport for Tcp {
+    type Stream = TcpStream;
+    type Incoming = BoxStream<'static, io::Result<TcpStream>>;
+
+    fn connect(&self, endpoint: Endpoint) -> BoxFuture<'static, Result<Channel, ConnectError>> {
+        let endpoint = endpoint.tcp_keepalive(Some(keepalive_durations::TCP));
+        async move { endpoint.connect().await }.boxed()
+    }
+
+    fn listen(
+        &self,
+        addr: SocketAddr,
+        reuse_port: bool,
+        logger: &Logger,
+    ) -> io::Result<Self::Incoming> {
+        let listener = match listen_socket::inherited(listen_socket::P2P) {
+            Some(listener) => {
+                info!(logger, "using the socket passed by the service manager");
+                listener
+            }
+            None => listen_socket::bind(addr, reuse_port)?,
+        };
+        let incoming = TcpListener::from_std(listener)?.map_ok(|stream| {
+            // best effort, as done by the transport when binding the socket itself
+            let _ = stream.set_keepalive(Some(keepalive_durations::TCP));
+            stream
+        });
+        Ok(incoming.boxed())
+    }
+}
+
+/// the transport the node is configured with: TCP, unless the node runs in
+/// a cluster where the nodes share an in-memory network
+#[derive(Clone)]
+pub enum NodeTransport {
+    Tcp,
+    #[cfg(feature = "in-memory-transport")]
+    Memory(MemoryNetwork),
+}
+
+impl Default for NodeTransport {
+    fn default() -> Self {
+        NodeTransport::Tcp
+    }
+}
+
+impl Transport for NodeTransport {
+    type Stream = NodeStream;
+    type Incoming = BoxStream<'static, io::Result<NodeStream>>;
+
+    fn connect(&self, endpoint: Endpoint) -> BoxFuture<'static, Result<Channel, ConnectError>> {
+        match self {
+            NodeTransport::Tcp => Tcp.connect(endpoint),
+            #[cfg(feature = "in-memory-transport")]
+            NodeTransport::Memory(network) => Transport::connect(network, endpoint),
+        }
+    }
+
+    fn listen(
+        &self,
+        addr: SocketAddr,
+        reuse_port: bool,
+        logger: &Logger,
+    ) -> io::Result<Self::Incoming> {
+        match self {
+            NodeTransport::Tcp => {
+                let incoming = Tcp.listen(addr, reuse_port, logger)?;
+                Ok(incoming.map_ok(NodeStream::Tcp).boxed())
+            }
+            #[cfg(feature = "in-memory-transport")]
+            NodeTransport::Memory(network) => {
+                let incoming = network.bind(addr)?;
+                Ok(incoming.map_ok(NodeStream::Memory).boxed())
+            }
+        }
+    }
+}
+
+/// a connection accepted over the transport of the node
+pub enum NodeStream {
+    Tcp(TcpStream),
+    #[cfg(feature = "in-memory-transport")]
+    Memory(MemoryStream),
+}
+
+impl Connected for NodeStream {
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        match self {
+            NodeStream::Tcp(stream) => stream.remote_addr(),
+            #[cfg(feature = "in-memory-transport")]
+            NodeStream::Memory(stream) => stream.remote_addr(),
+        }
+    }
+}
+
+impl AsyncRead for NodeStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            NodeStream::Tcp(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(feature = "in-memory-transport")]
+            NodeStream::Memory(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for NodeStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            NodeStream::Tcp(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(feature = "in-memory-transport")]
+            NodeStream::Memory(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            NodeStream::Tcp(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(feature = "in-memory-transport")]
+            NodeStream::Memory(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            NodeStream::Tcp(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(feature = "in-memory-transport")]
+            NodeStream::Memory(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
@@ -9,8 +9,14 @@ pub mod bootstrap;
 mod client;
 mod convert;
 mod grpc;
+pub mod node_key;
 pub mod p2p;
+pub mod port_mapping;
+mod request_limit;
 mod service;
+#[cfg(test)]
+mod simulation;
+mod stream_limit;
 mod subscription;
 
 use self::convert::Encode;
@@ -22,6 +28,7 @@ use rand_chacha::ChaChaRng;
 use thiserror::Error;
 use tokio::time;
 use tokio_util::sync::CancellationToken;
+use tracing_futures::Instrument;
 
 // Constants
 
@@ -51,6 +58,30 @@ mod buffer_sizes {
     }
 }
 
+// Occupancy gauges of the buffers sized in `buffer_sizes`,
+// to tune the sizes with the data from running nodes.
+mod buffer_gauges {
+    pub mod inbound {
+        use super::super::buffer_sizes::inbound as sizes;
+        use crate::utils::buffer_gauge::BufferGauge;
+
+        pub static HEADERS: BufferGauge = BufferGauge::new(sizes::HEADERS);
+        pub static BLOCKS: BufferGauge = BufferGauge::new(sizes::BLOCKS);
+        pub static FRAGMENTS: BufferGauge = BufferGauge::new(sizes::FRAGMENTS);
+    }
+    pub mod outbound {
+        use super::super::buffer_sizes::outbound as sizes;
+        use super::super::p2p::comm::BUFFER_LEN;
+        use crate::utils::buffer_gauge::BufferGauge;
+
+        pub static HEADERS: BufferGauge = BufferGauge::new(sizes::HEADERS);
+        pub static BLOCKS: BufferGauge = BufferGauge::new(sizes::BLOCKS);
+        // the subscriptions subject to an overflow policy
+        pub static FRAGMENTS: BufferGauge = BufferGauge::new(BUFFER_LEN);
+        pub static GOSSIP: BufferGauge = BufferGauge::new(BUFFER_LEN);
+    }
+}
+
 mod concurrency_limits {
     // How many concurrent requests are permitted per client connection
     pub const CLIENT_REQUESTS: usize = 256;
@@ -73,8 +104,15 @@ mod security_params {
     pub const NONCE_LEN: usize = 32;
 }
 
+mod cache_sizes {
+    // The number of recently received gossip entries remembered to drop
+    // the same entries advertised again by other peers.
+    pub const GOSSIP: usize = 4096;
+}
+
 use self::client::ConnectError;
-use self::p2p::{comm::Peers, P2pTopology};
+use self::p2p::{comm::Peers, GossipCache, P2pTopology};
+use self::request_limit::{MethodLimits, RequestLimit};
 use crate::blockcfg::{Block, HeaderHash};
 use crate::blockchain::{Blockchain as NewBlockchain, Tip};
 use crate::intercom::{BlockMsg, ClientMsg, NetworkMsg, PropagateMsg, TransactionMsg};
@@ -82,14 +120,17 @@ use crate::log;
 use crate::settings::start::network::{Configuration, Peer, Protocol};
 use crate::utils::{
     async_msg::{MessageBox, MessageQueue},
+    buffer_gauge::BufferUsage,
     task::TokioServiceInfo,
 };
 use chain_network::data::gossip::Gossip;
-use chain_network::data::NodeKeyPair;
+use chain_network::data::{NodeId, NodeKeyPair};
+use jormungandr_lib::interfaces::{
+    FragmentOrigin, GossipSettings, NodeSelfView, PortMappingStatus,
+};
 use poldercast::StrikeReason;
 use rand::seq::SliceRandom;
 use slog::Logger;
-use tonic::transport;
 
 use std::collections::BTreeMap;
 use std::error;
@@ -99,11 +140,14 @@ use std::sync::Arc;
 use std::time::Duration;
 
 pub use self::bootstrap::Error as BootstrapError;
+#[cfg(feature = "in-memory-transport")]
+pub use self::grpc::memory::MemoryNetwork;
+pub use self::grpc::NodeTransport;
 use crate::stats_counter::StatsCounter;
 
 #[derive(Debug)]
 pub struct ListenError {
-    cause: transport::Error,
+    cause: Box<dyn error::Error + Send + Sync>,
     sockaddr: SocketAddr,
 }
 
@@ -119,7 +163,7 @@ impl fmt::Display for ListenError {
 
 impl error::Error for ListenError {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
-        Some(&self.cause)
+        Some(&*self.cause)
     }
 }
 
@@ -149,6 +193,11 @@ pub struct GlobalState {
     stats_counter: StatsCounter,
     topology: P2pTopology,
     peers: Peers,
+    gossip_cache: GossipCache,
+    server_requests: RequestLimit,
+    client_requests: RequestLimit,
+    method_limits: MethodLimits,
+    port_mapping: port_mapping::Status,
     keypair: NodeKeyPair,
     logger: Logger,
 }
@@ -160,16 +209,44 @@ impl GlobalState {
     pub fn new(
         block0_hash: HeaderHash,
         config: Configuration,
+        keypair: NodeKeyPair,
         stats_counter: StatsCounter,
         logger: Logger,
     ) -> Self {
-        let peers = Peers::new(config.max_connections, logger.clone());
-
         let mut rng_seed = [0; 32];
         rand::thread_rng().fill(&mut rng_seed);
-        let mut prng = ChaChaRng::from_seed(rng_seed);
+        Self::with_rng_seed(
+            block0_hash,
+            config,
+            keypair,
+            stats_counter,
+            logger,
+            rng_seed,
+        )
+    }
+
+    /// the network global state, with the random choices of the topology
+    /// drawn from a generator with the given seed
+    fn with_rng_seed(
+        block0_hash: HeaderHash,
+        config: Configuration,
+        keypair: NodeKeyPair,
+        stats_counter: StatsCounter,
+        logger: Logger,
+        rng_seed: [u8; 32],
+    ) -> Self {
+        let peers = Peers::new(
+            config.max_connections,
+            config.overflow_policies.clone(),
+            config.block_fetch_strategy,
+            &config.handshake_nonces,
+            logger.clone(),
+        );
+        let server_requests = RequestLimit::new(config.max_server_requests);
+        let client_requests = RequestLimit::new(config.max_client_requests);
+        let method_limits = MethodLimits::new(&config.method_limits);
 
-        let keypair = NodeKeyPair::generate(&mut prng);
+        let prng = ChaChaRng::from_seed(rng_seed);
 
         let topology = P2pTopology::new(
             &config,
@@ -183,6 +260,11 @@ impl GlobalState {
             stats_counter,
             topology,
             peers,
+            gossip_cache: GossipCache::new(cache_sizes::GOSSIP),
+            server_requests,
+            client_requests,
+            method_limits,
+            port_mapping: Default::default(),
             keypair,
             logger,
         }
@@ -192,6 +274,81 @@ impl GlobalState {
         &self.logger
     }
 
+    /// check the node id of an authenticated client peer against the
+    /// trusted node ids, if client authentication is enforced
+    pub fn is_trusted_node_id(&self, node_id: &NodeId) -> bool {
+        match &self.config.trusted_node_ids {
+            Some(trusted) => trusted
+                .iter()
+                .any(|id| id.as_ref().as_ref() == node_id.as_bytes()),
+            None => true,
+        }
+    }
+
+    /// number of requests from the peers currently being served
+    pub fn server_requests_in_flight(&self) -> usize {
+        self.server_requests.in_flight()
+    }
+
+    /// number of requests to the peers made by this node currently
+    /// in progress
+    pub fn client_requests_in_flight(&self) -> usize {
+        self.client_requests.in_flight()
+    }
+
+    /// counts of the block fetches from the peers chosen by the
+    /// configured strategy
+    pub fn block_fetch_stats(&self) -> p2p::comm::BlockFetchStats {
+        self.peers.block_fetch_stats()
+    }
+
+    /// the outcome of the mapping of the public port on the router, if
+    /// enabled
+    pub fn port_mapping_status(&self) -> Option<PortMappingStatus> {
+        self.port_mapping.get()
+    }
+
+    /// remove the mapping of the public port from the router, if any,
+    /// blocking until the router answers
+    pub fn remove_port_mapping(&self) -> Result<(), port_mapping::Error> {
+        match &self.config.port_mapping {
+            Some(settings) => port_mapping::remove(settings, &self.port_mapping),
+            None => Ok(()),
+        }
+    }
+
+    /// the gossip settings in effect
+    pub fn gossip_settings(&self) -> GossipSettings {
+        GossipSettings {
+            interval: self.config.gossip_interval.into(),
+            fanout: self.config.gossip_fanout,
+            max_unreachable_nodes_to_connect_per_event: self
+                .config
+                .max_unreachable_nodes_to_connect_per_event,
+            preferred_list_view_max: self.config.layers.preferred_list.view_max.into(),
+        }
+    }
+
+    /// what the node tells the network about itself
+    pub fn self_view(&self) -> NodeSelfView {
+        NodeSelfView {
+            node_id: self.config.profile.id().to_string(),
+            address: self.node_address().map(ToString::to_string),
+            topics_of_interest: self
+                .config
+                .topics_of_interest
+                .iter()
+                .map(|(topic, interest)| (topic.to_string(), interest.to_string()))
+                .collect(),
+            layers: self
+                .topology
+                .layers()
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
+
     pub fn node_address(&self) -> Option<&Address> {
         self.config.profile.address()
     }
@@ -264,6 +421,9 @@ impl ConnectionState {
     }
 }
 
+/// how often the records of the policy are saved
+const POLICY_RECORDS_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
 pub struct TaskParams {
     pub global_state: GlobalStateR,
     pub input: MessageQueue<NetworkMsg>,
@@ -282,11 +442,17 @@ pub async fn start(service_info: TokioServiceInfo, params: TaskParams) {
     let listen_state = global_state.clone();
     let listen_channels = channels.clone();
     let logger = service_info.logger();
-    let listener = async move {
-        if let Some(listen) = listen_state.config.listen() {
+    // the socket passed by the service manager, if any, is taken by the
+    // first listener
+    let listeners = listen_state.config.listeners().into_iter().map(|listen| {
+        let listen_state = listen_state.clone();
+        let listen_channels = listen_channels.clone();
+        let logger = logger.clone();
+        async move {
             match listen.protocol {
                 Protocol::Grpc => {
-                    grpc::run_listen_socket(&listen, listen_state, listen_channels)
+                    let transport = listen_state.config.transport.clone();
+                    grpc::serve(&transport, &listen, listen_state, listen_channels)
                         .await
                         .unwrap_or_else(|e| {
                             error!(
@@ -298,7 +464,8 @@ pub async fn start(service_info: TokioServiceInfo, params: TaskParams) {
                 Protocol::Ntt => unimplemented!(),
             }
         }
-    };
+    });
+    let listener = future::join_all(listeners);
 
     service_info.spawn(
         "gossip",
@@ -316,6 +483,37 @@ pub async fn start(service_info: TokioServiceInfo, params: TaskParams) {
         });
     }
 
+    if global_state.config.mempool_warmup {
+        service_info.spawn(
+            "mempool warm-up",
+            warm_up_mempool(
+                global_state.clone(),
+                channels.transaction_box.clone(),
+                logger.clone(),
+            ),
+        );
+    }
+
+    if let Some(settings) = global_state.config.port_mapping.clone() {
+        service_info.spawn(
+            "port mapping",
+            port_mapping::run(settings, global_state.clone()),
+        );
+    }
+
+    if let Some(path) = global_state.config.policy_records.clone() {
+        let save_state = global_state.clone();
+        service_info.run_periodic_fallible(
+            "save policy records",
+            POLICY_RECORDS_SAVE_INTERVAL,
+            move || {
+                let state = save_state.clone();
+                let path = path.clone();
+                async move { state.topology.save_policy_records(path).await }
+            },
+        );
+    }
+
     let gossip = time::interval(global_state.config.gossip_interval)
         .for_each(move |_| send_gossip(global_state.clone(), channels.clone()));
 
@@ -330,7 +528,17 @@ async fn handle_network_input(
     while let Some(msg) = input.next().await {
         match msg {
             NetworkMsg::Propagate(msg) => {
-                handle_propagation_msg(msg, state.clone(), channels.clone()).await;
+                let span = match &msg {
+                    PropagateMsg::Block(header) => {
+                        tracing::info_span!("propagate_block", hash = %header.hash())
+                    }
+                    PropagateMsg::Fragment(fragment) => {
+                        tracing::debug_span!("propagate_fragment", hash = %fragment.hash())
+                    }
+                };
+                handle_propagation_msg(msg, state.clone(), channels.clone())
+                    .instrument(span)
+                    .await;
             }
             NetworkMsg::GetBlocks(block_ids) => state.peers.fetch_blocks(block_ids.encode()).await,
             NetworkMsg::GetNextBlock(node_id, block_id) => {
@@ -361,6 +569,17 @@ async fn handle_propagation_msg(msg: PropagateMsg, state: GlobalStateR, channels
     let prop_state = state.clone();
     let propagate_res = match &msg {
         PropagateMsg::Block(header) => {
+            if let Some(max_size) = state.config.max_block_content_size {
+                if header.block_content_size() > max_size {
+                    debug!(
+                        state.logger(),
+                        "not announcing a block larger than the max content size";
+                        "hash" => %header.hash(),
+                        "size" => header.block_content_size(),
+                    );
+                    return;
+                }
+            }
             debug!(state.logger(), "block to propagate"; "hash" => %header.hash());
             let header = header.encode();
             let view = state
@@ -369,6 +588,7 @@ async fn handle_propagation_msg(msg: PropagateMsg, state: GlobalStateR, channels
                     topic: p2p::topic::BLOCKS,
                 })
                 .await;
+            tracing::debug!(peers = ?view.peers, "announcing block to peers");
             prop_state.peers.propagate_block(view.peers, header).await
         }
         PropagateMsg::Fragment(fragment) => {
@@ -452,7 +672,13 @@ async fn send_gossip(state: GlobalStateR, channels: Channels) {
     let topology = &state.topology;
     let logger = state.logger().new(o!(log::KEY_SUB_TASK => "send_gossip"));
     let view = topology.view(poldercast::Selection::Any).await;
-    let peers = view.peers;
+    let mut peers = view.peers;
+    if let Some(fanout) = state.config.gossip_fanout {
+        if peers.len() > fanout {
+            peers.shuffle(&mut rand::thread_rng());
+            peers.truncate(fanout);
+        }
+    }
     debug!(logger, "sending gossip to {} peers", peers.len());
     for address in peers {
         let state_prop = state.clone();
@@ -499,8 +725,9 @@ fn connect_and_propagate(
     let conn_logger = state.logger().new(o!("peer" => node.to_string()));
     let conn_state = ConnectionState::new(state.clone(), &peer, conn_logger.clone());
     info!(conn_logger, "connecting to peer");
-    let (handle, connecting) = client::connect(conn_state, channels);
+    let (handle, connecting) = client::connect(&state.config.transport, conn_state, channels);
     let spawn_state = state.clone();
+    let span = tracing::info_span!("peer_connection", peer = %node, direction = "outbound");
     let cf = async move {
         state
             .peers
@@ -543,12 +770,14 @@ fn connect_and_propagate(
                     "connected to peer";
                     "client_count" => state.client_count(),
                 );
+                tracing::debug!("client connection established");
                 client.await;
                 state.dec_client_count();
+                tracing::debug!("client connection closed");
             }
         }
     };
-    spawn_state.spawn(cf);
+    spawn_state.spawn(cf.instrument(span));
 }
 
 fn trusted_peers_shuffled(config: &Configuration) -> Vec<SocketAddr> {
@@ -562,6 +791,30 @@ fn trusted_peers_shuffled(config: &Configuration) -> Vec<SocketAddr> {
     peers
 }
 
+/// occupancy of the buffers of the network streams, by stream type
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamBuffersUsage {
+    pub inbound_headers: BufferUsage,
+    pub inbound_blocks: BufferUsage,
+    pub inbound_fragments: BufferUsage,
+    pub outbound_headers: BufferUsage,
+    pub outbound_blocks: BufferUsage,
+    pub outbound_fragments: BufferUsage,
+    pub outbound_gossip: BufferUsage,
+}
+
+pub fn stream_buffers_usage() -> StreamBuffersUsage {
+    StreamBuffersUsage {
+        inbound_headers: buffer_gauges::inbound::HEADERS.usage(),
+        inbound_blocks: buffer_gauges::inbound::BLOCKS.usage(),
+        inbound_fragments: buffer_gauges::inbound::FRAGMENTS.usage(),
+        outbound_headers: buffer_gauges::outbound::HEADERS.usage(),
+        outbound_blocks: buffer_gauges::outbound::BLOCKS.usage(),
+        outbound_fragments: buffer_gauges::outbound::FRAGMENTS.usage(),
+        outbound_gossip: buffer_gauges::outbound::GOSSIP.usage(),
+    }
+}
+
 #[derive(Clone)]
 pub struct BootstrapPeers(BTreeMap<String, Peer>);
 
@@ -622,16 +875,17 @@ async fn netboot_peers(config: &Configuration, logger: &Logger) -> BootstrapPeer
         for tpeer in trusted_peers {
             // let peer = Peer::new(peer, Protocol::Grpc);
             let tp_logger = logger.new(o!("peer_addr" => tpeer.address().to_string()));
-            let received_peers = bootstrap::peers_from_trusted_peer(&tpeer, tp_logger.clone())
-                .await
-                .unwrap_or_else(|e| {
-                    warn!(
-                        tp_logger,
-                        "failed to retrieve the list of bootstrap peers from trusted peer";
-                        "reason" => %e,
-                    );
-                    vec![tpeer]
-                });
+            let received_peers =
+                bootstrap::peers_from_trusted_peer(&config.transport, &tpeer, tp_logger.clone())
+                    .await
+                    .unwrap_or_else(|e| {
+                        warn!(
+                            tp_logger,
+                            "failed to retrieve the list of bootstrap peers from trusted peer";
+                            "reason" => %e,
+                        );
+                        vec![tpeer]
+                    });
             let added = peers.add_peers(&received_peers);
             info!(logger, "adding {} peers from peer", added);
 
@@ -643,6 +897,66 @@ async fn netboot_peers(config: &Configuration, logger: &Logger) -> BootstrapPeer
     peers
 }
 
+/// Fill the mempool with the fragments pending in the mempool of the first
+/// trusted peer to answer, so the node does not start producing blocks with
+/// nothing to put in them. The fragments are processed as if they were
+/// received from the peer, but not propagated: the peers of the node got
+/// them already.
+async fn warm_up_mempool(
+    state: GlobalStateR,
+    mut transaction_box: MessageBox<TransactionMsg>,
+    logger: Logger,
+) {
+    let mut trusted_peers = state
+        .config
+        .trusted_peers
+        .iter()
+        .filter_map(|tp| tp.address.to_socket_addr().map(Peer::new))
+        .collect::<Vec<_>>();
+    trusted_peers.shuffle(&mut rand::rngs::OsRng);
+
+    for peer in trusted_peers {
+        let logger = logger.new(o!("peer_addr" => peer.address().to_string()));
+        match bootstrap::pull_mempool(&state.config.transport, &peer, &logger).await {
+            Ok(fragments) => {
+                info!(
+                    logger,
+                    "warming up the mempool with {} fragments from trusted peer",
+                    fragments.len()
+                );
+                if fragments.is_empty() {
+                    return;
+                }
+                let origin = FragmentOrigin::Network {
+                    addr: Some(peer.address()),
+                };
+                transaction_box
+                    .send(TransactionMsg::WarmUpTransactions(origin, fragments))
+                    .await
+                    .unwrap_or_else(|e| {
+                        error!(
+                            logger,
+                            "cannot send the mempool snapshot to the fragment task";
+                            "reason" => %e,
+                        )
+                    });
+                return;
+            }
+            Err(e) => {
+                warn!(
+                    logger,
+                    "failed to get the mempool snapshot from trusted peer";
+                    "reason" => %e,
+                );
+            }
+        }
+    }
+    warn!(
+        logger,
+        "no trusted peer sent its mempool snapshot, the mempool starts empty"
+    );
+}
+
 pub async fn bootstrap(
     config: &Configuration,
     blockchain: NewBlockchain,
@@ -676,9 +990,20 @@ pub async fn bootstrap(
         Either::Right(((), _)) => return Err(bootstrap::Error::Interrupted),
     };
 
-    for peer in netboot_peers.randomly() {
+    let ranked_peers = match select(
+        bootstrap::probe_peers(&config.transport, netboot_peers.randomly(), logger).boxed(),
+        cancellation_token.cancelled().boxed(),
+    )
+    .await
+    {
+        Either::Left((peers, _)) => peers,
+        Either::Right(((), _)) => return Err(bootstrap::Error::Interrupted),
+    };
+
+    for peer in &ranked_peers {
         let logger = logger.new(o!("peer_addr" => peer.address().to_string()));
         let res = bootstrap::bootstrap_from_peer(
+            &config.transport,
             peer,
             blockchain.clone(),
             branch.clone(),
@@ -738,7 +1063,7 @@ pub async fn fetch_block(
     for address in trusted_peers_shuffled(&config) {
         let logger = logger.new(o!("peer_address" => address.to_string()));
         let peer = Peer::new(address);
-        match grpc::fetch_block(&peer, hash, &logger).await {
+        match grpc::fetch_block(&config.transport, &peer, hash, &logger).await {
             Err(grpc::FetchBlockError::Connect { source: e }) => {
                 warn!(logger, "unable to reach peer for block download"; "reason" => %e);
             }
@@ -0,0 +1,104 @@
+//! persistence of the key identifying the node in the p2p network
+//!
+//! The key is stored bech32-encoded in a file of the storage directory, so
+//! the node keeps the same identity across restarts and the peers can keep
+//! associating their reputation and quarantine records with it.
+
+use crate::utils::fs::write_atomically_with;
+use chain_crypto::Ed25519;
+use jormungandr_lib::crypto::key::SigningKey;
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+const NODE_KEY_FILE: &str = "node_key";
+
+/// path of the node key file in the given storage directory
+pub fn path<P: AsRef<Path>>(storage_dir: P) -> PathBuf {
+    storage_dir.as_ref().join(NODE_KEY_FILE)
+}
+
+/// generate a new node key
+pub fn generate() -> SigningKey<Ed25519> {
+    SigningKey::generate(rand::thread_rng())
+}
+
+/// load the node key from the given file, generating and saving a new one
+/// if the file does not exist yet.
+pub fn load_or_generate(path: &Path) -> io::Result<SigningKey<Ed25519>> {
+    if path.exists() {
+        load(path)
+    } else {
+        rotate(path)
+    }
+}
+
+/// generate a new node key and save it in the given file, replacing the
+/// previous one.
+pub fn rotate(path: &Path) -> io::Result<SigningKey<Ed25519>> {
+    let key = generate();
+    save(path, &key)?;
+    Ok(key)
+}
+
+fn load(path: &Path) -> io::Result<SigningKey<Ed25519>> {
+    let content = fs::read_to_string(path)?;
+    SigningKey::from_bech32_str(content.trim()).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid node key in {}: {}", path.display(), e),
+        )
+    })
+}
+
+fn save(path: &Path, key: &SigningKey<Ed25519>) -> io::Result<()> {
+    // an interrupted write must not leave the node without a valid identity
+    let mut options = OpenOptions::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    write_atomically_with(path, &options, |file| {
+        writeln!(file, "{}", key.to_bech32_str())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_key_is_kept_across_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = path(dir.path());
+        let generated = load_or_generate(&path).unwrap();
+        let loaded = load_or_generate(&path).unwrap();
+        assert_eq!(generated.identifier(), loaded.identifier());
+    }
+
+    #[test]
+    fn rotation_replaces_the_saved_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = path(dir.path());
+        let previous = load_or_generate(&path).unwrap();
+        let rotated = rotate(&path).unwrap();
+        assert_ne!(previous.identifier(), rotated.identifier());
+        assert_eq!(load(&path).unwrap().identifier(), rotated.identifier());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn the_key_file_is_only_readable_by_the_user() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = path(dir.path());
+        rotate(&path).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
@@ -1,29 +1,37 @@
+mod auth_nonce;
 mod peer_map;
+mod peer_selection;
 
+use auth_nonce::AuthNonces;
 use peer_map::{CommStatus, PeerMap};
+pub use peer_selection::PeerSelectionStrategy;
 
-use crate::network::{client::ConnectHandle, p2p::Address, security_params::NONCE_LEN};
+use crate::network::{
+    buffer_gauges, client::ConnectHandle, p2p::Address, security_params::NONCE_LEN,
+};
+use crate::settings::start::network::{HandshakeNonces, OverflowPolicies};
+use crate::utils::buffer_gauge::{BufferGauge, BufferTracker};
 use chain_network::data::block::{BlockEvent, ChainPullRequest};
 use chain_network::data::{BlockId, BlockIds, Fragment, Gossip, Header, NodeId};
-use futures::channel::mpsc;
 use futures::lock::{Mutex, MutexLockFuture};
 use futures::prelude::*;
 use futures::stream;
-use rand::Rng;
 use slog::Logger;
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet, VecDeque};
 use std::fmt;
 use std::mem;
 use std::net::SocketAddr;
 use std::pin::Pin;
-use std::task::{Context, Poll};
-use std::time::SystemTime;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant, SystemTime};
 
 // Buffer size determines the number of stream items pending processing that
 // can be buffered before back pressure is applied to the inbound half of
 // a gRPC subscription stream.
-const BUFFER_LEN: usize = 8;
+pub(in crate::network) const BUFFER_LEN: usize = 8;
 
 #[derive(Debug)]
 pub struct PropagateError<T> {
@@ -46,7 +54,6 @@ pub enum ErrorKind {
     NotSubscribed,
     SubscriptionClosed,
     StreamOverflow,
-    Unexpected,
 }
 
 impl fmt::Display for ErrorKind {
@@ -56,22 +63,147 @@ impl fmt::Display for ErrorKind {
             NotSubscribed => "not subscribed",
             SubscriptionClosed => "subscription has been closed",
             StreamOverflow => "too many items queued",
-            Unexpected => "unexpected error (should never occur?)",
         };
         f.write_str(msg)
     }
 }
 
+/// What to do with an item to send to a peer whose subscription buffer
+/// is full.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// fail the propagation, so the peer is disconnected
+    Disconnect,
+    /// drop the item
+    DropNewest,
+    /// drop the oldest item in the buffer to make room for the item
+    DropOldest,
+}
+
+/// The bounded buffer of an outbound subscription, shared by the handle
+/// producing the items and the stream sending them to the peer.
+struct Queue<T> {
+    items: VecDeque<T>,
+    /// the handle has been dropped or has subscribed again
+    sender_closed: bool,
+    /// the stream has been dropped
+    receiver_closed: bool,
+    waker: Option<Waker>,
+    tracker: Option<BufferTracker>,
+}
+
+type SharedQueue<T> = Arc<std::sync::Mutex<Queue<T>>>;
+
+impl<T> Queue<T> {
+    fn push(&mut self, item: T) {
+        self.items.push_back(item);
+        if let Some(tracker) = &self.tracker {
+            tracker.items_queued(1);
+        }
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        let item = self.items.pop_front()?;
+        if let Some(tracker) = &self.tracker {
+            tracker.items_taken(1);
+        }
+        Some(item)
+    }
+}
+
+/// The producing end of an outbound subscription.
+struct QueueSender<T>(SharedQueue<T>);
+
+impl<T> QueueSender<T> {
+    fn try_send(&mut self, item: T, policy: OverflowPolicy) -> Result<(), PropagateError<T>> {
+        let mut queue = self.0.lock().unwrap();
+        if queue.receiver_closed {
+            return Err(PropagateError {
+                kind: ErrorKind::SubscriptionClosed,
+                item,
+            });
+        }
+        if queue.items.len() >= BUFFER_LEN {
+            match policy {
+                OverflowPolicy::Disconnect => {
+                    return Err(PropagateError {
+                        kind: ErrorKind::StreamOverflow,
+                        item,
+                    })
+                }
+                OverflowPolicy::DropNewest => {
+                    if let Some(tracker) = &queue.tracker {
+                        tracker.item_dropped();
+                    }
+                    return Ok(());
+                }
+                OverflowPolicy::DropOldest => {
+                    queue.pop();
+                    if let Some(tracker) = &queue.tracker {
+                        tracker.item_dropped();
+                    }
+                }
+            }
+        }
+        queue.push(item);
+        Ok(())
+    }
+
+    /// Queues the item even if the buffer is full, for the items which
+    /// must reach the peer.
+    fn send_unbounded(&mut self, item: T) -> Result<(), PropagateError<T>> {
+        let mut queue = self.0.lock().unwrap();
+        if queue.receiver_closed {
+            return Err(PropagateError {
+                kind: ErrorKind::SubscriptionClosed,
+                item,
+            });
+        }
+        queue.push(item);
+        Ok(())
+    }
+}
+
+impl<T> Drop for QueueSender<T> {
+    fn drop(&mut self) {
+        let mut queue = self.0.lock().unwrap();
+        queue.sender_closed = true;
+        if let Some(waker) = queue.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
 /// Stream used as the outbound half of a subscription stream.
 pub struct OutboundSubscription<T> {
-    inner: mpsc::Receiver<T>,
+    inner: SharedQueue<T>,
 }
 
 impl<T> Stream for OutboundSubscription<T> {
     type Item = T;
 
-    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
-        Pin::new(&mut self.inner).poll_next(cx)
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut queue = self.inner.lock().unwrap();
+        match queue.pop() {
+            Some(item) => Poll::Ready(Some(item)),
+            None if queue.sender_closed => Poll::Ready(None),
+            None => {
+                queue.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> Drop for OutboundSubscription<T> {
+    fn drop(&mut self) {
+        let mut queue = self.inner.lock().unwrap();
+        queue.receiver_closed = true;
+        while queue.pop().is_some() {}
     }
 }
 
@@ -160,14 +292,30 @@ impl<T> CommHandle<T> {
     /// If this method is called again on the same handle,
     /// the previous subscription is closed and its stream is terminated.
     pub fn subscribe(&mut self) -> OutboundSubscription<T> {
+        self.subscribe_gauged(None)
+    }
+
+    /// Like `subscribe`, reporting the occupancy of the subscription buffer
+    /// to the gauge.
+    pub fn subscribe_gauged(
+        &mut self,
+        gauge: Option<&'static BufferGauge>,
+    ) -> OutboundSubscription<T> {
         use self::SubscriptionState::*;
 
-        let (mut tx, rx) = mpsc::channel(BUFFER_LEN);
+        let mut queue = Queue {
+            items: VecDeque::with_capacity(BUFFER_LEN),
+            sender_closed: false,
+            receiver_closed: false,
+            waker: None,
+            tracker: gauge.map(BufferGauge::track),
+        };
         if let Pending(item) = mem::replace(&mut self.state, NotSubscribed) {
-            tx.try_send(item).unwrap();
+            queue.push(item);
         }
-        self.state = Subscribed(tx);
-        OutboundSubscription { inner: rx }
+        let queue = Arc::new(std::sync::Mutex::new(queue));
+        self.state = Subscribed(QueueSender(queue.clone()));
+        OutboundSubscription { inner: queue }
     }
 
     pub fn is_subscribed(&self) -> bool {
@@ -186,6 +334,16 @@ impl<T> CommHandle<T> {
     // If the subscription is in the pending state with an item already waiting
     // to be sent, the new item replaces the previous pending item.
     pub fn try_send(&mut self, item: T) -> Result<(), PropagateError<T>> {
+        self.try_send_with_policy(item, OverflowPolicy::Disconnect)
+    }
+
+    // Like `try_send`, but the policy decides what happens when the stream
+    // buffer is full.
+    pub fn try_send_with_policy(
+        &mut self,
+        item: T,
+        policy: OverflowPolicy,
+    ) -> Result<(), PropagateError<T>> {
         match self.state {
             SubscriptionState::NotSubscribed => Err(PropagateError {
                 kind: ErrorKind::NotSubscribed,
@@ -195,24 +353,23 @@ impl<T> CommHandle<T> {
                 *pending = item;
                 Ok(())
             }
-            SubscriptionState::Subscribed(ref mut sender) => sender.try_send(item).map_err(|e| {
-                if e.is_disconnected() {
-                    PropagateError {
-                        kind: ErrorKind::SubscriptionClosed,
-                        item: e.into_inner(),
-                    }
-                } else if e.is_full() {
-                    PropagateError {
-                        kind: ErrorKind::StreamOverflow,
-                        item: e.into_inner(),
-                    }
-                } else {
-                    PropagateError {
-                        kind: ErrorKind::Unexpected,
-                        item: e.into_inner(),
-                    }
-                }
+            SubscriptionState::Subscribed(ref mut sender) => sender.try_send(item, policy),
+        }
+    }
+
+    // Like `try_send`, but the item is queued even if the stream buffer is
+    // full, so it is neither dropped nor makes the peer disconnect.
+    pub fn send_unbounded(&mut self, item: T) -> Result<(), PropagateError<T>> {
+        match self.state {
+            SubscriptionState::NotSubscribed => Err(PropagateError {
+                kind: ErrorKind::NotSubscribed,
+                item,
             }),
+            SubscriptionState::Pending(ref mut pending) => {
+                *pending = item;
+                Ok(())
+            }
+            SubscriptionState::Subscribed(ref mut sender) => sender.send_unbounded(item),
         }
     }
 }
@@ -220,13 +377,12 @@ impl<T> CommHandle<T> {
 enum SubscriptionState<T> {
     NotSubscribed,
     Pending(T),
-    Subscribed(mpsc::Sender<T>),
+    Subscribed(QueueSender<T>),
 }
 
 enum PeerAuth {
     None,
     Authenticated(NodeId),
-    ServerNonce([u8; NONCE_LEN]),
 }
 
 impl Default for PeerAuth {
@@ -249,6 +405,7 @@ pub struct PeerComms {
     fragments: CommHandle<Fragment>,
     gossip: CommHandle<Gossip>,
     auth: PeerAuth,
+    latency: Option<Duration>,
 }
 
 impl PeerComms {
@@ -269,22 +426,18 @@ impl PeerComms {
         }
     }
 
-    pub fn auth_nonce(&self) -> Option<[u8; NONCE_LEN]> {
-        match self.auth {
-            PeerAuth::ServerNonce(nonce) => Some(nonce),
-            _ => None,
-        }
+    pub fn set_node_id(&mut self, id: NodeId) {
+        self.auth = PeerAuth::Authenticated(id);
     }
 
-    pub fn generate_auth_nonce(&mut self) -> [u8; NONCE_LEN] {
-        let mut nonce = [0u8; NONCE_LEN];
-        rand::thread_rng().fill(&mut nonce[..]);
-        self.auth = PeerAuth::ServerNonce(nonce);
-        nonce
+    /// The round trip time of the handshake, known if the node
+    /// connected to the peer.
+    pub fn latency(&self) -> Option<Duration> {
+        self.latency
     }
 
-    pub fn set_node_id(&mut self, id: NodeId) {
-        self.auth = PeerAuth::Authenticated(id);
+    pub fn set_latency(&mut self, latency: Duration) {
+        self.latency = Some(latency);
     }
 
     pub fn update(&mut self, newer: PeerComms) {
@@ -297,6 +450,9 @@ impl PeerComms {
         self.block_solicitations.update(newer.block_solicitations);
         self.chain_pulls.update(newer.chain_pulls);
         self.auth = newer.auth;
+        if newer.latency.is_some() {
+            self.latency = newer.latency;
+        }
     }
 
     pub fn clear_pending(&mut self) {
@@ -319,22 +475,32 @@ impl PeerComms {
         self.gossip = CommHandle::client_pending(gossip);
     }
 
+    /// Block announcements are never dropped, nor make a slow peer
+    /// disconnect: the buffer grows past its size instead. There is at most
+    /// an announcement per block, so the buffer grows slowly, and a peer
+    /// not reading its subscription at all is disconnected by the timeout
+    /// of the connection.
     pub fn try_send_block_announcement(
         &mut self,
         header: Header,
     ) -> Result<(), PropagateError<Header>> {
-        self.block_announcements.try_send(header)
+        self.block_announcements.send_unbounded(header)
     }
 
     pub fn try_send_fragment(
         &mut self,
         fragment: Fragment,
+        policy: OverflowPolicy,
     ) -> Result<(), PropagateError<Fragment>> {
-        self.fragments.try_send(fragment)
+        self.fragments.try_send_with_policy(fragment, policy)
     }
 
-    pub fn try_send_gossip(&mut self, gossip: Gossip) -> Result<(), PropagateError<Gossip>> {
-        self.gossip.try_send(gossip)
+    pub fn try_send_gossip(
+        &mut self,
+        gossip: Gossip,
+        policy: OverflowPolicy,
+    ) -> Result<(), PropagateError<Gossip>> {
+        self.gossip.try_send_with_policy(gossip, policy)
     }
 
     pub fn subscribe_to_block_announcements(&mut self) -> OutboundSubscription<Header> {
@@ -367,11 +533,13 @@ impl PeerComms {
     }
 
     pub fn subscribe_to_fragments(&mut self) -> FragmentSubscription {
-        self.fragments.subscribe()
+        self.fragments
+            .subscribe_gauged(Some(&buffer_gauges::outbound::FRAGMENTS))
     }
 
     pub fn subscribe_to_gossip(&mut self) -> GossipSubscription {
-        self.gossip.subscribe()
+        self.gossip
+            .subscribe_gauged(Some(&buffer_gauges::outbound::GOSSIP))
     }
 
     pub fn block_announcements_subscribed(&self) -> bool {
@@ -407,6 +575,8 @@ pub struct PeerStats {
     last_block_received: Option<SystemTime>,
     last_fragment_received: Option<SystemTime>,
     last_gossip_received: Option<SystemTime>,
+    version: Option<String>,
+    decode_errors: BTreeMap<&'static str, u64>,
 }
 
 impl Default for PeerStats {
@@ -416,6 +586,8 @@ impl Default for PeerStats {
             last_block_received: None,
             last_fragment_received: None,
             last_gossip_received: None,
+            version: None,
+            decode_errors: BTreeMap::new(),
         }
     }
 }
@@ -433,6 +605,25 @@ impl PeerStats {
         self.last_gossip_received
     }
 
+    /// The version string the peer has reported, if known.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    fn set_version(&mut self, version: String) {
+        self.version = Some(version);
+    }
+
+    /// The number of items received from the peer which could not be
+    /// decoded, by error code.
+    pub fn decode_errors(&self) -> &BTreeMap<&'static str, u64> {
+        &self.decode_errors
+    }
+
+    fn record_decode_error(&mut self, code: &'static str) {
+        *self.decode_errors.entry(code).or_insert(0) += 1;
+    }
+
     fn update_last_block_received(&mut self, timestamp: SystemTime) {
         update_last_timestamp(&mut self.last_block_received, timestamp)
     }
@@ -481,23 +672,66 @@ pub struct PeerInfo {
     pub stats: PeerStats,
 }
 
+/// Counts of the block fetches from the peers chosen by the
+/// selection strategy.
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockFetchStats {
+    pub strategy: PeerSelectionStrategy,
+    /// number of block fetches sent to a peer
+    pub requests: u64,
+    /// number of block fetches with no peer to send them to
+    pub no_peer: u64,
+    /// number of block fetches which could not be sent to the chosen peer
+    pub failed: u64,
+}
+
 /// The collection of currently connected peer nodes.
 ///
 /// This object uses internal locking and is shared between
 /// all network connection tasks.
 pub struct Peers {
     mutex: Mutex<PeerMap>,
+    auth_nonces: Mutex<AuthNonces>,
+    overflow_policies: OverflowPolicies,
+    block_fetch_strategy: PeerSelectionStrategy,
+    block_fetch_requests: AtomicU64,
+    block_fetch_no_peer: AtomicU64,
+    block_fetch_failed: AtomicU64,
     logger: Logger,
 }
 
 impl Peers {
-    pub fn new(capacity: usize, logger: Logger) -> Self {
+    pub fn new(
+        capacity: usize,
+        overflow_policies: OverflowPolicies,
+        block_fetch_strategy: PeerSelectionStrategy,
+        handshake_nonces: &HandshakeNonces,
+        logger: Logger,
+    ) -> Self {
         Peers {
-            mutex: Mutex::new(PeerMap::new(capacity)),
+            mutex: Mutex::new(PeerMap::new(capacity, block_fetch_strategy)),
+            auth_nonces: Mutex::new(AuthNonces::new(
+                handshake_nonces.max_pending,
+                handshake_nonces.expiry,
+            )),
+            overflow_policies,
+            block_fetch_strategy,
+            block_fetch_requests: AtomicU64::new(0),
+            block_fetch_no_peer: AtomicU64::new(0),
+            block_fetch_failed: AtomicU64::new(0),
             logger,
         }
     }
 
+    pub fn block_fetch_stats(&self) -> BlockFetchStats {
+        BlockFetchStats {
+            strategy: self.block_fetch_strategy,
+            requests: self.block_fetch_requests.load(Ordering::Relaxed),
+            no_peer: self.block_fetch_no_peer.load(Ordering::Relaxed),
+            failed: self.block_fetch_failed.load(Ordering::Relaxed),
+        }
+    }
+
     fn inner(&self) -> MutexLockFuture<PeerMap> {
         self.mutex.lock()
     }
@@ -535,16 +769,24 @@ impl Peers {
         map.remove_peer(peer)
     }
 
+    /// Generates the nonce sent to the peer in the handshake.
     pub async fn generate_auth_nonce(&self, peer: Address) -> [u8; NONCE_LEN] {
-        let mut map = self.inner().await;
-        let comms = map.server_comms(peer);
-        comms.generate_auth_nonce()
+        let mut nonces = self.auth_nonces.lock().await;
+        nonces.generate(peer, Instant::now())
     }
 
-    pub async fn get_auth_nonce(&self, peer: Address) -> Option<[u8; NONCE_LEN]> {
+    /// Takes the nonce sent to the peer in the handshake, for the peer to
+    /// authenticate with. The nonce cannot be used again, and is `None`
+    /// if it has expired.
+    pub async fn take_auth_nonce(&self, peer: Address) -> Option<[u8; NONCE_LEN]> {
+        let mut nonces = self.auth_nonces.lock().await;
+        nonces.take(&peer, Instant::now())
+    }
+
+    pub async fn client_node_id(&self, peer: Address) -> Option<NodeId> {
         let mut map = self.inner().await;
         let comms = map.server_comms(peer);
-        comms.auth_nonce()
+        comms.node_id()
     }
 
     pub async fn set_node_id(&self, peer: Address, id: NodeId) {
@@ -552,7 +794,7 @@ impl Peers {
             self.logger,
             "authenticated client peer node";
             "peer" => %peer,
-            "node_id" => ?id,
+            "node_id" => hex::encode(id.as_bytes()),
         );
         let mut map = self.inner().await;
         let comms = map.server_comms(peer);
@@ -656,8 +898,9 @@ impl Peers {
         fragment: Fragment,
     ) -> Result<(), Vec<Address>> {
         debug!(self.logger, "propagating fragment to {:?}", nodes);
+        let policy = self.overflow_policies.fragments;
         self.propagate_with(nodes, move |status| match status {
-            CommStatus::Established(comms) => comms.try_send_fragment(fragment.clone()),
+            CommStatus::Established(comms) => comms.try_send_fragment(fragment.clone(), policy),
             CommStatus::Connecting(comms) => {
                 comms.set_pending_fragment(fragment.clone());
                 Ok(())
@@ -675,7 +918,9 @@ impl Peers {
         let mut map = self.inner().await;
         if let Some(mut entry) = map.entry(target) {
             let res = match entry.update_comm_status() {
-                CommStatus::Established(comms) => comms.try_send_gossip(gossip),
+                CommStatus::Established(comms) => {
+                    comms.try_send_gossip(gossip, self.overflow_policies.gossip)
+                }
                 CommStatus::Connecting(comms) => {
                     comms.set_pending_gossip(gossip);
                     Ok(())
@@ -732,20 +977,48 @@ impl Peers {
         }
     }
 
+    /// Records the version string the peer tells in the headers of the
+    /// protocol.
+    pub async fn set_peer_version(&self, peer: Address, version: String) -> bool {
+        let mut map = self.inner().await;
+        match map.peer_stats_mut(&peer) {
+            Some(stats) => {
+                stats.set_version(version);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Counts an item received from the peer which could not be decoded.
+    pub async fn record_decode_error(&self, peer: Address, code: &'static str) -> bool {
+        let mut map = self.inner().await;
+        match map.peer_stats_mut(&peer) {
+            Some(stats) => {
+                stats.record_decode_error(code);
+                true
+            }
+            None => false,
+        }
+    }
+
     pub async fn fetch_blocks(&self, hashes: BlockIds) {
         let mut map = self.inner().await;
         if let Some((node_id, comms)) = map.next_peer_for_block_fetch() {
             debug!(self.logger, "fetching blocks from {}", node_id);
+            self.block_fetch_requests.fetch_add(1, Ordering::Relaxed);
             comms
                 .block_solicitations
                 .try_send(hashes)
                 .unwrap_or_else(|e| {
                     debug!(self.logger, "block fetch from {} failed: {:?}", node_id, e);
                     debug!(self.logger, "unsubscribing peer {}", node_id);
+                    self.block_fetch_failed.fetch_add(1, Ordering::Relaxed);
                     map.remove_peer(node_id);
                 });
         } else {
             warn!(self.logger, "no peers to fetch blocks from");
+            self.block_fetch_no_peer.fetch_add(1, Ordering::Relaxed);
         }
     }
 
@@ -815,3 +1088,136 @@ impl Peers {
         map.infos()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    fn subscribed() -> (CommHandle<u32>, OutboundSubscription<u32>) {
+        let mut handle = CommHandle::default();
+        let subscription = handle.subscribe();
+        (handle, subscription)
+    }
+
+    /// the items ready in the subscription, without waiting for more
+    fn ready_items(subscription: &mut OutboundSubscription<u32>) -> Vec<u32> {
+        let mut items = Vec::new();
+        while let Some(Some(item)) = subscription.next().now_or_never() {
+            items.push(item);
+        }
+        items
+    }
+
+    fn fill(handle: &mut CommHandle<u32>, policy: OverflowPolicy) {
+        for item in 0..BUFFER_LEN as u32 {
+            handle.try_send_with_policy(item, policy).unwrap();
+        }
+    }
+
+    #[test]
+    fn items_are_sent_in_order() {
+        let (mut handle, mut subscription) = subscribed();
+        handle.try_send(1).unwrap();
+        handle.try_send(2).unwrap();
+        assert_eq!(ready_items(&mut subscription), vec![1, 2]);
+    }
+
+    #[test]
+    fn pending_item_is_sent_on_subscription() {
+        let mut handle = CommHandle::client_pending(1);
+        handle.try_send(2).unwrap();
+        let mut subscription = handle.subscribe();
+        assert_eq!(ready_items(&mut subscription), vec![2]);
+    }
+
+    #[test]
+    fn overflow_disconnects_the_peer() {
+        let (mut handle, mut subscription) = subscribed();
+        fill(&mut handle, OverflowPolicy::Disconnect);
+        let e = handle
+            .try_send_with_policy(100, OverflowPolicy::Disconnect)
+            .unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::StreamOverflow);
+        assert_eq!(e.into_item(), 100);
+        assert_eq!(ready_items(&mut subscription).len(), BUFFER_LEN);
+    }
+
+    #[test]
+    fn overflow_drops_the_newest_item() {
+        let (mut handle, mut subscription) = subscribed();
+        fill(&mut handle, OverflowPolicy::DropNewest);
+        handle
+            .try_send_with_policy(100, OverflowPolicy::DropNewest)
+            .unwrap();
+        let expected: Vec<u32> = (0..BUFFER_LEN as u32).collect();
+        assert_eq!(ready_items(&mut subscription), expected);
+    }
+
+    #[test]
+    fn overflow_drops_the_oldest_item() {
+        let (mut handle, mut subscription) = subscribed();
+        fill(&mut handle, OverflowPolicy::DropOldest);
+        handle
+            .try_send_with_policy(100, OverflowPolicy::DropOldest)
+            .unwrap();
+        let mut expected: Vec<u32> = (1..BUFFER_LEN as u32).collect();
+        expected.push(100);
+        assert_eq!(ready_items(&mut subscription), expected);
+    }
+
+    #[test]
+    fn unbounded_items_are_never_dropped() {
+        let (mut handle, mut subscription) = subscribed();
+        let count = 3 * BUFFER_LEN as u32;
+        for item in 0..count {
+            handle.send_unbounded(item).unwrap();
+        }
+        let expected: Vec<u32> = (0..count).collect();
+        assert_eq!(ready_items(&mut subscription), expected);
+    }
+
+    #[test]
+    fn sending_fails_once_the_stream_is_dropped() {
+        let (mut handle, subscription) = subscribed();
+        drop(subscription);
+        assert_eq!(
+            handle.try_send(1).unwrap_err().kind(),
+            ErrorKind::SubscriptionClosed
+        );
+        assert_eq!(
+            handle.send_unbounded(1).unwrap_err().kind(),
+            ErrorKind::SubscriptionClosed
+        );
+    }
+
+    #[test]
+    fn stream_ends_when_subscribing_again() {
+        let (mut handle, mut subscription) = subscribed();
+        handle.try_send(1).unwrap();
+        let mut newer = handle.subscribe();
+        // the items queued before are still delivered
+        assert_eq!(block_on(subscription.next()), Some(1));
+        assert_eq!(block_on(subscription.next()), None);
+        handle.try_send(2).unwrap();
+        assert_eq!(ready_items(&mut newer), vec![2]);
+    }
+
+    #[test]
+    fn stream_is_woken_by_new_items() {
+        let (mut handle, mut subscription) = subscribed();
+        let next = std::thread::spawn(move || block_on(subscription.next()));
+        std::thread::sleep(Duration::from_millis(10));
+        handle.try_send(1).unwrap();
+        assert_eq!(next.join().unwrap(), Some(1));
+    }
+
+    #[test]
+    fn not_subscribed_handle_refuses_items() {
+        let mut handle = CommHandle::<u32>::default();
+        assert_eq!(
+            handle.try_send(1).unwrap_err().kind(),
+            ErrorKind::NotSubscribed
+        );
+    }
+}
@@ -0,0 +1,91 @@
+//! the nonces sent to the peers in the handshake
+//!
+//! A peer authenticating as a client signs the nonce the node sent it in
+//! the handshake. The nonces are kept apart from the peer map, so that the
+//! handshakes which are never followed by an authentication do not take
+//! the place of the connected peers. A nonce can be used once, and only
+//! until it expires; the oldest nonces are dropped when the table is full.
+
+use crate::network::{p2p::Address, security_params::NONCE_LEN};
+use lru::LruCache;
+use rand::Rng;
+use std::time::{Duration, Instant};
+
+struct PendingNonce {
+    nonce: [u8; NONCE_LEN],
+    expires: Instant,
+}
+
+pub struct AuthNonces {
+    pending: LruCache<Address, PendingNonce>,
+    expiry: Duration,
+}
+
+impl AuthNonces {
+    pub fn new(max_pending: usize, expiry: Duration) -> Self {
+        AuthNonces {
+            pending: LruCache::new(max_pending),
+            expiry,
+        }
+    }
+
+    /// generate the nonce for the peer, replacing the pending one if any
+    pub fn generate(&mut self, peer: Address, now: Instant) -> [u8; NONCE_LEN] {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill(&mut nonce[..]);
+        self.pending.put(
+            peer,
+            PendingNonce {
+                nonce,
+                expires: now + self.expiry,
+            },
+        );
+        nonce
+    }
+
+    /// take the pending nonce of the peer, if it has not expired
+    pub fn take(&mut self, peer: &Address, now: Instant) -> Option<[u8; NONCE_LEN]> {
+        self.pending
+            .pop(peer)
+            .filter(|pending| now < pending.expires)
+            .map(|pending| pending.nonce)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(port: u16) -> Address {
+        Address::tcp(([127, 0, 0, 1], port).into())
+    }
+
+    #[test]
+    fn nonces_are_used_once() {
+        let mut nonces = AuthNonces::new(4, Duration::from_secs(60));
+        let now = Instant::now();
+        let nonce = nonces.generate(peer(1), now);
+        assert_eq!(nonces.take(&peer(1), now), Some(nonce));
+        assert_eq!(nonces.take(&peer(1), now), None);
+    }
+
+    #[test]
+    fn nonces_expire() {
+        let mut nonces = AuthNonces::new(4, Duration::from_secs(60));
+        let now = Instant::now();
+        nonces.generate(peer(1), now);
+        assert_eq!(nonces.take(&peer(1), now + Duration::from_secs(60)), None);
+    }
+
+    #[test]
+    fn oldest_nonces_are_dropped() {
+        let mut nonces = AuthNonces::new(2, Duration::from_secs(60));
+        let now = Instant::now();
+        nonces.generate(peer(1), now);
+        nonces.generate(peer(2), now);
+        nonces.generate(peer(3), now);
+        assert_eq!(nonces.take(&peer(1), now), None);
+        assert!(nonces.take(&peer(2), now).is_some());
+        assert!(nonces.take(&peer(3), now).is_some());
+    }
+}
@@ -1,7 +1,10 @@
 use crate::network::{
     client::ConnectHandle,
     p2p::{
-        comm::{PeerComms, PeerInfo, PeerStats},
+        comm::{
+            peer_selection::{Candidate, PeerSelectionStrategy, SelectPeer},
+            PeerComms, PeerInfo, PeerStats,
+        },
         Address,
     },
 };
@@ -11,6 +14,7 @@ use linked_hash_map::LinkedHashMap;
 pub struct PeerMap {
     map: LinkedHashMap<Address, PeerData>,
     capacity: usize,
+    block_fetch_selector: Box<dyn SelectPeer>,
 }
 
 #[derive(Default)]
@@ -72,10 +76,11 @@ impl<'a> CommStatus<'a> {
 }
 
 impl PeerMap {
-    pub fn new(capacity: usize) -> Self {
+    pub fn new(capacity: usize, block_fetch_strategy: PeerSelectionStrategy) -> Self {
         PeerMap {
             map: LinkedHashMap::new(),
             capacity,
+            block_fetch_selector: block_fetch_strategy.selector(),
         }
     }
 
@@ -97,6 +102,11 @@ impl PeerMap {
         self.map.get_refresh(&id).map(|data| &mut data.stats)
     }
 
+    /// the statistics of the peer, without counting it as active
+    pub fn peer_stats_mut(&mut self, id: &Address) -> Option<&mut PeerStats> {
+        self.map.get_mut(id).map(|data| &mut data.stats)
+    }
+
     pub fn peer_comms(&mut self, id: &Address) -> Option<&mut PeerComms> {
         self.map
             .get_mut(id)
@@ -130,14 +140,23 @@ impl PeerMap {
     }
 
     pub fn next_peer_for_block_fetch(&mut self) -> Option<(Address, &mut PeerComms)> {
-        let mut iter = self.map.iter_mut();
-        while let Some((id, data)) = iter.next_back() {
+        let mut addresses = Vec::new();
+        let mut candidates = Vec::new();
+        for (id, data) in self.map.iter_mut() {
             match data.update_comm_status() {
-                CommStatus::Established(comms) => return Some((id.clone(), comms)),
+                CommStatus::Established(comms) => {
+                    addresses.push(id.clone());
+                    candidates.push(Candidate {
+                        latency: comms.latency(),
+                    });
+                }
                 CommStatus::Connecting(_) => {}
             }
         }
-        None
+        let index = self.block_fetch_selector.select(&candidates)?;
+        let id = addresses.swap_remove(index);
+        let comms = &mut self.map.get_mut(&id)?.comms;
+        Some((id, comms))
     }
 
     pub fn infos(&self) -> Vec<PeerInfo> {
@@ -0,0 +1,154 @@
+//! choice of the peer to solicit the missing blocks from
+//!
+//! The chain pulls and the solicitations of specific blocks go to the peer
+//! which announced them, but the blocks the node finds missing on its own are
+//! solicited from one of the connected peers, chosen by the configured
+//! strategy. The peers are not tied to the stake pools in the p2p protocol,
+//! so there is no strategy weighted by stake.
+
+use rand::Rng;
+use std::time::Duration;
+
+/// How to choose the peer to solicit the missing blocks from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PeerSelectionStrategy {
+    /// the peer which most recently announced a block or sent a fragment
+    /// or gossip
+    MostRecent,
+    /// a peer chosen at random
+    Random,
+    /// each peer in turn
+    RoundRobin,
+    /// the peer which answered the handshake of the node the fastest,
+    /// the peers which connected to the node coming last
+    Latency,
+}
+
+/// a peer the blocks can be solicited from
+pub struct Candidate {
+    /// the round trip time of the handshake, if the node connected to
+    /// the peer
+    pub latency: Option<Duration>,
+}
+
+/// The interface of the selection strategies.
+pub trait SelectPeer: Send {
+    /// choose the index of the peer to solicit the blocks from, the
+    /// candidates being listed from the least to the most recently active
+    fn select(&mut self, candidates: &[Candidate]) -> Option<usize>;
+}
+
+impl PeerSelectionStrategy {
+    pub fn selector(self) -> Box<dyn SelectPeer> {
+        match self {
+            PeerSelectionStrategy::MostRecent => Box::new(MostRecent),
+            PeerSelectionStrategy::Random => Box::new(Random),
+            PeerSelectionStrategy::RoundRobin => Box::new(RoundRobin { next: 0 }),
+            PeerSelectionStrategy::Latency => Box::new(Latency),
+        }
+    }
+}
+
+struct MostRecent;
+
+impl SelectPeer for MostRecent {
+    fn select(&mut self, candidates: &[Candidate]) -> Option<usize> {
+        candidates.len().checked_sub(1)
+    }
+}
+
+struct Random;
+
+impl SelectPeer for Random {
+    fn select(&mut self, candidates: &[Candidate]) -> Option<usize> {
+        if candidates.is_empty() {
+            return None;
+        }
+        Some(rand::thread_rng().gen_range(0, candidates.len()))
+    }
+}
+
+struct RoundRobin {
+    next: usize,
+}
+
+impl SelectPeer for RoundRobin {
+    fn select(&mut self, candidates: &[Candidate]) -> Option<usize> {
+        if candidates.is_empty() {
+            return None;
+        }
+        let index = self.next % candidates.len();
+        self.next = self.next.wrapping_add(1);
+        Some(index)
+    }
+}
+
+struct Latency;
+
+impl SelectPeer for Latency {
+    fn select(&mut self, candidates: &[Candidate]) -> Option<usize> {
+        // the most recently active peer among the fastest ones
+        candidates
+            .iter()
+            .enumerate()
+            .rev()
+            .min_by_key(|(_, candidate)| (candidate.latency.is_none(), candidate.latency))
+            .map(|(index, _)| index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn select(
+        strategy: PeerSelectionStrategy,
+        latencies: &[Option<u64>],
+        rounds: usize,
+    ) -> Vec<usize> {
+        let candidates: Vec<Candidate> = latencies
+            .iter()
+            .map(|latency| Candidate {
+                latency: latency.map(Duration::from_millis),
+            })
+            .collect();
+        let mut selector = strategy.selector();
+        (0..rounds)
+            .map(|_| selector.select(&candidates).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn strategies_choose_as_documented() {
+        let latencies = [Some(30), None, Some(10), Some(10)];
+        assert_eq!(
+            select(PeerSelectionStrategy::MostRecent, &latencies, 2),
+            [3, 3]
+        );
+        assert_eq!(
+            select(PeerSelectionStrategy::RoundRobin, &latencies, 5),
+            [0, 1, 2, 3, 0]
+        );
+        assert_eq!(select(PeerSelectionStrategy::Latency, &latencies, 1), [3]);
+        assert_eq!(
+            select(PeerSelectionStrategy::Latency, &[None, None], 1),
+            [1]
+        );
+        assert!(select(PeerSelectionStrategy::Random, &latencies, 8)
+            .iter()
+            .all(|&index| index < latencies.len()));
+    }
+
+    #[test]
+    fn no_candidate_no_choice() {
+        for strategy in &[
+            PeerSelectionStrategy::MostRecent,
+            PeerSelectionStrategy::Random,
+            PeerSelectionStrategy::RoundRobin,
+            PeerSelectionStrategy::Latency,
+        ] {
+            assert_eq!(strategy.selector().select(&[]), None);
+        }
+    }
+}
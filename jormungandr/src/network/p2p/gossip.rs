@@ -4,10 +4,12 @@ use crate::network::convert::Encode;
 use chain_core::property;
 use chain_network::data as net_data;
 use serde::{Deserialize, Serialize};
-use std::net::{IpAddr, Ipv4Addr};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
 use bincode::Options;
+use lru::LruCache;
 pub use net_data::{Peer, Peers};
+use std::sync::Mutex;
 
 #[derive(Clone, Debug, Serialize, Deserialize, Hash, PartialEq, Eq)]
 pub struct Gossip(poldercast::NodeProfile);
@@ -22,85 +24,110 @@ impl Gossip {
     }
 
     pub fn has_valid_address(&self) -> bool {
-        let addr = match self.address().and_then(|addr| addr.to_socket_addr()) {
-            None => return false,
-            Some(addr) => addr,
-        };
-
-        match addr.ip() {
-            IpAddr::V4(ip) => {
-                if ip.is_unspecified() {
-                    return false;
-                }
-                if ip.is_broadcast() {
-                    return false;
-                }
-                if ip.is_multicast() {
-                    return false;
-                }
-                if ip.is_documentation() {
-                    return false;
-                }
-            }
-            IpAddr::V6(ip) => {
-                if ip.is_unspecified() {
-                    return false;
-                }
-                if ip.is_multicast() {
-                    return false;
-                }
-            }
-        }
-
-        true
+        self.address()
+            .and_then(|addr| addr.to_socket_addr())
+            .map_or(false, |addr| is_valid_address(&addr))
     }
 
     /// Check if the bind address is a global address
-    /// Note: This method relies on IPV4 checks even for IPV6 addresses. If the IPV6 address
-    /// can not be transformed into a IPV4 one then the private and link_local checks are not performed on it.
     pub fn is_global(&self) -> bool {
-        if !self.has_valid_address() {
-            return false;
-        }
-
-        let addr = match self.address().and_then(|addr| addr.to_socket_addr()) {
-            None => return false,
-            Some(addr) => addr,
-        };
+        self.address()
+            .and_then(|addr| addr.to_socket_addr())
+            .map_or(false, |addr| is_global_address(&addr))
+    }
+}
 
-        fn is_ipv4_global(ip: Ipv4Addr) -> bool {
-            if ip.is_private() {
+/// Check if the address can be connected to
+pub fn is_valid_address(addr: &SocketAddr) -> bool {
+    match addr.ip() {
+        IpAddr::V4(ip) => {
+            if ip.is_unspecified() {
                 return false;
             }
-            if ip.is_loopback() {
+            if ip.is_broadcast() {
                 return false;
             }
-            if ip.is_link_local() {
+            if ip.is_multicast() {
+                return false;
+            }
+            if ip.is_documentation() {
                 return false;
             }
-            true
         }
+        IpAddr::V6(ip) => {
+            if ip.is_unspecified() {
+                return false;
+            }
+            if ip.is_multicast() {
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+/// Check if the address is a valid global address
+/// Note: This method relies on IPV4 checks even for IPV6 addresses. If the IPV6 address
+/// can not be transformed into a IPV4 one then the private and link_local checks are not performed on it.
+pub fn is_global_address(addr: &SocketAddr) -> bool {
+    if !is_valid_address(addr) {
+        return false;
+    }
+
+    fn is_ipv4_global(ip: Ipv4Addr) -> bool {
+        if ip.is_private() {
+            return false;
+        }
+        if ip.is_loopback() {
+            return false;
+        }
+        if ip.is_link_local() {
+            return false;
+        }
+        true
+    }
 
-        match addr.ip() {
-            IpAddr::V4(ip) => is_ipv4_global(ip),
-            IpAddr::V6(ip) => {
-                if ip.is_loopback() {
+    match addr.ip() {
+        IpAddr::V4(ip) => is_ipv4_global(ip),
+        IpAddr::V6(ip) => {
+            if ip.is_loopback() {
+                return false;
+            }
+            // Check using same methods by trying to cast address to ipv4
+            // FIXME: use Ipv6 tests when Ipv6Addr convenience methods get stabilized:
+            // https://github.com/rust-lang/rust/issues/27709
+            if let Some(ipv4) = ip.to_ipv4() {
+                if !is_ipv4_global(ipv4) {
                     return false;
                 }
-                // Check using same methods by trying to cast address to ipv4
-                // FIXME: use Ipv6 tests when Ipv6Addr convenience methods get stabilized:
-                // https://github.com/rust-lang/rust/issues/27709
-                if let Some(ipv4) = ip.to_ipv4() {
-                    if !is_ipv4_global(ipv4) {
-                        return false;
-                    }
-                }
-                true
             }
+            true
         }
     }
 }
 
+/// cache of the gossip entries received recently. The same entry is
+/// usually advertised by many peers, it only needs to be processed and
+/// propagated once.
+pub struct GossipCache(Mutex<LruCache<Gossip, ()>>);
+
+impl GossipCache {
+    pub fn new(capacity: usize) -> Self {
+        GossipCache(Mutex::new(LruCache::new(capacity)))
+    }
+
+    /// keep the entries which were not received recently, and record
+    /// them as received
+    pub fn retain_new(&self, gossips: Vec<Gossip>) -> Vec<Gossip> {
+        let mut cache = self.0.lock().unwrap();
+        gossips
+            .into_iter()
+            .filter(|gossip| cache.put(gossip.clone(), ()).is_none())
+            .collect()
+    }
+}
+
 impl From<Gossip> for poldercast::NodeProfile {
     fn from(gossip: Gossip) -> Self {
         gossip.0
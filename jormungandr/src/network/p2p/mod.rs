@@ -4,7 +4,9 @@ pub mod layers;
 mod policy;
 mod topology;
 
-pub use self::gossip::{Gossip, Gossips, Peer, Peers};
+pub use self::gossip::{
+    is_global_address, is_valid_address, Gossip, GossipCache, Gossips, Peer, Peers,
+};
 pub use self::policy::{Policy, PolicyConfig};
 pub use self::topology::P2pTopology;
 
@@ -1,10 +1,17 @@
+use crate::utils::fs::write_atomically;
 use jormungandr_lib::time::Duration;
 use lru::LruCache;
 use poldercast::{Address, Node, PolicyReport};
 use serde::{Deserialize, Serialize};
 use slog::Logger;
-use std::collections::HashSet;
-use std::time::Duration as StdDuration;
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration as StdDuration, SystemTime},
+};
 
 /// default quarantine duration is 10min
 const DEFAULT_QUARANTINE_DURATION: StdDuration = StdDuration::from_secs(10 * 60);
@@ -22,15 +29,26 @@ const DEFAULT_MAX_NUM_QUARANTINE_RECORDS: usize = 24_000;
 pub struct Policy {
     quarantine_duration: StdDuration,
     max_quarantine: StdDuration,
-    records: LruCache<Address, Records>,
+    records: PolicyRecords,
     quarantine_whitelist: HashSet<Address>,
     logger: Logger,
 }
 
+/// The records of the policy on the peers: their quarantines and when they
+/// were last seen. The policy is owned by the topology once set, this
+/// handle lets the records be saved meanwhile.
+#[derive(Debug, Clone)]
+pub struct PolicyRecords(Arc<Mutex<LruCache<Address, Records>>>);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Records {
     /// record the number of time the given node has been quarantined
     /// in known time.
     quarantine: u32,
+    /// the end of the current or last quarantine of the node
+    quarantined_until: Option<SystemTime>,
+    /// the last time the node was updated in the topology
+    last_seen: SystemTime,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,33 +71,104 @@ impl Policy {
                 .max_quarantine
                 .unwrap_or_else(|| DEFAULT_MAX_QUARANTINE_DURATION.into())
                 .into(),
-            records: LruCache::new(
+            records: PolicyRecords(Arc::new(Mutex::new(LruCache::new(
                 pc.max_num_quarantine_records
                     .unwrap_or(DEFAULT_MAX_NUM_QUARANTINE_RECORDS),
-            ),
+            )))),
             quarantine_whitelist: pc.quarantine_whitelist,
             logger,
         }
     }
 
-    fn quarantine_duration_for(&mut self, id: Address) -> StdDuration {
-        if let Some(r) = self.records.get_mut(&id) {
-            r.quarantine_for(self.quarantine_duration, self.max_quarantine)
-        } else {
-            let r = Records::new();
-            let t = r.quarantine_for(self.quarantine_duration, self.max_quarantine);
-            self.records.put(id, r);
-            t
-        }
+    pub fn records(&self) -> PolicyRecords {
+        self.records.clone()
     }
 
-    fn update(&mut self, id: Address) {
-        if let Some(r) = self.records.get_mut(&id) {
-            r.update();
-        } else {
-            let r = Records::new();
-            self.records.put(id, r);
+    /// load the records saved by a previous run of the node. The quarantine
+    /// counts are halved for every maximum quarantine duration the nodes
+    /// have not been seen since, so a node cannot wipe its records by
+    /// waiting for a restart but is eventually forgiven. The records of
+    /// the nodes never quarantined are dropped once not seen for as long.
+    pub fn load_records(&mut self, path: &Path) -> io::Result<usize> {
+        let saved: Vec<(Address, Records)> =
+            serde_json::from_reader(BufReader::new(File::open(path)?))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let now = SystemTime::now();
+        let mut records = self.records.lock();
+        // the records are saved from the most to the least recently used
+        for (id, mut r) in saved.into_iter().rev() {
+            r.decay(now, self.max_quarantine);
+            if !r.is_expired(now, self.max_quarantine) {
+                records.put(id, r);
+            }
         }
+        Ok(records.len())
+    }
+
+    /// the time the quarantine of the node ends
+    fn quarantine_end(&mut self, id: Address, since: SystemTime) -> SystemTime {
+        let (quarantine_duration, max_quarantine) = (self.quarantine_duration, self.max_quarantine);
+        let mut records = self.records.lock();
+        let r = record_mut(&mut records, id, since);
+        r.quarantined_until
+            .unwrap_or_else(|| since + r.quarantine_for(quarantine_duration, max_quarantine))
+    }
+
+    fn update(&mut self, id: Address, now: SystemTime) {
+        let (quarantine_duration, max_quarantine) = (self.quarantine_duration, self.max_quarantine);
+        let mut records = self.records.lock();
+        let r = record_mut(&mut records, id, now);
+        r.update();
+        r.quarantined_until = Some(now + r.quarantine_for(quarantine_duration, max_quarantine));
+    }
+
+    /// note the last update of a node
+    fn seen(&mut self, id: &Address, last_update: SystemTime) {
+        let mut records = self.records.lock();
+        let r = record_mut(&mut records, id.clone(), last_update);
+        r.last_seen = std::cmp::max(r.last_seen, last_update);
+    }
+
+    /// whether the node was in quarantine when the records were saved,
+    /// and is to be put back in quarantine until the end of it
+    fn quarantine_pending(&self, id: &Address, now: SystemTime) -> bool {
+        self.records
+            .lock()
+            .peek(id)
+            .and_then(|r| r.quarantined_until)
+            .map_or(false, |until| now < until)
+    }
+}
+
+fn record_mut<'a>(
+    records: &'a mut LruCache<Address, Records>,
+    id: Address,
+    now: SystemTime,
+) -> &'a mut Records {
+    if !records.contains(&id) {
+        records.put(id.clone(), Records::new(now));
+    }
+    records.get_mut(&id).unwrap()
+}
+
+impl PolicyRecords {
+    fn lock(&self) -> MutexGuard<'_, LruCache<Address, Records>> {
+        self.0.lock().unwrap()
+    }
+
+    /// save the records in the given file, replacing its content
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let saved: Vec<(Address, Records)> = self
+            .lock()
+            .iter()
+            .map(|(id, r)| (id.clone(), r.clone()))
+            .collect();
+        write_atomically(path, |file| {
+            let mut writer = BufWriter::new(file);
+            serde_json::to_writer(&mut writer, &saved)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            writer.flush()
+        })
     }
 }
 
@@ -95,14 +184,34 @@ impl Default for PolicyConfig {
 }
 
 impl Records {
-    fn new() -> Records {
-        Self { quarantine: 0 }
+    fn new(now: SystemTime) -> Records {
+        Self {
+            quarantine: 0,
+            quarantined_until: None,
+            last_seen: now,
+        }
     }
 
     fn update(&mut self) {
         self.quarantine += 1;
     }
 
+    fn decay(&mut self, now: SystemTime, period: StdDuration) {
+        let unseen = now.duration_since(self.last_seen).unwrap_or_default();
+        let halvings = unseen.as_secs() / std::cmp::max(period.as_secs(), 1);
+        self.quarantine = self
+            .quarantine
+            .checked_shr(std::cmp::min(halvings, 32) as u32)
+            .unwrap_or(0);
+    }
+
+    fn is_expired(&self, now: SystemTime, period: StdDuration) -> bool {
+        let unseen = now.duration_since(self.last_seen).unwrap_or_default();
+        self.quarantine == 0
+            && self.quarantined_until.map_or(true, |until| until <= now)
+            && unseen >= period
+    }
+
     fn quarantine_for(
         &self,
         quarantine_instant: StdDuration,
@@ -122,12 +231,11 @@ impl poldercast::Policy for Policy {
         let id = node.address().to_string();
         let logger = self.logger.new(o!("id" => id));
         let node_address = node.address();
+        let now = SystemTime::now();
+        self.seen(node_address, *node.logs().last_update());
         // if the node is already quarantined
         if let Some(since) = node.logs().quarantined() {
-            let duration = since.elapsed().unwrap();
-            let quarantine_duration = self.quarantine_duration_for(node.address().clone());
-
-            if duration < quarantine_duration {
+            if now < self.quarantine_end(node_address.clone(), *since) {
                 // the node still need to do some quarantine time
                 PolicyReport::None
             } else if node.logs().last_update().elapsed().unwrap() < self.quarantine_duration {
@@ -146,6 +254,12 @@ impl poldercast::Policy for Policy {
                 debug!(logger, "forgetting about the node");
                 PolicyReport::Forget
             }
+        } else if !self.quarantine_whitelist.contains(node_address)
+            && self.quarantine_pending(node_address, now)
+        {
+            // the node was in quarantine when the node was restarted
+            debug!(logger, "move node back to quarantine");
+            PolicyReport::Quarantine
         } else if node.record().is_clear() {
             // if the record is clear, do nothing, leave the Node in the available nodes
             PolicyReport::None
@@ -160,8 +274,45 @@ impl poldercast::Policy for Policy {
         } else {
             // if the record is not `clear` then we quarantine the block for some time
             debug!(logger, "move node to quarantine");
-            self.update(node.address().clone());
+            self.update(node.address().clone(), now);
             PolicyReport::Quarantine
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_decay_while_the_node_is_not_seen() {
+        let period = StdDuration::from_secs(3600);
+        let last_seen = SystemTime::UNIX_EPOCH + period;
+        let mut r = Records {
+            quarantine: 8,
+            quarantined_until: Some(last_seen + period),
+            last_seen,
+        };
+
+        r.decay(last_seen + period / 2, period);
+        assert_eq!(r.quarantine, 8);
+        r.decay(last_seen + period * 2, period);
+        assert_eq!(r.quarantine, 2);
+        assert!(!r.is_expired(last_seen));
+
+        r.decay(last_seen + period * 100, period);
+        assert_eq!(r.quarantine, 0);
+        assert!(!r.is_expired(last_seen + period / 2, period));
+        assert!(r.is_expired(last_seen + period * 2, period));
+    }
+
+    #[test]
+    fn records_of_nodes_never_quarantined_expire_once_not_seen() {
+        let period = StdDuration::from_secs(3600);
+        let last_seen = SystemTime::UNIX_EPOCH + period;
+        let r = Records::new(last_seen);
+
+        assert!(!r.is_expired(last_seen + period / 2, period));
+        assert!(r.is_expired(last_seen + period, period));
+    }
+}
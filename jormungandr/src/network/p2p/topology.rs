@@ -3,16 +3,22 @@
 
 use crate::{
     log::KEY_SUB_TASK,
-    network::p2p::{layers::PreferredListLayer, Address, Gossips, Policy, PolicyConfig},
+    network::p2p::{
+        layers::PreferredListLayer, policy::PolicyRecords, Address, Gossips, Policy, PolicyConfig,
+    },
     settings::start::network::Configuration,
 };
 use poldercast::{
     custom_layers,
     poldercast::{Cyclon, Rings, Vicinity},
-    NodeProfile, PolicyReport, StrikeReason, Topology,
+    Layer, NodeProfile, PolicyReport, StrikeReason, Topology,
 };
 use rand_chacha::ChaChaRng;
 use slog::Logger;
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 use tokio::sync::RwLock;
 
 pub struct View {
@@ -23,11 +29,15 @@ pub struct View {
 /// object holding the P2pTopology of the Node
 pub struct P2pTopology {
     lock: RwLock<Topology>,
+    policy_records: Option<PolicyRecords>,
+    layers: Vec<&'static str>,
 }
 
 /// Builder object used to initialize the `P2pTopology`
 struct Builder {
     topology: Topology,
+    policy_records: Option<PolicyRecords>,
+    layers: Vec<&'static str>,
     logger: Logger,
 }
 
@@ -36,46 +46,67 @@ impl Builder {
     fn new(node: poldercast::NodeProfile, logger: Logger) -> Self {
         Builder {
             topology: Topology::new(node),
+            policy_records: None,
+            layers: Vec::new(),
             logger,
         }
     }
 
-    fn set_policy(mut self, policy: PolicyConfig) -> Self {
-        self.topology.set_policy(Policy::new(
-            policy,
-            self.logger.new(o!(KEY_SUB_TASK => "policy")),
-        ));
+    fn set_policy(mut self, policy: PolicyConfig, records_path: Option<&Path>) -> Self {
+        let logger = self.logger.new(o!(KEY_SUB_TASK => "policy"));
+        let mut policy = Policy::new(policy, logger.clone());
+        if let Some(path) = records_path.filter(|path| path.exists()) {
+            match policy.load_records(path) {
+                Ok(count) => info!(logger, "loaded {} policy records", count),
+                Err(e) => warn!(
+                    logger,
+                    "failed to load the policy records, starting afresh";
+                    "path" => %path.display(),
+                    "reason" => %e,
+                ),
+            }
+        }
+        self.policy_records = Some(policy.records());
+        self.topology.set_policy(policy);
         self
     }
 
+    /// add the layer to the topology, recording its name
+    fn add_layer<L>(&mut self, layer: L)
+    where
+        L: Layer + Send + Sync + 'static,
+    {
+        self.layers.push(layer.alias());
+        self.topology.add_layer(layer);
+    }
+
     /// set all the default poldercast modules (Rings, Vicinity and Cyclon)
     fn set_poldercast_modules(mut self) -> Self {
-        self.topology.add_layer(Rings::default());
-        self.topology.add_layer(Vicinity::default());
-        self.topology.add_layer(Cyclon::default());
+        self.add_layer(Rings::default());
+        self.add_layer(Vicinity::default());
+        self.add_layer(Cyclon::default());
         self
     }
 
     fn set_custom_modules(mut self, config: &Configuration, rng: ChaChaRng) -> Self {
-        if let Some(size) = config.max_unreachable_nodes_to_connect_per_event {
-            self.topology
-                .add_layer(custom_layers::RandomDirectConnections::with_max_view_length(size));
-        } else {
-            self.topology
-                .add_layer(custom_layers::RandomDirectConnections::default());
-        }
+        self.add_layer(
+            custom_layers::RandomDirectConnections::with_max_view_length(
+                config.max_unreachable_nodes_to_connect_per_event,
+            ),
+        );
 
-        self.topology.add_layer(PreferredListLayer::new(
+        self.add_layer(PreferredListLayer::new(
             config.layers.preferred_list.clone(),
             rng,
         ));
-
         self
     }
 
     fn build(self) -> P2pTopology {
         P2pTopology {
             lock: RwLock::new(self.topology),
+            policy_records: self.policy_records,
+            layers: self.layers,
         }
     }
 }
@@ -85,10 +116,27 @@ impl P2pTopology {
         Builder::new(config.profile.clone(), logger)
             .set_poldercast_modules()
             .set_custom_modules(&config, rng)
-            .set_policy(config.policy.clone())
+            .set_policy(config.policy.clone(), config.policy_records.as_deref())
             .build()
     }
 
+    /// the names of the layers selecting the peers of the node
+    pub fn layers(&self) -> &[&'static str] {
+        &self.layers
+    }
+
+    /// save the records of the policy in the given file, on a thread where
+    /// blocking is allowed
+    pub async fn save_policy_records(&self, path: PathBuf) -> io::Result<()> {
+        let records = match &self.policy_records {
+            Some(records) => records.clone(),
+            None => return Ok(()),
+        };
+        tokio::task::spawn_blocking(move || records.save(&path))
+            .await
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+    }
+
     /// Returns a list of neighbors selected in this turn
     /// to contact for event dissemination.
     pub async fn view(&self, selection: poldercast::Selection) -> View {
@@ -171,3 +219,30 @@ impl P2pTopology {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use slog::Discard;
+
+    #[test]
+    fn layers_are_named_after_their_alias() {
+        let logger = Logger::root(Discard, o!());
+        let preferred_list =
+            PreferredListLayer::new(Default::default(), ChaChaRng::from_seed(Default::default()));
+        let expected = vec![
+            Rings::default().alias(),
+            Vicinity::default().alias(),
+            Cyclon::default().alias(),
+            preferred_list.alias(),
+        ];
+
+        let mut builder = Builder::new(poldercast::NodeProfileBuilder::new().build(), logger)
+            .set_poldercast_modules();
+        builder.add_layer(preferred_list);
+        let topology = builder.build();
+
+        assert_eq!(topology.layers(), expected.as_slice());
+    }
+}
@@ -0,0 +1,530 @@
+//! mapping of the public port of the node on the router of the local network
+//!
+//! A node behind a home router cannot be reached by its peers unless the
+//! router forwards the public port to it. The node can ask the router to do
+//! so with UPnP or NAT-PMP, and renews the mapping before its lease expires.
+//! The external address reported by the router is checked against the public
+//! address of the node, a mismatch meaning another NAT stands in the way.
+//!
+//! Once the port is mapped, the node asks one of its trusted peers to dial
+//! back the external port, from outside of the local network. A peer can only
+//! be asked to dial the address the request comes from, so it cannot be used
+//! to connect to a third party. The mapping is removed when the node shuts
+//! down.
+
+use super::{grpc, GlobalStateR};
+use crate::settings::start::network::{Peer, PortMappingSettings};
+use jormungandr_lib::{interfaces::PortMappingStatus, time::SystemTime};
+use rand::seq::SliceRandom;
+use slog::Logger;
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use thiserror::Error;
+use tokio::{net::TcpStream, task, time};
+
+/// the port NAT-PMP gateways listen to
+const NAT_PMP_PORT: u16 = 5351;
+
+/// the first NAT-PMP timeout, doubled for every retry
+const NAT_PMP_INITIAL_TIMEOUT: Duration = Duration::from_millis(250);
+
+const NAT_PMP_ATTEMPTS: usize = 5;
+
+/// how long to look for a UPnP gateway
+const UPNP_SEARCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// the shortest time between two renewals of the mapping, below the
+/// shortest lease of one second
+const MIN_RENEWAL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// how long to wait before trying again to map the port after a failure
+const RETRY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// how long to wait for the connection to the address to dial back
+const DIAL_BACK_TIMEOUT: Duration = Duration::from_secs(5);
+
+const MAPPING_DESCRIPTION: &str = "jormungandr";
+
+/// The protocols to ask the router to map the port with.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PortMappingProtocol {
+    Upnp,
+    NatPmp,
+    /// UPnP, then NAT-PMP if it fails
+    Any,
+}
+
+impl PortMappingProtocol {
+    fn name(self) -> &'static str {
+        match self {
+            PortMappingProtocol::Upnp => "upnp",
+            PortMappingProtocol::NatPmp => "nat_pmp",
+            PortMappingProtocol::Any => "any",
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("no UPnP gateway found")]
+    UpnpSearch(#[source] igd::SearchError),
+    #[error("failed to get the external address from the UPnP gateway")]
+    UpnpExternalIp(#[source] igd::GetExternalIpError),
+    #[error("failed to map the port on the UPnP gateway")]
+    UpnpAddPort(#[source] igd::AddPortError),
+    #[error("failed to find the local address the gateway is reached from")]
+    LocalAddress(#[source] io::Error),
+    #[error("the default gateway is unknown, it can be set in `p2p.port_mapping.gateway`")]
+    UnknownGateway(#[source] io::Error),
+    #[error("failed to query the NAT-PMP gateway")]
+    NatPmpIo(#[source] io::Error),
+    #[error("the NAT-PMP gateway did not answer")]
+    NatPmpTimeout,
+    #[error("the NAT-PMP gateway answered with result code {0}")]
+    NatPmpResult(u16),
+    #[error("malformed answer from the NAT-PMP gateway")]
+    NatPmpMalformed,
+    #[error("UPnP: {upnp}; NAT-PMP: {nat_pmp}")]
+    NoMapping {
+        upnp: Box<Error>,
+        nat_pmp: Box<Error>,
+    },
+    #[error("failed to remove the port mapping from the UPnP gateway")]
+    UpnpRemovePort(#[source] igd::RemovePortError),
+    #[error("the port mapping task failed")]
+    Task(#[from] task::JoinError),
+}
+
+/// a port mapped on the router
+struct Mapping {
+    protocol: PortMappingProtocol,
+    external_address: SocketAddr,
+    lease_duration: Duration,
+}
+
+/// The outcome of the port mapping, shared with the REST API.
+#[derive(Clone, Default)]
+pub struct Status {
+    status: Arc<Mutex<Option<PortMappingStatus>>>,
+    /// the protocol of the mapping in place, to remove it on shutdown
+    mapped: Arc<Mutex<Option<PortMappingProtocol>>>,
+}
+
+impl Status {
+    pub fn get(&self) -> Option<PortMappingStatus> {
+        self.status.lock().unwrap().clone()
+    }
+
+    fn update<F: FnOnce(&mut PortMappingStatus)>(&self, f: F) {
+        let mut status = self.status.lock().unwrap();
+        f(status.get_or_insert_with(|| PortMappingStatus {
+            protocol: None,
+            external_address: None,
+            mapped_at: None,
+            public_address_matches: None,
+            reachable: None,
+            last_error: None,
+        }))
+    }
+}
+
+/// map the port, then renew the mapping before its lease expires
+pub async fn run(settings: PortMappingSettings, state: GlobalStateR) {
+    let logger = state
+        .logger()
+        .new(o!("external_port" => settings.external_port));
+    let public_ip = state
+        .node_address()
+        .and_then(|address| address.to_socket_addr())
+        .map(|addr| addr.ip());
+    loop {
+        let next_attempt = match map_port(&settings).await {
+            Ok(mapping) => {
+                let matches = public_ip.map(|ip| ip == mapping.external_address.ip());
+                info!(
+                    logger,
+                    "mapped the public port on the router";
+                    "protocol" => mapping.protocol.name(),
+                    "external_address" => %mapping.external_address,
+                );
+                if matches == Some(false) {
+                    warn!(
+                        logger,
+                        "the external address reported by the router is not the public address of the node, \
+                         the peers may not be able to connect";
+                        "external_address" => %mapping.external_address,
+                    );
+                }
+                *state.port_mapping.mapped.lock().unwrap() = Some(mapping.protocol);
+                let reachable = check_reachable(&state, &mapping, &logger).await;
+                if reachable == Some(false) {
+                    warn!(
+                        logger,
+                        "a trusted peer cannot reach the node at the external address, the router \
+                         may not forward the port";
+                        "external_address" => %mapping.external_address,
+                    );
+                }
+                state.port_mapping.update(|status| {
+                    status.protocol = Some(mapping.protocol.name().to_owned());
+                    status.external_address = Some(mapping.external_address);
+                    status.mapped_at = Some(SystemTime::now());
+                    status.public_address_matches = matches;
+                    status.reachable = reachable;
+                    status.last_error = None;
+                });
+                renewal_interval(mapping.lease_duration)
+            }
+            Err(e) => {
+                warn!(logger, "failed to map the public port on the router"; "reason" => %e);
+                state
+                    .port_mapping
+                    .update(|status| status.last_error = Some(e.to_string()));
+                RETRY_INTERVAL
+            }
+        };
+        time::delay_for(next_attempt).await;
+    }
+}
+
+/// renew the mapping halfway through its lease, well before it expires
+fn renewal_interval(lease_duration: Duration) -> Duration {
+    if lease_duration == Duration::from_secs(0) {
+        // the router gave no lease to go by
+        RETRY_INTERVAL
+    } else {
+        std::cmp::max(lease_duration / 2, MIN_RENEWAL_INTERVAL)
+    }
+}
+
+/// ask the trusted peers, in random order, to dial back the external port
+/// of the mapping, until one of them tells whether it could
+async fn check_reachable(state: &GlobalStateR, mapping: &Mapping, logger: &Logger) -> Option<bool> {
+    let mut trusted_peers = state
+        .config
+        .trusted_peers
+        .iter()
+        .filter_map(|tp| tp.address.to_socket_addr().map(Peer::new))
+        .collect::<Vec<_>>();
+    trusted_peers.shuffle(&mut rand::rngs::OsRng);
+
+    let port = mapping.external_address.port();
+    for peer in trusted_peers {
+        match grpc::request_dial_back(&state.config.transport, &peer, port).await {
+            Ok(Some(reachable)) => return Some(reachable),
+            Ok(None) => debug!(
+                logger,
+                "the trusted peer does not dial back";
+                "peer_addr" => %peer.address(),
+            ),
+            Err(e) => debug!(
+                logger,
+                "failed to ask the trusted peer to dial back";
+                "peer_addr" => %peer.address(),
+                "reason" => %e,
+            ),
+        }
+    }
+    None
+}
+
+/// connect to the address a peer asks to be dialed back at
+pub(super) async fn dial_back(external_address: SocketAddr) -> bool {
+    matches!(
+        time::timeout(DIAL_BACK_TIMEOUT, TcpStream::connect(external_address)).await,
+        Ok(Ok(_))
+    )
+}
+
+/// remove the mapping in place, if any, blocking until the router answers
+pub fn remove(settings: &PortMappingSettings, status: &Status) -> Result<(), Error> {
+    match status.mapped.lock().unwrap().take() {
+        Some(PortMappingProtocol::Upnp) => unmap_upnp(settings),
+        Some(PortMappingProtocol::NatPmp) => unmap_nat_pmp(settings),
+        // a mapping is made with one of the protocols
+        Some(PortMappingProtocol::Any) | None => Ok(()),
+    }
+}
+
+async fn map_port(settings: &PortMappingSettings) -> Result<Mapping, Error> {
+    let settings = settings.clone();
+    task::spawn_blocking(move || match settings.protocol {
+        PortMappingProtocol::Upnp => map_upnp(&settings),
+        PortMappingProtocol::NatPmp => map_nat_pmp(&settings),
+        PortMappingProtocol::Any => map_upnp(&settings).or_else(|upnp| {
+            map_nat_pmp(&settings).map_err(|nat_pmp| Error::NoMapping {
+                upnp: Box::new(upnp),
+                nat_pmp: Box::new(nat_pmp),
+            })
+        }),
+    })
+    .await?
+}
+
+fn map_upnp(settings: &PortMappingSettings) -> Result<Mapping, Error> {
+    let gateway = igd::search_gateway(igd::SearchOptions {
+        timeout: Some(UPNP_SEARCH_TIMEOUT),
+        ..Default::default()
+    })
+    .map_err(Error::UpnpSearch)?;
+    let local_ip = local_ip(*gateway.addr.ip()).map_err(Error::LocalAddress)?;
+    let external_ip = gateway.get_external_ip().map_err(Error::UpnpExternalIp)?;
+    gateway
+        .add_port(
+            igd::PortMappingProtocol::TCP,
+            settings.external_port,
+            SocketAddrV4::new(local_ip, settings.internal_port),
+            settings.lease_duration.as_secs() as u32,
+            MAPPING_DESCRIPTION,
+        )
+        .map_err(Error::UpnpAddPort)?;
+    Ok(Mapping {
+        protocol: PortMappingProtocol::Upnp,
+        external_address: SocketAddr::new(IpAddr::V4(external_ip), settings.external_port),
+        lease_duration: settings.lease_duration,
+    })
+}
+
+fn unmap_upnp(settings: &PortMappingSettings) -> Result<(), Error> {
+    let gateway = igd::search_gateway(igd::SearchOptions {
+        timeout: Some(UPNP_SEARCH_TIMEOUT),
+        ..Default::default()
+    })
+    .map_err(Error::UpnpSearch)?;
+    gateway
+        .remove_port(igd::PortMappingProtocol::TCP, settings.external_port)
+        .map_err(Error::UpnpRemovePort)
+}
+
+fn nat_pmp_socket(settings: &PortMappingSettings) -> Result<UdpSocket, Error> {
+    let gateway = match settings.gateway {
+        Some(gateway) => gateway,
+        None => default_gateway().map_err(Error::UnknownGateway)?,
+    };
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).map_err(Error::NatPmpIo)?;
+    socket
+        .connect((gateway, NAT_PMP_PORT))
+        .map_err(Error::NatPmpIo)?;
+    Ok(socket)
+}
+
+/// the NAT-PMP request to map the TCP port, for the given lifetime in
+/// seconds. A lifetime of 0 removes the mapping, the external port then
+/// being 0 as well.
+fn nat_pmp_mapping_request(internal_port: u16, external_port: u16, lifetime: u32) -> [u8; 12] {
+    let mut request = [0u8; 12];
+    request[1] = 2;
+    request[4..6].copy_from_slice(&internal_port.to_be_bytes());
+    request[6..8].copy_from_slice(&external_port.to_be_bytes());
+    request[8..12].copy_from_slice(&lifetime.to_be_bytes());
+    request
+}
+
+fn unmap_nat_pmp(settings: &PortMappingSettings) -> Result<(), Error> {
+    let socket = nat_pmp_socket(settings)?;
+    let request = nat_pmp_mapping_request(settings.internal_port, 0, 0);
+    nat_pmp_request(&socket, &request, 16).map(|_| ())
+}
+
+fn map_nat_pmp(settings: &PortMappingSettings) -> Result<Mapping, Error> {
+    let socket = nat_pmp_socket(settings)?;
+
+    // the external address request
+    let response = nat_pmp_request(&socket, &[0, 0], 12)?;
+    let external_ip = Ipv4Addr::new(response[8], response[9], response[10], response[11]);
+
+    // the TCP mapping request
+    let request = nat_pmp_mapping_request(
+        settings.internal_port,
+        settings.external_port,
+        settings.lease_duration.as_secs() as u32,
+    );
+    let response = nat_pmp_request(&socket, &request, 16)?;
+    let external_port = u16::from_be_bytes([response[10], response[11]]);
+    let lifetime = u32::from_be_bytes([response[12], response[13], response[14], response[15]]);
+
+    Ok(Mapping {
+        protocol: PortMappingProtocol::NatPmp,
+        external_address: SocketAddr::new(IpAddr::V4(external_ip), external_port),
+        lease_duration: Duration::from_secs(lifetime.into()),
+    })
+}
+
+/// send a NAT-PMP request, retrying with a doubling timeout, and check the
+/// header of the answer
+fn nat_pmp_request(
+    socket: &UdpSocket,
+    request: &[u8],
+    response_len: usize,
+) -> Result<Vec<u8>, Error> {
+    let mut timeout = NAT_PMP_INITIAL_TIMEOUT;
+    let mut buf = [0u8; 16];
+    for _ in 0..NAT_PMP_ATTEMPTS {
+        socket.send(request).map_err(Error::NatPmpIo)?;
+        socket
+            .set_read_timeout(Some(timeout))
+            .map_err(Error::NatPmpIo)?;
+        match socket.recv(&mut buf) {
+            Ok(len) => return check_nat_pmp_response(request[1], &buf[..len], response_len),
+            Err(e)
+                if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut =>
+            {
+                timeout *= 2;
+            }
+            Err(e) => return Err(Error::NatPmpIo(e)),
+        }
+    }
+    Err(Error::NatPmpTimeout)
+}
+
+fn check_nat_pmp_response(opcode: u8, response: &[u8], len: usize) -> Result<Vec<u8>, Error> {
+    if response.len() < len || response[0] != 0 || response[1] != opcode | 0x80 {
+        return Err(Error::NatPmpMalformed);
+    }
+    match u16::from_be_bytes([response[2], response[3]]) {
+        0 => Ok(response[..len].to_vec()),
+        code => Err(Error::NatPmpResult(code)),
+    }
+}
+
+/// the address of the interface the node reaches the gateway through
+fn local_ip(gateway: Ipv4Addr) -> io::Result<Ipv4Addr> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    socket.connect((gateway, NAT_PMP_PORT))?;
+    match socket.local_addr()?.ip() {
+        IpAddr::V4(ip) => Ok(ip),
+        IpAddr::V6(_) => Err(io::Error::new(
+            io::ErrorKind::AddrNotAvailable,
+            "no IPv4 address to reach the gateway",
+        )),
+    }
+}
+
+/// the gateway of the default route
+#[cfg(target_os = "linux")]
+fn default_gateway() -> io::Result<Ipv4Addr> {
+    let routes = std::fs::read_to_string("/proc/net/route")?;
+    parse_default_gateway(&routes).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "no default route in /proc/net/route",
+        )
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn default_gateway() -> io::Result<Ipv4Addr> {
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        "the default gateway is only looked up on Linux",
+    ))
+}
+
+/// parse the gateway of the default route out of the Linux routing table,
+/// the addresses of which are in the byte order of the host
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_default_gateway(routes: &str) -> Option<Ipv4Addr> {
+    routes.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            [_, "00000000", gateway, ..] => u32::from_str_radix(gateway, 16)
+                .ok()
+                .map(|gateway| Ipv4Addr::from(gateway.to_ne_bytes()))
+                .filter(|gateway| !gateway.is_unspecified()),
+            _ => None,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nat_pmp_responses_are_checked() {
+        let response = [0, 130, 0, 0, 0, 0, 0, 1, 11, 184, 11, 185, 0, 0, 14, 16];
+        assert_eq!(
+            check_nat_pmp_response(2, &response, 16).unwrap(),
+            response.to_vec()
+        );
+        assert!(matches!(
+            check_nat_pmp_response(2, &response[..12], 16),
+            Err(Error::NatPmpMalformed)
+        ));
+        assert!(matches!(
+            check_nat_pmp_response(0, &response, 12),
+            Err(Error::NatPmpMalformed)
+        ));
+        let refused = [0, 130, 0, 2, 0, 0, 0, 1, 11, 184, 0, 0, 0, 0, 0, 0];
+        assert!(matches!(
+            check_nat_pmp_response(2, &refused, 16),
+            Err(Error::NatPmpResult(2))
+        ));
+    }
+
+    #[test]
+    fn nat_pmp_mapping_requests_are_encoded() {
+        assert_eq!(
+            nat_pmp_mapping_request(3000, 3001, 3600),
+            [0, 2, 0, 0, 11, 184, 11, 185, 0, 0, 14, 16]
+        );
+        // the removal of the mapping
+        assert_eq!(
+            nat_pmp_mapping_request(3000, 0, 0),
+            [0, 2, 0, 0, 11, 184, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[tokio::test]
+    async fn dial_back_tells_whether_the_address_is_reachable() {
+        let mut listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accept = tokio::spawn(async move { listener.accept().await });
+        assert!(dial_back(addr).await);
+        accept.await.unwrap().unwrap();
+        // the listener is dropped, nothing listens on the port anymore
+        assert!(!dial_back(addr).await);
+    }
+
+    #[test]
+    fn mappings_are_renewed_before_their_lease_expires() {
+        assert_eq!(
+            renewal_interval(Duration::from_secs(3600)),
+            Duration::from_secs(1800)
+        );
+        assert!(renewal_interval(Duration::from_secs(60)) < Duration::from_secs(60));
+        assert!(renewal_interval(Duration::from_secs(1)) < Duration::from_secs(1));
+        assert_eq!(renewal_interval(Duration::from_secs(0)), RETRY_INTERVAL);
+    }
+
+    #[test]
+    fn nothing_is_removed_without_a_mapping() {
+        let settings = PortMappingSettings {
+            protocol: PortMappingProtocol::NatPmp,
+            lease_duration: Duration::from_secs(3600),
+            gateway: Some(Ipv4Addr::new(192, 0, 2, 1)),
+            internal_port: 3000,
+            external_port: 3000,
+        };
+        remove(&settings, &Status::default()).unwrap();
+    }
+
+    #[test]
+    fn default_gateway_is_parsed_from_the_routing_table() {
+        let routes = "\
+Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+eth0\t0001A8C0\t00000000\t0001\t0\t0\t0\t00FFFFFF\t0\t0\t0
+eth0\t00000000\t0101A8C0\t0003\t0\t0\t0\t00000000\t0\t0\t0
+";
+        assert_eq!(
+            parse_default_gateway(routes),
+            Some(Ipv4Addr::new(192, 168, 1, 1))
+        );
+        assert_eq!(parse_default_gateway(routes.lines().next().unwrap()), None);
+    }
+}
@@ -0,0 +1,158 @@
+//! limits on the number of network requests processed concurrently
+//!
+//! The requests served to the peers and the requests made by the node to
+//! catch up with its peers use separate limits, so serving many downstream
+//! peers cannot starve the node's own synchronization.
+//!
+//! The most expensive methods of the protocol have their own limits on top
+//! of that. The requests over these limits are rejected right away rather
+//! than queued in the intercom channels.
+
+use crate::settings::start::network::{MethodLimitSettings, MethodLimitsSettings};
+use chain_network::error::{Code as ErrorCode, Error};
+use std::{future::Future, sync::Arc, time::Duration};
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    time,
+};
+
+pub struct RequestLimit {
+    semaphore: Arc<Semaphore>,
+    max_requests: usize,
+}
+
+impl RequestLimit {
+    pub fn new(max_requests: usize) -> Self {
+        RequestLimit {
+            semaphore: Arc::new(Semaphore::new(max_requests)),
+            max_requests,
+        }
+    }
+
+    /// wait for a request slot. The slot is released when the permit
+    /// is dropped.
+    pub async fn acquire(&self) -> OwnedSemaphorePermit {
+        Arc::clone(&self.semaphore).acquire_owned().await
+    }
+
+    /// number of requests currently holding a slot
+    pub fn in_flight(&self) -> usize {
+        self.max_requests - self.semaphore.available_permits()
+    }
+}
+
+/// limit on the requests of a method of the p2p protocol
+pub struct MethodLimit {
+    method: &'static str,
+    semaphore: Arc<Semaphore>,
+    timeout: Duration,
+}
+
+impl MethodLimit {
+    fn new(method: &'static str, settings: &MethodLimitSettings) -> Self {
+        MethodLimit {
+            method,
+            semaphore: Arc::new(Semaphore::new(settings.max_concurrent)),
+            timeout: settings.timeout,
+        }
+    }
+
+    /// take a slot for a request of this method, or reject the request
+    /// with `ResourceExhausted` if all slots are taken.
+    pub fn try_acquire(&self) -> Result<OwnedSemaphorePermit, Error> {
+        Arc::clone(&self.semaphore)
+            .try_acquire_owned()
+            .map_err(|_| {
+                Error::new(
+                    ErrorCode::ResourceExhausted,
+                    format!("too many concurrent {} requests", self.method),
+                )
+            })
+    }
+
+    /// fail the request with `DeadlineExceeded` if the given future,
+    /// setting up the response, does not complete in time.
+    pub async fn timeout<F, T>(&self, future: F) -> Result<T, Error>
+    where
+        F: Future<Output = Result<T, Error>>,
+    {
+        time::timeout(self.timeout, future).await.map_err(|_| {
+            Error::new(
+                ErrorCode::DeadlineExceeded,
+                format!("{} request timed out", self.method),
+            )
+        })?
+    }
+}
+
+pub struct MethodLimits {
+    pub pull_blocks: MethodLimit,
+    pub get_blocks: MethodLimit,
+    pub push_headers: MethodLimit,
+}
+
+impl MethodLimits {
+    pub fn new(settings: &MethodLimitsSettings) -> Self {
+        MethodLimits {
+            pull_blocks: MethodLimit::new("PullBlocks", &settings.pull_blocks),
+            get_blocks: MethodLimit::new("GetBlocks", &settings.get_blocks),
+            push_headers: MethodLimit::new("PushHeaders", &settings.push_headers),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(max_concurrent: usize, timeout: Duration) -> MethodLimit {
+        MethodLimit::new(
+            "PullBlocks",
+            &MethodLimitSettings {
+                max_concurrent,
+                timeout,
+            },
+        )
+    }
+
+    #[test]
+    fn requests_over_the_limit_are_rejected() {
+        let limit = limit(2, Duration::from_secs(1));
+        let first = limit.try_acquire().unwrap();
+        let _second = limit.try_acquire().unwrap();
+        let error = limit.try_acquire().err().unwrap();
+        assert!(matches!(error.code(), ErrorCode::ResourceExhausted));
+
+        // the slot is released with the permit
+        drop(first);
+        assert!(limit.try_acquire().is_ok());
+    }
+
+    #[tokio::test]
+    async fn slow_requests_time_out() {
+        let limit = limit(1, Duration::from_millis(10));
+        let error = limit
+            .timeout(async {
+                time::delay_for(Duration::from_secs(10)).await;
+                Ok(())
+            })
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(error.code(), ErrorCode::DeadlineExceeded));
+
+        let value = limit.timeout(async { Ok(42) }).await.unwrap();
+        assert_eq!(value, 42);
+    }
+
+    #[tokio::test]
+    async fn in_flight_requests_are_counted() {
+        let limit = RequestLimit::new(4);
+        assert_eq!(limit.in_flight(), 0);
+        let permit = limit.acquire().await;
+        let _other = limit.acquire().await;
+        assert_eq!(limit.in_flight(), 2);
+        drop(permit);
+        assert_eq!(limit.in_flight(), 1);
+    }
+}
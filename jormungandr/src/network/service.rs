@@ -1,26 +1,30 @@
 use super::{
-    buffer_sizes,
+    bootstrap, buffer_gauges,
     convert::{self, Decode, Encode, ResponseStream},
+    grpc,
     p2p::comm::{BlockEventSubscription, FragmentSubscription, GossipSubscription},
     p2p::Address,
+    stream_limit::{LimitExceeded, StreamLimit},
     subscription, Channels, GlobalStateR,
 };
 use crate::blockcfg as app_data;
-use crate::intercom::{self, BlockMsg, ClientMsg};
+use crate::intercom::{self, BlockMsg, ClientMsg, TransactionMsg};
 use crate::utils::async_msg::MessageBox;
 use chain_network::core::server::{BlockService, FragmentService, GossipService, Node, PushStream};
 use chain_network::data::p2p::{AuthenticatedNodeId, Peer, Peers};
 use chain_network::data::{
-    Block, BlockId, BlockIds, Fragment, FragmentIds, Gossip, HandshakeResponse, Header,
+    Block, BlockId, BlockIds, Fragment, FragmentIds, Gossip, HandshakeResponse, Header, NodeId,
 };
 use chain_network::error::{Code as ErrorCode, Error};
 
 use async_trait::async_trait;
 use futures::prelude::*;
 use futures::try_join;
+use poldercast::StrikeReason;
 use slog::Logger;
 
 use std::convert::TryFrom;
+use std::time::Duration;
 
 #[derive(Clone)]
 pub struct NodeService {
@@ -43,12 +47,54 @@ impl NodeService {
     pub fn logger(&self) -> &Logger {
         &self.logger
     }
+
+    fn reply_timeout(&self) -> Duration {
+        self.global_state.config.reply_timeout
+    }
 }
 
 impl NodeService {
-    fn subscription_logger(&self, subscriber: Peer, stream_name: &'static str) -> Logger {
-        self.logger
-            .new(o!("peer" => subscriber.to_string(), "stream" => stream_name))
+    /// reject the subscriptions of peers which have not authenticated,
+    /// if client authentication is enforced. Returns the node id of the
+    /// peer if it has authenticated.
+    async fn check_client_auth(&self, peer: Address) -> Result<Option<NodeId>, Error> {
+        let node_id = self.global_state.peers.client_node_id(peer).await;
+        if node_id.is_none() && self.global_state.config.trusted_node_ids.is_some() {
+            return Err(Error::new(
+                ErrorCode::Unauthenticated,
+                "client authentication is required by this node",
+            ));
+        }
+        Ok(node_id)
+    }
+
+    /// record the version the subscriber tells in the headers of its
+    /// request, once the subscription has put it in the peer map
+    async fn record_peer_version(&self, peer: Address) {
+        if let Some(version) = grpc::current_peer_version() {
+            self.global_state
+                .peers
+                .set_peer_version(peer, version)
+                .await;
+        }
+    }
+
+    /// the logger of a subscription, with the node id of the subscriber
+    /// if it has authenticated, so that its activity can be told apart
+    /// from the address it happens to connect from
+    fn subscription_logger(
+        &self,
+        subscriber: Peer,
+        node_id: Option<&NodeId>,
+        stream_name: &'static str,
+    ) -> Logger {
+        let logger = self
+            .logger
+            .new(o!("peer" => subscriber.to_string(), "stream" => stream_name));
+        match node_id {
+            Some(node_id) => logger.new(o!("node_id" => hex::encode(node_id.as_bytes()))),
+            None => logger,
+        }
     }
 }
 
@@ -75,15 +121,28 @@ impl Node for NodeService {
     /// Handles client ID authentication.
     async fn client_auth(&self, peer: Peer, auth: AuthenticatedNodeId) -> Result<(), Error> {
         let addr = Address::tcp(peer.addr());
-        let nonce = self.global_state.peers.get_auth_nonce(addr.clone()).await;
+        let nonce = self.global_state.peers.take_auth_nonce(addr.clone()).await;
         let nonce = nonce.ok_or_else(|| {
             Error::new(
                 ErrorCode::FailedPrecondition,
-                "nonce is missing, perform Handshake first",
+                "nonce is missing, used or expired, perform Handshake first",
             )
         })?;
         auth.verify(&nonce[..])?;
-        self.global_state.peers.set_node_id(addr, auth.into()).await;
+        let node_id = auth.into();
+        if !self.global_state.is_trusted_node_id(&node_id) {
+            info!(
+                self.logger,
+                "rejecting client peer with an untrusted node id";
+                "peer" => %addr,
+                "node_id" => hex::encode(node_id.as_bytes()),
+            );
+            return Err(Error::new(
+                ErrorCode::PermissionDenied,
+                "the node id is not trusted by this node",
+            ));
+        }
+        self.global_state.peers.set_node_id(addr, node_id).await;
         Ok(())
     }
 
@@ -100,6 +159,50 @@ impl Node for NodeService {
     }
 }
 
+/// count an item pushed by a peer against the limit of the stream, keeping
+/// the error for the peer to be penalized once the request is aborted
+fn check_stream_limit(
+    limit: &mut StreamLimit,
+    item: &[u8],
+    exceeded: &mut Option<LimitExceeded>,
+) -> Result<(), Error> {
+    limit.check(item.len()).map_err(|e| {
+        *exceeded = Some(e.clone());
+        e.into()
+    })
+}
+
+impl NodeService {
+    /// penalize the peer which pushed a stream past the limits: record a
+    /// strike against it in the topology and disconnect it
+    async fn penalize_pushing_peer(&self, e: LimitExceeded, logger: &Logger) {
+        let peer = match grpc::current_peer() {
+            Some(addr) => Address::tcp(addr),
+            None => {
+                warn!(
+                    logger,
+                    "peer stream exceeds the limits, aborting the request";
+                    "reason" => %e,
+                );
+                return;
+            }
+        };
+        warn!(
+            logger,
+            "peer stream exceeds the limits, disconnecting";
+            "peer" => %peer,
+            "reason" => %e,
+        );
+        future::join(
+            self.global_state
+                .topology
+                .report_node(peer.clone(), StrikeReason::InvalidData),
+            self.global_state.peers.remove_peer(peer),
+        )
+        .await;
+    }
+}
+
 async fn send_message<T>(mut mbox: MessageBox<T>, msg: T, logger: Logger) -> Result<(), Error> {
     mbox.send(msg).await.map_err(|e| {
         error!(
@@ -129,10 +232,11 @@ impl BlockService for NodeService {
 
     async fn tip(&self) -> Result<Header, Error> {
         let logger = self.logger().new(o!("request" => "Tip"));
+        let _permit = self.global_state.server_requests.acquire().await;
         let (reply_handle, reply_future) = intercom::unary_reply(logger.clone());
         let mbox = self.channels.client_box.clone();
         send_message(mbox, ClientMsg::GetBlockTip(reply_handle), logger).await?;
-        let header = reply_future.await?;
+        let header = reply_future.with_timeout(self.reply_timeout()).await?;
         Ok(header.encode())
     }
 
@@ -143,13 +247,23 @@ impl BlockService for NodeService {
     ) -> Result<Self::PullBlocksStream, Error> {
         let from = from.decode()?;
         let to = to.decode()?;
+        let limit = &self.global_state.method_limits.pull_blocks;
+        let method_permit = limit.try_acquire()?;
         let logger = self.logger().new(o!("request" => "PullBlocks"));
-        let (handle, future) =
-            intercom::stream_reply(buffer_sizes::outbound::BLOCKS, logger.clone());
-        let client_box = self.channels.client_box.clone();
-        send_message(client_box, ClientMsg::PullBlocks(from, to, handle), logger).await?;
-        let stream = future.await?;
-        Ok(convert::response_stream(stream))
+        let (permit, stream) = limit
+            .timeout(async {
+                let permit = self.global_state.server_requests.acquire().await;
+                let (handle, future) =
+                    intercom::stream_reply(&buffer_gauges::outbound::BLOCKS, logger.clone());
+                let client_box = self.channels.client_box.clone();
+                send_message(client_box, ClientMsg::PullBlocks(from, to, handle), logger).await?;
+                Ok((permit, future.with_timeout(self.reply_timeout()).await?))
+            })
+            .await?;
+        Ok(convert::response_stream(
+            stream,
+            vec![permit, method_permit],
+        ))
     }
 
     async fn pull_blocks_to_tip(
@@ -158,34 +272,46 @@ impl BlockService for NodeService {
     ) -> Result<Self::PullBlocksToTipStream, Error> {
         let from = from.decode()?;
         let logger = self.logger().new(o!("request" => "PullBlocksToTip"));
+        let permit = self.global_state.server_requests.acquire().await;
         let (handle, future) =
-            intercom::stream_reply(buffer_sizes::outbound::BLOCKS, logger.clone());
+            intercom::stream_reply(&buffer_gauges::outbound::BLOCKS, logger.clone());
         let client_box = self.channels.client_box.clone();
         send_message(client_box, ClientMsg::PullBlocksToTip(from, handle), logger).await?;
-        let stream = future.await?;
-        Ok(convert::response_stream(stream))
+        let stream = future.with_timeout(self.reply_timeout()).await?;
+        Ok(convert::response_stream(stream, vec![permit]))
     }
 
     async fn get_blocks(&self, ids: BlockIds) -> Result<Self::GetBlocksStream, Error> {
         let ids = ids.decode()?;
+        let limit = &self.global_state.method_limits.get_blocks;
+        let method_permit = limit.try_acquire()?;
         let logger = self.logger().new(o!("request" => "GetBlocks"));
-        let (handle, future) =
-            intercom::stream_reply(buffer_sizes::outbound::BLOCKS, logger.clone());
-        let client_box = self.channels.client_box.clone();
-        send_message(client_box, ClientMsg::GetBlocks(ids, handle), logger).await?;
-        let stream = future.await?;
-        Ok(convert::response_stream(stream))
+        let (permit, stream) = limit
+            .timeout(async {
+                let permit = self.global_state.server_requests.acquire().await;
+                let (handle, future) =
+                    intercom::stream_reply(&buffer_gauges::outbound::BLOCKS, logger.clone());
+                let client_box = self.channels.client_box.clone();
+                send_message(client_box, ClientMsg::GetBlocks(ids, handle), logger).await?;
+                Ok((permit, future.with_timeout(self.reply_timeout()).await?))
+            })
+            .await?;
+        Ok(convert::response_stream(
+            stream,
+            vec![permit, method_permit],
+        ))
     }
 
     async fn get_headers(&self, ids: BlockIds) -> Result<Self::GetHeadersStream, Error> {
         let ids = ids.decode()?;
         let logger = self.logger().new(o!("request" => "GetHeaders"));
+        let permit = self.global_state.server_requests.acquire().await;
         let (handle, future) =
-            intercom::stream_reply(buffer_sizes::outbound::HEADERS, logger.clone());
+            intercom::stream_reply(&buffer_gauges::outbound::HEADERS, logger.clone());
         let client_box = self.channels.client_box.clone();
         send_message(client_box, ClientMsg::GetHeaders(ids, handle), logger).await?;
-        let stream = future.await?;
-        Ok(convert::response_stream(stream))
+        let stream = future.with_timeout(self.reply_timeout()).await?;
+        Ok(convert::response_stream(stream, vec![permit]))
     }
 
     async fn pull_headers(
@@ -196,8 +322,9 @@ impl BlockService for NodeService {
         let from = from.decode()?;
         let to = to.decode()?;
         let logger = self.logger().new(o!("request" => "PullHeaders"));
+        let permit = self.global_state.server_requests.acquire().await;
         let (handle, future) =
-            intercom::stream_reply(buffer_sizes::outbound::HEADERS, logger.clone());
+            intercom::stream_reply(&buffer_gauges::outbound::HEADERS, logger.clone());
         let client_box = self.channels.client_box.clone();
         send_message(
             client_box,
@@ -205,37 +332,64 @@ impl BlockService for NodeService {
             logger,
         )
         .await?;
-        let stream = future.await?;
-        Ok(convert::response_stream(stream))
+        let stream = future.with_timeout(self.reply_timeout()).await?;
+        Ok(convert::response_stream(stream, vec![permit]))
     }
 
     async fn push_headers(&self, stream: PushStream<Header>) -> Result<(), Error> {
+        let limit = &self.global_state.method_limits.push_headers;
+        let _method_permit = limit.try_acquire()?;
         let logger = self.logger.new(o!("request" => "PushHeaders"));
-        let (handle, sink, reply) =
-            intercom::stream_request(buffer_sizes::inbound::HEADERS, logger.clone());
-        let block_box = self.channels.block_box.clone();
-        send_message(block_box, BlockMsg::ChainHeaders(handle), logger).await?;
-        try_join!(
-            stream
-                .and_then(|header| async { header.decode() })
-                .forward(sink.sink_err_into()),
-            reply.err_into(),
-        )?;
-        Ok(())
+        limit
+            .timeout(async {
+                let (handle, sink, reply) =
+                    intercom::stream_request(&buffer_gauges::inbound::HEADERS, logger.clone());
+                let block_box = self.channels.block_box.clone();
+                send_message(block_box, BlockMsg::ChainHeaders(handle), logger.clone()).await?;
+                let mut stream_limit =
+                    StreamLimit::headers(&self.global_state.config.stream_limits);
+                let mut exceeded = None;
+                let res = try_join!(
+                    stream
+                        .map(|item| {
+                            let header = item?;
+                            check_stream_limit(&mut stream_limit, header.as_ref(), &mut exceeded)?;
+                            header.decode()
+                        })
+                        .forward(sink.sink_err_into()),
+                    reply.err_into(),
+                );
+                if let Some(e) = exceeded {
+                    self.penalize_pushing_peer(e, &logger).await;
+                }
+                res?;
+                Ok(())
+            })
+            .await
     }
 
     async fn upload_blocks(&self, stream: PushStream<Block>) -> Result<(), Error> {
         let logger = self.logger.new(o!("request" => "UploadBlocks"));
         let (handle, sink, reply) =
-            intercom::stream_request(buffer_sizes::inbound::BLOCKS, logger.clone());
+            intercom::stream_request(&buffer_gauges::inbound::BLOCKS, logger.clone());
         let block_box = self.channels.block_box.clone();
-        send_message(block_box, BlockMsg::NetworkBlocks(handle), logger).await?;
-        try_join!(
+        send_message(block_box, BlockMsg::NetworkBlocks(handle), logger.clone()).await?;
+        let mut stream_limit = StreamLimit::blocks(&self.global_state.config.stream_limits);
+        let mut exceeded = None;
+        let res = try_join!(
             stream
-                .and_then(|block| async { block.decode() })
+                .map(|item| {
+                    let block = item?;
+                    check_stream_limit(&mut stream_limit, block.as_ref(), &mut exceeded)?;
+                    block.decode()
+                })
                 .forward(sink.sink_err_into()),
             reply.err_into(),
-        )?;
+        );
+        if let Some(e) = exceeded {
+            self.penalize_pushing_peer(e, &logger).await;
+        }
+        res?;
         Ok(())
     }
 
@@ -245,7 +399,8 @@ impl BlockService for NodeService {
         stream: PushStream<Header>,
     ) -> Result<Self::SubscriptionStream, Error> {
         let addr = subscriber.addr();
-        let logger = self.subscription_logger(subscriber, "block_events");
+        let node_id = self.check_client_auth(Address::tcp(addr)).await?;
+        let logger = self.subscription_logger(subscriber, node_id.as_ref(), "block_events");
         let subscriber = Address::tcp(addr);
 
         self.global_state
@@ -260,8 +415,9 @@ impl BlockService for NodeService {
         let outbound = self
             .global_state
             .peers
-            .subscribe_to_block_events(subscriber)
+            .subscribe_to_block_events(subscriber.clone())
             .await;
+        self.record_peer_version(subscriber).await;
         Ok(serve_subscription(outbound))
     }
 }
@@ -271,8 +427,30 @@ impl FragmentService for NodeService {
     type GetFragmentsStream = ResponseStream<app_data::Fragment>;
     type SubscriptionStream = SubscriptionStream<FragmentSubscription>;
 
-    async fn get_fragments(&self, _ids: FragmentIds) -> Result<Self::GetFragmentsStream, Error> {
-        Err(Error::unimplemented())
+    /// serves the pending fragments with the given ids, or a capped
+    /// snapshot of the mempool to the peers warming up their mempool
+    /// after a restart
+    async fn get_fragments(&self, ids: FragmentIds) -> Result<Self::GetFragmentsStream, Error> {
+        let ids = ids.decode()?;
+        let snapshot = ids == bootstrap::mempool_snapshot_request();
+        let request = if snapshot {
+            "GetMempoolSnapshot"
+        } else {
+            "GetFragments"
+        };
+        let logger = self.logger().new(o!("request" => request));
+        let permit = self.global_state.server_requests.acquire().await;
+        let (handle, future) =
+            intercom::stream_reply(&buffer_gauges::outbound::FRAGMENTS, logger.clone());
+        let msg = if snapshot {
+            TransactionMsg::GetMempoolSnapshot(handle)
+        } else {
+            TransactionMsg::GetFragments(ids, handle)
+        };
+        let transaction_box = self.channels.transaction_box.clone();
+        send_message(transaction_box, msg, logger).await?;
+        let stream = future.with_timeout(self.reply_timeout()).await?;
+        Ok(convert::response_stream(stream, vec![permit]))
     }
 
     async fn fragment_subscription(
@@ -281,7 +459,8 @@ impl FragmentService for NodeService {
         stream: PushStream<Fragment>,
     ) -> Result<Self::SubscriptionStream, Error> {
         let addr = subscriber.addr();
-        let logger = self.subscription_logger(subscriber, "fragments");
+        let node_id = self.check_client_auth(Address::tcp(addr)).await?;
+        let logger = self.subscription_logger(subscriber, node_id.as_ref(), "fragments");
         let subscriber = Address::tcp(addr);
 
         self.global_state.spawn(subscription::process_fragments(
@@ -295,8 +474,9 @@ impl FragmentService for NodeService {
         let outbound = self
             .global_state
             .peers
-            .subscribe_to_fragments(subscriber)
+            .subscribe_to_fragments(subscriber.clone())
             .await;
+        self.record_peer_version(subscriber).await;
         Ok(serve_subscription(outbound))
     }
 }
@@ -311,7 +491,8 @@ impl GossipService for NodeService {
         stream: PushStream<Gossip>,
     ) -> Result<Self::SubscriptionStream, Error> {
         let addr = subscriber.addr();
-        let logger = self.subscription_logger(subscriber, "gossip");
+        let node_id = self.check_client_auth(Address::tcp(addr)).await?;
+        let logger = self.subscription_logger(subscriber, node_id.as_ref(), "gossip");
         let subscriber = Address::tcp(addr);
 
         self.global_state.spawn(subscription::process_gossip(
@@ -324,8 +505,9 @@ impl GossipService for NodeService {
         let outbound = self
             .global_state
             .peers
-            .subscribe_to_gossip(subscriber)
+            .subscribe_to_gossip(subscriber.clone())
             .await;
+        self.record_peer_version(subscriber).await;
         Ok(serve_subscription(outbound))
     }
 
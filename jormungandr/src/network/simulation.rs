@@ -0,0 +1,344 @@
+//! deterministic simulation of the network tasks
+//!
+//! The nodes of a simulation serve and connect to each other over an
+//! in-memory network injecting faults in the connections. All the tasks run
+//! on a single thread against a paused clock that only advances when the
+//! simulation is stepped, and every random choice is drawn from the seed of
+//! the simulation: a failing run is replayed by running it again with the
+//! same seed. The other tasks of the node do not run: the requests the
+//! network tasks make to them fail, and the blocks and headers received from
+//! the peers are consumed without being processed.
+
+use super::{
+    client,
+    grpc::{
+        self,
+        memory::{Faults, MemoryNetwork},
+    },
+    Channels, ConnectionState, GlobalState, GlobalStateR,
+};
+use crate::{
+    intercom::{self, BlockMsg, ClientMsg, RequestStreamHandle, TransactionMsg},
+    settings::{
+        start::{
+            generate_network,
+            network::{Listen, Peer},
+        },
+        CommandLine,
+    },
+    stats_counter::StatsCounter,
+    utils::async_msg::{self, MessageQueue},
+};
+use chain_crypto::Ed25519;
+use chain_network::data::NodeKeyPair;
+use futures::prelude::*;
+use jormungandr_lib::crypto::{hash::Hash, key::SigningKey};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaChaRng;
+use slog::{Discard, Logger};
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use structopt::StructOpt;
+use tokio::{
+    runtime::{self, Runtime},
+    task, time,
+};
+
+/// the simulated time between two steps of the tasks
+const STEP: Duration = Duration::from_millis(10);
+
+/// the most times the tasks are polled in a step
+const MAX_POLLS_PER_STEP: usize = 8;
+
+pub struct Simulation {
+    runtime: Runtime,
+    network: MemoryNetwork,
+    rng: ChaChaRng,
+    block0_hash: Hash,
+    logger: Logger,
+}
+
+/// a node of the simulation, serving its address
+pub struct SimNode {
+    state: GlobalStateR,
+    channels: Channels,
+    addr: SocketAddr,
+}
+
+/// where a client connection of a node stands
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ClientStatus {
+    Connecting,
+    Connected,
+    Failed(String),
+    Disconnected,
+}
+
+/// a client connection of a node
+pub struct SimClient {
+    // the connection is canceled when the handle is dropped
+    _handle: client::ConnectHandle,
+    status: Arc<Mutex<ClientStatus>>,
+}
+
+impl SimClient {
+    pub fn status(&self) -> ClientStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// whether the client task is over
+    pub fn is_finished(&self) -> bool {
+        match self.status() {
+            ClientStatus::Failed(_) | ClientStatus::Disconnected => true,
+            ClientStatus::Connecting | ClientStatus::Connected => false,
+        }
+    }
+}
+
+impl Simulation {
+    pub fn new(seed: u64, faults: Faults) -> Self {
+        let mut runtime = runtime::Builder::new()
+            .basic_scheduler()
+            .enable_time()
+            .build()
+            .unwrap();
+        runtime.block_on(async { time::pause() });
+
+        let mut rng = ChaChaRng::seed_from_u64(seed);
+        let network = MemoryNetwork::with_faults(rng.gen(), faults);
+        Simulation {
+            runtime,
+            network,
+            block0_hash: Hash::from(rng.gen::<[u8; 32]>()),
+            rng,
+            logger: Logger::root(Discard, o!()),
+        }
+    }
+
+    pub fn network(&self) -> &MemoryNetwork {
+        &self.network
+    }
+
+    /// start a node serving the given port
+    pub fn start_node(&mut self, port: u16) -> SimNode {
+        let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port);
+        let start_arguments = CommandLine::from_iter_safe(&["jormungandr"])
+            .unwrap()
+            .start_arguments;
+        let config = generate_network(&start_arguments, &None, &self.logger).unwrap();
+        let key: SigningKey<Ed25519> = SigningKey::generate(&mut self.rng);
+        let state = Arc::new(GlobalState::with_rng_seed(
+            self.block0_hash.into_hash(),
+            config,
+            NodeKeyPair::from(key.as_ref().clone()),
+            StatsCounter::default(),
+            self.logger.clone(),
+            self.rng.gen(),
+        ));
+
+        let (client_box, client_queue) = async_msg::channel(16);
+        let (transaction_box, transaction_queue) = async_msg::channel(16);
+        let (block_box, block_queue) = async_msg::channel(16);
+        let channels = Channels {
+            client_box,
+            transaction_box,
+            block_box,
+        };
+        self.runtime.spawn(serve_client_requests(client_queue));
+        self.runtime
+            .spawn(serve_transaction_requests(transaction_queue));
+        self.runtime.spawn(serve_block_requests(block_queue));
+
+        let network = self.network.clone();
+        let serve_state = state.clone();
+        let serve_channels = channels.clone();
+        self.runtime.spawn(async move {
+            grpc::serve(&network, &Listen::new(addr), serve_state, serve_channels)
+                .await
+                .unwrap()
+        });
+
+        SimNode {
+            state,
+            channels,
+            addr,
+        }
+    }
+
+    /// connect the client of a node to another node
+    pub fn connect(&mut self, from: &SimNode, to: &SimNode) -> SimClient {
+        let conn_state =
+            ConnectionState::new(from.state.clone(), &Peer::new(to.addr), self.logger.clone());
+        let (handle, connecting) =
+            client::connect(&self.network, conn_state, from.channels.clone());
+        let status = Arc::new(Mutex::new(ClientStatus::Connecting));
+        let task_status = status.clone();
+        self.runtime.spawn(async move {
+            let client = match connecting.await {
+                Ok(client) => client,
+                Err(err) => {
+                    *task_status.lock().unwrap() = ClientStatus::Failed(err.to_string());
+                    return;
+                }
+            };
+            *task_status.lock().unwrap() = ClientStatus::Connected;
+            client.await;
+            *task_status.lock().unwrap() = ClientStatus::Disconnected;
+        });
+        SimClient {
+            _handle: handle,
+            status,
+        }
+    }
+
+    /// run the tasks for the given simulated time. The tasks are polled a
+    /// random number of times between the steps of the clock, to vary how
+    /// their progress interleaves with the timers.
+    pub fn run_for(&mut self, duration: Duration) {
+        let steps = (duration.as_millis() / STEP.as_millis()).max(1);
+        for _ in 0..steps {
+            let polls = self.rng.gen_range(1, MAX_POLLS_PER_STEP + 1);
+            self.runtime.block_on(async move {
+                for _ in 0..polls {
+                    task::yield_now().await;
+                }
+                time::advance(STEP).await;
+            });
+        }
+    }
+}
+
+fn unserved() -> intercom::Error {
+    intercom::Error::unimplemented("the request is not served in the simulation")
+}
+
+/// fail the requests of the peers for the blockchain of the node
+async fn serve_client_requests(mut queue: MessageQueue<ClientMsg>) {
+    while let Some(msg) = queue.next().await {
+        match msg {
+            ClientMsg::GetBlockTip(handle) => handle.reply_error(unserved()),
+            ClientMsg::GetHeaders(_, handle) => handle.reply_error(unserved()),
+            ClientMsg::GetHeadersRange(_, _, handle) => handle.reply_error(unserved()),
+            ClientMsg::GetBlocks(_, handle) => handle.reply_error(unserved()),
+            ClientMsg::PullBlocks(_, _, handle) => handle.reply_error(unserved()),
+            ClientMsg::PullBlocksToTip(_, handle) => handle.reply_error(unserved()),
+        }
+    }
+}
+
+/// fail the requests for the fragment pool, the fragments received from the
+/// peers are dropped
+async fn serve_transaction_requests(mut queue: MessageQueue<TransactionMsg>) {
+    while let Some(msg) = queue.next().await {
+        match msg {
+            TransactionMsg::SendTransaction(..)
+            | TransactionMsg::WarmUpTransactions(..)
+            | TransactionMsg::RemoveTransactions(..)
+            | TransactionMsg::RestoreTransactions(..) => (),
+            TransactionMsg::CancelTransaction(_, handle) => handle.reply_error(unserved()),
+            TransactionMsg::GetLogs(handle) => handle.reply_error(unserved()),
+            TransactionMsg::GetStatuses(_, handle) => handle.reply_error(unserved()),
+            TransactionMsg::GetFragment(_, handle) => handle.reply_error(unserved()),
+            TransactionMsg::GetFragments(_, handle) => handle.reply_error(unserved()),
+            TransactionMsg::GetMempoolSnapshot(handle) => handle.reply_error(unserved()),
+            TransactionMsg::SelectTransactions { reply_handle, .. } => {
+                reply_handle.reply_error(unserved())
+            }
+        }
+    }
+}
+
+/// consume the blocks and headers received from the peers, as the block
+/// task does when it is done processing them
+async fn serve_block_requests(mut queue: MessageQueue<BlockMsg>) {
+    while let Some(msg) = queue.next().await {
+        match msg {
+            BlockMsg::LeadershipBlock(_) | BlockMsg::AnnouncedBlock(..) => (),
+            BlockMsg::NetworkBlocks(handle) => consume(handle).await,
+            BlockMsg::ChainHeaders(handle) => consume(handle).await,
+        }
+    }
+}
+
+async fn consume<T>(handle: RequestStreamHandle<T, ()>) {
+    let (stream, reply) = handle.into_stream_and_reply();
+    stream.for_each(|_| future::ready(())).await;
+    reply.reply_ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::buffer_gauges;
+
+    const SEEDS: u64 = 16;
+
+    #[test]
+    fn client_connects_without_faults() {
+        for seed in 0..SEEDS {
+            let mut simulation = Simulation::new(seed, Faults::default());
+            let server = simulation.start_node(3000);
+            let node = simulation.start_node(3001);
+            let client = simulation.connect(&node, &server);
+
+            simulation.run_for(Duration::from_secs(10));
+            assert_eq!(client.status(), ClientStatus::Connected, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn requests_to_the_other_tasks_are_answered() {
+        let mut simulation = Simulation::new(0, Faults::default());
+        let node = simulation.start_node(3000);
+        let mut client_box = node.channels.client_box.clone();
+        let mut block_box = node.channels.block_box.clone();
+        let logger = simulation.logger.clone();
+
+        let (tip_result, blocks_result) = simulation.runtime.block_on(async move {
+            let (handle, tip) = intercom::unary_reply(logger.clone());
+            client_box
+                .send(ClientMsg::GetBlockTip(handle))
+                .await
+                .unwrap();
+
+            let (handle, sink, blocks) =
+                intercom::stream_request(&buffer_gauges::inbound::BLOCKS, logger);
+            block_box
+                .send(BlockMsg::NetworkBlocks(handle))
+                .await
+                .unwrap();
+            drop(sink);
+            (tip.await, blocks.await)
+        });
+        assert!(tip_result.is_err());
+        assert!(blocks_result.is_ok());
+    }
+
+    #[test]
+    fn client_notices_the_connection_reset() {
+        for seed in 0..SEEDS {
+            let faults = Faults {
+                reset_rate: 0.001,
+                delay_rate: 0.1,
+                max_delay: Duration::from_millis(500),
+            };
+            let mut simulation = Simulation::new(seed, faults);
+            let server = simulation.start_node(3000);
+            let node = simulation.start_node(3001);
+            let client = simulation.connect(&node, &server);
+
+            simulation.run_for(Duration::from_secs(30));
+            simulation.network().reset_all();
+            simulation.run_for(Duration::from_secs(10));
+            assert!(
+                client.is_finished(),
+                "seed {}: the client hangs with status {:?}",
+                seed,
+                client.status()
+            );
+        }
+    }
+}
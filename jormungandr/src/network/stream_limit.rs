@@ -0,0 +1,109 @@
+//! limits on the size of the streams of blocks and headers received from
+//! the peers
+//!
+//! The items of a stream are counted as they arrive, before they are
+//! decoded, so a peer sending an endless stream is cut off at the network
+//! layer instead of filling the buffers of the block task.
+
+use crate::settings::start::network::StreamLimits;
+use chain_network::error::{Code as ErrorCode, Error};
+
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum LimitExceeded {
+    #[error("the stream has more than {0} items")]
+    Items(usize),
+    #[error("the stream has more than {0} bytes")]
+    Bytes(u64),
+}
+
+impl From<LimitExceeded> for Error {
+    fn from(e: LimitExceeded) -> Self {
+        Error::new(ErrorCode::ResourceExhausted, e)
+    }
+}
+
+pub struct StreamLimit {
+    max_items: usize,
+    max_bytes: u64,
+    items: usize,
+    bytes: u64,
+}
+
+impl StreamLimit {
+    /// the limit of a stream of headers
+    pub fn headers(limits: &StreamLimits) -> Self {
+        Self::new(limits.max_headers, u64::MAX)
+    }
+
+    /// the limit of a stream of blocks
+    pub fn blocks(limits: &StreamLimits) -> Self {
+        Self::new(limits.max_blocks, limits.max_block_bytes)
+    }
+
+    fn new(max_items: usize, max_bytes: u64) -> Self {
+        StreamLimit {
+            max_items,
+            max_bytes,
+            items: 0,
+            bytes: 0,
+        }
+    }
+
+    /// count an item of the given size in bytes
+    pub fn check(&mut self, size: usize) -> Result<(), LimitExceeded> {
+        self.items += 1;
+        if self.items > self.max_items {
+            return Err(LimitExceeded::Items(self.max_items));
+        }
+        self.bytes = self.bytes.saturating_add(size as u64);
+        if self.bytes > self.max_bytes {
+            return Err(LimitExceeded::Bytes(self.max_bytes));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: StreamLimits = StreamLimits {
+        max_headers: 2,
+        max_blocks: 3,
+        max_block_bytes: 100,
+    };
+
+    #[test]
+    fn limits_are_inclusive() {
+        let mut headers = StreamLimit::headers(&LIMITS);
+        assert!(headers.check(1000).is_ok());
+        assert!(headers.check(1000).is_ok());
+        assert!(headers.check(1).is_err());
+
+        let mut blocks = StreamLimit::blocks(&LIMITS);
+        assert!(blocks.check(60).is_ok());
+        assert!(blocks.check(40).is_ok());
+        assert!(blocks.check(1).is_err());
+    }
+
+    #[test]
+    fn too_many_blocks_exceed_the_limit() {
+        let mut blocks = StreamLimit::blocks(&LIMITS);
+        for _ in 0..LIMITS.max_blocks {
+            blocks.check(1).unwrap();
+        }
+        assert!(matches!(blocks.check(1), Err(LimitExceeded::Items(3))));
+    }
+
+    #[test]
+    fn too_large_blocks_exceed_the_limit() {
+        let mut blocks = StreamLimit::blocks(&LIMITS);
+        assert!(matches!(blocks.check(101), Err(LimitExceeded::Bytes(100))));
+    }
+
+    #[test]
+    fn exceeded_limits_are_resource_exhausted_errors() {
+        let e: Error = LimitExceeded::Items(2).into();
+        assert!(matches!(e.code(), ErrorCode::ResourceExhausted));
+    }
+}
@@ -1,6 +1,6 @@
 use super::{
-    buffer_sizes,
-    convert::Decode,
+    buffer_gauges, buffer_sizes,
+    convert::{self, Decode},
     p2p::{Address, Gossip},
     GlobalStateR,
 };
@@ -8,7 +8,10 @@ use crate::{
     blockcfg::Fragment,
     intercom::{BlockMsg, TransactionMsg},
     settings::start::network::Configuration,
-    utils::async_msg::{self, MessageBox},
+    utils::{
+        async_msg::{self, MessageBox},
+        buffer_gauge::BufferTracker,
+    },
 };
 use chain_network::data as net_data;
 use chain_network::error::{Code, Error};
@@ -24,6 +27,17 @@ use std::mem;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
+/// count an item received from the peer which could not be decoded in
+/// the statistics of the peer
+pub(super) fn record_decode_error(global_state: &GlobalStateR, node_id: Address, e: &Error) {
+    if let Some(code) = convert::decode_error_code(e) {
+        let state = global_state.clone();
+        global_state.spawn(async move {
+            state.peers.record_decode_error(node_id, code).await;
+        });
+    }
+}
+
 fn filter_gossip_node(node: &Gossip, config: &Configuration) -> bool {
     if config.allow_private_addresses {
         node.has_valid_address()
@@ -162,6 +176,7 @@ pub struct FragmentProcessor {
     global_state: GlobalStateR,
     logger: Logger,
     buffered_fragments: Vec<Fragment>,
+    buffer_tracker: BufferTracker,
     pending_processing: PendingProcessing,
 }
 
@@ -178,6 +193,7 @@ impl FragmentProcessor {
             global_state,
             logger,
             buffered_fragments: Vec::with_capacity(buffer_sizes::inbound::FRAGMENTS),
+            buffer_tracker: buffer_gauges::inbound::FRAGMENTS.track(),
             pending_processing: PendingProcessing::default(),
         }
     }
@@ -242,6 +258,7 @@ impl Sink<net_data::Header> for BlockAnnouncementProcessor {
                 "failed to decode incoming block announcement header";
                 "reason" => %e.source().unwrap(),
             );
+            record_decode_error(&self.global_state, self.node_id.clone(), &e);
             e
         })?;
         let node_id = self.node_id.clone();
@@ -302,10 +319,12 @@ impl Sink<net_data::Fragment> for FragmentProcessor {
                 "failed to decode incoming fragment";
                 "reason" => %e.source().unwrap(),
             );
+            record_decode_error(&self.global_state, self.node_id.clone(), &e);
             e
         })?;
         debug!(self.logger, "received fragment"; "hash" => %fragment.hash());
         self.buffered_fragments.push(fragment);
+        self.buffer_tracker.items_queued(1);
         Ok(())
     }
 
@@ -344,10 +363,21 @@ impl Sink<net_data::Fragment> for FragmentProcessor {
     }
 }
 
+impl Drop for FragmentProcessor {
+    fn drop(&mut self) {
+        self.buffer_tracker
+            .items_taken(self.buffered_fragments.len());
+    }
+}
+
 impl FragmentProcessor {
     fn poll_send_fragments(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
         let logger = &self.logger;
-        ready!(self.mbox.poll_ready(cx)).map_err(|e| {
+        let poll = self.mbox.poll_ready(cx);
+        self.buffer_tracker.set_stalled(
+            poll.is_pending() && self.buffered_fragments.len() >= buffer_sizes::inbound::FRAGMENTS,
+        );
+        ready!(poll).map_err(|e| {
             debug!(logger, "error sending fragments for processing"; "reason" => %e);
             Error::new(Code::Internal, e)
         })?;
@@ -355,9 +385,12 @@ impl FragmentProcessor {
             &mut self.buffered_fragments,
             Vec::with_capacity(buffer_sizes::inbound::FRAGMENTS),
         );
+        self.buffer_tracker.items_taken(fragments.len());
         self.mbox
             .start_send(TransactionMsg::SendTransaction(
-                FragmentOrigin::Network,
+                FragmentOrigin::Network {
+                    addr: self.node_id.to_socket_addr(),
+                },
                 fragments,
             ))
             .map_err(|e| {
@@ -403,9 +436,15 @@ impl Sink<net_data::Gossip> for GossipProcessor {
                 "failed to decode incoming gossip";
                 "reason" => %e.source().unwrap(),
             );
+            record_decode_error(&self.global_state, self.node_id.clone(), &e);
             e
         })?;
         debug!(self.logger, "received gossip on {} nodes", nodes.len());
+        let received = nodes.len();
+        let nodes = self.global_state.gossip_cache.retain_new(nodes);
+        self.global_state
+            .stats_counter
+            .add_gossip_recv_cnt(received, received - nodes.len());
         let (nodes, filtered_out): (Vec<_>, Vec<_>) = nodes.into_iter().partition(|node| {
             filter_gossip_node(node, &self.global_state.config) || node.address().is_none()
         });
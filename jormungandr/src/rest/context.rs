@@ -11,7 +11,8 @@ use crate::{
     stats_counter::StatsCounter,
     utils::async_msg::MessageBox,
 };
-use jormungandr_lib::interfaces::NodeState;
+use chain_crypto::Ed25519;
+use jormungandr_lib::{crypto::key::SigningKey, interfaces::NodeState};
 
 use slog::Logger;
 use tokio::sync::RwLock;
@@ -145,4 +146,14 @@ pub struct FullContext {
     pub enclave: Enclave,
     pub network_state: NetworkStateR,
     pub explorer: Option<crate::explorer::Explorer>,
+    pub backup_status: Option<crate::backup::Status>,
+    pub read_only: bool,
+    /// the key identifying the node in the p2p network, signing the
+    /// receipts of the fragments
+    pub node_key: SigningKey<Ed25519>,
+    /// the cap of the node on the content size of the blocks it produces
+    pub max_block_content_size: Option<u32>,
+    /// the maximum number of addresses looked up by a single request to the
+    /// address index
+    pub max_addresses_per_request: usize,
 }
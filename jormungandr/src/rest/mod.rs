@@ -7,11 +7,17 @@ mod v1;
 
 pub use self::context::{Context, ContextLock, FullContext};
 
-use jormungandr_lib::interfaces::{Rest, Tls};
+use crate::utils::listen_socket;
+use jormungandr_lib::interfaces::{Rest, Tls, VirtualHost};
 
 use futures::{channel::mpsc, prelude::*};
-use std::{error::Error, net::SocketAddr, time::Duration};
-use warp::Filter;
+use std::{error::Error, net::SocketAddr, path::PathBuf, time::Duration};
+use tokio::net::TcpListener;
+use warp::{filters::BoxedFilter, host::Authority, Filter};
+
+/// the number of addresses a request to the address index can look up if
+/// not configured
+pub const DEFAULT_MAX_ADDRESSES_PER_REQUEST: usize = 1000;
 
 #[derive(Clone)]
 pub struct ServerStopper(mpsc::Sender<()>);
@@ -30,25 +36,80 @@ pub async fn start_rest_server(config: Rest, explorer_enabled: bool, context: Co
         .await
         .set_server_stopper(ServerStopper(stopper_tx));
 
-    let api =
-        warp::path!("api" / ..).and(v0::filter(context.clone()).or(v1::filter(context.clone())));
+    let virtual_host = virtual_host(config.virtual_host.as_ref());
+    let api = warp::path!("api" / ..).and(
+        v0::filter(context.clone()).or(v1::filter(context.clone(), config.admin_token.clone())),
+    );
     if explorer_enabled {
         let explorer = explorer::filter(context);
-        setup_cors(api.or(explorer), config, stopper_rx).await;
+        setup_cors(virtual_host.and(api.or(explorer)), config, stopper_rx).await;
     } else {
-        setup_cors(api, config, stopper_rx).await;
+        setup_cors(virtual_host.and(api), config, stopper_rx).await;
     }
 }
 
+/// match the requests addressed to this node: the authority of the request,
+/// taken from the `Host` header or from the `:authority` pseudo-header of
+/// HTTP/2, has to name the configured host and the path has to start with the configured
+/// prefix, which is consumed
+fn virtual_host(config: Option<&VirtualHost>) -> BoxedFilter<()> {
+    let mut filter = warp::any().boxed();
+    let config = match config {
+        Some(config) => config,
+        None => return filter,
+    };
+
+    if let Some(expected) = config.host.clone() {
+        filter = filter
+            .and(warp::host::optional())
+            .and_then(move |authority: Option<Authority>| {
+                let matches = authority.map_or(false, |authority| {
+                    authority.host().eq_ignore_ascii_case(&expected)
+                });
+                async move {
+                    if matches {
+                        Ok(())
+                    } else {
+                        Err(warp::reject::not_found())
+                    }
+                }
+            })
+            .untuple_one()
+            .boxed();
+    }
+
+    if let Some(prefix) = &config.path_prefix {
+        for segment in prefix.split('/').filter(|segment| !segment.is_empty()) {
+            let expected = segment.to_owned();
+            filter = filter
+                .and(warp::path::param::<String>())
+                .and_then(move |segment: String| {
+                    let matches = segment == expected;
+                    async move {
+                        if matches {
+                            Ok(())
+                        } else {
+                            Err(warp::reject::not_found())
+                        }
+                    }
+                })
+                .untuple_one()
+                .boxed();
+        }
+    }
+
+    filter
+}
+
 async fn setup_cors<App>(
     app: App,
-    config: Rest,
+    mut config: Rest,
     shutdown_signal: impl Future<Output = ()> + Send + 'static,
 ) where
     App: Filter<Error = warp::Rejection> + Clone + Send + Sync + 'static,
     App::Extract: warp::Reply,
 {
-    if let Some(cors_config) = config.cors {
+    if let Some(cors_config) = config.cors.take() {
         let allowed_origins: Vec<&str> = cors_config
             .allowed_origins
             .iter()
@@ -61,16 +122,54 @@ async fn setup_cors<App>(
             cors = cors.max_age(Duration::from_secs(max_age));
         }
 
-        run_server_with_app(app.with(cors), config.listen, config.tls, shutdown_signal).await;
+        run_servers(app.with(cors), config, shutdown_signal).await;
     } else {
-        run_server_with_app(app, config.listen, config.tls, shutdown_signal).await;
+        run_servers(app, config, shutdown_signal).await;
     }
 }
 
+async fn run_servers<App>(
+    app: App,
+    config: Rest,
+    shutdown_signal: impl Future<Output = ()> + Send + 'static,
+) where
+    App: Filter<Error = warp::Rejection> + Clone + Send + Sync + 'static,
+    App::Extract: warp::Reply,
+{
+    let shutdown_signal = shutdown_signal.shared();
+    let Rest {
+        listen,
+        tls,
+        reuse_port,
+        unix_socket,
+        ..
+    } = config;
+    let disable_tcp = unix_socket
+        .as_ref()
+        .map_or(false, |unix_socket| unix_socket.disable_tcp);
+
+    let tcp_server = {
+        let app = app.clone();
+        let shutdown_signal = shutdown_signal.clone();
+        async move {
+            if !disable_tcp {
+                run_server_with_app(app, listen, tls, reuse_port, shutdown_signal).await;
+            }
+        }
+    };
+    let unix_server = async move {
+        if let Some(unix_socket) = unix_socket {
+            run_unix_server_with_app(app, unix_socket.path, shutdown_signal).await;
+        }
+    };
+    future::join(tcp_server, unix_server).await;
+}
+
 async fn run_server_with_app<App>(
     app: App,
     listen_addr: SocketAddr,
     tls_config: Option<Tls>,
+    reuse_port: bool,
     shutdown_signal: impl Future<Output = ()> + Send + 'static,
 ) where
     App: Filter<Error = warp::Rejection> + Clone + Send + Sync + 'static,
@@ -85,11 +184,98 @@ async fn run_server_with_app<App>(
             .bind_with_graceful_shutdown(listen_addr, shutdown_signal);
         server_fut.await;
     } else {
-        let (_, server_fut) = server.bind_with_graceful_shutdown(listen_addr, shutdown_signal);
-        server_fut.await;
+        let listener = match listen_socket::inherited(listen_socket::REST) {
+            Some(listener) => Ok(listener),
+            None => listen_socket::bind(listen_addr, reuse_port),
+        };
+        let mut listener = match listener.and_then(TcpListener::from_std) {
+            Ok(listener) => listener,
+            Err(e) => panic!("error binding to {}: {}", listen_addr, e),
+        };
+        server
+            .serve_incoming_with_graceful_shutdown(listener.incoming(), shutdown_signal)
+            .await;
     };
 }
 
+#[cfg(unix)]
+async fn run_unix_server_with_app<App>(
+    app: App,
+    path: PathBuf,
+    shutdown_signal: impl Future<Output = ()> + Send + 'static,
+) where
+    App: Filter<Error = warp::Rejection> + Clone + Send + Sync + 'static,
+    App::Extract: warp::Reply,
+{
+    use std::fs;
+    use std::os::unix::fs::FileTypeExt;
+    use tokio::net::UnixListener;
+
+    // the socket left by a previous run would make the bind fail
+    if let Ok(metadata) = fs::symlink_metadata(&path) {
+        if metadata.file_type().is_socket() {
+            let _ = fs::remove_file(&path);
+        }
+    }
+    let listener = match bind_unix_socket(&path) {
+        Ok(listener) => listener,
+        Err(e) => panic!("error binding to {}: {}", path.display(), e),
+    };
+    let mut listener = match UnixListener::from_std(listener) {
+        Ok(listener) => listener,
+        Err(e) => panic!("error binding to {}: {}", path.display(), e),
+    };
+
+    warp::serve(app)
+        .serve_incoming_with_graceful_shutdown(listener.incoming(), shutdown_signal)
+        .await;
+    let _ = fs::remove_file(&path);
+}
+
+/// bind the socket in a directory only the node can enter, where it is
+/// given its permissions before it is moved to the path: only the processes
+/// running as the user or the group of the node can ever connect to it
+#[cfg(unix)]
+fn bind_unix_socket(path: &std::path::Path) -> std::io::Result<std::os::unix::net::UnixListener> {
+    use std::fs::{self, DirBuilder, Permissions};
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use std::os::unix::net::UnixListener;
+    use std::path::Path;
+
+    let file_name = path.file_name().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "the path has no file name",
+        )
+    })?;
+    let parent = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut private_dir_name = std::ffi::OsString::from(".");
+    private_dir_name.push(file_name);
+    private_dir_name.push(format!(".{}", std::process::id()));
+    let private_dir = parent.join(private_dir_name);
+    DirBuilder::new().mode(0o700).create(&private_dir)?;
+
+    let staged = private_dir.join(file_name);
+    let res = UnixListener::bind(&staged).and_then(|listener| {
+        listener.set_nonblocking(true)?;
+        fs::set_permissions(&staged, Permissions::from_mode(0o660))?;
+        fs::rename(&staged, path)?;
+        Ok(listener)
+    });
+    let _ = fs::remove_file(&staged);
+    let _ = fs::remove_dir(&private_dir);
+    res
+}
+
+#[cfg(not(unix))]
+async fn run_unix_server_with_app<App>(
+    _app: App,
+    _path: PathBuf,
+    _shutdown_signal: impl Future<Output = ()> + Send + 'static,
+) {
+    panic!("serving the REST API on a Unix socket is not supported on this platform");
+}
+
 pub(self) fn display_internal_server_error(err: &impl Error) -> String {
     use std::fmt::{self, Write};
 
@@ -106,3 +292,115 @@ pub(self) fn display_internal_server_error(err: &impl Error) -> String {
 
     error_to_body(err).unwrap_or_else(|err| format!("failed to process internal error: {}", err))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(host: Option<&str>, path_prefix: Option<&str>) -> VirtualHost {
+        VirtualHost {
+            host: host.map(ToOwned::to_owned),
+            path_prefix: path_prefix.map(ToOwned::to_owned),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_socket_is_only_open_to_the_user_and_group() {
+        use std::os::unix::fs::PermissionsExt;
+        use std::os::unix::net::UnixStream;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rest.sock");
+        let _listener = bind_unix_socket(&path).unwrap();
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o660);
+        assert!(UnixStream::connect(&path).is_ok());
+        // only the socket is left in the directory
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn any_request_matches_without_virtual_host() {
+        let filter = virtual_host(None);
+        assert!(
+            warp::test::request()
+                .path("/api/v0/node/stats")
+                .matches(&filter)
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn host_header_is_matched_without_the_port() {
+        let filter = virtual_host(Some(&config(Some("node.example"), None)));
+        assert!(
+            warp::test::request()
+                .header("host", "Node.Example:8443")
+                .matches(&filter)
+                .await
+        );
+        assert!(
+            !warp::test::request()
+                .header("host", "other.example")
+                .matches(&filter)
+                .await
+        );
+        assert!(!warp::test::request().matches(&filter).await);
+    }
+
+    #[tokio::test]
+    async fn authority_of_the_uri_is_matched() {
+        // HTTP/2 clients send the `:authority` pseudo-header, which ends
+        // up in the URI of the request, and no `Host` header
+        let filter = virtual_host(Some(&config(Some("node.example"), None)));
+        assert!(
+            warp::test::request()
+                .path("https://node.example:8443/api/v0/node/stats")
+                .matches(&filter)
+                .await
+        );
+        assert!(
+            !warp::test::request()
+                .path("https://other.example/api/v0/node/stats")
+                .matches(&filter)
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn ipv6_host_is_matched_without_the_port() {
+        let filter = virtual_host(Some(&config(Some("[::1]"), None)));
+        assert!(
+            warp::test::request()
+                .header("host", "[::1]:8443")
+                .matches(&filter)
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn path_prefix_is_consumed() {
+        let filter = virtual_host(Some(&config(None, Some("/node-1/"))))
+            .and(warp::path!("api" / "v0" / "node" / "stats"));
+        assert!(
+            warp::test::request()
+                .path("/node-1/api/v0/node/stats")
+                .matches(&filter)
+                .await
+        );
+        assert!(
+            !warp::test::request()
+                .path("/node-2/api/v0/node/stats")
+                .matches(&filter)
+                .await
+        );
+        assert!(
+            !warp::test::request()
+                .path("/api/v0/node/stats")
+                .matches(&filter)
+                .await
+        );
+    }
+}
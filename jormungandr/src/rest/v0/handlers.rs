@@ -26,13 +26,68 @@ pub async fn get_message_logs(context: ContextLock) -> Result<impl Reply, Reject
         .map(|r| warp::reply::json(&r))
 }
 
+pub async fn decode_fragment(message: bytes::Bytes) -> Result<impl Reply, Rejection> {
+    logic::decode_fragment(&message)
+        .await
+        .map(|r| warp::reply::json(&r))
+        .map_err(warp::reject::custom)
+}
+
+pub async fn dry_run_fragment(
+    message: bytes::Bytes,
+    context: ContextLock,
+) -> Result<impl Reply, Rejection> {
+    let context = context.read().await;
+    logic::dry_run_fragment(&context, &message)
+        .await
+        .map(|r| warp::reply::json(&r))
+        .map_err(warp::reject::custom)
+}
+
+pub async fn get_fragment(
+    fragment_id_hex: String,
+    context: ContextLock,
+) -> Result<impl Reply, Rejection> {
+    let context = context.read().await;
+    logic::get_fragment(&context, &fragment_id_hex)
+        .await
+        .map_err(warp::reject::custom)?
+        .map(|r| warp::reply::json(&r))
+        .ok_or_else(warp::reject::not_found)
+}
+
+#[derive(Deserialize)]
+pub struct PostMessageQuery {
+    receipt: Option<bool>,
+}
+
 pub async fn post_message(
     message: bytes::Bytes,
+    query: PostMessageQuery,
+    context: ContextLock,
+) -> Result<warp::reply::Response, Rejection> {
+    let context = context.read().await;
+    if query.receipt.unwrap_or(false) {
+        logic::post_message_with_receipt(&context, &message)
+            .await
+            .map(|r| warp::reply::json(&r).into_response())
+            .map_err(warp::reject::custom)
+    } else {
+        logic::post_message(&context, &message)
+            .await
+            .map(Reply::into_response)
+            .map_err(warp::reject::custom)
+    }
+}
+
+pub async fn post_addresses_used(
+    addresses: Vec<String>,
     context: ContextLock,
 ) -> Result<impl Reply, Rejection> {
     let context = context.read().await;
-    logic::post_message(&context, &message)
+    logic::post_addresses_used(&context, addresses)
         .await
+        .map(|r| warp::reply::json(&r))
         .map_err(warp::reject::custom)
 }
 
@@ -98,6 +153,22 @@ pub async fn get_stake_distribution_at(
         .ok_or_else(warp::reject::not_found)
 }
 
+pub async fn get_stake_snapshot(epoch: u32, context: ContextLock) -> Result<impl Reply, Rejection> {
+    let context = context.read().await;
+    logic::get_stake_snapshot(&context, epoch)
+        .await
+        .map_err(warp::reject::custom)?
+        .map(|snapshot| {
+            let body = warp::hyper::Body::wrap_stream(snapshot);
+            warp::reply::with_header(
+                warp::reply::Response::new(body),
+                "content-type",
+                "application/json",
+            )
+        })
+        .ok_or_else(warp::reject::not_found)
+}
+
 pub async fn get_settings(context: ContextLock) -> Result<impl Reply, Rejection> {
     let context = context.read().await;
     logic::get_settings(&context)
@@ -150,6 +221,18 @@ pub async fn get_leaders_logs(context: ContextLock) -> Result<impl Reply, Reject
         .map_err(warp::reject::custom)
 }
 
+pub async fn get_leaders_logs_epoch(
+    epoch: u32,
+    context: ContextLock,
+) -> Result<impl Reply, Rejection> {
+    let context = context.read().await;
+    logic::get_leaders_logs_epoch(&context, epoch)
+        .await
+        .map_err(warp::reject::custom)?
+        .map(|r| warp::reply::json(&r))
+        .ok_or_else(warp::reject::not_found)
+}
+
 pub async fn get_stake_pools(context: ContextLock) -> Result<impl Reply, Rejection> {
     let context = context.read().await;
     logic::get_stake_pools(&context)
@@ -166,6 +249,14 @@ pub async fn get_network_stats(context: ContextLock) -> Result<impl Reply, Rejec
         .map_err(warp::reject::custom)
 }
 
+pub async fn get_network_gossip_settings(context: ContextLock) -> Result<impl Reply, Rejection> {
+    let context = context.read().await;
+    logic::get_network_gossip_settings(&context)
+        .await
+        .map(|r| warp::reply::json(&r))
+        .map_err(warp::reject::custom)
+}
+
 pub async fn get_rewards_info_epoch(
     epoch: u32,
     context: ContextLock,
@@ -189,6 +280,37 @@ pub async fn get_rewards_info_history(
         .map_err(warp::reject::custom)
 }
 
+#[derive(Deserialize)]
+pub struct RewardsHistoryCsvQuery {
+    epochs: Option<usize>,
+}
+
+pub async fn get_rewards_info_history_csv(
+    query: RewardsHistoryCsvQuery,
+    context: ContextLock,
+) -> Result<impl Reply, Rejection> {
+    let context = context.read().await;
+    logic::get_rewards_info_history_csv(&context, query.epochs)
+        .await
+        .map(|csv| {
+            let body = warp::hyper::Body::wrap_stream(csv);
+            warp::reply::with_header(warp::reply::Response::new(body), "content-type", "text/csv")
+        })
+        .map_err(warp::reject::custom)
+}
+
+pub async fn get_epoch_accounting(
+    epoch: u32,
+    context: ContextLock,
+) -> Result<impl Reply, Rejection> {
+    let context = context.read().await;
+    logic::get_epoch_accounting(&context, epoch)
+        .await
+        .map_err(warp::reject::custom)?
+        .map(|r| warp::reply::json(&r))
+        .ok_or_else(warp::reject::not_found)
+}
+
 pub async fn get_utxo(
     fragment_id_hex: String,
     output_index: u8,
@@ -246,6 +368,14 @@ pub async fn get_network_p2p_available(context: ContextLock) -> Result<impl Repl
         .map_err(warp::reject::custom)
 }
 
+pub async fn get_network_p2p_self(context: ContextLock) -> Result<impl Reply, Rejection> {
+    let context = context.read().await;
+    logic::get_network_p2p_self(&context)
+        .await
+        .map(|r| warp::reply::json(&r))
+        .map_err(warp::reject::custom)
+}
+
 pub async fn get_network_p2p_view(context: ContextLock) -> Result<impl Reply, Rejection> {
     let context = context.read().await;
     logic::get_network_p2p_view(&context)
@@ -280,3 +410,27 @@ pub async fn get_active_vote_plans(context: ContextLock) -> Result<impl Reply, R
         .map(|r| warp::reply::json(&r))
         .map_err(warp::reject::custom)
 }
+
+pub async fn get_vote_plan_stats(
+    id_hex: String,
+    context: ContextLock,
+) -> Result<impl Reply, Rejection> {
+    let context = context.read().await;
+    logic::get_vote_plan_stats(&context, &id_hex)
+        .await
+        .map_err(warp::reject::custom)?
+        .map(|r| warp::reply::json(&r))
+        .ok_or_else(warp::reject::not_found)
+}
+
+pub async fn get_archived_vote_plan(
+    id_hex: String,
+    context: ContextLock,
+) -> Result<impl Reply, Rejection> {
+    let context = context.read().await;
+    logic::get_archived_vote_plan(&context, &id_hex)
+        .await
+        .map_err(warp::reject::custom)?
+        .map(|r| warp::reply::json(&r))
+        .ok_or_else(warp::reject::not_found)
+}
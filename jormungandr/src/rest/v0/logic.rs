@@ -7,9 +7,11 @@
 //   they are 400 or 500.
 
 use crate::{
-    blockchain::StorageError,
+    blockchain::{Ref, StorageError},
     diagnostic::Diagnostic,
+    explorer::{ExplorerAddress, ExplorerBlock},
     intercom::{self, NetworkMsg, TransactionMsg},
+    network,
     rest::Context,
     secure::NodeSecret,
 };
@@ -22,24 +24,30 @@ use chain_crypto::{
 };
 use chain_impl_mockchain::{
     account::{AccountAlg, Identifier},
-    block::Block as ChainBlock,
+    block::{Block as ChainBlock, BlockDate},
+    certificate::VotePlanId,
     fragment::{Fragment, FragmentId},
     key::Hash,
     leadership::{Leader, LeadershipConsensus},
-    transaction::Transaction,
+    ledger::{Ledger, LedgerParameters},
+    transaction::{self, InputEnum, Payload, Transaction},
     value::{Value, ValueError},
 };
 use jormungandr_lib::{
     interfaces::{
-        AccountState, EnclaveLeaderId, EpochRewardsInfo, FragmentLog, FragmentOrigin,
-        LeadershipLog, NodeStats, NodeStatsDto, PeerStats, Rewards as StakePoolRewards,
-        SettingsDto, StakeDistribution, StakeDistributionDto, StakePoolStats, TaxTypeSerde,
-        TransactionOutput, VotePlanStatus,
+        AccountBalanceChange, AccountIdentifier, AccountState, ActiveSlotCoefficient,
+        AddressActivityBlock, AddressUsage, CertificateFees, DryRunOutcome, EnclaveLeaderId,
+        EpochAccounting, EpochRewardsInfo, FragmentBlockContext, FragmentDescription,
+        FragmentDetails, FragmentDryRun, FragmentLog, FragmentOrigin, FragmentReceipt,
+        FragmentStatus, GossipSettings, LeadershipLog, NodeSelfView, NodeState, NodeStats,
+        NodeStatsDto, PeerStats, PoolStakeSnapshot, Rewards as StakePoolRewards, SettingsDto,
+        Stake, StakeDistribution, StakeDistributionDto, StakePoolStats, SyncState, TaxTypeSerde,
+        TransactionOutput, VotePlanStats, VotePlanStatus,
     },
-    time::SystemTime,
+    time::{SecondsSinceUnixEpoch, SystemTime},
 };
 
-use std::sync::Arc;
+use std::{convert::TryFrom, sync::Arc, time::Duration};
 
 use futures::{channel::mpsc::SendError, channel::mpsc::TrySendError, prelude::*};
 
@@ -74,6 +82,24 @@ pub enum Error {
     InvalidTopic,
     #[error(transparent)]
     Hex(#[from] hex::FromHexError),
+    #[error("the node is read-only and does not accept fragments")]
+    ReadOnly,
+    #[error("invalid address {0}")]
+    InvalidAddress(String),
+    #[error("the address index requires the explorer to be enabled")]
+    ExplorerDisabled,
+    #[error("too many addresses in the request, the maximum is {max}")]
+    TooManyAddresses { max: usize },
+    #[error("receipts are only issued for vote casts")]
+    ReceiptNotAvailable,
+    #[error("the fragment was rejected: {0}")]
+    FragmentRejected(String),
+    #[error("the fragment is not pending in the mempool")]
+    FragmentNotPending,
+    #[error("invalid vote plan id {0}")]
+    InvalidVotePlanId(String),
+    #[error("the vote plan statistics are not enabled")]
+    VotePlanStatsDisabled,
 }
 
 fn parse_account_id(id_hex: &str) -> Result<Identifier, Error> {
@@ -126,11 +152,314 @@ pub async fn get_message_logs(context: &Context) -> Result<Vec<FragmentLog>, Err
 pub async fn post_message(context: &Context, message: &[u8]) -> Result<String, Error> {
     let fragment = Fragment::deserialize(message).map_err(Error::Deserialize)?;
     let fragment_id = fragment.id().to_string();
+    let full_context = context.try_full()?;
+    if full_context.read_only {
+        return Err(Error::ReadOnly);
+    }
     let msg = TransactionMsg::SendTransaction(FragmentOrigin::Rest, vec![fragment]);
-    context.try_full()?.transaction_task.clone().try_send(msg)?;
+    full_context.transaction_task.clone().try_send(msg)?;
     Ok(fragment_id)
 }
 
+/// post a vote cast and wait for the mempool to accept it, returning a
+/// receipt signed with the node key
+pub async fn post_message_with_receipt(
+    context: &Context,
+    message: &[u8],
+) -> Result<FragmentReceipt, Error> {
+    let fragment = Fragment::deserialize(message).map_err(Error::Deserialize)?;
+    if !matches!(fragment, Fragment::VoteCast(_)) {
+        return Err(Error::ReceiptNotAvailable);
+    }
+    let fragment_id = fragment.id();
+    let full_context = context.try_full()?;
+    if full_context.read_only {
+        return Err(Error::ReadOnly);
+    }
+    let tip = context.blockchain_tip()?.get_ref().await.hash();
+    let logger = context.logger()?.new(o!("request" => "post_message"));
+
+    // the fragment task processes the messages in order, so the status is
+    // read once the fragment went through the mempool checks
+    let mut mbox = full_context.transaction_task.clone();
+    mbox.send(TransactionMsg::SendTransaction(
+        FragmentOrigin::Rest,
+        vec![fragment],
+    ))
+    .await?;
+    let (reply_handle, reply_future) = intercom::unary_reply(logger.clone());
+    mbox.send(TransactionMsg::GetStatuses(vec![fragment_id], reply_handle))
+        .await
+        .map_err(|e| {
+            debug!(&logger, "error getting the fragment status"; "reason" => %e);
+            Error::MsgSendError(e)
+        })?;
+
+    match reply_future.await?.remove(&fragment_id) {
+        Some(FragmentStatus::Pending) => Ok(FragmentReceipt::new(
+            fragment_id.into(),
+            tip.into(),
+            &full_context.node_key,
+        )),
+        Some(FragmentStatus::Rejected { reason, .. }) => Err(Error::FragmentRejected(reason)),
+        _ => Err(Error::FragmentNotPending),
+    }
+}
+
+pub async fn decode_fragment(message: &[u8]) -> Result<FragmentDescription, Error> {
+    let fragment = Fragment::deserialize(message).map_err(Error::Deserialize)?;
+    Ok(FragmentDescription::from(&fragment))
+}
+
+/// apply the fragment to a copy of the ledger of the tip, at the date of
+/// the current slot, without submitting it to the mempool
+pub async fn dry_run_fragment(context: &Context, message: &[u8]) -> Result<FragmentDryRun, Error> {
+    let fragment = Fragment::deserialize(message).map_err(Error::Deserialize)?;
+    let tip = context.blockchain_tip()?.get_ref().await;
+    let date = current_date(&tip);
+    let ledger = tip.ledger();
+
+    let outcome = dry_run(&ledger, tip.epoch_ledger_parameters(), &fragment, date);
+
+    Ok(FragmentDryRun {
+        fragment_id: fragment.id().into(),
+        tip: tip.hash().into(),
+        date: date.into(),
+        outcome,
+    })
+}
+
+fn dry_run(
+    ledger: &Ledger,
+    ledger_params: &LedgerParameters,
+    fragment: &Fragment,
+    date: BlockDate,
+) -> DryRunOutcome {
+    match ledger.apply_fragment(ledger_params, fragment, date) {
+        Ok(new_ledger) => DryRunOutcome::Applied {
+            balances: fragment_accounts(fragment)
+                .into_iter()
+                .map(|id| AccountBalanceChange {
+                    before: account_balance(ledger, &id),
+                    after: account_balance(&new_ledger, &id),
+                    account: AccountIdentifier::from(transaction::AccountIdentifier::Single(id)),
+                })
+                .collect(),
+        },
+        Err(error) => {
+            use std::error::Error as _;
+            let reason = if let Some(source) = error.source() {
+                format!("{}: {}", error, source)
+            } else {
+                error.to_string()
+            };
+            DryRunOutcome::Rejected {
+                reason,
+                ledger_error: format!("{:?}", error),
+            }
+        }
+    }
+}
+
+/// the date of the current slot, or the date of the tip if the clock is
+/// behind it
+fn current_date(tip: &Ref) -> BlockDate {
+    let tip_date = tip.block_date();
+    let era = tip.epoch_leadership_schedule().era();
+    tip.time_frame()
+        .slot_at(&std::time::SystemTime::now())
+        .and_then(|slot| era.from_slot_to_era(slot))
+        .map_or(tip_date, |position| {
+            let date = BlockDate {
+                epoch: position.epoch.0,
+                slot_id: position.slot.0,
+            };
+            date.max(tip_date)
+        })
+}
+
+/// the single accounts the fragment spends from or pays to, in order of
+/// appearance
+fn fragment_accounts(fragment: &Fragment) -> Vec<Identifier> {
+    fn transaction_accounts<P: Payload>(tx: &Transaction<P>) -> Vec<Identifier> {
+        let slice = tx.as_slice();
+        let inputs = slice
+            .inputs()
+            .iter()
+            .filter_map(|input| match input.to_enum() {
+                InputEnum::AccountInput(account, _) => account.to_single_account(),
+                InputEnum::UtxoInput(_) => None,
+            });
+        let outputs = slice
+            .outputs()
+            .iter()
+            .filter_map(|output| match output.address.kind() {
+                chain_addr::Kind::Account(key) => Some(Identifier::from(key.clone())),
+                _ => None,
+            });
+
+        let mut accounts = Vec::new();
+        for account in inputs.chain(outputs) {
+            if !accounts.contains(&account) {
+                accounts.push(account);
+            }
+        }
+        accounts
+    }
+
+    match fragment {
+        Fragment::Initial(_) => Vec::new(),
+        Fragment::OldUtxoDeclaration(_) => Vec::new(),
+        Fragment::Transaction(ref tx) => transaction_accounts(tx),
+        Fragment::OwnerStakeDelegation(ref tx) => transaction_accounts(tx),
+        Fragment::StakeDelegation(ref tx) => transaction_accounts(tx),
+        Fragment::PoolRegistration(ref tx) => transaction_accounts(tx),
+        Fragment::PoolRetirement(ref tx) => transaction_accounts(tx),
+        Fragment::PoolUpdate(ref tx) => transaction_accounts(tx),
+        Fragment::UpdateProposal(_) => Vec::new(),
+        Fragment::UpdateVote(_) => Vec::new(),
+        Fragment::VotePlan(ref tx) => transaction_accounts(tx),
+        Fragment::VoteCast(ref tx) => transaction_accounts(tx),
+        Fragment::VoteTally(ref tx) => transaction_accounts(tx),
+        Fragment::EncryptedVoteTally(ref tx) => transaction_accounts(tx),
+    }
+}
+
+fn account_balance(ledger: &Ledger, id: &Identifier) -> Option<jormungandr_lib::interfaces::Value> {
+    ledger
+        .accounts()
+        .get_state(id)
+        .ok()
+        .map(|state| state.value().into())
+}
+
+pub async fn get_fragment(
+    context: &Context,
+    fragment_id_hex: &str,
+) -> Result<Option<FragmentDetails>, Error> {
+    let fragment_id = parse_fragment_id(fragment_id_hex)?;
+    let full_context = context.try_full()?;
+    let logger = context.logger()?.new(o!("request" => "fragment"));
+    let (reply_handle, reply_future) = intercom::unary_reply(logger.clone());
+    full_context
+        .transaction_task
+        .clone()
+        .send(TransactionMsg::GetFragment(fragment_id, reply_handle))
+        .await
+        .map_err(|e| {
+            debug!(&logger, "error getting the fragment"; "reason" => %e);
+            Error::MsgSendError(e)
+        })?;
+
+    let (status, pending_fragment) = match reply_future.await? {
+        Some((status, fragment)) => (Some(status), fragment),
+        None => (None, None),
+    };
+
+    let block_id = match &status {
+        Some(FragmentStatus::InABlock { block, .. })
+        | Some(FragmentStatus::AlreadyConfirmed { block, .. }) => Some(block.into_hash()),
+        Some(_) => None,
+        // the fragment logs are only kept in memory for a while, the
+        // explorer may still know the block containing the fragment
+        None => match &full_context.explorer {
+            Some(explorer) => {
+                explorer
+                    .db
+                    .find_block_hash_by_transaction(&fragment_id)
+                    .await
+            }
+            None => None,
+        },
+    };
+
+    let in_block = match block_id {
+        Some(block_id) => context
+            .blockchain()?
+            .storage()
+            .get(block_id)?
+            .and_then(|block| {
+                block
+                    .contents
+                    .iter()
+                    .enumerate()
+                    .find(|(_, fragment)| fragment.id() == fragment_id)
+                    .map(|(index, fragment)| {
+                        let block_context = FragmentBlockContext {
+                            block: block_id.into(),
+                            date: block.header.block_date().into(),
+                            chain_length: block.header.chain_length().into(),
+                            index: index as u32,
+                        };
+                        (fragment.clone(), block_context)
+                    })
+            }),
+        None => None,
+    };
+
+    let (status, fragment, block) = match (status, in_block) {
+        (status, Some((fragment, block))) => (
+            // the refusal of a re-submission is still reported as such
+            status
+                .filter(FragmentStatus::is_already_confirmed)
+                .unwrap_or(FragmentStatus::InABlock {
+                    date: block.date,
+                    block: block.block,
+                }),
+            Some(fragment),
+            Some(block),
+        ),
+        (Some(status), None) => (status, pending_fragment, None),
+        (None, None) => return Ok(None),
+    };
+
+    let fragment = fragment
+        .map(|fragment| fragment.serialize_as_vec().map_err(Error::Serialize))
+        .transpose()?
+        .map(hex::encode);
+
+    Ok(Some(FragmentDetails {
+        id: fragment_id.into(),
+        status,
+        fragment,
+        block,
+    }))
+}
+
+pub async fn post_addresses_used(
+    context: &Context,
+    addresses: Vec<String>,
+) -> Result<Vec<AddressUsage>, Error> {
+    let full_context = context.try_full()?;
+    let explorer = full_context
+        .explorer
+        .as_ref()
+        .ok_or(Error::ExplorerDisabled)?;
+    if addresses.len() > full_context.max_addresses_per_request {
+        return Err(Error::TooManyAddresses {
+            max: full_context.max_addresses_per_request,
+        });
+    }
+    let mut usages = Vec::with_capacity(addresses.len());
+    for address in addresses {
+        let parsed = ExplorerAddress::from_bech32(&address)
+            .ok_or_else(|| Error::InvalidAddress(address.clone()))?;
+        let activity = explorer.db.get_address_activity(&parsed).await;
+        let to_activity_block = |block: &ExplorerBlock| AddressActivityBlock {
+            block: block.id.into(),
+            date: block.date.into(),
+            chain_length: block.chain_length.into(),
+        };
+        usages.push(AddressUsage {
+            address,
+            used: activity.is_some(),
+            first_activity: activity.as_ref().map(|(first, _)| to_activity_block(first)),
+            last_activity: activity.as_ref().map(|(_, last)| to_activity_block(last)),
+        });
+    }
+    Ok(usages)
+}
+
 pub async fn get_tip(context: &Context) -> Result<String, Error> {
     Ok(context.blockchain_tip()?.get_ref().await.hash().to_string())
 }
@@ -211,10 +540,16 @@ async fn create_stats(context: &Context) -> Result<Option<NodeStats>, Error> {
         .collect::<Result<(), ValueError>>()?;
     let nodes_count = full_context.network_state.topology().nodes_count().await;
     let tip_header = tip.header();
+    let protocol_max_size = tip.epoch_ledger_parameters().block_content_max_size;
     let stats = &full_context.stats_counter;
     let node_stats = NodeStats {
         block_recv_cnt: stats.block_recv_cnt(),
         last_block_content_size: tip_header.block_content_size(),
+        max_block_content_size: Some(
+            full_context
+                .max_block_content_size
+                .map_or(protocol_max_size, |cap| cap.min(protocol_max_size)),
+        ),
         last_block_date: tip_header.block_date().to_string().into(),
         last_block_fees: block_fee_sum.0,
         last_block_hash: tip_header.hash().to_string().into(),
@@ -223,6 +558,20 @@ async fn create_stats(context: &Context) -> Result<Option<NodeStats>, Error> {
         last_block_time: SystemTime::from(tip.time()).into(),
         last_block_tx: block_tx_count,
         last_received_block_time: stats.slot_start_time().map(SystemTime::from),
+        mempool_usage: stats.mempool_usage(),
+        gossip_recv_cnt: stats.gossip_recv_cnt(),
+        gossip_dup_cnt: stats.gossip_dup_cnt(),
+        server_requests_in_flight: full_context.network_state.server_requests_in_flight(),
+        client_requests_in_flight: full_context.network_state.client_requests_in_flight(),
+        block_cache_hit_cnt: stats.block_cache_hit_cnt(),
+        block_cache_miss_cnt: stats.block_cache_miss_cnt(),
+        sync_state: Some(sync_state(context, &tip, stats.slot_start_time())),
+        block_propagation: Some(stats.block_propagation()),
+        mempool: stats.mempool_summary(),
+        port_mapping: full_context.network_state.port_mapping_status(),
+        peer_clock_skew: stats.peer_clock_skew(),
+        ntp_clock_skew: stats.ntp_clock_skew(),
+        leader_wake_delay: Some(stats.leader_wake_delay()),
         peer_available_cnt: nodes_count.available_count,
         peer_connected_cnt: stats.peer_connected_cnt(),
         peer_quarantined_cnt: nodes_count.quarantined_count,
@@ -234,6 +583,55 @@ async fn create_stats(context: &Context) -> Result<Option<NodeStats>, Error> {
     Ok(Some(node_stats))
 }
 
+/// the tip is in sync up to this number of slots behind the current slot,
+/// as slots without a block are expected
+const SYNC_TOLERANCE_SLOTS: u64 = 100;
+
+/// the node is stalled when it is behind and no block was received for
+/// this long
+const SYNC_STALL_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+fn sync_state(
+    context: &Context,
+    tip: &Ref,
+    last_received: Option<SecondsSinceUnixEpoch>,
+) -> SyncState {
+    if *context.node_state() != NodeState::Running {
+        return SyncState::Bootstrapping;
+    }
+
+    classify_sync(
+        std::time::SystemTime::now(),
+        tip.time(),
+        tip.time_frame().slot_duration(),
+        last_received.map(SystemTime::from),
+    )
+}
+
+/// the sync state of a running node at `now`, given the time of the slot of
+/// its tip and the time the last block was received
+fn classify_sync(
+    now: std::time::SystemTime,
+    tip_time: std::time::SystemTime,
+    slot_duration: u64,
+    last_received: Option<SystemTime>,
+) -> SyncState {
+    let behind_by = now
+        .duration_since(tip_time)
+        .map_or(0, |age| age.as_secs() / slot_duration.max(1));
+    if behind_by <= SYNC_TOLERANCE_SLOTS {
+        return SyncState::Synced;
+    }
+
+    let since = last_received.unwrap_or_else(|| SystemTime::from(tip_time));
+    let quiet_for = now.duration_since(*since.as_ref()).unwrap_or_default();
+    if quiet_for > SYNC_STALL_TIMEOUT {
+        SyncState::Stalled { since }
+    } else {
+        SyncState::CatchingUp { behind_by }
+    }
+}
+
 pub async fn get_block_id(context: &Context, block_id_hex: &str) -> Result<Option<Vec<u8>>, Error> {
     context
         .blockchain()?
@@ -346,6 +744,83 @@ pub async fn get_stake_distribution_at(
         }))
 }
 
+/// The stake of every delegator used for the leader election of the given
+/// epoch, as the JSON of a `StakeSnapshot`. The snapshot can hold every
+/// account of the network, so it is serialized one pool at a time as the
+/// response is streamed.
+pub async fn get_stake_snapshot(
+    context: &Context,
+    epoch: u32,
+) -> Result<Option<impl Stream<Item = Result<String, serde_json::Error>> + Send + 'static>, Error> {
+    let mut tip_ref = context.blockchain_tip()?.get_ref().await;
+
+    if epoch > tip_ref.block_date().epoch {
+        return Ok(None);
+    }
+
+    while tip_ref.block_date().epoch != epoch {
+        match tip_ref.last_ref_previous_epoch() {
+            Some(previous_epoch) if epoch <= previous_epoch.block_date().epoch => {
+                tip_ref = Arc::clone(previous_epoch);
+            }
+            _ => return Ok(None),
+        }
+    }
+
+    let leadership = Arc::clone(tip_ref.epoch_leadership_schedule());
+    let (dangling, unassigned, pool_ids) = match leadership.stake_distribution() {
+        Some(distribution) => (
+            Stake::from(distribution.dangling),
+            Stake::from(distribution.unassigned),
+            distribution.to_pools.keys().cloned().collect::<Vec<_>>(),
+        ),
+        None => return Ok(None),
+    };
+
+    let pools = pool_ids.into_iter().map(move |pool_id| {
+        let pool = &leadership.stake_distribution().unwrap().to_pools[&pool_id];
+        PoolStakeSnapshot {
+            id: pool_id.into(),
+            total: pool.stake.total.into(),
+            delegators: pool
+                .stake
+                .accounts
+                .iter()
+                .map(|(account, stake)| (account.clone().into(), (*stake).into()))
+                .collect(),
+        }
+    });
+
+    Ok(Some(stake_snapshot_stream(
+        epoch, dangling, unassigned, pools,
+    )))
+}
+
+/// The JSON of a `StakeSnapshot`, the pools being serialized only as the
+/// stream is polled.
+fn stake_snapshot_stream<I>(
+    epoch: u32,
+    dangling: Stake,
+    unassigned: Stake,
+    pools: I,
+) -> impl Stream<Item = Result<String, serde_json::Error>> + Send + 'static
+where
+    I: Iterator<Item = PoolStakeSnapshot> + Send + 'static,
+{
+    let header = format!(
+        "{{\"epoch\":{},\"dangling\":{},\"unassigned\":{},\"pools\":[",
+        epoch, dangling, unassigned,
+    );
+    let pools = stream::iter(pools.enumerate()).map(|(index, pool)| {
+        let separator = if index == 0 { "" } else { "," };
+        serde_json::to_string(&pool).map(|pool| format!("{}{}", separator, pool))
+    });
+
+    stream::once(future::ok(header))
+        .chain(pools)
+        .chain(stream::once(future::ok("]}".to_string())))
+}
+
 pub async fn get_settings(context: &Context) -> Result<SettingsDto, Error> {
     let full_context = context.try_full()?;
     let blockchain_tip = context.blockchain_tip()?.get_ref().await;
@@ -360,7 +835,11 @@ pub async fn get_settings(context: &Context) -> Result<SettingsDto, Error> {
         .epoch_leadership_schedule()
         .era()
         .slots_per_epoch();
-    Ok(SettingsDto {
+    let active_slot_coefficient = match blockchain_tip.epoch_leadership_schedule().consensus() {
+        LeadershipConsensus::GenesisPraos(_) => ledger_active_slot_coefficient(&ledger),
+        _ => None,
+    };
+    let mut settings = SettingsDto {
         block0_hash: static_params.block0_initial_hash.to_string(),
         block0_time: SystemTime::from_secs_since_epoch(static_params.block0_start_time.0),
         curr_slot_start_time: full_context
@@ -375,7 +854,23 @@ pub async fn get_settings(context: &Context) -> Result<SettingsDto, Error> {
         slots_per_epoch,
         treasury_tax: current_params.treasury_tax,
         reward_params: current_params.reward_params.clone(),
-    })
+        active_slot_coefficient,
+        certificate_fees: CertificateFees::from(&fees),
+        settings_version: String::new(),
+    };
+    settings.settings_version = settings.compute_version();
+    Ok(settings)
+}
+
+/// the active slot coefficient currently in use by the ledger, which
+/// includes the updates adopted since the block0.
+fn ledger_active_slot_coefficient(ledger: &Ledger) -> Option<ActiveSlotCoefficient> {
+    ledger
+        .settings()
+        .to_config_params()
+        .iter()
+        .cloned()
+        .find_map(|param| ActiveSlotCoefficient::try_from(param).ok())
 }
 
 pub async fn shutdown(context: &mut Context) -> Result<(), Error> {
@@ -414,6 +909,13 @@ pub async fn get_leaders_logs(context: &Context) -> Result<Vec<LeadershipLog>, E
     Ok(context.try_full()?.leadership_logs.logs().await)
 }
 
+pub async fn get_leaders_logs_epoch(
+    context: &Context,
+    epoch: u32,
+) -> Result<Option<Vec<LeadershipLog>>, Error> {
+    Ok(context.try_full()?.leadership_logs.epoch(epoch).await)
+}
+
 pub async fn get_stake_pools(context: &Context) -> Result<Vec<String>, Error> {
     Ok(context
         .blockchain_tip()?
@@ -447,10 +949,21 @@ pub async fn get_network_stats(context: &Context) -> Result<Vec<PeerStats>, Erro
             last_block_received: info.stats.last_block_received().map(SystemTime::from),
             last_fragment_received: info.stats.last_fragment_received().map(SystemTime::from),
             last_gossip_received: info.stats.last_gossip_received().map(SystemTime::from),
+            version: info.stats.version().map(ToOwned::to_owned),
+            decode_errors: info
+                .stats
+                .decode_errors()
+                .iter()
+                .map(|(code, count)| (code.to_string(), *count))
+                .collect(),
         })
         .collect())
 }
 
+pub async fn get_network_gossip_settings(context: &Context) -> Result<GossipSettings, Error> {
+    Ok(context.try_full()?.network_state.gossip_settings())
+}
+
 pub async fn get_rewards_info_epoch(
     context: &Context,
     epoch: u32,
@@ -472,7 +985,7 @@ pub async fn get_rewards_info_epoch(
                 }
                 tip_ref = Arc::clone(previous_epoch);
             }
-            _ => return Ok(None),
+            _ => return Ok(context.blockchain()?.rewards_history().get(&epoch).await),
         }
     }
 
@@ -482,7 +995,7 @@ pub async fn get_rewards_info_epoch(
             epoch_rewards_info.as_ref(),
         )))
     } else {
-        Ok(None)
+        Ok(context.blockchain()?.rewards_history().get(&epoch).await)
     }
 }
 
@@ -510,9 +1023,85 @@ pub async fn get_rewards_info_history(
         }
     }
 
+    // the older epochs are no longer in memory, complete with the history
+    if vec.len() < length {
+        let oldest = vec.last().map(EpochRewardsInfo::epoch);
+        let history = context.blockchain()?.rewards_history();
+        let older = history
+            .latest(usize::MAX)
+            .await
+            .into_iter()
+            .filter(|info| oldest.map_or(true, |oldest| info.epoch() < oldest));
+        vec.extend(older.take(length - vec.len()));
+    }
+
     Ok(vec)
 }
 
+/// the rewards history of the given number of the most recent epochs as CSV,
+/// one line per reward received or distributed. Default to all the epochs
+/// retained by the node. The lines of an epoch are only formatted when the
+/// response is streamed.
+pub async fn get_rewards_info_history_csv(
+    context: &Context,
+    epochs: Option<usize>,
+) -> Result<impl Stream<Item = Result<String, std::convert::Infallible>> + Send + 'static, Error> {
+    let length = match epochs {
+        Some(epochs) => epochs,
+        None => context
+            .blockchain()?
+            .rewards_history()
+            .retention()
+            .await
+            .unwrap_or(usize::MAX),
+    };
+    let history = get_rewards_info_history(context, length).await?;
+
+    let header = "epoch,type,identifier,received,distributed\r\n".to_string();
+    let lines = history
+        .into_iter()
+        .rev()
+        .map(|info| Ok::<_, std::convert::Infallible>(rewards_csv_lines(&info)));
+    Ok(stream::once(future::ok(header)).chain(stream::iter(lines)))
+}
+
+fn rewards_csv_lines(info: &EpochRewardsInfo) -> String {
+    use std::fmt::Write as _;
+
+    let mut csv = String::new();
+    let epoch = info.epoch();
+    write!(csv, "{},drawn,,,{}\r\n", epoch, info.drawn()).unwrap();
+    write!(csv, "{},fees,,,{}\r\n", epoch, info.fees()).unwrap();
+    write!(csv, "{},treasury,,{},\r\n", epoch, info.treasury()).unwrap();
+    for (pool_id, (taxed, distributed)) in info.stake_pools() {
+        write!(
+            csv,
+            "{},pool,{},{},{}\r\n",
+            epoch, pool_id, taxed, distributed
+        )
+        .unwrap();
+    }
+    for (account_id, received) in info.accounts() {
+        write!(csv, "{},account,{},{},\r\n", epoch, account_id, received).unwrap();
+    }
+    csv
+}
+
+/// the value flows of the transition to the given epoch, the epochs
+/// still in memory are computed from their rewards when not recorded yet
+pub async fn get_epoch_accounting(
+    context: &Context,
+    epoch: u32,
+) -> Result<Option<EpochAccounting>, Error> {
+    if let Some(accounting) = context.blockchain()?.epoch_accounting().get(&epoch).await {
+        return Ok(Some(accounting));
+    }
+    Ok(get_rewards_info_epoch(context, epoch)
+        .await?
+        .as_ref()
+        .map(EpochAccounting::from))
+}
+
 pub async fn get_utxo(
     context: &Context,
     fragment_id_hex: &str,
@@ -557,8 +1146,15 @@ pub async fn get_stake_pool(
 }
 
 pub async fn get_diagnostic(context: &Context) -> Result<Diagnostic, Error> {
-    let diagnostic_data = context.get_diagnostic_data()?;
-    Ok(diagnostic_data.clone())
+    let mut diagnostic_data = context.get_diagnostic_data()?.clone();
+    diagnostic_data.stream_buffers = Some(network::stream_buffers_usage());
+    let full_context = context.try_full().ok();
+    diagnostic_data.backup = full_context
+        .and_then(|full_context| full_context.backup_status.as_ref())
+        .map(|status| status.get());
+    diagnostic_data.block_fetch =
+        full_context.map(|full_context| full_context.network_state.block_fetch_stats());
+    Ok(diagnostic_data)
 }
 
 pub async fn get_network_p2p_quarantined(
@@ -590,6 +1186,10 @@ pub async fn get_network_p2p_available(context: &Context) -> Result<Vec<polderca
         .await)
 }
 
+pub async fn get_network_p2p_self(context: &Context) -> Result<NodeSelfView, Error> {
+    Ok(context.try_full()?.network_state.self_view())
+}
+
 pub async fn get_network_p2p_view(context: &Context) -> Result<Vec<poldercast::Address>, Error> {
     Ok(context
         .try_full()?
@@ -649,3 +1249,203 @@ pub async fn get_active_vote_plans(context: &Context) -> Result<Vec<VotePlanStat
         .collect();
     Ok(vp)
 }
+
+pub async fn get_vote_plan_stats(
+    context: &Context,
+    id_hex: &str,
+) -> Result<Option<VotePlanStats>, Error> {
+    let id =
+        VotePlanId::from_str(id_hex).map_err(|_| Error::InvalidVotePlanId(id_hex.to_owned()))?;
+    let stats = context
+        .blockchain()?
+        .vote_stats()
+        .ok_or(Error::VotePlanStatsDisabled)?
+        .get(&id)
+        .await;
+    Ok(stats)
+}
+
+pub async fn get_archived_vote_plan(
+    context: &Context,
+    id_hex: &str,
+) -> Result<Option<VotePlanStatus>, Error> {
+    let id =
+        VotePlanId::from_str(id_hex).map_err(|_| Error::InvalidVotePlanId(id_hex.to_owned()))?;
+    Ok(context
+        .blockchain()?
+        .vote_plan_archive()
+        .get(&id.into())
+        .await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn fragments_are_decoded() {
+        use chain_impl_mockchain::transaction::TxBuilder;
+
+        let fragment = Fragment::Transaction(
+            TxBuilder::new()
+                .set_nopayload()
+                .set_ios(&[], &[])
+                .set_witnesses(&[])
+                .set_payload_auth(&()),
+        );
+        let description = decode_fragment(&fragment.serialize_as_vec().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(description.id, fragment.id().into());
+        assert_eq!(description.fragment_type, "Transaction");
+
+        assert!(matches!(
+            decode_fragment(b"not a fragment").await,
+            Err(Error::Deserialize(_))
+        ));
+    }
+
+    async fn stake_snapshot_json(pools: Vec<PoolStakeSnapshot>) -> String {
+        stake_snapshot_stream(3, Value(1).into(), Value(2).into(), pools.into_iter())
+            .try_concat()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn stake_snapshot_is_streamed_as_json() {
+        use jormungandr_lib::{crypto::account::SigningKey, interfaces::StakeSnapshot};
+
+        let pools: Vec<_> = (0..3u8)
+            .map(|pool| PoolStakeSnapshot {
+                id: [pool; 32].into(),
+                total: Value(10).into(),
+                delegators: vec![
+                    (
+                        SigningKey::generate(rand_core::OsRng).identifier(),
+                        Value(4).into(),
+                    ),
+                    (
+                        SigningKey::generate(rand_core::OsRng).identifier(),
+                        Value(6).into(),
+                    ),
+                ],
+            })
+            .collect();
+
+        let snapshot: StakeSnapshot =
+            serde_json::from_str(&stake_snapshot_json(pools.clone()).await).unwrap();
+        assert_eq!(
+            snapshot,
+            StakeSnapshot {
+                epoch: 3,
+                dangling: Value(1).into(),
+                unassigned: Value(2).into(),
+                pools,
+            }
+        );
+
+        let snapshot: StakeSnapshot =
+            serde_json::from_str(&stake_snapshot_json(Vec::new()).await).unwrap();
+        assert!(snapshot.pools.is_empty());
+    }
+
+    #[test]
+    fn sync_state_is_classified_by_the_lag_of_the_tip() {
+        let now = std::time::SystemTime::now();
+        let ago = |secs: u64| now - Duration::from_secs(secs);
+
+        assert_eq!(classify_sync(now, ago(200), 2, None), SyncState::Synced);
+        assert_eq!(
+            classify_sync(now, now + Duration::from_secs(5), 2, None),
+            SyncState::Synced
+        );
+        assert_eq!(
+            classify_sync(now, ago(400), 2, Some(SystemTime::from(ago(60)))),
+            SyncState::CatchingUp { behind_by: 200 }
+        );
+        assert_eq!(
+            classify_sync(now, ago(4000), 2, Some(SystemTime::from(ago(3600)))),
+            SyncState::Stalled {
+                since: SystemTime::from(ago(3600))
+            }
+        );
+        assert_eq!(
+            classify_sync(now, ago(4000), 2, None),
+            SyncState::Stalled {
+                since: SystemTime::from(ago(4000))
+            }
+        );
+    }
+
+    #[test]
+    fn dry_run_reports_the_balances_or_the_ledger_error() {
+        use crate::utils::test_fixtures;
+        use chain_crypto::Ed25519;
+
+        let key = chain_crypto::SecretKey::<Ed25519>::generate(rand_core::OsRng);
+        let receiver = chain_crypto::SecretKey::<Ed25519>::generate(rand_core::OsRng).to_public();
+        let leader = chain_crypto::SecretKey::<Ed25519>::generate(rand_core::OsRng).to_public();
+        let block0 = test_fixtures::block0_with_funds(&leader, &[(key.to_public(), 1_000)]);
+        let block0_hash = block0.header.hash();
+        let (ledger, ledger_params) = test_fixtures::ledger(&block0);
+
+        let transfer =
+            |counter: u32| test_fixtures::transfer(&block0_hash, &key, &receiver, counter);
+        let account = |pk: chain_crypto::PublicKey<Ed25519>| {
+            AccountIdentifier::from(transaction::AccountIdentifier::Single(pk.into()))
+        };
+
+        assert_eq!(
+            dry_run(&ledger, &ledger_params, &transfer(0), ledger.date()),
+            DryRunOutcome::Applied {
+                balances: vec![
+                    AccountBalanceChange {
+                        account: account(key.to_public()),
+                        before: Some(Value(1_000).into()),
+                        after: Some(Value(990).into()),
+                    },
+                    AccountBalanceChange {
+                        account: account(receiver.clone()),
+                        before: None,
+                        after: Some(Value(10).into()),
+                    },
+                ],
+            }
+        );
+
+        match dry_run(&ledger, &ledger_params, &transfer(1), ledger.date()) {
+            DryRunOutcome::Rejected {
+                reason,
+                ledger_error,
+            } => {
+                assert!(!reason.is_empty());
+                assert!(ledger_error.starts_with("AccountInvalidSignature"));
+            }
+            outcome => panic!("unexpected outcome {:?}", outcome),
+        }
+        // the ledger itself is left untouched
+        assert!(matches!(
+            dry_run(&ledger, &ledger_params, &transfer(0), ledger.date()),
+            DryRunOutcome::Applied { .. }
+        ));
+    }
+
+    #[test]
+    fn rewards_csv_has_a_line_per_flow() {
+        let info: EpochRewardsInfo = serde_json::from_value(serde_json::json!({
+            "epoch": 7,
+            "drawn": 100,
+            "fees": 10,
+            "treasury": 5,
+            "stake_pools": {},
+            "accounts": {},
+        }))
+        .unwrap();
+
+        assert_eq!(
+            rewards_csv_lines(&info),
+            "7,drawn,,,100\r\n7,fees,,,10\r\n7,treasury,,5,\r\n"
+        );
+    }
+}
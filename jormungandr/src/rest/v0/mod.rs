@@ -51,7 +51,20 @@ pub fn filter(
             .and_then(handlers::get_message_logs)
             .boxed();
 
-        root.and(logs).boxed()
+        let dry_run = warp::path!("dry-run")
+            .and(warp::post())
+            .and(warp::body::bytes())
+            .and(with_context.clone())
+            .and_then(handlers::dry_run_fragment)
+            .boxed();
+
+        let get = warp::path!(String)
+            .and(warp::get())
+            .and(with_context.clone())
+            .and_then(handlers::get_fragment)
+            .boxed();
+
+        root.and(logs.or(dry_run).or(get)).boxed()
     };
 
     let leaders = {
@@ -76,13 +89,20 @@ pub fn filter(
             .and_then(handlers::get_leaders_logs)
             .boxed();
 
+        let logs_epoch = warp::path!("logs" / u32)
+            .and(warp::get())
+            .and(with_context.clone())
+            .and_then(handlers::get_leaders_logs_epoch)
+            .boxed();
+
         let delete = warp::path!(u32)
             .and(warp::delete())
             .and(with_context.clone())
             .and_then(handlers::delete_leaders)
             .boxed();
 
-        root.and(get.or(post).or(logs).or(delete)).boxed()
+        root.and(get.or(post).or(logs).or(logs_epoch).or(delete))
+            .boxed()
     };
 
     let p2p = {
@@ -106,6 +126,12 @@ pub fn filter(
             .and_then(handlers::get_network_p2p_available)
             .boxed();
 
+        let self_view = warp::path!("self")
+            .and(warp::get())
+            .and(with_context.clone())
+            .and_then(handlers::get_network_p2p_self)
+            .boxed();
+
         let view = {
             let root = warp::path!("view" / ..);
 
@@ -124,8 +150,14 @@ pub fn filter(
             root.and(view.or(view_topic)).boxed()
         };
 
-        root.and(quarantined.or(non_public).or(available).or(view))
-            .boxed()
+        root.and(
+            quarantined
+                .or(non_public)
+                .or(available)
+                .or(self_view)
+                .or(view),
+        )
+        .boxed()
     };
 
     let network = {
@@ -137,7 +169,13 @@ pub fn filter(
             .and_then(handlers::get_network_stats)
             .boxed();
 
-        root.and(stats.or(p2p)).boxed()
+        let gossip_settings = warp::path!("stats" / "gossip")
+            .and(warp::get())
+            .and(with_context.clone())
+            .and_then(handlers::get_network_gossip_settings)
+            .boxed();
+
+        root.and(stats.or(gossip_settings).or(p2p)).boxed()
     };
 
     let settings = warp::path!("settings")
@@ -161,7 +199,13 @@ pub fn filter(
             .and_then(handlers::get_stake_distribution_at)
             .boxed();
 
-        root.and(get.or(get_at)).boxed()
+        let snapshot = warp::path!(u32 / "snapshot")
+            .and(warp::get())
+            .and(with_context.clone())
+            .and_then(handlers::get_stake_snapshot)
+            .boxed();
+
+        root.and(get.or(get_at).or(snapshot)).boxed()
     };
 
     let stake_pools = warp::path!("stake_pools")
@@ -179,10 +223,24 @@ pub fn filter(
     let message = warp::path!("message")
         .and(warp::post())
         .and(warp::body::bytes())
+        .and(warp::query())
         .and(with_context.clone())
         .and_then(handlers::post_message)
         .boxed();
 
+    let utils = warp::path!("utils" / "fragment" / "decode")
+        .and(warp::post())
+        .and(warp::body::bytes())
+        .and_then(handlers::decode_fragment)
+        .boxed();
+
+    let addresses_used = warp::path!("addresses" / "used")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_context.clone())
+        .and_then(handlers::post_addresses_used)
+        .boxed();
+
     let node_stats = warp::path!("node" / "stats")
         .and(warp::get())
         .and(with_context.clone())
@@ -210,9 +268,22 @@ pub fn filter(
             .and_then(handlers::get_rewards_info_epoch)
             .boxed();
 
-        root.and(history.or(epoch)).boxed()
+        let history_csv = warp::path!("history.csv")
+            .and(warp::get())
+            .and(warp::query())
+            .and(with_context.clone())
+            .and_then(handlers::get_rewards_info_history_csv)
+            .boxed();
+
+        root.and(history.or(epoch).or(history_csv)).boxed()
     };
 
+    let accounting = warp::path!("ledger" / "accounting" / u32)
+        .and(warp::get())
+        .and(with_context.clone())
+        .and_then(handlers::get_epoch_accounting)
+        .boxed();
+
     let utxo = warp::path!("utxo" / String / u8)
         .and(warp::get())
         .and(with_context.clone())
@@ -235,10 +306,24 @@ pub fn filter(
 
         let vote_plans = warp::path!("plans")
             .and(warp::get())
-            .and(with_context)
+            .and(with_context.clone())
             .and_then(handlers::get_active_vote_plans)
             .boxed();
-        root.and(committees.or(vote_plans)).boxed()
+
+        let vote_plan_stats = warp::path!("plans" / String / "stats")
+            .and(warp::get())
+            .and(with_context.clone())
+            .and_then(handlers::get_vote_plan_stats)
+            .boxed();
+
+        let archive = warp::path!("vote" / "archive" / String)
+            .and(warp::get())
+            .and(with_context)
+            .and_then(handlers::get_archived_vote_plan)
+            .boxed();
+        root.and(committees.or(vote_plans).or(vote_plan_stats))
+            .or(archive)
+            .boxed()
     };
 
     let routes = shutdown
@@ -252,9 +337,12 @@ pub fn filter(
         .or(stake_pools)
         .or(stake_pool)
         .or(message)
+        .or(addresses_used)
+        .or(utils)
         .or(node_stats)
         .or(tip)
         .or(rewards)
+        .or(accounting)
         .or(utxo)
         .or(diagnostic)
         .or(votes)
@@ -267,9 +355,16 @@ pub fn filter(
 async fn handle_rejection(err: Rejection) -> Result<impl Reply, Rejection> {
     if let Some(err) = err.find::<logic::Error>() {
         let (body, code) = match err {
-            logic::Error::PublicKey(_) | logic::Error::Hash(_) | logic::Error::Hex(_) => {
-                (err.to_string(), StatusCode::BAD_REQUEST)
-            }
+            logic::Error::PublicKey(_)
+            | logic::Error::Hash(_)
+            | logic::Error::Hex(_)
+            | logic::Error::Deserialize(_) => (err.to_string(), StatusCode::BAD_REQUEST),
+            logic::Error::ReadOnly => (err.to_string(), StatusCode::FORBIDDEN),
+            logic::Error::InvalidAddress(_) => (err.to_string(), StatusCode::BAD_REQUEST),
+            logic::Error::ExplorerDisabled => (err.to_string(), StatusCode::NOT_FOUND),
+            logic::Error::TooManyAddresses { .. } => (err.to_string(), StatusCode::BAD_REQUEST),
+            logic::Error::InvalidVotePlanId(_) => (err.to_string(), StatusCode::BAD_REQUEST),
+            logic::Error::VotePlanStatsDisabled => (err.to_string(), StatusCode::NOT_FOUND),
             err => (
                 display_internal_server_error(err),
                 StatusCode::INTERNAL_SERVER_ERROR,
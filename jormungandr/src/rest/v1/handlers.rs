@@ -35,6 +35,18 @@ pub async fn get_fragments_statuses(
         .map(|r| warp::reply::json(&r))
 }
 
+pub async fn delete_fragment(
+    fragment_id: String,
+    context: ContextLock,
+) -> Result<impl Reply, Rejection> {
+    let context = context.read().await;
+    logic::delete_fragment(&context, &fragment_id)
+        .await
+        .map_err(warp::reject::custom)?
+        .map(|()| warp::reply())
+        .ok_or_else(warp::reject::not_found)
+}
+
 pub async fn get_fragments_logs(context: ContextLock) -> Result<impl Reply, Rejection> {
     let context = context.read().await;
     logic::get_fragments_logs(&context)
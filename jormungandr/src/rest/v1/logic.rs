@@ -38,6 +38,10 @@ pub enum Error {
     Storage(#[from] StorageError),
     #[error(transparent)]
     Hex(#[from] hex::FromHexError),
+    #[error("the node is read-only and does not accept fragments")]
+    ReadOnly,
+    #[error("missing or invalid admin token")]
+    Unauthorized,
 }
 
 pub async fn get_fragments_statuses(
@@ -84,7 +88,11 @@ pub async fn post_fragments(
         .iter()
         .map(|fragment| fragment.id().to_string())
         .collect();
-    let mut msgbox = context.try_full()?.transaction_task.clone();
+    let full_context = context.try_full()?;
+    if full_context.read_only {
+        return Err(Error::ReadOnly);
+    }
+    let mut msgbox = full_context.transaction_task.clone();
     for fragment in fragments.into_iter() {
         let msg = TransactionMsg::SendTransaction(FragmentOrigin::Rest, vec![fragment]);
         msgbox.try_send(msg)?;
@@ -104,3 +112,21 @@ pub async fn get_fragments_logs(context: &Context) -> Result<Vec<FragmentLog>, E
         })?;
     reply_future.await.map_err(Into::into)
 }
+
+/// evict a pending fragment from the mempool of the node.
+///
+/// Returns `None` if the fragment is not in the mempool.
+pub async fn delete_fragment(context: &Context, id: &str) -> Result<Option<()>, Error> {
+    let fragment_id = FragmentId::from_str(id)?;
+    let logger = context.logger()?.new(o!("request" => "delete_fragment"));
+    let (reply_handle, reply_future) = intercom::unary_reply(logger.clone());
+    let mut mbox = context.try_full()?.transaction_task.clone();
+    mbox.send(TransactionMsg::CancelTransaction(fragment_id, reply_handle))
+        .await
+        .map_err(|e| {
+            debug!(&logger, "error cancelling fragment"; "reason" => %e);
+            Error::MsgSendError(e)
+        })?;
+    let cancelled = reply_future.await?;
+    Ok(if cancelled { Some(()) } else { None })
+}
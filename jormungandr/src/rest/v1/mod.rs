@@ -3,16 +3,18 @@ mod logic;
 
 use crate::rest::{display_internal_server_error, ContextLock};
 
+use subtle::ConstantTimeEq;
 use warp::{http::StatusCode, Filter, Rejection, Reply};
 
 pub fn filter(
     context: ContextLock,
+    admin_token: Option<String>,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     let with_context = warp::any().map(move || context.clone());
     let root = warp::path!("v1" / ..);
 
     let fragments = {
-        let root = warp::path!("fragments");
+        let root = warp::path!("fragments" / ..);
 
         let post = warp::path::end()
             .and(warp::post())
@@ -30,11 +32,18 @@ pub fn filter(
 
         let logs = warp::path!("logs")
             .and(warp::get())
-            .and(with_context)
+            .and(with_context.clone())
             .and_then(handlers::get_fragments_logs)
             .boxed();
 
-        root.and(post.or(status).or(logs)).boxed()
+        let delete = warp::path!(String)
+            .and(warp::delete())
+            .and(admin_auth(admin_token))
+            .and(with_context)
+            .and_then(handlers::delete_fragment)
+            .boxed();
+
+        root.and(post.or(status).or(logs).or(delete)).boxed()
     };
 
     let routes = fragments;
@@ -42,6 +51,31 @@ pub fn filter(
     root.and(routes).recover(handle_rejection).boxed()
 }
 
+/// Require the admin token as a bearer token in the `Authorization` header.
+/// The requests are always rejected if no admin token is configured. The
+/// tokens are compared in constant time, not to leak the configured one
+/// through the response times.
+fn admin_auth(admin_token: Option<String>) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and_then(move |authorization: Option<String>| {
+            let authorized = match (&admin_token, authorization) {
+                (Some(token), Some(authorization)) => match authorization.strip_prefix("Bearer ") {
+                    Some(given) => given.as_bytes().ct_eq(token.as_bytes()).into(),
+                    None => false,
+                },
+                _ => false,
+            };
+            async move {
+                if authorized {
+                    Ok(())
+                } else {
+                    Err(warp::reject::custom(logic::Error::Unauthorized))
+                }
+            }
+        })
+        .untuple_one()
+}
+
 /// Convert rejections to actual HTTP errors
 async fn handle_rejection(err: Rejection) -> Result<impl Reply, Rejection> {
     if let Some(err) = err.find::<logic::Error>() {
@@ -49,6 +83,8 @@ async fn handle_rejection(err: Rejection) -> Result<impl Reply, Rejection> {
             logic::Error::PublicKey(_) | logic::Error::Hash(_) | logic::Error::Hex(_) => {
                 (err.to_string(), StatusCode::BAD_REQUEST)
             }
+            logic::Error::Unauthorized => (err.to_string(), StatusCode::UNAUTHORIZED),
+            logic::Error::ReadOnly => (err.to_string(), StatusCode::FORBIDDEN),
             err => (
                 display_internal_server_error(err),
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -60,3 +96,38 @@ async fn handle_rejection(err: Rejection) -> Result<impl Reply, Rejection> {
 
     Err(err)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn authorized(admin_token: Option<&str>, authorization: Option<&str>) -> bool {
+        let filter = admin_auth(admin_token.map(str::to_string));
+        let request = warp::test::request();
+        let request = match authorization {
+            Some(authorization) => request.header("authorization", authorization),
+            None => request,
+        };
+        request.matches(&filter).await
+    }
+
+    #[tokio::test]
+    async fn the_configured_bearer_token_is_accepted() {
+        assert!(authorized(Some("secret"), Some("Bearer secret")).await);
+    }
+
+    #[tokio::test]
+    async fn other_tokens_are_rejected() {
+        assert!(!authorized(Some("secret"), Some("Bearer secreT")).await);
+        assert!(!authorized(Some("secret"), Some("Bearer secret2")).await);
+        assert!(!authorized(Some("secret"), Some("Bearer ")).await);
+        assert!(!authorized(Some("secret"), Some("secret")).await);
+        assert!(!authorized(Some("secret"), None).await);
+    }
+
+    #[tokio::test]
+    async fn everything_is_rejected_without_a_configured_token() {
+        assert!(!authorized(None, Some("Bearer secret")).await);
+        assert!(!authorized(None, None).await);
+    }
+}
@@ -0,0 +1,127 @@
+//! values of the node secret file read from somewhere else than the file
+//! itself, so the secrets do not have to be written in the configuration of
+//! the deployment:
+//!
+//! * `env:VAR` reads the value of the environment variable `VAR`;
+//! * `file:path` reads the content of the file, relative to the directory
+//!   of the secret file;
+//! * `command:...` runs the command with the shell and reads its output.
+//!
+//! Any other value is used as is. The trailing new lines are removed from
+//! the values read.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus},
+};
+use thiserror::Error;
+
+const ENV_PREFIX: &str = "env:";
+const FILE_PREFIX: &str = "file:";
+const COMMAND_PREFIX: &str = "command:";
+
+#[derive(Debug, Error)]
+pub enum IndirectionError {
+    #[error("environment variable '{0}' is not set or not valid unicode")]
+    EnvVar(String),
+    #[error("cannot read the file '{}'", .path.display())]
+    File {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("cannot run the command '{command}'")]
+    Command {
+        command: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("the command '{command}' failed with {status}")]
+    CommandFailed { command: String, status: ExitStatus },
+    #[error("the output of the command '{0}' is not valid unicode")]
+    CommandOutput(String),
+}
+
+/// the actual value of the given value of the secret file in the directory
+pub fn resolve(value: &str, dir: &Path) -> Result<String, IndirectionError> {
+    let resolved = if let Some(name) = value.strip_prefix(ENV_PREFIX) {
+        std::env::var(name).map_err(|_| IndirectionError::EnvVar(name.to_owned()))?
+    } else if let Some(path) = value.strip_prefix(FILE_PREFIX) {
+        let path = dir.join(path);
+        std::fs::read_to_string(&path).map_err(|source| IndirectionError::File { path, source })?
+    } else if let Some(command) = value.strip_prefix(COMMAND_PREFIX) {
+        run(command)?
+    } else {
+        return Ok(value.to_owned());
+    };
+    Ok(resolved.trim_end_matches(&['\r', '\n'][..]).to_owned())
+}
+
+fn run(command: &str) -> Result<String, IndirectionError> {
+    let output = if cfg!(windows) {
+        Command::new("cmd").args(&["/C", command]).output()
+    } else {
+        Command::new("sh").args(&["-c", command]).output()
+    }
+    .map_err(|source| IndirectionError::Command {
+        command: command.to_owned(),
+        source,
+    })?;
+
+    if !output.status.success() {
+        return Err(IndirectionError::CommandFailed {
+            command: command.to_owned(),
+            status: output.status,
+        });
+    }
+    String::from_utf8(output.stdout)
+        .map_err(|_| IndirectionError::CommandOutput(command.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_values_are_used_as_is() {
+        assert_eq!(resolve("value\n", Path::new(".")).unwrap(), "value\n");
+    }
+
+    #[test]
+    fn values_are_read_from_the_environment() {
+        std::env::set_var("JORMUNGANDR_TEST_INDIRECTION", "secret\n");
+        assert_eq!(
+            resolve("env:JORMUNGANDR_TEST_INDIRECTION", Path::new(".")).unwrap(),
+            "secret"
+        );
+        assert!(matches!(
+            resolve("env:JORMUNGANDR_TEST_INDIRECTION_UNSET", Path::new(".")),
+            Err(IndirectionError::EnvVar(name)) if name == "JORMUNGANDR_TEST_INDIRECTION_UNSET"
+        ));
+    }
+
+    #[test]
+    fn files_are_relative_to_the_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("secret"), "secret\r\n").unwrap();
+        assert_eq!(resolve("file:secret", dir.path()).unwrap(), "secret");
+        assert!(matches!(
+            resolve("file:missing", dir.path()),
+            Err(IndirectionError::File { path, .. }) if path == dir.path().join("missing")
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn values_are_read_from_the_output_of_commands() {
+        assert_eq!(
+            resolve("command:echo secret", Path::new(".")).unwrap(),
+            "secret"
+        );
+        assert!(matches!(
+            resolve("command:exit 3", Path::new(".")),
+            Err(IndirectionError::CommandFailed { status, .. }) if status.code() == Some(3)
+        ));
+    }
+}
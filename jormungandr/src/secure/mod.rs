@@ -1,14 +1,28 @@
-use chain_crypto::{Blake2b256, Curve25519_2HashDH, Ed25519, PublicKey, SumEd25519_12};
+use chain_crypto::{
+    AsymmetricKey, Blake2b256, Curve25519_2HashDH, Ed25519, PublicKey, SumEd25519_12,
+};
 use chain_impl_mockchain::leadership::{BftLeader, GenesisLeader};
 use jormungandr_lib::crypto::{
     hash::Hash,
     key::{Identifier, SigningKey},
+    keystore::{Keystore, KeystoreError},
 };
 use serde::Deserialize;
-use std::path::Path;
+use std::{
+    marker::PhantomData,
+    path::{Path, PathBuf},
+};
 use thiserror::Error;
+use zeroize::Zeroizing;
+
+/// environment variable holding the passphrase of the keystore, when the
+/// secret file does not point to a file holding it
+pub const KEYSTORE_PASSPHRASE_ENV_VAR: &str = "JORMUNGANDR_KEYSTORE_PASSPHRASE";
 
 pub mod enclave;
+mod indirection;
+
+pub use self::indirection::IndirectionError;
 
 /// hold the node's bft secret setting
 #[derive(Clone, Deserialize)]
@@ -56,18 +70,149 @@ pub struct NodePublic {
     pub block_publickey: PublicKey<Ed25519>,
 }
 
+/// The node secret file, where the secret keys can be given in plain text,
+/// read from elsewhere (see the `indirection` module) or as the name of an
+/// entry of an encrypted keystore:
+///
+/// ```yaml
+/// keystore:
+///   path: keystore
+///   passphrase: env:KEYSTORE_PASSPHRASE
+/// genesis:
+///   node_id: file:/run/secrets/node_id
+///   sig_key:
+///     keystore: pool-kes
+///   vrf_key: command:vault kv get -field=vrf_key secret/pool
+/// ```
+#[derive(Deserialize)]
+struct NodeSecretFile {
+    keystore: Option<KeystoreConfig>,
+    bft: Option<BftFile>,
+    genesis: Option<GenesisPraosFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeystoreConfig {
+    path: PathBuf,
+    passphrase: Option<String>,
+    passphrase_file: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct BftFile {
+    signing_key: SecretKeySource<Ed25519>,
+}
+
+#[derive(Deserialize)]
+struct GenesisPraosFile {
+    node_id: String,
+    sig_key: SecretKeySource<SumEd25519_12>,
+    vrf_key: SecretKeySource<Curve25519_2HashDH>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged, bound(deserialize = ""))]
+enum SecretKeySource<A: AsymmetricKey> {
+    Value(String),
+    Keystore {
+        keystore: String,
+        #[serde(skip)]
+        _algorithm: PhantomData<A>,
+    },
+}
+
+impl<A: AsymmetricKey> SecretKeySource<A> {
+    fn resolve(
+        self,
+        dir: &Path,
+        keystore: Option<&Keystore>,
+    ) -> Result<SigningKey<A>, NodeSecretFromFileError> {
+        match self {
+            SecretKeySource::Value(value) => {
+                SigningKey::from_bech32_str(&indirection::resolve(&value, dir)?)
+                    .map_err(NodeSecretFromFileError::InvalidKey)
+            }
+            SecretKeySource::Keystore { keystore: name, .. } => keystore
+                .ok_or_else(|| NodeSecretFromFileError::NoKeystore(name.clone()))?
+                .signing_key(&name)
+                .map_err(Into::into),
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum NodeSecretFromFileError {
     #[error("Cannot read node's secrets: {0}")]
     Io(#[from] std::io::Error),
     #[error("Invalid Node secret file: {0}")]
     Format(#[from] serde_yaml::Error),
+    #[error("Cannot use the keystore: {0}")]
+    Keystore(#[from] KeystoreError),
+    #[error("The key '{0}' is to be read from the keystore but no keystore is configured")]
+    NoKeystore(String),
+    #[error(
+        "No passphrase for the keystore, set JORMUNGANDR_KEYSTORE_PASSPHRASE or `passphrase_file`"
+    )]
+    NoKeystorePassphrase,
+    #[error("Cannot read a value of the secret file")]
+    Indirection(#[from] IndirectionError),
+    #[error("Invalid secret key")]
+    InvalidKey(#[source] chain_crypto::bech32::Error),
+    #[error("Invalid node id")]
+    InvalidNodeId(#[source] chain_crypto::hash::Error),
+}
+
+impl KeystoreConfig {
+    /// open the keystore, the paths being relative to the directory of the
+    /// secret file
+    fn open(&self, dir: &Path) -> Result<Keystore, NodeSecretFromFileError> {
+        let mut passphrase = Zeroizing::new(match (&self.passphrase, &self.passphrase_file) {
+            (Some(passphrase), _) => indirection::resolve(passphrase, dir)?,
+            (None, Some(file)) => std::fs::read_to_string(dir.join(file))?,
+            (None, None) => std::env::var(KEYSTORE_PASSPHRASE_ENV_VAR)
+                .map_err(|_| NodeSecretFromFileError::NoKeystorePassphrase)?,
+        });
+        let len = passphrase.trim_end_matches(&['\r', '\n'][..]).len();
+        passphrase.truncate(len);
+        Keystore::open(dir.join(&self.path), passphrase.as_bytes()).map_err(Into::into)
+    }
 }
 
 impl NodeSecret {
     pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<NodeSecret, NodeSecretFromFileError> {
-        let file = std::fs::File::open(path)?;
-        Ok(serde_yaml::from_reader(file)?)
+        let path = path.as_ref();
+        let file: NodeSecretFile = serde_yaml::from_reader(std::fs::File::open(path)?)?;
+
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let keystore = file
+            .keystore
+            .as_ref()
+            .map(|config| config.open(dir))
+            .transpose()?;
+
+        let bft = file
+            .bft
+            .map(|bft| -> Result<_, NodeSecretFromFileError> {
+                Ok(Bft {
+                    signing_key: bft.signing_key.resolve(dir, keystore.as_ref())?,
+                })
+            })
+            .transpose()?;
+        let genesis = file
+            .genesis
+            .map(|genesis| -> Result<_, NodeSecretFromFileError> {
+                Ok(GenesisPraos {
+                    node_id: indirection::resolve(&genesis.node_id, dir)?
+                        .parse()
+                        .map_err(NodeSecretFromFileError::InvalidNodeId)?,
+                    sig_key: genesis.sig_key.resolve(dir, keystore.as_ref())?,
+                    vrf_key: genesis.vrf_key.resolve(dir, keystore.as_ref())?,
+                })
+            })
+            .transpose()?;
+
+        Ok(NodeSecret { bft, genesis })
     }
 
     pub fn bft(&self) -> Option<BftLeader> {
@@ -25,7 +25,9 @@ pub struct StartArguments {
     #[structopt(long = "secret", parse(from_os_str))]
     pub secret: Vec<PathBuf>,
 
-    /// Path to the genesis block (the block0) of the blockchain
+    /// Path to the genesis block (the block0) of the blockchain. It can also
+    /// be an HTTP(S) URL to download the block from, in which case the
+    /// hash of the genesis block must be given too.
     #[structopt(long = "genesis-block", parse(try_from_str))]
     pub block_0_path: Option<PathBuf>,
 
@@ -46,6 +48,20 @@ pub struct StartArguments {
     #[structopt(long = "enable-explorer")]
     pub explorer_enabled: bool,
 
+    /// Run the node in read-only mode: the node keeps in sync with the
+    /// network and serves queries, but does not create blocks and neither
+    /// accepts nor propagates fragments.
+    #[structopt(long = "read-only")]
+    pub read_only: bool,
+
+    /// Run the node as a relay: the node stores and serves the blocks and
+    /// forwards the fragments, but does not apply the blocks to the ledger
+    /// state. Only available with the BFT consensus, the headers being
+    /// checked against the leaders of the block0. The node cannot create
+    /// blocks.
+    #[structopt(long = "relay")]
+    pub relay: bool,
+
     /// The address to listen from and accept connection from. This is the
     /// public address that will be distributed to other peers of the network.
     #[structopt(long = "public-address")]
@@ -113,6 +129,83 @@ pub struct CommandLine {
     /// this option is useful for scripting retrieving the logs of the version of this application.
     #[structopt(long = "source-version")]
     pub source_version: bool,
+
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Offline commands operating on the node's storage instead of starting
+/// the node.
+#[derive(StructOpt, Debug)]
+pub enum Command {
+    /// Leadership related commands
+    Leadership(LeadershipCommand),
+    /// Commands managing the keys of the node
+    Key(KeyCommand),
+    /// Commands managing the block storage of the node
+    Storage(StorageCommand),
+    /// Run several nodes in this process, e.g. for a local test network.
+    ///
+    /// Each node runs on its own thread, with the settings it would be
+    /// given on the command line. The nodes connect to each other in memory
+    /// through their p2p listen addresses.
+    #[cfg(feature = "in-memory-transport")]
+    Multi {
+        /// the cluster file (in YAML format) listing the nodes to run
+        #[structopt(long = "config", parse(from_os_str))]
+        config: PathBuf,
+    },
+}
+
+#[derive(StructOpt, Debug)]
+pub enum StorageCommand {
+    /// write the main chain of the storage in an archive, leaving out the
+    /// abandoned branches.
+    ///
+    /// The archive can be imported in the storage of another node to seed
+    /// it without downloading the chain from the network.
+    Export {
+        /// the file to write the archive to
+        #[structopt(long = "to", parse(from_os_str))]
+        to: PathBuf,
+    },
+    /// store the blocks of an archive created with `storage export`.
+    ///
+    /// The blocks already in the storage are skipped, so an interrupted
+    /// import can be resumed by running it again.
+    Import {
+        /// the archive file to import
+        #[structopt(long = "from", parse(from_os_str))]
+        from: PathBuf,
+    },
+}
+
+#[derive(StructOpt, Debug)]
+pub enum KeyCommand {
+    /// generate a new key identifying the node in the p2p network.
+    ///
+    /// The key is saved in the storage directory and replaces the current
+    /// one the next time the node starts. The new node id is printed.
+    RotateNodeId,
+}
+
+#[derive(StructOpt, Debug)]
+pub enum LeadershipCommand {
+    /// compute the slots the given leader would be elected for in an epoch,
+    /// using the blockchain state loaded from the storage.
+    ///
+    /// The leadership nonce of an epoch is only fixed once the previous epoch
+    /// is complete, so the epoch must not be later than the one following the
+    /// epoch of the stored tip.
+    Preview {
+        /// the secret file (in YAML format) holding the leader's VRF key
+        #[structopt(long = "secret", parse(from_os_str))]
+        secret: PathBuf,
+
+        /// the epoch to compute the leadership schedule for
+        #[structopt(long = "epoch")]
+        epoch: u32,
+    },
 }
 
 impl CommandLine {
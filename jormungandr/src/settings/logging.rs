@@ -1,4 +1,5 @@
-use crate::log::AsyncableDrain;
+use crate::log::{AsyncableDrain, SlogLayer};
+use crate::settings::start::config::Tracing;
 use slog::{Drain, FilterLevel, Logger};
 use slog_async::Async;
 #[cfg(feature = "gelf")]
@@ -12,6 +13,7 @@ use std::error;
 use std::fmt::{self, Display};
 use std::fs;
 use std::io;
+use std::path::Path;
 use std::str::FromStr;
 
 pub struct LogSettings(pub Vec<LogSettingsEntry>);
@@ -127,6 +129,112 @@ impl LogSettings {
     }
 }
 
+/// Installs the global `tracing` subscriber forwarding the events
+/// and span data of instrumented code to the given slog logger,
+/// and optionally exporting the spans to an OTLP collector.
+pub fn init_tracing(logger: &Logger, tracing_config: Option<Tracing>) -> Result<(), Error> {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let subscriber = tracing_subscriber::registry().with(SlogLayer::new(logger.clone()));
+    match tracing_config {
+        #[cfg(feature = "otlp")]
+        Some(config) => {
+            let subscriber = subscriber.with(otlp_layer(&config)?);
+            tracing::subscriber::set_global_default(subscriber).map_err(Error::TracingInitFailed)
+        }
+        #[cfg(not(feature = "otlp"))]
+        Some(_) => {
+            warn!(
+                logger,
+                "tracing export is configured, but the node is built without the `otlp` feature"
+            );
+            tracing::subscriber::set_global_default(subscriber).map_err(Error::TracingInitFailed)
+        }
+        None => {
+            tracing::subscriber::set_global_default(subscriber).map_err(Error::TracingInitFailed)
+        }
+    }
+}
+
+#[cfg(feature = "otlp")]
+fn otlp_layer<S>(
+    config: &Tracing,
+) -> Result<tracing_opentelemetry::OpenTelemetryLayer<S, opentelemetry::sdk::trace::Tracer>, Error>
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    use opentelemetry::{
+        sdk::{self, trace::Sampler},
+        KeyValue,
+    };
+
+    let trace_config = sdk::trace::config()
+        .with_sampler(Sampler::TraceIdRatioBased(config.sampling_rate))
+        .with_resource(sdk::Resource::new(vec![KeyValue::new(
+            "service.name",
+            "jormungandr",
+        )]));
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .with_endpoint(config.otlp_endpoint.clone())
+        .with_trace_config(trace_config)
+        .with_grpcio()
+        .install_batch(ExportRuntime)
+        .map_err(Error::OtlpExporter)?;
+    Ok(tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
+/// Exports the spans still waiting in the batches of the OTLP exporter, if
+/// one is installed.
+pub fn shutdown_tracing() {
+    #[cfg(feature = "otlp")]
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+/// The runtime of the batch span processor of the OTLP exporter. The
+/// exporter is installed before the runtimes of the node are started, and
+/// the processor is flushed when they are shut down, so it runs on a thread
+/// of its own.
+#[cfg(feature = "otlp")]
+#[derive(Clone, Debug)]
+struct ExportRuntime;
+
+#[cfg(feature = "otlp")]
+impl opentelemetry::runtime::Runtime for ExportRuntime {
+    type Interval = futures::stream::BoxStream<'static, ()>;
+    type Delay = futures::future::BoxFuture<'static, ()>;
+
+    // the timers are created on the first poll, in the runtime of the thread
+    fn interval(&self, duration: std::time::Duration) -> Self::Interval {
+        use futures::prelude::*;
+
+        stream::unfold((), move |()| async move {
+            tokio::time::delay_for(duration).await;
+            Some(((), ()))
+        })
+        .boxed()
+    }
+
+    fn spawn(&self, future: futures::future::BoxFuture<'static, ()>) {
+        std::thread::Builder::new()
+            .name("otlp_export".to_owned())
+            .spawn(move || {
+                let mut runtime = tokio::runtime::Builder::new()
+                    .basic_scheduler()
+                    .enable_time()
+                    .build()
+                    .expect("failed to build the runtime of the OTLP exporter");
+                runtime.block_on(future)
+            })
+            .expect("failed to spawn the thread of the OTLP exporter");
+    }
+
+    fn delay(&self, duration: std::time::Duration) -> Self::Delay {
+        use futures::prelude::*;
+
+        async move { tokio::time::delay_for(duration).await }.boxed()
+    }
+}
+
 impl LogSettingsEntry {
     pub fn to_logger(&self) -> Result<slog::Filter<Async, impl slog::FilterFn>, Error> {
         let filter_level = self.level;
@@ -204,6 +312,9 @@ impl LogOutput {
                 Ok(gelf_drain.into_async())
             }
             LogOutput::File(path) => {
+                if let Some(dir) = Path::new(path).parent() {
+                    fs::create_dir_all(dir).map_err(Error::FileError)?;
+                }
                 let file = fs::OpenOptions::new()
                     .create(true)
                     .write(true)
@@ -270,6 +381,9 @@ pub enum Error {
     #[cfg(feature = "gelf")]
     GelfConnectionFailed(io::Error),
     FileError(io::Error),
+    TracingInitFailed(tracing::subscriber::SetGlobalDefaultError),
+    #[cfg(feature = "otlp")]
+    OtlpExporter(opentelemetry::trace::TraceError),
 }
 
 impl Display for Error {
@@ -287,6 +401,9 @@ impl Display for Error {
             #[cfg(feature = "gelf")]
             Error::GelfConnectionFailed(_) => write!(f, "GELF connection failed"),
             Error::FileError(e) => write!(f, "failed to open the log file: {}", e),
+            Error::TracingInitFailed(_) => write!(f, "failed to set up tracing"),
+            #[cfg(feature = "otlp")]
+            Error::OtlpExporter(_) => write!(f, "failed to set up the OTLP trace exporter"),
         }
     }
 }
@@ -302,6 +419,9 @@ impl error::Error for Error {
             #[cfg(feature = "gelf")]
             Error::GelfConnectionFailed(err) => Some(err),
             Error::FileError(err) => Some(err),
+            Error::TracingInitFailed(err) => Some(err),
+            #[cfg(feature = "otlp")]
+            Error::OtlpExporter(err) => Some(err),
         }
     }
 }
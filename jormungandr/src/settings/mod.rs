@@ -2,7 +2,9 @@ mod command_arguments;
 pub mod logging;
 pub mod start;
 
-pub use self::command_arguments::CommandLine;
+pub use self::command_arguments::{
+    Command, CommandLine, KeyCommand, LeadershipCommand, StorageCommand,
+};
 pub use self::start::Error;
 use crate::blockcfg::HeaderHash;
 use std::path::PathBuf;
@@ -20,5 +22,7 @@ lazy_static! {
 #[derive(Clone, Debug)]
 pub enum Block0Info {
     Path(PathBuf, Option<HeaderHash>),
+    /// HTTP(S) URL to download the block0 from, with the expected hash
+    Url(String, HeaderHash),
     Hash(HeaderHash),
 }
@@ -1,17 +1,29 @@
 #![allow(deprecated)]
 use crate::{
-    network::p2p::{layers::LayersConfig, topic, Address, PolicyConfig},
+    network::{
+        p2p::{
+            comm::{OverflowPolicy, PeerSelectionStrategy},
+            layers::LayersConfig,
+            topic, Address, PolicyConfig,
+        },
+        port_mapping::PortMappingProtocol,
+    },
     settings::logging::{LogFormat, LogOutput},
     settings::LOG_FILTER_LEVEL_POSSIBLE_VALUES,
 };
+use chain_crypto::Ed25519;
 pub use jormungandr_lib::interfaces::{Cors, Rest, Tls};
-use jormungandr_lib::{interfaces::Mempool, time::Duration};
+use jormungandr_lib::{
+    crypto::{hash::Hash, key::Identifier},
+    interfaces::Mempool,
+    time::Duration,
+};
 
 use multiaddr::Multiaddr;
 use serde::{de::Error as _, de::Visitor, Deserialize, Deserializer, Serialize, Serializer};
 use slog::FilterLevel;
 
-use std::{collections::BTreeMap, fmt, path::PathBuf};
+use std::{collections::BTreeMap, fmt, net::Ipv4Addr, path::PathBuf};
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -19,8 +31,21 @@ pub struct Config {
     #[serde(default)]
     pub secret_files: Vec<PathBuf>,
     pub storage: Option<PathBuf>,
+
+    /// the hash of the genesis block of the network the node runs on
+    #[serde(default)]
+    pub genesis_block_hash: Option<Hash>,
+
+    /// keep the storage and the log files of each network in a subdirectory
+    /// named after the hash of its genesis block
+    #[serde(default)]
+    pub namespace_by_genesis: bool,
     pub log: Option<ConfigLogSettings>,
 
+    /// export of the tracing data to an OpenTelemetry collector
+    #[serde(default)]
+    pub tracing: Option<Tracing>,
+
     /// setting of the mempool, fragment logs and related data
     #[serde(default)]
     pub mempool: Mempool,
@@ -46,6 +71,86 @@ pub struct Config {
     pub bootstrap_from_trusted_peers: bool,
     #[serde(default)]
     pub skip_bootstrap: bool,
+
+    /// periodic backups of the chain stored by the node
+    #[serde(default)]
+    pub backup: Option<Backup>,
+
+    /// retention of the epoch reward histories served by the REST API
+    #[serde(default)]
+    pub rewards_history: Option<RewardsHistory>,
+
+    /// time series of the votes cast on the active vote plans, served by
+    /// the REST API
+    #[serde(default)]
+    pub vote_plan_stats: Option<VotePlanStats>,
+
+    /// command and webhook notified at each epoch transition
+    #[serde(default)]
+    pub epoch_hooks: Option<EpochHooks>,
+
+    /// checks of the clock of the node against its peers and a NTP server
+    #[serde(default)]
+    pub clock_check: Option<ClockCheck>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RewardsHistory {
+    /// the number of the most recent epochs to keep the rewards of
+    pub retention: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VotePlanStats {
+    /// the number of the most recent samples to keep for each vote plan
+    pub samples: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EpochHooks {
+    /// the shell command to run, the summary of the epoch transition being
+    /// written to its standard input
+    #[serde(default)]
+    pub command: Option<String>,
+
+    /// the URL to post the summary of the epoch transition to
+    #[serde(default)]
+    pub webhook: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClockCheck {
+    /// the NTP server to compare the clock to when the node starts, as
+    /// `host:port`
+    #[serde(default)]
+    pub ntp_server: Option<String>,
+
+    /// the skew of the clock above which a warning is logged
+    #[serde(default)]
+    pub max_skew: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Backup {
+    /// the directory to write the backups to
+    pub directory: PathBuf,
+
+    /// the time between two backups
+    #[serde(default)]
+    pub interval: Option<Duration>,
+
+    /// the number of epochs between two backups
+    #[serde(default)]
+    pub every_epochs: Option<u32>,
+
+    /// the number of the most recent backups to keep
+    #[serde(default)]
+    pub keep: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -60,6 +165,54 @@ pub struct ConfigLogSettingsEntry {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ConfigLogSettings(pub Vec<ConfigLogSettingsEntry>);
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Tracing {
+    /// the endpoint of the OTLP collector (e.g. `http://localhost:4317`)
+    pub otlp_endpoint: String,
+
+    /// the ratio of traces to export, between 0.0 and 1.0.
+    /// The default is to export all traces.
+    #[serde(default = "default_sampling_rate")]
+    pub sampling_rate: f64,
+}
+
+fn default_sampling_rate() -> f64 {
+    1.0
+}
+
+/// map the public port of the node on the router of the local network
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PortMappingConfig {
+    /// `upnp`, `nat_pmp` or `any` to try UPnP then NAT-PMP, the default
+    #[serde(default)]
+    pub protocol: Option<PortMappingProtocol>,
+
+    /// the lease of the mapping, renewed at half time. If not specified,
+    /// an internal default is used.
+    #[serde(default)]
+    pub lease_duration: Option<Duration>,
+
+    /// the address of the NAT-PMP gateway. If not specified, the gateway
+    /// of the default route is used.
+    #[serde(default)]
+    pub gateway: Option<Ipv4Addr>,
+}
+
+/// a socket address for the node to listen on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ListenConfig {
+    pub address: Address,
+
+    /// advertise the address to the peers as the public address of the
+    /// node. At most one address can be advertised, and only if
+    /// `public_address` is not set.
+    #[serde(default)]
+    pub advertise: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct P2pConfig {
@@ -72,6 +225,17 @@ pub struct P2pConfig {
     /// all network interfaces.
     pub listen_address: Option<Address>,
 
+    /// The socket addresses to listen on, e.g. on a private network
+    /// interface and on a public one, instead of `listen_address`.
+    #[serde(default)]
+    pub listen: Vec<ListenConfig>,
+
+    /// bind the listening socket with `SO_REUSEPORT`, so the node can be
+    /// restarted while the previous instance is still shutting down.
+    /// Not used when the socket is passed by the service manager.
+    #[serde(default)]
+    pub reuse_port: bool,
+
     /// keep the public id there and present, but yet make it optional as it is
     /// no longer needed.
     ///
@@ -102,6 +266,66 @@ pub struct P2pConfig {
     /// This setting is not used and is left for backward compatibility.
     pub max_connections_threshold: Option<usize>,
 
+    /// Limit on the number of requests from the peers served concurrently.
+    /// If not specified, an internal default limit is used.
+    #[serde(default)]
+    pub max_server_requests: Option<usize>,
+
+    /// Limit on the number of requests made concurrently to the peers to
+    /// catch up with the network. It is separate from the limit on the
+    /// served requests so serving many peers cannot starve the node's own
+    /// synchronization. If not specified, an internal default limit is used.
+    #[serde(default)]
+    pub max_client_requests: Option<usize>,
+
+    /// limits on the requests served for the most expensive methods of
+    /// the p2p protocol. If not specified, internal default limits are used.
+    #[serde(default)]
+    pub method_limits: MethodLimitsConfig,
+
+    /// limits on the size of the streams of blocks and headers received
+    /// from the peers. If not specified, internal default limits are used.
+    #[serde(default)]
+    pub stream_limits: StreamLimitsConfig,
+
+    /// limits on the nonces sent to the peers in the handshake. If not
+    /// specified, internal default limits are used.
+    #[serde(default)]
+    pub handshake_nonces: HandshakeNoncesConfig,
+
+    /// time limit for the node to serve a request of a peer entirely,
+    /// after which the processing of the request is abandoned.
+    /// If not specified, an internal default limit is used.
+    #[serde(default)]
+    pub reply_timeout: Option<Duration>,
+
+    /// number of the blocks recently served to the peers kept in memory,
+    /// 0 disables the cache. If not specified, an internal default is used.
+    #[serde(default)]
+    pub block_cache_size: Option<usize>,
+
+    /// what to do with the fragments and gossip to send to a peer whose
+    /// subscription buffer is full. If not specified, internal defaults
+    /// are used.
+    #[serde(default)]
+    pub overflow_policies: OverflowPoliciesConfig,
+
+    /// how to choose the peer to fetch the missing blocks from.
+    /// If not specified, the most recently active peer is chosen.
+    #[serde(default)]
+    pub block_fetch_strategy: Option<PeerSelectionStrategy>,
+
+    /// right after bootstrap, fill the mempool with the fragments pending
+    /// in the mempool of a trusted peer, so a restarted leader does not
+    /// produce near empty blocks for its first slots
+    #[serde(default)]
+    pub mempool_warmup: bool,
+
+    /// ask the router of the local network to forward the public port
+    /// to the node with UPnP or NAT-PMP
+    #[serde(default)]
+    pub port_mapping: Option<PortMappingConfig>,
+
     /// Whether to allow non-public IP addresses on the network.
     /// The default is to not allow advertising non-public IP addresses.
     #[serde(default)]
@@ -133,6 +357,12 @@ pub struct P2pConfig {
     #[serde(default)]
     pub gossip_interval: Option<Duration>,
 
+    /// the most peers to gossip with at every interval, chosen at random
+    /// in the topology view. The default is to gossip with all the peers
+    /// of the view.
+    #[serde(default)]
+    pub gossip_fanout: Option<usize>,
+
     /// If this value is set, it will trigger a force reset of the topology
     /// layers. The default is to not do force the reset. It is recommended
     /// to let the protocol handle it.
@@ -150,6 +380,82 @@ pub struct P2pConfig {
     /// gossip with the trusted peers if any are defined.
     #[serde(default)]
     pub max_bootstrap_attempts: Option<usize>,
+
+    /// require the inbound peers to authenticate with a trusted node id,
+    /// for permissioned networks. The default is to accept any peer.
+    #[serde(default)]
+    pub client_auth: Option<ClientAuthConfig>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MethodLimitsConfig {
+    #[serde(default)]
+    pub pull_blocks: MethodLimitConfig,
+    #[serde(default)]
+    pub get_blocks: MethodLimitConfig,
+    #[serde(default)]
+    pub push_headers: MethodLimitConfig,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StreamLimitsConfig {
+    /// maximum number of headers in a stream pushed by a peer or sent in
+    /// response to a chain pull
+    #[serde(default)]
+    pub max_headers: Option<usize>,
+
+    /// maximum number of blocks in a stream uploaded by a peer or sent in
+    /// response to a block solicitation
+    #[serde(default)]
+    pub max_blocks: Option<usize>,
+
+    /// maximum total size in bytes of the blocks of such a stream
+    #[serde(default)]
+    pub max_block_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HandshakeNoncesConfig {
+    /// time after which a nonce can no longer be used to authenticate
+    #[serde(default)]
+    pub expiry: Option<Duration>,
+
+    /// maximum number of nonces not used yet
+    #[serde(default)]
+    pub max_pending: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MethodLimitConfig {
+    /// maximum number of requests served concurrently, the requests over
+    /// this limit are rejected
+    #[serde(default)]
+    pub max_concurrent: Option<usize>,
+
+    /// time limit for the node to start serving a request
+    #[serde(default)]
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OverflowPoliciesConfig {
+    #[serde(default)]
+    pub fragments: Option<OverflowPolicy>,
+    #[serde(default)]
+    pub gossip: Option<OverflowPolicy>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClientAuthConfig {
+    /// the node ids (bech32-encoded ed25519 public keys) of the peers
+    /// allowed to connect to this node
+    pub trusted_node_ids: Vec<Identifier<Ed25519>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -174,6 +480,13 @@ pub struct Leadership {
     /// the least recently used log will be erased from the logs for a new one
     /// to be inserted.
     pub logs_capacity: usize,
+
+    /// path of the file recording the slots the node has already signed
+    /// a block for. It should be kept outside of the storage directory so
+    /// that restoring the storage from a backup does not restore an older
+    /// record. If not set, the record is only kept in memory.
+    #[serde(default)]
+    pub signed_slots_file: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
@@ -208,19 +521,34 @@ impl Default for P2pConfig {
         P2pConfig {
             public_address: None,
             listen_address: None,
+            listen: Vec::new(),
+            reuse_port: false,
             public_id: None,
             trusted_peers: None,
             topics_of_interest: None,
             max_connections: None,
             max_inbound_connections: None,
             max_connections_threshold: None,
+            max_server_requests: None,
+            max_client_requests: None,
+            method_limits: MethodLimitsConfig::default(),
+            stream_limits: StreamLimitsConfig::default(),
+            handshake_nonces: HandshakeNoncesConfig::default(),
+            reply_timeout: None,
+            block_cache_size: None,
+            overflow_policies: OverflowPoliciesConfig::default(),
+            block_fetch_strategy: None,
+            mempool_warmup: false,
+            port_mapping: None,
             allow_private_addresses: false,
             policy: PolicyConfig::default(),
             layers: LayersConfig::default(),
             max_unreachable_nodes_to_connect_per_event: None,
             gossip_interval: None,
+            gossip_fanout: None,
             topology_force_reset_interval: None,
             max_bootstrap_attempts: None,
+            client_auth: None,
         }
     }
 }
@@ -229,6 +557,7 @@ impl Default for Leadership {
     fn default() -> Self {
         Leadership {
             logs_capacity: 1_024,
+            signed_slots_file: None,
         }
     }
 }
@@ -258,6 +587,28 @@ impl std::str::FromStr for TrustedPeer {
     }
 }
 
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 == topic::MESSAGES {
+            f.write_str("messages")
+        } else if self.0 == topic::BLOCKS {
+            f.write_str("blocks")
+        } else {
+            write!(f, "{:?}", self.0)
+        }
+    }
+}
+
+impl fmt::Display for InterestLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            poldercast::InterestLevel::Low => f.write_str("low"),
+            poldercast::InterestLevel::Normal => f.write_str("normal"),
+            poldercast::InterestLevel::High => f.write_str("high"),
+        }
+    }
+}
+
 impl Serialize for InterestLevel {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -354,3 +705,21 @@ mod filter_level_opt_serde {
         data.map(|level| level.as_str()).serialize(serializer)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn topics_of_interest_display_as_configured() {
+        let interests: BTreeMap<Topic, InterestLevel> =
+            serde_yaml::from_str("messages: high\nblocks: low").unwrap();
+        let displayed: Vec<(String, String)> = interests
+            .iter()
+            .map(|(topic, interest)| (topic.to_string(), interest.to_string()))
+            .collect();
+        assert!(displayed.contains(&("messages".to_owned(), "high".to_owned())));
+        assert!(displayed.contains(&("blocks".to_owned(), "low".to_owned())));
+        assert_eq!(displayed.len(), 2);
+    }
+}
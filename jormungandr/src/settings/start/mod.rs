@@ -1,19 +1,28 @@
 pub mod config;
 pub mod network;
 
-use self::config::{Config, Leadership};
+use self::config::{Config, Leadership, Tracing};
 use self::network::{Protocol, TrustedPeer};
 use crate::settings::logging::{LogFormat, LogOutput, LogSettings, LogSettingsEntry};
-use crate::settings::{command_arguments::*, Block0Info};
+use crate::{
+    blockcfg::HeaderHash,
+    network::{p2p, port_mapping::PortMappingProtocol, NodeTransport},
+    settings::{command_arguments::*, Block0Info},
+};
 pub use jormungandr_lib::interfaces::{Cors, Mempool, Rest, Tls};
 use slog::{FilterLevel, Logger};
-use std::{fs::File, path::PathBuf};
+use std::{
+    fs::File,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
 use thiserror::Error;
 
 const DEFAULT_FILTER_LEVEL: FilterLevel = FilterLevel::Info;
 const DEFAULT_LOG_FORMAT: LogFormat = LogFormat::Plain;
 const DEFAULT_LOG_OUTPUT: LogOutput = LogOutput::Stderr;
 const DEFAULT_NO_BLOCKCHAIN_UPDATES_WARNING_INTERVAL: u64 = 1800; // 30 min
+const DEFAULT_MAX_CLOCK_SKEW: u64 = 5; // seconds
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -23,8 +32,62 @@ pub enum Error {
     Config(#[from] serde_yaml::Error),
     #[error("Cannot start the node without the information to retrieve the genesis block")]
     ExpectedBlock0Info,
+    #[error("The genesis block hash must be given to download the genesis block from a URL")]
+    ExpectedBlock0HashForUrl,
+    #[error("The genesis block hash is {command_line} on the command line but {config} in the node configuration file")]
+    Block0HashMismatch {
+        command_line: HeaderHash,
+        config: HeaderHash,
+    },
+    #[error("The genesis block hash must be given to namespace the storage by genesis block")]
+    ExpectedBlock0HashForNamespace,
     #[error("In the node configuration file, the `p2p.listen_address` value is not a valid address. Use format `/ip4/x.x.x.x/tcp/4920")]
     ListenAddressNotValid,
+    #[error("In the node configuration file, only one of `p2p.listen_address` and `p2p.listen` can be set")]
+    ListenAddressConflict,
+    #[error("In the node configuration file, at most one of the `p2p.listen` addresses can be advertised")]
+    SeveralAdvertisedListenAddresses,
+    #[error("In the node configuration file, a `p2p.listen` address cannot be advertised if `p2p.public_address` is set")]
+    AdvertisedListenAddressWithPublicAddress,
+    #[error("In the node configuration file, the advertised `p2p.listen` address {0} is not an address the peers can connect to")]
+    UnspecifiedAdvertisedListenAddress(SocketAddr),
+    #[error("The public address {0} of the node is not an address the peers can connect to")]
+    PublicAddressNotAdvertisable(SocketAddr),
+    #[error(
+        "In the node configuration file, `p2p.port_mapping` needs a public address with a TCP port"
+    )]
+    PortMappingWithoutPublicAddress,
+    #[error("In the node configuration file, `p2p.port_mapping.lease_duration` must be at least 1 second")]
+    ZeroPortMappingLease,
+    #[error("In the node configuration file, exactly one of `backup.interval` and `backup.every_epochs` must be set")]
+    InvalidBackupSchedule,
+    #[error("In the node configuration file, `epoch_hooks.webhook` is not a valid URL: {0}")]
+    InvalidEpochHookWebhook(String),
+    #[error("In the node configuration file, `epoch_hooks` needs a `command` or a `webhook`")]
+    EmptyEpochHooks,
+    #[error("In the node configuration file, `p2p.gossip_interval` must be at least {0:?}")]
+    GossipIntervalTooShort(std::time::Duration),
+    #[error("In the node configuration file, `p2p.gossip_fanout` must be at least 1")]
+    ZeroGossipFanout,
+    #[error(
+        "In the node configuration file, `p2p.handshake_nonces.max_pending` must be at least 1"
+    )]
+    ZeroHandshakeNonces,
+    #[error("In the node configuration file, `p2p.stream_limits.{0}` must be at least 1")]
+    ZeroStreamLimit(&'static str),
+    #[error("In the node configuration file, `mempool.selection_quotas.max_content_size` must be at least 1")]
+    ZeroMaxContentSize,
+    #[error(
+        "In the node configuration file, `mempool.confirmed_fragments_epochs` must be at least 1"
+    )]
+    ZeroConfirmedFragmentsEpochs,
+    #[error("In the node configuration file, the `mempool.selection_quotas.reserved_size` values add up to {reserved} bytes, above the `max_content_size` of {max_content_size} bytes")]
+    ReservedSizeAboveMaxContentSize {
+        reserved: u64,
+        max_content_size: u32,
+    },
+    #[error("In the node configuration file, `tracing.sampling_rate` must be between 0.0 and 1.0, not {0}")]
+    SamplingRateOutOfRange(f64),
 }
 
 /// Overall Settings for node
@@ -38,9 +101,53 @@ pub struct Settings {
     pub rewards_report_all: bool,
     pub leadership: Leadership,
     pub explorer: bool,
+    pub read_only: bool,
+    pub relay: bool,
     pub no_blockchain_updates_warning_interval: std::time::Duration,
+    pub backup: Option<BackupSettings>,
+    /// number of the most recent epochs to keep the reward history of
+    pub rewards_history_retention: usize,
+    /// number of the most recent samples of the votes cast to keep for
+    /// each vote plan, `None` if the statistics are not enabled
+    pub vote_plan_stats_samples: Option<usize>,
+    pub epoch_hooks: Option<EpochHookSettings>,
+    pub clock_check: ClockCheckSettings,
 }
 
+/// settings of the checks of the clock of the node
+#[derive(Clone, Debug)]
+pub struct ClockCheckSettings {
+    pub ntp_server: Option<String>,
+    /// the skew above which a warning is logged
+    pub max_skew: std::time::Duration,
+}
+
+/// settings of the notifications of the epoch transitions
+#[derive(Clone, Debug)]
+pub struct EpochHookSettings {
+    pub command: Option<String>,
+    pub webhook: Option<reqwest::Url>,
+}
+
+/// settings of the periodic backups of the chain
+#[derive(Clone, Debug)]
+pub struct BackupSettings {
+    pub directory: PathBuf,
+    pub schedule: BackupSchedule,
+    /// number of the most recent backups to keep
+    pub keep: usize,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum BackupSchedule {
+    Interval(std::time::Duration),
+    Epochs(u32),
+}
+
+const DEFAULT_BACKUPS_KEPT: usize = 3;
+
+const DEFAULT_REWARDS_HISTORY_RETENTION: usize = 100;
+
 pub struct RawSettings {
     command_line: CommandLine,
     config: Option<Config>,
@@ -48,11 +155,15 @@ pub struct RawSettings {
 
 impl RawSettings {
     pub fn load(command_line: CommandLine) -> Result<Self, Error> {
-        let config = if let Some(node_config) = &command_line.start_arguments.node_config {
-            Some(serde_yaml::from_reader(File::open(node_config)?)?)
-        } else {
-            None
-        };
+        let config: Option<Config> =
+            if let Some(node_config) = &command_line.start_arguments.node_config {
+                Some(serde_yaml::from_reader(File::open(node_config)?)?)
+            } else {
+                None
+            };
+        if let Some(tracing) = config.as_ref().and_then(|cfg| cfg.tracing.as_ref()) {
+            check_sampling_rate(tracing)?;
+        }
         Ok(Self {
             command_line,
             config,
@@ -67,7 +178,8 @@ impl RawSettings {
                 entries.push(LogSettingsEntry {
                     level: entry.level.clone().unwrap_or(DEFAULT_FILTER_LEVEL),
                     format: entry.format.clone().unwrap_or(DEFAULT_LOG_FORMAT),
-                    output: entry.output.clone().unwrap_or(DEFAULT_LOG_OUTPUT),
+                    output: self
+                        .namespaced_log_output(entry.output.clone().unwrap_or(DEFAULT_LOG_OUTPUT)),
                 })
             });
         }
@@ -80,7 +192,7 @@ impl RawSettings {
             entries.push(LogSettingsEntry {
                 level: cmd_level.unwrap_or(DEFAULT_FILTER_LEVEL),
                 format: cmd_format.unwrap_or(DEFAULT_LOG_FORMAT),
-                output: cmd_output.unwrap_or(DEFAULT_LOG_OUTPUT),
+                output: self.namespaced_log_output(cmd_output.unwrap_or(DEFAULT_LOG_OUTPUT)),
             });
         }
 
@@ -95,6 +207,53 @@ impl RawSettings {
         LogSettings(entries)
     }
 
+    /// the hash of the genesis block given on the command line, or else
+    /// in the node configuration file
+    fn block0_hash(&self) -> Option<HeaderHash> {
+        self.command_line.start_arguments.block_0_hash.or_else(|| {
+            self.config
+                .as_ref()
+                .and_then(|cfg| cfg.genesis_block_hash)
+                .map(|hash| hash.into_hash())
+        })
+    }
+
+    /// the name of the subdirectory holding the storage and the log files,
+    /// if they are namespaced by genesis block
+    fn genesis_namespace(&self) -> Option<String> {
+        if self
+            .config
+            .as_ref()
+            .map_or(false, |cfg| cfg.namespace_by_genesis)
+        {
+            self.block0_hash().map(|hash| hash.to_string())
+        } else {
+            None
+        }
+    }
+
+    fn namespaced_log_output(&self, output: LogOutput) -> LogOutput {
+        match (output, self.genesis_namespace()) {
+            (LogOutput::File(path), Some(namespace)) => {
+                let path = Path::new(&path);
+                let file_name = path.file_name().unwrap_or_default();
+                let parent = path.parent().unwrap_or_else(|| Path::new(""));
+                LogOutput::File(
+                    parent
+                        .join(namespace)
+                        .join(file_name)
+                        .to_string_lossy()
+                        .into_owned(),
+                )
+            }
+            (output, _) => output,
+        }
+    }
+
+    pub fn tracing_settings(&self) -> Option<Tracing> {
+        self.config.as_ref().and_then(|cfg| cfg.tracing.clone())
+    }
+
     fn rest_config(&self) -> Option<Rest> {
         let cmd_listen_opt = self.command_line.rest_arguments.listen;
         let config_rest_opt = self.config.as_ref().and_then(|cfg| cfg.rest.as_ref());
@@ -108,6 +267,11 @@ impl RawSettings {
                 listen: cmd_listen,
                 tls: None,
                 cors: None,
+                admin_token: None,
+                reuse_port: false,
+                unix_socket: None,
+                virtual_host: None,
+                max_addresses_per_request: None,
             }),
             (None, None) => None,
         }
@@ -120,12 +284,14 @@ impl RawSettings {
     /// This function will print&exit if anything is not as it should be.
     pub fn try_into_settings(self, logger: &Logger) -> Result<Settings, Error> {
         let rest = self.rest_config();
+        let block0_hash = self.block0_hash();
+        let genesis_namespace = self.genesis_namespace();
         let RawSettings {
             command_line,
             config,
         } = self;
         let command_arguments = &command_line.start_arguments;
-        let network = generate_network(&command_arguments, &config, &logger)?;
+        let mut network = generate_network(&command_arguments, &config, &logger)?;
 
         let storage = match (
             command_arguments.storage.as_ref(),
@@ -136,6 +302,35 @@ impl RawSettings {
             (None, None) => None,
         };
 
+        if let (Some(command_line), Some(config)) = (
+            command_arguments.block_0_hash,
+            config
+                .as_ref()
+                .and_then(|cfg| cfg.genesis_block_hash)
+                .map(|hash| hash.into_hash()),
+        ) {
+            if command_line != config {
+                return Err(Error::Block0HashMismatch {
+                    command_line,
+                    config,
+                });
+            }
+        }
+
+        let namespace_by_genesis = config
+            .as_ref()
+            .map_or(false, |cfg| cfg.namespace_by_genesis);
+        if namespace_by_genesis && genesis_namespace.is_none() {
+            return Err(Error::ExpectedBlock0HashForNamespace);
+        }
+        let storage = match (storage, genesis_namespace) {
+            (Some(path), Some(namespace)) => Some(path.join(namespace)),
+            (storage, _) => storage,
+        };
+        network.policy_records = storage
+            .as_ref()
+            .map(|dir| dir.join(network::POLICY_RECORDS_FILE));
+
         let mut secrets = command_arguments.secret.clone();
         if let Some(secret_files) = config.as_ref().map(|cfg| cfg.secret_files.clone()) {
             secrets.extend(secret_files);
@@ -148,11 +343,13 @@ impl RawSettings {
             );
         };
 
-        let block_0 = match (
-            &command_arguments.block_0_path,
-            &command_arguments.block_0_hash,
-        ) {
+        let block_0 = match (&command_arguments.block_0_path, &block0_hash) {
             (None, None) => return Err(Error::ExpectedBlock0Info),
+            (Some(path), hash) if is_block0_url(path) => {
+                let url = path.to_string_lossy().into_owned();
+                let hash = hash.ok_or(Error::ExpectedBlock0HashForUrl)?;
+                Block0Info::Url(url, hash)
+            }
             (Some(path), Some(hash)) => Block0Info::Path(path.clone(), Some(*hash)),
             (Some(path), None) => Block0Info::Path(path.clone(), None),
             (None, Some(hash)) => Block0Info::Hash(*hash),
@@ -165,6 +362,15 @@ impl RawSettings {
                     .map_or(false, |settings| settings.enabled)
             });
 
+        let mempool = config
+            .as_ref()
+            .map_or(Mempool::default(), |cfg| cfg.mempool.clone());
+        check_max_content_size(&mempool)?;
+        network.max_block_content_size = mempool.selection_quotas.max_content_size;
+        if mempool.confirmed_fragments_epochs == Some(0) {
+            return Err(Error::ZeroConfirmedFragmentsEpochs);
+        }
+
         Ok(Settings {
             storage,
             block_0,
@@ -172,13 +378,13 @@ impl RawSettings {
             secrets,
             rewards_report_all: command_line.rewards_report_all,
             rest,
-            mempool: config
-                .as_ref()
-                .map_or(Mempool::default(), |cfg| cfg.mempool.clone()),
+            mempool,
             leadership: config
                 .as_ref()
                 .map_or(Leadership::default(), |cfg| cfg.leadership.clone()),
             explorer,
+            read_only: command_arguments.read_only,
+            relay: command_arguments.relay,
             no_blockchain_updates_warning_interval: config
                 .as_ref()
                 .and_then(|config| config.no_blockchain_updates_warning_interval)
@@ -186,12 +392,69 @@ impl RawSettings {
                 .unwrap_or_else(|| {
                     std::time::Duration::from_secs(DEFAULT_NO_BLOCKCHAIN_UPDATES_WARNING_INTERVAL)
                 }),
+            backup: config
+                .as_ref()
+                .and_then(|config| config.backup.as_ref())
+                .map(backup_settings)
+                .transpose()?,
+            rewards_history_retention: config
+                .as_ref()
+                .and_then(|config| config.rewards_history.as_ref())
+                .map_or(DEFAULT_REWARDS_HISTORY_RETENTION, |config| config.retention),
+            vote_plan_stats_samples: config
+                .as_ref()
+                .and_then(|config| config.vote_plan_stats.as_ref())
+                .map(|config| config.samples),
+            epoch_hooks: config
+                .as_ref()
+                .and_then(|config| config.epoch_hooks.as_ref())
+                .map(epoch_hook_settings)
+                .transpose()?,
+            clock_check: ClockCheckSettings {
+                ntp_server: config
+                    .as_ref()
+                    .and_then(|config| config.clock_check.as_ref())
+                    .and_then(|clock_check| clock_check.ntp_server.clone()),
+                max_skew: config
+                    .as_ref()
+                    .and_then(|config| config.clock_check.as_ref())
+                    .and_then(|clock_check| clock_check.max_skew)
+                    .map(|d| d.into())
+                    .unwrap_or_else(|| std::time::Duration::from_secs(DEFAULT_MAX_CLOCK_SKEW)),
+            },
         })
     }
 }
 
+/// the cap on the content size of the produced blocks has to leave room
+/// for the space reserved to the fragment types
+fn check_sampling_rate(tracing: &Tracing) -> Result<(), Error> {
+    if (0.0..=1.0).contains(&tracing.sampling_rate) {
+        Ok(())
+    } else {
+        Err(Error::SamplingRateOutOfRange(tracing.sampling_rate))
+    }
+}
+
+fn check_max_content_size(mempool: &Mempool) -> Result<(), Error> {
+    let quotas = &mempool.selection_quotas;
+    let max_content_size = match quotas.max_content_size {
+        Some(0) => return Err(Error::ZeroMaxContentSize),
+        Some(max_content_size) => max_content_size,
+        None => return Ok(()),
+    };
+    let reserved: u64 = quotas.reserved_size.values().map(|size| *size as u64).sum();
+    if reserved > max_content_size as u64 {
+        return Err(Error::ReservedSizeAboveMaxContentSize {
+            reserved,
+            max_content_size,
+        });
+    }
+    Ok(())
+}
+
 #[allow(deprecated)]
-fn generate_network(
+pub(crate) fn generate_network(
     command_arguments: &StartArguments,
     config: &Option<Config>,
     logger: &Logger,
@@ -216,6 +479,29 @@ fn generate_network(
         p2p.trusted_peers = Some(command_arguments.trusted_peer.clone())
     }
 
+    let gossip_interval = p2p
+        .gossip_interval
+        .map(|d| d.into())
+        .unwrap_or(network::DEFAULT_GOSSIP_INTERVAL);
+    if gossip_interval < network::MIN_GOSSIP_INTERVAL {
+        return Err(Error::GossipIntervalTooShort(network::MIN_GOSSIP_INTERVAL));
+    }
+    if p2p.gossip_fanout == Some(0) {
+        return Err(Error::ZeroGossipFanout);
+    }
+    if p2p.handshake_nonces.max_pending == Some(0) {
+        return Err(Error::ZeroHandshakeNonces);
+    }
+    if p2p.stream_limits.max_headers == Some(0) {
+        return Err(Error::ZeroStreamLimit("max_headers"));
+    }
+    if p2p.stream_limits.max_blocks == Some(0) {
+        return Err(Error::ZeroStreamLimit("max_blocks"));
+    }
+    if p2p.stream_limits.max_block_bytes == Some(0) {
+        return Err(Error::ZeroStreamLimit("max_block_bytes"));
+    }
+
     let trusted_peers = p2p.trusted_peers.as_ref().map_or_else(Vec::new, |peers| {
         peers
             .iter()
@@ -240,11 +526,20 @@ fn generate_network(
             .collect()
     });
 
+    let listen_addresses = listen_addresses(command_arguments, &p2p)?;
+    let port_mapping = p2p.port_mapping.clone();
+    let advertised_listen_address = advertised_listen_address(&p2p)?;
+
+    let public_address = command_arguments
+        .public_address
+        .clone()
+        .or_else(|| p2p.public_address.clone())
+        .or(advertised_listen_address);
+
     let mut profile = poldercast::NodeProfileBuilder::new();
 
-    if let Some(address) = command_arguments.public_address.clone() {
-        profile.address(address);
-    } else if let Some(address) = p2p.public_address {
+    if let Some(address) = public_address {
+        check_public_address(&address, p2p.allow_private_addresses, logger)?;
         profile.address(address);
     }
 
@@ -253,10 +548,10 @@ fn generate_network(
         .unwrap_or_else(|| poldercast::Id::generate(rand::thread_rng()));
     profile.id(legacy_node_id);
 
-    for (topic, interest_level) in p2p
+    let topics_of_interest = p2p
         .topics_of_interest
-        .unwrap_or_else(config::default_interests)
-    {
+        .unwrap_or_else(config::default_interests);
+    for (topic, interest_level) in topics_of_interest.clone() {
         let sub = poldercast::Subscription {
             topic: topic.0,
             interest: interest_level.0,
@@ -264,20 +559,17 @@ fn generate_network(
         profile.add_subscription(sub);
     }
 
-    let p2p_listen_address = p2p.listen_address.as_ref();
-    let listen_address = command_arguments
-        .listen_address
-        .as_ref()
-        .or(p2p_listen_address)
-        .map(|v| v.to_socket_addr().ok_or(Error::ListenAddressNotValid))
-        .transpose()?;
-
     let mut network = network::Configuration {
         profile: profile.build(),
-        listen_address,
+        topics_of_interest,
+        listen_addresses,
+        reuse_port: p2p.reuse_port,
         trusted_peers,
         protocol: Protocol::Grpc,
+        transport: NodeTransport::Tcp,
         policy: p2p.policy.clone(),
+        policy_records: None,
+        max_block_content_size: None,
         layers: p2p.layers.clone(),
         max_connections: p2p
             .max_connections
@@ -285,21 +577,93 @@ fn generate_network(
         max_inbound_connections: p2p
             .max_inbound_connections
             .unwrap_or(network::DEFAULT_MAX_INBOUND_CONNECTIONS),
+        max_server_requests: p2p
+            .max_server_requests
+            .unwrap_or(network::DEFAULT_MAX_SERVER_REQUESTS),
+        max_client_requests: p2p
+            .max_client_requests
+            .unwrap_or(network::DEFAULT_MAX_CLIENT_REQUESTS),
+        method_limits: network::MethodLimitsSettings {
+            pull_blocks: method_limit(
+                &p2p.method_limits.pull_blocks,
+                network::DEFAULT_MAX_CONCURRENT_PULL_BLOCKS,
+            ),
+            get_blocks: method_limit(
+                &p2p.method_limits.get_blocks,
+                network::DEFAULT_MAX_CONCURRENT_GET_BLOCKS,
+            ),
+            push_headers: method_limit(
+                &p2p.method_limits.push_headers,
+                network::DEFAULT_MAX_CONCURRENT_PUSH_HEADERS,
+            ),
+        },
+        stream_limits: network::StreamLimits {
+            max_headers: p2p
+                .stream_limits
+                .max_headers
+                .unwrap_or(network::DEFAULT_MAX_STREAM_HEADERS),
+            max_blocks: p2p
+                .stream_limits
+                .max_blocks
+                .unwrap_or(network::DEFAULT_MAX_STREAM_BLOCKS),
+            max_block_bytes: p2p
+                .stream_limits
+                .max_block_bytes
+                .unwrap_or(network::DEFAULT_MAX_STREAM_BLOCK_BYTES),
+        },
+        handshake_nonces: network::HandshakeNonces {
+            expiry: p2p
+                .handshake_nonces
+                .expiry
+                .map(|d| d.into())
+                .unwrap_or(network::DEFAULT_HANDSHAKE_NONCE_EXPIRY),
+            max_pending: p2p
+                .handshake_nonces
+                .max_pending
+                .unwrap_or(network::DEFAULT_MAX_PENDING_HANDSHAKE_NONCES),
+        },
+        reply_timeout: p2p
+            .reply_timeout
+            .map(|d| d.into())
+            .unwrap_or(network::DEFAULT_REPLY_TIMEOUT),
+        block_cache_size: p2p
+            .block_cache_size
+            .unwrap_or(network::DEFAULT_BLOCK_CACHE_SIZE),
+        overflow_policies: network::OverflowPolicies {
+            fragments: p2p
+                .overflow_policies
+                .fragments
+                .unwrap_or(network::DEFAULT_FRAGMENT_OVERFLOW_POLICY),
+            gossip: p2p
+                .overflow_policies
+                .gossip
+                .unwrap_or(network::DEFAULT_GOSSIP_OVERFLOW_POLICY),
+        },
+        port_mapping: None,
+        block_fetch_strategy: p2p
+            .block_fetch_strategy
+            .unwrap_or(network::DEFAULT_BLOCK_FETCH_STRATEGY),
+        mempool_warmup: p2p.mempool_warmup,
         timeout: std::time::Duration::from_secs(15),
         allow_private_addresses: p2p.allow_private_addresses,
-        max_unreachable_nodes_to_connect_per_event: p2p.max_unreachable_nodes_to_connect_per_event,
-        gossip_interval: p2p
-            .gossip_interval
-            .map(|d| d.into())
-            .unwrap_or_else(|| std::time::Duration::from_secs(10)),
+        max_unreachable_nodes_to_connect_per_event: p2p
+            .max_unreachable_nodes_to_connect_per_event
+            .unwrap_or(network::DEFAULT_MAX_UNREACHABLE_NODES_TO_CONNECT_PER_EVENT),
+        gossip_interval,
+        gossip_fanout: p2p.gossip_fanout,
         topology_force_reset_interval: p2p.topology_force_reset_interval.map(|d| d.into()),
         max_bootstrap_attempts: p2p.max_bootstrap_attempts,
         http_fetch_block0_service,
         bootstrap_from_trusted_peers,
         skip_bootstrap,
         legacy_node_id: Some(legacy_node_id),
+        trusted_node_ids: p2p.client_auth.map(|auth| auth.trusted_node_ids),
     };
 
+    network.port_mapping = port_mapping
+        .map(|config| port_mapping_settings(&config, &network))
+        .transpose()?;
+
     if network.max_inbound_connections > network.max_connections {
         warn!(
             logger,
@@ -312,3 +676,323 @@ fn generate_network(
 
     Ok(network)
 }
+
+/// the addresses to listen on: the one given on the command line, else the
+/// ones of the configuration file
+fn listen_addresses(
+    command_arguments: &StartArguments,
+    p2p: &config::P2pConfig,
+) -> Result<Vec<SocketAddr>, Error> {
+    let addresses: Vec<&poldercast::Address> = match (
+        &command_arguments.listen_address,
+        &p2p.listen_address,
+        p2p.listen.is_empty(),
+    ) {
+        (Some(address), _, _) => vec![address],
+        (None, Some(_), false) => return Err(Error::ListenAddressConflict),
+        (None, Some(address), true) => vec![address],
+        (None, None, _) => p2p.listen.iter().map(|listen| &listen.address).collect(),
+    };
+    addresses
+        .into_iter()
+        .map(|address| address.to_socket_addr().ok_or(Error::ListenAddressNotValid))
+        .collect()
+}
+
+/// the listen address to advertise as the public address of the node, if any
+fn advertised_listen_address(
+    p2p: &config::P2pConfig,
+) -> Result<Option<poldercast::Address>, Error> {
+    let mut advertised = p2p.listen.iter().filter(|listen| listen.advertise);
+    let listen = match (advertised.next(), advertised.next()) {
+        (None, _) => return Ok(None),
+        (Some(_), Some(_)) => return Err(Error::SeveralAdvertisedListenAddresses),
+        (Some(listen), None) => listen,
+    };
+    if p2p.public_address.is_some() {
+        return Err(Error::AdvertisedListenAddressWithPublicAddress);
+    }
+    let addr = listen
+        .address
+        .to_socket_addr()
+        .ok_or(Error::ListenAddressNotValid)?;
+    if addr.ip().is_unspecified() {
+        return Err(Error::UnspecifiedAdvertisedListenAddress(addr));
+    }
+    Ok(Some(listen.address.clone()))
+}
+
+/// check the public address of the node before it is advertised in the
+/// gossip: the peers drop the gossip about the nodes they cannot connect to.
+/// The addresses which are not IP ones, like DNS names, are resolved by the
+/// peers and are left as they are.
+fn check_public_address(
+    address: &poldercast::Address,
+    allow_private_addresses: bool,
+    logger: &Logger,
+) -> Result<(), Error> {
+    let addr = match address.to_socket_addr() {
+        Some(addr) => addr,
+        None => return Ok(()),
+    };
+    if !p2p::is_valid_address(&addr) {
+        return Err(Error::PublicAddressNotAdvertisable(addr));
+    }
+    if !allow_private_addresses && !p2p::is_global_address(&addr) {
+        warn!(
+            logger,
+            "the public address of the node is not a global address, the peers not allowing private addresses will ignore it";
+            "address" => %addr,
+        );
+    }
+    Ok(())
+}
+
+/// map the port of the public address to the port the node listens to
+fn port_mapping_settings(
+    config: &config::PortMappingConfig,
+    network: &network::Configuration,
+) -> Result<network::PortMappingSettings, Error> {
+    let external_port = network
+        .address()
+        .and_then(|address| address.to_socket_addr())
+        .map(|addr| addr.port())
+        .ok_or(Error::PortMappingWithoutPublicAddress)?;
+    let internal_port = network
+        .listen_addresses
+        .first()
+        .map_or(external_port, |addr| addr.port());
+    let lease_duration = config
+        .lease_duration
+        .map(|d| d.into())
+        .unwrap_or(network::DEFAULT_PORT_MAPPING_LEASE);
+    // a NAT-PMP request with a lifetime of 0 removes the mapping
+    if lease_duration.as_secs() == 0 {
+        return Err(Error::ZeroPortMappingLease);
+    }
+    Ok(network::PortMappingSettings {
+        protocol: config.protocol.unwrap_or(PortMappingProtocol::Any),
+        lease_duration,
+        gateway: config.gateway,
+        internal_port,
+        external_port,
+    })
+}
+
+fn method_limit(
+    config: &config::MethodLimitConfig,
+    default_max_concurrent: usize,
+) -> network::MethodLimitSettings {
+    network::MethodLimitSettings {
+        max_concurrent: config.max_concurrent.unwrap_or(default_max_concurrent),
+        timeout: config
+            .timeout
+            .map(|d| d.into())
+            .unwrap_or(network::DEFAULT_METHOD_TIMEOUT),
+    }
+}
+
+fn is_block0_url(path: &std::path::Path) -> bool {
+    path.to_str().map_or(false, |path| {
+        path.starts_with("https://") || path.starts_with("http://")
+    })
+}
+
+fn epoch_hook_settings(config: &config::EpochHooks) -> Result<EpochHookSettings, Error> {
+    if config.command.is_none() && config.webhook.is_none() {
+        return Err(Error::EmptyEpochHooks);
+    }
+    let webhook = config
+        .webhook
+        .as_ref()
+        .map(|url| {
+            url.parse()
+                .map_err(|_| Error::InvalidEpochHookWebhook(url.clone()))
+        })
+        .transpose()?;
+    Ok(EpochHookSettings {
+        command: config.command.clone(),
+        webhook,
+    })
+}
+
+fn backup_settings(config: &config::Backup) -> Result<BackupSettings, Error> {
+    let schedule = match (config.interval, config.every_epochs) {
+        (Some(interval), None) => BackupSchedule::Interval(interval.into()),
+        (None, Some(epochs)) if epochs > 0 => BackupSchedule::Epochs(epochs),
+        _ => return Err(Error::InvalidBackupSchedule),
+    };
+    Ok(BackupSettings {
+        directory: config.directory.clone(),
+        schedule,
+        keep: config.keep.unwrap_or(DEFAULT_BACKUPS_KEPT).max(1),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use structopt::StructOpt;
+
+    fn p2p_config(yaml: &str) -> config::P2pConfig {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn start_arguments(args: &[&str]) -> StartArguments {
+        StartArguments::from_iter(std::iter::once("jormungandr").chain(args.iter().copied()))
+    }
+
+    fn addrs(addrs: &[&str]) -> Vec<SocketAddr> {
+        addrs.iter().map(|addr| addr.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn all_listen_addresses_are_used() {
+        let p2p = p2p_config(
+            "listen:\n  - address: /ip4/10.0.0.1/tcp/3000\n  - address: /ip4/0.0.0.0/tcp/3001\n",
+        );
+        assert_eq!(
+            listen_addresses(&start_arguments(&[]), &p2p).unwrap(),
+            addrs(&["10.0.0.1:3000", "0.0.0.0:3001"])
+        );
+    }
+
+    #[test]
+    fn command_line_listen_address_takes_precedence() {
+        let p2p = p2p_config("listen:\n  - address: /ip4/10.0.0.1/tcp/3000\n");
+        let args = start_arguments(&["--listen-address", "/ip4/127.0.0.1/tcp/4000"]);
+        assert_eq!(
+            listen_addresses(&args, &p2p).unwrap(),
+            addrs(&["127.0.0.1:4000"])
+        );
+    }
+
+    #[test]
+    fn listen_address_and_listen_conflict() {
+        let p2p = p2p_config(
+            "listen_address: /ip4/0.0.0.0/tcp/3000\nlisten:\n  - address: /ip4/10.0.0.1/tcp/3000\n",
+        );
+        assert!(matches!(
+            listen_addresses(&start_arguments(&[]), &p2p),
+            Err(Error::ListenAddressConflict)
+        ));
+        let p2p = p2p_config("listen_address: /ip4/0.0.0.0/tcp/3000\n");
+        assert_eq!(
+            listen_addresses(&start_arguments(&[]), &p2p).unwrap(),
+            addrs(&["0.0.0.0:3000"])
+        );
+    }
+
+    #[test]
+    fn at_most_one_listen_address_is_advertised() {
+        let p2p = p2p_config(
+            "listen:\n  - address: /ip4/10.0.0.1/tcp/3000\n  - address: /ip4/1.2.3.4/tcp/3001\n    advertise: true\n",
+        );
+        assert_eq!(
+            advertised_listen_address(&p2p)
+                .unwrap()
+                .map(|address| address.to_string()),
+            Some("/ip4/1.2.3.4/tcp/3001".to_owned())
+        );
+        assert!(advertised_listen_address(&p2p_config("listen: []\n"))
+            .unwrap()
+            .is_none());
+
+        let p2p = p2p_config(
+            "listen:\n  - address: /ip4/10.0.0.1/tcp/3000\n    advertise: true\n  - address: /ip4/1.2.3.4/tcp/3001\n    advertise: true\n",
+        );
+        assert!(matches!(
+            advertised_listen_address(&p2p),
+            Err(Error::SeveralAdvertisedListenAddresses)
+        ));
+    }
+
+    #[test]
+    fn advertised_listen_address_must_be_reachable() {
+        let p2p = p2p_config("listen:\n  - address: /ip4/0.0.0.0/tcp/3000\n    advertise: true\n");
+        assert!(matches!(
+            advertised_listen_address(&p2p),
+            Err(Error::UnspecifiedAdvertisedListenAddress(_))
+        ));
+
+        let p2p = p2p_config(
+            "public_address: /ip4/1.2.3.4/tcp/3000\nlisten:\n  - address: /ip4/1.2.3.4/tcp/3000\n    advertise: true\n",
+        );
+        assert!(matches!(
+            advertised_listen_address(&p2p),
+            Err(Error::AdvertisedListenAddressWithPublicAddress)
+        ));
+    }
+
+    fn network_config(yaml: &str) -> Result<network::Configuration, Error> {
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        generate_network(
+            &start_arguments(&[]),
+            &Some(config),
+            &Logger::root(slog::Discard, o!()),
+        )
+    }
+
+    #[test]
+    fn gossip_settings_fall_back_to_the_defaults() {
+        let network = network_config("p2p: {}\n").unwrap();
+        assert_eq!(network.gossip_interval, network::DEFAULT_GOSSIP_INTERVAL);
+        assert_eq!(network.gossip_fanout, None);
+        assert_eq!(
+            network.max_unreachable_nodes_to_connect_per_event,
+            network::DEFAULT_MAX_UNREACHABLE_NODES_TO_CONNECT_PER_EVENT
+        );
+
+        let network = network_config("p2p:\n  gossip_interval: 2s\n  gossip_fanout: 8\n").unwrap();
+        assert_eq!(network.gossip_interval, std::time::Duration::from_secs(2));
+        assert_eq!(network.gossip_fanout, Some(8));
+    }
+
+    #[test]
+    fn gossip_settings_are_checked() {
+        assert!(matches!(
+            network_config("p2p:\n  gossip_interval: 500ms\n"),
+            Err(Error::GossipIntervalTooShort(_))
+        ));
+        assert!(matches!(
+            network_config("p2p:\n  gossip_fanout: 0\n"),
+            Err(Error::ZeroGossipFanout)
+        ));
+    }
+
+    fn tracing(sampling_rate: f64) -> Tracing {
+        Tracing {
+            otlp_endpoint: "http://localhost:4317".to_owned(),
+            sampling_rate,
+        }
+    }
+
+    #[test]
+    fn method_limits_fall_back_to_the_defaults() {
+        let config: config::MethodLimitConfig = serde_yaml::from_str("max_concurrent: 4").unwrap();
+        let settings = method_limit(&config, 32);
+        assert_eq!(settings.max_concurrent, 4);
+        assert_eq!(settings.timeout, network::DEFAULT_METHOD_TIMEOUT);
+
+        let config: config::MethodLimitConfig = serde_yaml::from_str("timeout: 5s").unwrap();
+        let settings = method_limit(&config, 32);
+        assert_eq!(settings.max_concurrent, 32);
+        assert_eq!(settings.timeout, std::time::Duration::from_secs(5));
+    }
+
+    #[test]
+    fn sampling_rate_must_be_a_ratio() {
+        assert!(check_sampling_rate(&tracing(0.0)).is_ok());
+        assert!(check_sampling_rate(&tracing(0.25)).is_ok());
+        assert!(check_sampling_rate(&tracing(1.0)).is_ok());
+        assert!(matches!(
+            check_sampling_rate(&tracing(1.5)),
+            Err(Error::SamplingRateOutOfRange(_))
+        ));
+        assert!(matches!(
+            check_sampling_rate(&tracing(-0.1)),
+            Err(Error::SamplingRateOutOfRange(_))
+        ));
+        assert!(check_sampling_rate(&tracing(f64::NAN)).is_err());
+    }
+}
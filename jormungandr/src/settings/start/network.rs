@@ -1,11 +1,29 @@
 #![allow(deprecated)]
 use super::config;
-use crate::network::p2p::{layers::LayersConfig, Address, PolicyConfig};
-use jormungandr_lib::multiaddr::{self, multiaddr_resolve_dns};
+use crate::network::{
+    p2p::{
+        comm::{OverflowPolicy, PeerSelectionStrategy},
+        layers::LayersConfig,
+        Address, PolicyConfig,
+    },
+    port_mapping::PortMappingProtocol,
+    NodeTransport,
+};
+use chain_crypto::Ed25519;
+use jormungandr_lib::{
+    crypto::key::Identifier,
+    multiaddr::{self, multiaddr_resolve_dns},
+};
 use poldercast::NodeProfile;
 
 use std::convert::TryFrom;
-use std::{net::SocketAddr, str, time::Duration};
+use std::{
+    collections::BTreeMap,
+    net::{Ipv4Addr, SocketAddr},
+    path::PathBuf,
+    str,
+    time::Duration,
+};
 
 /// Protocol to use for a connection.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -46,6 +64,141 @@ pub const DEFAULT_MAX_CONNECTIONS: usize = 256;
 /// used unless the corresponding configuration option is specified.
 pub const DEFAULT_MAX_INBOUND_CONNECTIONS: usize = 192;
 
+/// The limit on the number of requests from the peers served concurrently
+/// used unless the corresponding configuration option is specified.
+pub const DEFAULT_MAX_SERVER_REQUESTS: usize = 512;
+
+/// The limit on the number of concurrent requests made to the peers
+/// used unless the corresponding configuration option is specified.
+pub const DEFAULT_MAX_CLIENT_REQUESTS: usize = 64;
+
+/// The time limit for serving a request of a peer entirely, used unless
+/// the corresponding configuration option is specified. It is long enough
+/// for a peer to pull the whole chain when bootstrapping.
+pub const DEFAULT_REPLY_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// The number of blocks recently served to the peers kept in memory,
+/// used unless the corresponding configuration option is specified.
+pub const DEFAULT_BLOCK_CACHE_SIZE: usize = 256;
+
+/// limits on the requests served for a method of the p2p protocol
+#[derive(Clone, Debug)]
+pub struct MethodLimitSettings {
+    /// Maximum number of requests served concurrently, the requests
+    /// over this limit are rejected
+    pub max_concurrent: usize,
+    /// Time limit for the node to start serving a request, or to
+    /// process it entirely for the requests without a response stream
+    pub timeout: Duration,
+}
+
+#[derive(Clone, Debug)]
+pub struct MethodLimitsSettings {
+    pub pull_blocks: MethodLimitSettings,
+    pub get_blocks: MethodLimitSettings,
+    pub push_headers: MethodLimitSettings,
+}
+
+/// The default time limit for the methods with a limit on the requests
+pub const DEFAULT_METHOD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The default limit on the concurrent PullBlocks requests
+pub const DEFAULT_MAX_CONCURRENT_PULL_BLOCKS: usize = 32;
+
+/// The default limit on the concurrent GetBlocks requests
+pub const DEFAULT_MAX_CONCURRENT_GET_BLOCKS: usize = 128;
+
+/// The default limit on the concurrent PushHeaders requests
+pub const DEFAULT_MAX_CONCURRENT_PUSH_HEADERS: usize = 64;
+
+/// limits on the size of the streams of blocks and headers received from
+/// the peers
+#[derive(Clone, Debug)]
+pub struct StreamLimits {
+    pub max_headers: usize,
+    pub max_blocks: usize,
+    pub max_block_bytes: u64,
+}
+
+/// The default limit on the number of headers in a stream. A chain pull
+/// longer than that stops at the limit, the rest of the chain is left to the
+/// next chain pull.
+pub const DEFAULT_MAX_STREAM_HEADERS: usize = 50_000;
+
+/// The default limit on the number of blocks in a stream, as many as the
+/// headers of a chain pull
+pub const DEFAULT_MAX_STREAM_BLOCKS: usize = DEFAULT_MAX_STREAM_HEADERS;
+
+/// The default limit on the total size of the blocks of a stream
+pub const DEFAULT_MAX_STREAM_BLOCK_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// limits on the nonces sent to the peers in the handshake, for them to
+/// authenticate with
+#[derive(Clone, Debug)]
+pub struct HandshakeNonces {
+    /// time after which a nonce can no longer be used
+    pub expiry: Duration,
+    /// maximum number of nonces not used yet, the oldest ones are dropped
+    pub max_pending: usize,
+}
+
+/// The default time after which a handshake nonce expires. The peers
+/// authenticate right after the handshake.
+pub const DEFAULT_HANDSHAKE_NONCE_EXPIRY: Duration = Duration::from_secs(60);
+
+/// The default limit on the number of handshake nonces not used yet
+pub const DEFAULT_MAX_PENDING_HANDSHAKE_NONCES: usize = 1024;
+
+/// What to do with the items to send to the peers whose subscription
+/// buffer is full, by subscription. The block announcements are never
+/// dropped.
+#[derive(Clone, Debug)]
+pub struct OverflowPolicies {
+    pub fragments: OverflowPolicy,
+    pub gossip: OverflowPolicy,
+}
+
+/// The default overflow policy of the fragment subscriptions: the newest
+/// fragments are the most likely to still be valid
+pub const DEFAULT_FRAGMENT_OVERFLOW_POLICY: OverflowPolicy = OverflowPolicy::DropOldest;
+
+/// The default overflow policy of the gossip subscriptions
+pub const DEFAULT_GOSSIP_OVERFLOW_POLICY: OverflowPolicy = OverflowPolicy::DropNewest;
+
+/// The default strategy choosing the peer to fetch the missing blocks from
+pub const DEFAULT_BLOCK_FETCH_STRATEGY: PeerSelectionStrategy = PeerSelectionStrategy::MostRecent;
+
+/// The default number of unreachable nodes to connect to for every
+/// propagation event
+pub const DEFAULT_MAX_UNREACHABLE_NODES_TO_CONNECT_PER_EVENT: usize = 20;
+
+/// The default interval between the gossip rounds
+pub const DEFAULT_GOSSIP_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The shortest interval between the gossip rounds accepted in the
+/// configuration
+pub const MIN_GOSSIP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The default lease of the port mapped on the router
+pub const DEFAULT_PORT_MAPPING_LEASE: Duration = Duration::from_secs(60 * 60);
+
+/// how to map the public port of the node on the router of the local network
+#[derive(Clone, Debug)]
+pub struct PortMappingSettings {
+    pub protocol: PortMappingProtocol,
+    pub lease_duration: Duration,
+    /// the NAT-PMP gateway, the gateway of the default route if not set
+    pub gateway: Option<Ipv4Addr>,
+    /// the port the node listens to
+    pub internal_port: u16,
+    /// the port of the public address of the node
+    pub external_port: u16,
+}
+
+/// The name of the file of the storage directory holding the quarantine
+/// records of the policy
+pub const POLICY_RECORDS_FILE: &str = "p2p_quarantine_records.json";
+
 /// The default timeout for connections
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
 
@@ -53,39 +206,95 @@ const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
 /// The network static configuration settings
 #[derive(Clone)]
 pub struct Configuration {
-    /// Local socket address to listen to, if different from public address.
+    /// Local socket addresses to listen to, if different from public address.
     /// The IP address can be given as 0.0.0.0 or :: to bind to all
     /// network interfaces.
-    pub listen_address: Option<SocketAddr>,
+    pub listen_addresses: Vec<SocketAddr>,
+
+    /// Whether to bind the listening socket with `SO_REUSEPORT`
+    pub reuse_port: bool,
 
     pub profile: NodeProfile,
 
+    /// The topics subscribed to in the profile, with the interest in them
+    pub topics_of_interest: BTreeMap<config::Topic, config::InterestLevel>,
+
     /// list of trusted addresses
     pub trusted_peers: Vec<TrustedPeer>,
 
     /// the protocol to utilise for the p2p network
     pub protocol: Protocol,
 
+    /// the transport of the connections with the peers, TCP unless the node
+    /// runs in a cluster
+    pub transport: NodeTransport,
+
     /// Maximum allowed number of peer connections.
     pub max_connections: usize,
 
     /// Maximum allowed number of client connections.
     pub max_inbound_connections: usize,
 
+    /// Maximum number of requests from the peers served concurrently.
+    pub max_server_requests: usize,
+
+    /// Maximum number of requests to the peers made concurrently
+    /// by this node to catch up with the network.
+    pub max_client_requests: usize,
+
+    /// Limits on the requests served for the most expensive methods
+    pub method_limits: MethodLimitsSettings,
+
+    /// Limits on the size of the streams received from the peers
+    pub stream_limits: StreamLimits,
+
+    /// Limits on the nonces of the handshakes
+    pub handshake_nonces: HandshakeNonces,
+
+    /// Time limit for serving a request of a peer entirely
+    pub reply_timeout: Duration,
+
+    /// Number of the blocks recently served to the peers kept in memory
+    pub block_cache_size: usize,
+
+    /// What to do when the subscription buffer of a peer is full
+    pub overflow_policies: OverflowPolicies,
+
+    /// How to choose the peer to fetch the missing blocks from
+    pub block_fetch_strategy: PeerSelectionStrategy,
+
+    /// The cap of the node on the content size of the blocks, the larger
+    /// blocks are not announced to the peers
+    pub max_block_content_size: Option<u32>,
+
+    /// Whether to fill the mempool from a trusted peer after bootstrap
+    pub mempool_warmup: bool,
+
+    /// How to map the public port on the router, if enabled
+    pub port_mapping: Option<PortMappingSettings>,
+
     /// the default value for the timeout for inactive connection
     pub timeout: Duration,
 
     pub policy: PolicyConfig,
 
+    /// The file the records of the policy on the peers are saved in, so they
+    /// survive the restarts of the node. Only set if the node has storage.
+    pub policy_records: Option<PathBuf>,
+
     pub layers: LayersConfig,
 
     /// Whether to allow non-public IP addresses in gossip
     pub allow_private_addresses: bool,
 
-    pub max_unreachable_nodes_to_connect_per_event: Option<usize>,
+    pub max_unreachable_nodes_to_connect_per_event: usize,
 
     pub gossip_interval: Duration,
 
+    /// The most peers to gossip with at every interval, all the peers of
+    /// the topology view if not set
+    pub gossip_fanout: Option<usize>,
+
     pub topology_force_reset_interval: Option<Duration>,
 
     pub max_bootstrap_attempts: Option<usize>,
@@ -100,6 +309,9 @@ pub struct Configuration {
 
     /// A pre-0.9 node ID to put in "node-id-bin" metadata when subscribing
     pub legacy_node_id: Option<poldercast::Id>,
+
+    /// If set, the inbound peers must authenticate with one of these node ids
+    pub trusted_node_ids: Option<Vec<Identifier<Ed25519>>>,
 }
 
 #[derive(Clone)]
@@ -166,15 +378,22 @@ impl Configuration {
         self.profile.address()
     }
 
-    /// Returns the listener configuration, if the options defining it
-    /// were set.
-    pub fn listen(&self) -> Option<Listen> {
-        self.listen_address
-            .or_else(|| {
-                self.profile
-                    .address()
-                    .and_then(|address| address.to_socket_addr())
-            })
-            .map(Listen::new)
+    /// Returns the listener configurations, listening on the public
+    /// address if no listen address is set.
+    pub fn listeners(&self) -> Vec<Listen> {
+        if self.listen_addresses.is_empty() {
+            self.profile
+                .address()
+                .and_then(|address| address.to_socket_addr())
+                .map(Listen::new)
+                .into_iter()
+                .collect()
+        } else {
+            self.listen_addresses
+                .iter()
+                .cloned()
+                .map(Listen::new)
+                .collect()
+        }
     }
 }
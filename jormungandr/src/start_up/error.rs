@@ -2,7 +2,7 @@ use crate::{
     blockcfg, blockchain,
     blockchain::StorageError,
     diagnostic::DiagnosticError,
-    explorer, network, secure,
+    explorer, leadership, network, secure,
     settings::{self, logging},
 };
 use std::io;
@@ -14,6 +14,25 @@ pub enum ErrorKind {
     BlockStorage,
     #[error("Block0")]
     Block0,
+    #[error("signed slots record")]
+    SignedSlots,
+    #[error("node key")]
+    NodeKey,
+    #[error("chain archive")]
+    ChainArchive,
+    #[error("rewards history")]
+    RewardsHistory,
+    #[error("epoch accounting")]
+    EpochAccounting,
+    #[error("vote plan archive")]
+    VotePlanArchive,
+    #[error("epoch hooks")]
+    EpochHooks,
+    #[error("leadership logs")]
+    LeadershipLogs,
+    #[cfg(feature = "in-memory-transport")]
+    #[error("cluster file")]
+    Cluster,
 }
 
 #[derive(Debug, Error)]
@@ -39,6 +58,11 @@ pub enum Error {
         expected: blockcfg::HeaderId,
         got: blockcfg::HeaderId,
     },
+    #[error("The storage holds the blockchain of the genesis block {stored}, not {expected}")]
+    StorageBlock0Mismatch {
+        expected: blockcfg::HeaderId,
+        stored: blockcfg::HeaderId,
+    },
     #[error("Storage error")]
     StorageError(#[from] StorageError),
     #[error("Error while loading the legacy blockchain state")]
@@ -47,6 +71,8 @@ pub enum Error {
     Block0(#[from] blockcfg::Block0Error),
     #[error("Error fetching the genesis block from the network")]
     FetchBlock0(#[from] network::FetchBlockError),
+    #[error("Unable to download the genesis block from {url}: {reason}")]
+    DownloadBlock0 { url: String, reason: String },
     #[error("Error while loading the blockchain from the network")]
     NetworkBootstrapError(#[source] network::BootstrapError),
     #[error("Error while loading the node's secrets.")]
@@ -61,6 +87,25 @@ pub enum Error {
     DiagnosticError(#[from] DiagnosticError),
     #[error("Interrupted by the user")]
     Interrupted,
+    #[error("Unable to preview the leadership schedule")]
+    LeadershipPreview(#[from] leadership::preview::PreviewError),
+    #[error("The node key can only be persisted in a storage directory, but none is configured")]
+    NodeKeyWithoutStorage,
+    #[error("The command requires a storage directory, but none is configured")]
+    StorageRequired,
+    #[error("Error while exporting or importing the chain archive")]
+    ChainArchive(#[from] blockchain::archive::Error),
+    #[cfg(feature = "in-memory-transport")]
+    #[error("The cluster file does not list any node")]
+    EmptyCluster,
+    #[cfg(feature = "in-memory-transport")]
+    #[error("Invalid settings for the node {name} of the cluster: {reason}")]
+    InvalidClusterNode { name: String, reason: String },
+    #[error("Relay mode is only supported with the BFT consensus, the leader signatures of Genesis Praos cannot be verified without the ledger state")]
+    RelayRequiresBft,
+    #[cfg(feature = "in-memory-transport")]
+    #[error("The node {name} of the cluster failed")]
+    ClusterNode { name: String, code: i32 },
 }
 
 impl From<network::BootstrapError> for Error {
@@ -85,13 +130,26 @@ impl Error {
             Error::Blockchain { .. } => 6,
             Error::Block0 { .. } => 7,
             Error::Block0Mismatch { .. } => 7,
+            Error::StorageBlock0Mismatch { .. } => 7,
             Error::Block0InFuture => 7,
             Error::NodeSecrets { .. } => 8,
             Error::FetchBlock0 { .. } => 9,
+            Error::DownloadBlock0 { .. } => 9,
             Error::NetworkBootstrapError { .. } => 10,
             Error::ExplorerBootstrapError { .. } => 11,
             Error::ServiceTerminatedWithError => 12,
             Error::DiagnosticError { .. } => 13,
+            Error::LeadershipPreview { .. } => 14,
+            Error::NodeKeyWithoutStorage => 15,
+            Error::StorageRequired => 17,
+            Error::ChainArchive { .. } => 16,
+            #[cfg(feature = "in-memory-transport")]
+            Error::EmptyCluster => 2,
+            #[cfg(feature = "in-memory-transport")]
+            Error::InvalidClusterNode { .. } => 2,
+            Error::RelayRequiresBft => 2,
+            #[cfg(feature = "in-memory-transport")]
+            Error::ClusterNode { code, .. } => *code,
         }
     }
 }
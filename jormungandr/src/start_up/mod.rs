@@ -2,12 +2,26 @@ mod error;
 
 pub use self::error::{Error, ErrorKind};
 use crate::{
-    blockcfg::{Block, HeaderId},
-    blockchain::{Blockchain, ErrorKind as BlockchainError, Storage, Tip},
-    log, network,
+    blockcfg::{Block, Block0DataSource as _, ConsensusVersion, HeaderId},
+    blockchain::{
+        archive, epoch_accounting, epoch_hooks, rewards_history, vote_archive, Blockchain,
+        EpochAccountingLog, EpochHooks, ErrorKind as BlockchainError, NotifiedEpochs,
+        RewardsHistory, Storage, Tip, VotePlanArchive, VoteStats,
+    },
+    log,
+    network::{self, node_key},
     settings::start::Settings,
 };
+use chain_crypto::Ed25519;
+use jormungandr_lib::crypto::key::SigningKey;
 use slog::Logger;
+use std::{fs::File, path::Path, time::Duration};
+
+/// number of attempts to download the block0 from a URL
+const BLOCK0_DOWNLOAD_ATTEMPTS: u32 = 5;
+/// delay before the first retry of the block0 download, doubled at
+/// every following retry
+const BLOCK0_DOWNLOAD_RETRY_DELAY: Duration = Duration::from_secs(1);
 
 /// prepare the block storage from the given settings
 pub fn prepare_storage(setting: &Settings, logger: &Logger) -> Result<Storage, Error> {
@@ -26,6 +40,219 @@ pub fn prepare_storage(setting: &Settings, logger: &Logger) -> Result<Storage, E
     }
 }
 
+/// load the history of the rewards, persisted in the storage directory
+/// when there is one.
+pub fn prepare_rewards_history(settings: &Settings) -> Result<RewardsHistory, Error> {
+    let retention = settings.rewards_history_retention;
+    match &settings.storage {
+        Some(dir) => {
+            RewardsHistory::open(rewards_history::path(dir), Some(retention)).map_err(|err| {
+                Error::IO {
+                    source: err,
+                    reason: ErrorKind::RewardsHistory,
+                }
+            })
+        }
+        None => Ok(RewardsHistory::in_memory(Some(retention))),
+    }
+}
+
+/// load the accounting of the epoch transitions, persisted in the storage
+/// directory when there is one.
+pub fn prepare_epoch_accounting(settings: &Settings) -> Result<EpochAccountingLog, Error> {
+    match &settings.storage {
+        Some(dir) => {
+            EpochAccountingLog::open(epoch_accounting::path(dir), None).map_err(|err| Error::IO {
+                source: err,
+                reason: ErrorKind::EpochAccounting,
+            })
+        }
+        None => Ok(EpochAccountingLog::in_memory(None)),
+    }
+}
+
+/// set up the hooks run at the epoch transitions, if configured. The latest
+/// epoch notified is persisted in the storage directory when there is one.
+pub fn prepare_epoch_hooks(
+    settings: &Settings,
+    logger: &Logger,
+) -> Result<Option<EpochHooks>, Error> {
+    let hooks = match &settings.epoch_hooks {
+        Some(hooks) => hooks.clone(),
+        None => return Ok(None),
+    };
+    let notified = match &settings.storage {
+        Some(dir) => {
+            NotifiedEpochs::open(epoch_hooks::path(dir), Some(1)).map_err(|err| Error::IO {
+                source: err,
+                reason: ErrorKind::EpochHooks,
+            })?
+        }
+        None => NotifiedEpochs::in_memory(Some(1)),
+    };
+    let logger = logger.new(o!(log::KEY_SUB_TASK => "epoch_hooks"));
+    Ok(Some(EpochHooks::new(hooks, notified, logger)))
+}
+
+/// load the final status of the closed vote plans, persisted in the
+/// storage directory when there is one.
+pub fn prepare_vote_plan_archive(settings: &Settings) -> Result<VotePlanArchive, Error> {
+    match &settings.storage {
+        Some(dir) => {
+            VotePlanArchive::open(vote_archive::path(dir), None).map_err(|err| Error::IO {
+                source: err,
+                reason: ErrorKind::VotePlanArchive,
+            })
+        }
+        None => Ok(VotePlanArchive::in_memory(None)),
+    }
+}
+
+/// load the key identifying the node in the p2p network. The key is
+/// persisted in the storage directory, a new key is generated for every
+/// run if the node has no storage.
+pub fn prepare_node_key(
+    settings: &Settings,
+    logger: &Logger,
+) -> Result<SigningKey<Ed25519>, Error> {
+    let key = match &settings.storage {
+        Some(dir) => {
+            let path = node_key::path(dir);
+            node_key::load_or_generate(&path).map_err(|err| Error::IO {
+                source: err,
+                reason: ErrorKind::NodeKey,
+            })?
+        }
+        None => node_key::generate(),
+    };
+    info!(logger, "node id: {}", key.identifier());
+    Ok(key)
+}
+
+/// replace the node key persisted in the storage directory with a new one
+pub fn rotate_node_key(settings: &Settings) -> Result<SigningKey<Ed25519>, Error> {
+    let dir = settings
+        .storage
+        .as_ref()
+        .ok_or(Error::NodeKeyWithoutStorage)?;
+    let io_error = |err| Error::IO {
+        source: err,
+        reason: ErrorKind::NodeKey,
+    };
+    std::fs::create_dir_all(dir).map_err(io_error)?;
+    node_key::rotate(&node_key::path(dir)).map_err(io_error)
+}
+
+/// download the block0 from the given URL and check it has the expected hash
+async fn fetch_block0_url(block0_id: &HeaderId, url: &str) -> Result<Block, String> {
+    use chain_core::property::Deserialize as _;
+
+    let response = reqwest::get(url)
+        .await
+        .map_err(|e| format!("cannot get {}", e))?;
+    if response.status() != reqwest::StatusCode::OK {
+        return Err(format!("fetch failed status code: {}", response.status()));
+    }
+
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| format!("cannot get data {}", e))?;
+    let block =
+        Block::deserialize(bytes.as_ref()).map_err(|err| format!("parse error on data {}", err))?;
+    let got = block.header.id();
+    if &got != block0_id {
+        return Err(format!("invalid block expecting {} got {}", block0_id, got));
+    }
+    Ok(block)
+}
+
+/// download the block0 from the URL given in place of the block0 path,
+/// retrying with an increasing delay on failure.
+async fn download_block0(logger: &Logger, url: &str, block0_id: &HeaderId) -> Result<Block, Error> {
+    let mut delay = BLOCK0_DOWNLOAD_RETRY_DELAY;
+    let mut attempt = 1;
+    loop {
+        match fetch_block0_url(block0_id, url).await {
+            Ok(block) => {
+                info!(logger, "block0 {} downloaded from {}", block0_id, url);
+                return Ok(block);
+            }
+            Err(reason) if attempt >= BLOCK0_DOWNLOAD_ATTEMPTS => {
+                return Err(Error::DownloadBlock0 {
+                    url: url.to_owned(),
+                    reason,
+                });
+            }
+            Err(reason) => {
+                warn!(
+                    logger,
+                    "failed to download block0 from {}, retrying in {:?}", url, delay;
+                    "attempt" => attempt,
+                    "reason" => reason,
+                );
+                tokio::time::delay_for(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// write the main chain of the node storage in an archive at the given path,
+/// returning the number of exported blocks
+pub async fn export_chain(settings: &Settings, path: &Path, logger: &Logger) -> Result<u32, Error> {
+    if settings.storage.is_none() {
+        return Err(Error::StorageRequired);
+    }
+    let storage = prepare_storage(settings, logger)?;
+    let file = File::create(path).map_err(|err| Error::IO {
+        source: err,
+        reason: ErrorKind::ChainArchive,
+    })?;
+    archive::export(&storage, file).await.map_err(Into::into)
+}
+
+/// store the blocks of the archive at the given path in the node storage.
+/// The archive must hold the chain of the genesis block of the node.
+pub fn import_chain(
+    settings: &Settings,
+    path: &Path,
+    logger: &Logger,
+) -> Result<archive::ImportSummary, Error> {
+    use crate::settings::Block0Info;
+
+    if settings.storage.is_none() {
+        return Err(Error::StorageRequired);
+    }
+    let block0_id = match &settings.block_0 {
+        Block0Info::Path(_, Some(block0_id))
+        | Block0Info::Url(_, block0_id)
+        | Block0Info::Hash(block0_id) => *block0_id,
+        Block0Info::Path(path, None) => read_block0(path)?.header.id(),
+    };
+    let storage = prepare_storage(settings, logger)?;
+    let file = File::open(path).map_err(|err| Error::IO {
+        source: err,
+        reason: ErrorKind::ChainArchive,
+    })?;
+    archive::import(&storage, file, block0_id, logger).map_err(Into::into)
+}
+
+fn read_block0(path: &Path) -> Result<Block, Error> {
+    use chain_core::property::Deserialize as _;
+
+    let f = File::open(path).map_err(|err| Error::IO {
+        source: err,
+        reason: ErrorKind::Block0,
+    })?;
+    let reader = std::io::BufReader::new(f);
+    Block::deserialize(reader).map_err(|err| Error::ParseError {
+        source: err,
+        reason: ErrorKind::Block0,
+    })
+}
+
 /// Try to fetch the block0_id from the HTTP base URL (services) in the array
 ///
 /// The HTTP url is expecting to be of the form: URL/<hash-id>.block0
@@ -34,37 +261,14 @@ async fn fetch_block0_http(
     base_services: &[String],
     block0_id: &HeaderId,
 ) -> Option<Block> {
-    use chain_core::property::Deserialize as _;
-
     if base_services.is_empty() {
         return None;
     }
 
-    async fn fetch_one(block0_id: &HeaderId, url: &str) -> Result<Block, String> {
-        let response = reqwest::get(url)
-            .await
-            .map_err(|e| format!("cannot get {}", e))?;
-        if response.status() != reqwest::StatusCode::OK {
-            return Err(format!("fetch failed status code: {}", response.status()));
-        }
-
-        let bytes = response
-            .bytes()
-            .await
-            .map_err(|e| format!("cannot get data {}", e))?;
-        let block = Block::deserialize(bytes.as_ref())
-            .map_err(|err| format!("parse error on data {}", err))?;
-        let got = block.header.id();
-        if &got != block0_id {
-            return Err(format!("invalid block expecting {} got {}", block0_id, got));
-        }
-        Ok(block)
-    }
-
     for base_url in base_services {
         // trying to fetch from service base url
         let url = format!("{}/{}.block0", base_url, block0_id.to_string());
-        match fetch_one(block0_id, &url).await {
+        match fetch_block0_url(block0_id, &url).await {
             Err(e) => {
                 debug!(
                     logger,
@@ -91,7 +295,9 @@ async fn fetch_block0_http(
 /// there are different cases that we may encounter:
 ///
 /// 1. we have the block_0 given as parameter of the settings: easy, we read it;
-/// 2. we have the block_0 hash only:
+/// 2. we have the block_0 URL and hash: we download it, unless it is already
+///    in the storage;
+/// 3. we have the block_0 hash only:
 ///     1. check the storage if we don't have it already there;
 ///     2. check the network nodes we know about
 pub async fn prepare_block_0(
@@ -100,19 +306,10 @@ pub async fn prepare_block_0(
     logger: &Logger,
 ) -> Result<Block, Error> {
     use crate::settings::Block0Info;
-    use chain_core::property::Deserialize as _;
     match &settings.block_0 {
         Block0Info::Path(path, opt_block0_id) => {
             debug!(logger, "parsing block0 from file path `{:?}'", path);
-            let f = std::fs::File::open(path).map_err(|err| Error::IO {
-                source: err,
-                reason: ErrorKind::Block0,
-            })?;
-            let reader = std::io::BufReader::new(f);
-            let block = Block::deserialize(reader).map_err(|err| Error::ParseError {
-                source: err,
-                reason: ErrorKind::Block0,
-            })?;
+            let block = read_block0(path)?;
 
             // check if the block0 match, the optional expected hash value
             match opt_block0_id {
@@ -130,6 +327,18 @@ pub async fn prepare_block_0(
 
             Ok(block)
         }
+        Block0Info::Url(url, block0_id) => {
+            if let Some(block0) = storage.get(*block0_id)? {
+                debug!(
+                    logger,
+                    "retrieved block0 from storage with hash {}", block0_id
+                );
+                Ok(block0)
+            } else {
+                debug!(logger, "downloading block0 from `{}'", url);
+                download_block0(logger, url, block0_id).await
+            }
+        }
         Block0Info::Hash(block0_id) => {
             let storage_or_http_block0 = {
                 if let Some(block0) = storage.get(*block0_id).unwrap() {
@@ -167,18 +376,46 @@ pub async fn prepare_block_0(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn load_blockchain(
     block0: Block,
     storage: Storage,
     cache_capacity: usize,
     rewards_report_all: bool,
+    rewards_history: RewardsHistory,
+    epoch_accounting: EpochAccountingLog,
+    vote_plan_archive: VotePlanArchive,
+    vote_stats: Option<VoteStats>,
+    epoch_hooks: Option<EpochHooks>,
+    relay: bool,
     logger: &Logger,
 ) -> Result<(Blockchain, Tip), Error> {
+    // refuse to mix the blocks of different networks in the same storage
+    if let Some(stored) = storage.get_blocks_by_chain_length(0)?.pop() {
+        let expected = block0.header.hash();
+        let stored = stored.header.hash();
+        if stored != expected {
+            return Err(Error::StorageBlock0Mismatch { expected, stored });
+        }
+    }
+
+    // the headers of Genesis Praos cannot be verified without the stake
+    // distribution, which is not maintained by a relay
+    if relay && block0.consensus_version()? != ConsensusVersion::Bft {
+        return Err(Error::RelayRequiresBft);
+    }
+
     let blockchain = Blockchain::new(
         block0.header.hash(),
         storage,
         cache_capacity,
         rewards_report_all,
+        rewards_history,
+        epoch_accounting,
+        vote_plan_archive,
+        vote_stats,
+        epoch_hooks,
+        relay,
     );
 
     let main_branch = match blockchain.load_from_block0(block0.clone()).await {
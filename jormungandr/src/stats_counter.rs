@@ -1,12 +1,37 @@
 use arc_swap::ArcSwapOption;
-use chain_impl_mockchain::block::Block;
-use jormungandr_lib::time::SecondsSinceUnixEpoch;
+use chain_impl_mockchain::{block::Block, header::HeaderId};
+use jormungandr_lib::{
+    interfaces::{
+        BlockPropagationStats, FirstAnnouncerCount, LatencyPercentiles, MempoolOriginUsage,
+        MempoolSummary,
+    },
+    time::SecondsSinceUnixEpoch,
+};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::time::Instant;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 
 const SLOT_START_TIME_UNDEFINED: u64 = u64::max_value();
 
+/// number of the most recent blocks the propagation delays are computed on
+const PROPAGATION_SAMPLES: usize = 1_000;
+
+/// number of peers the first announcements are counted for, the
+/// announcements of the peers beyond it are not counted
+const PROPAGATION_MAX_ANNOUNCERS: usize = 1_024;
+
+/// number of the most recent block announcements the skew of the clock is
+/// estimated from
+const CLOCK_SKEW_SAMPLES: usize = 100;
+
+/// number of block announcements needed to estimate the skew of the clock
+const CLOCK_SKEW_MIN_SAMPLES: usize = 5;
+
+/// number of the most recent leader events the delays of the wake-ups
+/// are computed on
+const LEADER_WAKE_SAMPLES: usize = 1_000;
+
 #[derive(Clone, Debug, Default)]
 pub struct StatsCounter {
     stats: Arc<StatsCounterImpl>,
@@ -20,6 +45,33 @@ struct StatsCounterImpl {
     slot_start_time: AtomicU64,
     tip_block: ArcSwapOption<Block>,
     peers_connected_cnt: AtomicUsize,
+    gossip_recv_cnt: AtomicUsize,
+    gossip_dup_cnt: AtomicUsize,
+    block_cache_hit_cnt: AtomicU64,
+    block_cache_miss_cnt: AtomicU64,
+    mempool_usage: ArcSwapOption<Vec<MempoolOriginUsage>>,
+    mempool_summary: ArcSwapOption<MempoolSummary>,
+    propagation: Mutex<Propagation>,
+    clock_skew: Mutex<ClockSkew>,
+    leader_wake_ms: Mutex<VecDeque<u64>>,
+}
+
+#[derive(Debug, Default)]
+struct ClockSkew {
+    /// delays between the start of the slot of the announced blocks and
+    /// their announcement, negative for the blocks announced before
+    peer_delays_ms: VecDeque<i64>,
+    ntp_skew_ms: Option<i64>,
+}
+
+#[derive(Debug, Default)]
+struct Propagation {
+    arrival_ms: VecDeque<u64>,
+    adoption_ms: VecDeque<u64>,
+    /// the most recently announced blocks, oldest first
+    announced: VecDeque<HeaderId>,
+    announced_set: HashSet<HeaderId>,
+    first_announcers: HashMap<String, u64>,
 }
 
 impl Default for StatsCounterImpl {
@@ -31,6 +83,15 @@ impl Default for StatsCounterImpl {
             slot_start_time: AtomicU64::new(SLOT_START_TIME_UNDEFINED),
             tip_block: ArcSwapOption::from(None),
             peers_connected_cnt: AtomicUsize::default(),
+            gossip_recv_cnt: AtomicUsize::default(),
+            gossip_dup_cnt: AtomicUsize::default(),
+            block_cache_hit_cnt: AtomicU64::default(),
+            block_cache_miss_cnt: AtomicU64::default(),
+            mempool_usage: ArcSwapOption::from(None),
+            mempool_summary: ArcSwapOption::from(None),
+            propagation: Mutex::new(Propagation::default()),
+            clock_skew: Mutex::new(ClockSkew::default()),
+            leader_wake_ms: Mutex::new(VecDeque::new()),
         }
     }
 }
@@ -71,6 +132,43 @@ impl StatsCounter {
         self.stats.peers_connected_cnt.load(Ordering::Relaxed)
     }
 
+    pub fn add_gossip_recv_cnt(&self, received: usize, duplicates: usize) {
+        self.stats
+            .gossip_recv_cnt
+            .fetch_add(received, Ordering::Relaxed);
+        self.stats
+            .gossip_dup_cnt
+            .fetch_add(duplicates, Ordering::Relaxed);
+    }
+
+    pub fn gossip_recv_cnt(&self) -> u64 {
+        self.stats.gossip_recv_cnt.load(Ordering::Relaxed) as u64
+    }
+
+    pub fn gossip_dup_cnt(&self) -> u64 {
+        self.stats.gossip_dup_cnt.load(Ordering::Relaxed) as u64
+    }
+
+    pub fn add_block_cache_hit(&self) {
+        self.stats
+            .block_cache_hit_cnt
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add_block_cache_miss(&self) {
+        self.stats
+            .block_cache_miss_cnt
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn block_cache_hit_cnt(&self) -> u64 {
+        self.stats.block_cache_hit_cnt.load(Ordering::Relaxed)
+    }
+
+    pub fn block_cache_miss_cnt(&self) -> u64 {
+        self.stats.block_cache_miss_cnt.load(Ordering::Relaxed)
+    }
+
     pub fn uptime_sec(&self) -> u64 {
         self.stats.start_time.elapsed().as_secs()
     }
@@ -99,4 +197,153 @@ impl StatsCounter {
     pub fn get_tip_block(&self) -> Option<Arc<Block>> {
         self.stats.tip_block.load_full()
     }
+
+    pub fn set_mempool_usage(&self, usage: Vec<MempoolOriginUsage>) {
+        self.stats.mempool_usage.store(Some(Arc::new(usage)));
+    }
+
+    pub fn mempool_usage(&self) -> Vec<MempoolOriginUsage> {
+        self.stats
+            .mempool_usage
+            .load_full()
+            .map(|usage| usage.as_ref().clone())
+            .unwrap_or_default()
+    }
+
+    pub fn set_mempool_summary(&self, summary: MempoolSummary) {
+        self.stats.mempool_summary.store(Some(Arc::new(summary)));
+    }
+
+    pub fn mempool_summary(&self) -> Option<MempoolSummary> {
+        self.stats
+            .mempool_summary
+            .load_full()
+            .map(|summary| summary.as_ref().clone())
+    }
+
+    /// record the arrival of a block of the network, given the start time
+    /// of its slot
+    pub fn record_block_arrival(&self, slot_time: SystemTime) {
+        let mut propagation = self.stats.propagation.lock().unwrap();
+        push_sample(&mut propagation.arrival_ms, delay_ms(slot_time));
+    }
+
+    /// record a block of the network becoming the tip, given the start time
+    /// of its slot
+    pub fn record_block_adoption(&self, slot_time: SystemTime) {
+        let mut propagation = self.stats.propagation.lock().unwrap();
+        push_sample(&mut propagation.adoption_ms, delay_ms(slot_time));
+    }
+
+    /// record the announcement of a block by a peer, counting the peer if
+    /// it is the first one to announce this block. Returns whether it is
+    /// the first announcement of the block.
+    pub fn record_block_announcement(&self, block: HeaderId, peer: String) -> bool {
+        let mut propagation = self.stats.propagation.lock().unwrap();
+        if !propagation.announced_set.insert(block) {
+            return false;
+        }
+        propagation.announced.push_back(block);
+        if propagation.announced.len() > PROPAGATION_SAMPLES {
+            if let Some(oldest) = propagation.announced.pop_front() {
+                propagation.announced_set.remove(&oldest);
+            }
+        }
+
+        let announcers = propagation.first_announcers.len();
+        match propagation.first_announcers.get_mut(&peer) {
+            Some(count) => *count += 1,
+            None if announcers < PROPAGATION_MAX_ANNOUNCERS => {
+                propagation.first_announcers.insert(peer, 1);
+            }
+            None => (),
+        }
+        true
+    }
+
+    /// record the first announcement of a checked block near the tip, given
+    /// the start time of its slot
+    pub fn record_peer_clock_delay(&self, slot_time: SystemTime) {
+        let delay_ms = match SystemTime::now().duration_since(slot_time) {
+            Ok(delay) => delay.as_millis() as i64,
+            Err(e) => -(e.duration().as_millis() as i64),
+        };
+        let mut clock_skew = self.stats.clock_skew.lock().unwrap();
+        clock_skew.peer_delays_ms.push_back(delay_ms);
+        if clock_skew.peer_delays_ms.len() > CLOCK_SKEW_SAMPLES {
+            clock_skew.peer_delays_ms.pop_front();
+        }
+    }
+
+    /// the skew of the clock estimated from the block announcements of the
+    /// peers, in milliseconds: the median delay between the start of the
+    /// slot of the blocks and their announcement
+    pub fn peer_clock_skew(&self) -> Option<i64> {
+        let clock_skew = self.stats.clock_skew.lock().unwrap();
+        if clock_skew.peer_delays_ms.len() < CLOCK_SKEW_MIN_SAMPLES {
+            return None;
+        }
+        let mut delays: Vec<i64> = clock_skew.peer_delays_ms.iter().copied().collect();
+        delays.sort_unstable();
+        Some(delays[delays.len() / 2])
+    }
+
+    pub fn set_ntp_clock_skew(&self, skew_ms: i64) {
+        self.stats.clock_skew.lock().unwrap().ntp_skew_ms = Some(skew_ms);
+    }
+
+    pub fn ntp_clock_skew(&self) -> Option<i64> {
+        self.stats.clock_skew.lock().unwrap().ntp_skew_ms
+    }
+
+    /// record how late the leadership task woke up for a leader event,
+    /// after the start of its slot
+    pub fn record_leader_wake_delay(&self, delay: Duration) {
+        let mut samples = self.stats.leader_wake_ms.lock().unwrap();
+        samples.push_back(delay.as_millis() as u64);
+        if samples.len() > LEADER_WAKE_SAMPLES {
+            samples.pop_front();
+        }
+    }
+
+    pub fn leader_wake_delay(&self) -> LatencyPercentiles {
+        percentiles(&self.stats.leader_wake_ms.lock().unwrap())
+    }
+
+    pub fn block_propagation(&self) -> BlockPropagationStats {
+        let propagation = self.stats.propagation.lock().unwrap();
+        let mut first_announcers: Vec<FirstAnnouncerCount> = propagation
+            .first_announcers
+            .iter()
+            .map(|(peer, blocks)| FirstAnnouncerCount {
+                peer: peer.clone(),
+                blocks: *blocks,
+            })
+            .collect();
+        first_announcers.sort_by(|a, b| b.blocks.cmp(&a.blocks).then(a.peer.cmp(&b.peer)));
+        BlockPropagationStats {
+            arrival: percentiles(&propagation.arrival_ms),
+            adoption: percentiles(&propagation.adoption_ms),
+            first_announcers,
+        }
+    }
+}
+
+/// the delay since the given time, 0 if it is in the future
+fn delay_ms(since: SystemTime) -> u64 {
+    SystemTime::now()
+        .duration_since(since)
+        .map_or(0, |delay| delay.as_millis() as u64)
+}
+
+fn push_sample(samples: &mut VecDeque<u64>, sample: u64) {
+    samples.push_back(sample);
+    if samples.len() > PROPAGATION_SAMPLES {
+        samples.pop_front();
+    }
+}
+
+fn percentiles(samples: &VecDeque<u64>) -> LatencyPercentiles {
+    let samples: Vec<u64> = samples.iter().copied().collect();
+    LatencyPercentiles::from_samples(&samples)
 }
@@ -1,11 +1,29 @@
-use crate::{blockchain, utils::task::TokioServiceInfo};
+use crate::{
+    blockchain::{self, Ref},
+    utils::task::TokioServiceInfo,
+};
 use chain_time::{
     era::{EpochPosition, EpochSlotOffset},
     Epoch,
 };
-use std::time::{Duration, SystemTime};
-use tokio::time::interval;
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::time::timeout;
+
+/// the delay between the first notifications, doubled after each one
+/// while the blockchain does not move
+const FIRST_NOTIFICATION_DELAY: Duration = Duration::from_secs(60);
 
+/// the longest delay between two notifications
+const MAX_NOTIFICATION_DELAY: Duration = Duration::from_secs(30 * 60);
+
+/// Warn when the blockchain does not move for longer than `check_interval`.
+/// The task sleeps until the tip is that old, unless it moves meanwhile,
+/// and then warns until it moves again: a minute after the first warning,
+/// then with the delay doubling each time, up to one warning every 30
+/// minutes.
 pub async fn check_last_block_time(
     service_info: TokioServiceInfo,
     blockchain_tip: blockchain::Tip,
@@ -13,57 +31,61 @@ pub async fn check_last_block_time(
 ) {
     let logger = service_info.logger().clone();
 
-    // those are different values, because check_interval can be big
-    // (30 minutes) and the notification may remain unseen
-    let check_period = check_interval;
-    let notification_period = Duration::from_secs(60);
-
-    let mut interval = interval(notification_period);
+    let mut tip_updates = blockchain_tip.updates();
+    let mut tip = blockchain_tip.get_ref().await;
+    let mut notification_delay = FIRST_NOTIFICATION_DELAY;
 
     loop {
-        interval.tick().await;
-        let tip = blockchain_tip.get_ref().await;
-        let era = tip.epoch_leadership_schedule().era();
+        let tip_time = match block_time(&tip) {
+            Some(tip_time) => tip_time,
+            None => {
+                error!(logger, "cannot convert the block tip date to system time");
+                break;
+            }
+        };
 
         let now = SystemTime::now();
-
-        let tip_date = tip.block_date();
-        let tip_slot = era.from_era_to_slot(EpochPosition {
-            epoch: Epoch(tip_date.epoch),
-            slot: EpochSlotOffset(tip_date.slot_id),
-        });
-        let tip_time = if let Some(tip_time) = tip.time_frame().slot_to_systemtime(tip_slot) {
-            tip_time
+        let period_since_last_block = now.duration_since(tip_time).unwrap_or_default();
+        let wait = if period_since_last_block > check_interval {
+            let era = tip.epoch_leadership_schedule().era();
+            let system_current_blockdate = tip
+                .time_frame()
+                .slot_at(&now)
+                .and_then(|scs| era.from_slot_to_era(scs))
+                .map(|ep| format!("{}", ep))
+                .unwrap_or_else(|| "date-computation-error".to_string());
+            warn!(
+                logger,
+                "blockchain is not moving up, system-date={}, the last tip {} was {} seconds ago",
+                system_current_blockdate,
+                tip.header().description(),
+                period_since_last_block.as_secs()
+            );
+            let wait = notification_delay;
+            notification_delay = std::cmp::min(notification_delay * 2, MAX_NOTIFICATION_DELAY);
+            wait
         } else {
-            error!(logger, "cannot convert the block tip date to system time");
-            break;
+            // the tip may be in the future if the clock of the node is late
+            check_interval - period_since_last_block + Duration::from_secs(1)
         };
 
-        let system_current_slot = tip.time_frame().slot_at(&now);
-        let system_current_blockdate = system_current_slot
-            .and_then(|scs| era.from_slot_to_era(scs))
-            .map(|ep| format!("{}", ep))
-            .unwrap_or_else(|| "date-computation-error".to_string());
-
-        let header = tip.header();
-        match now.duration_since(tip_time) {
-            Ok(period_since_last_block) => {
-                if period_since_last_block > check_period {
-                    warn!(
-                            logger,
-                            "blockchain is not moving up, system-date={}, the last tip {} was {} seconds ago",
-                            system_current_blockdate, header.description(), period_since_last_block.as_secs()
-                        );
-                }
-            }
-            Err(e) => {
-                // don't make the future fail because of this error. This can happen only
-                // if the tip has just been updated
-                error!(
-                    logger,
-                    "cannot compute the time passed since the last block: {}", e
-                );
+        match timeout(wait, tip_updates.changed()).await {
+            Ok(Some(new_tip)) => {
+                tip = new_tip;
+                notification_delay = FIRST_NOTIFICATION_DELAY;
             }
+            Ok(None) => break,
+            Err(_) => (),
         }
     }
 }
+
+fn block_time(tip: &Arc<Ref>) -> Option<SystemTime> {
+    let era = tip.epoch_leadership_schedule().era();
+    let tip_date = tip.block_date();
+    let tip_slot = era.from_era_to_slot(EpochPosition {
+        epoch: Epoch(tip_date.epoch),
+        slot: EpochSlotOffset(tip_date.slot_id),
+    });
+    tip.time_frame().slot_to_systemtime(tip_slot)
+}
@@ -1,6 +1,7 @@
 //! Multiple producer, single-consumer in-memory FIFO channels with
 //! asynchronous reading.
 
+use super::buffer_gauge::{BufferGauge, BufferTracker};
 use futures::channel::mpsc::{self, Receiver, Sender};
 pub use futures::channel::mpsc::{SendError, TrySendError};
 use futures::prelude::*;
@@ -9,20 +10,52 @@ use std::task::{Context, Poll};
 
 /// The output end of an in-memory FIFO channel.
 #[derive(Debug)]
-pub struct MessageBox<Msg>(Sender<Msg>);
+pub struct MessageBox<Msg> {
+    sender: Sender<Msg>,
+    tracker: Option<BufferTracker>,
+}
 
 /// The input end of an in-memory FIFO channel.
 /// This can be read asynchronously in a Tokio task using its
 /// Stream implementation.
 #[derive(Debug)]
-pub struct MessageQueue<Msg>(Receiver<Msg>);
+pub struct MessageQueue<Msg> {
+    receiver: Receiver<Msg>,
+    tracker: Option<BufferTracker>,
+}
 
 /// Constructs an in-memory channel and returns the output and input halves.
 /// The parameter specifies the number of messages that are allowed
 /// to be pending in the channel.
 pub fn channel<Msg>(buffer: usize) -> (MessageBox<Msg>, MessageQueue<Msg>) {
-    let (tx, rx) = mpsc::channel(buffer);
-    (MessageBox(tx), MessageQueue(rx))
+    let (sender, receiver) = mpsc::channel(buffer);
+    (
+        MessageBox {
+            sender,
+            tracker: None,
+        },
+        MessageQueue {
+            receiver,
+            tracker: None,
+        },
+    )
+}
+
+/// Constructs an in-memory channel with the buffer size given by the gauge,
+/// reporting the occupancy of the channel buffer to the gauge.
+pub fn gauged_channel<Msg>(gauge: &'static BufferGauge) -> (MessageBox<Msg>, MessageQueue<Msg>) {
+    let (sender, receiver) = mpsc::channel(gauge.capacity());
+    let tracker = gauge.track();
+    (
+        MessageBox {
+            sender,
+            tracker: Some(tracker.clone()),
+        },
+        MessageQueue {
+            receiver,
+            tracker: Some(tracker),
+        },
+    )
 }
 
 impl<Msg> MessageBox<Msg> {
@@ -36,7 +69,14 @@ impl<Msg> MessageBox<Msg> {
     /// If the channel is full or the receiving MessageQueue has been dropped,
     /// an error is returned in `Err`.
     pub fn try_send(&mut self, a: Msg) -> Result<(), TrySendError<Msg>> {
-        self.0.try_send(a)
+        let res = self.sender.try_send(a);
+        if let Some(tracker) = &mut self.tracker {
+            match &res {
+                Ok(()) => tracker.items_queued(1),
+                Err(e) => tracker.set_stalled(e.is_full()),
+            }
+        }
+        res
     }
 
     /// Sends a message on the channel.
@@ -44,13 +84,21 @@ impl<Msg> MessageBox<Msg> {
     /// This function should be only called after `poll_ready` has reported
     /// that the channel is ready to receive a message.
     pub fn start_send(&mut self, a: Msg) -> Result<(), SendError> {
-        self.0.start_send(a)
+        self.sender.start_send(a)?;
+        if let Some(tracker) = &self.tracker {
+            tracker.items_queued(1);
+        }
+        Ok(())
     }
 
     /// Polls the channel to determine if there is guaranteed to be capacity
     /// to send at least one item without waiting.
     pub fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), SendError>> {
-        self.0.poll_ready(cx)
+        let res = self.sender.poll_ready(cx);
+        if let Some(tracker) = &mut self.tracker {
+            tracker.set_stalled(res.is_pending());
+        }
+        res
     }
 }
 
@@ -58,19 +106,19 @@ impl<Msg> Sink<Msg> for MessageBox<Msg> {
     type Error = SendError;
 
     fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), SendError>> {
-        self.0.poll_ready(cx)
+        MessageBox::poll_ready(&mut *self, cx)
     }
 
     fn start_send(mut self: Pin<&mut Self>, msg: Msg) -> Result<(), SendError> {
-        self.0.start_send(msg)
+        MessageBox::start_send(&mut *self, msg)
     }
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), SendError>> {
-        Pin::new(&mut self.0).poll_flush(cx)
+        Pin::new(&mut self.sender).poll_flush(cx)
     }
 
     fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), SendError>> {
-        Pin::new(&mut self.0).poll_close(cx)
+        Pin::new(&mut self.sender).poll_close(cx)
     }
 }
 
@@ -78,16 +126,64 @@ impl<Msg> Stream for MessageQueue<Msg> {
     type Item = Msg;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Msg>> {
-        Pin::new(&mut self.0).poll_next(cx)
+        let res = Pin::new(&mut self.receiver).poll_next(cx);
+        if let (Poll::Ready(Some(_)), Some(tracker)) = (&res, &self.tracker) {
+            tracker.items_taken(1);
+        }
+        res
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        self.0.size_hint()
+        self.receiver.size_hint()
+    }
+}
+
+impl<Msg> Drop for MessageQueue<Msg> {
+    fn drop(&mut self) {
+        // account for the messages left in the buffer
+        if let Some(tracker) = &self.tracker {
+            self.receiver.close();
+            while let Ok(Some(_)) = self.receiver.try_next() {
+                tracker.items_taken(1);
+            }
+        }
     }
 }
 
 impl<Msg> Clone for MessageBox<Msg> {
     fn clone(&self) -> Self {
-        MessageBox(self.0.clone())
+        MessageBox {
+            sender: self.sender.clone(),
+            tracker: self.tracker.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn gauged_channels_report_their_occupancy() {
+        static GAUGE: BufferGauge = BufferGauge::new(2);
+        let (mut sender, mut receiver) = gauged_channel::<u32>(&GAUGE);
+
+        // the channel holds one more message per sender than its buffer
+        let mut sent = 0;
+        while sender.try_send(sent).is_ok() {
+            sent += 1;
+        }
+        assert!(sender.try_send(sent).unwrap_err().is_full());
+        let usage = GAUGE.usage();
+        assert_eq!(usage.current, sent as usize);
+        assert_eq!(usage.high_water_mark, sent as usize);
+        assert_eq!(usage.stalls, 1);
+
+        assert_eq!(receiver.next().await, Some(0));
+        assert_eq!(GAUGE.usage().current, sent as usize - 1);
+
+        // the messages left are accounted for when the queue is dropped
+        drop(receiver);
+        assert_eq!(GAUGE.usage().current, 0);
     }
 }
@@ -0,0 +1,179 @@
+//! Occupancy gauges of bounded buffers.
+//!
+//! A `BufferGauge` aggregates the usage of all buffers of one kind,
+//! e.g. the buffers of all outbound block streams, so the size chosen
+//! for these buffers can be checked against the observed load.
+
+use std::sync::{
+    atomic::{AtomicIsize, AtomicU64, AtomicUsize, Ordering},
+    Arc,
+};
+
+/// Usage statistics of the buffers of one kind.
+#[derive(Debug)]
+pub struct BufferGauge {
+    capacity: usize,
+    current: AtomicIsize,
+    high_water_mark: AtomicUsize,
+    stalls: AtomicU64,
+    drops: AtomicU64,
+}
+
+/// A snapshot of the usage of the buffers of one kind.
+#[derive(Debug, Clone, Serialize)]
+pub struct BufferUsage {
+    /// capacity of each buffer
+    pub capacity: usize,
+    /// number of items currently held in all buffers
+    pub current: usize,
+    /// the largest number of items observed in a single buffer
+    pub high_water_mark: usize,
+    /// number of times a producer had to wait for a full buffer
+    pub stalls: u64,
+    /// number of items dropped because their buffer was full
+    pub drops: u64,
+}
+
+impl BufferGauge {
+    pub const fn new(capacity: usize) -> Self {
+        BufferGauge {
+            capacity,
+            current: AtomicIsize::new(0),
+            high_water_mark: AtomicUsize::new(0),
+            stalls: AtomicU64::new(0),
+            drops: AtomicU64::new(0),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Start tracking the occupancy of a new buffer.
+    pub fn track(&'static self) -> BufferTracker {
+        BufferTracker {
+            gauge: self,
+            occupancy: Arc::new(AtomicIsize::new(0)),
+            stalled: false,
+        }
+    }
+
+    pub fn usage(&self) -> BufferUsage {
+        BufferUsage {
+            capacity: self.capacity,
+            current: self.current.load(Ordering::Relaxed).max(0) as usize,
+            high_water_mark: self.high_water_mark.load(Ordering::Relaxed),
+            stalls: self.stalls.load(Ordering::Relaxed),
+            drops: self.drops.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Tracks the occupancy of a single buffer and reports it to the gauge.
+///
+/// The clones of a tracker refer to the same buffer, so the producing and
+/// the consuming ends of a channel can each hold one.
+#[derive(Debug)]
+pub struct BufferTracker {
+    gauge: &'static BufferGauge,
+    occupancy: Arc<AtomicIsize>,
+    stalled: bool,
+}
+
+impl BufferTracker {
+    pub fn items_queued(&self, n: usize) {
+        let n = n as isize;
+        let occupancy = self.occupancy.fetch_add(n, Ordering::Relaxed) + n;
+        self.gauge.current.fetch_add(n, Ordering::Relaxed);
+        self.gauge
+            .high_water_mark
+            .fetch_max(occupancy.max(0) as usize, Ordering::Relaxed);
+    }
+
+    pub fn items_taken(&self, n: usize) {
+        let n = n as isize;
+        self.occupancy.fetch_sub(n, Ordering::Relaxed);
+        self.gauge.current.fetch_sub(n, Ordering::Relaxed);
+    }
+
+    /// Record an item dropped rather than added to the full buffer.
+    pub fn item_dropped(&self) {
+        self.gauge.drops.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record the readiness of the producer to add an item to the buffer.
+    /// A stall is counted once each time the producer starts waiting.
+    pub fn set_stalled(&mut self, stalled: bool) {
+        if stalled && !self.stalled {
+            self.gauge.stalls.fetch_add(1, Ordering::Relaxed);
+        }
+        self.stalled = stalled;
+    }
+}
+
+impl Clone for BufferTracker {
+    fn clone(&self) -> Self {
+        BufferTracker {
+            gauge: self.gauge,
+            occupancy: Arc::clone(&self.occupancy),
+            stalled: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn occupancy_is_aggregated_over_the_buffers() {
+        static GAUGE: BufferGauge = BufferGauge::new(8);
+        let first = GAUGE.track();
+        let second = GAUGE.track();
+
+        first.items_queued(3);
+        second.items_queued(2);
+        let usage = GAUGE.usage();
+        assert_eq!(usage.capacity, 8);
+        assert_eq!(usage.current, 5);
+        // the high water mark is the occupancy of a single buffer
+        assert_eq!(usage.high_water_mark, 3);
+
+        first.items_taken(3);
+        second.items_queued(2);
+        let usage = GAUGE.usage();
+        assert_eq!(usage.current, 4);
+        assert_eq!(usage.high_water_mark, 4);
+    }
+
+    #[test]
+    fn clones_track_the_same_buffer() {
+        static GAUGE: BufferGauge = BufferGauge::new(8);
+        let producer = GAUGE.track();
+        let consumer = producer.clone();
+
+        producer.items_queued(2);
+        producer.items_queued(2);
+        consumer.items_taken(1);
+        let usage = GAUGE.usage();
+        assert_eq!(usage.current, 3);
+        assert_eq!(usage.high_water_mark, 4);
+    }
+
+    #[test]
+    fn a_stall_is_counted_once_until_the_producer_resumes() {
+        static GAUGE: BufferGauge = BufferGauge::new(1);
+        let mut tracker = GAUGE.track();
+
+        tracker.set_stalled(true);
+        tracker.set_stalled(true);
+        assert_eq!(GAUGE.usage().stalls, 1);
+
+        tracker.set_stalled(false);
+        tracker.set_stalled(true);
+        assert_eq!(GAUGE.usage().stalls, 2);
+
+        tracker.item_dropped();
+        assert_eq!(GAUGE.usage().drops, 1);
+    }
+}
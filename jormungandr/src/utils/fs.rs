@@ -0,0 +1,78 @@
+//! file system helpers
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io,
+    path::Path,
+};
+
+/// Replace the content of the file at the given path with what `write`
+/// writes. The content is written next to the file and moved in place once
+/// synced, so an interrupted write does not lose the previous content. The
+/// partial file is removed if the write fails.
+pub fn write_atomically<F>(path: &Path, write: F) -> io::Result<()>
+where
+    F: FnOnce(&mut File) -> io::Result<()>,
+{
+    write_atomically_with(path, &OpenOptions::new(), write)
+}
+
+/// Same as `write_atomically`, creating the file with the given options, for
+/// example to restrict its permissions.
+pub fn write_atomically_with<F>(path: &Path, options: &OpenOptions, write: F) -> io::Result<()>
+where
+    F: FnOnce(&mut File) -> io::Result<()>,
+{
+    let tmp_path = path.with_extension("tmp");
+
+    let result = options
+        .clone()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&tmp_path)
+        .and_then(|mut file| {
+            write(&mut file)?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&tmp_path, path));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn content_is_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.json");
+
+        write_atomically(&path, |file| file.write_all(b"first")).unwrap();
+        write_atomically(&path, |file| file.write_all(b"second")).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert!(!path.with_extension("tmp").exists());
+    }
+
+    #[test]
+    fn failed_write_keeps_the_previous_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.json");
+
+        write_atomically(&path, |file| file.write_all(b"first")).unwrap();
+        let result = write_atomically(&path, |file| {
+            file.write_all(b"partial")?;
+            Err(io::Error::new(io::ErrorKind::Other, "interrupted"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+        assert!(!path.with_extension("tmp").exists());
+    }
+}
@@ -0,0 +1,266 @@
+//! records kept in memory and persisted as a JSON array in a file
+//!
+//! The stores hold the records the node computes once and serves later,
+//! such as the rewards of the past epochs. Every change rewrites the file,
+//! off the async runtime and without holding the records, the changes made
+//! while a write is in progress being saved together by the next one.
+
+use crate::utils::fs::write_atomically;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::{sync::Mutex, task};
+
+/// a record of a store, identified by its key
+pub trait Record: Clone + Serialize + DeserializeOwned + Send + Sync + 'static {
+    type Key: Ord + Clone + Send;
+
+    fn key(&self) -> Self::Key;
+}
+
+pub struct JsonStore<R: Record> {
+    inner: Arc<Mutex<Inner<R>>>,
+    /// the version of the records last written in the file, held while
+    /// writing so the writes happen in order
+    saved: Arc<Mutex<u64>>,
+}
+
+struct Inner<R: Record> {
+    file: Option<PathBuf>,
+    retention: Option<usize>,
+    records: BTreeMap<R::Key, R>,
+    /// incremented on every change of the records
+    version: u64,
+}
+
+impl<R: Record> Clone for JsonStore<R> {
+    fn clone(&self) -> Self {
+        JsonStore {
+            inner: Arc::clone(&self.inner),
+            saved: Arc::clone(&self.saved),
+        }
+    }
+}
+
+impl<R: Record> JsonStore<R> {
+    /// only keep the records in memory, they are lost when the node stops.
+    /// With a retention, only the records with the greatest keys are kept.
+    pub fn in_memory(retention: Option<usize>) -> Self {
+        Self::new(Inner {
+            file: None,
+            retention,
+            records: BTreeMap::new(),
+            version: 0,
+        })
+    }
+
+    /// load the records from the file at the given path, if it exists
+    pub fn open<P: AsRef<Path>>(path: P, retention: Option<usize>) -> io::Result<Self> {
+        let path = path.as_ref();
+
+        let mut records = BTreeMap::new();
+        if path.exists() {
+            let entries: Vec<R> = serde_json::from_reader(BufReader::new(File::open(path)?))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            records.extend(entries.into_iter().map(|record| (record.key(), record)));
+        }
+
+        let mut inner = Inner {
+            file: Some(path.to_path_buf()),
+            retention,
+            records,
+            version: 0,
+        };
+        inner.prune();
+
+        Ok(Self::new(inner))
+    }
+
+    fn new(inner: Inner<R>) -> Self {
+        JsonStore {
+            inner: Arc::new(Mutex::new(inner)),
+            saved: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    /// add the record, replacing the one with the same key
+    pub async fn record(&self, record: R) -> io::Result<()> {
+        let version = {
+            let mut inner = self.inner.lock().await;
+            inner.records.insert(record.key(), record);
+            inner.prune();
+            inner.version += 1;
+            inner.version
+        };
+        self.save(version).await
+    }
+
+    /// write the records in the file, unless a write already did since
+    /// the given version
+    async fn save(&self, version: u64) -> io::Result<()> {
+        let mut saved = self.saved.lock().await;
+        if *saved >= version {
+            return Ok(());
+        }
+
+        let (path, records, version) = {
+            let inner = self.inner.lock().await;
+            match &inner.file {
+                Some(path) => (
+                    path.clone(),
+                    inner.records.values().cloned().collect::<Vec<R>>(),
+                    inner.version,
+                ),
+                None => return Ok(()),
+            }
+        };
+        task::spawn_blocking(move || {
+            write_atomically(&path, |file| {
+                let mut writer = BufWriter::new(file);
+                serde_json::to_writer(&mut writer, &records)
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+                writer.flush()
+            })
+        })
+        .await
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))??;
+
+        *saved = version;
+        Ok(())
+    }
+
+    pub async fn get(&self, key: &R::Key) -> Option<R> {
+        self.inner.lock().await.records.get(key).cloned()
+    }
+
+    /// the records with the greatest keys, greatest first
+    pub async fn latest(&self, length: usize) -> Vec<R> {
+        self.inner
+            .lock()
+            .await
+            .records
+            .values()
+            .rev()
+            .take(length)
+            .cloned()
+            .collect()
+    }
+
+    /// the records matching the predicate, in the order of their keys
+    pub async fn filter<F>(&self, predicate: F) -> Vec<R>
+    where
+        F: Fn(&R) -> bool,
+    {
+        self.inner
+            .lock()
+            .await
+            .records
+            .values()
+            .filter(|record| predicate(record))
+            .cloned()
+            .collect()
+    }
+
+    pub async fn retention(&self) -> Option<usize> {
+        self.inner.lock().await.retention
+    }
+}
+
+impl<R: Record> Inner<R> {
+    fn prune(&mut self) {
+        if let Some(retention) = self.retention {
+            while self.records.len() > retention {
+                let oldest = self.records.keys().next().unwrap().clone();
+                self.records.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    struct Entry {
+        id: u32,
+        value: String,
+    }
+
+    impl Record for Entry {
+        type Key = u32;
+
+        fn key(&self) -> u32 {
+            self.id
+        }
+    }
+
+    fn entry(id: u32, value: &str) -> Entry {
+        Entry {
+            id,
+            value: value.to_owned(),
+        }
+    }
+
+    #[tokio::test]
+    async fn records_with_the_same_key_are_replaced() {
+        let store = JsonStore::in_memory(None);
+        store.record(entry(1, "fork")).await.unwrap();
+        store.record(entry(1, "main")).await.unwrap();
+
+        assert_eq!(store.get(&1).await, Some(entry(1, "main")));
+        assert_eq!(store.latest(10).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn only_the_greatest_keys_are_retained() {
+        let store = JsonStore::in_memory(Some(2));
+        for id in 0..4 {
+            store.record(entry(id, "")).await.unwrap();
+        }
+
+        assert_eq!(store.get(&1).await, None);
+        let latest: Vec<u32> = store.latest(10).await.iter().map(|e| e.id).collect();
+        assert_eq!(latest, vec![3, 2]);
+    }
+
+    #[tokio::test]
+    async fn records_are_reloaded_from_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("store.json");
+
+        let store = JsonStore::open(&path, Some(3)).unwrap();
+        for id in 0..4 {
+            store.record(entry(id, "saved")).await.unwrap();
+        }
+
+        let reloaded: JsonStore<Entry> = JsonStore::open(&path, Some(2)).unwrap();
+        assert_eq!(reloaded.get(&1).await, None);
+        assert_eq!(reloaded.get(&2).await, Some(entry(2, "saved")));
+        assert_eq!(reloaded.get(&3).await, Some(entry(3, "saved")));
+        assert!(!path.with_extension("tmp").exists());
+    }
+
+    #[tokio::test]
+    async fn concurrent_records_are_all_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("store.json");
+
+        let store = JsonStore::open(&path, None).unwrap();
+        let writes = (0..16).map(|id| {
+            let store = store.clone();
+            async move { store.record(entry(id, "concurrent")).await }
+        });
+        for result in futures::future::join_all(writes).await {
+            result.unwrap();
+        }
+
+        let reloaded: JsonStore<Entry> = JsonStore::open(&path, None).unwrap();
+        assert_eq!(reloaded.latest(100).await.len(), 16);
+    }
+}
@@ -0,0 +1,120 @@
+//! Listening sockets of the node, inherited from the service manager or
+//! bound by the node itself.
+//!
+//! With systemd socket activation the sockets stay open while the node is
+//! restarted, the connection attempts being queued by the kernel instead of
+//! refused. The sockets are matched by the name given with
+//! `FileDescriptorName=` in the socket units, `p2p` and `rest`. Without
+//! names, the first socket is used for the p2p and the second for the REST.
+//!
+//! Otherwise the sockets can be bound with `SO_REUSEPORT`, so the new node
+//! can listen on its ports while the old one is still draining its
+//! connections.
+
+use socket2::{Domain, Protocol, Socket, Type};
+use std::{collections::HashMap, io, net::SocketAddr, net::TcpListener, sync::Mutex};
+
+pub const P2P: &str = "p2p";
+pub const REST: &str = "rest";
+
+const LISTEN_BACKLOG: i32 = 1024;
+
+lazy_static! {
+    static ref INHERITED: Mutex<HashMap<String, TcpListener>> = Mutex::new(inherit());
+}
+
+/// take the socket of the given name passed by the service manager, if any
+pub fn inherited(name: &str) -> Option<TcpListener> {
+    INHERITED.lock().unwrap().remove(name)
+}
+
+/// close the sockets passed by the service manager, returning how many
+/// there were. The nodes of a cluster bind their own sockets, a socket
+/// taken by one of them would be missed by the others.
+#[cfg(feature = "in-memory-transport")]
+pub fn close_inherited() -> usize {
+    let mut inherited = INHERITED.lock().unwrap();
+    let count = inherited.len();
+    inherited.clear();
+    count
+}
+
+/// bind a new listening socket, non-blocking as expected by tokio
+pub fn bind(addr: SocketAddr, reuse_port: bool) -> io::Result<TcpListener> {
+    let domain = if addr.is_ipv4() {
+        Domain::ipv4()
+    } else {
+        Domain::ipv6()
+    };
+    let socket = Socket::new(domain, Type::stream(), Some(Protocol::tcp()))?;
+    socket.set_reuse_address(true)?;
+    if reuse_port {
+        set_reuse_port(&socket)?;
+    }
+    socket.bind(&addr.into())?;
+    socket.listen(LISTEN_BACKLOG)?;
+    socket.set_nonblocking(true)?;
+    Ok(socket.into_tcp_listener())
+}
+
+#[cfg(unix)]
+fn set_reuse_port(socket: &Socket) -> io::Result<()> {
+    socket.set_reuse_port(true)
+}
+
+#[cfg(not(unix))]
+fn set_reuse_port(_socket: &Socket) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        "SO_REUSEPORT is not supported on this platform",
+    ))
+}
+
+/// the sockets passed with the systemd socket activation protocol
+#[cfg(unix)]
+fn inherit() -> HashMap<String, TcpListener> {
+    use nix::fcntl::{fcntl, FcntlArg, FdFlag};
+    use std::{
+        env,
+        os::unix::io::{AsRawFd, FromRawFd, RawFd},
+    };
+
+    const LISTEN_FDS_START: RawFd = 3;
+
+    let for_this_process = env::var("LISTEN_PID")
+        .ok()
+        .and_then(|pid| pid.parse::<u32>().ok())
+        .map_or(false, |pid| pid == std::process::id());
+    if !for_this_process {
+        return HashMap::new();
+    }
+    let count = env::var("LISTEN_FDS")
+        .ok()
+        .and_then(|count| count.parse::<RawFd>().ok())
+        .unwrap_or(0);
+    let mut names = env::var("LISTEN_FDNAMES")
+        .map(|names| names.split(':').map(str::to_owned).collect())
+        .unwrap_or_else(|_| vec![P2P.to_owned(), REST.to_owned()])
+        .into_iter();
+
+    // The variables are left in the environment: removing them would race
+    // with the other threads reading it. The processes started by the node
+    // ignore them as `LISTEN_PID` is not their own, and the sockets are
+    // closed on exec.
+    (0..count)
+        .filter_map(|index| {
+            // owning the descriptor first closes it on the error paths, and
+            // closes the sockets without a name
+            let listener = unsafe { TcpListener::from_raw_fd(LISTEN_FDS_START + index) };
+            let name = names.next()?;
+            fcntl(listener.as_raw_fd(), FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC)).ok()?;
+            listener.set_nonblocking(true).ok()?;
+            Some((name, listener))
+        })
+        .collect()
+}
+
+#[cfg(not(unix))]
+fn inherit() -> HashMap<String, TcpListener> {
+    HashMap::new()
+}
@@ -1,4 +1,10 @@
 pub mod async_msg;
 pub mod borrow;
+pub mod buffer_gauge;
 pub mod fire_forget_scheduler;
+pub mod fs;
+pub mod json_store;
+pub mod listen_socket;
 pub mod task;
+#[cfg(test)]
+pub mod test_fixtures;
@@ -0,0 +1,92 @@
+//! chains and fragments shared by the unit tests
+
+use crate::blockcfg::{
+    Block, ConsensusVersion, Fragment, HeaderId, Leadership, Ledger, LedgerParameters,
+};
+use chain_addr::{Address, Discrimination, Kind};
+use chain_crypto::{Ed25519, PublicKey, SecretKey};
+use chain_impl_mockchain::{
+    account::SpendingCounter,
+    fee::LinearFee,
+    leadership::bft::LeaderId,
+    transaction::{Input, Output, TxBuilder, Witness},
+    value::Value,
+};
+use jormungandr_lib::interfaces::{
+    Block0Configuration, BlockchainConfiguration, ConsensusLeaderId, Initial, InitialUTxO,
+};
+
+/// the block0 of a BFT chain without fees, led by the given leader, with
+/// the given funds on the accounts of the given keys
+pub fn block0_with_funds(
+    leader: &PublicKey<Ed25519>,
+    funds: &[(PublicKey<Ed25519>, u64)],
+) -> Block {
+    let mut blockchain_configuration = BlockchainConfiguration::new(
+        Discrimination::Test,
+        ConsensusVersion::Bft,
+        LinearFee::new(0, 0, 0),
+    );
+    blockchain_configuration.consensus_leader_ids =
+        vec![ConsensusLeaderId(LeaderId::from(leader.clone()))];
+
+    let mut initial = Vec::new();
+    if !funds.is_empty() {
+        let utxos = funds
+            .iter()
+            .map(|(key, value)| InitialUTxO {
+                address: account_address(key).into(),
+                value: (*value).into(),
+            })
+            .collect();
+        initial.push(Initial::Fund(utxos));
+    }
+
+    Block0Configuration {
+        blockchain_configuration,
+        initial,
+    }
+    .to_block()
+}
+
+/// the block0 of a BFT chain without fees or funds, led by a new leader
+pub fn block0() -> Block {
+    let leader = SecretKey::<Ed25519>::generate(rand_core::OsRng).to_public();
+    block0_with_funds(&leader, &[])
+}
+
+/// the ledger of the block0 and its parameters
+pub fn ledger(block0: &Block) -> (Ledger, LedgerParameters) {
+    let ledger = Ledger::new(block0.header.hash(), block0.contents.iter()).unwrap();
+    let ledger_params = Leadership::new(block0.header.block_date().epoch, &ledger)
+        .ledger_parameters()
+        .clone();
+    (ledger, ledger_params)
+}
+
+pub fn account_address(key: &PublicKey<Ed25519>) -> Address {
+    Address(Discrimination::Test, Kind::Account(key.clone()))
+}
+
+/// a transfer of 10 from the account of the key to the account of the
+/// receiver, with the given spending counter
+pub fn transfer(
+    block0_hash: &HeaderId,
+    key: &SecretKey<Ed25519>,
+    receiver: &PublicKey<Ed25519>,
+    counter: u32,
+) -> Fragment {
+    let input = Input::from_account_public_key(key.to_public(), Value(10));
+    let output = Output::from_address(account_address(receiver), Value(10));
+    let builder = TxBuilder::new()
+        .set_nopayload()
+        .set_ios(&[input], &[output]);
+    let sign_data_hash = builder.get_auth_data_for_witness().hash();
+    let witness = Witness::new_account(
+        block0_hash,
+        &sign_data_hash,
+        SpendingCounter::from(counter),
+        |data| key.sign(data),
+    );
+    Fragment::Transaction(builder.set_witnesses(&[witness]).set_payload_auth(&()))
+}
@@ -125,7 +125,8 @@ impl Controller {
                         FragmentStatus::Rejected { .. } => {
                             self.remove_pending_transaction(id);
                         }
-                        FragmentStatus::InABlock { .. } => {
+                        FragmentStatus::InABlock { .. }
+                        | FragmentStatus::AlreadyConfirmed { .. } => {
                             self.confirm_transaction(*id);
                         }
                         _ => (),
@@ -47,6 +47,12 @@ impl<'a> FragmentCheck<'a> {
         self.assert_log_shows_rejected(&expected_reason);
     }
 
+    pub fn assert_already_confirmed(&self) {
+        let wait: Wait = Default::default();
+        self.wait_until_processed(&wait).unwrap();
+        self.assert_log_shows_already_confirmed();
+    }
+
     pub fn wait_until_processed(&self, wait: &Wait) -> Result<FragmentId, Error> {
         run_process_until_response_matches(
             JCliCommand::new(Command::new(self.jcli.path()))
@@ -126,6 +132,31 @@ impl<'a> FragmentCheck<'a> {
         self.id
     }
 
+    fn assert_log_shows_already_confirmed(&self) {
+        let fragments = self
+            .jcli
+            .rest()
+            .v0()
+            .message()
+            .logs(self.jormungandr.rest_uri());
+        match fragments
+            .iter()
+            .find(|x| *x.fragment_id() == Hash::from_hash(self.id))
+        {
+            Some(x) => assert!(
+                x.is_already_confirmed(),
+                "Fragment should be already confirmed, actual: {:?}. Logs: {:?}",
+                &x,
+                self.jormungandr.logger.get_log_content()
+            ),
+            None => panic!(
+                "cannot find any fragment in rest message log, output: {:?}. Node log: {:?}",
+                &fragments,
+                self.jormungandr.logger.get_log_content()
+            ),
+        }
+    }
+
     pub fn assert_log_shows_rejected(self, expected_msg: &str) {
         let fragments = self
             .jcli
@@ -145,7 +176,9 @@ impl<'a> FragmentCheck<'a> {
                     self.jormungandr.logger.get_log_content()
                 );
                 match x.status() {
-                    FragmentStatus::Rejected { reason } => assert!(reason.contains(&expected_msg)),
+                    FragmentStatus::Rejected { reason, .. } => {
+                        assert!(reason.contains(&expected_msg))
+                    }
                     _ => panic!("Non expected state for for rejected log"),
                 }
             }
@@ -209,6 +209,11 @@ impl ConfigurationBuilder {
         self
     }
 
+    pub fn with_mempool_warmup(&mut self, mempool_warmup: bool) -> &mut Self {
+        self.node_config_builder.with_mempool_warmup(mempool_warmup);
+        self
+    }
+
     pub fn with_trusted_peers(&mut self, trusted_peers: Vec<TrustedPeer>) -> &mut Self {
         self.node_config_builder.with_trusted_peers(trusted_peers);
         self
@@ -7,6 +7,7 @@ use chain_impl_mockchain::fragment::FragmentId;
 pub use configuration_builder::ConfigurationBuilder;
 use jormungandr_lib::crypto::hash::Hash;
 use jormungandr_lib::interfaces::BlockDate;
+use jormungandr_lib::interfaces::FragmentDetails;
 use jormungandr_lib::interfaces::FragmentLog;
 use jormungandr_testing_utils::testing::MemPoolCheck;
 pub use process::*;
@@ -37,6 +38,17 @@ impl FragmentNode for JormungandrProcess {
             .fragment_logs()
             .map_err(|e| FragmentNodeError::ListFragmentError(e.to_string()))
     }
+    fn fragment_details(
+        &self,
+        fragment_id: &FragmentId,
+    ) -> Result<Option<FragmentDetails>, FragmentNodeError> {
+        self.rest().fragment_details(fragment_id).map_err(|e| {
+            FragmentNodeError::FragmentDetailsError {
+                reason: e.to_string(),
+                fragment_id: *fragment_id,
+            }
+        })
+    }
     fn send_fragment(&self, fragment: Fragment) -> Result<MemPoolCheck, FragmentNodeError> {
         self.rest().send_fragment(fragment.clone()).map_err(|e| {
             FragmentNodeError::CannotSendFragment {
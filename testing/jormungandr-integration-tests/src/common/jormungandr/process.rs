@@ -9,7 +9,7 @@ use jormungandr_lib::{
 use jormungandr_testing_utils::testing::{
     node::{
         uri_from_socket_addr, Explorer, JormungandrLogger, JormungandrRest,
-        JormungandrStateVerifier,
+        JormungandrStateVerifier, LogCapture, LogQuery, LogTail,
     },
     JormungandrParams, SyncNode, TestConfig,
 };
@@ -22,6 +22,7 @@ use std::str::FromStr;
 pub struct JormungandrProcess {
     pub child: Child,
     pub logger: JormungandrLogger,
+    log_capture: Option<LogCapture>,
     temp_dir: Option<TempDir>,
     alias: String,
     p2p_public_address: poldercast::Address,
@@ -34,6 +35,7 @@ pub struct JormungandrProcess {
 impl JormungandrProcess {
     pub(crate) fn from_config<Conf: TestConfig>(
         child: Child,
+        log_capture: Option<LogCapture>,
         params: &JormungandrParams<Conf>,
         temp_dir: Option<TempDir>,
         alias: String,
@@ -45,6 +47,7 @@ impl JormungandrProcess {
             temp_dir,
             alias,
             logger: JormungandrLogger::new(log_file_path),
+            log_capture,
             p2p_public_address: node_config.p2p_public_address(),
             rest_socket_addr: node_config.rest_socket_addr(),
             genesis_block_hash: Hash::from_str(params.genesis_block_hash()).unwrap(),
@@ -57,6 +60,17 @@ impl JormungandrProcess {
         &self.alias
     }
 
+    /// the capture of the node output, when started with
+    /// `Starter::capture_logs`
+    pub fn log_capture(&self) -> Option<&LogCapture> {
+        self.log_capture.as_ref()
+    }
+
+    /// follow the output of the node from now on, when it is captured
+    pub fn tail_logs(&self) -> Option<LogTail> {
+        self.log_capture.as_ref().map(LogCapture::tail)
+    }
+
     pub fn rest(&self) -> JormungandrRest {
         JormungandrRest::new(self.rest_uri())
     }
@@ -107,6 +121,12 @@ impl JormungandrProcess {
         );
     }
 
+    /// assert the log contains no errors other than the ones matching the
+    /// given queries, for the tests provoking some errors on purpose
+    pub fn assert_no_errors_in_log_except(&self, expected: &[LogQuery]) {
+        self.logger.assert_no_errors_except(expected);
+    }
+
     pub fn check_no_errors_in_log(&self) -> Result<(), JormungandrError> {
         let error_lines = self.logger.get_lines_with_error().collect::<Vec<String>>();
 
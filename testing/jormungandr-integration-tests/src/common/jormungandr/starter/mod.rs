@@ -14,9 +14,9 @@ use jormungandr_testing_utils::{
     testing::{
         file,
         network_builder::LeadershipMode,
-        node::{configuration::legacy, JormungandrLogger},
-        JormungandrParams, LegacyConfigConverter, LegacyConfigConverterError, SpeedBenchmarkDef,
-        SpeedBenchmarkRun, TestConfig,
+        node::{configuration::legacy, JormungandrLogger, LogCapture, LogQuery, LogRotation},
+        record_benchmark, BenchmarkRecord, JormungandrParams, LegacyConfigConverter,
+        LegacyConfigConverterError, SpeedBenchmarkDef, SpeedBenchmarkRun, TestConfig,
     },
     Version,
 };
@@ -175,9 +175,11 @@ impl<'a, Conf: TestConfig> StartupVerification for LogStartupVerification<'a, Co
 
         let logger = JormungandrLogger::new(self.config.log_file_path());
 
-        bootstrap_completed_msgs
-            .iter()
-            .any(|msg| logger.contains_message(msg).unwrap_or(false))
+        bootstrap_completed_msgs.iter().any(|msg| {
+            logger
+                .contains_entry(&LogQuery::new().message(*msg))
+                .unwrap_or(false)
+        })
     }
 }
 
@@ -193,7 +195,8 @@ pub struct Starter {
     temp_dir: Option<TempDir>,
     legacy: Option<Version>,
     config: Option<JormungandrParams>,
-    benchmark: Option<SpeedBenchmarkDef>,
+    benchmark: Option<String>,
+    log_rotation: Option<LogRotation>,
 }
 
 impl Default for Starter {
@@ -216,6 +219,7 @@ impl Starter {
             legacy: None,
             config: None,
             benchmark: None,
+            log_rotation: None,
             jormungandr_app_path: get_jormungandr_app(),
         }
     }
@@ -241,7 +245,7 @@ impl Starter {
     }
 
     pub fn benchmark(&mut self, name: &str) -> &mut Self {
-        self.benchmark = Some(SpeedBenchmarkDef::new(name.to_owned()));
+        self.benchmark = Some(name.to_owned());
         self
     }
 
@@ -288,6 +292,13 @@ impl Starter {
         self
     }
 
+    /// capture the standard output and error of the node into rotating
+    /// files next to its log file, which can be tailed while the node runs
+    pub fn capture_logs(&mut self, rotation: LogRotation) -> &mut Self {
+        self.log_rotation = Some(rotation);
+        self
+    }
+
     fn build_configuration(
         &mut self,
     ) -> Result<(JormungandrParams, Option<TempDir>), StartupError> {
@@ -304,14 +315,21 @@ impl Starter {
 
     pub fn start_benchmark_run(&self) -> Option<SpeedBenchmarkRun> {
         match &self.benchmark {
-            Some(benchmark_def) => Some(benchmark_def.clone().target(self.timeout).start()),
+            Some(name) => Some(
+                SpeedBenchmarkDef::new(name.clone())
+                    .target(self.timeout)
+                    .start(),
+            ),
             None => None,
         }
     }
 
-    pub fn finish_benchmark(&self, benchmark_run: Option<SpeedBenchmarkRun>) {
-        if let Some(benchmark_run) = benchmark_run {
+    /// print the result of the run and record it in the benchmark history
+    pub fn finish_benchmark(&self, benchmark_run: Option<SpeedBenchmarkRun>, elapsed: Duration) {
+        if let (Some(benchmark_run), Some(name)) = (benchmark_run, &self.benchmark) {
             benchmark_run.stop().print();
+            record_benchmark(BenchmarkRecord::duration(name, elapsed))
+                .unwrap_or_else(|error| panic!("{}", error));
         }
     }
 
@@ -340,13 +358,14 @@ impl Starter {
 
     pub fn start(&mut self) -> Result<JormungandrProcess, StartupError> {
         let (params, temp_dir) = self.build_configuration()?;
+        let started = Instant::now();
         let benchmark = self.start_benchmark_run();
         let process = if let Some(version) = self.legacy.as_ref() {
             ConfiguredStarter::legacy(self, version.clone(), params, temp_dir)?.start()?
         } else {
             ConfiguredStarter::new(self, params, temp_dir).start()?
         };
-        self.finish_benchmark(benchmark);
+        self.finish_benchmark(benchmark, started.elapsed());
         Ok(process)
     }
 
@@ -428,7 +447,7 @@ where
         }
     }
 
-    fn start_process(&self) -> Child {
+    fn start_process(&self) -> (Child, Option<LogCapture>) {
         println!("Starting node");
         println!(
             "Log file: {}",
@@ -448,14 +467,44 @@ where
 
         println!("Bootstrapping...");
 
-        command
+        let rotation = match self.starter.log_rotation {
+            Some(rotation) => rotation,
+            None => {
+                let child = command
+                    .spawn()
+                    .expect("failed to execute 'start jormungandr node'");
+                return (child, None);
+            }
+        };
+
+        let log_file_path = self.params.log_file_path();
+        // without a log file in the node configuration the node logs to
+        // stderr, which is then captured in place of the log file
+        let stderr_path = if self.params.node_config().log_file_path().is_none() {
+            log_file_path.to_path_buf()
+        } else {
+            log_file_path.with_extension("stderr.log")
+        };
+        let mut child = command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
             .spawn()
-            .expect("failed to execute 'start jormungandr node'")
+            .expect("failed to execute 'start jormungandr node'");
+        let log_capture = LogCapture::start(
+            &mut child,
+            log_file_path.with_extension("stdout.log"),
+            stderr_path,
+            rotation,
+        )
+        .expect("cannot capture the node output");
+        (child, Some(log_capture))
     }
 
     fn start_async(self) -> Result<JormungandrProcess, StartupError> {
+        let (child, log_capture) = self.start_process();
         Ok(JormungandrProcess::from_config(
-            self.start_process(),
+            child,
+            log_capture,
             &self.params,
             self.temp_dir,
             self.starter.alias.clone(),
@@ -465,9 +514,10 @@ where
     fn start(mut self) -> Result<JormungandrProcess, StartupError> {
         let mut retry_counter = 1;
         loop {
-            let process = self.start_process();
+            let (process, log_capture) = self.start_process();
             let mut jormungandr = JormungandrProcess::from_config(
                 process,
+                log_capture,
                 &self.params,
                 self.temp_dir.take(),
                 self.starter.alias.clone(),
@@ -1,8 +1,16 @@
-use crate::common::jormungandr::{ConfigurationBuilder, Starter};
-use jormungandr_lib::interfaces::{Log, LogEntry, LogOutput};
+use crate::common::{
+    jormungandr::{ConfigurationBuilder, Starter},
+    startup,
+};
+use jormungandr_lib::interfaces::{InitialUTxO, Log, LogEntry, LogOutput};
+use jormungandr_testing_utils::testing::{
+    node::{LogQuery, LogRotation},
+    FragmentSender, FragmentSenderSetup,
+};
 
 use assert_fs::prelude::*;
 use assert_fs::TempDir;
+use std::time::Duration;
 
 #[test]
 pub fn test_jormungandr_leader_node_starts_successfully() {
@@ -91,3 +99,95 @@ pub fn test_jormungandr_without_logger_starts_successfully() {
     let config = ConfigurationBuilder::new().without_log().build(&temp_dir);
     let _jormungandr = Starter::new().config(config).start().unwrap();
 }
+
+#[test]
+pub fn test_jormungandr_captured_logs_can_be_tailed() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = ConfigurationBuilder::new()
+        .with_log(Log(vec![LogEntry {
+            format: "json".to_string(),
+            level: "info".to_string(),
+            output: LogOutput::Stderr,
+        }]))
+        .build(&temp_dir);
+    let jormungandr = Starter::new()
+        .config(config)
+        .capture_logs(LogRotation::default())
+        .start()
+        .unwrap();
+
+    let tail = jormungandr.tail_logs().unwrap();
+    let created_block = tail
+        .wait_for_entry(
+            &LogQuery::new()
+                .message("block from leader event successfully stored")
+                .task("block"),
+            Duration::from_secs(30),
+        )
+        .unwrap();
+
+    assert!(jormungandr
+        .logger
+        .get_log_entries()
+        .any(|entry| entry.hash == created_block.hash));
+    jormungandr.assert_no_errors_in_log();
+}
+
+#[test]
+pub fn test_passive_node_warms_up_its_mempool_from_trusted_peer() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut sender = startup::create_new_account_address();
+    let receiver = startup::create_new_account_address();
+
+    let leader_dir = temp_dir.child("leader");
+    leader_dir.create_dir_all().unwrap();
+    // slots long enough for the transaction to stay pending in the mempool
+    // of the leader while the passive node starts
+    let leader_config = ConfigurationBuilder::new()
+        .with_slot_duration(100)
+        .with_funds(vec![InitialUTxO {
+            address: sender.address(),
+            value: 1_000.into(),
+        }])
+        .build(&leader_dir);
+    let jormungandr_leader = Starter::new()
+        .config(leader_config.clone())
+        .start()
+        .unwrap();
+
+    let check = FragmentSender::new(
+        jormungandr_leader.genesis_block_hash(),
+        jormungandr_leader.fees(),
+        FragmentSenderSetup::no_verify(),
+    )
+    .send_transaction(&mut sender, &receiver, &jormungandr_leader, 1.into())
+    .unwrap();
+
+    let passive_dir = temp_dir.child("passive");
+    passive_dir.create_dir_all().unwrap();
+    let passive_config = ConfigurationBuilder::new()
+        .with_trusted_peers(vec![jormungandr_leader.to_trusted_peer()])
+        .with_block_hash(leader_config.genesis_block_hash())
+        .with_mempool_warmup(true)
+        .build(&passive_dir);
+    let jormungandr_passive = Starter::new()
+        .config(passive_config)
+        .passive()
+        .start()
+        .unwrap();
+
+    // the mempool is warmed up in the background once the node is up
+    let warmed_up = (0..10).any(|_| {
+        let logs = jormungandr_passive.rest().fragment_logs().unwrap();
+        if logs.contains_key(check.fragment_id()) {
+            return true;
+        }
+        std::thread::sleep(Duration::from_secs(1));
+        false
+    });
+    assert!(
+        warmed_up,
+        "the pending transaction of the trusted peer is not in the mempool of the passive node"
+    );
+    jormungandr_passive.assert_no_errors_in_log();
+}
@@ -1,12 +1,22 @@
 use crate::common::{
-    jcli::JCli, jormungandr::ConfigurationBuilder, startup, transaction_utils::TransactionHash,
+    jcli::JCli,
+    jormungandr::{ConfigurationBuilder, Starter},
+    startup,
+    transaction_utils::TransactionHash,
 };
+use assert_fs::TempDir;
 use chain_impl_mockchain::fragment::FragmentId;
 use chain_impl_mockchain::key::Hash;
+use chain_impl_mockchain::{certificate::VotePlan, vote::Choice};
 use jormungandr_lib::interfaces::ActiveSlotCoefficient;
 use jormungandr_testing_utils::stake_pool::StakePool;
-use jormungandr_testing_utils::testing::node::Explorer;
+use jormungandr_testing_utils::testing::{
+    node::{explorer::data::vote_plan_by_id, Explorer},
+    vote_plan_cert, FragmentSender, FragmentSenderSetup, VotePlanExtension,
+};
+use jormungandr_testing_utils::wallet::Wallet;
 use jortestkit::process::Wait;
+use rand::rngs::OsRng;
 use std::str::FromStr;
 use std::time::Duration;
 
@@ -96,13 +106,48 @@ pub fn explorer_sanity_test() {
 fn transaction_by_id(explorer: &Explorer, fragment_id: FragmentId) {
     let explorer_transaction = explorer
         .transaction(fragment_id.into())
-        .expect("non existing transaction");
+        .expect("non existing transaction")
+        .data
+        .unwrap()
+        .transaction;
 
     assert_eq!(
         fragment_id,
-        Hash::from_str(&explorer_transaction.data.unwrap().transaction.id).unwrap(),
+        Hash::from_str(&explorer_transaction.id).unwrap(),
         "incorrect fragment id"
     );
+    assert_eq!(explorer_transaction.inputs.len(), 1, "wrong inputs count");
+    assert!(
+        explorer_transaction
+            .outputs
+            .iter()
+            .any(|output| output.amount == "1000"),
+        "no output with the value sent: {:?}",
+        explorer_transaction.outputs
+    );
+
+    block_by_id(explorer, &explorer_transaction.block.id, fragment_id);
+}
+
+fn block_by_id(explorer: &Explorer, block_id: &str, fragment_id: FragmentId) {
+    let block = explorer
+        .block(block_id.parse().unwrap(), 1000)
+        .expect("non existing block")
+        .data
+        .unwrap()
+        .block;
+
+    assert_eq!(block_id, block.id, "incorrect block id");
+    assert!(
+        block
+            .transactions
+            .edges
+            .iter()
+            .any(|edge| edge.node.id == fragment_id.to_string()),
+        "transaction {} is not in block {}",
+        fragment_id,
+        block_id
+    );
 }
 
 fn blocks(explorer: &Explorer, blocks_from_logs: Vec<Hash>) {
@@ -177,3 +222,69 @@ fn epoch(explorer: &Explorer) {
 
     assert_eq!(epoch.data.unwrap().epoch.id, "1", "can't find epoch");
 }
+
+#[test]
+pub fn explorer_vote_plan_test() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut rng = OsRng;
+    let mut alice = Wallet::new_account(&mut rng);
+    let bob = Wallet::new_account(&mut rng);
+
+    let vote_plan = VotePlan::new_with_3_proposals(10);
+    let wallets = [&alice, &bob];
+    let config = ConfigurationBuilder::new()
+        .with_funds(
+            wallets
+                .iter()
+                .map(|x| x.to_initial_fund(1_000_000))
+                .collect(),
+        )
+        .with_committees(&wallets)
+        .with_slots_per_epoch(60)
+        .with_certs(vec![vote_plan_cert(&alice, &vote_plan).into()])
+        .with_explorer()
+        .with_slot_duration(1)
+        .build(&temp_dir);
+    let jormungandr = Starter::new().config(config).start().unwrap();
+
+    FragmentSender::new(
+        jormungandr.genesis_block_hash(),
+        jormungandr.fees(),
+        FragmentSenderSetup::resend_3_times(),
+    )
+    .send_vote_cast(&mut alice, &vote_plan, 0, &Choice::new(1), &jormungandr)
+    .unwrap();
+
+    let explorer_vote_plan = jormungandr
+        .explorer()
+        .vote_plan(vote_plan.to_id().to_string(), 100)
+        .expect("non existing vote plan")
+        .data
+        .unwrap()
+        .vote_plan;
+
+    assert_eq!(
+        vote_plan.to_id().to_string(),
+        explorer_vote_plan.id,
+        "incorrect vote plan id"
+    );
+    assert!(
+        matches!(
+            explorer_vote_plan.payload_type,
+            vote_plan_by_id::PayloadType::PUBLIC
+        ),
+        "wrong payload type"
+    );
+    assert_eq!(
+        explorer_vote_plan.proposals.len(),
+        3,
+        "wrong proposals count"
+    );
+
+    let votes: Vec<_> = explorer_vote_plan
+        .proposals
+        .iter()
+        .map(|proposal| proposal.votes.total_count.as_str())
+        .collect();
+    assert_eq!(votes, vec!["1", "0", "0"], "wrong votes count");
+}
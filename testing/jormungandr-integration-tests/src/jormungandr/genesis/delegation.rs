@@ -0,0 +1,78 @@
+use crate::common::{jormungandr::ConfigurationBuilder, startup};
+use jormungandr_testing_utils::testing::{
+    ExpectedStakeDistribution, FragmentSender, FragmentSenderSetup,
+};
+
+const INITIAL_FUNDS: u64 = 1_000_000_000;
+
+#[test]
+pub fn split_delegation_then_redelegation_then_removal() {
+    let owner_1 = startup::create_new_account_address();
+    let owner_2 = startup::create_new_account_address();
+    let mut delegator = startup::create_new_account_address();
+
+    let (jormungandr, stake_pools) = startup::start_stake_pool(
+        &[owner_1, owner_2],
+        &[delegator.clone()],
+        ConfigurationBuilder::new()
+            .with_slots_per_epoch(20)
+            .with_slot_duration(2),
+    )
+    .unwrap();
+    let pool_1 = &stake_pools[0];
+    let pool_2 = &stake_pools[1];
+
+    let sender = FragmentSender::new(
+        jormungandr.genesis_block_hash(),
+        jormungandr.fees(),
+        FragmentSenderSetup::resend_3_times(),
+    );
+
+    ExpectedStakeDistribution::new()
+        .unassigned(INITIAL_FUNDS)
+        .pool(pool_1, INITIAL_FUNDS)
+        .pool(pool_2, INITIAL_FUNDS)
+        .verify(&jormungandr.rest().stake_distribution().unwrap())
+        .unwrap();
+
+    // a 1:2 split does not divide the funds evenly, the remainder goes to
+    // the first pool
+    let split = [(pool_1, 1u8), (pool_2, 2u8)];
+    sender
+        .send_split_delegation(&mut delegator, &split, &jormungandr)
+        .unwrap();
+    startup::sleep_till_next_epoch(10, jormungandr.block0_configuration());
+
+    ExpectedStakeDistribution::new()
+        .unassigned(0)
+        .pool(pool_1, INITIAL_FUNDS)
+        .pool(pool_2, INITIAL_FUNDS)
+        .delegation(INITIAL_FUNDS, &split)
+        .verify(&jormungandr.rest().stake_distribution().unwrap())
+        .unwrap();
+
+    sender
+        .send_redelegation(&mut delegator, &[(pool_2, 1u8)], &jormungandr)
+        .unwrap();
+    startup::sleep_till_next_epoch(10, jormungandr.block0_configuration());
+
+    ExpectedStakeDistribution::new()
+        .unassigned(0)
+        .pool(pool_1, INITIAL_FUNDS)
+        .pool(pool_2, INITIAL_FUNDS)
+        .delegation(INITIAL_FUNDS, &[(pool_2, 1u8)])
+        .verify(&jormungandr.rest().stake_distribution().unwrap())
+        .unwrap();
+
+    sender
+        .send_delegation_removal(&mut delegator, &jormungandr)
+        .unwrap();
+    startup::sleep_till_next_epoch(10, jormungandr.block0_configuration());
+
+    ExpectedStakeDistribution::new()
+        .unassigned(INITIAL_FUNDS)
+        .pool(pool_1, INITIAL_FUNDS)
+        .pool(pool_2, INITIAL_FUNDS)
+        .verify(&jormungandr.rest().stake_distribution().unwrap())
+        .unwrap();
+}
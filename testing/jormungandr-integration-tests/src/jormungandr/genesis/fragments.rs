@@ -2,7 +2,8 @@ use crate::common::{jcli::JCli, jormungandr::ConfigurationBuilder, startup};
 use jormungandr_testing_utils::{
     stake_pool::StakePool,
     testing::{
-        AdversaryFragmentSender, AdversaryFragmentSenderSetup, FragmentSender, FragmentSenderSetup,
+        AdversaryFragmentSender, AdversaryFragmentSenderSetup, ExpectedRejection, FragmentNode,
+        FragmentSender, FragmentSenderSetup, FragmentVerifier,
     },
 };
 
@@ -207,3 +208,60 @@ pub fn test_all_adversary_fragments() {
         .value_moved_between_wallets(&faucet, &stake_pool_owner, stake_pool_owner_stake.into())
         .unwrap();
 }
+
+#[test]
+pub fn test_fragments_block_order_and_rejection_reason() {
+    let mut sender = startup::create_new_account_address();
+    let receiver = startup::create_new_account_address();
+
+    let (jormungandr, _) = startup::start_stake_pool(
+        &[sender.clone()],
+        &[receiver.clone()],
+        &mut ConfigurationBuilder::new(),
+    )
+    .unwrap();
+    let verifier = FragmentVerifier;
+    let mut stale_sender = sender.clone();
+
+    let fragments = (1..=3u64)
+        .map(|value| {
+            let fragment = sender
+                .transaction_to(
+                    &jormungandr.genesis_block_hash(),
+                    &jormungandr.fees(),
+                    receiver.address(),
+                    value.into(),
+                )
+                .unwrap();
+            sender.confirm_transaction();
+            fragment
+        })
+        .collect();
+    let checks = jormungandr.send_batch_fragments(fragments).unwrap();
+    for check in &checks {
+        verifier
+            .wait_and_verify_is_in_block(Duration::from_secs(2), check.clone(), &jormungandr)
+            .unwrap();
+    }
+    verifier.are_in_block_order(&checks, &jormungandr).unwrap();
+
+    // the spending counter was used by the first transaction of the batch,
+    // the witness signed with it no longer verifies
+    let replayed = stale_sender
+        .transaction_to(
+            &jormungandr.genesis_block_hash(),
+            &jormungandr.fees(),
+            receiver.address(),
+            10.into(),
+        )
+        .unwrap();
+    let check = jormungandr.send_fragment(replayed).unwrap();
+    verifier
+        .wait_and_verify_is_rejected_with(
+            Duration::from_secs(2),
+            check,
+            &ExpectedRejection::ledger_error("AccountInvalidSignature"),
+            &jormungandr,
+        )
+        .unwrap();
+}
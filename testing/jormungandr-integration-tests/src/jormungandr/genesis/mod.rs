@@ -1,3 +1,4 @@
+pub mod delegation;
 pub mod fragments;
 pub mod leadership;
 pub mod pool_update;
@@ -2,7 +2,7 @@ use crate::common::{configuration, jormungandr::starter::Starter};
 
 use jormungandr_testing_utils::testing::node::{
     grpc::server::{MethodType, MockBuilder, MockExitCode, ProtocolVersion},
-    Level,
+    Level, LogQuery,
 };
 
 use super::setup::Fixture;
@@ -69,13 +69,13 @@ pub async fn wrong_genesis_hash() {
     );
 
     server.shutdown();
+    let query = LogQuery::new()
+        .level(Level::INFO)
+        .message("connection to peer failed")
+        .field_contains("error", "Block0Mismatch")
+        .field("peer_addr", mock_address);
     assert!(
-        server.logger.get_log_entries().any(|x| {
-            x.msg == "connection to peer failed"
-                && x.error_contains("Block0Mismatch")
-                && x.peer_addr == Some(mock_address.clone())
-                && x.level == Level::INFO
-        }),
+        server.logger.entries_matching(&query).next().is_some(),
         format!("Log content: {}", server.logger.get_log_content())
     );
 }
@@ -110,8 +110,8 @@ pub async fn handshake_ok() {
         "Handshake with mock never happened"
     );
 
-    assert!(!server
-        .logger
-        .get_log_entries()
-        .any(|x| { x.peer_addr == Some(mock_address.clone()) && x.level == Level::WARN }));
+    let query = LogQuery::new()
+        .level(Level::WARN)
+        .field("peer_addr", mock_address);
+    assert!(server.logger.entries_matching(&query).next().is_none());
 }
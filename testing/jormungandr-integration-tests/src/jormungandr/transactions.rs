@@ -1,8 +1,12 @@
 use crate::common::{
-    jcli::JCli, jormungandr::ConfigurationBuilder, startup, transaction_utils::TransactionHash,
+    jcli::JCli,
+    jormungandr::{ConfigurationBuilder, Starter},
+    startup,
+    transaction_utils::TransactionHash,
 };
+use assert_fs::TempDir;
 use chain_impl_mockchain::fee::LinearFee;
-use jormungandr_lib::interfaces::{ActiveSlotCoefficient, Mempool, Value};
+use jormungandr_lib::interfaces::{ActiveSlotCoefficient, Mempool, SelectionQuotas, Value};
 
 #[test]
 pub fn accounts_funds_are_updated_after_transaction() {
@@ -23,6 +27,9 @@ pub fn accounts_funds_are_updated_after_transaction() {
             .with_mempool(Mempool {
                 pool_max_entries: 1_000_000usize.into(),
                 log_max_entries: 1_000_000usize.into(),
+                origin_max_entries: None,
+                confirmed_fragments_epochs: None,
+                selection_quotas: Default::default(),
             }),
     )
     .unwrap();
@@ -87,3 +94,77 @@ pub fn accounts_funds_are_updated_after_transaction() {
         "receiver value after transaction"
     );
 }
+
+#[test]
+pub fn resubmitted_fragment_is_refused_as_already_confirmed() {
+    let jcli: JCli = Default::default();
+    let receiver = startup::create_new_account_address();
+    let mut sender = startup::create_new_account_address();
+
+    let (jormungandr, _) = startup::start_stake_pool(
+        &[sender.clone()],
+        &[receiver.clone()],
+        ConfigurationBuilder::new()
+            .with_slot_duration(1)
+            .with_mempool(Mempool {
+                // the logs only keep the latest fragment, so only the
+                // confirmed fragments remember the first one
+                log_max_entries: 1usize.into(),
+                confirmed_fragments_epochs: Some(2),
+                ..Default::default()
+            }),
+    )
+    .unwrap();
+
+    let mut transfer = || {
+        let transaction = sender
+            .transaction_to(
+                &jormungandr.genesis_block_hash(),
+                &jormungandr.fees(),
+                receiver.address(),
+                1.into(),
+            )
+            .unwrap()
+            .encode();
+        sender.confirm_transaction();
+        transaction
+    };
+    let first = transfer();
+    let second = transfer();
+
+    jcli.fragment_sender(&jormungandr)
+        .send(&first)
+        .assert_in_block();
+    jcli.fragment_sender(&jormungandr)
+        .send(&second)
+        .assert_in_block();
+
+    jcli.fragment_sender(&jormungandr)
+        .send(&first)
+        .assert_already_confirmed();
+}
+
+#[test]
+pub fn node_stats_report_the_capped_block_content_size() {
+    // the protocol limit of the test block0 is 4096 bytes
+    assert_max_block_content_size(Some(1024), 1024);
+    assert_max_block_content_size(Some(1_000_000), 4096);
+    assert_max_block_content_size(None, 4096);
+}
+
+fn assert_max_block_content_size(cap: Option<u32>, expected: u32) {
+    let temp_dir = TempDir::new().unwrap();
+    let config = ConfigurationBuilder::new()
+        .with_mempool(Mempool {
+            selection_quotas: SelectionQuotas {
+                max_content_size: cap,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .build(&temp_dir);
+    let jormungandr = Starter::new().config(config).start().unwrap();
+
+    let stats = jormungandr.rest().stats().unwrap().stats.unwrap();
+    assert_eq!(stats.max_block_content_size, Some(expected));
+}
@@ -1,15 +1,16 @@
 use crate::common::startup::start_stake_pool;
 use crate::common::{
     jcli::JCli,
-    jormungandr::{ConfigurationBuilder, Starter},
+    jormungandr::{ConfigurationBuilder, JormungandrProcess, Starter},
 };
 use assert_fs::{
     fixture::{FileWriteStr, PathChild},
     TempDir,
 };
 use chain_impl_mockchain::{
-    certificate::VotePlan,
+    certificate::{Proposals, VotePlan},
     chaintypes::ConsensusType,
+    fee::LinearFee,
     milli::Milli,
     testing::VoteTestGen,
     value::Value,
@@ -25,7 +26,8 @@ use jormungandr_testing_utils::testing::VotePlanExtension;
 use jormungandr_testing_utils::{
     testing::{
         node::time::{self, wait_for_epoch},
-        vote_plan_cert, FragmentSender, FragmentSenderSetup,
+        offchain_proposal, vote_plan_cert, AdversaryFragmentSender, AdversaryFragmentSenderSetup,
+        FragmentSender, FragmentSenderSetup, PrivateVoteTestFlow,
     },
     wallet::Wallet,
 };
@@ -472,3 +474,247 @@ pub fn jcli_e2e_flow() {
         3
     );
 }
+
+#[test]
+pub fn test_private_vote_flow_bft() {
+    let initial_fund_per_wallet = 1_000_000;
+    let temp_dir = TempDir::new().unwrap();
+
+    let mut rng = OsRng;
+    let mut alice = Wallet::new_account(&mut rng);
+    let mut bob = Wallet::new_account(&mut rng);
+    let mut clarice = Wallet::new_account(&mut rng);
+
+    let mut proposals = Proposals::new();
+    for _ in 0..2 {
+        let _ = proposals.push(offchain_proposal());
+    }
+    let mut flow = PrivateVoteTestFlow::new(&mut rng, TEST_COMMITTEE_SIZE, 2, proposals);
+
+    let vote_plan_cert = vote_plan_cert(&alice, flow.vote_plan()).into();
+    let wallets = [&alice, &bob, &clarice];
+    let config = ConfigurationBuilder::new()
+        .with_funds(
+            wallets
+                .iter()
+                .map(|x| x.to_initial_fund(initial_fund_per_wallet))
+                .collect(),
+        )
+        .with_committees(&wallets)
+        .with_slots_per_epoch(60)
+        .with_certs(vec![vote_plan_cert])
+        .with_explorer()
+        .with_slot_duration(1)
+        .build(&temp_dir);
+
+    let jormungandr = Starter::new().config(config).start().unwrap();
+
+    let transaction_sender = FragmentSender::new(
+        jormungandr.genesis_block_hash(),
+        jormungandr.fees(),
+        FragmentSenderSetup::resend_3_times(),
+    );
+
+    flow.cast_vote(
+        &transaction_sender,
+        &mut alice,
+        initial_fund_per_wallet,
+        0,
+        1,
+        &jormungandr,
+    )
+    .unwrap();
+    flow.cast_vote(
+        &transaction_sender,
+        &mut bob,
+        initial_fund_per_wallet,
+        0,
+        2,
+        &jormungandr,
+    )
+    .unwrap();
+    flow.cast_vote(
+        &transaction_sender,
+        &mut clarice,
+        initial_fund_per_wallet,
+        1,
+        1,
+        &jormungandr,
+    )
+    .unwrap();
+
+    wait_for_epoch(1, jormungandr.explorer().clone());
+
+    flow.send_encrypted_tally(&transaction_sender, &mut alice, &jormungandr)
+        .unwrap();
+    flow.send_tally(
+        &transaction_sender,
+        &mut alice,
+        &jormungandr.rest().vote_plan_statuses().unwrap(),
+        &jormungandr,
+    )
+    .unwrap();
+
+    flow.verify_results(&jormungandr.rest().vote_plan_statuses().unwrap())
+        .unwrap();
+}
+
+#[test]
+pub fn test_faulty_vote_casts_are_rejected_without_side_effects() {
+    let initial_fund_per_wallet = 1_000_000;
+    let temp_dir = TempDir::new().unwrap();
+
+    let mut rng = OsRng;
+    let mut alice = Wallet::new_account(&mut rng);
+    let mut bob = Wallet::new_account(&mut rng);
+    let clarice = Wallet::new_account(&mut rng);
+
+    let public_vote_plan = VotePlan::new_with_3_proposals(10);
+    let mut proposals = Proposals::new();
+    let _ = proposals.push(offchain_proposal());
+    let private_flow = PrivateVoteTestFlow::new(&mut rng, TEST_COMMITTEE_SIZE, 2, proposals);
+    let private_vote_plan = private_flow.vote_plan();
+
+    let wallets = [&alice, &bob, &clarice];
+    let config = ConfigurationBuilder::new()
+        .with_funds(
+            wallets
+                .iter()
+                .map(|x| x.to_initial_fund(initial_fund_per_wallet))
+                .collect(),
+        )
+        .with_committees(&wallets)
+        .with_linear_fees(LinearFee::new(1, 2, 3))
+        .with_slots_per_epoch(60)
+        .with_certs(vec![
+            vote_plan_cert(&alice, &public_vote_plan).into(),
+            vote_plan_cert(&alice, private_vote_plan).into(),
+        ])
+        .with_explorer()
+        .with_slot_duration(1)
+        .build(&temp_dir);
+
+    let jormungandr = Starter::new().config(config).start().unwrap();
+
+    let transaction_sender = FragmentSender::new(
+        jormungandr.genesis_block_hash(),
+        jormungandr.fees(),
+        FragmentSenderSetup::resend_3_times(),
+    );
+    let adversary_sender = AdversaryFragmentSender::new(
+        jormungandr.genesis_block_hash(),
+        jormungandr.fees(),
+        AdversaryFragmentSenderSetup::with_verify(),
+    );
+
+    transaction_sender
+        .send_vote_cast(
+            &mut alice,
+            &public_vote_plan,
+            0,
+            &Choice::new(1),
+            &jormungandr,
+        )
+        .unwrap();
+
+    let verifier = jormungandr
+        .correct_state_verifier()
+        .record_wallets_state(vec![&alice, &bob])
+        .record_vote_plans_state();
+
+    adversary_sender
+        .send_vote_cast_with_choice_out_of_range(&bob, &public_vote_plan, 0, &jormungandr)
+        .unwrap();
+    adversary_sender
+        .send_vote_cast_with_proposal_out_of_range(&bob, &public_vote_plan, &jormungandr)
+        .unwrap();
+    adversary_sender
+        .send_public_vote_cast_for_private_plan(&bob, private_vote_plan, 0, &jormungandr)
+        .unwrap();
+    adversary_sender
+        .send_vote_cast_with_bad_proof(
+            &bob,
+            private_vote_plan,
+            0,
+            &Choice::new(1),
+            private_flow.encrypting_vote_key(),
+            &jormungandr,
+        )
+        .unwrap();
+
+    // rejected fragments neither count as votes nor take fees, and do not
+    // stay in the mempool
+    verifier.votes_cast_unchanged().unwrap();
+    verifier.no_pending_fragments().unwrap();
+    verifier.wallet_lost_value(&alice, 0.into()).unwrap();
+    verifier.wallet_lost_value(&bob, 0.into()).unwrap();
+
+    // a second vote of the account on the same proposal is accepted and
+    // replaces the first one: it takes the fee but is not counted again
+    let verifier = jormungandr
+        .correct_state_verifier()
+        .record_wallets_state(vec![&alice])
+        .record_vote_plans_state();
+    transaction_sender
+        .send_vote_cast(
+            &mut alice,
+            &public_vote_plan,
+            0,
+            &Choice::new(2),
+            &jormungandr,
+        )
+        .unwrap();
+    verifier.votes_cast_unchanged().unwrap();
+    let fees = jormungandr.fees();
+    verifier
+        .wallet_lost_value(
+            &alice,
+            (fees.constant + fees.coefficient + fees.certificate).into(),
+        )
+        .unwrap();
+
+    // the node still accepts a valid vote from the same account
+    transaction_sender
+        .send_vote_cast(
+            &mut bob,
+            &public_vote_plan,
+            0,
+            &Choice::new(1),
+            &jormungandr,
+        )
+        .unwrap();
+    assert_first_proposal_has_votes_cast(2, &public_vote_plan, &jormungandr);
+
+    wait_for_epoch(1, jormungandr.explorer().clone());
+
+    let verifier = jormungandr
+        .correct_state_verifier()
+        .record_vote_plans_state();
+    adversary_sender
+        .send_well_formed_vote_cast(
+            &clarice,
+            &public_vote_plan,
+            0,
+            &Choice::new(1),
+            &jormungandr,
+        )
+        .unwrap();
+    verifier.votes_cast_unchanged().unwrap();
+    verifier.no_pending_fragments().unwrap();
+}
+
+fn assert_first_proposal_has_votes_cast(
+    votes_cast: usize,
+    vote_plan: &VotePlan,
+    jormungandr: &JormungandrProcess,
+) {
+    let id = vote_plan.to_id().to_string();
+    let vote_plan_status = jormungandr
+        .rest()
+        .vote_plan_statuses()
+        .unwrap()
+        .into_iter()
+        .find(|status| status.id.to_string() == id)
+        .unwrap();
+    assert_eq!(votes_cast, vote_plan_status.proposals[0].votes_cast);
+}
@@ -144,6 +144,8 @@ pub fn node_whitelist_itself() {
         .public_address;
     let policy = Policy {
         quarantine_duration: Some(Duration::new(1, 0)),
+        max_quarantine: None,
+        max_num_quarantine_records: None,
         quarantine_whitelist: Some(vec![client_public_address]),
     };
 
@@ -174,6 +176,8 @@ pub fn node_does_not_quarantine_whitelisted_node() {
         .public_address;
     let policy = Policy {
         quarantine_duration: Some(Duration::new(30, 0)),
+        max_quarantine: None,
+        max_num_quarantine_records: None,
         quarantine_whitelist: Some(vec![server_public_address]),
     };
 
@@ -215,6 +219,8 @@ pub fn node_put_in_quarantine_nodes_which_are_not_whitelisted() {
         .public_address;
     let policy = Policy {
         quarantine_duration: Some(Duration::new(1, 0)),
+        max_quarantine: None,
+        max_num_quarantine_records: None,
         quarantine_whitelist: Some(vec![client_public_address]),
     };
 
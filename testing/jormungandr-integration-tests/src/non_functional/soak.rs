@@ -24,6 +24,9 @@ pub fn test_blocks_are_being_created_for_48_hours() {
             .with_mempool(Mempool {
                 pool_max_entries: 1_000_000usize.into(),
                 log_max_entries: 1_000_000usize.into(),
+                origin_max_entries: None,
+                confirmed_fragments_epochs: None,
+                selection_quotas: Default::default(),
             }),
     )
     .unwrap();
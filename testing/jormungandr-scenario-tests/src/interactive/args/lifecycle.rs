@@ -0,0 +1,121 @@
+use super::{
+    spawn::{legacy_node, spawn_node, LegacyNode},
+    UserInteractionController,
+};
+use crate::{style, test::Result};
+use jormungandr_testing_utils::testing::network_builder::{LeadershipMode, PersistenceMode};
+use jortestkit::console::InteractiveCommandError;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+pub struct Stop {
+    /// alias of the node to stop
+    #[structopt(name = "ALIAS")]
+    pub alias: String,
+}
+
+impl Stop {
+    pub fn exec(&self, controller: &mut UserInteractionController) -> Result<()> {
+        stop_node(controller, &self.alias)?;
+        Ok(())
+    }
+}
+
+#[derive(StructOpt, Debug)]
+pub struct Restart {
+    /// alias of the node to restart
+    #[structopt(name = "ALIAS")]
+    pub alias: String,
+    /// released version to restart the node on, instead of the tested one
+    #[structopt(short = "v", long = "version")]
+    pub version: Option<String>,
+    #[structopt(short = "w", long = "wait")]
+    pub wait: bool,
+}
+
+impl Restart {
+    pub fn exec(&self, controller: &mut UserInteractionController) -> Result<()> {
+        // the release is resolved first, not to leave the node stopped if
+        // it cannot be found or downloaded
+        let legacy = legacy_node(controller, self.version.as_deref())?;
+        stop_node(controller, &self.alias)?;
+        respawn_node(controller, &self.alias, legacy, self.wait)
+    }
+}
+
+#[derive(StructOpt, Debug)]
+pub struct Upgrade {
+    /// alias of the legacy node to restart on the tested version
+    #[structopt(name = "ALIAS")]
+    pub alias: String,
+    #[structopt(short = "w", long = "wait")]
+    pub wait: bool,
+}
+
+impl Upgrade {
+    pub fn exec(&self, controller: &mut UserInteractionController) -> Result<()> {
+        if !controller
+            .legacy_nodes()
+            .iter()
+            .any(|node| *node.alias() == self.alias)
+        {
+            return Err(InteractiveCommandError::UserError(format!(
+                "'{}' is not a running legacy node",
+                self.alias
+            ))
+            .into());
+        }
+        stop_node(controller, &self.alias)?;
+        respawn_node(controller, &self.alias, None, self.wait)
+    }
+}
+
+/// shutdown the node with the given alias and forget about it
+fn stop_node(controller: &mut UserInteractionController, alias: &str) -> Result<()> {
+    if let Some(index) = controller
+        .nodes()
+        .iter()
+        .position(|node| *node.alias() == alias)
+    {
+        let node = controller.nodes_mut().remove(index);
+        node.shutdown()?;
+    } else if let Some(index) = controller
+        .legacy_nodes()
+        .iter()
+        .position(|node| *node.alias() == alias)
+    {
+        let node = controller.legacy_nodes_mut().remove(index);
+        node.shutdown()?;
+    } else {
+        return Err(
+            InteractiveCommandError::UserError(format!("no running node '{}'", alias)).into(),
+        );
+    }
+    println!(
+        "{}",
+        style::info.apply_to(format!("node '{}' stopped", alias))
+    );
+    Ok(())
+}
+
+/// spawn again a stopped node, with the leadership and persistence modes
+/// it was first spawned with. Only the nodes spawned with a storage keep
+/// their blocks across the restart.
+fn respawn_node(
+    controller: &mut UserInteractionController,
+    alias: &str,
+    legacy: Option<LegacyNode>,
+    wait: bool,
+) -> Result<()> {
+    let (leadership_mode, persistence_mode) = controller
+        .spawn_modes(alias)
+        .unwrap_or((LeadershipMode::Passive, PersistenceMode::InMemory));
+    spawn_node(
+        controller,
+        leadership_mode,
+        persistence_mode,
+        alias,
+        legacy,
+        wait,
+    )
+}
@@ -1,14 +1,19 @@
 use crate::{legacy::LegacyNodeController, test::Result};
 use crate::{node::NodeController, scenario::Controller};
 use jormungandr_testing_utils::{
-    testing::{FragmentNode, SyncNode},
+    testing::{
+        network_builder::{LeadershipMode, NodeAlias, PersistenceMode},
+        FragmentNode, SyncNode,
+    },
     wallet::Wallet,
 };
+use std::collections::HashMap;
 use structopt::{clap::AppSettings, StructOpt};
 
 use jormungandr_lib::interfaces::Value;
 
 mod describe;
+mod lifecycle;
 mod send;
 mod show;
 mod spawn;
@@ -18,6 +23,7 @@ pub struct UserInteractionController<'a> {
     wallets: Vec<Wallet>,
     nodes: Vec<NodeController>,
     legacy_nodes: Vec<LegacyNodeController>,
+    spawn_modes: HashMap<NodeAlias, (LeadershipMode, PersistenceMode)>,
 }
 
 impl<'a> UserInteractionController<'a> {
@@ -28,6 +34,7 @@ impl<'a> UserInteractionController<'a> {
             wallets,
             nodes: Vec::new(),
             legacy_nodes: Vec::new(),
+            spawn_modes: HashMap::new(),
         }
     }
 
@@ -54,6 +61,21 @@ impl<'a> UserInteractionController<'a> {
         &mut self.nodes
     }
 
+    /// remember how a node was spawned, to restart it the same way
+    pub fn record_spawn_modes(
+        &mut self,
+        alias: &str,
+        leadership_mode: LeadershipMode,
+        persistence_mode: PersistenceMode,
+    ) {
+        self.spawn_modes
+            .insert(alias.to_owned(), (leadership_mode, persistence_mode));
+    }
+
+    pub fn spawn_modes(&self, alias: &str) -> Option<(LeadershipMode, PersistenceMode)> {
+        self.spawn_modes.get(alias).copied()
+    }
+
     pub fn controller(&self) -> &Controller {
         &self.controller
     }
@@ -107,6 +129,13 @@ pub enum InteractiveCommand {
     Describe(describe::Describe),
     /// send fragments
     Send(send::Send),
+    /// Stop a node
+    Stop(lifecycle::Stop),
+    /// Stop a node and spawn it again, on the tested version or on a
+    /// legacy one
+    Restart(lifecycle::Restart),
+    /// Restart a legacy node on the tested version
+    Upgrade(lifecycle::Upgrade),
 }
 
 fn do_for_all_alias<F: Fn(&NodeController), G: Fn(&LegacyNodeController)>(
@@ -3,11 +3,13 @@ use crate::{style, test::Result};
 use jormungandr_testing_utils::{
     testing::{
         network_builder::{LeadershipMode, PersistenceMode, SpawnParams},
-        node::download_last_n_releases,
+        node::{download_last_n_releases, get_jormungandr_bin},
+        Release,
     },
     Version,
 };
 use jortestkit::console::InteractiveCommandError;
+use std::path::PathBuf;
 use structopt::StructOpt;
 
 #[derive(StructOpt, Debug)]
@@ -38,13 +40,14 @@ pub struct SpawnPassiveNode {
 }
 
 impl SpawnPassiveNode {
-    pub fn exec(&self, mut controller: &mut UserInteractionController) -> Result<()> {
+    pub fn exec(&self, controller: &mut UserInteractionController) -> Result<()> {
+        let legacy = legacy_node(controller, self.legacy.as_deref())?;
         spawn_node(
-            &mut controller,
+            controller,
             LeadershipMode::Passive,
-            self.storage,
+            persistence_mode(self.storage),
             &self.alias,
-            self.legacy.as_ref().map(|x| Version::parse(&x).unwrap()),
+            legacy,
             self.wait,
         )
     }
@@ -62,37 +65,62 @@ pub struct SpawnLeaderNode {
     pub alias: String,
 }
 
-fn spawn_node(
+fn persistence_mode(storage: bool) -> PersistenceMode {
+    if storage {
+        PersistenceMode::Persistent
+    } else {
+        PersistenceMode::InMemory
+    }
+}
+
+/// a released version of the node, with its binary
+pub(super) struct LegacyNode {
+    release: Release,
+    jormungandr: PathBuf,
+}
+
+/// find the given version in the last releases of the node and download
+/// its binary
+pub(super) fn legacy_node(
+    controller: &UserInteractionController,
+    version: Option<&str>,
+) -> Result<Option<LegacyNode>> {
+    let version = match version {
+        Some(version) => Version::parse(version)
+            .map_err(|_| InteractiveCommandError::UserError(version.to_owned()))?,
+        None => return Ok(None),
+    };
+    let release = download_last_n_releases(5)
+        .into_iter()
+        .find(|x| x.version() == version)
+        .ok_or_else(|| InteractiveCommandError::UserError(version.to_string()))?;
+    let jormungandr = get_jormungandr_bin(&release, controller.controller().working_directory());
+    Ok(Some(LegacyNode {
+        release,
+        jormungandr,
+    }))
+}
+
+pub(super) fn spawn_node(
     controller: &mut UserInteractionController,
     leadership_mode: LeadershipMode,
-    storage: bool,
+    persistence_mode: PersistenceMode,
     alias: &str,
-    legacy: Option<Version>,
+    legacy: Option<LegacyNode>,
     wait: bool,
 ) -> Result<()> {
-    let persistence_mode = {
-        if storage {
-            PersistenceMode::Persistent
-        } else {
-            PersistenceMode::InMemory
-        }
-    };
-
     let mut spawn_params = SpawnParams::new(alias);
     spawn_params
         .persistence_mode(persistence_mode)
         .leadership_mode(leadership_mode);
+    controller.record_spawn_modes(alias, leadership_mode, persistence_mode);
 
-    if let Some(version) = legacy {
-        let releases = download_last_n_releases(5);
-        let legacy_release = releases
-            .iter()
-            .find(|x| x.version() == version)
-            .ok_or_else(|| InteractiveCommandError::UserError(version.to_string()))?;
+    if let Some(legacy) = legacy {
+        spawn_params.jormungandr(legacy.jormungandr);
 
         let node = controller
             .controller_mut()
-            .spawn_legacy_node(&mut spawn_params, &legacy_release.version())?;
+            .spawn_legacy_node(&mut spawn_params, &legacy.release.version())?;
         println!(
             "{}",
             style::info.apply_to(format!("node '{}' spawned", alias))
@@ -139,13 +167,14 @@ fn spawn_node(
 }
 
 impl SpawnLeaderNode {
-    pub fn exec(&self, mut controller: &mut UserInteractionController) -> Result<()> {
+    pub fn exec(&self, controller: &mut UserInteractionController) -> Result<()> {
+        let legacy = legacy_node(controller, self.legacy.as_deref())?;
         spawn_node(
-            &mut controller,
+            controller,
             LeadershipMode::Leader,
-            self.storage,
+            persistence_mode(self.storage),
             &self.alias,
-            self.legacy.as_ref().map(|x| Version::parse(&x).unwrap()),
+            legacy,
             self.wait,
         )
     }
@@ -1,7 +1,7 @@
 mod args;
 use crate::interactive::args::UserInteractionController;
 use crate::{
-    scenario::{repository::ScenarioResult, Context},
+    scenario::{definition::ScenarioDefinition, repository::ScenarioResult, Context},
     test::Result,
 };
 pub use args::InteractiveCommand;
@@ -17,6 +17,31 @@ use function_name::named;
 #[named]
 pub fn interactive(mut context: Context<ChaChaRng>) -> Result<ScenarioResult> {
     let name = function_name!();
+    if let Some(path) = context.scenario_file().map(|path| path.to_path_buf()) {
+        let definition = ScenarioDefinition::load(path)?;
+        let mut controller = definition.controller_builder(&mut context).build(context)?;
+        let spawned = definition.spawn(&mut controller)?;
+        let mut user_interaction_controller = UserInteractionController::new(&mut controller);
+        user_interaction_controller
+            .nodes_mut()
+            .extend(spawned.nodes);
+        user_interaction_controller
+            .legacy_nodes_mut()
+            .extend(spawned.legacy_nodes);
+        for spawn in &definition.spawn {
+            user_interaction_controller.record_spawn_modes(
+                &spawn.alias,
+                spawn.leadership_mode(),
+                spawn.persistence_mode(),
+            );
+        }
+        jormungandr_user_interaction().interact(&mut JormungandrInteractiveCommandExec {
+            controller: user_interaction_controller,
+        })?;
+        controller.finalize();
+        return Ok(ScenarioResult::passed(name));
+    }
+
     let scenario_settings = prepare_scenario! {
         name,
         &mut context,
@@ -87,6 +112,9 @@ impl InteractiveCommandExec for JormungandrInteractiveCommandExec<'_> {
                             describe.exec(&mut self.controller)
                         }
                         InteractiveCommand::Send(send) => send.exec(&mut self.controller),
+                        InteractiveCommand::Stop(stop) => stop.exec(&mut self.controller),
+                        InteractiveCommand::Restart(restart) => restart.exec(&mut self.controller),
+                        InteractiveCommand::Upgrade(upgrade) => upgrade.exec(&mut self.controller),
                     }
                 } {
                     console.format_error(InteractiveCommandError::UserError(err.to_string()));
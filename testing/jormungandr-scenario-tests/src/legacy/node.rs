@@ -17,7 +17,7 @@ use jormungandr_lib::{
 };
 pub use jormungandr_testing_utils::testing::{
     network_builder::{
-        LeadershipMode, NodeAlias, NodeBlock0, NodeSetting, PersistenceMode, Settings,
+        Cgroup, LeadershipMode, NodeAlias, NodeBlock0, NodeSetting, PersistenceMode, Settings,
     },
     node::{grpc::JormungandrClient, JormungandrLogger},
     FragmentNode, FragmentNodeError, MemPoolCheck,
@@ -42,6 +42,8 @@ pub struct LegacyNodeController {
     settings: LegacySettings,
     progress_bar: ProgressBarController,
     status: Arc<Mutex<Status>>,
+    /// shared with the node so that the cgroup outlives the spawning code
+    cgroup: Option<Arc<Cgroup>>,
 }
 
 pub struct LegacyNode {
@@ -55,6 +57,9 @@ pub struct LegacyNode {
     pub progress_bar: ProgressBarController,
     pub node_settings: LegacySettings,
     pub status: Arc<Mutex<Status>>,
+
+    /// the cgroup enforcing the resource limits of the node, if any
+    pub cgroup: Option<Arc<Cgroup>>,
 }
 
 const NODE_CONFIG: &str = "node_config.yaml";
@@ -71,6 +76,11 @@ impl LegacyNodeController {
         *self.status.lock().unwrap()
     }
 
+    /// the cgroup enforcing the resource limits of the node, if any
+    pub fn cgroup(&self) -> Option<&Cgroup> {
+        self.cgroup.as_deref()
+    }
+
     pub fn check_running(&self) -> bool {
         self.status() == Status::Running
     }
@@ -307,7 +317,7 @@ impl LegacyNodeController {
                             check.fragment_id()
                         ));
                     }
-                    Rejected { reason } => {
+                    Rejected { reason, .. } => {
                         self.progress_bar.log_info(format!(
                             "Fragment '{}' rejected: {}",
                             check.fragment_id(),
@@ -315,7 +325,7 @@ impl LegacyNodeController {
                         ));
                         return Ok(status);
                     }
-                    InABlock { date, block } => {
+                    InABlock { date, block } | AlreadyConfirmed { date, block } => {
                         self.progress_bar.log_info(format!(
                             "Fragment '{}' in block: {} ({})",
                             check.fragment_id(),
@@ -528,6 +538,7 @@ impl LegacyNode {
             settings: self.node_settings.clone(),
             status: self.status.clone(),
             progress_bar: self.progress_bar.clone(),
+            cgroup: self.cgroup.clone(),
         }
     }
 
@@ -45,6 +45,11 @@ struct CommandArgs {
     #[structopt(long = "scenario", default_value = "*")]
     scenario: String,
 
+    /// YAML file describing the network of the scenario, run by the
+    /// `from_file` scenario or loaded by the `interactive` one
+    #[structopt(long = "scenario-file")]
+    scenario_file: Option<PathBuf>,
+
     /// in order to group scenarios (for example long_running, short running)
     /// one can use tag parameter to run entire set of scenarios
     /// by default all scenarios are run
@@ -132,6 +137,7 @@ fn main() {
         generate_documentation,
         progress_bar_mode,
         log_level,
+        command_args.scenario_file,
     );
 
     introduction(&context);
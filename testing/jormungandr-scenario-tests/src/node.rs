@@ -14,13 +14,14 @@ use chain_impl_mockchain::{
 use jormungandr_lib::{
     crypto::hash::Hash,
     interfaces::{
-        EnclaveLeaderId, FragmentLog, LeadershipLog, Log, LogEntry, LogOutput, NodeState,
-        NodeStatsDto, PeerRecord, PeerStats,
+        EnclaveLeaderId, FragmentDetails, FragmentLog, LeadershipLog, Log, LogEntry, LogOutput,
+        NodeState, NodeStatsDto, PeerRecord, PeerStats,
     },
 };
 pub use jormungandr_testing_utils::testing::{
     network_builder::{
-        LeadershipMode, NodeAlias, NodeBlock0, NodeSetting, PersistenceMode, Settings,
+        Cgroup, LeadershipMode, NodeAlias, NodeBlock0, NodeSetting, PersistenceMode,
+        ResourceLimits, Settings,
     },
     node::{
         grpc::{client::MockClientError, JormungandrClient},
@@ -71,6 +72,8 @@ pub enum Error {
     },
     #[error("cannot spawn the node")]
     CannotSpawnNode(#[source] io::Error),
+    #[error("cannot limit the resources of the node")]
+    CannotLimitResources(#[source] io::Error),
     // FIXME: duplicate of GrpcError?
     #[error("invalid grpc call")]
     InvalidGrpcCall(#[source] MockClientError),
@@ -159,6 +162,8 @@ pub struct NodeController {
     progress_bar: ProgressBarController,
     status: Arc<Mutex<Status>>,
     process_id: u32,
+    /// shared with the node so that the cgroup outlives the spawning code
+    cgroup: Option<Arc<Cgroup>>,
 }
 
 /// Node is going to be used by the `Controller` to monitor the node process
@@ -175,6 +180,9 @@ pub struct Node {
     progress_bar: ProgressBarController,
     node_settings: NodeSetting,
     status: Arc<Mutex<Status>>,
+
+    /// the cgroup enforcing the resource limits of the node, if any
+    cgroup: Option<Arc<Cgroup>>,
 }
 
 const NODE_CONFIG: &str = "node_config.yaml";
@@ -195,6 +203,11 @@ impl NodeController {
         self.status() == Status::Running
     }
 
+    /// the cgroup enforcing the resource limits of the node, if any
+    pub fn cgroup(&self) -> Option<&Cgroup> {
+        self.cgroup.as_deref()
+    }
+
     fn path(&self, path: &str) -> String {
         format!("{}/{}", self.base_url(), path)
     }
@@ -294,6 +307,19 @@ impl NodeController {
         Ok(p2p_view)
     }
 
+    /// the status code and the body of the response of a REST endpoint,
+    /// relative to `/api`, whatever the status. The body is parsed as JSON,
+    /// or kept as a string if it is not JSON.
+    pub fn rest_response(&self, path: &str) -> Result<(u16, serde_json::Value)> {
+        let url = format!("http://{}/api/{}", self.settings.config.rest.listen, path);
+        self.progress_bar.log_info(format!("GET '{}'", url));
+        let response = reqwest::blocking::get(&url)?;
+        let status = response.status().as_u16();
+        let text = response.text()?;
+        let body = serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text));
+        Ok((status, body))
+    }
+
     pub fn all_blocks_hashes(&self) -> Result<Vec<HeaderId>> {
         let genesis_hash = self
             .genesis_block_hash()
@@ -339,6 +365,10 @@ impl NodeController {
         Ok(logs)
     }
 
+    pub fn fragment_details(&self, fragment_id: &FragmentId) -> Result<Option<FragmentDetails>> {
+        Ok(self.rest_client.fragment_details(fragment_id)?)
+    }
+
     pub fn leaders(&self) -> Result<Vec<EnclaveLeaderId>> {
         let leaders = self.rest_client.leaders()?;
         self.progress_bar
@@ -534,6 +564,7 @@ impl Node {
             status: self.status.clone(),
             progress_bar: self.progress_bar.clone(),
             process_id: self.process.id(),
+            cgroup: self.cgroup.clone(),
         }
     }
 
@@ -680,6 +711,9 @@ pub struct SpawnBuilder<'a, R: RngCore, N> {
     block0: NodeBlock0,
     working_dir: PathBuf,
     peristence_mode: PersistenceMode,
+    storage: Option<PathBuf>,
+    env: Vec<(String, String)>,
+    resource_limits: ResourceLimits,
     phantom_data: PhantomData<N>,
 }
 
@@ -694,6 +728,9 @@ impl<'a, R: RngCore, N> SpawnBuilder<'a, R, N> {
             block0: NodeBlock0::Hash(TestGen::hash()),
             working_dir: PathBuf::new(),
             peristence_mode: PersistenceMode::Persistent,
+            storage: None,
+            env: Vec::new(),
+            resource_limits: ResourceLimits::default(),
             phantom_data: PhantomData,
         }
     }
@@ -727,6 +764,23 @@ impl<'a, R: RngCore, N> SpawnBuilder<'a, R, N> {
         self
     }
 
+    /// store the blockchain at the given path instead of the working
+    /// directory of the node, used in the persistent mode only
+    pub fn storage(&mut self, storage: Option<PathBuf>) -> &mut Self {
+        self.storage = storage;
+        self
+    }
+
+    pub fn env(&mut self, env: &[(String, String)]) -> &mut Self {
+        self.env = env.to_vec();
+        self
+    }
+
+    pub fn resource_limits(&mut self, resource_limits: ResourceLimits) -> &mut Self {
+        self.resource_limits = resource_limits;
+        self
+    }
+
     fn write_config_file<P: AsRef<Path>>(&self, config_file: P) -> Result<()> {
         serde_yaml::to_writer(
             std::fs::File::create(config_file.as_ref()).map_err(|e| Error::CannotCreateFile {
@@ -757,7 +811,10 @@ impl<'a, R: RngCore, N> SpawnBuilder<'a, R, N> {
 
     fn apply_persistence_setting(&mut self, dir: &PathBuf) {
         if self.peristence_mode == PersistenceMode::Persistent {
-            let path_to_storage = dir.join(NODE_STORAGE);
+            let path_to_storage = self
+                .storage
+                .clone()
+                .unwrap_or_else(|| dir.join(NODE_STORAGE));
             self.node_settings.config.storage = Some(path_to_storage);
         }
     }
@@ -801,9 +858,44 @@ impl<'a, R: RngCore, N> SpawnBuilder<'a, R, N> {
             }
         }
 
+        command.envs(self.env.iter().map(|(key, value)| (key, value)));
         command.stderr(Stdio::piped());
         command
     }
+
+    /// create a cgroup enforcing the resource limits for the process of
+    /// the node to enter when spawned; the limits are only reported as not
+    /// enforced on the hosts without cgroup v2, so that the scenarios still
+    /// run there
+    fn limit_resources(
+        &self,
+        command: &mut Command,
+        progress_bar: &ProgressBarController,
+    ) -> Result<Option<Arc<Cgroup>>> {
+        if self.resource_limits.is_empty() {
+            return Ok(None);
+        }
+        if !ResourceLimits::available() {
+            progress_bar.log_info(&format!(
+                "cgroups are not available, the resource limits {:?} are not enforced",
+                self.resource_limits
+            ));
+            return Ok(None);
+        }
+        let cgroup = self
+            .resource_limits
+            .create(&self.alias)
+            .map_err(Error::CannotLimitResources)?;
+        cgroup
+            .enter_on_spawn(command)
+            .map_err(Error::CannotLimitResources)?;
+        progress_bar.log_info(&format!(
+            "resource limits {:?} enforced by cgroup {}",
+            self.resource_limits,
+            cgroup.path().display()
+        ));
+        Ok(Some(Arc::new(cgroup)))
+    }
 }
 
 impl<'a, R: RngCore> SpawnBuilder<'a, R, Node> {
@@ -821,13 +913,13 @@ impl<'a, R: RngCore> SpawnBuilder<'a, R, Node> {
         self.write_secret_file(&config_secret)?;
 
         let mut command = self.command(config_file, config_secret);
-        let process = command.spawn().map_err(Error::CannotSpawnNode)?;
-
         let progress_bar = ProgressBarController::new(
-            self.progress_bar,
+            self.progress_bar.clone(),
             format!("{}@{}", self.alias, self.node_settings.config().rest.listen),
             self.context.progress_bar_mode(),
         );
+        let cgroup = self.limit_resources(&mut command, &progress_bar)?;
+        let process = command.spawn().map_err(Error::CannotSpawnNode)?;
 
         let node = Node {
             alias: self.alias.clone(),
@@ -836,6 +928,7 @@ impl<'a, R: RngCore> SpawnBuilder<'a, R, Node> {
             progress_bar,
             node_settings: self.node_settings.clone(),
             status: Arc::new(Mutex::new(Status::Running)),
+            cgroup,
         };
 
         node.progress_bar_start();
@@ -860,13 +953,13 @@ impl<'a, R: RngCore> SpawnBuilder<'a, R, LegacyNode> {
         self.write_secret_file(&config_secret)?;
 
         let mut command = self.command(config_file, config_secret);
-        let process = command.spawn().map_err(Error::CannotSpawnNode)?;
-
         let progress_bar = ProgressBarController::new(
-            self.progress_bar,
+            self.progress_bar.clone(),
             format!("{}@{}", self.alias, self.node_settings.config().rest.listen),
             self.context.progress_bar_mode(),
         );
+        let cgroup = self.limit_resources(&mut command, &progress_bar)?;
+        let process = command.spawn().map_err(Error::CannotSpawnNode)?;
 
         let legacy_settngs = LegacySettings::from_settings(self.node_settings.clone(), version);
 
@@ -877,6 +970,7 @@ impl<'a, R: RngCore> SpawnBuilder<'a, R, LegacyNode> {
             progress_bar,
             node_settings: legacy_settngs,
             status: Arc::new(Mutex::new(Status::Running)),
+            cgroup,
         };
 
         node.progress_bar_start();
@@ -35,6 +35,7 @@ pub struct Context<RNG: RngCore + Sized = ChaChaRng> {
     generate_documentation: bool,
     progress_bar_mode: ProgressBarMode,
     log_level: String,
+    scenario_file: Option<PathBuf>,
 }
 
 impl Context<ChaChaRng> {
@@ -46,6 +47,7 @@ impl Context<ChaChaRng> {
         generate_documentation: bool,
         progress_bar_mode: ProgressBarMode,
         log_level: String,
+        scenario_file: Option<PathBuf>,
     ) -> Self {
         let rng = Random::<ChaChaRng>::new(seed);
 
@@ -65,6 +67,7 @@ impl Context<ChaChaRng> {
             generate_documentation,
             progress_bar_mode,
             log_level,
+            scenario_file,
         }
     }
 
@@ -84,6 +87,7 @@ impl Context<ChaChaRng> {
             generate_documentation: self.generate_documentation,
             progress_bar_mode: self.progress_bar_mode,
             log_level: self.log_level.clone(),
+            scenario_file: self.scenario_file.clone(),
         }
     }
 
@@ -115,6 +119,11 @@ impl<RNG: RngCore> Context<RNG> {
         self.log_level.clone()
     }
 
+    /// the YAML file describing the scenario to run, if any
+    pub fn scenario_file(&self) -> Option<&Path> {
+        self.scenario_file.as_deref()
+    }
+
     pub fn generate_new_rest_listen_address(&mut self) -> SocketAddr {
         use std::net::{IpAddr, Ipv4Addr};
 
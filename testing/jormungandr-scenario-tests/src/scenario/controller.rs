@@ -257,9 +257,8 @@ impl Controller {
         wallet_proxy: &WalletProxyController,
     ) -> Result<iapyx::Controller> {
         let settings = RestSettings {
-            use_https_for_post: false,
             enable_debug: true,
-            certificate: None,
+            ..Default::default()
         };
 
         let backend = WalletBackend::new_from_addresses(
@@ -370,7 +369,10 @@ impl Controller {
             .alias(params.get_alias())
             .block0(block0_setting)
             .working_dir(self.working_directory.path())
-            .peristence_mode(params.get_persistence_mode());
+            .peristence_mode(params.get_persistence_mode())
+            .storage(params.get_storage().clone())
+            .env(params.get_env())
+            .resource_limits(params.get_resource_limits().clone());
         let node = spawn_builder.build(version)?;
         Ok(node.controller())
     }
@@ -414,7 +416,10 @@ impl Controller {
             .alias(params.get_alias())
             .block0(block0_setting)
             .working_dir(self.working_directory.path())
-            .peristence_mode(params.get_persistence_mode());
+            .peristence_mode(params.get_persistence_mode())
+            .storage(params.get_storage().clone())
+            .env(params.get_env())
+            .resource_limits(params.get_resource_limits().clone());
         let node = spawn_builder.build()?;
 
         Ok(node.controller())
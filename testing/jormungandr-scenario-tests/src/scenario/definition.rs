@@ -0,0 +1,408 @@
+//! scenarios described in YAML files
+//!
+//! A definition file describes the same network as the `prepare_scenario!`
+//! macro (the topology and the blockchain with its wallets and vote plans)
+//! and the order in which the nodes are spawned, so new scenarios can be
+//! set up without recompiling the test suite:
+//!
+//! ```yaml
+//! title: two_nodes
+//! topology:
+//!   - alias: node1
+//!   - alias: node2
+//!     trusted_peers: [ node1 ]
+//! blockchain:
+//!   consensus: bft
+//!   slots_per_epoch: 10
+//!   slot_duration: 1
+//!   leaders: [ node1 ]
+//!   initials:
+//!     - account: faucet1
+//!       funds: 1000000000
+//!     - account: faucet2
+//!       funds: 2000000000
+//!       delegates_to: node2
+//! spawn:
+//!   - alias: node1
+//!     leadership: leader
+//!   - alias: node2
+//!     legacy: 0.9.0
+//!     wait: true
+//! ```
+
+use crate::{
+    legacy::LegacyNodeController,
+    node::NodeController,
+    scenario::{
+        repository::ScenarioResult, ActiveSlotCoefficient, Blockchain, ConsensusVersion,
+        ContextChaCha, Controller, ControllerBuilder, ErrorKind, KESUpdateSpeed, Milli, Node,
+        NumberOfSlotsPerEpoch, Result, SlotDuration, SpawnParams, TopologyBuilder,
+    },
+};
+use chain_impl_mockchain::testing::{
+    scenario::template::{ProposalDefBuilder, VotePlanDefBuilder},
+    VoteTestGen,
+};
+use jormungandr_testing_utils::{
+    testing::{
+        network_builder::{LeadershipMode, LegacyWalletTemplate, PersistenceMode, WalletTemplate},
+        node::{download_last_n_releases, get_jormungandr_bin},
+    },
+    Version,
+};
+use serde_derive::Deserialize;
+use std::{collections::HashSet, path::Path};
+
+/// number of the latest releases searched for the legacy versions
+const LEGACY_RELEASES_SEARCHED: u32 = 5;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioDefinition {
+    pub title: String,
+    pub topology: Vec<NodeDefinition>,
+    pub blockchain: BlockchainDefinition,
+    /// the nodes to spawn, in order
+    #[serde(default)]
+    pub spawn: Vec<SpawnDefinition>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NodeDefinition {
+    pub alias: String,
+    #[serde(default)]
+    pub trusted_peers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsensusDefinition {
+    Bft,
+    GenesisPraos,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlockchainDefinition {
+    pub consensus: ConsensusDefinition,
+    pub slots_per_epoch: u32,
+    pub slot_duration: u8,
+    #[serde(default)]
+    pub leaders: Vec<String>,
+    pub initials: Vec<InitialWalletDefinition>,
+    #[serde(default)]
+    pub committees: Vec<String>,
+    #[serde(default)]
+    pub legacy: Vec<LegacyWalletDefinition>,
+    #[serde(default)]
+    pub vote_plans: Vec<VotePlanDefinition>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InitialWalletDefinition {
+    pub account: String,
+    pub funds: u64,
+    pub delegates_to: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LegacyWalletDefinition {
+    pub name: String,
+    pub address: String,
+    pub mnemonics: String,
+    pub funds: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VotePlanDefinition {
+    pub name: String,
+    pub owner: String,
+    pub vote_start: u32,
+    pub vote_tally: u32,
+    pub vote_end: u32,
+    pub proposals: Vec<ProposalDefinition>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProposalDefinition {
+    /// `rewards`, `treasury` or any other value for an off chain proposal
+    pub action_target: String,
+    #[serde(default)]
+    pub action_value: u64,
+    pub options: u8,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeadershipDefinition {
+    Leader,
+    Passive,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PersistenceDefinition {
+    InMemory,
+    Persistent,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpawnDefinition {
+    pub alias: String,
+    #[serde(default = "default_leadership")]
+    pub leadership: LeadershipDefinition,
+    #[serde(default = "default_persistence")]
+    pub persistence: PersistenceDefinition,
+    /// spawn a released version of the node instead of the tested one
+    pub legacy: Option<String>,
+    /// wait for the node to bootstrap before spawning the next one
+    #[serde(default)]
+    pub wait: bool,
+}
+
+impl SpawnDefinition {
+    pub fn leadership_mode(&self) -> LeadershipMode {
+        match self.leadership {
+            LeadershipDefinition::Leader => LeadershipMode::Leader,
+            LeadershipDefinition::Passive => LeadershipMode::Passive,
+        }
+    }
+
+    pub fn persistence_mode(&self) -> PersistenceMode {
+        match self.persistence {
+            PersistenceDefinition::InMemory => PersistenceMode::InMemory,
+            PersistenceDefinition::Persistent => PersistenceMode::Persistent,
+        }
+    }
+}
+
+fn default_leadership() -> LeadershipDefinition {
+    LeadershipDefinition::Passive
+}
+
+fn default_persistence() -> PersistenceDefinition {
+    PersistenceDefinition::InMemory
+}
+
+/// the nodes spawned from a definition
+#[derive(Default)]
+pub struct SpawnedNodes {
+    pub nodes: Vec<NodeController>,
+    pub legacy_nodes: Vec<LegacyNodeController>,
+}
+
+impl ScenarioDefinition {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        let definition: ScenarioDefinition = serde_yaml::from_reader(file)?;
+        definition.validate()?;
+        Ok(definition)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let aliases: HashSet<&str> = self.topology.iter().map(|n| n.alias.as_str()).collect();
+        let check_alias = |alias: &str, context: &str| {
+            if aliases.contains(alias) {
+                Ok(())
+            } else {
+                Err(ErrorKind::InvalidScenarioDefinition(format!(
+                    "{} refers to the unknown node '{}'",
+                    context, alias
+                )))
+            }
+        };
+
+        for node in &self.topology {
+            for peer in &node.trusted_peers {
+                check_alias(peer, &format!("node '{}'", node.alias))?;
+            }
+        }
+        for wallet in &self.blockchain.initials {
+            if let Some(delegate) = &wallet.delegates_to {
+                check_alias(delegate, &format!("wallet '{}'", wallet.account))?;
+            }
+        }
+        for spawn in &self.spawn {
+            check_alias(&spawn.alias, "spawn")?;
+            if let Some(version) = &spawn.legacy {
+                Version::parse(version).map_err(|_| {
+                    ErrorKind::InvalidScenarioDefinition(format!(
+                        "invalid legacy version '{}' of node '{}'",
+                        version, spawn.alias
+                    ))
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// prepare the controller of the scenario, as the `prepare_scenario!`
+    /// macro does
+    pub fn controller_builder(&self, context: &mut ContextChaCha) -> ControllerBuilder {
+        let mut builder = ControllerBuilder::new(&self.title);
+
+        let mut topology_builder = TopologyBuilder::new();
+        for node_definition in &self.topology {
+            let mut node = Node::new(node_definition.alias.as_str());
+            for peer in &node_definition.trusted_peers {
+                node.add_trusted_peer(peer.as_str());
+            }
+            topology_builder.register_node(node);
+        }
+        builder.set_topology(topology_builder.build());
+
+        let definition = &self.blockchain;
+        let consensus = match definition.consensus {
+            ConsensusDefinition::Bft => ConsensusVersion::Bft,
+            ConsensusDefinition::GenesisPraos => ConsensusVersion::GenesisPraos,
+        };
+        let mut blockchain = Blockchain::new(
+            consensus,
+            NumberOfSlotsPerEpoch::new(definition.slots_per_epoch)
+                .expect("valid number of slots per epoch"),
+            SlotDuration::new(definition.slot_duration).expect("valid slot duration in seconds"),
+            KESUpdateSpeed::new(46800).expect("valid kes update speed in seconds"),
+            ActiveSlotCoefficient::new(Milli::from_millis(700))
+                .expect("active slot coefficient in millis"),
+        );
+
+        for leader in &definition.leaders {
+            blockchain.add_leader(leader.as_str());
+        }
+
+        for initial in &definition.initials {
+            let mut wallet =
+                WalletTemplate::new_account(initial.account.as_str(), initial.funds.into());
+            *wallet.delegate_mut() = initial.delegates_to.clone();
+            blockchain.add_wallet(wallet);
+        }
+
+        for committee in &definition.committees {
+            blockchain.add_committee(committee.as_str());
+        }
+
+        for legacy in &definition.legacy {
+            blockchain.add_legacy_wallet(LegacyWalletTemplate::new(
+                legacy.name.as_str(),
+                legacy.funds.into(),
+                legacy.address.clone(),
+                legacy.mnemonics.clone(),
+            ));
+        }
+
+        for vote_plan in &definition.vote_plans {
+            let mut vote_plan_builder = VotePlanDefBuilder::new(&vote_plan.name);
+            vote_plan_builder.owner(&vote_plan.owner);
+            vote_plan_builder.vote_phases(
+                vote_plan.vote_start,
+                vote_plan.vote_tally,
+                vote_plan.vote_end,
+            );
+
+            for proposal in &vote_plan.proposals {
+                let mut proposal_builder =
+                    ProposalDefBuilder::new(VoteTestGen::external_proposal_id());
+                proposal_builder.options(proposal.options.into());
+                match proposal.action_target.as_str() {
+                    "rewards" => proposal_builder.action_rewards_add(proposal.action_value),
+                    "treasury" => {
+                        proposal_builder.action_transfer_to_rewards(proposal.action_value)
+                    }
+                    _ => proposal_builder.action_off_chain(),
+                };
+                vote_plan_builder.with_proposal(&mut proposal_builder);
+            }
+
+            blockchain.add_vote_plan(vote_plan_builder.build());
+        }
+
+        builder.set_blockchain(blockchain);
+        builder.build_settings(context);
+        builder
+    }
+
+    /// spawn the nodes in the order of the definition
+    pub fn spawn(&self, controller: &mut Controller) -> Result<SpawnedNodes> {
+        let mut spawned = SpawnedNodes::default();
+        let releases = if self.spawn.iter().any(|spawn| spawn.legacy.is_some()) {
+            download_last_n_releases(LEGACY_RELEASES_SEARCHED)
+        } else {
+            Vec::new()
+        };
+
+        for spawn in &self.spawn {
+            let mut params = SpawnParams::new(&spawn.alias);
+            params
+                .leadership_mode(spawn.leadership_mode())
+                .persistence_mode(spawn.persistence_mode());
+
+            match &spawn.legacy {
+                Some(version) => {
+                    let version = Version::parse(version).expect("version checked on load");
+                    let release = releases
+                        .iter()
+                        .find(|release| release.version() == version)
+                        .ok_or_else(|| {
+                            ErrorKind::InvalidScenarioDefinition(format!(
+                                "release {} of node '{}' not found in the last {} releases",
+                                version, spawn.alias, LEGACY_RELEASES_SEARCHED
+                            ))
+                        })?;
+                    let jormungandr = get_jormungandr_bin(release, controller.working_directory());
+                    params.jormungandr(jormungandr);
+                    let node = controller.spawn_legacy_node(&mut params, &release.version())?;
+                    if spawn.wait {
+                        node.wait_for_bootstrap()?;
+                    }
+                    spawned.legacy_nodes.push(node);
+                }
+                None => {
+                    let node = controller.spawn_node_custom(&mut params)?;
+                    if spawn.wait {
+                        node.wait_for_bootstrap()?;
+                    }
+                    spawned.nodes.push(node);
+                }
+            }
+        }
+
+        Ok(spawned)
+    }
+}
+
+/// run the scenario described by the file given with `--scenario-file`:
+/// the nodes are spawned in order and the scenario passes when they all
+/// bootstrap.
+pub fn from_file(mut context: ContextChaCha) -> crate::test::Result<ScenarioResult> {
+    let path = match context.scenario_file() {
+        Some(path) => path.to_path_buf(),
+        None => return Ok(ScenarioResult::ignored("from_file")),
+    };
+    let definition = ScenarioDefinition::load(path)?;
+    let mut controller = definition.controller_builder(&mut context).build(context)?;
+
+    let spawned = definition.spawn(&mut controller)?;
+    controller.monitor_nodes();
+
+    for node in &spawned.nodes {
+        node.wait_for_bootstrap()?;
+    }
+    for node in &spawned.legacy_nodes {
+        node.wait_for_bootstrap()?;
+    }
+    for node in spawned.nodes {
+        node.shutdown()?;
+    }
+    for node in spawned.legacy_nodes {
+        node.shutdown()?;
+    }
+
+    controller.finalize();
+    Ok(ScenarioResult::passed(definition.title))
+}
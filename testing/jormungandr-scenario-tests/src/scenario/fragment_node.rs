@@ -1,7 +1,7 @@
 use crate::node::NodeController;
 use chain_impl_mockchain::fragment::{Fragment, FragmentId};
 use jormungandr_lib::crypto::hash::Hash;
-use jormungandr_lib::interfaces::{BlockDate, FragmentLog};
+use jormungandr_lib::interfaces::{BlockDate, FragmentDetails, FragmentLog};
 use jormungandr_testing_utils::testing::{FragmentNode, FragmentNodeError, MemPoolCheck};
 use std::collections::HashMap;
 
@@ -14,6 +14,16 @@ impl FragmentNode for NodeController {
         self.fragment_logs()
             .map_err(|_| FragmentNodeError::UnknownError)
     }
+    fn fragment_details(
+        &self,
+        fragment_id: &FragmentId,
+    ) -> Result<Option<FragmentDetails>, FragmentNodeError> {
+        self.fragment_details(fragment_id)
+            .map_err(|e| FragmentNodeError::FragmentDetailsError {
+                reason: e.to_string(),
+                fragment_id: *fragment_id,
+            })
+    }
     fn send_fragment(&self, fragment: Fragment) -> Result<MemPoolCheck, FragmentNodeError> {
         //TODO: implement conversion
         self.send_fragment(fragment)
@@ -1,5 +1,6 @@
 mod context;
 mod controller;
+pub mod definition;
 mod fragment_node;
 pub mod repository;
 pub mod settings;
@@ -33,6 +34,7 @@ error_chain! {
         VitStationControllerError(crate::VitStationControllerError);
         IapyxWalletError(iapyx::ControllerError);
         WalletProxyError(crate::WalletProxyError);
+        Yaml(serde_yaml::Error);
     }
 
     errors {
@@ -52,6 +54,10 @@ error_chain! {
             description("Vote plan was not found"),
             display("Vote plan '{}' was not found", name)
         }
+        InvalidScenarioDefinition(reason: String) {
+            description("Invalid scenario definition"),
+            display("Invalid scenario definition: {}", reason)
+        }
     }
 }
 
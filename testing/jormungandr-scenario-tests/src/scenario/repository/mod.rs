@@ -17,7 +17,8 @@ use crate::{
         features::{
             explorer::passive_node_explorer, leader_promotion::*,
             leadership_log::leader_restart_preserves_leadership_log, p2p::*,
-            stake_pool::retire::retire_stake_pool_explorer, vote::vote_e2e_flow,
+            rest_snapshot::rest_api_snapshot, stake_pool::retire::retire_stake_pool_explorer,
+            vote::vote_e2e_flow,
         },
         legacy,
         network::{
@@ -176,6 +177,12 @@ fn scenarios_repository() -> Vec<Scenario> {
 
     repository.push(Scenario::new("example", scenario_2, vec![Tag::Example]));
 
+    repository.push(Scenario::new(
+        "from_file",
+        crate::scenario::definition::from_file,
+        vec![Tag::Example],
+    ));
+
     repository.push(Scenario::new(
         "leader_restart",
         leader_restart,
@@ -366,6 +373,12 @@ fn scenarios_repository() -> Vec<Scenario> {
         vec![Tag::Short, Tag::Unstable],
     ));
 
+    repository.push(Scenario::new(
+        "rest_api_snapshot",
+        rest_api_snapshot,
+        vec![Tag::Short],
+    ));
+
     repository.push(Scenario::new(
         "passive_node_explorer",
         passive_node_explorer,
@@ -279,11 +279,22 @@ impl Prepare for NodeConfig {
             rest: Rest::prepare(context),
             p2p: P2p::prepare(context),
             storage: None,
+            genesis_block_hash: None,
+            namespace_by_genesis: false,
             log: None,
+            tracing: None,
+            leadership: None,
             mempool: Some(Mempool::prepare(context)),
             explorer: Explorer::prepare(context),
             bootstrap_from_trusted_peers: None,
             skip_bootstrap: None,
+            http_fetch_block0_service: Vec::new(),
+            no_blockchain_updates_warning_interval: None,
+            backup: None,
+            rewards_history: None,
+            vote_plan_stats: None,
+            epoch_hooks: None,
+            clock_check: None,
         }
     }
 }
@@ -297,6 +308,11 @@ impl Prepare for Rest {
             listen: context.generate_new_rest_listen_address(),
             tls: None,
             cors: None,
+            admin_token: None,
+            reuse_port: false,
+            unix_socket: None,
+            virtual_host: None,
+            max_addresses_per_request: None,
         }
     }
 }
@@ -329,12 +345,32 @@ impl Prepare for P2p {
             trusted_peers: Vec::new(),
             allow_private_addresses: true,
             listen_address: None,
+            listen: Vec::new(),
             max_connections: None,
             max_inbound_connections: None,
+            max_connections_threshold: None,
             topics_of_interest: Some(TopicsOfInterest::prepare(context)),
             policy: Some(Policy::prepare(context)),
             layers: None,
             public_id: None,
+            reuse_port: false,
+            max_server_requests: None,
+            max_client_requests: None,
+            method_limits: None,
+            reply_timeout: None,
+            block_cache_size: None,
+            max_unreachable_nodes_to_connect_per_event: None,
+            gossip_interval: None,
+            gossip_fanout: None,
+            topology_force_reset_interval: None,
+            max_bootstrap_attempts: None,
+            stream_limits: None,
+            handshake_nonces: None,
+            overflow_policies: None,
+            block_fetch_strategy: None,
+            mempool_warmup: false,
+            port_mapping: None,
+            client_auth: None,
         }
     }
 }
@@ -358,6 +394,8 @@ impl Prepare for Policy {
     {
         Policy {
             quarantine_duration: Some(Duration::new(30, 0)),
+            max_quarantine: None,
+            max_num_quarantine_records: None,
             quarantine_whitelist: None,
         }
     }
@@ -103,6 +103,8 @@ pub fn leader_restart(mut context: Context<ChaChaRng>) -> Result<ScenarioResult>
 
     let policy = Policy {
         quarantine_duration: Some(Duration::new(5, 0).into()),
+        max_quarantine: None,
+        max_num_quarantine_records: None,
         quarantine_whitelist: None,
     };
 
@@ -2,5 +2,6 @@ pub mod explorer;
 pub mod leader_promotion;
 pub mod leadership_log;
 pub mod p2p;
+pub mod rest_snapshot;
 pub mod stake_pool;
 pub mod vote;
@@ -0,0 +1,406 @@
+//! compatibility snapshots of the REST API
+//!
+//! The responses of the REST endpoints of a fresh network are compared with
+//! the golden files in `resources/rest_snapshots`, which hold the status code
+//! and the JSON body of each response as recorded from a running node. The
+//! fields whose values change from one run to another (hashes, ids, times,
+//! counters) are replaced with `"<volatile>"` before the comparison, so that
+//! only their presence is checked. The comparison is done on the JSON values,
+//! not on the text, and every difference is reported with its path in the
+//! response.
+//!
+//! Every `GET` endpoint is covered, except the ones serving binary or CSV
+//! data (`block`, `rewards/history.csv`) and the ones looking up an item the
+//! fresh network does not have (`utxo`, `fragment/{id}`, `fragments/statuses`,
+//! the vote plan statistics and archive). The endpoints changing the state of
+//! the node are not called.
+//!
+//! The golden files missing are recorded from the responses, unless the
+//! `CI` environment variable is set. Run the scenario with the
+//! `UPDATE_REST_SNAPSHOTS` environment variable set to record all of them
+//! again after a deliberate change of the API.
+
+use crate::{
+    node::{LeadershipMode, NodeController, PersistenceMode},
+    test::{utils, ErrorKind, Result},
+    Context, ScenarioResult,
+};
+use function_name::named;
+use rand_chacha::ChaChaRng;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+const LEADER1: &str = "LEADER1";
+
+const DELEGATED_ACCOUNT: &str = "delegated1";
+
+const VOLATILE: &str = "<volatile>";
+
+const UPDATE_SNAPSHOTS_VAR: &str = "UPDATE_REST_SNAPSHOTS";
+
+/// set on the CI, where the golden files are never recorded
+const CI_VAR: &str = "CI";
+
+/// an endpoint of the snapshot and the paths of its volatile fields in the
+/// body of the response, `*` standing for any field or array index and the
+/// empty path for the whole body. In the path of the endpoint, relative to
+/// `/api`, `{account}` stands for the id of the delegated account and
+/// `{stake_pool}` for the id of the stake pool of the leader.
+struct Snapshot {
+    name: &'static str,
+    path: &'static str,
+    volatile: &'static [&'static str],
+}
+
+const SNAPSHOTS: &[Snapshot] = &[
+    Snapshot {
+        name: "account",
+        path: "v0/account/{account}",
+        volatile: &["/delegation"],
+    },
+    Snapshot {
+        name: "diagnostic",
+        path: "v0/diagnostic",
+        volatile: &["/*"],
+    },
+    Snapshot {
+        name: "fragment_logs",
+        path: "v0/fragment/logs",
+        volatile: &[],
+    },
+    Snapshot {
+        name: "fragments_logs_v1",
+        path: "v1/fragments/logs",
+        volatile: &[],
+    },
+    Snapshot {
+        name: "leaders",
+        path: "v0/leaders",
+        volatile: &[],
+    },
+    Snapshot {
+        name: "leaders_logs",
+        path: "v0/leaders/logs",
+        volatile: &["/*/*"],
+    },
+    Snapshot {
+        name: "leaders_logs_epoch",
+        path: "v0/leaders/logs/0",
+        volatile: &["/*/*"],
+    },
+    Snapshot {
+        name: "ledger_accounting",
+        path: "v0/ledger/accounting/0",
+        volatile: &["/*"],
+    },
+    Snapshot {
+        name: "network_stats",
+        path: "v0/network/stats",
+        volatile: &[],
+    },
+    Snapshot {
+        name: "network_stats_gossip",
+        path: "v0/network/stats/gossip",
+        volatile: &[],
+    },
+    Snapshot {
+        name: "network_p2p_quarantined",
+        path: "v0/network/p2p/quarantined",
+        volatile: &[],
+    },
+    Snapshot {
+        name: "network_p2p_non_public",
+        path: "v0/network/p2p/non_public",
+        volatile: &[],
+    },
+    Snapshot {
+        name: "network_p2p_available",
+        path: "v0/network/p2p/available",
+        volatile: &[],
+    },
+    Snapshot {
+        name: "network_p2p_view",
+        path: "v0/network/p2p/view",
+        volatile: &[],
+    },
+    Snapshot {
+        name: "network_p2p_self",
+        path: "v0/network/p2p/self",
+        volatile: &["/nodeId", "/address", "/topicsOfInterest/*"],
+    },
+    Snapshot {
+        name: "node_stats",
+        path: "v0/node/stats",
+        volatile: &[
+            "/version",
+            "/uptime",
+            "/blockRecvCnt",
+            "/lastBlockContentSize",
+            "/lastBlockDate",
+            "/lastBlockFees",
+            "/lastBlockHash",
+            "/lastBlockHeight",
+            "/lastBlockSum",
+            "/lastBlockTime",
+            "/lastBlockTx",
+            "/lastReceivedBlockTime",
+            "/mempoolUsage",
+            "/serverRequestsInFlight",
+            "/clientRequestsInFlight",
+            "/syncState",
+            "/blockPropagation",
+            "/mempool",
+            "/portMapping",
+            "/ntpClockSkew",
+            "/leaderWakeDelay",
+        ],
+    },
+    Snapshot {
+        name: "rewards_epoch",
+        path: "v0/rewards/epoch/0",
+        volatile: &["/*"],
+    },
+    Snapshot {
+        name: "rewards_history",
+        path: "v0/rewards/history/1",
+        volatile: &["/*/*"],
+    },
+    Snapshot {
+        name: "settings",
+        path: "v0/settings",
+        volatile: &[
+            "/block0Hash",
+            "/block0Time",
+            "/currSlotStartTime",
+            "/settingsVersion",
+            "/treasuryTax",
+            "/rewardParams/*",
+        ],
+    },
+    Snapshot {
+        name: "stake",
+        path: "v0/stake",
+        volatile: &["/epoch", "/stake/unassigned", "/stake/pools/*"],
+    },
+    Snapshot {
+        name: "stake_epoch",
+        path: "v0/stake/0",
+        volatile: &["/epoch", "/stake/unassigned", "/stake/pools/*"],
+    },
+    Snapshot {
+        name: "stake_epoch_snapshot",
+        path: "v0/stake/0/snapshot",
+        volatile: &["/*"],
+    },
+    Snapshot {
+        name: "stake_pools",
+        path: "v0/stake_pools",
+        volatile: &["/*"],
+    },
+    Snapshot {
+        name: "stake_pool",
+        path: "v0/stake_pool/{stake_pool}",
+        volatile: &["/*"],
+    },
+    Snapshot {
+        name: "tip",
+        path: "v0/tip",
+        volatile: &[""],
+    },
+    Snapshot {
+        name: "vote_active_committees",
+        path: "v0/vote/active/committees",
+        volatile: &["/*"],
+    },
+    Snapshot {
+        name: "vote_active_plans",
+        path: "v0/vote/active/plans",
+        volatile: &[],
+    },
+];
+
+#[named]
+pub fn rest_api_snapshot(mut context: Context<ChaChaRng>) -> Result<ScenarioResult> {
+    let name = function_name!();
+    let scenario_settings = prepare_scenario! {
+        name,
+        &mut context,
+        topology [
+            LEADER1,
+        ]
+        blockchain {
+            consensus = GenesisPraos,
+            number_of_slots_per_epoch = 60,
+            slot_duration = 2,
+            leaders = [ LEADER1 ],
+            initials = [
+                account "delegated1" with 2_000_000_000 delegates to LEADER1,
+                account "unassigned1" with 1_000_000_000,
+            ],
+        }
+    };
+
+    let mut controller = scenario_settings.build(context)?;
+
+    let leader1 =
+        controller.spawn_node(LEADER1, LeadershipMode::Leader, PersistenceMode::Persistent)?;
+    leader1.wait_for_bootstrap()?;
+    utils::wait(10);
+
+    let account = hex::encode(
+        controller
+            .wallet(DELEGATED_ACCOUNT)?
+            .identifier()
+            .as_ref()
+            .as_ref(),
+    );
+    let stake_pool = match leader1.rest_response("v0/stake_pools")?.1 {
+        Value::Array(ids) if !ids.is_empty() => ids[0].as_str().unwrap_or_default().to_owned(),
+        _ => String::new(),
+    };
+
+    let update = std::env::var_os(UPDATE_SNAPSHOTS_VAR).is_some();
+    let record_missing = std::env::var_os(CI_VAR).is_none();
+    let mut differences = Vec::new();
+    let mut recorded = Vec::new();
+    for snapshot in SNAPSHOTS {
+        let path = snapshot
+            .path
+            .replace("{account}", &account)
+            .replace("{stake_pool}", &stake_pool);
+        let response = snapshot_response(&leader1, &path, snapshot.volatile)?;
+
+        let golden_path = golden_file(snapshot.name);
+        if update || (record_missing && !golden_path.exists()) {
+            let text = serde_json::to_string_pretty(&response).expect("JSON values serialize");
+            std::fs::write(&golden_path, text + "\n")?;
+            recorded.push(golden_path.display().to_string());
+            continue;
+        }
+
+        let golden: Value = match std::fs::read(&golden_path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        {
+            Some(golden) => golden,
+            None => {
+                differences.push(format!(
+                    "{}: no valid golden file at {}",
+                    snapshot.path,
+                    golden_path.display()
+                ));
+                continue;
+            }
+        };
+        let mut snapshot_differences = Vec::new();
+        diff("", &golden, &response, &mut snapshot_differences);
+        differences.extend(
+            snapshot_differences
+                .into_iter()
+                .map(|difference| format!("{}: {}", snapshot.path, difference)),
+        );
+    }
+
+    leader1.shutdown()?;
+    controller.finalize();
+
+    if !recorded.is_empty() {
+        println!(
+            "recorded the golden files of the REST snapshot, to be committed:\n{}",
+            recorded.join("\n")
+        );
+    }
+    if !differences.is_empty() {
+        return Err(ErrorKind::AssertionFailed(format!(
+            "the REST responses differ from the snapshots, set {} to update them:\n{}",
+            UPDATE_SNAPSHOTS_VAR,
+            differences.join("\n")
+        ))
+        .into());
+    }
+    Ok(ScenarioResult::passed(name))
+}
+
+/// the status code and the body of the response, the volatile fields of
+/// the body being masked. A body which is not JSON is kept as a string.
+fn snapshot_response(node: &NodeController, path: &str, volatile: &[&str]) -> Result<Value> {
+    let (status, mut body) = node.rest_response(path)?;
+    for volatile in volatile {
+        mask_volatile(&mut body, &volatile.split('/').skip(1).collect::<Vec<_>>());
+    }
+    Ok(json!({ "status": status, "body": body }))
+}
+
+fn golden_file(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("resources")
+        .join("rest_snapshots")
+        .join(format!("{}.json", name))
+}
+
+/// replace the values at the given path with the volatile placeholder
+fn mask_volatile(value: &mut Value, path: &[&str]) {
+    let (segment, rest) = match path.split_first() {
+        Some(split) => split,
+        None => {
+            *value = Value::String(VOLATILE.to_owned());
+            return;
+        }
+    };
+    match value {
+        Value::Object(object) => {
+            for (key, field) in object.iter_mut() {
+                if *segment == "*" || key.as_str() == *segment {
+                    mask_volatile(field, rest);
+                }
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                if *segment == "*" || index.to_string() == *segment {
+                    mask_volatile(item, rest);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// the differences between the golden value and the response, with their
+/// paths
+fn diff(path: &str, golden: &Value, response: &Value, differences: &mut Vec<String>) {
+    match (golden, response) {
+        (Value::Object(golden), Value::Object(response)) => {
+            for (key, golden_field) in golden {
+                let field_path = format!("{}/{}", path, key);
+                match response.get(key) {
+                    Some(field) => diff(&field_path, golden_field, field, differences),
+                    None => differences.push(format!("{} is missing", field_path)),
+                }
+            }
+            for key in response.keys().filter(|key| !golden.contains_key(*key)) {
+                differences.push(format!("{}/{} is unexpected", path, key));
+            }
+        }
+        (Value::Array(golden), Value::Array(response)) if golden.len() == response.len() => {
+            for (index, (golden, response)) in golden.iter().zip(response).enumerate() {
+                diff(
+                    &format!("{}/{}", path, index),
+                    golden,
+                    response,
+                    differences,
+                );
+            }
+        }
+        (Value::Array(golden), Value::Array(response)) => differences.push(format!(
+            "{} has {} items instead of {}",
+            path,
+            response.len(),
+            golden.len()
+        )),
+        (golden, response) if golden != response => {
+            differences.push(format!("{} is {} instead of {}", path, response, golden))
+        }
+        _ => {}
+    }
+}
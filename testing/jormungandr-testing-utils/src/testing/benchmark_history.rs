@@ -0,0 +1,287 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+use sysinfo::{System, SystemExt};
+use thiserror::Error;
+
+/// environment variable overriding the revision the results are recorded for,
+/// used on CI where the sources are not always a git checkout
+pub const REVISION_ENV_VAR: &str = "JORMUNGANDR_BENCHMARK_REVISION";
+/// environment variable pointing to the history file the benchmark runs are
+/// recorded in, nothing is recorded when it is not set
+pub const HISTORY_ENV_VAR: &str = "JORMUNGANDR_BENCHMARK_HISTORY";
+/// environment variable overriding the regression, in percents, failing a run
+pub const THRESHOLD_ENV_VAR: &str = "JORMUNGANDR_BENCHMARK_THRESHOLD";
+pub const DEFAULT_THRESHOLD: f64 = 10.0;
+
+#[derive(Debug, Error)]
+pub enum BenchmarkHistoryError {
+    #[error("cannot read or write benchmark history")]
+    Io(#[from] std::io::Error),
+    #[error("malformed benchmark history")]
+    Serialization(#[from] serde_json::Error),
+    #[error("benchmark '{name}' regressed by {change:.2}% (threshold {threshold:.2}%): {previous} {unit} at {previous_revision} -> {current} {unit} at {current_revision}")]
+    Regression {
+        name: String,
+        unit: String,
+        previous: f64,
+        current: f64,
+        previous_revision: String,
+        current_revision: String,
+        change: f64,
+        threshold: f64,
+    },
+}
+
+/// which way a benchmark value gets better
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Trend {
+    LowerIsBetter,
+    HigherIsBetter,
+}
+
+/// characteristics of the machine the benchmark was run on, the results
+/// are only comparable between the runs on the same profile
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MachineProfile {
+    pub os: String,
+    pub cpus: usize,
+    pub total_memory_kb: u64,
+}
+
+impl MachineProfile {
+    pub fn current() -> Self {
+        let system = System::new_all();
+        let os = os_info::get();
+        Self {
+            os: format!("{} {}", os.os_type(), os.version()),
+            cpus: system.get_processors().len(),
+            total_memory_kb: system.get_total_memory(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkRecord {
+    pub name: String,
+    pub revision: String,
+    pub machine: MachineProfile,
+    pub value: f64,
+    pub unit: String,
+    pub trend: Trend,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl BenchmarkRecord {
+    pub fn new<S: Into<String>, U: Into<String>>(
+        name: S,
+        value: f64,
+        unit: U,
+        trend: Trend,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            revision: current_revision(),
+            machine: MachineProfile::current(),
+            value,
+            unit: unit.into(),
+            trend,
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// record of a speed benchmark, the shorter the better
+    pub fn duration<S: Into<String>>(name: S, duration: Duration) -> Self {
+        Self::new(
+            name,
+            duration.as_millis() as f64,
+            "ms",
+            Trend::LowerIsBetter,
+        )
+    }
+
+    pub fn revision<S: Into<String>>(mut self, revision: S) -> Self {
+        self.revision = revision.into();
+        self
+    }
+
+    /// change relative to the previous value in percents, positive when
+    /// the value got worse
+    pub fn regression_against(&self, previous: &BenchmarkRecord) -> f64 {
+        if previous.value == 0.0 {
+            return 0.0;
+        }
+        let change = (self.value - previous.value) / previous.value * 100.0;
+        match self.trend {
+            Trend::LowerIsBetter => change,
+            Trend::HigherIsBetter => -change,
+        }
+    }
+}
+
+/// Results of the benchmarks over time, stored in a JSON file and keyed by
+/// the git revision and the machine profile.
+#[derive(Debug)]
+pub struct BenchmarkHistory {
+    path: PathBuf,
+    records: Vec<BenchmarkRecord>,
+}
+
+impl BenchmarkHistory {
+    /// load the history from the file, starting an empty one if the file
+    /// does not exist yet
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, BenchmarkHistoryError> {
+        let path = path.as_ref().to_path_buf();
+        let records = if path.exists() {
+            serde_json::from_reader(File::open(&path)?)?
+        } else {
+            Vec::new()
+        };
+        Ok(Self { path, records })
+    }
+
+    pub fn save(&self) -> Result<(), BenchmarkHistoryError> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        serde_json::to_writer_pretty(File::create(&self.path)?, &self.records)?;
+        Ok(())
+    }
+
+    pub fn records(&self) -> &[BenchmarkRecord] {
+        &self.records
+    }
+
+    /// the last result of the benchmark on the same machine profile, from
+    /// a revision other than the one of the record
+    pub fn previous(&self, record: &BenchmarkRecord) -> Option<&BenchmarkRecord> {
+        self.records
+            .iter()
+            .filter(|x| {
+                x.name == record.name
+                    && x.machine == record.machine
+                    && x.revision != record.revision
+            })
+            .max_by_key(|x| x.timestamp)
+    }
+
+    /// compare the record against the previous run, failing when it regressed
+    /// by more than the threshold (in percents)
+    pub fn compare(
+        &self,
+        record: &BenchmarkRecord,
+        threshold: f64,
+    ) -> Result<Option<f64>, BenchmarkHistoryError> {
+        let previous = match self.previous(record) {
+            Some(previous) => previous,
+            None => return Ok(None),
+        };
+        let change = record.regression_against(previous);
+        if change > threshold {
+            return Err(BenchmarkHistoryError::Regression {
+                name: record.name.clone(),
+                unit: record.unit.clone(),
+                previous: previous.value,
+                current: record.value,
+                previous_revision: previous.revision.clone(),
+                current_revision: record.revision.clone(),
+                change,
+                threshold,
+            });
+        }
+        Ok(Some(change))
+    }
+
+    pub fn push(&mut self, record: BenchmarkRecord) {
+        self.records.push(record);
+    }
+
+    /// compare the record against the previous run, then store it in the
+    /// history file regardless of the outcome
+    pub fn record(
+        &mut self,
+        record: BenchmarkRecord,
+        threshold: f64,
+    ) -> Result<Option<f64>, BenchmarkHistoryError> {
+        let comparison = self.compare(&record, threshold);
+        self.push(record);
+        self.save()?;
+        comparison
+    }
+}
+
+/// record the result of a run in the history file given by the environment,
+/// failing when it regressed against the previous run on the same machine
+pub fn record_benchmark(record: BenchmarkRecord) -> Result<Option<f64>, BenchmarkHistoryError> {
+    let path = match std::env::var_os(HISTORY_ENV_VAR) {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    let threshold = std::env::var(THRESHOLD_ENV_VAR)
+        .ok()
+        .and_then(|threshold| threshold.parse().ok())
+        .unwrap_or(DEFAULT_THRESHOLD);
+    BenchmarkHistory::load(path)?.record(record, threshold)
+}
+
+/// revision of the sources under test, taken from the environment or from git
+pub fn current_revision() -> String {
+    if let Ok(revision) = std::env::var(REVISION_ENV_VAR) {
+        return revision;
+    }
+    Command::new("git")
+        .args(&["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::TempDir;
+
+    fn run(revision: &str, millis: u64) -> BenchmarkRecord {
+        BenchmarkRecord::duration("bootstrap", Duration::from_millis(millis)).revision(revision)
+    }
+
+    #[test]
+    fn records_are_compared_against_the_previous_revision() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("history.json");
+
+        let mut history = BenchmarkHistory::load(&path).unwrap();
+        assert_eq!(history.record(run("a", 100), 10.0).unwrap(), None);
+        assert_eq!(history.record(run("b", 105), 10.0).unwrap(), Some(5.0));
+
+        let history = BenchmarkHistory::load(&path).unwrap();
+        assert_eq!(history.records().len(), 2);
+    }
+
+    #[test]
+    fn regressions_over_the_threshold_fail_but_are_recorded() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("history.json");
+
+        let mut history = BenchmarkHistory::load(&path).unwrap();
+        history.record(run("a", 100), 10.0).unwrap();
+        assert!(matches!(
+            history.record(run("b", 150), 10.0),
+            Err(BenchmarkHistoryError::Regression { .. })
+        ));
+        assert_eq!(BenchmarkHistory::load(&path).unwrap().records().len(), 2);
+    }
+
+    #[test]
+    fn improvements_are_negative_changes() {
+        let mut faster = run("b", 50);
+        assert_eq!(faster.regression_against(&run("a", 100)), -50.0);
+        faster.trend = Trend::HigherIsBetter;
+        assert_eq!(faster.regression_against(&run("a", 100)), 50.0);
+    }
+}
@@ -9,18 +9,23 @@ use crate::{
 };
 use chain_core::property::Fragment as _;
 use chain_impl_mockchain::{
-    certificate::{Certificate, PoolId},
+    certificate::{Certificate, PoolId, VoteCast, VotePlan},
     fee::LinearFee,
     fragment::Fragment,
-    testing::{build_owner_stake_full_delegation, FaultTolerantTxCertBuilder, TestGen},
+    testing::{
+        build_owner_stake_full_delegation, scenario::FragmentFactory, FaultTolerantTxCertBuilder,
+        TestGen,
+    },
     transaction::{Input, Output, TransactionSignDataHash, TxBuilder, Witness},
+    vote::{encrypt_vote, Choice, Payload},
 };
 use chain_impl_mockchain::{fee::FeeAlgorithm, ledger::OutputAddress, value::Value};
+use chain_vote::EncryptingVoteKey;
 use jormungandr_lib::{
     crypto::hash::Hash,
     interfaces::{BlockDate, FragmentStatus},
 };
-use rand::{thread_rng, Rng};
+use rand::{rngs::OsRng, thread_rng, Rng};
 use std::{path::PathBuf, time::Duration};
 
 /// Send malformed transactions
@@ -190,6 +195,104 @@ impl<'a> AdversaryFragmentSender<'a> {
         }
     }
 
+    /// send a public vote for an option past the ones of the proposal
+    pub fn send_vote_cast_with_choice_out_of_range<
+        A: FragmentNode + SyncNode + Sized + Sync + Send,
+    >(
+        &self,
+        from: &Wallet,
+        vote_plan: &VotePlan,
+        proposal_index: u8,
+        via: &A,
+    ) -> Result<MemPoolCheck, AdversaryFragmentSenderError> {
+        let fragment =
+            self.faulty_vote_cast_builder()
+                .choice_out_of_range(from, vote_plan, proposal_index);
+        self.dump_fragment_if_enabled(from, &fragment, via)?;
+        self.send_fragment(fragment, via)
+    }
+
+    /// send a public vote for a proposal past the ones of the vote plan
+    pub fn send_vote_cast_with_proposal_out_of_range<
+        A: FragmentNode + SyncNode + Sized + Sync + Send,
+    >(
+        &self,
+        from: &Wallet,
+        vote_plan: &VotePlan,
+        via: &A,
+    ) -> Result<MemPoolCheck, AdversaryFragmentSenderError> {
+        let fragment = self
+            .faulty_vote_cast_builder()
+            .proposal_out_of_range(from, vote_plan);
+        self.dump_fragment_if_enabled(from, &fragment, via)?;
+        self.send_fragment(fragment, via)
+    }
+
+    /// send a public vote for a proposal of a private vote plan
+    pub fn send_public_vote_cast_for_private_plan<
+        A: FragmentNode + SyncNode + Sized + Sync + Send,
+    >(
+        &self,
+        from: &Wallet,
+        vote_plan: &VotePlan,
+        proposal_index: u8,
+        via: &A,
+    ) -> Result<MemPoolCheck, AdversaryFragmentSenderError> {
+        let fragment = self
+            .faulty_vote_cast_builder()
+            .public_vote_for_private_plan(from, vote_plan, proposal_index);
+        self.dump_fragment_if_enabled(from, &fragment, via)?;
+        self.send_fragment(fragment, via)
+    }
+
+    /// send an encrypted vote along with the proof of another encryption
+    /// of the same vote, which does not verify
+    pub fn send_vote_cast_with_bad_proof<A: FragmentNode + SyncNode + Sized + Sync + Send>(
+        &self,
+        from: &Wallet,
+        vote_plan: &VotePlan,
+        proposal_index: u8,
+        choice: &Choice,
+        encrypting_vote_key: &EncryptingVoteKey,
+        via: &A,
+    ) -> Result<MemPoolCheck, AdversaryFragmentSenderError> {
+        let fragment = self.faulty_vote_cast_builder().bad_proof(
+            from,
+            vote_plan,
+            proposal_index,
+            choice,
+            encrypting_vote_key,
+        );
+        self.dump_fragment_if_enabled(from, &fragment, via)?;
+        self.send_fragment(fragment, via)
+    }
+
+    /// send a well formed public vote, for the scenarios where the node
+    /// rejects it because of the state of the vote plan, e.g. before the
+    /// start or after the end of its voting period; the caller is
+    /// responsible for the timing
+    pub fn send_well_formed_vote_cast<A: FragmentNode + SyncNode + Sized + Sync + Send>(
+        &self,
+        from: &Wallet,
+        vote_plan: &VotePlan,
+        proposal_index: u8,
+        choice: &Choice,
+        via: &A,
+    ) -> Result<MemPoolCheck, AdversaryFragmentSenderError> {
+        let fragment = super::FragmentBuilder::new(&self.block0_hash, &self.fees).vote_cast(
+            from,
+            vote_plan,
+            proposal_index,
+            choice,
+        );
+        self.dump_fragment_if_enabled(from, &fragment, via)?;
+        self.send_fragment(fragment, via)
+    }
+
+    fn faulty_vote_cast_builder(&self) -> FaultyVoteCastBuilder {
+        FaultyVoteCastBuilder::new(self.block0_hash, self.fees)
+    }
+
     pub fn send_faulty_transactions<A: FragmentNode + SyncNode + Sized + Sync + Send>(
         &self,
         n: u32,
@@ -227,7 +330,7 @@ impl<'a> AdversaryFragmentSender<'a> {
     ) -> Result<(), AdversaryFragmentSenderError> {
         let verifier = FragmentVerifier;
         match verifier.wait_fragment(Duration::from_secs(2), check.clone(), node)? {
-            FragmentStatus::Rejected { .. } => Ok(()),
+            FragmentStatus::Rejected { .. } | FragmentStatus::AlreadyConfirmed { .. } => Ok(()),
             FragmentStatus::InABlock { date, block } => {
                 Err(AdversaryFragmentSenderError::FragmentNotRejected {
                     alias: FragmentNode::alias(node).to_string(),
@@ -376,3 +479,97 @@ impl FaultyTransactionBuilder {
         Ok(Fragment::Transaction(tx))
     }
 }
+
+/// Builds vote casts which are well formed transactions, signed and
+/// paying their fees, but carry a certificate the ledger has to reject.
+pub struct FaultyVoteCastBuilder {
+    block0_hash: Hash,
+    fees: LinearFee,
+}
+
+impl FaultyVoteCastBuilder {
+    pub fn new(block0_hash: Hash, fees: LinearFee) -> Self {
+        Self { block0_hash, fees }
+    }
+
+    pub fn choice_out_of_range(
+        &self,
+        from: &Wallet,
+        vote_plan: &VotePlan,
+        proposal_index: u8,
+    ) -> Fragment {
+        let choice = Choice::new(options_of(vote_plan, proposal_index));
+        self.vote_cast(from, vote_plan, proposal_index, Payload::public(choice))
+    }
+
+    pub fn proposal_out_of_range(&self, from: &Wallet, vote_plan: &VotePlan) -> Fragment {
+        let proposal_index = vote_plan.proposals().len() as u8;
+        self.vote_cast(
+            from,
+            vote_plan,
+            proposal_index,
+            Payload::public(Choice::new(0)),
+        )
+    }
+
+    pub fn public_vote_for_private_plan(
+        &self,
+        from: &Wallet,
+        vote_plan: &VotePlan,
+        proposal_index: u8,
+    ) -> Fragment {
+        self.vote_cast(
+            from,
+            vote_plan,
+            proposal_index,
+            Payload::public(Choice::new(0)),
+        )
+    }
+
+    pub fn bad_proof(
+        &self,
+        from: &Wallet,
+        vote_plan: &VotePlan,
+        proposal_index: u8,
+        choice: &Choice,
+        encrypting_vote_key: &EncryptingVoteKey,
+    ) -> Fragment {
+        let options = options_of(vote_plan, proposal_index) as usize;
+        let vote = || chain_vote::Vote::new(options, choice.as_byte() as usize);
+        let (encrypted_vote, _) = encrypt_vote(&mut OsRng, encrypting_vote_key, vote());
+        let (_, proof) = encrypt_vote(&mut OsRng, encrypting_vote_key, vote());
+        self.vote_cast(
+            from,
+            vote_plan,
+            proposal_index,
+            Payload::Private {
+                encrypted_vote,
+                proof,
+            },
+        )
+    }
+
+    fn vote_cast(
+        &self,
+        from: &Wallet,
+        vote_plan: &VotePlan,
+        proposal_index: u8,
+        payload: Payload,
+    ) -> Fragment {
+        let vote_cast = VoteCast::new(vote_plan.to_id(), proposal_index, payload);
+        FragmentFactory::new(self.block0_hash.into_hash(), self.fees)
+            .vote_cast(&from.clone().into(), vote_cast)
+    }
+}
+
+/// the number of options of the proposal
+fn options_of(vote_plan: &VotePlan, proposal_index: u8) -> u8 {
+    vote_plan
+        .proposals()
+        .iter()
+        .nth(proposal_index as usize)
+        .expect("the proposal is in the vote plan")
+        .options()
+        .choice_range()
+        .end
+}
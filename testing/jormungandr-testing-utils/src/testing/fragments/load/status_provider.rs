@@ -37,14 +37,14 @@ fn into_status(fragment_log: &FragmentLog, id: &FragmentId) -> Status {
                 .unwrap();
             Status::new_pending(duration.into(), id.to_string())
         }
-        FragmentStatus::Rejected { reason } => {
+        FragmentStatus::Rejected { reason, .. } => {
             let duration = fragment_log
                 .last_updated_at()
                 .duration_since(*fragment_log.received_at())
                 .unwrap();
             Status::new_failure(duration.into(), id.to_string(), reason.to_string())
         }
-        FragmentStatus::InABlock { .. } => {
+        FragmentStatus::InABlock { .. } | FragmentStatus::AlreadyConfirmed { .. } => {
             let duration = fragment_log
                 .last_updated_at()
                 .duration_since(*fragment_log.received_at())
@@ -1,6 +1,7 @@
 pub use self::{
     adversary::{
         AdversaryFragmentSender, AdversaryFragmentSenderError, AdversaryFragmentSenderSetup,
+        FaultyVoteCastBuilder,
     },
     export::{FragmentExporter, FragmentExporterError},
     initial_certificates::{signed_delegation_cert, signed_stake_pool_cert, vote_plan_cert},
@@ -8,24 +9,28 @@ pub use self::{
     sender::{FragmentSender, FragmentSenderError},
     setup::{FragmentSenderSetup, FragmentSenderSetupBuilder, VerifyStrategy},
     transaction::{transaction_to, transaction_to_many},
-    verifier::{FragmentVerifier, FragmentVerifierError},
+    verifier::{ExpectedRejection, FragmentVerifier, FragmentVerifierError},
 };
 use crate::{stake_pool::StakePool, wallet::Wallet};
 use chain_impl_mockchain::{
-    certificate::{PoolId, VoteCast, VotePlan, VoteTally},
+    certificate::{
+        DecryptedPrivateTally, EncryptedVoteTally, PoolId, VoteCast, VotePlan, VoteTally,
+    },
     fee::LinearFee,
     fragment::Fragment,
     testing::{
         data::{StakePool as StakePoolLib, Wallet as WalletLib},
         scenario::FragmentFactory,
     },
-    vote::{Choice, Payload},
+    vote::{encrypt_vote, Choice, Payload},
 };
+use chain_vote::EncryptingVoteKey;
 use jormungandr_lib::{
     crypto::hash::Hash,
     interfaces::{Address, Initial, Value},
 };
 pub use load::{BatchFragmentGenerator, FragmentGenerator, FragmentStatusProvider};
+use rand::rngs::OsRng;
 use thiserror::Error;
 
 mod adversary;
@@ -203,4 +208,57 @@ impl FragmentBuilder {
         self.fragment_factory()
             .vote_tally(&inner_wallet, vote_tally)
     }
+
+    /// cast a vote of a private vote plan, encrypted with the key of its
+    /// committee
+    pub fn private_vote_cast(
+        &self,
+        wallet: &Wallet,
+        vote_plan: &VotePlan,
+        proposal_index: u8,
+        choice: &Choice,
+        encrypting_vote_key: &EncryptingVoteKey,
+    ) -> Fragment {
+        let inner_wallet = wallet.clone().into();
+        let options = vote_plan
+            .proposals()
+            .iter()
+            .nth(proposal_index as usize)
+            .expect("the proposal is in the vote plan")
+            .options()
+            .choice_range()
+            .end;
+        let vote = chain_vote::Vote::new(options as usize, choice.as_byte() as usize);
+        let (encrypted_vote, proof) = encrypt_vote(&mut OsRng, encrypting_vote_key, vote);
+        let vote_cast = VoteCast::new(
+            vote_plan.to_id(),
+            proposal_index,
+            Payload::Private {
+                encrypted_vote,
+                proof,
+            },
+        );
+        self.fragment_factory().vote_cast(&inner_wallet, vote_cast)
+    }
+
+    pub fn encrypted_vote_tally(&self, wallet: &Wallet, vote_plan: &VotePlan) -> Fragment {
+        let inner_wallet = wallet.clone().into();
+        let vote_tally = EncryptedVoteTally::new(vote_plan.to_id());
+        self.fragment_factory()
+            .encrypted_vote_tally(&inner_wallet, vote_tally)
+    }
+
+    /// the tally of a private vote plan, with the decrypted results and the
+    /// decryption shares of the committee
+    pub fn private_vote_tally(
+        &self,
+        wallet: &Wallet,
+        vote_plan: &VotePlan,
+        decrypted_tally: DecryptedPrivateTally,
+    ) -> Fragment {
+        let inner_wallet = wallet.clone().into();
+        let vote_tally = VoteTally::new_private(vote_plan.to_id(), decrypted_tally);
+        self.fragment_factory()
+            .vote_tally(&inner_wallet, vote_tally)
+    }
 }
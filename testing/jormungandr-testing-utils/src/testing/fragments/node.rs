@@ -1,7 +1,7 @@
 use chain_impl_mockchain::fragment::{Fragment, FragmentId};
 use jormungandr_lib::{
     crypto::hash::Hash,
-    interfaces::{BlockDate, FragmentLog},
+    interfaces::{BlockDate, FragmentDetails, FragmentLog},
 };
 
 use std::collections::HashMap;
@@ -22,6 +22,13 @@ pub enum FragmentNodeError {
     UnknownError,
     #[error("cannot list fragments error due to '{0}'")]
     ListFragmentError(String),
+    #[error("cannot get the details of fragment '{fragment_id}' due to '{reason}'")]
+    FragmentDetailsError {
+        reason: String,
+        fragment_id: FragmentId,
+    },
+    #[error("node '{alias}' does not expose the details of the fragments")]
+    FragmentDetailsNotSupported { alias: String },
     #[error(
         "cannot send one of the fragments {fragment_ids:?} due to '{reason}' to to node '{alias}'"
     )]
@@ -52,6 +59,17 @@ impl FragmentNodeError {
 pub trait FragmentNode {
     fn alias(&self) -> &str;
     fn fragment_logs(&self) -> Result<HashMap<FragmentId, FragmentLog>, FragmentNodeError>;
+    /// the details of the fragment, including its position in the block,
+    /// `None` if the node does not know the fragment. Not every node
+    /// exposes them, e.g. the legacy releases do not.
+    fn fragment_details(
+        &self,
+        _fragment_id: &FragmentId,
+    ) -> Result<Option<FragmentDetails>, FragmentNodeError> {
+        Err(FragmentNodeError::FragmentDetailsNotSupported {
+            alias: self.alias().to_string(),
+        })
+    }
     fn send_fragment(&self, fragment: Fragment) -> Result<MemPoolCheck, FragmentNodeError>;
     fn send_batch_fragments(
         &self,
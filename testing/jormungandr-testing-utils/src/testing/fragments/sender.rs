@@ -10,8 +10,12 @@ use crate::{
 };
 use chain_core::property::Fragment as _;
 use chain_impl_mockchain::{
-    certificate::VotePlan, fee::LinearFee, fragment::Fragment, vote::Choice,
+    certificate::{DecryptedPrivateTally, VotePlan},
+    fee::LinearFee,
+    fragment::Fragment,
+    vote::Choice,
 };
+use chain_vote::EncryptingVoteKey;
 use jormungandr_lib::interfaces::Address;
 use jormungandr_lib::{
     crypto::hash::Hash,
@@ -152,6 +156,28 @@ impl<'a> FragmentSender<'a> {
         self.send_fragment(from, fragment, via)
     }
 
+    pub fn send_redelegation<A: FragmentNode + SyncNode + Sized + Sync + Send>(
+        &self,
+        from: &mut Wallet,
+        distribution: &[(&StakePool, u8)],
+        via: &A,
+    ) -> Result<MemPoolCheck, FragmentSenderError> {
+        let fragment =
+            from.issue_redelegation_cert(&self.block0_hash, &self.fees, distribution.to_vec())?;
+        self.dump_fragment_if_enabled(from, &fragment, via)?;
+        self.send_fragment(from, fragment, via)
+    }
+
+    pub fn send_delegation_removal<A: FragmentNode + SyncNode + Sized + Sync + Send>(
+        &self,
+        from: &mut Wallet,
+        via: &A,
+    ) -> Result<MemPoolCheck, FragmentSenderError> {
+        let fragment = from.remove_delegation_cert(&self.block0_hash, &self.fees)?;
+        self.dump_fragment_if_enabled(from, &fragment, via)?;
+        self.send_fragment(from, fragment, via)
+    }
+
     pub fn send_owner_delegation<A: FragmentNode + SyncNode + Sized + Sync + Send>(
         &self,
         from: &mut Wallet,
@@ -239,6 +265,56 @@ impl<'a> FragmentSender<'a> {
         self.send_fragment(from, fragment, via)
     }
 
+    pub fn send_private_vote_cast<A: FragmentNode + SyncNode + Sized + Sync + Send>(
+        &self,
+        from: &mut Wallet,
+        vote_plan: &VotePlan,
+        proposal_index: u8,
+        choice: &Choice,
+        encrypting_vote_key: &EncryptingVoteKey,
+        via: &A,
+    ) -> Result<MemPoolCheck, FragmentSenderError> {
+        let fragment = from.issue_private_vote_cast_cert(
+            &self.block0_hash,
+            &self.fees,
+            vote_plan,
+            proposal_index,
+            choice,
+            encrypting_vote_key,
+        )?;
+        self.dump_fragment_if_enabled(from, &fragment, via)?;
+        self.send_fragment(from, fragment, via)
+    }
+
+    pub fn send_encrypted_vote_tally<A: FragmentNode + SyncNode + Sized + Sync + Send>(
+        &self,
+        from: &mut Wallet,
+        vote_plan: &VotePlan,
+        via: &A,
+    ) -> Result<MemPoolCheck, FragmentSenderError> {
+        let fragment =
+            from.issue_encrypted_vote_tally_cert(&self.block0_hash, &self.fees, vote_plan)?;
+        self.dump_fragment_if_enabled(from, &fragment, via)?;
+        self.send_fragment(from, fragment, via)
+    }
+
+    pub fn send_private_vote_tally<A: FragmentNode + SyncNode + Sized + Sync + Send>(
+        &self,
+        from: &mut Wallet,
+        vote_plan: &VotePlan,
+        decrypted_tally: DecryptedPrivateTally,
+        via: &A,
+    ) -> Result<MemPoolCheck, FragmentSenderError> {
+        let fragment = from.issue_private_vote_tally_cert(
+            &self.block0_hash,
+            &self.fees,
+            vote_plan,
+            decrypted_tally,
+        )?;
+        self.dump_fragment_if_enabled(from, &fragment, via)?;
+        self.send_fragment(from, fragment, via)
+    }
+
     pub fn send_transactions<A: FragmentNode + SyncNode + Sized + Sync + Send>(
         &self,
         n: u32,
@@ -305,12 +381,14 @@ impl<'a> FragmentSender<'a> {
     ) -> Result<(), FragmentSenderError> {
         let verifier = FragmentVerifier;
         match verifier.wait_fragment(Duration::from_secs(2), check.clone(), node)? {
-            FragmentStatus::Rejected { reason } => Err(FragmentSenderError::FragmentNotInBlock {
-                alias: FragmentNode::alias(node).to_string(),
-                reason,
-                logs: FragmentNode::log_content(node),
-            }),
-            FragmentStatus::InABlock { .. } => Ok(()),
+            FragmentStatus::Rejected { reason, .. } => {
+                Err(FragmentSenderError::FragmentNotInBlock {
+                    alias: FragmentNode::alias(node).to_string(),
+                    reason,
+                    logs: FragmentNode::log_content(node),
+                })
+            }
+            FragmentStatus::InABlock { .. } | FragmentStatus::AlreadyConfirmed { .. } => Ok(()),
             _ => unimplemented!(),
         }
     }
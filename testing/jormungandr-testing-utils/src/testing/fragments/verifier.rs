@@ -1,6 +1,6 @@
 use crate::testing::fragments::node::{FragmentNode, FragmentNodeError, MemPoolCheck};
 use chain_impl_mockchain::fragment::FragmentId;
-use jormungandr_lib::interfaces::FragmentStatus;
+use jormungandr_lib::interfaces::{FragmentBlockContext, FragmentStatus};
 use std::time::Duration;
 
 #[derive(custom_debug::Debug, thiserror::Error)]
@@ -27,6 +27,44 @@ pub enum FragmentVerifierError {
         #[debug(skip)]
         logs: Vec<String>,
     },
+    #[error("fragment sent to node: {alias} is not rejected :({status:?})")]
+    FragmentNotRejected {
+        alias: String,
+        status: FragmentStatus,
+        #[debug(skip)]
+        logs: Vec<String>,
+    },
+    #[error("fragment sent to node: {alias} is rejected for another reason than {expected} :({status:?})")]
+    UnexpectedRejection {
+        alias: String,
+        expected: ExpectedRejection,
+        status: FragmentStatus,
+        #[debug(skip)]
+        logs: Vec<String>,
+    },
+    #[error("fragment sent to node: {alias} has no block position :({fragment_id})")]
+    FragmentPositionUnknown {
+        alias: String,
+        fragment_id: FragmentId,
+        #[debug(skip)]
+        logs: Vec<String>,
+    },
+    #[error("fragment {after} is not after fragment {before} in the blocks of node: {alias}")]
+    FragmentsOutOfOrder {
+        alias: String,
+        before: FragmentId,
+        after: FragmentId,
+        #[debug(skip)]
+        logs: Vec<String>,
+    },
+    #[error("fragments {first} and {other} are not in the same block of node: {alias}")]
+    FragmentsNotInSameBlock {
+        alias: String,
+        first: FragmentId,
+        other: FragmentId,
+        #[debug(skip)]
+        logs: Vec<String>,
+    },
     #[error("fragment node error")]
     FragmentNode(#[from] FragmentNodeError),
 }
@@ -38,6 +76,11 @@ impl FragmentVerifierError {
             FragmentNotInBlock { logs, .. }
             | FragmentIsPendingForTooLong { logs, .. }
             | FragmentNotInMemPoolLogs { logs, .. }
+            | FragmentNotRejected { logs, .. }
+            | UnexpectedRejection { logs, .. }
+            | FragmentPositionUnknown { logs, .. }
+            | FragmentsOutOfOrder { logs, .. }
+            | FragmentsNotInSameBlock { logs, .. }
             | FragmentNode(FragmentNodeError::CannotSendFragment { logs, .. }) => Some(logs),
             FragmentNode(_) => None,
         };
@@ -49,6 +92,54 @@ impl FragmentVerifierError {
     }
 }
 
+/// the reason a fragment is expected to be rejected for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpectedRejection {
+    /// rejected by the ledger with the error of the given variant, e.g.
+    /// `NotEnoughSignatures`, whatever its parameters
+    LedgerError(String),
+    /// rejected with a reason containing the text, for the fragments
+    /// rejected before reaching the ledger, e.g. by the pool
+    Reason(String),
+}
+
+impl ExpectedRejection {
+    pub fn ledger_error<S: Into<String>>(variant: S) -> Self {
+        ExpectedRejection::LedgerError(variant.into())
+    }
+
+    pub fn reason<S: Into<String>>(text: S) -> Self {
+        ExpectedRejection::Reason(text.into())
+    }
+
+    fn matches(&self, reason: &str, ledger_error: Option<&str>) -> bool {
+        match self {
+            ExpectedRejection::LedgerError(variant) => ledger_error
+                .map(|ledger_error| error_variant(ledger_error) == variant)
+                .unwrap_or(false),
+            ExpectedRejection::Reason(text) => reason.contains(text.as_str()),
+        }
+    }
+}
+
+impl std::fmt::Display for ExpectedRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ExpectedRejection::LedgerError(variant) => write!(f, "ledger error {}", variant),
+            ExpectedRejection::Reason(text) => write!(f, "'{}'", text),
+        }
+    }
+}
+
+/// the name of the variant of a debug formatted error, the ledger errors
+/// being reported with their parameters, e.g. `NotEnoughSignatures(2, 1)`
+fn error_variant(ledger_error: &str) -> &str {
+    let end = ledger_error
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or_else(|| ledger_error.len());
+    &ledger_error[..end]
+}
+
 pub struct FragmentVerifier;
 
 impl FragmentVerifier {
@@ -77,6 +168,111 @@ impl FragmentVerifier {
         Ok(())
     }
 
+    pub fn wait_and_verify_is_rejected_with<A: FragmentNode + ?Sized>(
+        &self,
+        duration: Duration,
+        check: MemPoolCheck,
+        expected: &ExpectedRejection,
+        node: &A,
+    ) -> Result<(), FragmentVerifierError> {
+        let status = self.wait_fragment(duration, check, node)?;
+        self.is_rejected_with(status, expected, node)
+    }
+
+    pub fn is_rejected_with<A: FragmentNode + ?Sized>(
+        &self,
+        status: FragmentStatus,
+        expected: &ExpectedRejection,
+        node: &A,
+    ) -> Result<(), FragmentVerifierError> {
+        let matches = match &status {
+            FragmentStatus::Rejected {
+                reason,
+                ledger_error,
+            } => expected.matches(reason, ledger_error.as_deref()),
+            _ => {
+                return Err(FragmentVerifierError::FragmentNotRejected {
+                    alias: node.alias().to_string(),
+                    status,
+                    logs: node.log_content(),
+                })
+            }
+        };
+        if !matches {
+            return Err(FragmentVerifierError::UnexpectedRejection {
+                alias: node.alias().to_string(),
+                expected: expected.clone(),
+                status,
+                logs: node.log_content(),
+            });
+        }
+        Ok(())
+    }
+
+    /// the position of the fragment in the blockchain of the node
+    pub fn block_position<A: FragmentNode + ?Sized>(
+        &self,
+        check: &MemPoolCheck,
+        node: &A,
+    ) -> Result<FragmentBlockContext, FragmentVerifierError> {
+        node.fragment_details(check.fragment_id())?
+            .and_then(|details| details.block)
+            .ok_or_else(|| FragmentVerifierError::FragmentPositionUnknown {
+                alias: node.alias().to_string(),
+                fragment_id: *check.fragment_id(),
+                logs: node.log_content(),
+            })
+    }
+
+    /// verify that the fragments are in blocks, in the given order: every
+    /// fragment is in a later block than the previous one, or after it in
+    /// the same block
+    pub fn are_in_block_order<A: FragmentNode + ?Sized>(
+        &self,
+        checks: &[MemPoolCheck],
+        node: &A,
+    ) -> Result<Vec<FragmentBlockContext>, FragmentVerifierError> {
+        let positions = checks
+            .iter()
+            .map(|check| self.block_position(check, node))
+            .collect::<Result<Vec<_>, _>>()?;
+        for (index, pair) in positions.windows(2).enumerate() {
+            if (pair[0].chain_length, pair[0].index) >= (pair[1].chain_length, pair[1].index) {
+                return Err(FragmentVerifierError::FragmentsOutOfOrder {
+                    alias: node.alias().to_string(),
+                    before: *checks[index].fragment_id(),
+                    after: *checks[index + 1].fragment_id(),
+                    logs: node.log_content(),
+                });
+            }
+        }
+        Ok(positions)
+    }
+
+    /// verify that the fragments are all in the same block
+    pub fn are_in_same_block<A: FragmentNode + ?Sized>(
+        &self,
+        checks: &[MemPoolCheck],
+        node: &A,
+    ) -> Result<(), FragmentVerifierError> {
+        let mut positions = checks.iter().map(|check| self.block_position(check, node));
+        let first = match positions.next() {
+            Some(position) => position?,
+            None => return Ok(()),
+        };
+        for (check, position) in checks.iter().skip(1).zip(positions) {
+            if position?.block != first.block {
+                return Err(FragmentVerifierError::FragmentsNotInSameBlock {
+                    alias: node.alias().to_string(),
+                    first: *checks[0].fragment_id(),
+                    other: *check.fragment_id(),
+                    logs: node.log_content(),
+                });
+            }
+        }
+        Ok(())
+    }
+
     pub fn fragment_status<A: FragmentNode + ?Sized>(
         &self,
         check: MemPoolCheck,
@@ -89,10 +285,11 @@ impl FragmentVerifier {
                 FragmentStatus::Pending => {
                     node.log_pending_fragment(*check.fragment_id());
                 }
-                FragmentStatus::Rejected { reason } => {
+                FragmentStatus::Rejected { reason, .. } => {
                     node.log_rejected_fragment(*check.fragment_id(), reason.to_string());
                 }
-                FragmentStatus::InABlock { date, block } => {
+                FragmentStatus::InABlock { date, block }
+                | FragmentStatus::AlreadyConfirmed { date, block } => {
                     node.log_in_block_fragment(*check.fragment_id(), *date, *block);
                 }
             }
@@ -126,6 +323,7 @@ impl FragmentVerifier {
             match status {
                 FragmentStatus::Rejected { .. } => return Ok(status),
                 FragmentStatus::InABlock { .. } => return Ok(status),
+                FragmentStatus::AlreadyConfirmed { .. } => return Ok(status),
                 _ => (),
             }
             std::thread::sleep(duration);
@@ -1,3 +1,4 @@
+mod benchmark_history;
 pub mod file;
 mod fragments;
 pub mod network_builder;
@@ -9,12 +10,17 @@ pub mod sync;
 mod verify;
 mod vit;
 
+pub use benchmark_history::{
+    current_revision, record_benchmark, BenchmarkHistory, BenchmarkHistoryError, BenchmarkRecord,
+    MachineProfile, Trend,
+};
 pub use fragments::{
     signed_delegation_cert, signed_stake_pool_cert, vote_plan_cert, AdversaryFragmentSender,
     AdversaryFragmentSenderError, AdversaryFragmentSenderSetup, BatchFragmentGenerator,
-    FragmentBuilder, FragmentBuilderError, FragmentGenerator, FragmentNode, FragmentNodeError,
-    FragmentSender, FragmentSenderError, FragmentSenderSetup, FragmentSenderSetupBuilder,
-    FragmentStatusProvider, FragmentVerifier, FragmentVerifierError, MemPoolCheck, VerifyStrategy,
+    ExpectedRejection, FaultyVoteCastBuilder, FragmentBuilder, FragmentBuilderError,
+    FragmentGenerator, FragmentNode, FragmentNodeError, FragmentSender, FragmentSenderError,
+    FragmentSenderSetup, FragmentSenderSetupBuilder, FragmentStatusProvider, FragmentVerifier,
+    FragmentVerifierError, MemPoolCheck, VerifyStrategy,
 };
 pub use jortestkit::archive::decompress;
 pub use jortestkit::github::{GitHubApi, GitHubApiError, Release};
@@ -28,14 +34,17 @@ pub use jortestkit::measurement::{
 pub use remote::{RemoteJormungandr, RemoteJormungandrBuilder};
 pub use storage::{BranchCount, StopCriteria, StorageBuilder};
 pub use sync::{
-    ensure_node_is_in_sync_with_others, ensure_nodes_are_in_sync, MeasurementReportInterval,
-    MeasurementReporter, SyncNode, SyncNodeError, SyncWaitParams,
+    ensure_node_is_in_sync_with_others, ensure_nodes_are_in_sync,
+    ensure_nodes_are_in_sync_with_timeline, MeasurementReportInterval, MeasurementReporter,
+    NodeLag, SyncNode, SyncNodeError, SyncTimeline, SyncWaitParams,
+};
+pub use vit::{
+    offchain_proposal, PrivateVoteTestFlow, PrivateVoteTestFlowError, VotePlanExtension,
 };
-pub use vit::VotePlanExtension;
 
 pub use jortestkit::web::download_file;
 
-pub use verify::{assert, assert_equals, Error as VerificationError};
+pub use verify::{assert, assert_equals, Error as VerificationError, ExpectedStakeDistribution};
 
 pub use file::*;
 pub use jortestkit::openssl::Openssl;
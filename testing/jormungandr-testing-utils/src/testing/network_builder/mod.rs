@@ -1,4 +1,5 @@
 mod blockchain;
+mod resource_limits;
 mod rng;
 mod settings;
 mod spawn_params;
@@ -7,6 +8,7 @@ mod wallet;
 
 pub use blockchain::Blockchain;
 use chain_impl_mockchain::header::HeaderId;
+pub use resource_limits::{Cgroup, ResourceLimits};
 pub use rng::{Random, Seed};
 pub use settings::{NodeSetting, Settings, WalletProxySettings};
 pub use spawn_params::SpawnParams;
@@ -0,0 +1,186 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+const CPU_PERIOD_MICROS: u64 = 100_000;
+
+/// tells apart the cgroups of the nodes spawned by this process, a node
+/// restarted under the same alias gets a new one
+static CGROUP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Limits on the resources of a spawned node, enforced by putting the node
+/// process in a cgroup of its own. Only the unified hierarchy (cgroup v2) is
+/// supported, and the test process needs the permission to create cgroups,
+/// which is usually the case when running as root or in a delegated cgroup.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// maximum memory of the node in bytes, the node is killed by the OOM
+    /// killer past it
+    pub memory_bytes: Option<u64>,
+    /// maximum share of a CPU core the node can use, in percent, e.g. 50
+    /// for half of a core or 200 for two cores
+    pub cpu_percent: Option<u32>,
+}
+
+impl ResourceLimits {
+    pub fn memory_bytes(&mut self, memory_bytes: u64) -> &mut Self {
+        self.memory_bytes = Some(memory_bytes);
+        self
+    }
+
+    pub fn cpu_percent(&mut self, cpu_percent: u32) -> &mut Self {
+        self.cpu_percent = Some(cpu_percent);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.memory_bytes.is_none() && self.cpu_percent.is_none()
+    }
+
+    /// whether the limits can be enforced on this host
+    pub fn available() -> bool {
+        Path::new(CGROUP_ROOT).join("cgroup.controllers").exists()
+    }
+
+    /// create a cgroup with the limits, named after the node, for the node
+    /// process to enter with `Cgroup::enter_on_spawn`
+    pub fn create(&self, name: &str) -> io::Result<Cgroup> {
+        self.create_in(Path::new(CGROUP_ROOT), name)
+    }
+
+    fn create_in(&self, root: &Path, name: &str) -> io::Result<Cgroup> {
+        // the limit files of a cgroup only exist when their controllers are
+        // enabled for the children of its parent
+        let controllers = self.controllers();
+        if !controllers.is_empty() {
+            fs::write(root.join("cgroup.subtree_control"), controllers.join(" "))?;
+        }
+
+        let path = root.join(format!(
+            "jormungandr-test-{}-{}-{}",
+            std::process::id(),
+            name,
+            CGROUP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir(&path)?;
+        let cgroup = Cgroup { path };
+        if let Some(memory_bytes) = self.memory_bytes {
+            cgroup.write("memory.max", &memory_bytes.to_string())?;
+            cgroup.write("memory.swap.max", "0").ok();
+        }
+        if let Some(cpu_percent) = self.cpu_percent {
+            cgroup.write("cpu.max", &cpu_max(cpu_percent))?;
+        }
+        Ok(cgroup)
+    }
+
+    fn controllers(&self) -> Vec<&'static str> {
+        let mut controllers = Vec::new();
+        if self.memory_bytes.is_some() {
+            controllers.push("+memory");
+        }
+        if self.cpu_percent.is_some() {
+            controllers.push("+cpu");
+        }
+        controllers
+    }
+}
+
+/// the quota of CPU time per period, in the format of `cpu.max`
+fn cpu_max(cpu_percent: u32) -> String {
+    let quota = CPU_PERIOD_MICROS * u64::from(cpu_percent) / 100;
+    format!("{} {}", quota, CPU_PERIOD_MICROS)
+}
+
+/// A cgroup created for a node, removed when dropped. The removal fails
+/// while the node is still running, the cgroup is left behind then.
+#[derive(Debug)]
+pub struct Cgroup {
+    path: PathBuf,
+}
+
+impl Cgroup {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// make the process spawned by the command enter the cgroup before it
+    /// executes, so that the limits apply from its start
+    #[cfg(unix)]
+    pub fn enter_on_spawn(&self, command: &mut Command) -> io::Result<()> {
+        use std::io::Write as _;
+        use std::os::unix::process::CommandExt as _;
+
+        // opened beforehand as nothing should be allocated between the fork
+        // and the exec, the file is closed on exec
+        let procs = fs::OpenOptions::new()
+            .write(true)
+            .open(self.path.join("cgroup.procs"))?;
+        // writing 0 moves the writing process
+        unsafe {
+            command.pre_exec(move || (&procs).write_all(b"0"));
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    pub fn enter_on_spawn(&self, _command: &mut Command) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "cgroups are only supported on Linux",
+        ))
+    }
+
+    fn write(&self, file: &str, value: &str) -> io::Result<()> {
+        fs::write(self.path.join(file), value)
+    }
+}
+
+impl Drop for Cgroup {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::TempDir;
+
+    fn read(path: &Path, file: &str) -> String {
+        fs::read_to_string(path.join(file)).unwrap()
+    }
+
+    #[test]
+    fn limits_are_written_to_the_cgroup() {
+        let root = TempDir::new().unwrap();
+        let mut limits = ResourceLimits::default();
+        assert!(limits.is_empty());
+        limits.memory_bytes(64 * 1024 * 1024).cpu_percent(50);
+        assert!(!limits.is_empty());
+
+        let cgroup = limits.create_in(root.path(), "node").unwrap();
+        assert_eq!(read(root.path(), "cgroup.subtree_control"), "+memory +cpu");
+        assert_eq!(read(cgroup.path(), "memory.max"), "67108864");
+        assert_eq!(read(cgroup.path(), "memory.swap.max"), "0");
+        assert_eq!(read(cgroup.path(), "cpu.max"), "50000 100000");
+    }
+
+    #[test]
+    fn only_the_limited_controllers_are_enabled() {
+        let root = TempDir::new().unwrap();
+        let mut limits = ResourceLimits::default();
+        limits.cpu_percent(200);
+
+        let first = limits.create_in(root.path(), "node").unwrap();
+        let second = limits.create_in(root.path(), "node").unwrap();
+        assert_ne!(first.path(), second.path());
+        assert_eq!(read(root.path(), "cgroup.subtree_control"), "+cpu");
+        assert_eq!(read(first.path(), "cpu.max"), "200000 100000");
+        assert!(!first.path().join("memory.max").exists());
+    }
+}
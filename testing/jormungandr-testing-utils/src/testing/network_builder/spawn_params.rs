@@ -2,7 +2,7 @@ use jormungandr_lib::interfaces::{
     Explorer, LayersConfig, Mempool, NodeConfig, Policy, TopicsOfInterest, TrustedPeer,
 };
 
-use super::{LeadershipMode, PersistenceMode};
+use super::{LeadershipMode, PersistenceMode, ResourceLimits};
 use crate::testing::node::Version;
 use std::path::PathBuf;
 
@@ -25,6 +25,9 @@ pub struct SpawnParams {
     pub version: Option<Version>,
     pub bootstrap_from_peers: Option<bool>,
     pub skip_bootstrap: Option<bool>,
+    pub storage: Option<PathBuf>,
+    pub env: Vec<(String, String)>,
+    pub resource_limits: ResourceLimits,
 }
 
 impl SpawnParams {
@@ -47,6 +50,9 @@ impl SpawnParams {
             version: None,
             bootstrap_from_peers: None,
             skip_bootstrap: None,
+            storage: None,
+            env: Vec::new(),
+            resource_limits: ResourceLimits::default(),
         }
     }
 
@@ -169,6 +175,41 @@ impl SpawnParams {
         &self.jormungandr
     }
 
+    /// the storage of a persistent node, in place of the one in the node
+    /// working directory
+    pub fn storage(&mut self, storage: PathBuf) -> &mut Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    pub fn get_storage(&self) -> &Option<PathBuf> {
+        &self.storage
+    }
+
+    /// an environment variable set for the node process
+    pub fn env<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) -> &mut Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn get_env(&self) -> &[(String, String)] {
+        &self.env
+    }
+
+    pub fn memory_limit(&mut self, memory_bytes: u64) -> &mut Self {
+        self.resource_limits.memory_bytes(memory_bytes);
+        self
+    }
+
+    pub fn cpu_limit(&mut self, cpu_percent: u32) -> &mut Self {
+        self.resource_limits.cpu_percent(cpu_percent);
+        self
+    }
+
+    pub fn get_resource_limits(&self) -> &ResourceLimits {
+        &self.resource_limits
+    }
+
     pub fn override_settings(&self, node_config: &mut NodeConfig) {
         if let Some(topics_of_interest) = &self.topics_of_interest {
             node_config.p2p.topics_of_interest = Some(topics_of_interest.clone());
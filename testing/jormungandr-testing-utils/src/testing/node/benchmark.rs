@@ -1,7 +1,10 @@
-use crate::testing::{Speed, SpeedBenchmarkDef, SpeedBenchmarkFinish, Timestamp};
+use crate::testing::{
+    record_benchmark, BenchmarkRecord, Speed, SpeedBenchmarkDef, SpeedBenchmarkFinish, Timestamp,
+};
+use chrono::DateTime;
 use std::time::Duration;
 
-use crate::testing::node::JormungandrLogger;
+use crate::testing::node::{JormungandrLogger, LogEntry};
 
 pub fn storage_loading_benchmark_from_log(
     log: &JormungandrLogger,
@@ -24,17 +27,24 @@ pub fn speed_benchmark_from_log(
     start_measurement: &str,
     stop_measurement: &str,
 ) -> SpeedBenchmarkFinish {
-    let start_entry: Timestamp = log
+    let start_entry = log
         .get_log_entries()
         .find(|x| x.msg.contains(start_measurement))
-        .expect("cannot find start mesurement entry in log")
-        .into();
+        .expect("cannot find start mesurement entry in log");
 
-    let stop_entry: Timestamp = log
+    let stop_entry = log
         .get_log_entries()
         .find(|x| x.msg.contains(stop_measurement))
-        .expect("cannot find stop mesurement entry in log")
-        .into();
+        .expect("cannot find stop mesurement entry in log");
+
+    record_benchmark(BenchmarkRecord::duration(
+        name,
+        elapsed_between(&start_entry, &stop_entry),
+    ))
+    .unwrap_or_else(|error| panic!("{}", error));
+
+    let start_entry: Timestamp = start_entry.into();
+    let stop_entry: Timestamp = stop_entry.into();
 
     let definition = SpeedBenchmarkDef::new(name.to_string())
         .target(timeout)
@@ -43,3 +53,9 @@ pub fn speed_benchmark_from_log(
 
     SpeedBenchmarkFinish::new(definition, speed)
 }
+
+fn elapsed_between(start: &LogEntry, stop: &LogEntry) -> Duration {
+    let start = DateTime::parse_from_rfc3339(&start.ts).expect("malformed log timestamp");
+    let stop = DateTime::parse_from_rfc3339(&stop.ts).expect("malformed log timestamp");
+    (stop - start).to_std().unwrap_or_default()
+}
@@ -14,15 +14,19 @@ use self::serde_derive::{Deserialize, Serialize};
 use chain_impl_mockchain::{chaintypes::ConsensusVersion, fee::LinearFee};
 use jormungandr_lib::{
     interfaces::{
-        ActiveSlotCoefficient, Block0Configuration, BlockchainConfiguration, CommitteeIdDef,
-        ConsensusLeaderId, EpochStabilityDepth, FeesGoTo, Initial, InitialUTxO, KESUpdateSpeed,
+        initial_certificates_from_csv, initial_funds_from_csv, ActiveSlotCoefficient,
+        Block0Configuration, BlockchainConfiguration, CommitteeIdDef, ConsensusLeaderId,
+        EpochStabilityDepth, FeesGoTo, Initial, InitialCsvError, InitialUTxO, KESUpdateSpeed,
         NumberOfSlotsPerEpoch, Ratio, RewardConstraints, RewardParams, SlotDuration, TaxType,
         Value,
     },
     time::SecondsSinceUnixEpoch,
 };
 
+use std::fs::File;
+use std::io::BufReader;
 use std::num::NonZeroU32;
+use std::path::Path;
 use std::vec::Vec;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -80,6 +84,18 @@ impl Block0ConfigurationBuilder {
         self.initial.extend(funds.iter().cloned());
         self
     }
+
+    /// add the initial funds of a CSV file, see `initial_funds_from_csv`.
+    /// The addresses are checked against the discrimination set so far.
+    pub fn with_funds_from_csv<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<&mut Self, InitialCsvError> {
+        let reader = BufReader::new(File::open(path)?);
+        let funds = initial_funds_from_csv(reader, self.blockchain_configuration.discrimination)?;
+        Ok(self.with_funds(funds))
+    }
+
     pub fn with_leaders(&mut self, leaders_ids: Vec<ConsensusLeaderId>) -> &mut Self {
         self.blockchain_configuration.consensus_leader_ids = leaders_ids;
         self
@@ -147,6 +163,17 @@ impl Block0ConfigurationBuilder {
         self
     }
 
+    /// add the initial certificates of a CSV file, see
+    /// `initial_certificates_from_csv`.
+    pub fn with_certs_from_csv<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<&mut Self, InitialCsvError> {
+        let reader = BufReader::new(File::open(path)?);
+        let certs = initial_certificates_from_csv(reader)?;
+        Ok(self.with_certs(certs))
+    }
+
     pub fn with_initial(&mut self, initial: Vec<Initial>) -> &mut Self {
         self.initial.extend(initial.iter().cloned());
         self
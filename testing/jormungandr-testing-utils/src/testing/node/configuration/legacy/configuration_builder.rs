@@ -1,21 +1,22 @@
 use super::config::{P2p, TrustedPeer};
+use super::migration::{migrate, newest_version};
 use crate::testing::node::configuration::legacy::NodeConfig;
 use crate::testing::node::configuration::JormungandrParams;
-use crate::testing::node::{version_0_8_19, Version};
+use crate::testing::node::Version;
 use jormungandr_lib::interfaces::{NodeConfig as NewestNodeConfig, Rest};
-use rand::RngCore;
-use rand_core::OsRng;
 use thiserror::Error;
 #[derive(Error, Debug)]
 pub enum LegacyConfigConverterError {
     #[error("unsupported version")]
     UnsupportedVersion(Version),
+    #[error("the configuration does not match the newest schema")]
+    IncompatibleConfig(#[from] serde_yaml::Error),
 }
 
 /// Used to build configuration for legacy nodes.
-/// It uses yaml_rust instead of serde yaml serializer
-/// beacuse config model is always up to date with newest config schema
-/// while legacy node requires na old one
+/// The configuration is converted from the newest schema to the schema of
+/// the legacy version, or back, by the chain of the breaking changes made
+/// in between, see `migration`
 pub struct LegacyConfigConverter {
     version: Version,
 }
@@ -34,6 +35,24 @@ impl LegacyConfigConverter {
         Ok(self.build_configuration(params, node_config))
     }
 
+    pub fn convert_forward(
+        &self,
+        params: JormungandrParams<NodeConfig>,
+    ) -> Result<JormungandrParams<NewestNodeConfig>, LegacyConfigConverterError> {
+        let node_config_converter = LegacyNodeConfigConverter::new(self.version.clone());
+        let node_config = node_config_converter.convert_forward(params.node_config())?;
+        Ok(JormungandrParams::new(
+            node_config,
+            params.node_config_path(),
+            params.genesis_block_path(),
+            params.genesis_block_hash(),
+            params.secret_model_paths(),
+            params.block0_configuration().clone(),
+            params.rewards_history(),
+            params.log_file_path(),
+        ))
+    }
+
     fn build_configuration(
         &self,
         params: JormungandrParams<NewestNodeConfig>,
@@ -61,80 +80,51 @@ impl LegacyNodeConfigConverter {
         Self { version }
     }
 
-    ///0.8.19 is a breaking point where in trusted peer id was obsoleted
+    /// convert the newest configuration to the one of the legacy version
     pub fn convert(
         &self,
         source: &NewestNodeConfig,
     ) -> Result<NodeConfig, LegacyConfigConverterError> {
-        if self.version > version_0_8_19() {
-            return Ok(self.build_node_config_after_0_8_19(source));
-        }
-        Ok(self.build_node_config_before_0_8_19(source))
+        let mut node_config = Self::from_newest(source);
+        migrate(&mut node_config, &newest_version(), &self.version);
+        Self::keep_trusted_peer_ids(&mut node_config, source);
+        Ok(node_config)
     }
 
-    fn build_node_config_after_0_8_19(&self, source: &NewestNodeConfig) -> NodeConfig {
-        let trusted_peers: Vec<TrustedPeer> = source
+    /// the versions taking trusted peer ids get the ids set in the source,
+    /// instead of the ones generated by the migration
+    fn keep_trusted_peer_ids(node_config: &mut NodeConfig, source: &NewestNodeConfig) {
+        for (peer, source_peer) in node_config
             .p2p
             .trusted_peers
-            .iter()
-            .map(|peer| TrustedPeer {
-                id: None,
-                address: peer.address.clone(),
-            })
-            .collect();
-
-        NodeConfig {
-            storage: source.storage.clone(),
-            log: source.log.clone(),
-            rest: Rest {
-                listen: source.rest.listen,
-                cors: None,
-                tls: None,
-            },
-            p2p: P2p {
-                trusted_peers,
-                public_address: source.p2p.public_address.clone(),
-                listen_address: None,
-                max_inbound_connections: None,
-                max_connections: None,
-                topics_of_interest: source.p2p.topics_of_interest.clone(),
-                allow_private_addresses: source.p2p.allow_private_addresses,
-                policy: source.p2p.policy.clone(),
-                layers: None,
-                public_id: None,
-            },
-            mempool: source.mempool.clone(),
-            explorer: source.explorer.clone(),
-            bootstrap_from_trusted_peers: source.bootstrap_from_trusted_peers,
-            skip_bootstrap: source.skip_bootstrap,
+            .iter_mut()
+            .zip(source.p2p.trusted_peers.iter())
+        {
+            if let (Some(id), Some(source_id)) = (peer.id.as_mut(), source_peer.id) {
+                *id = source_id.to_string();
+            }
         }
     }
 
-    fn generate_legacy_poldercast_id(rng: &mut OsRng) -> String {
-        let mut bytes: [u8; 24] = [0; 24];
-        rng.fill_bytes(&mut bytes);
-        hex::encode(&bytes)
+    /// convert the configuration of the legacy version to the newest one
+    pub fn convert_forward(
+        &self,
+        source: &NodeConfig,
+    ) -> Result<NewestNodeConfig, LegacyConfigConverterError> {
+        let mut node_config = source.clone();
+        migrate(&mut node_config, &self.version, &newest_version());
+        let value = serde_yaml::to_value(&node_config)?;
+        Ok(serde_yaml::from_value(value)?)
     }
 
-    fn build_node_config_before_0_8_19(&self, source: &NewestNodeConfig) -> NodeConfig {
-        let mut rng = OsRng;
+    fn from_newest(source: &NewestNodeConfig) -> NodeConfig {
         let trusted_peers: Vec<TrustedPeer> = source
             .p2p
             .trusted_peers
             .iter()
-            .map(|peer| {
-                let id = {
-                    if let Some(id) = peer.id {
-                        id.to_string()
-                    } else {
-                        Self::generate_legacy_poldercast_id(&mut rng)
-                    }
-                };
-
-                TrustedPeer {
-                    id: Some(id),
-                    address: peer.address.clone(),
-                }
+            .map(|peer| TrustedPeer {
+                id: None,
+                address: peer.address.clone(),
             })
             .collect();
 
@@ -144,6 +134,11 @@ impl LegacyNodeConfigConverter {
             rest: Rest {
                 listen: source.rest.listen,
                 cors: None,
+                admin_token: None,
+                reuse_port: false,
+                unix_socket: None,
+                virtual_host: None,
+                max_addresses_per_request: None,
                 tls: None,
             },
             p2p: P2p {
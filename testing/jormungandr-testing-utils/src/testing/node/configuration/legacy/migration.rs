@@ -0,0 +1,206 @@
+use super::config::NodeConfig;
+use crate::testing::node::{version_0_8_19, Version};
+use rand::RngCore;
+use rand_core::OsRng;
+
+/// A breaking change of the node configuration schema, made in the release
+/// following `after`.
+struct Migration {
+    after: Version,
+    /// convert a configuration for `after` to the next release
+    upgrade: fn(&mut NodeConfig),
+    /// convert a configuration for the next release to `after`
+    downgrade: fn(&mut NodeConfig),
+}
+
+/// The breaking changes of the node configuration, oldest first.
+fn migrations() -> Vec<Migration> {
+    vec![Migration {
+        after: version_0_8_19(),
+        upgrade: drop_trusted_peer_ids,
+        downgrade: add_trusted_peer_ids,
+    }]
+}
+
+/// the version of the newest configuration schema, the one of
+/// `jormungandr_lib::interfaces::NodeConfig`
+pub fn newest_version() -> Version {
+    Version::parse(jormungandr_lib::interfaces::NODE_CONFIG_VERSION)
+        .expect("the node version is valid semver")
+}
+
+/// Convert a configuration for the release `from` to the release `to`,
+/// applying every breaking change made in between, forward or backward.
+pub fn migrate(config: &mut NodeConfig, from: &Version, to: &Version) {
+    let migrations = migrations();
+    if from < to {
+        for migration in migrations
+            .iter()
+            .filter(|migration| *from <= migration.after && migration.after < *to)
+        {
+            (migration.upgrade)(config);
+        }
+    } else {
+        for migration in migrations
+            .iter()
+            .rev()
+            .filter(|migration| *to <= migration.after && migration.after < *from)
+        {
+            (migration.downgrade)(config);
+        }
+    }
+}
+
+/// The trusted peer ids were obsoleted after 0.8.19, the legacy ids do not
+/// parse as the node ids of the newer releases.
+fn drop_trusted_peer_ids(config: &mut NodeConfig) {
+    for peer in config.p2p.trusted_peers.iter_mut() {
+        peer.id = None;
+    }
+}
+
+/// Up to 0.8.19 the trusted peers must have an id, any will do.
+fn add_trusted_peer_ids(config: &mut NodeConfig) {
+    let mut rng = OsRng;
+    for peer in config.p2p.trusted_peers.iter_mut() {
+        if peer.id.is_none() {
+            peer.id = Some(generate_legacy_poldercast_id(&mut rng));
+        }
+    }
+}
+
+fn generate_legacy_poldercast_id(rng: &mut OsRng) -> String {
+    let mut bytes: [u8; 24] = [0; 24];
+    rng.fill_bytes(&mut bytes);
+    hex::encode(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::node::configuration::legacy::{
+        config::TrustedPeer, LegacyNodeConfigConverter,
+    };
+    use jormungandr_lib::interfaces::NodeConfig as NewestNodeConfig;
+
+    const NEWEST_CONFIG: &str = r#"
+rest:
+  listen: "127.0.0.1:8443"
+p2p:
+  public_address: /ip4/127.0.0.1/tcp/3000
+  trusted_peers:
+    - address: /ip4/127.0.0.1/tcp/3001
+    - address: /ip4/127.0.0.1/tcp/3002
+  allow_private_addresses: true
+  topics_of_interest:
+    messages: high
+    blocks: high
+  policy: ~
+log: ~
+explorer:
+  enabled: false
+mempool: ~
+bootstrap_from_trusted_peers: true
+skip_bootstrap: false
+"#;
+
+    const NEWEST_CONFIG_WITH_IDS: &str = r#"
+rest:
+  listen: "127.0.0.1:8443"
+p2p:
+  public_address: /ip4/127.0.0.1/tcp/3000
+  trusted_peers:
+    - address: /ip4/127.0.0.1/tcp/3001
+      id: fedcba9876543210fedcba9876543210
+    - address: /ip4/127.0.0.1/tcp/3002
+  allow_private_addresses: true
+  topics_of_interest:
+    messages: high
+    blocks: high
+  policy: ~
+log: ~
+explorer:
+  enabled: false
+mempool: ~
+bootstrap_from_trusted_peers: true
+skip_bootstrap: false
+"#;
+
+    fn has_id(peer: &TrustedPeer) -> bool {
+        peer.id.is_some()
+    }
+
+    fn supported_versions() -> Vec<Version> {
+        vec![
+            Version::new(0, 8, 18),
+            version_0_8_19(),
+            Version::new(0, 9, 0),
+            newest_version(),
+        ]
+    }
+
+    #[test]
+    fn configs_round_trip_through_every_supported_version() {
+        let newest: NewestNodeConfig = serde_yaml::from_str(NEWEST_CONFIG).unwrap();
+        for version in supported_versions() {
+            let converter = LegacyNodeConfigConverter::new(version.clone());
+            let legacy = converter.convert(&newest).unwrap();
+            let ids_expected = version <= version_0_8_19();
+            assert!(
+                legacy
+                    .p2p
+                    .trusted_peers
+                    .iter()
+                    .all(|peer| peer.id.is_some() == ids_expected),
+                "trusted peer ids for {}",
+                version
+            );
+
+            let forward = converter.convert_forward(&legacy).unwrap();
+            assert_eq!(
+                serde_yaml::to_value(&forward).unwrap(),
+                serde_yaml::to_value(&newest).unwrap(),
+                "round trip through {}",
+                version
+            );
+        }
+    }
+
+    #[test]
+    fn trusted_peer_ids_are_dropped_after_0_8_19() {
+        let newest: NewestNodeConfig = serde_yaml::from_str(NEWEST_CONFIG_WITH_IDS).unwrap();
+        for version in vec![Version::new(0, 9, 0), newest_version()] {
+            let legacy = LegacyNodeConfigConverter::new(version.clone())
+                .convert(&newest)
+                .unwrap();
+            assert!(
+                !legacy.p2p.trusted_peers.iter().any(has_id),
+                "trusted peer ids for {}",
+                version
+            );
+        }
+    }
+
+    #[test]
+    fn trusted_peer_ids_are_kept_up_to_0_8_19() {
+        let newest: NewestNodeConfig = serde_yaml::from_str(NEWEST_CONFIG_WITH_IDS).unwrap();
+        let source_id = newest.p2p.trusted_peers[0].id.unwrap().to_string();
+        let legacy = LegacyNodeConfigConverter::new(version_0_8_19())
+            .convert(&newest)
+            .unwrap();
+        assert_eq!(legacy.p2p.trusted_peers[0].id, Some(source_id));
+        assert!(has_id(&legacy.p2p.trusted_peers[1]));
+    }
+
+    #[test]
+    fn migrations_chain_across_versions() {
+        let newest: NewestNodeConfig = serde_yaml::from_str(NEWEST_CONFIG).unwrap();
+        let mut config = LegacyNodeConfigConverter::new(Version::new(0, 8, 0))
+            .convert(&newest)
+            .unwrap();
+        migrate(&mut config, &Version::new(0, 8, 0), &version_0_8_19());
+        assert!(config.p2p.trusted_peers.iter().all(has_id));
+        migrate(&mut config, &version_0_8_19(), &Version::new(0, 9, 0));
+        assert!(!config.p2p.trusted_peers.iter().any(has_id));
+    }
+}
@@ -1,5 +1,6 @@
 mod config;
 mod configuration_builder;
+mod migration;
 mod node;
 
 pub use config::NodeConfig;
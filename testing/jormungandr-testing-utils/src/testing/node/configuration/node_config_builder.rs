@@ -3,8 +3,13 @@
 use std::path::PathBuf;
 
 use jormungandr_lib::{
+    crypto::hash::Hash,
     interfaces::{
-        Explorer, Log, Mempool, NodeConfig, P2p, Policy, Rest, Tls, TopicsOfInterest, TrustedPeer,
+        Backup, ClientAuth, ClockCheck, Cors, EpochHooks, Explorer, HandshakeNonces, LayersConfig,
+        Leadership, Listen, Log, Mempool, MethodLimits, NodeConfig, OverflowPolicies, P2p,
+        PeerSelectionStrategy, Policy, PortMapping, PreferredListConfig, Rest, RewardsHistory,
+        StreamLimits, Tls, TopicsOfInterest, Tracing, TrustedPeer, UnixSocket, VirtualHost,
+        VotePlanStatsConfig,
     },
     time::Duration,
 };
@@ -12,11 +17,24 @@ use jormungandr_lib::{
 #[derive(Debug, Clone)]
 pub struct NodeConfigBuilder {
     pub storage: Option<PathBuf>,
+    pub genesis_block_hash: Option<Hash>,
+    pub namespace_by_genesis: bool,
     pub log: Option<Log>,
+    pub tracing: Option<Tracing>,
+    pub leadership: Option<Leadership>,
     pub rest: Rest,
     pub p2p: P2p,
     pub mempool: Option<Mempool>,
     pub explorer: Explorer,
+    pub bootstrap_from_trusted_peers: Option<bool>,
+    pub skip_bootstrap: Option<bool>,
+    pub http_fetch_block0_service: Vec<String>,
+    pub no_blockchain_updates_warning_interval: Option<Duration>,
+    pub backup: Option<Backup>,
+    pub rewards_history: Option<RewardsHistory>,
+    pub vote_plan_stats: Option<VotePlanStatsConfig>,
+    pub epoch_hooks: Option<EpochHooks>,
+    pub clock_check: Option<ClockCheck>,
 }
 
 const DEFAULT_HOST: &str = "127.0.0.1";
@@ -41,20 +59,31 @@ impl NodeConfigBuilder {
 
         NodeConfigBuilder {
             storage: None,
+            genesis_block_hash: None,
+            namespace_by_genesis: false,
             log: None,
+            tracing: None,
+            leadership: None,
             rest: Rest {
                 listen: format!("{}:{}", DEFAULT_HOST, rest_port.to_string())
                     .parse()
                     .unwrap(),
                 tls: None,
                 cors: None,
+                admin_token: None,
+                reuse_port: false,
+                unix_socket: None,
+                virtual_host: None,
+                max_addresses_per_request: None,
             },
             p2p: P2p {
                 trusted_peers: vec![],
                 public_address: grpc_public_address,
                 listen_address: None,
+                listen: Vec::new(),
                 max_inbound_connections: None,
                 max_connections: None,
+                max_connections_threshold: None,
                 topics_of_interest: Some(TopicsOfInterest {
                     messages: String::from("high"),
                     blocks: String::from("high"),
@@ -62,13 +91,42 @@ impl NodeConfigBuilder {
                 allow_private_addresses: true,
                 policy: Some(Policy {
                     quarantine_duration: Some(Duration::new(1, 0)),
+                    max_quarantine: None,
+                    max_num_quarantine_records: None,
                     quarantine_whitelist: None,
                 }),
                 layers: None,
                 public_id: None,
+                reuse_port: false,
+                max_server_requests: None,
+                max_client_requests: None,
+                method_limits: None,
+                reply_timeout: None,
+                block_cache_size: None,
+                max_unreachable_nodes_to_connect_per_event: None,
+                gossip_interval: None,
+                gossip_fanout: None,
+                topology_force_reset_interval: None,
+                max_bootstrap_attempts: None,
+                stream_limits: None,
+                handshake_nonces: None,
+                overflow_policies: None,
+                block_fetch_strategy: None,
+                mempool_warmup: false,
+                port_mapping: None,
+                client_auth: None,
             },
             mempool: Some(Mempool::default()),
             explorer: Explorer { enabled: false },
+            bootstrap_from_trusted_peers: None,
+            skip_bootstrap: None,
+            http_fetch_block0_service: Vec::new(),
+            no_blockchain_updates_warning_interval: None,
+            backup: None,
+            rewards_history: None,
+            vote_plan_stats: None,
+            epoch_hooks: None,
+            clock_check: None,
         }
     }
 
@@ -102,6 +160,11 @@ impl NodeConfigBuilder {
         self
     }
 
+    pub fn with_listen(&mut self, listen: Vec<Listen>) -> &mut Self {
+        self.p2p.listen = listen;
+        self
+    }
+
     pub fn with_rest_tls_config(&mut self, tls: Tls) -> &mut Self {
         self.rest.tls = Some(tls);
         self
@@ -117,16 +180,342 @@ impl NodeConfigBuilder {
         self
     }
 
+    pub fn with_genesis_block_hash(&mut self, genesis_block_hash: Hash) -> &mut Self {
+        self.genesis_block_hash = Some(genesis_block_hash);
+        self
+    }
+
+    pub fn with_namespace_by_genesis(&mut self, namespace_by_genesis: bool) -> &mut Self {
+        self.namespace_by_genesis = namespace_by_genesis;
+        self
+    }
+
+    pub fn with_tracing(&mut self, tracing: Tracing) -> &mut Self {
+        self.tracing = Some(tracing);
+        self
+    }
+
+    pub fn with_leadership(&mut self, leadership: Leadership) -> &mut Self {
+        self.leadership = Some(leadership);
+        self
+    }
+
+    pub fn with_rest_cors(&mut self, cors: Cors) -> &mut Self {
+        self.rest.cors = Some(cors);
+        self
+    }
+
+    pub fn with_rest_admin_token(&mut self, admin_token: String) -> &mut Self {
+        self.rest.admin_token = Some(admin_token);
+        self
+    }
+
+    pub fn with_rest_reuse_port(&mut self) -> &mut Self {
+        self.rest.reuse_port = true;
+        self
+    }
+
+    pub fn with_rest_unix_socket(&mut self, unix_socket: UnixSocket) -> &mut Self {
+        self.rest.unix_socket = Some(unix_socket);
+        self
+    }
+
+    pub fn with_rest_virtual_host(&mut self, virtual_host: VirtualHost) -> &mut Self {
+        self.rest.virtual_host = Some(virtual_host);
+        self
+    }
+
+    pub fn with_topics_of_interest(&mut self, topics_of_interest: TopicsOfInterest) -> &mut Self {
+        self.p2p.topics_of_interest = Some(topics_of_interest);
+        self
+    }
+
+    pub fn with_allow_private_addresses(&mut self, allow_private_addresses: bool) -> &mut Self {
+        self.p2p.allow_private_addresses = allow_private_addresses;
+        self
+    }
+
+    pub fn with_max_connections(&mut self, max_connections: u32) -> &mut Self {
+        self.p2p.max_connections = Some(max_connections);
+        self
+    }
+
+    pub fn with_max_inbound_connections(&mut self, max_inbound_connections: u32) -> &mut Self {
+        self.p2p.max_inbound_connections = Some(max_inbound_connections);
+        self
+    }
+
+    pub fn with_max_connections_threshold(&mut self, threshold: u32) -> &mut Self {
+        self.p2p.max_connections_threshold = Some(threshold);
+        self
+    }
+
+    pub fn with_p2p_reuse_port(&mut self) -> &mut Self {
+        self.p2p.reuse_port = true;
+        self
+    }
+
+    pub fn with_quarantine_duration(&mut self, duration: Duration) -> &mut Self {
+        self.policy_mut().quarantine_duration = Some(duration);
+        self
+    }
+
+    pub fn with_max_quarantine(&mut self, duration: Duration) -> &mut Self {
+        self.policy_mut().max_quarantine = Some(duration);
+        self
+    }
+
+    pub fn with_max_num_quarantine_records(&mut self, records: usize) -> &mut Self {
+        self.policy_mut().max_num_quarantine_records = Some(records);
+        self
+    }
+
+    pub fn with_quarantine_whitelist(&mut self, whitelist: Vec<poldercast::Address>) -> &mut Self {
+        self.policy_mut().quarantine_whitelist = Some(whitelist);
+        self
+    }
+
+    fn policy_mut(&mut self) -> &mut Policy {
+        self.p2p.policy.get_or_insert_with(Policy::default)
+    }
+
+    pub fn with_layers(&mut self, layers: LayersConfig) -> &mut Self {
+        self.p2p.layers = Some(layers);
+        self
+    }
+
+    pub fn with_preferred_list(&mut self, view_max: usize, peers: Vec<TrustedPeer>) -> &mut Self {
+        self.with_layers(LayersConfig {
+            preferred_list: PreferredListConfig {
+                view_max: view_max.into(),
+                peers,
+            },
+        })
+    }
+
+    pub fn with_max_server_requests(&mut self, max_server_requests: usize) -> &mut Self {
+        self.p2p.max_server_requests = Some(max_server_requests);
+        self
+    }
+
+    pub fn with_max_client_requests(&mut self, max_client_requests: usize) -> &mut Self {
+        self.p2p.max_client_requests = Some(max_client_requests);
+        self
+    }
+
+    pub fn with_method_limits(&mut self, method_limits: MethodLimits) -> &mut Self {
+        self.p2p.method_limits = Some(method_limits);
+        self
+    }
+
+    pub fn with_reply_timeout(&mut self, reply_timeout: Duration) -> &mut Self {
+        self.p2p.reply_timeout = Some(reply_timeout);
+        self
+    }
+
+    pub fn with_block_cache_size(&mut self, block_cache_size: usize) -> &mut Self {
+        self.p2p.block_cache_size = Some(block_cache_size);
+        self
+    }
+
+    pub fn with_max_unreachable_nodes_to_connect_per_event(&mut self, max: usize) -> &mut Self {
+        self.p2p.max_unreachable_nodes_to_connect_per_event = Some(max);
+        self
+    }
+
+    pub fn with_gossip_interval(&mut self, gossip_interval: Duration) -> &mut Self {
+        self.p2p.gossip_interval = Some(gossip_interval);
+        self
+    }
+
+    pub fn with_gossip_fanout(&mut self, gossip_fanout: usize) -> &mut Self {
+        self.p2p.gossip_fanout = Some(gossip_fanout);
+        self
+    }
+
+    pub fn with_topology_force_reset_interval(&mut self, interval: Duration) -> &mut Self {
+        self.p2p.topology_force_reset_interval = Some(interval);
+        self
+    }
+
+    pub fn with_max_bootstrap_attempts(&mut self, max_bootstrap_attempts: usize) -> &mut Self {
+        self.p2p.max_bootstrap_attempts = Some(max_bootstrap_attempts);
+        self
+    }
+
+    pub fn with_stream_limits(&mut self, stream_limits: StreamLimits) -> &mut Self {
+        self.p2p.stream_limits = Some(stream_limits);
+        self
+    }
+
+    pub fn with_handshake_nonces(&mut self, handshake_nonces: HandshakeNonces) -> &mut Self {
+        self.p2p.handshake_nonces = Some(handshake_nonces);
+        self
+    }
+
+    pub fn with_overflow_policies(&mut self, overflow_policies: OverflowPolicies) -> &mut Self {
+        self.p2p.overflow_policies = Some(overflow_policies);
+        self
+    }
+
+    pub fn with_block_fetch_strategy(&mut self, strategy: PeerSelectionStrategy) -> &mut Self {
+        self.p2p.block_fetch_strategy = Some(strategy);
+        self
+    }
+
+    pub fn with_port_mapping(&mut self, port_mapping: PortMapping) -> &mut Self {
+        self.p2p.port_mapping = Some(port_mapping);
+        self
+    }
+
+    pub fn with_client_auth(&mut self, client_auth: ClientAuth) -> &mut Self {
+        self.p2p.client_auth = Some(client_auth);
+        self
+    }
+
+    /// fill the mempool from a trusted peer after bootstrap
+    pub fn with_mempool_warmup(&mut self, mempool_warmup: bool) -> &mut Self {
+        self.p2p.mempool_warmup = mempool_warmup;
+        self
+    }
+
+    /// by default the node bootstraps from the trusted peers if it has any
+    pub fn with_bootstrap_from_trusted_peers(&mut self, bootstrap: bool) -> &mut Self {
+        self.bootstrap_from_trusted_peers = Some(bootstrap);
+        self
+    }
+
+    /// by default the node skips the bootstrap if it has no trusted peers
+    pub fn with_skip_bootstrap(&mut self, skip_bootstrap: bool) -> &mut Self {
+        self.skip_bootstrap = Some(skip_bootstrap);
+        self
+    }
+
+    pub fn with_http_fetch_block0_service(&mut self, services: Vec<String>) -> &mut Self {
+        self.http_fetch_block0_service = services;
+        self
+    }
+
+    pub fn with_no_blockchain_updates_warning_interval(&mut self, interval: Duration) -> &mut Self {
+        self.no_blockchain_updates_warning_interval = Some(interval);
+        self
+    }
+
+    pub fn with_backup(&mut self, backup: Backup) -> &mut Self {
+        self.backup = Some(backup);
+        self
+    }
+
+    pub fn with_rewards_history(&mut self, rewards_history: RewardsHistory) -> &mut Self {
+        self.rewards_history = Some(rewards_history);
+        self
+    }
+
+    pub fn with_vote_plan_stats(&mut self, vote_plan_stats: VotePlanStatsConfig) -> &mut Self {
+        self.vote_plan_stats = Some(vote_plan_stats);
+        self
+    }
+
+    pub fn with_epoch_hooks(&mut self, epoch_hooks: EpochHooks) -> &mut Self {
+        self.epoch_hooks = Some(epoch_hooks);
+        self
+    }
+
+    pub fn with_clock_check(&mut self, clock_check: ClockCheck) -> &mut Self {
+        self.clock_check = Some(clock_check);
+        self
+    }
+
     pub fn build(&self) -> NodeConfig {
         NodeConfig {
             storage: self.storage.clone(),
+            genesis_block_hash: self.genesis_block_hash,
+            namespace_by_genesis: self.namespace_by_genesis,
             log: self.log.clone(),
+            tracing: self.tracing.clone(),
+            leadership: self.leadership.clone(),
             rest: self.rest.clone(),
             p2p: self.p2p.clone(),
             mempool: self.mempool.clone(),
             explorer: self.explorer.clone(),
-            bootstrap_from_trusted_peers: Some(!self.p2p.trusted_peers.is_empty()),
-            skip_bootstrap: Some(self.p2p.trusted_peers.is_empty()),
+            bootstrap_from_trusted_peers: Some(
+                self.bootstrap_from_trusted_peers
+                    .unwrap_or_else(|| !self.p2p.trusted_peers.is_empty()),
+            ),
+            skip_bootstrap: Some(
+                self.skip_bootstrap
+                    .unwrap_or_else(|| self.p2p.trusted_peers.is_empty()),
+            ),
+            http_fetch_block0_service: self.http_fetch_block0_service.clone(),
+            no_blockchain_updates_warning_interval: self.no_blockchain_updates_warning_interval,
+            backup: self.backup.clone(),
+            rewards_history: self.rewards_history.clone(),
+            vote_plan_stats: self.vote_plan_stats.clone(),
+            epoch_hooks: self.epoch_hooks.clone(),
+            clock_check: self.clock_check.clone(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jormungandr_lib::interfaces::{OverflowPolicy, PortMappingProtocol};
+
+    #[test]
+    fn options_are_serialized_when_set() {
+        let config = NodeConfigBuilder::new()
+            .with_max_quarantine(Duration::new(600, 0))
+            .with_preferred_list(5, vec![])
+            .with_gossip_fanout(3)
+            .with_max_bootstrap_attempts(0)
+            .with_rest_admin_token("token".to_owned())
+            .with_skip_bootstrap(false)
+            .build();
+        let yaml = serde_yaml::to_value(&config).unwrap();
+        assert_eq!(yaml["p2p"]["policy"]["max_quarantine"], "10m");
+        assert_eq!(yaml["p2p"]["layers"]["preferred_list"]["view_max"], 5);
+        assert_eq!(yaml["p2p"]["gossip_fanout"], 3);
+        assert_eq!(yaml["p2p"]["max_bootstrap_attempts"], 0);
+        assert_eq!(yaml["rest"]["admin_token"], "token");
+        assert_eq!(yaml["skip_bootstrap"], false);
+        assert!(yaml["p2p"].get("reply_timeout").is_none());
+    }
+
+    #[test]
+    fn node_settings_are_serialized_when_set() {
+        let config = NodeConfigBuilder::new()
+            .with_namespace_by_genesis(true)
+            .with_leadership(Leadership {
+                logs_capacity: 16,
+                signed_slots_file: None,
+            })
+            .with_block_fetch_strategy(PeerSelectionStrategy::RoundRobin)
+            .with_overflow_policies(OverflowPolicies {
+                fragments: Some(OverflowPolicy::DropOldest),
+                gossip: None,
+            })
+            .with_port_mapping(PortMapping {
+                protocol: Some(PortMappingProtocol::NatPmp),
+                lease_duration: None,
+                gateway: None,
+            })
+            .with_rewards_history(RewardsHistory { retention: 4 })
+            .with_clock_check(ClockCheck {
+                ntp_server: None,
+                max_skew: Some(Duration::new(2, 0)),
+            })
+            .build();
+        let yaml = serde_yaml::to_value(&config).unwrap();
+        assert_eq!(yaml["namespace_by_genesis"], true);
+        assert_eq!(yaml["leadership"]["logs_capacity"], 16);
+        assert_eq!(yaml["p2p"]["block_fetch_strategy"], "round_robin");
+        assert_eq!(yaml["p2p"]["overflow_policies"]["fragments"], "drop_oldest");
+        assert_eq!(yaml["p2p"]["port_mapping"]["protocol"], "nat_pmp");
+        assert_eq!(yaml["rewards_history"]["retention"], 4);
+        assert_eq!(yaml["clock_check"]["max_skew"], "2s");
+        assert!(yaml.get("backup").is_none());
+        assert!(yaml["p2p"].get("listen").is_none());
+        assert!(yaml["p2p"].get("client_auth").is_none());
+    }
+}
@@ -8,6 +8,9 @@ pub type NonZero = String;
 pub type VotePlanId = String;
 pub type PoolCount = String;
 pub type VotePlanCount = String;
+pub type TransactionCount = String;
+pub type VoteStatusCount = String;
+pub type ExternalProposalId = String;
 
 use graphql_client::GraphQLQuery;
 
@@ -35,6 +38,14 @@ pub struct AllBlocks;
 )]
 pub struct AllStakePools;
 
+#[derive(GraphQLQuery)]
+#[graphql(
+    query_path = "resources/explorer/graphql/block_by_id.graphql",
+    schema_path = "resources/explorer/graphql/schema.graphql",
+    response_derives = "Debug"
+)]
+pub struct BlockById;
+
 #[derive(GraphQLQuery)]
 #[graphql(
     query_path = "resources/explorer/graphql/blockbychainlength.graphql",
@@ -90,3 +101,11 @@ pub struct TransactionById;
     response_derives = "Debug"
 )]
 pub struct AllVotePlans;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    query_path = "resources/explorer/graphql/voteplan_by_id.graphql",
+    schema_path = "resources/explorer/graphql/schema.graphql",
+    response_derives = "Debug"
+)]
+pub struct VotePlanById;
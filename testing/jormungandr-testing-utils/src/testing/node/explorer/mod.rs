@@ -1,9 +1,10 @@
 use self::{
     client::GraphQLClient,
     data::{
-        address, all_blocks, all_stake_pools, all_vote_plans, block_by_chain_length, epoch,
-        last_block, stake_pool, status, transaction_by_id, Address, AllBlocks, AllStakePools,
-        AllVotePlans, BlockByChainLength, Epoch, LastBlock, StakePool, Status, TransactionById,
+        address, all_blocks, all_stake_pools, all_vote_plans, block_by_chain_length, block_by_id,
+        epoch, last_block, stake_pool, status, transaction_by_id, vote_plan_by_id, Address,
+        AllBlocks, AllStakePools, AllVotePlans, BlockByChainLength, BlockById, Epoch, LastBlock,
+        StakePool, Status, TransactionById, VotePlanById,
     },
 };
 use graphql_client::GraphQLQuery;
@@ -11,9 +12,9 @@ use graphql_client::*;
 use jormungandr_lib::crypto::hash::Hash;
 use std::str::FromStr;
 mod client;
-mod data;
+pub mod data;
 pub mod load;
-use data::PoolId;
+use data::{PoolId, VotePlanId};
 use jortestkit::file;
 use serde::Serialize;
 use std::path::Path;
@@ -111,6 +112,23 @@ impl Explorer {
         Ok(response_body)
     }
 
+    /// the block with its first `transactions_limit` transactions
+    pub fn block(
+        &self,
+        hash: Hash,
+        transactions_limit: i64,
+    ) -> Result<Response<block_by_id::ResponseData>, ExplorerError> {
+        let query = BlockById::build_query(block_by_id::Variables {
+            id: hash.to_string(),
+            transactions: transactions_limit,
+        });
+        self.print_request(&query);
+        let response = self.client.run(query).map_err(ExplorerError::ClientError)?;
+        let response_body = response.json()?;
+        self.print_log(&response_body);
+        Ok(response_body)
+    }
+
     pub fn block_at_chain_length(
         &self,
         length: u32,
@@ -175,6 +193,23 @@ impl Explorer {
         Ok(response_body)
     }
 
+    /// the vote plan with the first `votes_limit` votes of every proposal
+    pub fn vote_plan(
+        &self,
+        id: VotePlanId,
+        votes_limit: i64,
+    ) -> Result<Response<vote_plan_by_id::ResponseData>, ExplorerError> {
+        let query = VotePlanById::build_query(vote_plan_by_id::Variables {
+            id,
+            votes: votes_limit,
+        });
+        self.print_request(&query);
+        let response = self.client.run(query).map_err(ExplorerError::ClientError)?;
+        let response_body = response.json()?;
+        self.print_log(&response_body);
+        Ok(response_body)
+    }
+
     pub fn transaction(
         &self,
         hash: Hash,
@@ -1,6 +1,6 @@
 use crate::{
     testing::{
-        node::{RawRest, RestError, RestSettings},
+        node::{check_response, RawRest, RestError, RestSettings},
         MemPoolCheck,
     },
     wallet::Wallet,
@@ -8,7 +8,6 @@ use crate::{
 use chain_core::property::Fragment as _;
 use chain_impl_mockchain::fragment::{Fragment, FragmentId};
 use jormungandr_lib::{crypto::hash::Hash, interfaces::FragmentLog};
-use reqwest::blocking::Response;
 use std::collections::HashMap;
 
 /// Legacy tolerant rest api
@@ -38,12 +37,6 @@ impl BackwardCompatibleRest {
         }
     }
 
-    fn print_debug_response(&self, response: &Response) {
-        if self.settings.enable_debug {
-            println!("Response: {:?}", response);
-        }
-    }
-
     pub fn disable_logger(&mut self) {
         self.settings.enable_debug = false;
         self.raw.update_settings(self.settings.clone());
@@ -54,81 +47,92 @@ impl BackwardCompatibleRest {
         self.raw.update_settings(self.settings.clone());
     }
 
-    pub fn epoch_reward_history(&self, epoch: u32) -> Result<String, reqwest::Error> {
-        let response_text = self.raw().epoch_reward_history(epoch)?.text()?;
+    pub fn epoch_reward_history(&self, epoch: u32) -> Result<String, RestError> {
+        let response_text = check_response(self.raw().epoch_reward_history(epoch)?)?;
         self.print_response_text(&response_text);
         Ok(response_text)
     }
 
-    pub fn reward_history(&self, length: u32) -> Result<String, reqwest::Error> {
-        let response_text = self.raw().reward_history(length)?.text()?;
+    pub fn reward_history(&self, length: u32) -> Result<String, RestError> {
+        let response_text = check_response(self.raw().reward_history(length)?)?;
         self.print_response_text(&response_text);
         Ok(response_text)
     }
 
-    pub fn stake_distribution(&self) -> Result<String, reqwest::Error> {
-        let response_text = self.raw().stake_distribution()?.text()?;
+    pub fn stake_distribution(&self) -> Result<String, RestError> {
+        let response_text = check_response(self.raw().stake_distribution()?)?;
         self.print_response_text(&response_text);
         Ok(response_text)
     }
 
-    pub fn account_state(&self, wallet: &Wallet) -> Result<String, reqwest::Error> {
+    pub fn account_state(&self, wallet: &Wallet) -> Result<String, RestError> {
         self.account_state_by_pk(&wallet.identifier().to_bech32_str())
     }
 
-    pub fn account_state_by_pk(&self, bech32_str: &str) -> Result<String, reqwest::Error> {
-        let response_text = self.raw().account_state_by_pk(bech32_str)?.text()?;
+    pub fn account_state_by_pk(&self, bech32_str: &str) -> Result<String, RestError> {
+        let response_text = check_response(self.raw().account_state_by_pk(bech32_str)?)?;
         self.print_response_text(&response_text);
         Ok(response_text)
     }
 
-    pub fn stake_pools(&self) -> Result<String, reqwest::Error> {
-        let response_text = self.raw().stake_pools()?.text()?;
+    pub fn stake_pools(&self) -> Result<String, RestError> {
+        let response_text = check_response(self.raw().stake_pools()?)?;
         self.print_response_text(&response_text);
         Ok(response_text)
     }
 
-    pub fn stake_distribution_at(&self, epoch: u32) -> Result<String, reqwest::Error> {
-        let response_text = self.raw().stake_distribution_at(epoch)?.text()?;
+    pub fn stake_distribution_at(&self, epoch: u32) -> Result<String, RestError> {
+        let response_text = check_response(self.raw().stake_distribution_at(epoch)?)?;
         self.print_response_text(&response_text);
         Ok(response_text)
     }
 
-    pub fn stats(&self) -> Result<String, reqwest::Error> {
-        self.raw().stats()?.text()
+    pub fn stats(&self) -> Result<String, RestError> {
+        check_response(self.raw().stats()?)
     }
 
-    pub fn network_stats(&self) -> Result<String, reqwest::Error> {
-        self.raw().network_stats()?.text()
+    pub fn network_stats(&self) -> Result<String, RestError> {
+        check_response(self.raw().network_stats()?)
     }
 
-    pub fn p2p_quarantined(&self) -> Result<String, reqwest::Error> {
-        self.raw().p2p_quarantined()?.text()
+    pub fn p2p_quarantined(&self) -> Result<String, RestError> {
+        check_response(self.raw().p2p_quarantined()?)
     }
 
-    pub fn p2p_non_public(&self) -> Result<String, reqwest::Error> {
-        self.raw().p2p_non_public()?.text()
+    pub fn p2p_non_public(&self) -> Result<String, RestError> {
+        check_response(self.raw().p2p_non_public()?)
     }
 
-    pub fn p2p_available(&self) -> Result<String, reqwest::Error> {
-        self.raw().p2p_available()?.text()
+    pub fn p2p_available(&self) -> Result<String, RestError> {
+        check_response(self.raw().p2p_available()?)
     }
 
-    pub fn p2p_view(&self) -> Result<String, reqwest::Error> {
-        self.raw().p2p_view()?.text()
+    pub fn p2p_view(&self) -> Result<String, RestError> {
+        check_response(self.raw().p2p_view()?)
     }
 
-    pub fn leaders_log(&self) -> Result<String, reqwest::Error> {
-        self.raw().leaders_log()?.text()
+    pub fn leaders_log(&self) -> Result<String, RestError> {
+        check_response(self.raw().leaders_log()?)
     }
 
     pub fn tip(&self) -> Result<Hash, RestError> {
-        let tip = self.raw().tip()?.text()?;
+        let tip = check_response(self.raw().tip()?)?;
         tip.parse().map_err(RestError::HashParseError)
     }
 
+    /// the details of the fragment, `None` if the node does not know it
+    pub fn fragment_details(&self, fragment_id: &FragmentId) -> Result<Option<String>, RestError> {
+        let response = self.raw().fragment(fragment_id)?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response_text = check_response(response)?;
+        self.print_response_text(&response_text);
+        Ok(Some(response_text))
+    }
+
     pub fn fragment_logs(&self) -> Result<HashMap<FragmentId, FragmentLog>, RestError> {
-        let logs = self.raw().fragment_logs()?.text()?;
+        let logs = check_response(self.raw().fragment_logs()?)?;
         let logs: Vec<FragmentLog> = if logs.is_empty() {
             Vec::new()
         } else {
@@ -143,24 +147,21 @@ impl BackwardCompatibleRest {
         Ok(logs)
     }
 
-    pub fn leaders(&self) -> Result<String, reqwest::Error> {
-        self.raw().leaders()?.text()
+    pub fn leaders(&self) -> Result<String, RestError> {
+        check_response(self.raw().leaders()?)
     }
 
-    pub fn send_fragment(&self, fragment: Fragment) -> Result<MemPoolCheck, reqwest::Error> {
+    pub fn send_fragment(&self, fragment: Fragment) -> Result<MemPoolCheck, RestError> {
         let fragment_id = fragment.id();
-        let response = self.raw().send_fragment(fragment)?;
-        self.print_response_text(&response.text()?);
+        let response_text = check_response(self.raw().send_fragment(fragment)?)?;
+        self.print_response_text(&response_text);
         Ok(MemPoolCheck::new(fragment_id))
     }
 
-    pub fn send_raw_fragment(
-        &self,
-        body: Vec<u8>,
-    ) -> Result<reqwest::blocking::Response, reqwest::Error> {
-        let response = self.raw.send_raw_fragment(body)?;
-        self.print_debug_response(&response);
-        Ok(response)
+    pub fn send_raw_fragment(&self, body: Vec<u8>) -> Result<String, RestError> {
+        let response_text = check_response(self.raw.send_raw_fragment(body)?)?;
+        self.print_response_text(&response_text);
+        Ok(response_text)
     }
 
     pub fn send_fragment_batch(
@@ -171,12 +172,12 @@ impl BackwardCompatibleRest {
             .iter()
             .map(|x| MemPoolCheck::new(x.id()))
             .collect();
-        let response = self.raw.send_fragment_batch(fragments)?;
-        self.print_debug_response(&response);
+        let response_text = check_response(self.raw.send_fragment_batch(fragments)?)?;
+        self.print_response_text(&response_text);
         Ok(checks)
     }
 
-    pub fn vote_plan_statuses(&self) -> Result<String, reqwest::Error> {
-        self.raw().vote_plan_statuses()?.text()
+    pub fn vote_plan_statuses(&self) -> Result<String, RestError> {
+        check_response(self.raw().vote_plan_statuses()?)
     }
 }
@@ -0,0 +1,326 @@
+use super::{LogEntry, LogQuery, LoggerError};
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    process::Child,
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// When the capture files of a node are rotated: once the current file
+/// would grow past `max_file_bytes`, it is renamed with a `.1` suffix, the
+/// previous ones being shifted to `.2`, `.3` and so on, and only the
+/// `max_files` newest rotated files are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogRotation {
+    pub max_file_bytes: u64,
+    pub max_files: usize,
+}
+
+impl Default for LogRotation {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: 50 * 1024 * 1024,
+            max_files: 5,
+        }
+    }
+}
+
+/// The path of the `index`th rotated file of a log, 0 being the current one.
+pub fn rotated_log_path(path: &Path, index: usize) -> PathBuf {
+    if index == 0 {
+        return path.to_path_buf();
+    }
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}", index));
+    path.with_file_name(name)
+}
+
+/// The existing files of a rotated log, oldest first.
+pub fn rotated_log_files(path: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = (1..)
+        .map(|index| rotated_log_path(path, index))
+        .take_while(|path| path.exists())
+        .collect();
+    files.reverse();
+    if path.exists() {
+        files.push(path.to_path_buf());
+    }
+    files
+}
+
+struct RotatingFile {
+    path: PathBuf,
+    rotation: LogRotation,
+    file: File,
+    written: u64,
+}
+
+impl RotatingFile {
+    fn create(path: PathBuf, rotation: LogRotation) -> io::Result<Self> {
+        let file = File::create(&path)?;
+        Ok(Self {
+            path,
+            rotation,
+            file,
+            written: 0,
+        })
+    }
+
+    /// write the line right away, so nothing is lost if the test is killed
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.written > 0 && self.written + len > self.rotation.max_file_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.written += len;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let oldest = rotated_log_path(&self.path, self.rotation.max_files);
+        if oldest.exists() {
+            fs::remove_file(oldest)?;
+        }
+        for index in (0..self.rotation.max_files).rev() {
+            let from = rotated_log_path(&self.path, index);
+            if from.exists() {
+                fs::rename(from, rotated_log_path(&self.path, index + 1))?;
+            }
+        }
+        self.file = File::create(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+/// The number of read errors in a row after which the output of a node is
+/// no longer captured.
+const MAX_CONSECUTIVE_READ_ERRORS: usize = 16;
+
+type Subscribers = Arc<Mutex<Vec<Sender<String>>>>;
+
+/// Captures the standard output and error of a node into rotating files as
+/// the node writes them, and lets tests follow the lines live with
+/// `tail`. The child must have been spawned with both streams piped.
+pub struct LogCapture {
+    stdout_path: PathBuf,
+    stderr_path: PathBuf,
+    subscribers: Subscribers,
+}
+
+impl LogCapture {
+    pub fn start(
+        child: &mut Child,
+        stdout_path: impl Into<PathBuf>,
+        stderr_path: impl Into<PathBuf>,
+        rotation: LogRotation,
+    ) -> io::Result<Self> {
+        let capture = Self {
+            stdout_path: stdout_path.into(),
+            stderr_path: stderr_path.into(),
+            subscribers: Default::default(),
+        };
+        if let Some(stdout) = child.stdout.take() {
+            capture.spawn_reader(stdout, capture.stdout_path.clone(), rotation)?;
+        }
+        if let Some(stderr) = child.stderr.take() {
+            capture.spawn_reader(stderr, capture.stderr_path.clone(), rotation)?;
+        }
+        Ok(capture)
+    }
+
+    fn spawn_reader<R: Read + Send + 'static>(
+        &self,
+        stream: R,
+        path: PathBuf,
+        rotation: LogRotation,
+    ) -> io::Result<()> {
+        let mut file = RotatingFile::create(path, rotation)?;
+        let subscribers = Arc::clone(&self.subscribers);
+        thread::spawn(move || {
+            let mut reader = BufReader::new(stream);
+            let mut buf = Vec::new();
+            let mut errors = 0;
+            loop {
+                buf.clear();
+                // the node must never block on a full pipe, so the stream
+                // is drained until it is closed, whatever it contains
+                match reader.read_until(b'\n', &mut buf) {
+                    Ok(0) => break,
+                    Ok(_) => errors = 0,
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(err) => {
+                        errors += 1;
+                        if errors >= MAX_CONSECUTIVE_READ_ERRORS {
+                            eprintln!("cannot read node log for {:?}: {}", file.path, err);
+                            break;
+                        }
+                        continue;
+                    }
+                }
+                let line = String::from_utf8_lossy(&buf);
+                let line = line.trim_end_matches(|c| c == '\n' || c == '\r');
+                if let Err(err) = file.write_line(line) {
+                    eprintln!("cannot capture node log in {:?}: {}", file.path, err);
+                }
+                subscribers
+                    .lock()
+                    .unwrap()
+                    .retain(|subscriber| subscriber.send(line.to_owned()).is_ok());
+            }
+        });
+        Ok(())
+    }
+
+    pub fn stdout_path(&self) -> &Path {
+        &self.stdout_path
+    }
+
+    pub fn stderr_path(&self) -> &Path {
+        &self.stderr_path
+    }
+
+    /// follow the lines written by the node from now on, on both streams
+    pub fn tail(&self) -> LogTail {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.lock().unwrap().push(sender);
+        LogTail { receiver }
+    }
+}
+
+/// The lines written by a node since the tail was opened, in order.
+pub struct LogTail {
+    receiver: Receiver<String>,
+}
+
+impl LogTail {
+    /// the lines received so far and not read yet
+    pub fn lines(&self) -> impl Iterator<Item = String> + '_ {
+        self.receiver.try_iter()
+    }
+
+    /// the log entries received so far and not read yet, the lines which are
+    /// not log entries being skipped
+    pub fn entries(&self) -> impl Iterator<Item = LogEntry> + '_ {
+        self.lines()
+            .filter_map(|line| serde_json::from_str(&line).ok())
+    }
+
+    /// wait for a line fulfilling the predicate, the lines before it being
+    /// consumed
+    pub fn wait_for_line<P>(&self, predicate: P, timeout: Duration) -> Option<String>
+    where
+        P: Fn(&str) -> bool,
+    {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.receiver.recv_timeout(remaining) {
+                Ok(line) if predicate(&line) => return Some(line),
+                Ok(_) => continue,
+                Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => {
+                    return None
+                }
+            }
+        }
+    }
+
+    /// wait for a log entry matching the query, the lines before it being
+    /// consumed
+    pub fn wait_for_entry(
+        &self,
+        query: &LogQuery,
+        timeout: Duration,
+    ) -> Result<LogEntry, LoggerError> {
+        let matches = |line: &str| {
+            serde_json::from_str::<LogEntry>(line)
+                .map(|entry| query.matches(&entry))
+                .unwrap_or(false)
+        };
+        self.wait_for_line(matches, timeout)
+            .and_then(|line| serde_json::from_str(&line).ok())
+            .ok_or_else(|| LoggerError::EntryNotFound {
+                log_file: "live tail".to_string(),
+                timeout,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::TempDir;
+
+    #[test]
+    fn rotation_keeps_the_newest_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("node.log");
+        let mut file = RotatingFile::create(
+            path.clone(),
+            LogRotation {
+                max_file_bytes: 4,
+                max_files: 2,
+            },
+        )
+        .unwrap();
+        for line in &["a", "b", "c", "d", "e", "f", "g"] {
+            file.write_line(line).unwrap();
+        }
+
+        let contents: Vec<String> = rotated_log_files(&path)
+            .iter()
+            .map(|path| fs::read_to_string(path).unwrap())
+            .collect();
+        assert_eq!(contents, vec!["c\nd\n", "e\nf\n", "g\n"]);
+    }
+
+    #[test]
+    fn log_content_spans_the_rotated_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("node.log");
+        let mut file = RotatingFile::create(
+            path.clone(),
+            LogRotation {
+                max_file_bytes: 4,
+                max_files: 2,
+            },
+        )
+        .unwrap();
+        for line in &["a", "b", "c"] {
+            file.write_line(line).unwrap();
+        }
+
+        let logger = super::super::JormungandrLogger::new(path);
+        assert_eq!(logger.get_log_content(), "a\nb\nc");
+    }
+
+    #[test]
+    fn reader_keeps_the_lines_after_invalid_utf8() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("node.log");
+        let capture = LogCapture {
+            stdout_path: path.clone(),
+            stderr_path: temp_dir.path().join("node.err"),
+            subscribers: Default::default(),
+        };
+        let tail = capture.tail();
+        let output = b"first\r\n\xffsecond\nlast".to_vec();
+        capture
+            .spawn_reader(io::Cursor::new(output), path.clone(), Default::default())
+            .unwrap();
+
+        let last = tail.wait_for_line(|line| line == "last", Duration::from_secs(5));
+        assert_eq!(last.as_deref(), Some("last"));
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "first\n\u{fffd}second\nlast\n"
+        );
+    }
+}
@@ -3,20 +3,25 @@ extern crate serde;
 extern crate serde_json;
 
 use self::serde::{Deserialize, Serialize};
-use crate::testing::file as file_utils;
 use chain_core::property::FromStr;
 use chain_impl_mockchain::{block, key::Hash};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
+use super::log_capture::rotated_log_files;
 use crate::testing::Timestamp;
 use jormungandr_lib::{interfaces::BlockDate, time::SystemTime};
 #[derive(Debug, Error)]
 pub enum LoggerError {
     #[error("{log_file}")]
     LogFileDoesNotExist { log_file: String },
+    #[error("no expected entry in log {log_file} after {timeout:?}")]
+    EntryNotFound { log_file: String, timeout: Duration },
 }
 
 #[derive(Debug)]
@@ -24,7 +29,7 @@ pub struct JormungandrLogger {
     pub log_file_path: PathBuf,
 }
 
-#[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, Copy)]
 pub enum Level {
     WARN,
     INFO,
@@ -34,6 +39,10 @@ pub enum Level {
 }
 
 const SUCCESFULLY_CREATED_BLOCK_MSG: &str = "block from leader event successfully stored";
+const VALIDATED_BLOCK_MSG: &str = "validated block";
+
+/// how often the log file is read again while waiting for an entry
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(200);
 
 // TODO: convert strings to enums for level/task/
 // TODO: convert ts to DateTime
@@ -48,6 +57,9 @@ pub struct LogEntry {
     pub error: Option<String>,
     pub block_date: Option<String>,
     pub peer_addr: Option<String>,
+    /// the other key-value pairs of the entry
+    #[serde(flatten)]
+    pub fields: HashMap<String, JsonValue>,
 }
 
 impl LogEntry {
@@ -65,6 +77,25 @@ impl LogEntry {
         }
     }
 
+    /// the value of a key of the entry, the non-string values being
+    /// returned in their JSON representation
+    pub fn field(&self, key: &str) -> Option<String> {
+        match key {
+            "msg" => Some(self.msg.clone()),
+            "ts" => Some(self.ts.clone()),
+            "task" => self.task.clone(),
+            "hash" => self.hash.clone(),
+            "reason" => self.reason.clone(),
+            "error" => self.error.clone(),
+            "block_date" => self.block_date.clone(),
+            "peer_addr" => self.peer_addr.clone(),
+            key => self.fields.get(key).map(|value| match value {
+                JsonValue::String(value) => value.clone(),
+                value => value.to_string(),
+            }),
+        }
+    }
+
     pub fn block_date(&self) -> Option<BlockDate> {
         self.block_date
             .clone()
@@ -83,6 +114,88 @@ impl Into<Timestamp> for LogEntry {
     }
 }
 
+#[derive(Debug, Clone)]
+enum Matcher {
+    Equals(String),
+    Contains(String),
+}
+
+impl Matcher {
+    fn matches(&self, value: &str) -> bool {
+        match self {
+            Matcher::Equals(expected) => value == expected,
+            Matcher::Contains(part) => value.contains(part.as_str()),
+        }
+    }
+}
+
+/// Query on the structured log entries of a node, matching the entries
+/// which fulfill all of its criteria. An empty query matches any entry.
+///
+/// ```ignore
+/// let query = LogQuery::new()
+///     .level(Level::INFO)
+///     .message("connection to peer failed")
+///     .field_contains("error", "Block0Mismatch");
+/// assert!(logger.contains_entry(&query)?);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct LogQuery {
+    level: Option<Level>,
+    msg: Option<Matcher>,
+    fields: Vec<(String, Matcher)>,
+}
+
+impl LogQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    pub fn message<S: Into<String>>(mut self, msg: S) -> Self {
+        self.msg = Some(Matcher::Equals(msg.into()));
+        self
+    }
+
+    pub fn message_contains<S: Into<String>>(mut self, part: S) -> Self {
+        self.msg = Some(Matcher::Contains(part.into()));
+        self
+    }
+
+    pub fn task<S: Into<String>>(self, task: S) -> Self {
+        self.field("task", task)
+    }
+
+    pub fn field<K: Into<String>, S: Into<String>>(mut self, key: K, value: S) -> Self {
+        self.fields
+            .push((key.into(), Matcher::Equals(value.into())));
+        self
+    }
+
+    pub fn field_contains<K: Into<String>, S: Into<String>>(mut self, key: K, part: S) -> Self {
+        self.fields
+            .push((key.into(), Matcher::Contains(part.into())));
+        self
+    }
+
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        self.level.map_or(true, |level| entry.level == level)
+            && self
+                .msg
+                .as_ref()
+                .map_or(true, |matcher| matcher.matches(&entry.msg))
+            && self.fields.iter().all(|(key, matcher)| {
+                entry
+                    .field(key)
+                    .map_or(false, |value| matcher.matches(&value))
+            })
+    }
+}
+
 impl JormungandrLogger {
     pub fn new(path: impl Into<PathBuf>) -> Self {
         JormungandrLogger {
@@ -94,8 +207,17 @@ impl JormungandrLogger {
         vec!["panicked"]
     }
 
+    /// the content of the log, including the files rotated by a
+    /// `LogCapture`
     pub fn get_log_content(&self) -> String {
-        file_utils::read_file(&self.log_file_path)
+        let mut content: String = rotated_log_files(&self.log_file_path)
+            .iter()
+            .map(|path| std::fs::read_to_string(path).expect("cannot read file"))
+            .collect();
+        if content.ends_with('\n') {
+            content.pop();
+        }
+        content
     }
 
     pub fn get_lines_with_error(&self) -> impl Iterator<Item = String> + '_ {
@@ -118,8 +240,9 @@ impl JormungandrLogger {
     }
 
     pub fn last_validated_block_date(&self) -> Option<BlockDate> {
+        let query = LogQuery::new().message(VALIDATED_BLOCK_MSG);
         self.get_log_entries()
-            .filter(|x| x.msg.contains("validated block"))
+            .filter(|x| query.matches(x))
             .map(|x| x.block_date())
             .last()
             .unwrap_or(None)
@@ -171,10 +294,69 @@ impl JormungandrLogger {
     }
 
     fn filter_entries_with_block_creation(&self) -> impl Iterator<Item = LogEntry> + '_ {
-        let expected_task = Some("block".to_string());
-        self.get_log_entries().filter(move |x| {
-            x.msg == SUCCESFULLY_CREATED_BLOCK_MSG && x.task == expected_task && x.hash.is_some()
-        })
+        let query = LogQuery::new()
+            .message(SUCCESFULLY_CREATED_BLOCK_MSG)
+            .task("block");
+        self.get_log_entries()
+            .filter(move |x| query.matches(x) && x.hash.is_some())
+    }
+
+    pub fn entries_matching<'a>(
+        &'a self,
+        query: &'a LogQuery,
+    ) -> impl Iterator<Item = LogEntry> + 'a {
+        self.get_log_entries().filter(move |x| query.matches(x))
+    }
+
+    pub fn contains_entry(&self, query: &LogQuery) -> Result<bool, LoggerError> {
+        self.verify_file_exists()?;
+        Ok(self.entries_matching(query).next().is_some())
+    }
+
+    /// the error entries of the log not matched by any of the given queries
+    pub fn errors_except(&self, expected: &[LogQuery]) -> Vec<LogEntry> {
+        self.get_log_entries()
+            .filter(|x| x.level == Level::ERRO && !expected.iter().any(|query| query.matches(x)))
+            .collect()
+    }
+
+    /// panics if the log contains error entries other than the expected ones
+    pub fn assert_no_errors_except(&self, expected: &[LogQuery]) {
+        let errors = self.errors_except(expected);
+        assert!(
+            errors.is_empty(),
+            "there are some unexpected errors in log ({:?}): {:?}",
+            self.log_file_path,
+            errors
+        );
+    }
+
+    /// wait until an entry fulfilling the predicate is logged and return it,
+    /// reading the log again periodically until the timeout. The log file
+    /// does not need to exist when waiting starts.
+    pub fn wait_for_log_event<P>(
+        &self,
+        predicate: P,
+        timeout: Duration,
+    ) -> Result<LogEntry, LoggerError>
+    where
+        P: Fn(&LogEntry) -> bool,
+    {
+        let start = Instant::now();
+        loop {
+            if self.log_file_path.exists() {
+                if let Some(entry) = self.get_log_entries().find(|x| predicate(x)) {
+                    return Ok(entry);
+                }
+            }
+            if start.elapsed() > timeout {
+                return Err(LoggerError::EntryNotFound {
+                    log_file: self.log_file_path.to_string_lossy().to_string(),
+                    timeout,
+                });
+            }
+            std::thread::sleep(WAIT_POLL_INTERVAL);
+        }
     }
 
     fn is_error_line(&self, line: &str) -> bool {
@@ -203,10 +385,15 @@ impl JormungandrLogger {
     }
 
     pub fn get_lines_from_log(&self) -> impl Iterator<Item = String> {
-        let file = File::open(self.log_file_path.clone())
-            .unwrap_or_else(|_| panic!("cannot find log file: {:?}", &self.log_file_path));
-        let reader = BufReader::new(file);
-        reader.lines().map(|line| line.unwrap())
+        let files = rotated_log_files(&self.log_file_path);
+        if files.is_empty() {
+            panic!("cannot find log file: {:?}", &self.log_file_path);
+        }
+        files.into_iter().flat_map(|path| {
+            let file =
+                File::open(&path).unwrap_or_else(|_| panic!("cannot find log file: {:?}", &path));
+            BufReader::new(file).lines().map(|line| line.unwrap())
+        })
     }
 
     pub fn get_log_entries(&self) -> impl Iterator<Item = LogEntry> + '_ {
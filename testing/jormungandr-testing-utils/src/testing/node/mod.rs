@@ -2,6 +2,7 @@ mod benchmark;
 pub mod configuration;
 pub mod grpc;
 mod legacy;
+mod log_capture;
 mod logger;
 mod rest;
 pub mod time;
@@ -11,8 +12,10 @@ pub mod explorer;
 pub use benchmark::*;
 pub use explorer::{Explorer, ExplorerError};
 pub use legacy::{download_last_n_releases, get_jormungandr_bin, version_0_8_19, Version};
-pub use logger::{JormungandrLogger, Level, LogEntry};
+pub use log_capture::{rotated_log_files, rotated_log_path, LogCapture, LogRotation, LogTail};
+pub use logger::{JormungandrLogger, Level, LogEntry, LogQuery, LoggerError};
 pub use rest::{
-    uri_from_socket_addr, JormungandrRest, RawRest, RestError, RestRequestGen, RestSettings,
+    check_response, uri_from_socket_addr, AsyncJormungandrRest, JormungandrRest, NodeError,
+    NodeErrorKind, RawRest, RestError, RestRequestGen, RestSettings, RetryPolicy,
 };
 pub use verifier::JormungandrStateVerifier;
@@ -0,0 +1,175 @@
+use super::{
+    raw::{account_identifier, post_uri},
+    RestError, RestSettings, RetryPolicy,
+};
+use crate::{testing::MemPoolCheck, wallet::Wallet};
+use chain_core::property::Fragment as _;
+use chain_impl_mockchain::fragment::{Fragment, FragmentId};
+use jormungandr_client::RestClient;
+use jormungandr_lib::{
+    crypto::hash::Hash,
+    interfaces::{
+        AccountState, EnclaveLeaderId, EpochRewardsInfo, FragmentDetails, FragmentLog,
+        LeadershipLog, NodeStatsDto, PeerRecord, PeerStats, StakeDistributionDto, VotePlanStatus,
+    },
+};
+use std::{collections::HashMap, future::Future};
+
+/// Asynchronous counterpart of `JormungandrRest`, for driving many nodes
+/// concurrently from a single thread. It is a thin layer over the
+/// `jormungandr-client` crate, talking to the current node api only, and
+/// shares the pooled client of its settings with every clone.
+#[derive(Debug, Clone)]
+pub struct AsyncJormungandrRest {
+    uri: String,
+    settings: RestSettings,
+}
+
+impl AsyncJormungandrRest {
+    pub fn new(uri: String) -> Self {
+        Self::new_with_custom_settings(uri, Default::default())
+    }
+
+    pub fn new_with_custom_settings(uri: String, settings: RestSettings) -> Self {
+        Self { uri, settings }
+    }
+
+    /// call the node again, following the policy, while the call fails
+    /// with an error which may go away
+    pub async fn with_retry<'a, T, F, Fut>(
+        &'a self,
+        policy: &RetryPolicy,
+        action: F,
+    ) -> Result<T, RestError>
+    where
+        F: Fn(&'a AsyncJormungandrRest) -> Fut,
+        Fut: Future<Output = Result<T, RestError>>,
+    {
+        policy.run_async(|| action(self)).await
+    }
+
+    fn client(&self) -> Result<RestClient, RestError> {
+        Ok(RestClient::with_client(
+            self.uri.clone(),
+            self.settings.async_client()?,
+        ))
+    }
+
+    /// the client for the fragment submissions, which may have to go
+    /// through https
+    fn post_client(&self) -> Result<RestClient, RestError> {
+        Ok(RestClient::with_client(
+            post_uri(&self.uri, &self.settings),
+            self.settings.async_client()?,
+        ))
+    }
+
+    pub async fn epoch_reward_history(&self, epoch: u32) -> Result<EpochRewardsInfo, RestError> {
+        Ok(self.client()?.epoch_reward_history(epoch).await?)
+    }
+
+    pub async fn reward_history(&self, length: u32) -> Result<Vec<EpochRewardsInfo>, RestError> {
+        Ok(self.client()?.reward_history(length).await?)
+    }
+
+    pub async fn stake_distribution(&self) -> Result<StakeDistributionDto, RestError> {
+        Ok(self.client()?.stake_distribution().await?)
+    }
+
+    pub async fn stake_distribution_at(
+        &self,
+        epoch: u32,
+    ) -> Result<StakeDistributionDto, RestError> {
+        Ok(self.client()?.stake_distribution_at(epoch).await?)
+    }
+
+    pub async fn stake_pools(&self) -> Result<Vec<String>, RestError> {
+        Ok(self.client()?.stake_pools().await?)
+    }
+
+    pub async fn stats(&self) -> Result<NodeStatsDto, RestError> {
+        Ok(self.client()?.stats().await?)
+    }
+
+    pub async fn account_state(&self, wallet: &Wallet) -> Result<AccountState, RestError> {
+        self.account_state_by_pk(&wallet.identifier().to_bech32_str())
+            .await
+    }
+
+    pub async fn account_state_by_pk(&self, bech32_str: &str) -> Result<AccountState, RestError> {
+        let identifier = account_identifier(bech32_str);
+        Ok(self.client()?.account_state(&identifier).await?)
+    }
+
+    pub async fn network_stats(&self) -> Result<Vec<PeerStats>, RestError> {
+        Ok(self.client()?.network_stats().await?)
+    }
+
+    pub async fn p2p_quarantined(&self) -> Result<Vec<PeerRecord>, RestError> {
+        Ok(self.client()?.p2p_quarantined().await?)
+    }
+
+    pub async fn p2p_non_public(&self) -> Result<Vec<PeerRecord>, RestError> {
+        Ok(self.client()?.p2p_non_public().await?)
+    }
+
+    pub async fn p2p_available(&self) -> Result<Vec<PeerRecord>, RestError> {
+        Ok(self.client()?.p2p_available().await?)
+    }
+
+    pub async fn p2p_view(&self) -> Result<Vec<String>, RestError> {
+        Ok(self.client()?.p2p_view().await?)
+    }
+
+    pub async fn tip(&self) -> Result<Hash, RestError> {
+        Ok(self.client()?.tip().await?)
+    }
+
+    pub async fn fragment_logs(&self) -> Result<HashMap<FragmentId, FragmentLog>, RestError> {
+        let logs = self.client()?.fragment_logs().await?;
+        Ok(logs
+            .into_iter()
+            .map(|log| (log.fragment_id().clone().into_hash(), log))
+            .collect())
+    }
+
+    /// the details of the fragment, `None` if the node does not know it
+    pub async fn fragment_details(
+        &self,
+        fragment_id: &FragmentId,
+    ) -> Result<Option<FragmentDetails>, RestError> {
+        Ok(self.client()?.fragment_details(fragment_id).await?)
+    }
+
+    pub async fn leaders_log(&self) -> Result<Vec<LeadershipLog>, RestError> {
+        Ok(self.client()?.leaders_log().await?)
+    }
+
+    pub async fn leaders(&self) -> Result<Vec<EnclaveLeaderId>, RestError> {
+        Ok(self.client()?.leaders().await?)
+    }
+
+    pub async fn send_fragment(&self, fragment: Fragment) -> Result<MemPoolCheck, RestError> {
+        let fragment_id = self.post_client()?.send_fragment(&fragment).await?;
+        Ok(MemPoolCheck::new(fragment_id))
+    }
+
+    pub async fn send_raw_fragment(&self, bytes: Vec<u8>) -> Result<(), RestError> {
+        Ok(self.post_client()?.send_raw_fragment(bytes).await?)
+    }
+
+    pub async fn send_fragment_batch(
+        &self,
+        fragments: Vec<Fragment>,
+    ) -> Result<Vec<MemPoolCheck>, RestError> {
+        self.post_client()?.send_fragment_batch(&fragments).await?;
+        Ok(fragments
+            .iter()
+            .map(|fragment| MemPoolCheck::new(fragment.id()))
+            .collect())
+    }
+
+    pub async fn vote_plan_statuses(&self) -> Result<Vec<VotePlanStatus>, RestError> {
+        Ok(self.client()?.vote_plan_statuses().await?)
+    }
+}
@@ -1,18 +1,22 @@
+mod async_rest;
 mod load;
 mod raw;
 mod settings;
 
+pub use async_rest::AsyncJormungandrRest;
+pub use jormungandr_client::{NodeError, NodeErrorKind, RetryPolicy};
 pub use load::RestRequestGen;
-pub use raw::RawRest;
+pub use raw::{check_response, RawRest};
 pub use settings::RestSettings;
 
 use crate::{testing::node::legacy, testing::MemPoolCheck, wallet::Wallet};
 use chain_impl_mockchain::fragment::{Fragment, FragmentId};
+use jormungandr_client::{ClientError, Retryable};
 use jormungandr_lib::{
     crypto::hash::Hash,
     interfaces::{
-        AccountState, EnclaveLeaderId, EpochRewardsInfo, FragmentLog, LeadershipLog, NodeStatsDto,
-        PeerRecord, PeerStats, StakeDistributionDto, VotePlanStatus,
+        AccountState, EnclaveLeaderId, EpochRewardsInfo, FragmentDetails, FragmentLog,
+        LeadershipLog, NodeStatsDto, PeerRecord, PeerStats, StakeDistributionDto, VotePlanStatus,
     },
 };
 use std::collections::HashMap;
@@ -30,6 +34,57 @@ pub enum RestError {
     HashParseError(#[from] chain_crypto::hash::Error),
     #[error("error while polling endpoint")]
     PollError(#[from] jortestkit::process::WaitError),
+    #[error(transparent)]
+    NodeError(#[from] NodeError),
+    #[error("cannot serialize fragment")]
+    FragmentSerialization(#[source] std::io::Error),
+    #[error("request still failing after {attempts} attempts")]
+    RetriesExhausted {
+        attempts: u32,
+        #[source]
+        last: Box<RestError>,
+    },
+}
+
+impl RestError {
+    /// whether the same request may succeed later, the node being
+    /// unreachable or failing for now
+    pub fn is_transient(&self) -> bool {
+        match self {
+            RestError::RequestError(error) => error.is_connect() || error.is_timeout(),
+            RestError::NodeError(error) => error.is_transient(),
+            _ => false,
+        }
+    }
+}
+
+impl Retryable for RestError {
+    fn is_transient(&self) -> bool {
+        RestError::is_transient(self)
+    }
+
+    fn retries_exhausted(attempts: u32, last: Self) -> Self {
+        RestError::RetriesExhausted {
+            attempts,
+            last: Box::new(last),
+        }
+    }
+}
+
+impl From<ClientError> for RestError {
+    fn from(error: ClientError) -> Self {
+        match error {
+            ClientError::Request(error) => RestError::RequestError(error),
+            ClientError::Node(error) => RestError::NodeError(error),
+            ClientError::CannotDeserialize(error) => RestError::CannotDeserialize(error),
+            ClientError::HashParse(error) => RestError::HashParseError(error),
+            ClientError::FragmentSerialization(error) => RestError::FragmentSerialization(error),
+            ClientError::RetriesExhausted { attempts, last } => RestError::RetriesExhausted {
+                attempts,
+                last: Box::new((*last).into()),
+            },
+        }
+    }
 }
 
 pub fn uri_from_socket_addr(addr: SocketAddr) -> String {
@@ -67,6 +122,24 @@ impl JormungandrRest {
         self.inner.raw()
     }
 
+    /// call the node again, following the policy, while the call fails
+    /// with an error which may go away
+    pub fn with_retry<T, F>(&self, policy: &RetryPolicy, action: F) -> Result<T, RestError>
+    where
+        F: Fn(&JormungandrRest) -> Result<T, RestError>,
+    {
+        policy.run(|| action(self))
+    }
+
+    /// an asynchronous client for the same node, sharing the connection
+    /// pool of this one
+    pub fn to_async(&self) -> AsyncJormungandrRest {
+        AsyncJormungandrRest::new_with_custom_settings(
+            self.raw().uri().to_string(),
+            self.raw().settings().clone(),
+        )
+    }
+
     pub fn new_with_cert<P: AsRef<Path>>(uri: String, cert_file: P) -> Self {
         //replace http with https
         //replace localhost ip to localhost
@@ -75,7 +148,7 @@ impl JormungandrRest {
             .replace("127.0.0.1", "localhost");
 
         let mut settings: RestSettings = Default::default();
-        settings.certificate = Some(Self::extract_certificate(cert_file.as_ref()));
+        settings.set_certificate(Self::extract_certificate(cert_file.as_ref()));
 
         Self {
             inner: legacy::BackwardCompatibleRest::new(url, settings),
@@ -124,9 +197,7 @@ impl JormungandrRest {
     }
 
     pub fn account_state_by_pk_raw(&self, bech32_str: &str) -> Result<String, RestError> {
-        self.inner
-            .account_state_by_pk(bech32_str)
-            .map_err(Into::into)
+        self.inner.account_state_by_pk(bech32_str)
     }
 
     pub fn account_state_by_pk(&self, bech32_str: &str) -> Result<AccountState, RestError> {
@@ -162,6 +233,18 @@ impl JormungandrRest {
         self.inner.fragment_logs()
     }
 
+    pub fn fragment_details(
+        &self,
+        fragment_id: &FragmentId,
+    ) -> Result<Option<FragmentDetails>, RestError> {
+        match self.inner.fragment_details(fragment_id)? {
+            Some(details) => serde_json::from_str(&details)
+                .map(Some)
+                .map_err(RestError::CannotDeserialize),
+            None => Ok(None),
+        }
+    }
+
     pub fn leaders_log(&self) -> Result<Vec<LeadershipLog>, RestError> {
         serde_json::from_str(&self.inner.leaders_log()?).map_err(RestError::CannotDeserialize)
     }
@@ -177,7 +260,7 @@ impl JormungandrRest {
     }
 
     pub fn send_fragment(&self, fragment: Fragment) -> Result<MemPoolCheck, RestError> {
-        self.inner.send_fragment(fragment).map_err(Into::into)
+        self.inner.send_fragment(fragment)
     }
 
     pub fn send_raw_fragment(&self, bytes: Vec<u8>) -> Result<(), RestError> {
@@ -189,9 +272,7 @@ impl JormungandrRest {
         &self,
         fragments: Vec<Fragment>,
     ) -> Result<Vec<MemPoolCheck>, RestError> {
-        self.inner
-            .send_fragment_batch(fragments)
-            .map_err(Into::into)
+        self.inner.send_fragment_batch(fragments)
     }
 
     pub fn vote_plan_statuses(&self) -> Result<Vec<VotePlanStatus>, RestError> {
@@ -1,10 +1,10 @@
-use super::RestSettings;
+use super::{NodeError, RestSettings, RetryPolicy};
 use crate::{testing::node::RestError, wallet::Wallet};
 use bech32::FromBase32;
 use chain_core::property::Serialize;
 use chain_crypto::PublicKey;
 use chain_impl_mockchain::account;
-use chain_impl_mockchain::fragment::Fragment;
+use chain_impl_mockchain::fragment::{Fragment, FragmentId};
 use jortestkit::process::Wait;
 use reqwest::{
     blocking::Response,
@@ -40,6 +40,14 @@ impl RawRest {
         Self { uri, settings }
     }
 
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    pub fn settings(&self) -> &RestSettings {
+        &self.settings
+    }
+
     pub fn update_settings(&mut self, settings: RestSettings) {
         self.settings = settings;
     }
@@ -63,35 +71,15 @@ impl RawRest {
     fn get(&self, path: &str) -> Result<reqwest::blocking::Response, reqwest::Error> {
         let request = self.path(path);
         self.print_request_path(&request);
-        match &self.settings.certificate {
-            None => reqwest::blocking::get(&request),
-            Some(cert) => {
-                let client = reqwest::blocking::Client::builder()
-                    .use_rustls_tls()
-                    .add_root_certificate(cert.clone())
-                    .build()
-                    .unwrap();
-                client.get(&request).send()
-            }
-        }
+        self.settings.blocking_client()?.get(&request).send()
     }
 
     fn path(&self, path: &str) -> String {
-        format!("{}/v0/{}", self.uri, path)
+        get_path(&self.uri, path)
     }
 
     fn path_http_or_https(&self, path: &str, api_version: ApiVersion) -> String {
-        if self.settings.use_https_for_post {
-            let url = url::Url::parse(&self.uri).unwrap();
-            return format!(
-                "https://{}:443/{}/{}/{}",
-                url.domain().unwrap(),
-                url.path_segments().unwrap().next().unwrap(),
-                api_version.to_string(),
-                path
-            );
-        }
-        format!("{}/{}/{}", self.uri, api_version, path)
+        post_path(&self.uri, &self.settings, path, api_version)
     }
 
     pub fn stake_distribution(&self) -> Result<Response, reqwest::Error> {
@@ -103,15 +91,7 @@ impl RawRest {
     }
 
     pub fn account_state_by_pk(&self, bech32_str: &str) -> Result<Response, reqwest::Error> {
-        let key = hex::encode(Self::try_from_str(bech32_str).as_ref().as_ref());
-        self.get(&format!("account/{}", key))
-    }
-
-    fn try_from_str(src: &str) -> account::Identifier {
-        let (_, data) = bech32::decode(src).unwrap();
-        let dat = Vec::from_base32(&data).unwrap();
-        let pk = PublicKey::from_binary(&dat).unwrap();
-        account::Identifier::from(pk)
+        self.get(&format!("account/{}", account_key(bech32_str)))
     }
 
     pub fn stake_pools(&self) -> Result<Response, reqwest::Error> {
@@ -159,17 +139,12 @@ impl RawRest {
         self.get("fragment/logs")
     }
 
-    pub fn leaders(&self) -> Result<Response, reqwest::Error> {
-        self.get("leaders")
+    pub fn fragment(&self, fragment_id: &FragmentId) -> Result<Response, reqwest::Error> {
+        self.get(&format!("fragment/{}", fragment_id))
     }
 
-    fn construct_headers(&self) -> HeaderMap {
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            CONTENT_TYPE,
-            HeaderValue::from_static("application/octet-stream"),
-        );
-        headers
+    pub fn leaders(&self) -> Result<Response, reqwest::Error> {
+        self.get("leaders")
     }
 
     fn post(
@@ -177,11 +152,10 @@ impl RawRest {
         path: &str,
         body: Vec<u8>,
     ) -> Result<reqwest::blocking::Response, reqwest::Error> {
-        let builder = reqwest::blocking::Client::builder();
-        let client = builder.build()?;
-        client
+        self.settings
+            .blocking_client()?
             .post(&self.path_http_or_https(path, ApiVersion::V0))
-            .headers(self.construct_headers())
+            .headers(construct_headers())
             .body(body)
             .send()
     }
@@ -202,21 +176,11 @@ impl RawRest {
         &self,
         fragments: Vec<Fragment>,
     ) -> Result<Response, reqwest::Error> {
-        let builder = reqwest::blocking::Client::builder();
-        let client = builder.build()?;
-        client
+        self.settings
+            .blocking_client()?
             .post(&self.path_http_or_https("fragments", ApiVersion::V1))
-            .headers(self.construct_headers())
-            .json(
-                &fragments
-                    .iter()
-                    .map(|x| {
-                        std::str::from_utf8(&x.serialize_as_vec().unwrap())
-                            .unwrap()
-                            .to_string()
-                    })
-                    .collect::<Vec<String>>(),
-            )
+            .headers(construct_headers())
+            .json(&fragment_batch_body(&fragments))
             .send()
     }
 
@@ -224,6 +188,15 @@ impl RawRest {
         self.get("vote/active/plans")
     }
 
+    /// the text of the first successful response, calling the node again
+    /// following the policy while it fails with an error which may go away
+    pub fn send_with_retry<F>(&self, policy: &RetryPolicy, action: F) -> Result<String, RestError>
+    where
+        F: Fn(&RawRest) -> Result<Response, reqwest::Error>,
+    {
+        policy.run(|| check_response(action(&self)?))
+    }
+
     pub fn send_until_ok<F>(&self, action: F, mut wait: Wait) -> Result<(), RestError>
     where
         F: Fn(&RawRest) -> Result<Response, reqwest::Error>,
@@ -241,3 +214,68 @@ impl RawRest {
         }
     }
 }
+
+fn get_path(uri: &str, path: &str) -> String {
+    format!("{}/v0/{}", uri, path)
+}
+
+fn post_path(uri: &str, settings: &RestSettings, path: &str, api_version: ApiVersion) -> String {
+    format!("{}/{}/{}", post_uri(uri, settings), api_version, path)
+}
+
+/// the address of the api to post to, which may be served over https only
+pub(super) fn post_uri(uri: &str, settings: &RestSettings) -> String {
+    if settings.use_https_for_post {
+        let url = url::Url::parse(uri).unwrap();
+        return format!(
+            "https://{}:443/{}",
+            url.domain().unwrap(),
+            url.path_segments().unwrap().next().unwrap(),
+        );
+    }
+    uri.to_string()
+}
+
+/// the text of a successful response, or the error the node replied with
+pub fn check_response(response: Response) -> Result<String, RestError> {
+    let status = response.status();
+    let body = response.text()?;
+    if status.is_success() {
+        Ok(body)
+    } else {
+        Err(NodeError::parse(status, &body).into())
+    }
+}
+
+fn construct_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/octet-stream"),
+    );
+    headers
+}
+
+fn fragment_batch_body(fragments: &[Fragment]) -> Vec<String> {
+    fragments
+        .iter()
+        .map(|x| {
+            std::str::from_utf8(&x.serialize_as_vec().unwrap())
+                .unwrap()
+                .to_string()
+        })
+        .collect()
+}
+
+/// the account identifier of a bech32 encoded account public key
+pub(super) fn account_identifier(bech32_str: &str) -> account::Identifier {
+    let (_, data) = bech32::decode(bech32_str).unwrap();
+    let dat = Vec::from_base32(&data).unwrap();
+    let pk = PublicKey::from_binary(&dat).unwrap();
+    account::Identifier::from(pk)
+}
+
+/// the hex encoded account identifier of a bech32 encoded account public key
+fn account_key(bech32_str: &str) -> String {
+    hex::encode(account_identifier(bech32_str).as_ref().as_ref())
+}
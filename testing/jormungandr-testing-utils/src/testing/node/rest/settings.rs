@@ -1,19 +1,69 @@
 use reqwest::Certificate;
+use std::sync::{Arc, Mutex};
 
 #[derive(Debug, Clone)]
 pub struct RestSettings {
     pub enable_debug: bool,
     pub use_https_for_post: bool,
-    pub certificate: Option<Certificate>,
+    certificate: Option<Certificate>,
+    clients: Clients,
+}
+
+/// The http clients shared by all the clones of the settings, so the
+/// connections to a node are pooled and reused across requests instead of
+/// opening a new one each time. They are built on first use, and built
+/// again after the certificate changes.
+#[derive(Debug, Clone, Default)]
+struct Clients {
+    blocking: Arc<Mutex<Option<reqwest::blocking::Client>>>,
+    asynchronous: Arc<Mutex<Option<reqwest::Client>>>,
 }
 
 impl RestSettings {
     pub fn new_use_https_for_post() -> Self {
         RestSettings {
-            enable_debug: false,
             use_https_for_post: true,
-            certificate: None,
+            ..Default::default()
+        }
+    }
+
+    pub fn certificate(&self) -> Option<&Certificate> {
+        self.certificate.as_ref()
+    }
+
+    /// trust the given certificate. The clients already built do not, so
+    /// these settings stop sharing them with their previous clones.
+    pub fn set_certificate(&mut self, certificate: Certificate) {
+        self.certificate = Some(certificate);
+        self.clients = Clients::default();
+    }
+
+    pub fn blocking_client(&self) -> Result<reqwest::blocking::Client, reqwest::Error> {
+        let mut client = self.clients.blocking.lock().unwrap();
+        if let Some(client) = client.as_ref() {
+            return Ok(client.clone());
+        }
+        let mut builder = reqwest::blocking::Client::builder();
+        if let Some(cert) = &self.certificate {
+            builder = builder.use_rustls_tls().add_root_certificate(cert.clone());
         }
+        let new_client = builder.build()?;
+        *client = Some(new_client.clone());
+        Ok(new_client)
+    }
+
+    pub fn async_client(&self) -> Result<reqwest::Client, reqwest::Error> {
+        let mut client = self.clients.asynchronous.lock().unwrap();
+        if let Some(client) = client.as_ref() {
+            return Ok(client.clone());
+        }
+        let mut builder = reqwest::Client::builder();
+        if let Some(cert) = &self.certificate {
+            builder = builder.use_rustls_tls().add_root_certificate(cert.clone());
+        }
+        let new_client = builder.build()?;
+        *client = Some(new_client.clone());
+        Ok(new_client)
     }
 }
 
@@ -23,6 +73,45 @@ impl Default for RestSettings {
             enable_debug: false,
             use_https_for_post: false,
             certificate: None,
+            clients: Default::default(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_the_clients() {
+        let settings = RestSettings::default();
+        let clone = settings.clone();
+        settings.blocking_client().unwrap();
+        assert!(clone.clients.blocking.lock().unwrap().is_some());
+        assert!(clone.clients.asynchronous.lock().unwrap().is_none());
+        clone.async_client().unwrap();
+        assert!(settings.clients.asynchronous.lock().unwrap().is_some());
+    }
+
+    #[test]
+    fn setting_the_certificate_resets_the_clients() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../jormungandr-integration-tests/resources/tls/ca.crt");
+        let certificate = Certificate::from_pem(&std::fs::read(path).unwrap()).unwrap();
+
+        let mut settings = RestSettings::default();
+        settings.blocking_client().unwrap();
+        let clone = settings.clone();
+        settings.set_certificate(certificate);
+        assert!(settings.certificate().is_some());
+        assert!(settings.clients.blocking.lock().unwrap().is_none());
+        assert!(clone.certificate().is_none());
+        assert!(clone.clients.blocking.lock().unwrap().is_some());
+
+        settings.blocking_client().unwrap();
+        assert!(!Arc::ptr_eq(
+            &settings.clients.blocking,
+            &clone.clients.blocking
+        ));
+    }
+}
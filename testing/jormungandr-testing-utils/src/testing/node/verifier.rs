@@ -1,10 +1,11 @@
 use super::JormungandrRest;
 use crate::wallet::Wallet;
-use jormungandr_lib::interfaces::{AccountState, Value};
+use jormungandr_lib::interfaces::{AccountState, Value, VotePlanStatus};
 
 pub struct JormungandrStateVerifier {
     rest: JormungandrRest,
     snapshot_before: Option<StateSnapshot>,
+    vote_plans_before: Option<Vec<VotePlanStatus>>,
 }
 
 impl JormungandrStateVerifier {
@@ -12,6 +13,7 @@ impl JormungandrStateVerifier {
         Self {
             rest,
             snapshot_before: None,
+            vote_plans_before: None,
         }
     }
 
@@ -32,6 +34,60 @@ impl JormungandrStateVerifier {
         self
     }
 
+    pub fn record_vote_plans_state(mut self) -> Self {
+        self.vote_plans_before = Some(
+            self.rest
+                .vote_plan_statuses()
+                .expect("cannot retrieve vote plan statuses"),
+        );
+        self
+    }
+
+    /// no vote was counted on any proposal since the vote plans state was
+    /// recorded
+    pub fn votes_cast_unchanged(&self) -> Result<(), StateVerifierError> {
+        let before = self
+            .vote_plans_before
+            .as_ref()
+            .ok_or(StateVerifierError::NoSnapshot)?;
+        let after = self.rest.vote_plan_statuses()?;
+        for vote_plan in before {
+            let id = vote_plan.id.to_string();
+            let current = after
+                .iter()
+                .find(|current| current.id == vote_plan.id)
+                .ok_or_else(|| StateVerifierError::NoVotePlanInSnapshot(id.clone()))?;
+            for (proposal, current_proposal) in
+                vote_plan.proposals.iter().zip(current.proposals.iter())
+            {
+                if proposal.votes_cast != current_proposal.votes_cast {
+                    return Err(StateVerifierError::VotesCastChanged {
+                        vote_plan: id,
+                        proposal: proposal.index,
+                        before: proposal.votes_cast,
+                        after: current_proposal.votes_cast,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// every fragment sent to the node is either in a block or rejected
+    pub fn no_pending_fragments(&self) -> Result<(), StateVerifierError> {
+        let pending: Vec<String> = self
+            .rest
+            .fragment_logs()?
+            .values()
+            .filter(|log| log.is_pending())
+            .map(|log| log.fragment_id().to_string())
+            .collect();
+        if !pending.is_empty() {
+            return Err(StateVerifierError::PendingFragments(pending));
+        }
+        Ok(())
+    }
+
     pub fn value_moved_between_wallets(
         &self,
         from: &Wallet,
@@ -92,6 +148,17 @@ use thiserror::Error;
 pub enum StateVerifierError {
     #[error("cannot find wallet in snapshot {0}")]
     NoWalletInSnapshot(String),
+    #[error("cannot find vote plan in snapshot {0}")]
+    NoVotePlanInSnapshot(String),
+    #[error("votes cast on proposal {proposal} of vote plan {vote_plan} changed from {before} to {after}")]
+    VotesCastChanged {
+        vote_plan: String,
+        proposal: u8,
+        before: usize,
+        after: usize,
+    },
+    #[error("fragments are still pending: {0:?}")]
+    PendingFragments(Vec<String>),
     #[error("no snapshot was made prior assert execution")]
     NoSnapshot,
     #[error("rest error")]
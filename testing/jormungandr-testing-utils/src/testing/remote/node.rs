@@ -7,7 +7,7 @@ use chain_core::property::Fragment as _;
 use chain_impl_mockchain::{fragment::Fragment, fragment::FragmentId};
 use jormungandr_lib::{
     crypto::hash::Hash,
-    interfaces::{BlockDate, FragmentLog, NodeConfig},
+    interfaces::{BlockDate, FragmentDetails, FragmentLog, NodeConfig},
 };
 use std::{collections::HashMap, net::SocketAddr, path::PathBuf};
 
@@ -102,6 +102,17 @@ impl FragmentNode for RemoteJormungandr {
             .fragment_logs()
             .map_err(|e| FragmentNodeError::ListFragmentError(e.to_string()))
     }
+    fn fragment_details(
+        &self,
+        fragment_id: &FragmentId,
+    ) -> Result<Option<FragmentDetails>, FragmentNodeError> {
+        self.rest().fragment_details(fragment_id).map_err(|e| {
+            FragmentNodeError::FragmentDetailsError {
+                reason: e.to_string(),
+                fragment_id: *fragment_id,
+            }
+        })
+    }
     fn send_fragment(&self, fragment: Fragment) -> Result<MemPoolCheck, FragmentNodeError> {
         self.rest().send_fragment(fragment.clone()).map_err(|e| {
             FragmentNodeError::CannotSendFragment {
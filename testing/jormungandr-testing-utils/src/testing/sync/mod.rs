@@ -4,12 +4,14 @@ use crate::testing::{benchmark_speed, Speed, Thresholds};
 mod measure;
 mod node;
 mod report;
+mod timeline;
 mod wait;
 
 use jormungandr_lib::time::Duration as LibsDuration;
 pub use measure::*;
 pub use node::{SyncNode, SyncNodeError, SyncNodeRecord};
 pub use report::{MeasurementReportInterval, MeasurementReporter};
+pub use timeline::{NodeLag, SyncSample, SyncTimeline};
 pub use wait::SyncWaitParams;
 
 pub fn ensure_node_is_in_sync_with_others(
@@ -58,11 +60,21 @@ pub fn ensure_nodes_are_in_sync<A: SyncNode + ?Sized>(
     sync_wait: SyncWaitParams,
     nodes: &[&A],
 ) -> Result<(), VerificationError> {
+    ensure_nodes_are_in_sync_with_timeline(sync_wait, nodes).map(|_| ())
+}
+
+/// same as `ensure_nodes_are_in_sync`, but returns the block heights of the
+/// nodes sampled during the sync grace period, for the lag assertions
+pub fn ensure_nodes_are_in_sync_with_timeline<A: SyncNode + ?Sized>(
+    sync_wait: SyncWaitParams,
+    nodes: &[&A],
+) -> Result<SyncTimeline, VerificationError> {
+    let mut timeline = SyncTimeline::new(nodes);
     if nodes.len() < 2 {
-        return Ok(());
+        return Ok(timeline);
     }
 
-    wait_for_nodes_sync(&sync_wait);
+    timeline.record_for(nodes, sync_wait.wait_time());
     let duration: LibsDuration = sync_wait.wait_time().into();
     let first_node = nodes.iter().next().unwrap();
 
@@ -74,26 +86,28 @@ pub fn ensure_nodes_are_in_sync<A: SyncNode + ?Sized>(
         assert_equals(
             &expected_tip,
             &tip,
-            &format!("nodes are out of sync (different block hashes) after sync grace period: ({}) . Left node: alias: {}, content: {}, Right node: alias: {}, content: {}",
+            &format!("nodes are out of sync (different block hashes) after sync grace period: ({}) . Left node: alias: {}, content: {}, Right node: alias: {}, content: {}\nTimeline:\n{}",
                 duration,
                 first_node.alias(),
                 first_node.log_content(),
                 node.alias(),
-                node.log_content()),
+                node.log_content(),
+                timeline.ascii_chart()),
         )?;
         assert_equals(
             &block_height,
             &node.last_block_height(),
-            &format!("nodes are out of sync (different block height) after sync grace period: ({}) . Left node: alias: {}, content: {}, Right node: alias: {}, content: {}",
+            &format!("nodes are out of sync (different block height) after sync grace period: ({}) . Left node: alias: {}, content: {}, Right node: alias: {}, content: {}\nTimeline:\n{}",
                 duration,
                 first_node.alias(),
                 first_node.log_content(),
                 node.alias(),
-                node.log_content()
+                node.log_content(),
+                timeline.ascii_chart()
                 ),
         )?;
     }
-    Ok(())
+    Ok(timeline)
 }
 
 pub fn wait_for_nodes_sync(sync_wait_params: &SyncWaitParams) {
@@ -0,0 +1,172 @@
+use super::SyncNode;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// how often the tips of the nodes are sampled while waiting for the sync
+pub const SAMPLING_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone)]
+pub struct SyncSample {
+    pub elapsed: Duration,
+    pub block_heights: Vec<u32>,
+}
+
+impl SyncSample {
+    fn max_block_height(&self) -> u32 {
+        self.block_heights.iter().cloned().max().unwrap_or(0)
+    }
+
+    fn is_converged(&self) -> bool {
+        let max = self.max_block_height();
+        self.block_heights.iter().all(|x| *x == max)
+    }
+}
+
+/// lag of a node behind the highest tip of the network, in blocks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeLag {
+    pub alias: String,
+    pub current: u32,
+    pub max: u32,
+}
+
+/// Block heights of the nodes sampled over time while waiting for them to
+/// converge on the same tip.
+#[derive(Debug, Clone)]
+pub struct SyncTimeline {
+    started: Instant,
+    aliases: Vec<String>,
+    samples: Vec<SyncSample>,
+}
+
+impl SyncTimeline {
+    pub fn new<A: SyncNode + ?Sized>(nodes: &[&A]) -> Self {
+        Self {
+            started: Instant::now(),
+            aliases: nodes.iter().map(|node| node.alias().to_string()).collect(),
+            samples: Vec::new(),
+        }
+    }
+
+    /// sample the current block heights of the nodes
+    pub fn record<A: SyncNode + ?Sized>(&mut self, nodes: &[&A]) -> &SyncSample {
+        let sample = SyncSample {
+            elapsed: self.started.elapsed(),
+            block_heights: nodes.iter().map(|node| node.last_block_height()).collect(),
+        };
+        self.samples.push(sample);
+        self.samples.last().unwrap()
+    }
+
+    /// sample the nodes periodically for the given duration
+    pub fn record_for<A: SyncNode + ?Sized>(&mut self, nodes: &[&A], duration: Duration) {
+        let deadline = Instant::now() + duration;
+        loop {
+            self.record(nodes);
+            let now = Instant::now();
+            if now >= deadline {
+                return;
+            }
+            std::thread::sleep(std::cmp::min(SAMPLING_INTERVAL, deadline - now));
+        }
+    }
+
+    pub fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+
+    pub fn samples(&self) -> &[SyncSample] {
+        &self.samples
+    }
+
+    /// time since the start of the measurement after which the nodes stayed
+    /// on the same block height until the last sample
+    pub fn converged_after(&self) -> Option<Duration> {
+        let diverged = self.samples.iter().rposition(|x| !x.is_converged());
+        match diverged {
+            Some(index) => self.samples.get(index + 1).map(|x| x.elapsed),
+            None => self.samples.first().map(|x| x.elapsed),
+        }
+    }
+
+    pub fn lags(&self) -> Vec<NodeLag> {
+        self.aliases
+            .iter()
+            .enumerate()
+            .map(|(index, alias)| {
+                let lag_at =
+                    |sample: &SyncSample| sample.max_block_height() - sample.block_heights[index];
+                NodeLag {
+                    alias: alias.clone(),
+                    current: self.samples.last().map(lag_at).unwrap_or(0),
+                    max: self.samples.iter().map(lag_at).max().unwrap_or(0),
+                }
+            })
+            .collect()
+    }
+
+    pub fn lag_of(&self, alias: &str) -> Option<NodeLag> {
+        self.lags().into_iter().find(|x| x.alias == alias)
+    }
+
+    pub fn max_lag(&self) -> u32 {
+        self.lags().iter().map(|x| x.max).max().unwrap_or(0)
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("elapsed_ms,{}\n", self.aliases.join(","));
+        for sample in &self.samples {
+            let heights: Vec<String> = sample.block_heights.iter().map(|x| x.to_string()).collect();
+            writeln!(csv, "{},{}", sample.elapsed.as_millis(), heights.join(",")).unwrap();
+        }
+        csv
+    }
+
+    pub fn write_csv<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        let mut file = std::fs::File::create(path)?;
+        file.write_all(self.to_csv().as_bytes())
+    }
+
+    /// lag of every node over time, one row per node and one column per
+    /// sample, the taller the bar the further behind the node was
+    pub fn ascii_chart(&self) -> String {
+        const BARS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+        let max_lag = std::cmp::max(self.max_lag(), 1);
+        let alias_width = self.aliases.iter().map(|x| x.len()).max().unwrap_or(0);
+        let mut chart = String::new();
+        for (index, lag) in self.lags().iter().enumerate() {
+            let bars: String = self
+                .samples
+                .iter()
+                .map(|sample| {
+                    let node_lag = sample.max_block_height() - sample.block_heights[index];
+                    BARS[(node_lag as usize * (BARS.len() - 1)) / max_lag as usize]
+                })
+                .collect();
+            writeln!(
+                chart,
+                "{:width$} |{}| lag: {} (max {})",
+                lag.alias,
+                bars,
+                lag.current,
+                lag.max,
+                width = alias_width
+            )
+            .unwrap();
+        }
+        writeln!(
+            chart,
+            "{:width$}  {} samples every {:?}, max lag {} blocks",
+            "",
+            self.samples.len(),
+            SAMPLING_INTERVAL,
+            self.max_lag(),
+            width = alias_width
+        )
+        .unwrap();
+        chart
+    }
+}
@@ -1,3 +1,7 @@
+mod stake;
+
+pub use stake::ExpectedStakeDistribution;
+
 use std::fmt;
 
 use thiserror::Error;
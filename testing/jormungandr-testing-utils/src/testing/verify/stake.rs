@@ -0,0 +1,102 @@
+use super::Error;
+use crate::stake_pool::StakePool;
+use jormungandr_lib::{
+    crypto::hash::Hash,
+    interfaces::{Stake, StakeDistributionDto},
+};
+use std::str::FromStr;
+
+/// The stake distribution expected from the node, built from the
+/// delegations of the accounts. Only the pools given here are checked,
+/// as well as the unassigned and dangling stake when they are set.
+#[derive(Debug, Clone, Default)]
+pub struct ExpectedStakeDistribution {
+    unassigned: Option<u64>,
+    dangling: Option<u64>,
+    pools: Vec<(Hash, u64)>,
+}
+
+impl ExpectedStakeDistribution {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn unassigned(&mut self, unassigned: u64) -> &mut Self {
+        self.unassigned = Some(unassigned);
+        self
+    }
+
+    pub fn dangling(&mut self, dangling: u64) -> &mut Self {
+        self.dangling = Some(dangling);
+        self
+    }
+
+    /// add stake to the pool, e.g. the one of its owner
+    pub fn pool(&mut self, stake_pool: &StakePool, stake: u64) -> &mut Self {
+        let id = pool_hash(stake_pool);
+        match self.pools.iter_mut().find(|(pool, _)| *pool == id) {
+            Some((_, total)) => *total += stake,
+            None => self.pools.push((id, stake)),
+        }
+        self
+    }
+
+    /// add the value of an account delegating to the pools with the given
+    /// ratios, split the way the ledger does: every pool gets its parts of
+    /// the value and the remainder of the division goes to the first pool
+    pub fn delegation(&mut self, value: u64, distribution: &[(&StakePool, u8)]) -> &mut Self {
+        let parts: u64 = distribution
+            .iter()
+            .map(|(_, ratio)| u64::from(*ratio))
+            .sum();
+        let part = value / parts;
+        let remainder = value % parts;
+        for (index, (stake_pool, ratio)) in distribution.iter().enumerate() {
+            let mut stake = part * u64::from(*ratio);
+            if index == 0 {
+                stake += remainder;
+            }
+            self.pool(stake_pool, stake);
+        }
+        self
+    }
+
+    pub fn verify(&self, stake_distribution: &StakeDistributionDto) -> Result<(), Error> {
+        let stake = &stake_distribution.stake;
+        if let Some(unassigned) = self.unassigned {
+            check_stake("unassigned", unassigned, &stake.unassigned)?;
+        }
+        if let Some(dangling) = self.dangling {
+            check_stake("dangling", dangling, &stake.dangling)?;
+        }
+        for (id, expected) in &self.pools {
+            let actual = stake
+                .pools
+                .iter()
+                .find(|(pool, _)| pool == id)
+                .map(|(_, stake)| *stake)
+                .ok_or_else(|| {
+                    Error::VerificationFailed(format!(
+                        "no stake pool {} in the stake distribution of epoch {}",
+                        id, stake_distribution.epoch
+                    ))
+                })?;
+            check_stake(&format!("stake pool {}", id), *expected, &actual)?;
+        }
+        Ok(())
+    }
+}
+
+fn pool_hash(stake_pool: &StakePool) -> Hash {
+    Hash::from_str(&stake_pool.id().to_string()).expect("pool ids are hashes")
+}
+
+fn check_stake(info: &str, expected: u64, actual: &Stake) -> Result<(), Error> {
+    if Stake::from(expected) != *actual {
+        return Err(Error::VerificationFailed(format!(
+            "wrong {} stake: expected {} but got {}",
+            info, expected, actual
+        )));
+    }
+    Ok(())
+}
@@ -1,3 +1,7 @@
+mod private_vote;
+
+pub use private_vote::{PrivateVoteTestFlow, PrivateVoteTestFlowError};
+
 use chain_core::property::BlockDate as _;
 use chain_impl_mockchain::{
     block::BlockDate,
@@ -0,0 +1,309 @@
+use crate::{
+    testing::{
+        FragmentNode, FragmentSender, FragmentSenderError, FragmentVerifier, FragmentVerifierError,
+        MemPoolCheck, SyncNode,
+    },
+    wallet::Wallet,
+};
+use chain_core::property::BlockDate as _;
+use chain_impl_mockchain::{
+    block::BlockDate,
+    certificate::{DecryptedPrivateTally, DecryptedPrivateTallyProposal, Proposals, VotePlan},
+    vote::{Choice, PayloadType},
+};
+use chain_vote::{
+    committee::MemberPublicKey, EncryptedTally, EncryptingVoteKey, MemberCommunicationKey,
+    MemberState, TallyDecryptShare, CRS,
+};
+use jormungandr_lib::interfaces::{PrivateTallyState, Tally, VotePlanStatus};
+use rand_core::{CryptoRng, RngCore};
+use std::time::Duration;
+use thiserror::Error;
+
+/// the longest wait for a fragment of the flow to be in a block
+const FRAGMENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Error)]
+pub enum PrivateVoteTestFlowError {
+    #[error("cannot send the fragment")]
+    FragmentSender(#[from] FragmentSenderError),
+    #[error("the fragment did not make it into a block")]
+    FragmentVerifier(#[from] FragmentVerifierError),
+    #[error("vote plan {0} is not in the vote plan statuses")]
+    VotePlanNotFound(String),
+    #[error("proposal {0} has no encrypted tally")]
+    NoEncryptedTally(u8),
+    #[error("proposal {0} has an encrypted tally which cannot be decoded")]
+    InvalidEncryptedTally(u8),
+    #[error("the results of proposal {0} cannot be decrypted")]
+    UndecryptableResults(u8),
+    #[error("proposal {0} has no decrypted tally")]
+    NoDecryptedTally(u8),
+    #[error("decrypted results {actual:?} differ from the votes cast {expected:?}")]
+    UnexpectedResults {
+        expected: Vec<Vec<u64>>,
+        actual: Vec<Vec<u64>>,
+    },
+}
+
+/// The stages of a private vote, from the generation of the committee keys
+/// to the check of the decrypted results:
+///
+/// 1. the committee keys and the private vote plan are generated with `new`;
+/// 2. the vote plan is registered with `send_vote_plan`, or put in the
+///    genesis block with `vote_plan`;
+/// 3. the encrypted votes are cast with `cast_vote`, which records them;
+/// 4. the encrypted tally is sent with `send_encrypted_tally` once the vote
+///    is over;
+/// 5. the tally is decrypted with the shares of every committee member and
+///    sent along with them with `send_tally`;
+/// 6. the results published by the node are compared with the votes cast
+///    with `verify_results`.
+///
+/// The vote starts in the first epoch, the tally can be sent in the second
+/// one and the committee period ends with the third one.
+pub struct PrivateVoteTestFlow {
+    members: Vec<MemberState>,
+    encrypting_vote_key: EncryptingVoteKey,
+    vote_plan: VotePlan,
+    /// the proposal, the option and the stake of every vote in a block
+    votes: Vec<(u8, u8, u64)>,
+}
+
+impl PrivateVoteTestFlow {
+    /// generate the keys of a committee of `members` members, `threshold`
+    /// of them being needed to decrypt the tally, and a private vote plan
+    /// with the given proposals
+    pub fn new<RNG: CryptoRng + RngCore>(
+        rng: &mut RNG,
+        members: usize,
+        threshold: usize,
+        proposals: Proposals,
+    ) -> Self {
+        let crs = CRS::random(rng);
+        let communication_keys: Vec<_> = (0..members)
+            .map(|_| MemberCommunicationKey::new(rng).to_public())
+            .collect();
+        let members: Vec<_> = (0..members)
+            .map(|index| MemberState::new(rng, threshold, &crs, &communication_keys, index))
+            .collect();
+        let member_public_keys: Vec<MemberPublicKey> =
+            members.iter().map(MemberState::public_key).collect();
+        let encrypting_vote_key = EncryptingVoteKey::from_participants(&member_public_keys);
+        let vote_plan = VotePlan::new(
+            BlockDate::from_epoch_slot_id(0, 0),
+            BlockDate::from_epoch_slot_id(1, 0),
+            BlockDate::from_epoch_slot_id(2, 0),
+            proposals,
+            PayloadType::Private,
+            member_public_keys,
+        );
+        Self {
+            members,
+            encrypting_vote_key,
+            vote_plan,
+            votes: Vec::new(),
+        }
+    }
+
+    pub fn vote_plan(&self) -> &VotePlan {
+        &self.vote_plan
+    }
+
+    pub fn encrypting_vote_key(&self) -> &EncryptingVoteKey {
+        &self.encrypting_vote_key
+    }
+
+    pub fn send_vote_plan<A: FragmentNode + SyncNode + Sized + Sync + Send>(
+        &self,
+        sender: &FragmentSender,
+        owner: &mut Wallet,
+        via: &A,
+    ) -> Result<MemPoolCheck, PrivateVoteTestFlowError> {
+        Ok(sender.send_vote_plan(owner, &self.vote_plan, via)?)
+    }
+
+    /// cast an encrypted vote for the option of the proposal, the vote
+    /// weighing the stake of the voter. The vote is counted in the expected
+    /// results once it is in a block.
+    pub fn cast_vote<A: FragmentNode + SyncNode + Sized + Sync + Send>(
+        &mut self,
+        sender: &FragmentSender,
+        voter: &mut Wallet,
+        stake: u64,
+        proposal_index: u8,
+        choice: u8,
+        via: &A,
+    ) -> Result<MemPoolCheck, PrivateVoteTestFlowError> {
+        let check = sender.send_private_vote_cast(
+            voter,
+            &self.vote_plan,
+            proposal_index,
+            &Choice::new(choice),
+            &self.encrypting_vote_key,
+            via,
+        )?;
+        FragmentVerifier.wait_and_verify_is_in_block(FRAGMENT_TIMEOUT, check.clone(), via)?;
+        self.votes.push((proposal_index, choice, stake));
+        Ok(check)
+    }
+
+    pub fn send_encrypted_tally<A: FragmentNode + SyncNode + Sized + Sync + Send>(
+        &self,
+        sender: &FragmentSender,
+        committee_member: &mut Wallet,
+        via: &A,
+    ) -> Result<MemPoolCheck, PrivateVoteTestFlowError> {
+        let check = sender.send_encrypted_vote_tally(committee_member, &self.vote_plan, via)?;
+        FragmentVerifier.wait_and_verify_is_in_block(FRAGMENT_TIMEOUT, check.clone(), via)?;
+        Ok(check)
+    }
+
+    /// decrypt the encrypted tally published by the node and send the
+    /// results along with the decryption shares of the committee
+    pub fn send_tally<A: FragmentNode + SyncNode + Sized + Sync + Send>(
+        &self,
+        sender: &FragmentSender,
+        committee_member: &mut Wallet,
+        vote_plan_statuses: &[VotePlanStatus],
+        via: &A,
+    ) -> Result<MemPoolCheck, PrivateVoteTestFlowError> {
+        let proposals = self
+            .decrypt(vote_plan_statuses)?
+            .into_iter()
+            .map(|(shares, results)| DecryptedPrivateTallyProposal {
+                decrypt_shares: shares.into_boxed_slice(),
+                tally_result: results.into_boxed_slice(),
+            })
+            .collect();
+        let check = sender.send_private_vote_tally(
+            committee_member,
+            &self.vote_plan,
+            DecryptedPrivateTally::new(proposals),
+            via,
+        )?;
+        FragmentVerifier.wait_and_verify_is_in_block(FRAGMENT_TIMEOUT, check.clone(), via)?;
+        Ok(check)
+    }
+
+    /// the stake voted for every option of every proposal, from the votes
+    /// cast
+    pub fn expected_results(&self) -> Vec<Vec<u64>> {
+        self.vote_plan
+            .proposals()
+            .iter()
+            .enumerate()
+            .map(|(proposal_index, proposal)| {
+                let mut results = vec![0; proposal.options().choice_range().end as usize];
+                for (_, choice, stake) in self
+                    .votes
+                    .iter()
+                    .filter(|(index, _, _)| *index as usize == proposal_index)
+                {
+                    results[*choice as usize] += stake;
+                }
+                results
+            })
+            .collect()
+    }
+
+    fn status<'a>(
+        &self,
+        vote_plan_statuses: &'a [VotePlanStatus],
+    ) -> Result<&'a VotePlanStatus, PrivateVoteTestFlowError> {
+        let id = self.vote_plan.to_id().to_string();
+        vote_plan_statuses
+            .iter()
+            .find(|status| status.id.to_string() == id)
+            .ok_or(PrivateVoteTestFlowError::VotePlanNotFound(id))
+    }
+
+    /// decrypt the encrypted tally of every proposal of the vote plan with
+    /// the decryption shares of the whole committee
+    pub fn decrypt_results(
+        &self,
+        vote_plan_statuses: &[VotePlanStatus],
+    ) -> Result<Vec<Vec<u64>>, PrivateVoteTestFlowError> {
+        Ok(self
+            .decrypt(vote_plan_statuses)?
+            .into_iter()
+            .map(|(_, results)| results)
+            .collect())
+    }
+
+    /// the decryption shares of the committee and the decrypted results of
+    /// every proposal of the vote plan
+    fn decrypt(
+        &self,
+        vote_plan_statuses: &[VotePlanStatus],
+    ) -> Result<Vec<(Vec<TallyDecryptShare>, Vec<u64>)>, PrivateVoteTestFlowError> {
+        self.status(vote_plan_statuses)?
+            .proposals
+            .iter()
+            .map(|proposal| {
+                let (encrypted_tally, total_stake) = match &proposal.tally {
+                    Some(Tally::Private {
+                        state:
+                            PrivateTallyState::Encrypted {
+                                encrypted_tally,
+                                total_stake,
+                            },
+                    }) => (encrypted_tally, total_stake),
+                    _ => return Err(PrivateVoteTestFlowError::NoEncryptedTally(proposal.index)),
+                };
+                let encrypted_tally = EncryptedTally::from_bytes(encrypted_tally.as_ref()).ok_or(
+                    PrivateVoteTestFlowError::InvalidEncryptedTally(proposal.index),
+                )?;
+                let shares: Vec<TallyDecryptShare> = self
+                    .members
+                    .iter()
+                    .map(|member| encrypted_tally.finish(member.secret_key()).1)
+                    .collect();
+
+                let max_votes: u64 = (*total_stake).into();
+                let options = proposal.options.end as u64;
+                let table_size = (max_votes / options) as usize;
+                let result =
+                    chain_vote::result(max_votes, table_size, &encrypted_tally.state(), &shares);
+                let results = result
+                    .votes
+                    .into_iter()
+                    .collect::<Option<Vec<u64>>>()
+                    .ok_or(PrivateVoteTestFlowError::UndecryptableResults(
+                        proposal.index,
+                    ))?;
+                Ok((shares, results))
+            })
+            .collect()
+    }
+
+    /// the decrypted results published by the node
+    pub fn published_results(
+        &self,
+        vote_plan_statuses: &[VotePlanStatus],
+    ) -> Result<Vec<Vec<u64>>, PrivateVoteTestFlowError> {
+        self.status(vote_plan_statuses)?
+            .proposals
+            .iter()
+            .map(|proposal| match &proposal.tally {
+                Some(Tally::Private {
+                    state: PrivateTallyState::Decrypted { result },
+                }) => Ok(result.results()),
+                _ => Err(PrivateVoteTestFlowError::NoDecryptedTally(proposal.index)),
+            })
+            .collect()
+    }
+
+    /// verify that the results published by the node match the votes cast
+    pub fn verify_results(
+        &self,
+        vote_plan_statuses: &[VotePlanStatus],
+    ) -> Result<(), PrivateVoteTestFlowError> {
+        let expected = self.expected_results();
+        let actual = self.published_results(vote_plan_statuses)?;
+        if expected != actual {
+            return Err(PrivateVoteTestFlowError::UnexpectedResults { expected, actual });
+        }
+        Ok(())
+    }
+}
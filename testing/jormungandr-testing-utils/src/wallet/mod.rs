@@ -7,7 +7,7 @@ use crate::{
     testing::{FragmentBuilder, FragmentBuilderError},
 };
 use chain_impl_mockchain::{
-    certificate::VotePlan,
+    certificate::{DecryptedPrivateTally, VotePlan},
     fee::FeeAlgorithm,
     key::EitherEd25519SecretKey,
     testing::data::{AddressData, AddressDataValue, Wallet as WalletLib},
@@ -36,6 +36,7 @@ pub use chain_impl_mockchain::{
     milli::Milli,
     transaction::{Input, TransactionBindingAuthData, UnspecifiedAccountIdentifier},
 };
+use chain_vote::EncryptingVoteKey;
 use rand_core::{CryptoRng, RngCore};
 use std::{fs::File, path::Path};
 use thiserror::Error;
@@ -322,6 +323,20 @@ impl Wallet {
         Ok(FragmentBuilder::new(block0_hash, fees).delegation_to_many(&self, distribution))
     }
 
+    /// move the stake of an account to other pools, fully delegated to the
+    /// pool when there is a single one, split with the ratios otherwise
+    pub fn issue_redelegation_cert(
+        &mut self,
+        block0_hash: &Hash,
+        fees: &LinearFee,
+        distribution: Vec<(&StakePool, u8)>,
+    ) -> Result<Fragment, WalletError> {
+        match distribution.as_slice() {
+            [(stake_pool, _)] => self.issue_full_delegation_cert(block0_hash, fees, stake_pool),
+            _ => self.issue_split_delegation_cert(block0_hash, fees, distribution),
+        }
+    }
+
     pub fn remove_delegation_cert(
         &mut self,
         block0_hash: &Hash,
@@ -364,6 +379,47 @@ impl Wallet {
         Ok(FragmentBuilder::new(block0_hash, fees).vote_tally(&self, vote_plan))
     }
 
+    pub fn issue_private_vote_cast_cert(
+        &mut self,
+        block0_hash: &Hash,
+        fees: &LinearFee,
+        vote_plan: &VotePlan,
+        proposal_index: u8,
+        choice: &Choice,
+        encrypting_vote_key: &EncryptingVoteKey,
+    ) -> Result<Fragment, WalletError> {
+        Ok(FragmentBuilder::new(block0_hash, fees).private_vote_cast(
+            &self,
+            vote_plan,
+            proposal_index,
+            choice,
+            encrypting_vote_key,
+        ))
+    }
+
+    pub fn issue_encrypted_vote_tally_cert(
+        &mut self,
+        block0_hash: &Hash,
+        fees: &LinearFee,
+        vote_plan: &VotePlan,
+    ) -> Result<Fragment, WalletError> {
+        Ok(FragmentBuilder::new(block0_hash, fees).encrypted_vote_tally(&self, vote_plan))
+    }
+
+    pub fn issue_private_vote_tally_cert(
+        &mut self,
+        block0_hash: &Hash,
+        fees: &LinearFee,
+        vote_plan: &VotePlan,
+        decrypted_tally: DecryptedPrivateTally,
+    ) -> Result<Fragment, WalletError> {
+        Ok(FragmentBuilder::new(block0_hash, fees).private_vote_tally(
+            &self,
+            vote_plan,
+            decrypted_tally,
+        ))
+    }
+
     pub fn to_committee_id(&self) -> CommitteeIdDef {
         CommitteeIdDef::from(CommitteeId::from(
             self.address().1.public_key().unwrap().clone(),